@@ -0,0 +1,117 @@
+//! Register classes and the roles AAPCS64 gives each register, for
+//! calling-convention-aware analyses: argument recovery, clobber summaries,
+//! prologue matching.
+//!
+//! ```text
+//! x0...x7    argument, result, caller-saved     v0...v7    argument, result, caller-saved
+//! x8         indirect result, caller-saved      v8...v15   callee-saved (low 64 bits)
+//! x9...x15   caller-saved                       v16...v31  caller-saved
+//! x16, x17   intra-procedure-call, caller-saved z, p, ffr  caller-saved
+//! x18        platform, caller-saved             nzcv       caller-saved
+//! x19...x28  callee-saved                       sp         stack pointer, callee-saved
+//! x29        frame pointer, callee-saved
+//! x30        link register, caller-saved
+//! ```
+//!
+//! This is the base procedure call standard. Where a platform reserves X18
+//! it is neither saved nor a temporary; see `DefUse::without_reserved`. Only
+//! the low 64 bits of V8...V15 (D8...D15) are preserved, so the Z registers
+//! they are part of count as caller-saved.
+
+use crate::aarch64_defuse::Loc;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RegClass {
+    /// X0...X30
+    Gpr,
+    /// V0...V31
+    Fpr,
+    /// Z0...Z31
+    Sve,
+    /// P0...P15 and FFR
+    Predicate,
+    /// The SME ZA array
+    Za,
+    Sp,
+    Flags,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Role {
+    Argument,
+    Result,
+    /// X8, the address a large result is written to.
+    IndirectResult,
+    /// X16 and X17, which veneers and PLT stubs may clobber.
+    IntraProcedureCall,
+    /// X18
+    Platform,
+    /// Preserved across calls.
+    CalleeSaved,
+    /// Clobbered by calls.
+    CallerSaved,
+    FramePointer,
+    LinkRegister,
+    StackPointer,
+}
+
+pub fn class(loc: Loc) -> RegClass {
+    match loc {
+        Loc::X(_) => RegClass::Gpr,
+        Loc::V(_) => RegClass::Fpr,
+        Loc::Z(_) => RegClass::Sve,
+        Loc::P(_) | Loc::FFR => RegClass::Predicate,
+        Loc::ZA => RegClass::Za,
+        Loc::SP => RegClass::Sp,
+        Loc::NZCV => RegClass::Flags,
+    }
+}
+
+/// The roles of loc; the last says whether calls preserve it.
+pub fn roles(loc: Loc) -> Vec<Role> {
+    use Role::*;
+    match loc {
+        Loc::X(0..=7) | Loc::V(0..=7) => vec![Argument, Result, CallerSaved],
+        Loc::X(8) => vec![IndirectResult, CallerSaved],
+        Loc::X(16 | 17) => vec![IntraProcedureCall, CallerSaved],
+        Loc::X(18) => vec![Platform, CallerSaved],
+        Loc::X(19..=28) | Loc::V(8..=15) => vec![CalleeSaved],
+        Loc::X(29) => vec![FramePointer, CalleeSaved],
+        Loc::X(30) => vec![LinkRegister, CallerSaved],
+        Loc::SP => vec![StackPointer, CalleeSaved],
+        _ => vec![CallerSaved],
+    }
+}
+
+pub fn has_role(loc: Loc, role: Role) -> bool {
+    roles(loc).contains(&role)
+}
+
+/// Every location, in Loc order.
+pub fn locations() -> impl Iterator<Item = Loc> {
+    (0..31).map(Loc::X)
+        .chain([Loc::SP])
+        .chain((0..32).map(Loc::V))
+        .chain((0..32).map(Loc::Z))
+        .chain((0..16).map(Loc::P))
+        .chain([Loc::FFR, Loc::ZA, Loc::NZCV])
+}
+
+/// The locations with the role, e.g. what a call clobbers.
+pub fn with_role(role: Role) -> Vec<Loc> {
+    locations().filter(|&l| has_role(l, role)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aapcs64_roles() {
+        assert_eq!(with_role(Role::Argument), (0..8).map(Loc::X).chain((0..8).map(Loc::V)).collect::<Vec<_>>());
+        assert_eq!(with_role(Role::CalleeSaved).len(), 10 + 2 + 8);
+        assert!(locations().all(|l| has_role(l, Role::CalleeSaved) != has_role(l, Role::CallerSaved)));
+        assert_eq!(roles(Loc::X(29)), vec![Role::FramePointer, Role::CalleeSaved]);
+        assert_eq!(class(Loc::FFR), RegClass::Predicate);
+    }
+}
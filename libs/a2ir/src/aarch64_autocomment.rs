@@ -0,0 +1,117 @@
+//! Comments derived by constant propagation, for listings: the values of
+//! MOVZ/MOVK chains, the symbols ADRP/ADD pairs and loads refer to, and the
+//! bit ranges of logical immediates.
+//!
+//! ```text
+//!         1004:  movk x0, #0x567, lsl #32         // = 0x56712340000
+//!         100c:  add x1, x1, #0x10                // counter
+//!         1014:  and w2, w2, #0xff00              // bits 15:8
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::aarch64_constprop::{propagate, ConstOptions};
+use crate::aarch64_defuse::is_memory;
+use crate::aarch64_listing::Annotate;
+use crate::aarch64_program::Program;
+use crate::aarch64_reader::FlagMasks::W32;
+use crate::aarch64_reader::{Inst, Op};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AutoComments {
+    pub comments: BTreeMap<u64, String>,
+}
+
+fn run(mask: u64) -> Option<(u32, u32)> {
+    let lo = mask.trailing_zeros();
+    let hi = 63 - mask.leading_zeros();
+    (mask != 0 && (mask >> lo).count_ones() == hi - lo + 1).then_some((hi, lo))
+}
+
+/// The bits set in a logical immediate of width 32 or 64: a range, or the
+/// element repeated across the register.
+pub fn mask_text(mask: u64, width: u32) -> String {
+    if let Some((hi, lo)) = run(mask) {
+        return format!("bits {}:{}", hi, lo);
+    }
+    let mut size = 2;
+    while size < width {
+        let element = mask & ((1 << size) - 1);
+        let repeated = (0..width / size).fold(0u64, |acc, i| acc | element << (i * size));
+        if repeated == mask {
+            return match run(element) {
+                Some((hi, lo)) => format!("bits {}:{} of each {} bits", hi, lo, size),
+                None => format!("{:#x} in each {} bits", element, size),
+            };
+        }
+        size *= 2;
+    }
+    format!("{:#x}", mask)
+}
+
+impl AutoComments {
+    /// The comments of the functions, as [entry, end) ranges, naming the
+    /// addresses in symbols.
+    pub fn new(program: &Program, functions: &[(u64, u64)], symbols: &BTreeMap<u64, String>) -> AutoComments {
+        let mut comments = BTreeMap::new();
+        for &(entry, end) in functions {
+            let block: Vec<_> = program.range(entry..end).map(|(a, i)| (a, i.clone())).collect();
+            let resolved = propagate(&block, &ConstOptions::default());
+            for (pc, inst) in &block {
+                let value = resolved.values.get(pc).copied();
+                let symbol = |addr: u64| symbols.get(&addr).cloned();
+                let comment = match inst.op {
+                    Op::A64_MOVK => value.map(|v| format!("= {:#x}", v)),
+                    Op::A64_ADD_IMM | Op::A64_SUB_IMM => value.map(|v| symbol(v).unwrap_or_else(|| format!("= {:#x}", v))),
+                    Op::A64_ADR | Op::A64_ADRP => value.and_then(symbol),
+                    Op::A64_AND_IMM | Op::A64_ORR_IMM | Op::A64_EOR_IMM | Op::A64_TST_IMM => {
+                        Some(mask_text(inst.imm, if inst.flags & W32 != 0 { 32 } else { 64 }))
+                    }
+                    _ if is_memory(&inst.op) => resolved.data_addresses.get(pc).and_then(|&a| symbol(a)).map(|s| format!("[{}]", s)),
+                    _ => None,
+                };
+                if let Some(comment) = comment {
+                    comments.insert(*pc, comment);
+                }
+            }
+        }
+        AutoComments { comments }
+    }
+}
+
+impl Annotate for AutoComments {
+    fn comment(&self, addr: u64, _inst: &Inst) -> Option<String> {
+        self.comments.get(&addr).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_listing::listing;
+
+    #[test]
+    fn values_symbols_masks() {
+        let program = Program::from_words(&[
+            0xD2A24680, // mov x0, #0x12340000
+            0xF2C0ACE0, // movk x0, #0x567, lsl #32
+            0xB0000001, // adrp x1, .+0x1000       (0x2000)
+            0x91004021, // add x1, x1, #0x10
+            0xF9400022, // ldr x2, [x1]
+            0x12181C42, // and w2, w2, #0xff00
+            0xB200E063, // orr x3, x3, #0x1111111111111111
+            0xD65F03C0, // ret
+        ], 0x1000);
+        let symbols = BTreeMap::from([(0x2010, "counter".to_string())]);
+        let auto = AutoComments::new(&program, &[(0x1000, 0x1020)], &symbols);
+        let comments: Vec<_> = auto.comments.iter().map(|(a, c)| (*a, c.as_str())).collect();
+        assert_eq!(comments, vec![
+            (0x1004, "= 0x56712340000"),
+            (0x100C, "counter"),
+            (0x1010, "[counter]"),
+            (0x1014, "bits 15:8"),
+            (0x1018, "bits 0:0 of each 4 bits"),
+        ]);
+        assert!(listing(&program, &[&auto]).contains("// bits 15:8\n"));
+    }
+}
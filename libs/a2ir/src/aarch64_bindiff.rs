@@ -0,0 +1,281 @@
+//! Matching of the functions of two versions of a binary and ranking of the
+//! ones that changed, for patch diffing.
+//!
+//! Functions are paired in three passes, each followed by propagation along
+//! the call graph:
+//!
+//! 1. identical fingerprints that are unique on both sides;
+//! 2. the callees of matched functions, by call order, if similar enough;
+//! 3. the best remaining pairs by fingerprint similarity.
+//!
+//! A matched pair is then diffed instruction by instruction (longest common
+//! subsequence), with PC-relative instructions compared by opcode and
+//! destination only, as their offsets move with the code around them.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::aarch64_program::Program;
+use crate::aarch64_reader::{Inst, Op};
+use crate::aarch64_relocate::{is_pc_relative, target_address};
+use crate::aarch64_similar::{fingerprint, Fingerprint};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MatchMethod {
+    Identical,
+    CallGraph,
+    Fingerprint,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionMatch {
+    pub old: u64,
+    pub new: u64,
+    pub method: MatchMethod,
+    pub similarity: f32,
+    /// Instructions of either version outside the common subsequence.
+    pub changed: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BinDiff {
+    /// In the order of the old entries.
+    pub matches: Vec<FunctionMatch>,
+    /// Entries of functions without a counterpart.
+    pub unmatched_old: Vec<u64>,
+    pub unmatched_new: Vec<u64>,
+}
+
+/// Functions of one version: [entry, end) ranges over a Program.
+struct Side<'a> {
+    program: &'a Program,
+    prints: BTreeMap<u64, Fingerprint>,
+    /// Distinct callees among the functions, in call order.
+    callees: BTreeMap<u64, Vec<u64>>,
+    ranges: BTreeMap<u64, u64>,
+}
+
+impl<'a> Side<'a> {
+    fn new(program: &'a Program, functions: &[(u64, u64)]) -> Side<'a> {
+        let ranges: BTreeMap<u64, u64> = functions.iter().copied().collect();
+        let mut prints = BTreeMap::new();
+        let mut callees = BTreeMap::new();
+        for (&entry, &end) in &ranges {
+            prints.insert(entry, fingerprint(program, entry, end));
+            let mut seen = BTreeSet::new();
+            let calls: Vec<u64> = program
+                .range(entry..end)
+                .filter(|(_, inst)| inst.branch_kind().is_some_and(|k| k.is_call()))
+                .filter_map(|(pc, inst)| target_address(inst, pc))
+                .filter(|target| ranges.contains_key(target) && seen.insert(*target))
+                .collect();
+            callees.insert(entry, calls);
+        }
+        Side { program, prints, callees, ranges }
+    }
+
+    fn insts(&self, entry: u64) -> Vec<&Inst> {
+        self.program.range(entry..self.ranges[&entry]).map(|(_, inst)| inst).collect()
+    }
+}
+
+fn same(a: &Inst, b: &Inst) -> bool {
+    if is_pc_relative(a) || is_pc_relative(b) {
+        a.op == b.op && a.rd == b.rd
+    } else {
+        a == b
+    }
+}
+
+/// Instructions of a and b outside their longest common subsequence.
+pub fn changed_insts(a: &[&Inst], b: &[&Inst]) -> usize {
+    let mut prev = vec![0usize; b.len() + 1];
+    let mut row = vec![0usize; b.len() + 1];
+    for x in a {
+        for (j, y) in b.iter().enumerate() {
+            row[j + 1] = if same(x, y) { prev[j] + 1 } else { prev[j + 1].max(row[j]) };
+        }
+        std::mem::swap(&mut prev, &mut row);
+    }
+    a.len() + b.len() - 2 * prev[b.len()]
+}
+
+struct Matcher<'a> {
+    old: Side<'a>,
+    new: Side<'a>,
+    threshold: f32,
+    pairs: BTreeMap<u64, (u64, MatchMethod, f32)>,
+    taken: BTreeSet<u64>,
+}
+
+impl Matcher<'_> {
+    fn pair(&mut self, old: u64, new: u64, method: MatchMethod, similarity: f32) -> bool {
+        if self.pairs.contains_key(&old) || self.taken.contains(&new) {
+            return false;
+        }
+        self.pairs.insert(old, (new, method, similarity));
+        self.taken.insert(new);
+        true
+    }
+
+    fn propagate(&mut self) {
+        let mut worklist: Vec<(u64, u64)> = self.pairs.iter().map(|(&old, &(new, ..))| (old, new)).collect();
+        while let Some((old, new)) = worklist.pop() {
+            let (a, b) = (self.old.callees[&old].clone(), self.new.callees[&new].clone());
+            for (&x, &y) in a.iter().zip(&b) {
+                let score = self.old.prints[&x].similarity(&self.new.prints[&y]);
+                if score >= self.threshold && self.pair(x, y, MatchMethod::CallGraph, score) {
+                    worklist.push((x, y));
+                }
+            }
+        }
+    }
+
+    fn identical(&mut self) {
+        let key = |p: &Fingerprint| (p.insts, p.grams.clone());
+        let mut groups: BTreeMap<_, (Vec<u64>, Vec<u64>)> = BTreeMap::new();
+        for p in self.old.prints.values() {
+            groups.entry(key(p)).or_default().0.push(p.entry);
+        }
+        for p in self.new.prints.values() {
+            groups.entry(key(p)).or_default().1.push(p.entry);
+        }
+        for (olds, news) in groups.into_values() {
+            if let ([old], [new]) = (&olds[..], &news[..]) {
+                self.pair(*old, *new, MatchMethod::Identical, 1.0);
+            }
+        }
+    }
+
+    fn best_remaining(&mut self) {
+        let mut candidates = Vec::new();
+        for (&old, a) in self.old.prints.iter().filter(|(e, _)| !self.pairs.contains_key(e)) {
+            for (&new, b) in self.new.prints.iter().filter(|(e, _)| !self.taken.contains(e)) {
+                let (small, large) = (a.grams.len().min(b.grams.len()), a.grams.len().max(b.grams.len()));
+                if (small as f32) < self.threshold * large as f32 {
+                    continue;
+                }
+                let score = a.similarity(b);
+                if score >= self.threshold {
+                    candidates.push((score, old, new));
+                }
+            }
+        }
+        candidates.sort_by(|x, y| y.0.total_cmp(&x.0).then((x.1, x.2).cmp(&(y.1, y.2))));
+        for (score, old, new) in candidates {
+            self.pair(old, new, MatchMethod::Fingerprint, score);
+        }
+    }
+}
+
+/// Pairs the functions of the old and new versions, given as [entry, end)
+/// ranges, matching by similarity only at or above threshold.
+pub fn bindiff(
+    old: &Program,
+    old_functions: &[(u64, u64)],
+    new: &Program,
+    new_functions: &[(u64, u64)],
+    threshold: f32,
+) -> BinDiff {
+    let mut m = Matcher {
+        old: Side::new(old, old_functions),
+        new: Side::new(new, new_functions),
+        threshold,
+        pairs: BTreeMap::new(),
+        taken: BTreeSet::new(),
+    };
+    m.identical();
+    m.propagate();
+    m.best_remaining();
+    m.propagate();
+
+    let mut out = BinDiff::default();
+    for (&old, &(new, method, similarity)) in &m.pairs {
+        let changed = changed_insts(&m.old.insts(old), &m.new.insts(new));
+        out.matches.push(FunctionMatch { old, new, method, similarity, changed });
+    }
+    out.unmatched_old = m.old.ranges.keys().copied().filter(|e| !m.pairs.contains_key(e)).collect();
+    out.unmatched_new = m.new.ranges.keys().copied().filter(|e| !m.taken.contains(e)).collect();
+    out
+}
+
+/// Function entries of a program without symbols: the start of each region
+/// and the targets of BL inside the program.
+pub fn call_entries(program: &Program) -> BTreeSet<u64> {
+    let mut out: BTreeSet<u64> = program.regions().iter().map(|&(start, _)| start).collect();
+    for (pc, inst) in program.iter() {
+        if inst.op == Op::A64_BL {
+            out.extend(target_address(inst, pc).filter(|&t| program.get(t).is_some()));
+        }
+    }
+    out
+}
+
+/// [entry, end) ranges for the entries: each function runs to the next entry
+/// or the end of its region. Shared epilogues and interleaved functions
+/// need the CFG: see `aarch64_functions`.
+pub fn function_ranges(program: &Program, entries: &BTreeSet<u64>) -> Vec<(u64, u64)> {
+    let regions = program.regions();
+    let entries: Vec<u64> = entries.iter().copied().collect();
+    let mut out = Vec::new();
+    for (i, &entry) in entries.iter().enumerate() {
+        let Some(&(_, region_end)) = regions.iter().find(|&&(start, end)| (start..end).contains(&entry)) else {
+            continue;
+        };
+        let next = entries.get(i + 1).copied().unwrap_or(u64::MAX);
+        out.push((entry, next.min(region_end)));
+    }
+    out
+}
+
+impl BinDiff {
+    /// The matches with changed instructions, most changed first.
+    pub fn changed(&self) -> Vec<&FunctionMatch> {
+        let mut out: Vec<&FunctionMatch> = self.matches.iter().filter(|m| m.changed > 0).collect();
+        out.sort_by(|a, b| b.changed.cmp(&a.changed).then(a.similarity.total_cmp(&b.similarity)));
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\"changed\":[");
+        for (i, m) in self.changed().iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(
+                out,
+                "{}{{\"old\":{},\"new\":{},\"method\":\"{:?}\",\"similarity\":{:.3},\"changed\":{}}}",
+                sep, m.old, m.new, m.method, m.similarity, m.changed
+            )
+            .unwrap();
+        }
+        let unchanged = self.matches.len() - self.changed().len();
+        write!(out, "],\"unchanged\":{},\"unmatched_old\":{:?},\"unmatched_new\":{:?}}}", unchanged, self.unmatched_old, self.unmatched_new)
+            .unwrap();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moved_callee_with_new_constant() {
+        // caller: bl callee; add x0, x0, #1; mul x0, x0, x1; ret
+        // callee: add x0, x0, #1; mul x0, x0, x1; sub x0, x0, #2; lsl x0, x0, #3; ret
+        let old = Program::from_words(&[
+            0x94000004, 0x91000400, 0x9B017C00, 0xD65F03C0,
+            0x91000400, 0x9B017C00, 0xD1000800, 0xD37DF000, 0xD65F03C0,
+        ], 0);
+        // The callee moves down one instruction and subtracts #3 instead.
+        let new = Program::from_words(&[
+            0x94000005, 0x91000400, 0x9B017C00, 0xD65F03C0, 0xD503201F,
+            0x91000400, 0x9B017C00, 0xD1000C00, 0xD37DF000, 0xD65F03C0,
+        ], 0);
+        let diff = bindiff(&old, &[(0x0, 0x10), (0x10, 0x24)], &new, &[(0x0, 0x10), (0x14, 0x28)], 0.5);
+        assert_eq!(diff.matches.len(), 2);
+        assert_eq!((diff.matches[0].old, diff.matches[0].new, diff.matches[0].changed), (0x0, 0x0, 0));
+        let callee = &diff.matches[1];
+        assert_eq!((callee.old, callee.new, callee.method, callee.changed), (0x10, 0x14, MatchMethod::Identical, 2));
+        assert_eq!(diff.changed(), vec![callee]);
+    }
+}
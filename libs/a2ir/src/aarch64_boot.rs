@@ -0,0 +1,121 @@
+//! Flat images of firmware, bootloaders and kernels: the bytes decoded at
+//! the address they are loaded at, the entry point, and the registers the
+//! boot protocol passes, for constant propagation to start from.
+//!
+//! ```text
+//! BootImage::raw(&bytes, 0x4000_0000).with_dtb(0x4800_0000)
+//!     entry 0x4000_0000, x0 = 0x4800_0000 (the DTB), x1...x3 = 0
+//! BootImage::linux(&image, 0x4000_0000)
+//!     loaded at 0x4000_0000 + text_offset, as the arm64 Image header says
+//! ```
+
+use crate::aarch64_buffer::{decode_buffer, DecodeOptions};
+use crate::aarch64_constprop::ConstOptions;
+use crate::aarch64_program::Program;
+
+/// "ARM\x64" at offset 0x38 of the arm64 Image header.
+const LINUX_MAGIC: u32 = 0x644D_5241;
+const LINUX_HEADER_SIZE: usize = 0x40;
+/// The text_offset of Images older than 3.17, whose image_size is 0.
+const LINUX_OLD_TEXT_OFFSET: u64 = 0x8_0000;
+/// Images are loaded text_offset bytes above a 2MB-aligned base.
+const LINUX_BASE_ALIGN: u64 = 0x20_0000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BootImage {
+    /// The address of the first byte.
+    pub load: u64,
+    pub entry: u64,
+    pub bytes: Vec<u8>,
+    /// The values of X0...X30 at the entry, if known.
+    pub regs: [Option<u64>; 31],
+}
+
+impl BootImage {
+    /// An image loaded at base and entered at its first byte.
+    pub fn raw(bytes: &[u8], base: u64) -> BootImage {
+        BootImage { load: base, entry: base, bytes: bytes.to_vec(), regs: [None; 31] }
+    }
+
+    /// A Linux arm64 Image, loaded text_offset bytes above base as its
+    /// header asks and entered at its first byte.
+    pub fn linux(bytes: &[u8], base: u64) -> Result<BootImage, String> {
+        let u64_at = |o: usize| u64::from_le_bytes(bytes[o..o + 8].try_into().expect("8 bytes"));
+        if bytes.len() < LINUX_HEADER_SIZE {
+            return Err("boot: Image shorter than its header".to_string());
+        }
+        if u32::from_le_bytes(bytes[0x38..0x3C].try_into().expect("4 bytes")) != LINUX_MAGIC {
+            return Err("boot: not an arm64 Image".to_string());
+        }
+        let (text_offset, image_size, flags) = (u64_at(8), u64_at(16), u64_at(24));
+        if image_size != 0 && flags & 1 != 0 {
+            return Err("boot: big-endian Image".to_string());
+        }
+        if !base.is_multiple_of(LINUX_BASE_ALIGN) {
+            return Err(format!("boot: base {:#x} is not 2MB-aligned", base));
+        }
+        let text_offset = if image_size == 0 { LINUX_OLD_TEXT_OFFSET } else { text_offset };
+        Ok(BootImage::raw(bytes, base + text_offset))
+    }
+
+    /// Passes the DTB at dtb in X0, and zero in X1...X3, as the arm64
+    /// Linux boot protocol (and U-Boot's bootm, for any image) does.
+    pub fn with_dtb(mut self, dtb: u64) -> BootImage {
+        self.regs[..4].copy_from_slice(&[Some(dtb), Some(0), Some(0), Some(0)]);
+        self
+    }
+
+    /// The image decoded at the address it is loaded at.
+    pub fn program(&self) -> Program {
+        Program::from_buffer(decode_buffer(&self.bytes, &DecodeOptions { base: self.load, ..Default::default() }))
+    }
+
+    /// Constant propagation options for the block at the entry.
+    pub fn entry_options(&self) -> ConstOptions {
+        ConstOptions { regs: self.regs, ..Default::default() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_constprop::propagate;
+
+    fn words(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn raw_image_with_dtb() {
+        let image = BootImage::raw(&words(&[
+            0xB9400001, // ldr w1, [x0]: the DTB's magic
+            0x91004002, // add x2, x0, #0x10
+        ]), 0x4000_0000).with_dtb(0x4800_0000);
+        let program = image.program();
+        assert_eq!(program.first().map(|(a, _)| a), Some(image.entry));
+        let block: Vec<_> = program.iter().map(|(a, i)| (a, i.clone())).collect();
+        let resolved = propagate(&block, &image.entry_options());
+        assert_eq!(resolved.data_addresses.get(&0x4000_0000), Some(&0x4800_0000));
+        assert_eq!(resolved.values.get(&0x4000_0004), Some(&0x4800_0010));
+        assert!(propagate(&block, &ConstOptions::default()).data_addresses.is_empty());
+    }
+
+    #[test]
+    fn linux_image_header() {
+        let mut image = words(&[0x14000010, 0]); // b .+0x40
+        image.extend(0x8_0000u64.to_le_bytes()); // text_offset
+        image.extend(0x44u64.to_le_bytes()); // image_size
+        image.extend(0xAu64.to_le_bytes()); // flags: 4KB pages, anywhere
+        image.extend([0; 24]);
+        image.extend(words(&[LINUX_MAGIC, 0]));
+        image.extend(words(&[0xB9400001])); // ldr w1, [x0]
+        let boot = BootImage::linux(&image, 0x4000_0000).unwrap();
+        assert_eq!((boot.load, boot.entry), (0x4008_0000, 0x4008_0000));
+        assert!(boot.program().get(0x4008_0040).is_some());
+
+        assert!(BootImage::linux(&image, 0x4010_0000).is_err());
+        image[24] = 0xB; // big-endian
+        assert!(BootImage::linux(&image, 0x4000_0000).is_err());
+        assert!(BootImage::linux(&image[..0x38], 0x4000_0000).is_err());
+    }
+}
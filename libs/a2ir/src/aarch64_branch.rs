@@ -0,0 +1,91 @@
+//! Classification of control transfers.
+
+use crate::aarch64_reader::{Inst, Op};
+use crate::aarch64_relocate::target_address;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BranchKind {
+    /// B.cond, CBZ, CBNZ, TBZ, TBNZ
+    Conditional,
+    /// B
+    Direct,
+    /// BR, BRA*
+    Indirect { authenticated: bool },
+    /// BL
+    Call,
+    /// BLR, BLRA*
+    IndirectCall { authenticated: bool },
+    /// RET, RETA*
+    Return { authenticated: bool },
+    /// ERET, ERETA*, DRPS
+    ExceptionReturn { authenticated: bool },
+}
+
+impl BranchKind {
+    pub fn is_call(self) -> bool {
+        matches!(self, BranchKind::Call | BranchKind::IndirectCall { .. })
+    }
+
+    /// Whether the target isn't encoded in the instruction.
+    pub fn is_indirect(self) -> bool {
+        !matches!(self, BranchKind::Conditional | BranchKind::Direct | BranchKind::Call)
+    }
+
+    pub fn is_authenticated(self) -> bool {
+        match self {
+            BranchKind::Indirect { authenticated }
+            | BranchKind::IndirectCall { authenticated }
+            | BranchKind::Return { authenticated }
+            | BranchKind::ExceptionReturn { authenticated } => authenticated,
+            _ => false,
+        }
+    }
+
+    /// Whether execution may continue with the next instruction: after a
+    /// not-taken conditional branch, or after a call returns.
+    pub fn falls_through(self) -> bool {
+        matches!(self, BranchKind::Conditional | BranchKind::Call | BranchKind::IndirectCall { .. })
+    }
+}
+
+impl Inst {
+    /// None for instructions that aren't branches. Exception generating
+    /// instructions aren't branches either.
+    pub fn branch_kind(&self) -> Option<BranchKind> {
+        Some(match self.op {
+            Op::A64_BCOND | Op::A64_CBZ | Op::A64_CBNZ | Op::A64_TBZ | Op::A64_TBNZ => BranchKind::Conditional,
+            Op::A64_B => BranchKind::Direct,
+            Op::A64_BL => BranchKind::Call,
+            Op::A64_BR => BranchKind::Indirect { authenticated: false },
+            Op::A64_BRA => BranchKind::Indirect { authenticated: true },
+            Op::A64_BLR => BranchKind::IndirectCall { authenticated: false },
+            Op::A64_BLRA => BranchKind::IndirectCall { authenticated: true },
+            Op::A64_RET => BranchKind::Return { authenticated: false },
+            Op::A64_RETA => BranchKind::Return { authenticated: true },
+            Op::A64_ERET | Op::A64_DRPS => BranchKind::ExceptionReturn { authenticated: false },
+            Op::A64_ERETA => BranchKind::ExceptionReturn { authenticated: true },
+            _ => return None,
+        })
+    }
+}
+
+/// Whether the instruction at pc is a tail call: a B to the entry of a
+/// function, as told by is_entry. Indirect branches might be tail calls too,
+/// but can't be told apart from computed jumps statically.
+pub fn is_tail_call(inst: &Inst, pc: u64, is_entry: impl Fn(u64) -> bool) -> bool {
+    inst.branch_kind() == Some(BranchKind::Direct) && target_address(inst, pc).is_some_and(is_entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_reader::decode;
+
+    #[test]
+    fn pac_variants() {
+        assert_eq!(decode(0xD65F0BFF).branch_kind(), Some(BranchKind::Return { authenticated: true })); // retaa
+        assert_eq!(decode(0xD63F0A1F).branch_kind(), Some(BranchKind::IndirectCall { authenticated: true })); // blraaz x16
+        assert_eq!(decode(0xD69F03E0).branch_kind(), Some(BranchKind::ExceptionReturn { authenticated: false })); // eret
+        assert_eq!(decode(0xD503201F).branch_kind(), None); // nop
+    }
+}
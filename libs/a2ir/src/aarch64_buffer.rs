@@ -0,0 +1,145 @@
+//! Decoding of whole buffers of instructions.
+//!
+//! Buffers don't need to be perfectly sized: bytes before the first
+//! word-aligned address and after the last full word are reported instead of
+//! decoded, and an optional validity mask restricts decoding to the words the
+//! caller knows to be code.
+
+use crate::aarch64_decoder::{A64Decoder, Decoder};
+use crate::aarch64_reader::{decode, Inst};
+
+/// Byte order of the instruction words in a buffer. A64 instructions are
+/// always little-endian in memory, but dumps and captures sometimes aren't.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ByteOrder {
+    #[default]
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    pub fn word(self, bytes: [u8; 4]) -> u32 {
+        match self {
+            ByteOrder::Little => u32::from_le_bytes(bytes),
+            ByteOrder::Big => u32::from_be_bytes(bytes),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DecodeOptions<'a> {
+    /// Virtual address of the first byte of the buffer.
+    pub base: u64,
+    pub byte_order: ByteOrder,
+    /// Only words whose address passes this predicate are decoded.
+    pub valid: Option<&'a dyn Fn(u64) -> bool>,
+    /// Decodes the words; the A64 reader if None.
+    pub decoder: Option<&'a dyn Decoder>,
+}
+
+pub struct DecodedBuffer {
+    /// Address and decoding of every decoded word, in address order.
+    pub insts: Vec<(u64, Inst)>,
+    /// Bytes before the first word-aligned address (only if base is unaligned).
+    pub leading: Vec<u8>,
+    /// Bytes after the last full word.
+    pub trailing: Vec<u8>,
+    /// Addresses of the words rejected by the validity mask.
+    pub skipped: Vec<u64>,
+}
+
+impl DecodedBuffer {
+    /// Address of the first trailing byte, if any.
+    pub fn trailing_address(&self, opts: &DecodeOptions) -> Option<u64> {
+        if self.trailing.is_empty() {
+            return None;
+        }
+        let decoded_len = self.leading.len() + 4 * (self.insts.len() + self.skipped.len());
+        Some(opts.base + decoded_len as u64)
+    }
+}
+
+pub fn decode_buffer(bytes: &[u8], opts: &DecodeOptions) -> DecodedBuffer {
+    let misalign = (opts.base % 4) as usize;
+    let lead = if misalign == 0 { 0 } else { (4 - misalign).min(bytes.len()) };
+    let (leading, rest) = bytes.split_at(lead);
+    let aligned_base = opts.base + lead as u64;
+
+    let mut out = DecodedBuffer {
+        insts: Vec::with_capacity(rest.len() / 4),
+        leading: leading.to_vec(),
+        trailing: rest.chunks_exact(4).remainder().to_vec(),
+        skipped: Vec::new(),
+    };
+
+    let decoder = opts.decoder.unwrap_or(&A64Decoder);
+    for (i, chunk) in rest.chunks_exact(4).enumerate() {
+        let addr = aligned_base + 4 * i as u64;
+        if let Some(valid) = opts.valid {
+            if !valid(addr) {
+                out.skipped.push(addr);
+                continue;
+            }
+        }
+        let binst = opts.byte_order.word([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        out.insts.push((addr, decoder.decode(binst, addr)));
+    }
+
+    out
+}
+
+/// Decodes words that are already assembled, e.g. read from guest memory,
+/// the first of them located at base.
+pub fn decode_words(words: &[u32], base: u64) -> Vec<(u64, Inst)> {
+    decode_iter(words.iter().copied(), base).collect()
+}
+
+/// decode_words with another decoder.
+pub fn decode_words_with(words: &[u32], base: u64, decoder: &dyn Decoder) -> Vec<(u64, Inst)> {
+    words.iter().enumerate().map(|(i, &binst)| (base + 4 * i as u64, decoder.decode(binst, base + 4 * i as u64))).collect()
+}
+
+/// Lazily decodes a stream of words, the first of them located at base.
+pub fn decode_iter<I: IntoIterator<Item = u32>>(words: I, base: u64) -> impl Iterator<Item = (u64, Inst)> {
+    words.into_iter().enumerate().map(move |(i, binst)| (base + 4 * i as u64, decode(binst)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unaligned_head_and_tail() {
+        let bytes = [0xAA, 0x1F, 0x20, 0x03, 0xD5, 0xBB, 0xCC];
+        let opts = DecodeOptions { base: 0x1003, ..Default::default() };
+        let out = decode_buffer(&bytes, &opts);
+        assert_eq!(out.leading, vec![0xAA]);
+        assert_eq!(out.insts.len(), 1);
+        assert_eq!(out.insts[0].0, 0x1004);
+        assert_eq!(out.trailing, vec![0xBB, 0xCC]);
+        assert_eq!(out.trailing_address(&opts), Some(0x1008));
+    }
+
+    #[test]
+    fn big_endian_words() {
+        let le = decode_buffer(&[0x1F, 0x20, 0x03, 0xD5], &DecodeOptions::default());
+        let opts = DecodeOptions { byte_order: ByteOrder::Big, ..Default::default() };
+        let be = decode_buffer(&[0xD5, 0x03, 0x20, 0x1F], &opts);
+        assert_eq!(le.insts[0].1, be.insts[0].1);
+    }
+
+    #[test]
+    fn words_iterators_and_bytes_agree() {
+        let words = [0xD503201F, 0x91000420, 0xD65F03C0]; // nop; add x0, x1, #1; ret
+        let bytes: Vec<u8> = words.iter().flat_map(|w: &u32| w.to_le_bytes()).collect();
+        let from_bytes = decode_buffer(&bytes, &DecodeOptions { base: 0x4000, ..Default::default() }).insts;
+        let from_words = decode_words(&words, 0x4000);
+        assert_eq!(from_words, from_bytes);
+        assert_eq!(decode_iter(words.iter().copied(), 0x4000).collect::<Vec<_>>(), from_words);
+        assert_eq!(from_words.iter().map(|(a, _)| *a).collect::<Vec<_>>(), vec![0x4000, 0x4004, 0x4008]);
+
+        // Lazily: only the words taken are decoded, from an endless stream.
+        let mut endless = decode_iter(std::iter::repeat(0xD503201F), 0);
+        assert_eq!(endless.nth(1000).map(|(a, _)| a), Some(4000));
+    }
+}
@@ -0,0 +1,216 @@
+//! Call graphs: the call sites of every function and what they call,
+//! including imported functions called through PLT stubs, IFUNCs with all
+//! their candidate implementations, TLS descriptor resolvers, and indirect
+//! calls whose target constant propagation recovers.
+//!
+//! ```text
+//! main 0x1008: call malloc@plt -> malloc
+//! main 0x100c: call plt_1040 -> ifunc memcpy [memcpy_generic, memcpy_simd]
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::aarch64_branch::{is_tail_call, BranchKind};
+use crate::aarch64_constprop::{propagate, ConstOptions};
+use crate::aarch64_dynlink::{Ifunc, PltStub};
+use crate::aarch64_program::Program;
+use crate::aarch64_relocate::target_address;
+use crate::aarch64_tls::{TlsAccess, TlsModel};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Callee {
+    Function(u64),
+    /// Through the PLT stub at stub; symbol is None for unnamed GOT slots.
+    Import { stub: u64, symbol: Option<String> },
+    /// Through a PLT stub (if stub is Some) or directly to the resolver of
+    /// a GNU IFUNC; any of the candidates may run.
+    Ifunc { stub: Option<u64>, resolver: u64, candidates: Vec<u64> },
+    /// The resolver of the TLS descriptor at slot, which returns the
+    /// offset of the variable from the thread pointer.
+    TlsDescriptor { slot: Option<u64>, symbol: Option<String> },
+    /// Indirect call to one of several targets, as value sets tell.
+    Candidates(Vec<u64>),
+    /// Indirect call with an unknown target.
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallSite {
+    pub pc: u64,
+    /// Entry of the calling function.
+    pub caller: u64,
+    /// A B (or BR) to the entry of a function instead of a BL (or BLR).
+    pub tail: bool,
+    pub callee: Callee,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CallGraph {
+    pub sites: Vec<CallSite>,
+    /// Function names by entry, for describe.
+    pub names: BTreeMap<u64, String>,
+    stubs: BTreeMap<u64, PltStub>,
+    /// By GOT slot.
+    ifuncs: BTreeMap<u64, Ifunc>,
+}
+
+impl CallGraph {
+    /// The call graph of the functions, as (entry, end) ranges of program.
+    pub fn build(program: &Program, functions: &[(u64, u64)], stubs: &[PltStub], ifuncs: &[Ifunc]) -> CallGraph {
+        let mut graph = CallGraph {
+            stubs: stubs.iter().map(|s| (s.addr, s.clone())).collect(),
+            ifuncs: ifuncs.iter().map(|f| (f.got_slot, f.clone())).collect(),
+            ..CallGraph::default()
+        };
+        let is_entry = |addr: u64| functions.iter().any(|&(entry, _)| entry == addr) || graph.stubs.contains_key(&addr);
+        let mut sites = Vec::new();
+        for &(entry, end) in functions {
+            let block: Vec<_> = program.range(entry..end).map(|(a, i)| (a, i.clone())).collect();
+            let resolved = propagate(&block, &ConstOptions::default());
+            for (pc, inst) in &block {
+                let (target, tail) = match inst.branch_kind() {
+                    Some(BranchKind::Call) => (target_address(inst, *pc), false),
+                    Some(BranchKind::IndirectCall { .. }) => (resolved.branch_targets.get(pc).copied(), false),
+                    Some(BranchKind::Direct) if is_tail_call(inst, *pc, is_entry) => (target_address(inst, *pc), true),
+                    Some(BranchKind::Indirect { .. }) => match resolved.branch_targets.get(pc) {
+                        Some(&target) if is_entry(target) => (Some(target), true),
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                sites.push(CallSite { pc: *pc, caller: entry, tail, callee: graph.callee(target) });
+            }
+        }
+        graph.sites = sites;
+        graph
+    }
+
+    fn callee(&self, target: Option<u64>) -> Callee {
+        match target {
+            Some(addr) => {
+                let stub = self.stubs.get(&addr);
+                let ifunc = match stub {
+                    Some(stub) => self.ifuncs.get(&stub.got_slot),
+                    None => self.ifuncs.values().find(|f| f.resolver == addr),
+                };
+                match (stub, ifunc) {
+                    (_, Some(f)) => Callee::Ifunc {
+                        stub: stub.map(|s| s.addr),
+                        resolver: f.resolver,
+                        candidates: f.candidates.clone(),
+                    },
+                    (Some(stub), None) => Callee::Import { stub: addr, symbol: stub.symbol.clone() },
+                    (None, None) => Callee::Function(addr),
+                }
+            }
+            None => Callee::Unknown,
+        }
+    }
+
+    /// Turns the unknown calls of TLS descriptor sequences into
+    /// TlsDescriptor callees.
+    pub fn mark_tls_calls(&mut self, accesses: &[TlsAccess]) {
+        for access in accesses.iter().filter(|a| a.model == TlsModel::Descriptor) {
+            for site in self.sites.iter_mut().filter(|s| s.pc == access.pc && s.callee == Callee::Unknown) {
+                site.callee = Callee::TlsDescriptor { slot: access.slot, symbol: access.symbol.clone() };
+            }
+        }
+    }
+
+    /// Gives the unknown calls the targets of `aarch64_valueset`, by pc.
+    pub fn resolve_indirect(&mut self, targets: &BTreeMap<u64, Vec<u64>>) {
+        for i in 0..self.sites.len() {
+            if self.sites[i].callee != Callee::Unknown {
+                continue;
+            }
+            self.sites[i].callee = match targets.get(&self.sites[i].pc).map(Vec::as_slice) {
+                Some(&[target]) => self.callee(Some(target)),
+                Some(many) if !many.is_empty() => Callee::Candidates(many.to_vec()),
+                _ => continue,
+            };
+        }
+    }
+
+    /// The call sites in the function at entry.
+    pub fn calls_from(&self, entry: u64) -> Vec<&CallSite> {
+        self.sites.iter().filter(|s| s.caller == entry).collect()
+    }
+
+    /// The call sites calling the function or stub at entry.
+    pub fn calls_to(&self, entry: u64) -> Vec<&CallSite> {
+        self.sites
+            .iter()
+            .filter(|s| match &s.callee {
+                Callee::Function(a) | Callee::Import { stub: a, .. } => *a == entry,
+                Callee::Candidates(candidates) => candidates.contains(&entry),
+                Callee::Ifunc { stub, resolver, candidates } => {
+                    *stub == Some(entry) || *resolver == entry || candidates.contains(&entry)
+                }
+                Callee::TlsDescriptor { .. } | Callee::Unknown => false,
+            })
+            .collect()
+    }
+
+    fn name(&self, entry: u64) -> String {
+        self.names.get(&entry).cloned().unwrap_or_else(|| format!("sub_{:x}", entry))
+    }
+
+    /// One line per call site.
+    pub fn describe(&self) -> String {
+        let mut out = String::new();
+        for site in &self.sites {
+            let kind = if site.tail { "tail call" } else { "call" };
+            let callee = match &site.callee {
+                Callee::Function(addr) => self.name(*addr),
+                Callee::Import { stub, symbol } => {
+                    let plt = self.stubs[stub].name();
+                    match symbol {
+                        Some(symbol) => format!("{} -> {}", plt, symbol),
+                        None => plt,
+                    }
+                }
+                Callee::Ifunc { stub, resolver, candidates } => {
+                    let names: Vec<String> = candidates.iter().map(|&c| self.name(c)).collect();
+                    let ifunc = format!("ifunc {} [{}]", self.name(*resolver), names.join(", "));
+                    match stub {
+                        Some(stub) => format!("{} -> {}", self.stubs[stub].name(), ifunc),
+                        None => ifunc,
+                    }
+                }
+                Callee::TlsDescriptor { symbol, .. } => format!("tlsdesc {}", symbol.as_deref().unwrap_or("?")),
+                Callee::Candidates(candidates) => {
+                    let names: Vec<String> = candidates.iter().map(|&c| self.name(c)).collect();
+                    format!("[{}]", names.join(", "))
+                }
+                Callee::Unknown => "?".to_string(),
+            };
+            out += &format!("{} {:#x}: {} {}\n", self.name(site.caller), site.pc, kind, callee);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_dynlink::find_plt_stubs;
+
+    #[test]
+    fn import_and_local_calls() {
+        let program = Program::from_words(&[
+            0x94000003, // bl .+12            (0x100c, the stub)
+            0x94000006, // bl .+24            (0x101c)
+            0xD63F0100, // blr x8
+            0xB0000010, // adrp x16, .+0x1000 (0x2000)
+            0xF9400E11, // ldr x17, [x16, #0x18]
+            0x91006210, // add x16, x16, #0x18
+            0xD61F0220, // br x17
+            0xD65F03C0, // ret
+        ], 0x1000);
+        let got = BTreeMap::from([(0x2018, "malloc".to_string())]);
+        let mut graph = CallGraph::build(&program, &[(0x1000, 0x100C), (0x101C, 0x1020)], &find_plt_stubs(&program, &got), &[]);
+        graph.names.insert(0x1000, "main".to_string());
+        assert_eq!(graph.describe(), "main 0x1000: call malloc@plt -> malloc\nmain 0x1004: call sub_101c\nmain 0x1008: call ?\n");
+        assert_eq!(graph.calls_to(0x100C).len(), 1);
+    }
+}
@@ -0,0 +1,199 @@
+//! Stack protectors: the canary a function copies from __stack_chk_guard
+//! (or from the TLS slot bionic keeps it in) into its frame, checks before
+//! returning, and the call to __stack_chk_fail when the check fails.
+//!
+//! ```text
+//! adrp x0, :got:__stack_chk_guard
+//! ldr  x0, [x0, #:got_lo12:__stack_chk_guard]
+//! ldr  x1, [x0]                 // load
+//! str  x1, [sp, #0x28]          // store to the frame slot
+//! ...
+//! ldr  x2, [sp, #0x28]          // check
+//! ...
+//! bl   __stack_chk_fail         // fail call
+//! ```
+//!
+//! The instructions of the pattern are reported together so that listings
+//! can annotate them and later consumers elide them.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::aarch64_constprop::{propagate, ConstOptions};
+use crate::aarch64_defuse::{def_use, Loc};
+use crate::aarch64_dynlink::PltStub;
+use crate::aarch64_listing::Annotate;
+use crate::aarch64_program::Program;
+use crate::aarch64_reader::FlagMasks::W32;
+use crate::aarch64_reader::{fad_get_addrmode, AddrMode, Inst, Op, Registries};
+use crate::aarch64_relocate::target_address;
+use crate::aarch64_sysreg::SysReg;
+
+/// Offset of the canary from TPIDR_EL0 on bionic (TLS_SLOT_STACK_GUARD).
+pub const TLS_CANARY_OFFSET: i64 = 0x28;
+
+/// Where the canary and the fail function are.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GuardSymbols {
+    /// Addresses of __stack_chk_guard.
+    pub guards: BTreeSet<u64>,
+    /// GOT slots holding the address of __stack_chk_guard.
+    pub guard_slots: BTreeSet<u64>,
+    /// Entries of __stack_chk_fail and of its PLT stubs.
+    pub fail: BTreeSet<u64>,
+}
+
+impl GuardSymbols {
+    /// From symbols by address, the symbols of the GOT slots, and the PLT
+    /// stubs.
+    pub fn new(names: &BTreeMap<u64, String>, got: &BTreeMap<u64, String>, stubs: &[PltStub]) -> GuardSymbols {
+        let named = |map: &BTreeMap<u64, String>, name: &str| -> BTreeSet<u64> {
+            map.iter().filter(|(_, n)| n.as_str() == name).map(|(a, _)| *a).collect()
+        };
+        let mut fail = named(names, "__stack_chk_fail");
+        fail.extend(stubs.iter().filter(|s| s.symbol.as_deref() == Some("__stack_chk_fail")).map(|s| s.addr));
+        GuardSymbols { guards: named(names, "__stack_chk_guard"), guard_slots: named(got, "__stack_chk_guard"), fail }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GuardSource {
+    /// __stack_chk_guard
+    Global,
+    /// TPIDR_EL0 plus the offset
+    ThreadPointer(i64),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StackProtector {
+    pub entry: u64,
+    pub source: GuardSource,
+    /// Loads of the canary from its source.
+    pub loads: Vec<u64>,
+    /// Stores of the canary to the frame.
+    pub stores: Vec<u64>,
+    /// The frame slot: base register (SP or X29) and offset.
+    pub slot: Option<(u8, i64)>,
+    /// Reloads of the frame slot for the check.
+    pub checks: Vec<u64>,
+    pub fail_calls: Vec<u64>,
+}
+
+impl StackProtector {
+    /// All instructions of the pattern, for eliding them. The ADRP and LDR
+    /// of the guard's address aren't included, since they may be shared.
+    pub fn insts(&self) -> BTreeSet<u64> {
+        self.loads.iter().chain(&self.stores).chain(&self.checks).chain(&self.fail_calls).copied().collect()
+    }
+}
+
+fn frame_slot(inst: &Inst) -> Option<(u8, i64)> {
+    let based = inst.rn == Registries::STACK_POINTER || inst.rn == 29;
+    let mode = fad_get_addrmode(inst.flags);
+    (based && matches!(mode, AddrMode::AM_OFF_IMM | AddrMode::AM_SIMPLE)).then_some((inst.rn, inst.offset))
+}
+
+/// The stack protector of the function at [entry, end); None if it has none.
+pub fn stack_protector(program: &Program, entry: u64, end: u64, symbols: &GuardSymbols) -> Option<StackProtector> {
+    let block: Vec<_> = program.range(entry..end).map(|(a, i)| (a, i.clone())).collect();
+    let resolved = propagate(&block, &ConstOptions::default());
+    let mut protector =
+        StackProtector { entry, source: GuardSource::Global, loads: vec![], stores: vec![], slot: None, checks: vec![], fail_calls: vec![] };
+    // Registers holding the guard's address, TPIDR_EL0, or the canary.
+    let (mut guard_ptr, mut tp, mut canary) = (BTreeSet::new(), BTreeSet::new(), BTreeSet::new());
+
+    for (pc, inst) in &block {
+        let (pc, x64) = (*pc, inst.flags & W32 == 0);
+        let data = resolved.data_addresses.get(&pc).copied();
+        let (mut is_guard_ptr, mut is_tp, mut is_canary) = (false, false, false);
+        match inst.op {
+            Op::A64_LDR if x64 && data.is_some_and(|a| symbols.guard_slots.contains(&a)) => is_guard_ptr = true,
+            Op::A64_LDR if x64 && (data.is_some_and(|a| symbols.guards.contains(&a)) || guard_ptr.contains(&inst.rn)) => {
+                protector.loads.push(pc);
+                is_canary = true;
+            }
+            Op::A64_LDR if x64 && tp.contains(&inst.rn) && inst.offset == TLS_CANARY_OFFSET => {
+                protector.source = GuardSource::ThreadPointer(inst.offset);
+                protector.loads.push(pc);
+                is_canary = true;
+            }
+            Op::A64_LDR if x64 && frame_slot(inst).is_some() && frame_slot(inst) == protector.slot => {
+                protector.checks.push(pc);
+            }
+            Op::A64_STR if x64 && canary.contains(&inst.rd) && frame_slot(inst).is_some() => {
+                protector.slot = frame_slot(inst);
+                protector.stores.push(pc);
+            }
+            Op::A64_MRS if inst.sysreg() == Some(SysReg::TPIDR_EL0) => is_tp = true,
+            Op::A64_BL | Op::A64_B if target_address(inst, pc).is_some_and(|t| symbols.fail.contains(&t)) => {
+                protector.fail_calls.push(pc);
+            }
+            _ => {}
+        }
+        if resolved.values.get(&pc).is_some_and(|v| symbols.guards.contains(v)) {
+            is_guard_ptr = true;
+        }
+        for def in def_use(inst).defs {
+            if let Loc::X(r) = def {
+                guard_ptr.remove(&r);
+                tp.remove(&r);
+                canary.remove(&r);
+            }
+        }
+        let rd = inst.rd;
+        if is_guard_ptr {
+            guard_ptr.insert(rd);
+        }
+        if is_tp {
+            tp.insert(rd);
+        }
+        if is_canary {
+            canary.insert(rd);
+        }
+    }
+    (!protector.stores.is_empty() || !protector.fail_calls.is_empty()).then_some(protector)
+}
+
+/// Marks the instructions of the pattern in listings.
+impl Annotate for StackProtector {
+    fn comment(&self, addr: u64, _inst: &Inst) -> Option<String> {
+        let role = if self.loads.contains(&addr) {
+            "load"
+        } else if self.stores.contains(&addr) {
+            "store"
+        } else if self.checks.contains(&addr) {
+            "check"
+        } else if self.fail_calls.contains(&addr) {
+            "fail"
+        } else {
+            return None;
+        };
+        Some(format!("stack protector: {}", role))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn got_guard() {
+        let program = Program::from_words(&[
+            0xB0000000, // adrp x0, .+0x1000        (0x2000)
+            0xF9400400, // ldr x0, [x0, #8]         (the GOT slot)
+            0xF9400001, // ldr x1, [x0]
+            0xF90017E1, // str x1, [sp, #0x28]
+            0xF9400002, // ldr x2, [x0]
+            0xF94017E3, // ldr x3, [sp, #0x28]
+            0x94000002, // bl .+8                   (__stack_chk_fail)
+            0xD65F03C0, // ret
+            0xD65F03C0, // ret
+        ], 0x1000);
+        let names = BTreeMap::from([(0x1020, "__stack_chk_fail".to_string())]);
+        let got = BTreeMap::from([(0x2008, "__stack_chk_guard".to_string())]);
+        let symbols = GuardSymbols::new(&names, &got, &[]);
+        let protector = stack_protector(&program, 0x1000, 0x1020, &symbols).unwrap();
+        assert_eq!(protector.slot, Some((Registries::STACK_POINTER, 0x28)));
+        assert_eq!(protector.insts().into_iter().collect::<Vec<_>>(), vec![0x1008, 0x100C, 0x1010, 0x1014, 0x1018]);
+        assert_eq!(stack_protector(&program, 0x101C, 0x1020, &symbols), None);
+    }
+}
@@ -0,0 +1,233 @@
+//! Control flow graphs: the basic blocks of a program and how each ends.
+//!
+//! An indirect branch through a jump table becomes a Switch terminator,
+//! with the case values and their targets, once the table is known:
+//!
+//! ```text
+//! cmp  w0, #3
+//! b.hi default
+//! ...                           // load the entry of the table for w0
+//! br   x2                       // Switch { index: 0, cases: 0..=3, default }
+//! ```
+//!
+//! The bound and the default come from the CMP and B.HI guarding the
+//! branch. The table load and the address computation (register offset
+//! loads, ADD with extended or shifted registers) aren't decoded, so the
+//! format of the table is the caller's: see `read_jump_table`.
+
+use std::collections::BTreeMap;
+
+use crate::aarch64_branch::{is_tail_call, BranchKind};
+use crate::aarch64_program::{Program, INST_SIZE};
+use crate::aarch64_reader::{fad_get_cond, Cond, Op};
+use crate::aarch64_relocate::target_address;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Terminator {
+    /// Continues into the next block, which starts at a branch target.
+    FallThrough(u64),
+    Jump(u64),
+    /// B to the entry of a function, which returns to the caller of this
+    /// one; not an edge of the CFG.
+    TailCall(u64),
+    /// BL to a function that doesn't return, such as abort.
+    NoReturnCall(u64),
+    Branch { taken: u64, fallthrough: u64 },
+    Return,
+    /// BR with an unknown target.
+    Indirect,
+    /// Jump on the value of X<index> (or W<index>) to the target of its
+    /// case; values without a case go to default.
+    Switch { index: u8, cases: Vec<(u64, u64)>, default: Option<u64> },
+    /// No successor: a gap, the end of the program, or an exception return.
+    End,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    pub start: u64,
+    pub end: u64,
+    pub terminator: Terminator,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cfg {
+    pub blocks: BTreeMap<u64, Block>,
+    /// Landing pads of the calls that may throw, by call pc.
+    pub exception_edges: BTreeMap<u64, u64>,
+    /// The possible targets of Indirect terminators, where they are known,
+    /// by branch pc; see `aarch64_valueset`.
+    pub indirect_targets: BTreeMap<u64, Vec<u64>>,
+}
+
+impl Cfg {
+    pub fn build(program: &Program) -> Cfg {
+        let mut cfg = Cfg::default();
+        for (start, end) in program.basic_blocks() {
+            let (last, inst) = program.prev(end).filter(|(a, _)| *a >= start).expect("blocks aren't empty");
+            let next = program.get(end).map(|_| end);
+            let terminator = match inst.branch_kind() {
+                Some(BranchKind::Direct) => target_address(inst, last).map_or(Terminator::End, Terminator::Jump),
+                Some(BranchKind::Conditional) => match (target_address(inst, last), next) {
+                    (Some(taken), Some(fallthrough)) => Terminator::Branch { taken, fallthrough },
+                    (Some(taken), None) => Terminator::Jump(taken),
+                    _ => Terminator::End,
+                },
+                Some(BranchKind::Indirect { .. }) => Terminator::Indirect,
+                Some(BranchKind::Return { .. }) => Terminator::Return,
+                Some(BranchKind::ExceptionReturn { .. }) => Terminator::End,
+                _ => next.map_or(Terminator::End, Terminator::FallThrough),
+            };
+            cfg.blocks.insert(start, Block { start, end, terminator });
+        }
+        cfg
+    }
+
+    pub fn successors(&self, start: u64) -> Vec<u64> {
+        let Some(block) = self.blocks.get(&start) else {
+            return Vec::new();
+        };
+        match &block.terminator {
+            Terminator::FallThrough(next) | Terminator::Jump(next) => vec![*next],
+            Terminator::Branch { taken, fallthrough } => vec![*taken, *fallthrough],
+            Terminator::Switch { cases, default, .. } => {
+                let mut out: Vec<u64> = cases.iter().map(|(_, t)| *t).chain(*default).collect();
+                out.sort();
+                out.dedup();
+                out
+            }
+            Terminator::Indirect => self.indirect_targets.get(&(block.end - INST_SIZE)).cloned().unwrap_or_default(),
+            Terminator::TailCall(_) | Terminator::NoReturnCall(_) | Terminator::Return | Terminator::End => Vec::new(),
+        }
+    }
+
+    /// Splits the block containing addr so that a block starts there.
+    pub fn split_at(&mut self, addr: u64) {
+        let Some((_, block)) = self.blocks.range_mut(..addr).next_back().filter(|(_, b)| addr < b.end) else {
+            return;
+        };
+        let mut tail = block.clone();
+        block.end = addr;
+        block.terminator = Terminator::FallThrough(addr);
+        tail.start = addr;
+        self.blocks.insert(addr, tail);
+    }
+
+    /// Turns the jumps to function entries, as told by is_entry, into tail
+    /// calls.
+    pub fn mark_tail_calls(&mut self, program: &Program, is_entry: impl Fn(u64) -> bool) {
+        for block in self.blocks.values_mut() {
+            let Terminator::Jump(target) = block.terminator else { continue };
+            let Some((pc, inst)) = program.prev(block.end) else { continue };
+            if is_tail_call(inst, pc, &is_entry) {
+                block.terminator = Terminator::TailCall(target);
+            }
+        }
+    }
+
+    /// The bound guarding the indirect branch ending the block at start:
+    /// the register, the largest case value and the default target, from a
+    /// preceding `cmp wN, #bound; b.hi default`.
+    pub fn switch_bound(&self, program: &Program, start: u64) -> Option<(u8, u64, u64)> {
+        let guard = self.blocks.values().find(|b| {
+            matches!(b.terminator, Terminator::Branch { fallthrough, .. } if fallthrough == start)
+        })?;
+        let Terminator::Branch { taken: default, .. } = guard.terminator else { return None };
+        let (bhi_pc, bhi) = program.prev(guard.end)?;
+        let (_, cmp) = program.prev(bhi_pc)?;
+        let guarded = bhi.op == Op::A64_BCOND && fad_get_cond(bhi.flags) == Cond::COND_HI && cmp.op == Op::A64_CMP_IMM;
+        guarded.then_some((cmp.rn, cmp.imm, default))
+    }
+
+    /// Lowers the indirect branch ending the block at start into a Switch
+    /// on the cases 0, 1, ... going to targets.
+    pub fn set_switch(&mut self, start: u64, index: u8, targets: &[u64], default: Option<u64>) -> Result<(), String> {
+        let block = self.blocks.get_mut(&start).ok_or(format!("set_switch: no block at {:#x}", start))?;
+        if block.terminator != Terminator::Indirect {
+            return Err(format!("set_switch: block {:#x} doesn't end with an indirect branch", start));
+        }
+        let cases = targets.iter().enumerate().map(|(i, &t)| (i as u64, t)).collect();
+        block.terminator = Terminator::Switch { index, cases, default };
+        Ok(())
+    }
+}
+
+/// The format of the entries of a jump table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TableFormat {
+    /// 1, 2, 4 or 8 bytes
+    pub size: usize,
+    pub signed: bool,
+    /// Entries are scaled by 1 << shift, e.g. 2 for entries counting
+    /// instructions.
+    pub shift: u32,
+    /// Added to the scaled entries; the table address for relative tables,
+    /// an ADR target for GCC's, zero for absolute ones.
+    pub base: u64,
+}
+
+/// The targets of the count entries of the table at table, read from the
+/// bytes at data_base.
+pub fn read_jump_table(data: &[u8], data_base: u64, table: u64, count: usize, format: &TableFormat) -> Result<Vec<u64>, String> {
+    if !matches!(format.size, 1 | 2 | 4 | 8) {
+        return Err(format!("read_jump_table: bad entry size {}", format.size));
+    }
+    let offset = table.checked_sub(data_base).ok_or(format!("read_jump_table: {:#x} before the data", table))? as usize;
+    let bytes = data
+        .get(offset..offset + count * format.size)
+        .ok_or(format!("read_jump_table: {} entries at {:#x} past the data", count, table))?;
+    Ok(bytes
+        .chunks(format.size)
+        .map(|entry| {
+            let mut raw = [0u8; 8];
+            raw[..format.size].copy_from_slice(entry);
+            let mut value = u64::from_le_bytes(raw);
+            if format.signed && format.size < 8 {
+                let unused = 64 - 8 * format.size as u32;
+                value = (((value << unused) as i64) >> unused) as u64;
+            }
+            format.base.wrapping_add(value << format.shift)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jump_table_switch() {
+        let program = Program::from_words(&[
+            0x7100081F, // cmp w0, #2
+            0x54000088, // b.hi .+16          (0x1014)
+            0xD503201F, // nop                (the table load)
+            0xD61F0040, // br x2
+            0xD65F03C0, // ret                case 0
+            0xD65F03C0, // ret                default, case 1
+            0xD65F03C0, // ret                case 2
+        ], 0x1000);
+        let mut cfg = Cfg::build(&program);
+        assert_eq!(cfg.blocks[&0x1008].terminator, Terminator::Indirect);
+        let (index, bound, default) = cfg.switch_bound(&program, 0x1008).unwrap();
+        assert_eq!((index, bound, default), (0, 2, 0x1014));
+
+        let format = TableFormat { size: 1, signed: true, shift: 2, base: 0x1010 };
+        let targets = read_jump_table(&[0x00, 0x01, 0x02], 0x2000, 0x2000, bound as usize + 1, &format).unwrap();
+        cfg.set_switch(0x1008, index, &targets, Some(default)).unwrap();
+        assert_eq!(cfg.successors(0x1008), vec![0x1010, 0x1014, 0x1018]);
+    }
+
+    #[test]
+    fn tail_call() {
+        let program = Program::from_words(&[
+            0x14000002, // b .+8
+            0xD65F03C0, // ret
+            0xD65F03C0, // ret                other function
+        ], 0x1000);
+        let mut cfg = Cfg::build(&program);
+        assert_eq!(cfg.successors(0x1000), vec![0x1008]);
+        cfg.mark_tail_calls(&program, |a| a == 0x1008);
+        assert_eq!(cfg.blocks[&0x1000].terminator, Terminator::TailCall(0x1008));
+        assert!(cfg.successors(0x1000).is_empty());
+    }
+}
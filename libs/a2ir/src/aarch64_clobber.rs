@@ -0,0 +1,172 @@
+//! The registers each function may modify, directly or through its
+//! callees: what a rewriter has to save around a call it inserts, and what
+//! is free to use where the function is called.
+//!
+//! A callee-saved register the function both spills to and reloads from
+//! its frame (an SP or X29 based store and load) counts as preserved; SP
+//! itself is assumed balanced. Calls to functions outside the list, BLR and
+//! BR with an unknown target clobber every caller-saved register, as
+//! AAPCS64 allows. A function containing words the decoder doesn't know
+//! may write anything, so it clobbers every register, callee-saved ones
+//! included, and so do its callers. Summaries grow until they stop changing
+//! over the call graph, so recursion is covered.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::aarch64_abi::{has_role, with_role, Role};
+use crate::aarch64_branch::BranchKind;
+use crate::aarch64_cfg::{Cfg, Terminator};
+use crate::aarch64_defuse::{all_locs, def_use, gpr, Loc};
+use crate::aarch64_functions::Function;
+use crate::aarch64_program::Program;
+use crate::aarch64_reader::{Inst, Op};
+use crate::aarch64_relocate::target_address;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Clobbers {
+    /// Registers that may differ after the function returns.
+    pub regs: BTreeSet<Loc>,
+    /// Some path calls or jumps to unknown code.
+    pub unknown_callee: bool,
+    /// The function or a callee contains undecoded words; regs holds every
+    /// register but SP.
+    pub undecoded: bool,
+}
+
+impl Clobbers {
+    /// The caller-saved registers the function leaves alone, usable as
+    /// scratch around its call sites.
+    pub fn spares(&self) -> Vec<Loc> {
+        with_role(Role::CallerSaved).into_iter().filter(|l| !self.regs.contains(l)).collect()
+    }
+}
+
+fn frame_base(inst: &Inst) -> bool {
+    matches!(gpr(inst.rn), Some(Loc::SP | Loc::X(29)))
+}
+
+fn every_register() -> BTreeSet<Loc> {
+    all_locs().into_iter().filter(|l| *l != Loc::SP).collect()
+}
+
+/// The direct clobbers of function, whether it contains undecoded words, and
+/// the targets of its calls and tail calls, None for unknown ones.
+fn direct(program: &Program, cfg: &Cfg, function: &Function) -> (BTreeSet<Loc>, bool, Vec<Option<u64>>) {
+    let (mut defs, mut spilled, mut reloaded) = (BTreeSet::new(), BTreeSet::new(), BTreeSet::new());
+    let mut callees = Vec::new();
+    let mut undecoded = false;
+    for &start in &function.blocks {
+        let block = &cfg.blocks[&start];
+        for (pc, inst) in program.range(start..block.end) {
+            let du = def_use(inst);
+            undecoded |= du.unknown;
+            match inst.op {
+                Op::A64_STR | Op::A64_STP | Op::A64_STR_FP | Op::A64_STP_FP if frame_base(inst) => spilled.extend(du.uses.iter().copied()),
+                Op::A64_LDR | Op::A64_LDP | Op::A64_LDR_FP | Op::A64_LDP_FP if frame_base(inst) => reloaded.extend(du.defs.iter().copied()),
+                _ => {}
+            }
+            defs.extend(du.defs);
+            match inst.branch_kind() {
+                Some(BranchKind::Call) => callees.push(target_address(inst, pc)),
+                Some(BranchKind::IndirectCall { .. }) => callees.push(None),
+                _ => {}
+            }
+        }
+        match block.terminator {
+            Terminator::TailCall(target) => callees.push(Some(target)),
+            Terminator::Indirect => callees.push(None),
+            _ => {}
+        }
+    }
+    if undecoded {
+        return (every_register(), true, callees);
+    }
+    defs.retain(|l| *l != Loc::SP && !(has_role(*l, Role::CalleeSaved) && spilled.contains(l) && reloaded.contains(l)));
+    (defs, false, callees)
+}
+
+/// The clobbers of the functions, by entry.
+pub fn clobbers(program: &Program, cfg: &Cfg, functions: &[Function]) -> BTreeMap<u64, Clobbers> {
+    let mut out = BTreeMap::new();
+    let mut calls = Vec::new();
+    for function in functions {
+        let (regs, undecoded, callees) = direct(program, cfg, function);
+        out.insert(function.entry, Clobbers { regs, unknown_callee: false, undecoded });
+        calls.push((function.entry, callees));
+    }
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (entry, callees) in &calls {
+            let mut next = out[entry].clone();
+            for callee in callees {
+                match callee.and_then(|c| out.get(&c)) {
+                    Some(c) => {
+                        next.regs.extend(c.regs.iter().copied());
+                        next.unknown_callee |= c.unknown_callee;
+                        next.undecoded |= c.undecoded;
+                    }
+                    None => {
+                        next.regs.extend(with_role(Role::CallerSaved));
+                        next.unknown_callee = true;
+                    }
+                }
+            }
+            if next != out[entry] {
+                out.insert(*entry, next);
+                changed = true;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_functions::functions;
+
+    #[test]
+    fn transitive_clobbers() {
+        let program = Program::from_words(&[
+            0xA9BF53F3, // 0x1000: stp x19, x20, [sp, #-16]!
+            0xD2800053, //         mov x19, #2
+            0x94000004, //         bl 0x1018
+            0xA8C153F3, //         ldp x19, x20, [sp], #16
+            0xD65F03C0, //         ret
+            0xD503201F, //         nop
+            0xB100043F, // 0x1018: cmn x1, #1
+            0xD2800029, //         mov x9, #1
+            0xD65F03C0, //         ret
+        ], 0x1000);
+        let mut cfg = Cfg::build(&program);
+        let functions = functions(&mut cfg, &BTreeSet::from([0x1000, 0x1018]));
+        let clobbers = clobbers(&program, &cfg, &functions);
+        let leaf = &clobbers[&0x1018];
+        assert_eq!(leaf.regs, BTreeSet::from([Loc::X(9), Loc::NZCV]));
+        assert!(leaf.spares().contains(&Loc::X(0)) && !leaf.unknown_callee);
+        // x19 is saved and restored; x30 is the call's, x9 and the flags the callee's.
+        assert_eq!(clobbers[&0x1000].regs, BTreeSet::from([Loc::X(9), Loc::X(30), Loc::NZCV]));
+    }
+
+    #[test]
+    fn undecoded_words_clobber_everything() {
+        let program = Program::from_words(&[
+            0xAA0103E0, // 0x1000: mov x0, x1: not decoded
+            0xD65F03C0, //         ret
+            0xA9BF7BF3, // 0x1008: stp x19, x30, [sp, #-16]!
+            0x97FFFFFD, //         bl 0x1000
+            0xA8C17BF3, //         ldp x19, x30, [sp], #16
+            0xD65F03C0, //         ret
+        ], 0x1000);
+        let mut cfg = Cfg::build(&program);
+        let functions = functions(&mut cfg, &BTreeSet::from([0x1000, 0x1008]));
+        let clobbers = clobbers(&program, &cfg, &functions);
+        for entry in [0x1000, 0x1008] {
+            let c = &clobbers[&entry];
+            assert!(c.undecoded && !c.unknown_callee, "{:#x}", entry);
+            assert!(c.regs.contains(&Loc::X(0)) && c.regs.contains(&Loc::X(19)) && !c.regs.contains(&Loc::SP), "{:#x}", entry);
+            assert_eq!(c.spares(), vec![], "{:#x}", entry);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Constant propagation over straight-line code, recovering the targets of
+//! indirect branches and the addresses of loads and stores that are built
+//! from ADR(P), ADD/SUB (immediate), MOV (wide and bitmask immediates) and
+//! MOVK.
+//!
+//! With a PacConfig, values are stripped of their pointer authentication
+//! code where they are used as code or data pointers, as XPACI and XPACD
+//! would; otherwise signed pointers (e.g. in arm64e binaries) don't resolve.
+//! Calls clobber the caller-saved registers X0...X18 and X30, and words the
+//! decoder doesn't know clobber all registers (see def_use). The arguments
+//! in X0 and X1 of the exception generating instructions are recorded too,
+//! and X8 of SVC, the system call number; their results clobber X0 (SVC and
+//! HLT) or X0...X17 (HVC and SMC).
+//! The values MSR writes to system registers are recorded as well. The
+//! registers start out unknown, unless ConstOptions gives their values.
+
+use std::collections::BTreeMap;
+
+use crate::aarch64_defuse::{def_use, is_memory, Loc};
+use crate::aarch64_pac::PacConfig;
+use crate::aarch64_reader::FlagMasks::W32;
+use crate::aarch64_reader::{fad_get_addrmode, AddrMode, Inst, Op, Registries};
+use crate::aarch64_relocate::target_address;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConstOptions {
+    /// Strip PACs from recovered pointers.
+    pub pac: Option<PacConfig>,
+    /// Known values of X0...X30 at the start of the block, e.g. those a
+    /// boot protocol passes.
+    pub regs: [Option<u64>; 31],
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Resolved {
+    /// Targets of BR, BLR and their authenticating variants, by pc.
+    pub branch_targets: BTreeMap<u64, u64>,
+    /// Addresses of loads and stores (before any writeback), by pc.
+    pub data_addresses: BTreeMap<u64, u64>,
+    /// X0 and X1 at SVC, HVC, SMC and HLT, by pc, if either is known.
+    pub trap_args: BTreeMap<u64, [Option<u64>; 2]>,
+    /// X8 at SVC, the system call number of Linux, by pc, if known.
+    pub syscall_numbers: BTreeMap<u64, u64>,
+    /// The values MSR (register) writes, by pc, if known.
+    pub sysreg_writes: BTreeMap<u64, u64>,
+    /// Values the propagated opcodes write to Rd, by pc, if known.
+    pub values: BTreeMap<u64, u64>,
+}
+
+/// Known values of X0...X30; SP and the flags aren't tracked.
+#[derive(Clone, Debug, Default)]
+struct State {
+    regs: [Option<u64>; 31],
+}
+
+impl State {
+    fn get(&self, r: u8) -> Option<u64> {
+        match r {
+            Registries::ZERO_REG => Some(0),
+            Registries::STACK_POINTER => None,
+            _ => self.regs[r as usize],
+        }
+    }
+
+    fn set(&mut self, r: u8, value: Option<u64>, w32: bool) {
+        if (r as usize) < self.regs.len() {
+            self.regs[r as usize] = if w32 { value.map(|v| v & 0xFFFF_FFFF) } else { value };
+        }
+    }
+}
+
+/// The value the instruction writes to Rd, if it is one of the propagated
+/// opcodes and its inputs are known.
+fn evaluate(state: &State, inst: &Inst, pc: u64) -> Option<u64> {
+    match inst.op {
+        Op::A64_ADR | Op::A64_ADRP => target_address(inst, pc),
+        Op::A64_MOV_IMM => Some(inst.imm),
+        Op::A64_MOVK => {
+            let lsl = inst.movk.lsl;
+            Some((state.get(inst.rd)? & !(0xFFFF << lsl)) | ((inst.movk.imm16 as u64) << lsl))
+        }
+        Op::A64_ADD_IMM => Some(state.get(inst.rn)?.wrapping_add(inst.imm)),
+        Op::A64_SUB_IMM => Some(state.get(inst.rn)?.wrapping_sub(inst.imm)),
+        Op::A64_ORR_IMM => Some(state.get(inst.rn)? | inst.imm),
+        Op::A64_MOV_SP => state.get(inst.rn),
+        _ => None,
+    }
+}
+
+pub fn propagate(block: &[(u64, Inst)], options: &ConstOptions) -> Resolved {
+    let mut state = State { regs: options.regs };
+    let mut out = Resolved::default();
+    let strip = |ptr: u64, code: bool| options.pac.map_or(ptr, |pac| pac.strip(ptr, code));
+
+    for (pc, inst) in block {
+        let pc = *pc;
+        match inst.op {
+            Op::A64_BR | Op::A64_BLR | Op::A64_BRA | Op::A64_BLRA => {
+                if let Some(target) = state.get(inst.rn) {
+                    out.branch_targets.insert(pc, strip(target, true));
+                }
+            }
+            Op::A64_SVC | Op::A64_HVC | Op::A64_SMC | Op::A64_HLT => {
+                let args = [state.get(0), state.get(1)];
+                if args.iter().any(Option::is_some) {
+                    out.trap_args.insert(pc, args);
+                }
+                if let (Op::A64_SVC, Some(number)) = (inst.op, state.get(8)) {
+                    out.syscall_numbers.insert(pc, number);
+                }
+            }
+            Op::A64_MSR_REG => {
+                if let Some(value) = state.get(inst.rd) {
+                    out.sysreg_writes.insert(pc, value);
+                }
+            }
+            _ if is_memory(&inst.op) && inst.op != Op::A64_PRFM => {
+                let mode = fad_get_addrmode(inst.flags);
+                let base = if mode == AddrMode::AM_LITERAL { target_address(inst, pc) } else { state.get(inst.rn) };
+                let offset = match mode {
+                    AddrMode::AM_OFF_IMM | AddrMode::AM_PRE => Some(inst.offset),
+                    AddrMode::AM_SIMPLE | AddrMode::AM_POST | AddrMode::AM_LITERAL => Some(0),
+                    _ => None, // register offsets
+                };
+                if let (Some(base), Some(offset)) = (base, offset) {
+                    out.data_addresses.insert(pc, strip(base, false).wrapping_add(offset as u64));
+                }
+            }
+            _ => {}
+        }
+
+        let w32 = inst.flags & W32 != 0;
+        let value = evaluate(&state, inst, pc);
+        for def in def_use(inst).defs {
+            if let Loc::X(r) = def {
+                state.set(r, None, false);
+            }
+        }
+        if let Some(v) = value {
+            out.values.insert(pc, if w32 { v & 0xFFFF_FFFF } else { v });
+            state.set(inst.rd, value, w32);
+        }
+        if inst.branch_kind().is_some_and(|kind| kind.is_call()) {
+            for r in (0..=18).chain([30]) {
+                state.set(r, None, false);
+            }
+        }
+        // Results come back in X0 (SVC, semihosting) or X0...X17 (SMCCC).
+        let last_result = match inst.op {
+            Op::A64_SVC | Op::A64_HLT => Some(0),
+            Op::A64_HVC | Op::A64_SMC => Some(17),
+            _ => None,
+        };
+        if let Some(last) = last_result {
+            for r in 0..=last {
+                state.set(r, None, false);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_buffer::decode_words;
+
+    #[test]
+    fn signed_branch_target() {
+        // movz x16, #0x1234, lsl #16; movk x16, #0x2a, lsl #48; br x16
+        let block = decode_words(&[0xD2A24690, 0xF2E00550, 0xD61F0200], 0x1000);
+        let plain = propagate(&block, &ConstOptions::default());
+        assert_eq!(plain.branch_targets.get(&0x1008), Some(&0x002A_0000_1234_0000));
+        let stripped = propagate(&block, &ConstOptions { pac: Some(PacConfig::default()), ..Default::default() });
+        assert_eq!(stripped.branch_targets.get(&0x1008), Some(&0x1234_0000));
+    }
+
+    #[test]
+    fn undecoded_words_clobber_registers() {
+        let block = decode_words(&[
+            0xD2820000, // mov x0, #0x1000
+            0xD2820011, // mov x17, #0x1000
+            0xAA0103E0, // mov x0, x1: not decoded
+            0xD61F0000, // br x0
+            0xD61F0220, // br x17
+        ], 0x1000);
+        let resolved = propagate(&block, &ConstOptions::default());
+        assert_eq!(resolved.branch_targets, BTreeMap::new());
+        assert_eq!(resolved.values.len(), 2);
+    }
+}
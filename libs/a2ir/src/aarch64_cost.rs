@@ -0,0 +1,114 @@
+//! Cycle estimates for straight-line code (basic blocks), from the timing
+//! tables and the def/use sets.
+//!
+//! Two simple models are provided:
+//!
+//! - in-order: one instruction issues per cycle, in program order, and waits
+//!   until all its inputs are ready;
+//! - idealized out-of-order: unlimited window and perfect scheduling, so the
+//!   block takes as long as the longer of its critical dependency path and its
+//!   busiest timing class (count / throughput).
+//!
+//! Instructions without timing data count as single-cycle ALU operations.
+
+use std::collections::HashMap;
+
+use crate::aarch64_defuse::{def_use, Loc};
+use crate::aarch64_reader::Inst;
+use crate::aarch64_timing::{timing, timing_class, uops, Core, Timing, TimingClass};
+
+const DEFAULT_TIMING: Timing = Timing { latency: 1, throughput: 1.0 };
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockCost {
+    /// Cycles under the in-order model.
+    pub in_order: f32,
+    /// Cycles under the idealized out-of-order model.
+    pub out_of_order: f32,
+    /// Length of the longest dependency chain, in cycles.
+    pub critical_path: u32,
+    /// Number of instructions without timing data.
+    pub unknown: usize,
+    /// Estimated micro-ops; instructions without an estimate count as one.
+    pub uops: u32,
+}
+
+pub fn block_cost(core: Core, block: &[Inst]) -> BlockCost {
+    let mut unknown = 0;
+    let mut total_uops = 0u32;
+
+    // In-order: ready[loc] is the cycle in which loc's latest value is available.
+    let mut ready: HashMap<Loc, u32> = HashMap::new();
+    let mut issue = 0u32;
+    let mut last_done = 0u32;
+
+    // Out-of-order: same dependency tracking, but issue is never delayed by
+    // program order.
+    let mut ooo_ready: HashMap<Loc, u32> = HashMap::new();
+    let mut critical_path = 0u32;
+    let mut class_load: HashMap<Option<TimingClass>, f32> = HashMap::new();
+
+    for (i, inst) in block.iter().enumerate() {
+        let t = timing(core, inst).unwrap_or_else(|| {
+            unknown += 1;
+            DEFAULT_TIMING
+        });
+        total_uops += uops(core, inst).unwrap_or(1) as u32;
+        let du = def_use(inst);
+
+        let inputs = du.uses.iter().map(|u| ready.get(u).copied().unwrap_or(0)).max().unwrap_or(0);
+        if i > 0 {
+            issue += 1;
+        }
+        issue = issue.max(inputs);
+        for &d in &du.defs {
+            ready.insert(d, issue + t.latency as u32);
+        }
+        last_done = last_done.max(issue + t.latency as u32);
+
+        let start = du.uses.iter().map(|u| ooo_ready.get(u).copied().unwrap_or(0)).max().unwrap_or(0);
+        let done = start + t.latency as u32;
+        for &d in &du.defs {
+            ooo_ready.insert(d, done);
+        }
+        critical_path = critical_path.max(done);
+
+        *class_load.entry(timing_class(&inst.op)).or_insert(0.0) += 1.0 / t.throughput;
+    }
+
+    let in_order = if block.is_empty() { 0.0 } else { (issue + 1).max(last_done) as f32 };
+    let resource_bound = class_load.values().copied().fold(0.0, f32::max);
+    BlockCost {
+        in_order,
+        out_of_order: resource_bound.max(critical_path as f32),
+        critical_path,
+        unknown,
+        uops: total_uops,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_reader::decode;
+
+    #[test]
+    fn dependent_chain_is_latency_bound() {
+        // add x0, x0, #1 (three times)
+        let block: Vec<Inst> = [0x91000400u32; 3].iter().map(|&w| decode(w)).collect();
+        let cost = block_cost(Core::CortexA76, &block);
+        assert_eq!(cost.critical_path, 3);
+        assert_eq!(cost.out_of_order, 3.0);
+        assert_eq!(cost.in_order, 3.0);
+        assert_eq!(cost.unknown, 0);
+        assert_eq!(cost.uops, 3);
+    }
+
+    #[test]
+    fn writeback_and_stores_crack() {
+        // ldp x29, x30, [sp], #16; str x0, [x1]; bl .
+        let block: Vec<Inst> = [0xA8C17BFDu32, 0xF9000020, 0x94000000].iter().map(|&w| decode(w)).collect();
+        assert_eq!(block_cost(Core::NeoverseN1, &block).uops, 2 + 2 + 2);
+        assert_eq!(block_cost(Core::AppleFirestorm, &block).uops, 2 + 1 + 1);
+    }
+}
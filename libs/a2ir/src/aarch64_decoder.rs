@@ -0,0 +1,52 @@
+//! One interface for the decoders of instruction words, so that buffers and
+//! programs can be decoded by something other than the A64 reader: a
+//! feature-restricted profile, a table-generated decoder, or a decoder for
+//! another instruction set producing the same `Inst`s.
+
+use crate::aarch64_features::{decode_with, DecoderConfig};
+use crate::aarch64_reader::{decode, Inst};
+
+pub trait Decoder {
+    /// The instruction binst at pc.
+    fn decode(&self, binst: u32, pc: u64) -> Inst;
+}
+
+/// The A64 reader, accepting every extension it knows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct A64Decoder;
+
+impl Decoder for A64Decoder {
+    fn decode(&self, binst: u32, _pc: u64) -> Inst {
+        decode(binst)
+    }
+}
+
+/// The A64 reader restricted to the extensions of the profile.
+impl Decoder for DecoderConfig {
+    fn decode(&self, binst: u32, _pc: u64) -> Inst {
+        decode_with(binst, self)
+    }
+}
+
+impl<F: Fn(u32, u64) -> Inst> Decoder for F {
+    fn decode(&self, binst: u32, pc: u64) -> Inst {
+        self(binst, pc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_program::Program;
+    use crate::aarch64_reader::Op;
+
+    #[test]
+    fn swapped_backends() {
+        let words = [0x48227C04, 0xD503201F]; // casp x2, x3, x4, x5, [x0]; nop
+        let op = |program: &Program| program.get(0x1000).map(|i| i.op);
+        assert_eq!(op(&Program::from_words_with(&words, 0x1000, &A64Decoder)), Some(Op::A64_CASP));
+        assert_eq!(op(&Program::from_words_with(&words, 0x1000, &DecoderConfig::base())), Some(Op::A64_UNKNOWN));
+        let nops = |_: u32, _: u64| decode(0xD503201F);
+        assert_eq!(op(&Program::from_words_with(&words, 0x1000, &nops)), Some(Op::A64_HINT));
+    }
+}
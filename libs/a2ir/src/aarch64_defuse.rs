@@ -0,0 +1,681 @@
+//! Registers read (used) and written (defined) by decoded instructions.
+//!
+//! The zero register is neither a use nor a def. Words the decoder doesn't
+//! decode yet (A64_UNKNOWN) may read and write anything: they are marked
+//! unknown and define and use every location, so analyses treat them as
+//! such without checking the marker.
+
+use crate::aarch64_group::Group;
+use crate::aarch64_operand::is_sve_unary;
+use crate::aarch64_pac::{PacHint, PacModifier};
+use crate::aarch64_sysreg::SysReg;
+use crate::aarch64_reader::{fad_get_addrmode, AddrMode, Inst, Op, Predication, Registries};
+use crate::aarch64_reader::FlagMasks::SET_FLAGS;
+
+/// A register-like location.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Loc {
+    /// General purpose register X0...X30 (or its W facet)
+    X(u8),
+    SP,
+    /// SIMD&FP register V0...V31 (or its B, H, S, D, Q facet)
+    V(u8),
+    /// SVE vector register Z0...Z31. Writes to V registers zero the upper
+    /// bits of the Z register they are part of.
+    Z(u8),
+    /// SVE predicate register P0...P15
+    P(u8),
+    /// SVE first-fault register
+    FFR,
+    /// The SME matrix array, as a whole: its tiles and slices overlap
+    ZA,
+    /// The condition flags
+    NZCV,
+}
+
+/// Location of a general purpose register number using our numbering (see
+/// Registries); None for the zero register.
+pub fn gpr(r: u8) -> Option<Loc> {
+    match r {
+        Registries::ZERO_REG => None,
+        Registries::STACK_POINTER => Some(Loc::SP),
+        _ => Some(Loc::X(r)),
+    }
+}
+
+/// Every location, in Loc order.
+pub fn all_locs() -> Vec<Loc> {
+    let mut out: Vec<Loc> = (0..31).map(Loc::X).collect();
+    out.push(Loc::SP);
+    out.extend((0..32).map(Loc::V));
+    out.extend((0..32).map(Loc::Z));
+    out.extend((0..16).map(Loc::P));
+    out.extend([Loc::FFR, Loc::ZA, Loc::NZCV]);
+    out
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DefUse {
+    pub defs: Vec<Loc>,
+    pub uses: Vec<Loc>,
+    /// The instruction wasn't decoded; defs and uses are all_locs().
+    pub unknown: bool,
+}
+
+impl DefUse {
+    fn def(&mut self, loc: Option<Loc>) {
+        if let Some(loc) = loc {
+            if !self.defs.contains(&loc) {
+                self.defs.push(loc);
+            }
+        }
+    }
+
+    fn uses(&mut self, loc: Option<Loc>) {
+        if let Some(loc) = loc {
+            if !self.uses.contains(&loc) {
+                self.uses.push(loc);
+            }
+        }
+    }
+
+    /// The sets without the registers a platform reserves, e.g. X18 where it
+    /// holds the shadow call stack, so they don't take part in liveness.
+    pub fn without_reserved(mut self, reserved: &[Loc]) -> DefUse {
+        self.defs.retain(|l| !reserved.contains(l));
+        self.uses.retain(|l| !reserved.contains(l));
+        self
+    }
+}
+
+/// Loads, stores, exclusives, compare and swaps and prefetches, whose flags
+/// hold an addressing mode and an access size instead of SET_FLAGS.
+pub fn is_memory(op: &Op) -> bool {
+    matches!(op, Op::A64_LDR | Op::A64_LDP | Op::A64_LDNP | Op::A64_LDR_FP | Op::A64_LDP_FP
+        | Op::A64_LDNP_FP | Op::A64_STR | Op::A64_STP | Op::A64_STNP | Op::A64_STR_FP
+        | Op::A64_STP_FP | Op::A64_STNP_FP | Op::A64_PRFM | Op::A64_LDXR | Op::A64_STXR
+        | Op::A64_LDXP | Op::A64_STXP | Op::A64_CAS | Op::A64_CASP | Op::A64_LDAPR | Op::A64_LDADD
+        | Op::A64_LDCLR | Op::A64_LDEOR | Op::A64_LDSET | Op::A64_LDSMAX | Op::A64_LDSMIN
+        | Op::A64_LDUMAX | Op::A64_LDUMIN | Op::A64_SWP)
+}
+
+/// The SVE operations of Zdn and an immediate: the bitwise ones, the
+/// integer ones and the predicated floating-point ones.
+fn is_sve_imm(op: Op) -> bool {
+    use Op::*;
+    matches!(op, A64_ORR_IMM_Z | A64_EOR_IMM_Z | A64_AND_IMM_Z | A64_ADD_IMM_Z | A64_SUB_IMM_Z | A64_SUBR_IMM_Z
+        | A64_QADD_IMM_Z | A64_QSUB_IMM_Z | A64_MAX_IMM_Z | A64_MIN_IMM_Z | A64_MUL_IMM_Z | A64_FADD_IMM_Z | A64_FSUB_IMM_Z
+        | A64_FMUL_IMM_Z | A64_FSUBR_IMM_Z | A64_FMAXNM_IMM_Z | A64_FMINNM_IMM_Z | A64_FMAX_IMM_Z | A64_FMIN_IMM_Z)
+}
+
+/// The SVE loads and stores, whose operands are in Inst.sve_ldst.
+pub fn is_sve_memory(op: Op) -> bool {
+    op >= Op::A64_LD1_Z && op <= Op::A64_ST4_Z
+}
+
+pub fn def_use(inst: &Inst) -> DefUse {
+    let mut du = DefUse::default();
+    let set_flags = inst.flags & SET_FLAGS != 0;
+    let is_memory = is_memory(&inst.op);
+
+    match inst.op {
+        Op::A64_UNKNOWN => return DefUse { defs: all_locs(), uses: all_locs(), unknown: true },
+        Op::A64_ADR | Op::A64_ADRP | Op::A64_MOV_IMM => du.def(gpr(inst.rd)),
+        Op::A64_MOVK => {
+            du.uses(gpr(inst.rd));
+            du.def(gpr(inst.rd));
+        }
+        Op::A64_ADD_IMM | Op::A64_SUB_IMM | Op::A64_CMN_IMM | Op::A64_CMP_IMM | Op::A64_MOV_SP
+        | Op::A64_AND_IMM | Op::A64_ORR_IMM | Op::A64_EOR_IMM | Op::A64_TST_IMM
+        | Op::A64_SBFIZ | Op::A64_SBFX | Op::A64_UBFIZ | Op::A64_UBFX | Op::A64_ASR_IMM
+        | Op::A64_LSL_IMM | Op::A64_LSR_IMM | Op::A64_ROR_IMM | Op::A64_EXTEND => {
+            du.uses(gpr(inst.rn));
+            du.def(gpr(inst.rd));
+        }
+        // Insertions keep the other bits of the destination.
+        Op::A64_BFI | Op::A64_BFXIL => {
+            du.uses(gpr(inst.rn));
+            du.uses(gpr(inst.rd));
+            du.def(gpr(inst.rd));
+        }
+        Op::A64_BFC => {
+            du.uses(gpr(inst.rd));
+            du.def(gpr(inst.rd));
+        }
+        Op::A64_EXTR => {
+            du.uses(gpr(inst.rn));
+            du.uses(gpr(inst.rm));
+            du.def(gpr(inst.rd));
+        }
+        Op::A64_BCOND => du.uses(Some(Loc::NZCV)),
+        Op::A64_CBZ | Op::A64_CBNZ | Op::A64_TBZ | Op::A64_TBNZ => du.uses(gpr(inst.rd)),
+        Op::A64_BL => du.def(Some(Loc::X(30))),
+        Op::A64_BLR => {
+            du.uses(gpr(inst.rn));
+            du.def(Some(Loc::X(30)));
+        }
+        Op::A64_BR | Op::A64_RET => du.uses(gpr(inst.rn)),
+        Op::A64_BRA | Op::A64_BLRA => {
+            du.uses(gpr(inst.rn));
+            du.uses(gpr(inst.rm));
+            if inst.op == Op::A64_BLRA {
+                du.def(Some(Loc::X(30)));
+            }
+        }
+        // RETA* authenticate X30 with SP as the modifier.
+        Op::A64_RETA => {
+            du.uses(Some(Loc::X(30)));
+            du.uses(Some(Loc::SP));
+        }
+        Op::A64_MRS | Op::A64_SYSL => {
+            du.def(gpr(inst.rd));
+            if inst.sysreg() == Some(SysReg::NZCV) {
+                du.uses(Some(Loc::NZCV));
+            }
+        }
+        Op::A64_MSR_REG | Op::A64_SYS => {
+            du.uses(gpr(inst.rd));
+            if inst.sysreg() == Some(SysReg::NZCV) {
+                du.def(Some(Loc::NZCV));
+            }
+        }
+        Op::A64_HINT => match inst.pac_hint() {
+            Some(hint @ (PacHint::Sign { modifier, .. } | PacHint::Auth { modifier, .. })) => {
+                du.uses(Some(Loc::X(hint.pointer())));
+                du.uses(match modifier {
+                    PacModifier::Sp => Some(Loc::SP),
+                    PacModifier::Zero => None,
+                    PacModifier::X16 => Some(Loc::X(16)),
+                });
+                du.def(Some(Loc::X(hint.pointer())));
+            }
+            Some(PacHint::StripLr) => {
+                du.uses(Some(Loc::X(30)));
+                du.def(Some(Loc::X(30)));
+            }
+            None => {}
+        },
+        Op::A64_LDR | Op::A64_LDP | Op::A64_LDNP => {
+            du.def(gpr(inst.rd));
+            if inst.op != Op::A64_LDR {
+                du.def(gpr(inst.rt2));
+            }
+        }
+        Op::A64_LDR_FP | Op::A64_LDP_FP | Op::A64_LDNP_FP => {
+            du.def(Some(Loc::V(inst.rd)));
+            if inst.op != Op::A64_LDR_FP {
+                du.def(Some(Loc::V(inst.rt2)));
+            }
+        }
+        Op::A64_STR | Op::A64_STP | Op::A64_STNP => {
+            du.uses(gpr(inst.rd));
+            if inst.op != Op::A64_STR {
+                du.uses(gpr(inst.rt2));
+            }
+        }
+        Op::A64_STR_FP | Op::A64_STP_FP | Op::A64_STNP_FP => {
+            du.uses(Some(Loc::V(inst.rd)));
+            if inst.op != Op::A64_STR_FP {
+                du.uses(Some(Loc::V(inst.rt2)));
+            }
+        }
+        Op::A64_LDXR | Op::A64_LDXP => {
+            du.def(gpr(inst.rd));
+            if inst.op == Op::A64_LDXP {
+                du.def(gpr(inst.rt2));
+            }
+        }
+        Op::A64_LDAPR => du.def(gpr(inst.rd)),
+        // Rt receives the old value; XZR for STADD and friends.
+        Op::A64_LDADD | Op::A64_LDCLR | Op::A64_LDEOR | Op::A64_LDSET | Op::A64_LDSMAX | Op::A64_LDSMIN
+        | Op::A64_LDUMAX | Op::A64_LDUMIN | Op::A64_SWP => {
+            du.uses(gpr(inst.rs));
+            du.def(gpr(inst.rd));
+        }
+        // Rs receives the status.
+        Op::A64_STXR | Op::A64_STXP => {
+            du.uses(gpr(inst.rd));
+            if inst.op == Op::A64_STXP {
+                du.uses(gpr(inst.rt2));
+            }
+            du.def(gpr(inst.rs));
+        }
+        // Rs (Rs:Rs+1) is compared and receives the old value; Rt (Rt:Rt+1)
+        // is the new value.
+        Op::A64_CAS | Op::A64_CASP => {
+            du.uses(gpr(inst.rs));
+            du.uses(gpr(inst.rd));
+            du.def(gpr(inst.rs));
+            if inst.op == Op::A64_CASP {
+                du.uses(gpr(inst.rs + 1));
+                du.uses(gpr(inst.rt2));
+                du.def(gpr(inst.rs + 1));
+            }
+        }
+        // TBX keeps the elements whose index is out of range.
+        Op::A64_TBL | Op::A64_TBX => {
+            for i in 0..inst.imm as u8 {
+                du.uses(Some(Loc::V((inst.rn + i) % 32)));
+            }
+            du.uses(Some(Loc::V(inst.rm)));
+            if inst.op == Op::A64_TBX {
+                du.uses(Some(Loc::V(inst.rd)));
+            }
+            du.def(Some(Loc::V(inst.rd)));
+        }
+        // Single structure loads keep the other lanes.
+        _ if inst.op >= Op::A64_LD1_MULT && inst.op <= Op::A64_LD4R => {
+            let load = !matches!(inst.op, Op::A64_ST1_MULT | Op::A64_ST2_MULT | Op::A64_ST3_MULT | Op::A64_ST4_MULT
+                | Op::A64_ST1_SINGLE | Op::A64_ST2_SINGLE | Op::A64_ST3_SINGLE | Op::A64_ST4_SINGLE);
+            let insert = matches!(inst.op, Op::A64_LD1_SINGLE | Op::A64_LD2_SINGLE | Op::A64_LD3_SINGLE | Op::A64_LD4_SINGLE);
+            for i in 0..inst.simd_ldst.nreg as u8 {
+                let v = Some(Loc::V((inst.rd + i) % 32));
+                if load {
+                    if insert {
+                        du.uses(v);
+                    }
+                    du.def(v);
+                } else {
+                    du.uses(v);
+                }
+            }
+            du.uses(gpr(inst.rn));
+            if fad_get_addrmode(inst.flags) == AddrMode::AM_POST {
+                du.uses(gpr(inst.rm));
+                du.def(gpr(inst.rn));
+            }
+        }
+        _ if is_sve_memory(inst.op) => {
+            let load = inst.op <= Op::A64_LD4_Z;
+            for i in 0..inst.sve_ldst.nreg {
+                let z = Some(Loc::Z((inst.rd + i) % 32));
+                if load { du.def(z) } else { du.uses(z) }
+            }
+            du.uses(inst.sve.pg.map(Loc::P));
+            match inst.sve_ldst.mode {
+                // The base of gathers and scatters by vector plus immediate.
+                AddrMode::AM_OFF_IMM if inst.sve_ldst.vector => du.uses(Some(Loc::Z(inst.rn))),
+                AddrMode::AM_OFF_IMM => du.uses(gpr(inst.rn)),
+                AddrMode::AM_OFF_EXT => {
+                    du.uses(gpr(inst.rn));
+                    du.uses(Some(Loc::Z(inst.rm)));
+                }
+                _ => {
+                    du.uses(gpr(inst.rn));
+                    du.uses(gpr(inst.rm));
+                }
+            }
+            // First-fault and non-fault loads clear the FFR elements from
+            // the first one that faults.
+            if matches!(inst.op, Op::A64_LDFF1_Z | Op::A64_LDNF1_Z) {
+                du.uses(Some(Loc::FFR));
+                du.def(Some(Loc::FFR));
+            }
+        }
+        Op::A64_PTRUE | Op::A64_PFALSE => du.def(Some(Loc::P(inst.rd))),
+        // Pdn is both: PFIRST and PNEXT step through the active elements.
+        Op::A64_PFIRST | Op::A64_PNEXT => {
+            du.uses(inst.sve.pg.map(Loc::P));
+            if inst.op == Op::A64_PNEXT {
+                du.uses(Some(Loc::P(inst.rn)));
+            }
+            du.uses(Some(Loc::P(inst.rd)));
+            du.def(Some(Loc::P(inst.rd)));
+        }
+        Op::A64_PTEST => {
+            du.uses(inst.sve.pg.map(Loc::P));
+            du.uses(Some(Loc::P(inst.rn)));
+        }
+        Op::A64_BRKA | Op::A64_BRKB => {
+            du.uses(inst.sve.pg.map(Loc::P));
+            du.uses(Some(Loc::P(inst.rn)));
+            if inst.sve.mode == Some(Predication::Merging) {
+                du.uses(Some(Loc::P(inst.rd)));
+            }
+            du.def(Some(Loc::P(inst.rd)));
+        }
+        Op::A64_WHILE => {
+            du.uses(gpr(inst.rn));
+            du.uses(gpr(inst.rm));
+            du.def(Some(Loc::P(inst.rd)));
+        }
+        Op::A64_CNTP => {
+            du.uses(inst.sve.pg.map(Loc::P));
+            du.uses(Some(Loc::P(inst.rn)));
+            du.def(gpr(inst.rd));
+        }
+        Op::A64_RDFFR => {
+            du.uses(Some(Loc::FFR));
+            du.uses(inst.sve.pg.map(Loc::P));
+            du.def(Some(Loc::P(inst.rd)));
+        }
+        Op::A64_SETFFR => du.def(Some(Loc::FFR)),
+        Op::A64_WRFFR => {
+            du.uses(Some(Loc::P(inst.rn)));
+            du.def(Some(Loc::FFR));
+        }
+        // Pdm of BRKN is Inst.rm too.
+        Op::A64_AND_P | Op::A64_BIC_P | Op::A64_EOR_P | Op::A64_SEL_P | Op::A64_ORR_P | Op::A64_ORN_P
+        | Op::A64_NOR_P | Op::A64_NAND_P | Op::A64_BRKN | Op::A64_BRKPA | Op::A64_BRKPB => {
+            du.uses(Some(Loc::P(inst.rn)));
+            du.uses(Some(Loc::P(inst.rm)));
+            du.uses(inst.sve.pg.map(Loc::P));
+            du.def(Some(Loc::P(inst.rd)));
+        }
+        Op::A64_INCP | Op::A64_DECP | Op::A64_QINCP | Op::A64_QDECP => {
+            du.uses(Some(Loc::P(inst.rn)));
+            du.uses(gpr(inst.rd));
+            du.def(gpr(inst.rd));
+        }
+        Op::A64_INCP_Z | Op::A64_DECP_Z | Op::A64_QINCP_Z | Op::A64_QDECP_Z => {
+            du.uses(Some(Loc::P(inst.rn)));
+            du.uses(Some(Loc::Z(inst.rd)));
+            du.def(Some(Loc::Z(inst.rd)));
+        }
+        Op::A64_ZIP1_P | Op::A64_ZIP2_P | Op::A64_UZP1_P | Op::A64_UZP2_P | Op::A64_TRN1_P | Op::A64_TRN2_P => {
+            du.uses(Some(Loc::P(inst.rn)));
+            du.uses(Some(Loc::P(inst.rm)));
+            du.def(Some(Loc::P(inst.rd)));
+        }
+        Op::A64_REV_P | Op::A64_PUNPKLO | Op::A64_PUNPKHI => {
+            du.uses(Some(Loc::P(inst.rn)));
+            du.def(Some(Loc::P(inst.rd)));
+        }
+        // V comes from C when the termination condition doesn't hold.
+        Op::A64_CTERMEQ | Op::A64_CTERMNE => {
+            du.uses(gpr(inst.rn));
+            du.uses(gpr(inst.rm));
+            du.uses(Some(Loc::NZCV));
+            du.def(Some(Loc::NZCV));
+        }
+        Op::A64_MATCH_Z | Op::A64_NMATCH_Z => {
+            du.uses(Some(Loc::Z(inst.rn)));
+            du.uses(Some(Loc::Z(inst.rm)));
+            du.uses(inst.sve.pg.map(Loc::P));
+            du.def(Some(Loc::P(inst.rd)));
+        }
+        Op::A64_AESMC_Z | Op::A64_AESIMC_Z => {
+            du.uses(Some(Loc::Z(inst.rn)));
+            du.def(Some(Loc::Z(inst.rd)));
+        }
+        // Loads and MOVA into ZA write only a slice (LDR a vector) of it.
+        Op::A64_LDR_ZA | Op::A64_STR_ZA | Op::A64_LD1_ZA | Op::A64_ST1_ZA | Op::A64_MOVA_Z | Op::A64_MOVA_ZA => {
+            du.uses(Some(Loc::X(12 + inst.za.rv)));
+            du.uses(inst.sve.pg.map(Loc::P));
+            match inst.op {
+                Op::A64_MOVA_Z => du.uses(Some(Loc::Z(inst.rd))),
+                Op::A64_MOVA_ZA => du.uses(Some(Loc::Z(inst.rn))),
+                _ => {
+                    du.uses(gpr(inst.rn));
+                    du.uses(gpr(inst.rm));
+                }
+            }
+            du.uses(Some(Loc::ZA));
+            match inst.op {
+                Op::A64_MOVA_Z => du.def(Some(Loc::Z(inst.rd))),
+                Op::A64_LDR_ZA | Op::A64_LD1_ZA | Op::A64_MOVA_ZA => du.def(Some(Loc::ZA)),
+                _ => {}
+            }
+        }
+        // The outer products accumulate into a tile.
+        _ if inst.op >= Op::A64_FMOPA_ZA => {
+            du.uses(Some(Loc::Z(inst.rn)));
+            du.uses(Some(Loc::Z(inst.rm)));
+            du.uses(inst.sve.pg.map(Loc::P));
+            du.uses(Some(Loc::P(inst.mop.pm)));
+            du.uses(Some(Loc::ZA));
+            du.def(Some(Loc::ZA));
+        }
+        // ZERO {ZA} clears the whole array; other masks only some tiles.
+        Op::A64_ZERO_ZA => {
+            if inst.imm != 0xFF {
+                du.uses(Some(Loc::ZA));
+            }
+            du.def(Some(Loc::ZA));
+        }
+        // The multiply-adds: Zdn times Zm plus Za.
+        Op::A64_MAD_Z | Op::A64_MSB_Z | Op::A64_FMAD_Z | Op::A64_FMSB_Z | Op::A64_FNMAD_Z | Op::A64_FNMSB_Z => {
+            du.uses(Some(Loc::Z(inst.rn)));
+            du.uses(Some(Loc::Z(inst.rm)));
+            du.uses(Some(Loc::Z(inst.ra)));
+            du.uses(inst.sve.pg.map(Loc::P));
+            du.def(Some(Loc::Z(inst.rd)));
+        }
+        // The unary operations and those with an immediate, merging into Zd
+        // if predicated.
+        _ if is_sve_unary(inst.op) || is_sve_imm(inst.op) || matches!(inst.op, Op::A64_REV_Z | Op::A64_DUP_ELEM_Z) => {
+            du.uses(Some(Loc::Z(inst.rn)));
+            if let Some(pg) = inst.sve.pg {
+                du.uses(Some(Loc::P(pg)));
+                du.uses(Some(Loc::Z(inst.rd)));
+            }
+            du.def(Some(Loc::Z(inst.rd)));
+        }
+        Op::A64_DUPM_Z | Op::A64_INDEX_IMM_Z | Op::A64_DUP_IMM_Z | Op::A64_FDUP_Z => du.def(Some(Loc::Z(inst.rd))),
+        Op::A64_DUP_REG_Z => {
+            du.uses(gpr(inst.rn));
+            du.def(Some(Loc::Z(inst.rd)));
+        }
+        // The copies under a predicate, and MOVPRFX, which keep the inactive
+        // elements of Zd if merging.
+        Op::A64_CPY_IMM_Z | Op::A64_FCPY_Z | Op::A64_CPY_REG_Z | Op::A64_CPY_FP_Z | Op::A64_MOVPRFX_Z => {
+            match inst.op {
+                Op::A64_CPY_REG_Z => du.uses(gpr(inst.rn)),
+                Op::A64_CPY_FP_Z => du.uses(Some(Loc::V(inst.rn))),
+                Op::A64_MOVPRFX_Z => du.uses(Some(Loc::Z(inst.rn))),
+                _ => {}
+            }
+            du.uses(inst.sve.pg.map(Loc::P));
+            if inst.sve.mode == Some(Predication::Merging) {
+                du.uses(Some(Loc::Z(inst.rd)));
+            }
+            du.def(Some(Loc::Z(inst.rd)));
+        }
+        // The element counts; INC<T> and friends update Xdn or Zdn.
+        Op::A64_CNT_ELEMS => du.def(gpr(inst.rd)),
+        Op::A64_INC_ELEMS | Op::A64_DEC_ELEMS | Op::A64_QINC_ELEMS | Op::A64_QDEC_ELEMS => {
+            du.uses(gpr(inst.rn));
+            du.def(gpr(inst.rd));
+        }
+        Op::A64_INC_ELEMS_Z | Op::A64_DEC_ELEMS_Z | Op::A64_QINC_ELEMS_Z | Op::A64_QDEC_ELEMS_Z => {
+            du.uses(Some(Loc::Z(inst.rn)));
+            du.def(Some(Loc::Z(inst.rd)));
+        }
+        // An element into a scalar register; CLASTA and CLASTB keep Rdn or
+        // Vdn if none is active.
+        Op::A64_LASTA_REG_Z | Op::A64_LASTB_REG_Z | Op::A64_LASTA_FP_Z | Op::A64_LASTB_FP_Z | Op::A64_CLASTA_REG_Z
+        | Op::A64_CLASTB_REG_Z | Op::A64_CLASTA_FP_Z | Op::A64_CLASTB_FP_Z => {
+            let fp = matches!(inst.op, Op::A64_LASTA_FP_Z | Op::A64_LASTB_FP_Z | Op::A64_CLASTA_FP_Z | Op::A64_CLASTB_FP_Z);
+            let scalar = |r| if fp { Some(Loc::V(r)) } else { gpr(r) };
+            match inst.op {
+                Op::A64_LASTA_REG_Z | Op::A64_LASTB_REG_Z | Op::A64_LASTA_FP_Z | Op::A64_LASTB_FP_Z => du.uses(Some(Loc::Z(inst.rn))),
+                _ => {
+                    du.uses(scalar(inst.rn));
+                    du.uses(Some(Loc::Z(inst.rm)));
+                }
+            }
+            du.uses(inst.sve.pg.map(Loc::P));
+            du.def(scalar(inst.rd));
+        }
+        // The unpredicated multiply-adds accumulate into Zda.
+        Op::A64_DOT_Z | Op::A64_DOT_ELEM_Z | Op::A64_FMLA_ELEM_Z | Op::A64_FMLS_ELEM_Z | Op::A64_FCMLA_ELEM_Z | Op::A64_CMLA_Z
+        | Op::A64_CMLA_ELEM_Z => {
+            du.uses(Some(Loc::Z(inst.rn)));
+            du.uses(Some(Loc::Z(inst.rm)));
+            du.uses(Some(Loc::Z(inst.rd)));
+            du.def(Some(Loc::Z(inst.rd)));
+        }
+        Op::A64_INDEX_REG_IMM_Z | Op::A64_INDEX_IMM_REG_Z | Op::A64_INDEX_REG_Z => {
+            if inst.op != Op::A64_INDEX_IMM_REG_Z {
+                du.uses(gpr(inst.rn));
+            }
+            if inst.op != Op::A64_INDEX_REG_IMM_Z {
+                du.uses(gpr(inst.rm));
+            }
+            du.def(Some(Loc::Z(inst.rd)));
+        }
+        // The reductions into a scalar; FADDA also adds Vdn in order.
+        Op::A64_ADDV_Z | Op::A64_MAXV_Z | Op::A64_MINV_Z | Op::A64_ORV_Z | Op::A64_EORV_Z | Op::A64_ANDV_Z | Op::A64_FADDV_Z
+        | Op::A64_FMAXNMV_Z | Op::A64_FMINNMV_Z | Op::A64_FMAXV_Z | Op::A64_FMINV_Z | Op::A64_FADDA_Z => {
+            if inst.op == Op::A64_FADDA_Z {
+                du.uses(Some(Loc::V(inst.rn)));
+                du.uses(Some(Loc::Z(inst.rm)));
+            } else {
+                du.uses(Some(Loc::Z(inst.rn)));
+            }
+            du.uses(inst.sve.pg.map(Loc::P));
+            du.def(Some(Loc::V(inst.rd)));
+        }
+        // The compares write Pd; the integer ones set the flags too.
+        Op::A64_CMP_Z | Op::A64_CMP_WIDE_Z | Op::A64_CMP_IMM_Z | Op::A64_FCM_Z | Op::A64_FAC_Z | Op::A64_FCM_ZERO_Z => {
+            du.uses(Some(Loc::Z(inst.rn)));
+            if !matches!(inst.op, Op::A64_CMP_IMM_Z | Op::A64_FCM_ZERO_Z) {
+                du.uses(Some(Loc::Z(inst.rm)));
+            }
+            du.uses(inst.sve.pg.map(Loc::P));
+            du.def(Some(Loc::P(inst.rd)));
+        }
+        // The bitwise ternaries read Zdn, Zm and Zk.
+        Op::A64_EOR3_Z | Op::A64_BCAX_Z | Op::A64_BSL_Z | Op::A64_BSL1N_Z | Op::A64_BSL2N_Z | Op::A64_NBSL_Z => {
+            du.uses(Some(Loc::Z(inst.rn)));
+            du.uses(Some(Loc::Z(inst.rm)));
+            du.uses(Some(Loc::Z(inst.ra)));
+            du.def(Some(Loc::Z(inst.rd)));
+        }
+        // The shift and extract narrows and SSHLLB read Zn alone; the top
+        // narrowing ops keep the even elements of Zd.
+        _ if (Op::A64_ADDLB_Z..=Op::A64_SUBHNT_Z).contains(&inst.op) => {
+            du.uses(Some(Loc::Z(inst.rn)));
+            if !(Op::A64_SHLLB_Z..=Op::A64_SQXTUNT_Z).contains(&inst.op) {
+                du.uses(Some(Loc::Z(inst.rm)));
+            }
+            if matches!(inst.op, Op::A64_SHRNT_Z | Op::A64_QSHRNT_Z | Op::A64_SQSHRUNT_Z | Op::A64_QXTNT_Z
+                | Op::A64_SQXTUNT_Z | Op::A64_ADDHNT_Z | Op::A64_SUBHNT_Z)
+            {
+                du.uses(Some(Loc::Z(inst.rd)));
+            }
+            du.def(Some(Loc::Z(inst.rd)));
+        }
+        // Merging keeps the inactive elements of Zd, and the multiply-adds
+        // accumulate into it.
+        _ if inst.group() == Group::Sve => {
+            du.uses(Some(Loc::Z(inst.rn)));
+            du.uses(Some(Loc::Z(inst.rm)));
+            if let Some(pg) = inst.sve.pg {
+                du.uses(Some(Loc::P(pg)));
+                if inst.sve.mode == Some(Predication::Merging) {
+                    du.uses(Some(Loc::Z(inst.rd)));
+                }
+            }
+            du.def(Some(Loc::Z(inst.rd)));
+        }
+        _ => {}
+    }
+
+    // Base register of loads, stores and prefetches, and its writeback.
+    if is_memory {
+        match fad_get_addrmode(inst.flags) {
+            AddrMode::AM_LITERAL => {}
+            AddrMode::AM_PRE | AddrMode::AM_POST => {
+                du.uses(gpr(inst.rn));
+                du.def(gpr(inst.rn));
+            }
+            _ => du.uses(gpr(inst.rn)),
+        }
+    }
+
+    // Loads and stores reuse the SET_FLAGS bit for the access size.
+    if set_flags && !is_memory {
+        du.def(Some(Loc::NZCV));
+    }
+
+    du
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_reader::decode;
+    use Loc::*;
+
+    #[test]
+    fn integer_branch_and_system() {
+        let cases: [(u32, &[Loc], &[Loc]); 19] = [
+            (0xD2800020, &[X(0)], &[]), // mov x0, #1
+            (0xF2A24681, &[X(1)], &[X(1)]), // movk x1, #0x1234, lsl #16
+            (0x910043E0, &[X(0)], &[SP]), // add x0, sp, #0x10
+            (0xF100043F, &[NZCV], &[X(1)]), // cmp x1, #1
+            (0xB3780C20, &[X(0)], &[X(1), X(0)]), // bfi x0, x1, #8, #4
+            (0x93C21020, &[X(0)], &[X(1), X(2)]), // extr x0, x1, x2, #4
+            (0x54000040, &[], &[NZCV]), // b.eq .+8
+            (0xB4000043, &[], &[X(3)]), // cbz x3, .+8
+            (0x94000002, &[X(30)], &[]), // bl .+8
+            (0xD63F0200, &[X(30)], &[X(16)]), // blr x16
+            (0xD65F03C0, &[], &[X(30)]), // ret
+            (0xD71F0A11, &[], &[X(16), X(17)]), // braa x16, x17
+            (0xD65F0BFF, &[], &[X(30), SP]), // retaa
+            (0xD53B4200, &[X(0)], &[NZCV]), // mrs x0, nzcv
+            (0xD51B4201, &[NZCV], &[X(1)]), // msr nzcv, x1
+            (0xD53BD042, &[X(2)], &[]), // mrs x2, tpidr_el0
+            (0xD503233F, &[X(30)], &[X(30), SP]), // paciasp
+            (0xD503211F, &[X(17)], &[X(17), X(16)]), // pacia1716
+            (0xD50320FF, &[X(30)], &[X(30)]), // xpaclri
+        ];
+        for (word, defs, uses) in cases {
+            let du = def_use(&decode(word));
+            assert_eq!((du.defs.as_slice(), du.uses.as_slice()), (defs, uses), "{:#010x}", word);
+        }
+        assert_eq!(def_use(&decode(0xD503201F)), DefUse::default()); // nop
+    }
+
+    #[test]
+    fn undecoded_words_define_and_use_everything() {
+        for word in [
+            0xAA0103E0, // mov x0, x1
+            0xCB2063FF, // sub sp, sp, x0
+            0xEB02001F, // cmp x0, x2
+        ] {
+            let du = def_use(&decode(word));
+            assert!(du.unknown, "{:#010x}", word);
+            assert_eq!((du.defs.len(), du.uses.len()), (115, 115), "{:#010x}", word);
+            assert!(du.defs.contains(&X(0)) && du.defs.contains(&SP) && du.defs.contains(&NZCV), "{:#010x}", word);
+        }
+        assert!(!def_use(&decode(0xD2800020)).unknown); // mov x0, #1
+    }
+
+    #[test]
+    fn loads_stores_and_writeback() {
+        let cases: [(u32, &[Loc], &[Loc]); 8] = [
+            (0xF8408420, &[X(0), X(1)], &[X(1)]), // ldr x0, [x1], #8
+            (0xA8C17BFD, &[X(29), X(30), SP], &[SP]), // ldp x29, x30, [sp], #0x10
+            (0xF9000420, &[], &[X(0), X(1)]), // str x0, [x1, #8]
+            (0xA900045F, &[], &[X(1), X(2)]), // stp xzr, x1, [x2]
+            (0x58000040, &[X(0)], &[]), // ldr x0, .+8
+            (0x39C00001, &[X(1)], &[X(0)]), // ldrsb w1, [x0]
+            (0xB8200041, &[X(1)], &[X(0), X(2)]), // ldadd w0, w1, [x2]
+            (0xB820005F, &[], &[X(0), X(2)]), // stadd w0, [x2]
+        ];
+        for (word, defs, uses) in cases {
+            let du = def_use(&decode(word));
+            assert_eq!((du.defs.as_slice(), du.uses.as_slice()), (defs, uses), "{:#010x}", word);
+        }
+        assert!(!is_memory(&Op::A64_ADD_IMM) && is_memory(&Op::A64_PRFM));
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn simd_registers() {
+        let du = def_use(&decode(0x3DC00020)); // ldr q0, [x1]
+        assert_eq!((du.defs, du.uses), (vec![V(0)], vec![X(1)]));
+        let du = def_use(&decode(0xADBF07E0)); // stp q0, q1, [sp, #-0x20]!
+        assert_eq!((du.defs, du.uses), (vec![SP], vec![V(0), V(1), SP]));
+    }
+
+    #[test]
+    fn registers_and_reserved() {
+        assert_eq!((gpr(Registries::ZERO_REG), gpr(Registries::STACK_POINTER), gpr(18)), (None, Some(SP), Some(X(18))));
+        // blr x16, with X16 reserved
+        let du = def_use(&decode(0xD63F0200)).without_reserved(&[X(16)]);
+        assert_eq!((du.defs, du.uses), (vec![X(30)], vec![]));
+    }
+}
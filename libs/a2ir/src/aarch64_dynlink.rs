@@ -0,0 +1,194 @@
+//! Dynamic linking: the PLT stubs of ELF binaries and the imported symbols
+//! they jump to through the GOT.
+//!
+//! ```text
+//! adrp x16, page           // (bti c first with BTI PLTs)
+//! ldr  x17, [x16, #off]    // the GOT slot
+//! add  x16, x16, #off
+//! br   x17
+//! ```
+//!
+//! GOT slots with an R_AARCH64_IRELATIVE relocation hold the result of a
+//! GNU IFUNC resolver, which picks one of several implementations (e.g. of
+//! memcpy) at load time; all the implementations the resolver may return
+//! are candidates.
+//!
+//! The crate doesn't read ELF; the dynamic relocations naming the GOT slots
+//! come from the output of `readelf -rW`.
+
+use std::collections::BTreeMap;
+
+use crate::aarch64_constprop::{propagate, ConstOptions};
+use crate::aarch64_program::{Program, INST_SIZE};
+use crate::aarch64_reader::{Inst, Op};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Relocation {
+    pub offset: u64,
+    /// e.g. "R_AARCH64_JUMP_SLOT"
+    pub typ: String,
+    /// Without the version, e.g. "malloc" for "malloc@GLIBC_2.17".
+    pub symbol: Option<String>,
+    pub addend: i64,
+}
+
+fn parse_hex(s: &str) -> Result<u64, String> {
+    u64::from_str_radix(s.strip_prefix("0x").unwrap_or(s), 16).map_err(|e| format!("bad number {}: {}", s, e))
+}
+
+/// Parses the relocation lines of `readelf -rW` output; section headers and
+/// other lines are skipped.
+pub fn parse_readelf_relocs(text: &str) -> Result<Vec<Relocation>, String> {
+    let mut out = Vec::new();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3 || !fields[2].starts_with("R_") || parse_hex(fields[0]).is_err() {
+            continue;
+        }
+        let offset = parse_hex(fields[0])?;
+        let (symbol, addend) = match &fields[3..] {
+            [] => (None, 0),
+            [addend] => (None, parse_hex(addend)? as i64),
+            [_value, name, sign, addend] => {
+                let addend = parse_hex(addend)? as i64;
+                let name = name.split('@').next().unwrap_or(name).to_string();
+                (Some(name), if *sign == "-" { -addend } else { addend })
+            }
+            [_value, name] => (Some(name.split('@').next().unwrap_or(name).to_string()), 0),
+            _ => return Err(format!("bad relocation line: {}", line.trim())),
+        };
+        out.push(Relocation { offset, typ: fields[2].to_string(), symbol, addend });
+    }
+    Ok(out)
+}
+
+/// The symbols of the GOT slots the dynamic linker fills in, by slot.
+pub fn got_symbols(relocs: &[Relocation]) -> BTreeMap<u64, String> {
+    relocs
+        .iter()
+        .filter(|r| matches!(r.typ.as_str(), "R_AARCH64_JUMP_SLOT" | "R_AARCH64_GLOB_DAT"))
+        .filter_map(|r| Some((r.offset, r.symbol.clone()?)))
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PltStub {
+    pub addr: u64,
+    pub got_slot: u64,
+    /// None if no relocation names the slot.
+    pub symbol: Option<String>,
+}
+
+impl PltStub {
+    /// "malloc@plt", or "plt_<address>" for unnamed slots.
+    pub fn name(&self) -> String {
+        match &self.symbol {
+            Some(symbol) => format!("{}@plt", symbol),
+            None => format!("plt_{:x}", self.addr),
+        }
+    }
+}
+
+fn is_bti_c(inst: &Inst) -> bool {
+    inst.op == Op::A64_HINT && inst.imm == 34
+}
+
+/// The PLT stubs of program, with the symbols of their GOT slots.
+pub fn find_plt_stubs(program: &Program, got: &BTreeMap<u64, String>) -> Vec<PltStub> {
+    let mut out = Vec::new();
+    for (addr, inst) in program.iter() {
+        let after_bti = program.get(addr.wrapping_sub(INST_SIZE)).is_some_and(is_bti_c);
+        if inst.op == Op::A64_ADRP && after_bti {
+            continue; // found from the BTI
+        }
+        let start = if is_bti_c(inst) { addr + INST_SIZE } else { addr };
+        let block: Vec<_> = program.range(start..start + 4 * INST_SIZE).map(|(a, i)| (a, i.clone())).collect();
+        let [(_, adrp), (ldr_pc, ldr), (_, add), (_, br)] = block.as_slice() else { continue };
+        let shape = adrp.op == Op::A64_ADRP
+            && adrp.rd == 16
+            && ldr.op == Op::A64_LDR
+            && (ldr.rd, ldr.rn) == (17, 16)
+            && add.op == Op::A64_ADD_IMM
+            && (add.rd, add.rn) == (16, 16)
+            && br.op == Op::A64_BR
+            && br.rn == 17;
+        if !shape || block[0].0 != start || block[3].0 != start + 3 * INST_SIZE {
+            continue;
+        }
+        let Some(&got_slot) = propagate(&block, &ConstOptions::default()).data_addresses.get(ldr_pc) else { continue };
+        out.push(PltStub { addr, got_slot, symbol: got.get(&got_slot).cloned() });
+    }
+    out
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ifunc {
+    pub got_slot: u64,
+    pub resolver: u64,
+    /// Function entries the resolver may return, in address order.
+    pub candidates: Vec<u64>,
+}
+
+/// The IFUNCs of the IRELATIVE relocations (the addend is the resolver),
+/// given the functions of program as (entry, end) ranges. The candidates
+/// are the entries whose address the resolver computes.
+pub fn find_ifuncs(program: &Program, relocs: &[Relocation], functions: &[(u64, u64)]) -> Vec<Ifunc> {
+    let entries: BTreeMap<u64, u64> = functions.iter().copied().collect();
+    let mut out = Vec::new();
+    for reloc in relocs.iter().filter(|r| r.typ == "R_AARCH64_IRELATIVE") {
+        let resolver = reloc.addend as u64;
+        let end = entries.get(&resolver).copied().unwrap_or(resolver);
+        let block: Vec<_> = program.range(resolver..end).map(|(a, i)| (a, i.clone())).collect();
+        let resolved = propagate(&block, &ConstOptions::default());
+        let computed = resolved.values.values().copied();
+        let mut candidates: Vec<u64> = computed.filter(|a| *a != resolver && entries.contains_key(a)).collect();
+        candidates.sort();
+        candidates.dedup();
+        out.push(Ifunc { got_slot: reloc.offset, resolver, candidates });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malloc_stub() {
+        let relocs = parse_readelf_relocs(
+            "Relocation section '.rela.plt' at offset 0x4a8 contains 1 entry:\n\
+             \x20   Offset             Info             Type               Symbol's Value  Symbol's Name + Addend\n\
+             0000000000011018  0000000300000402 R_AARCH64_JUMP_SLOT    0000000000000000 malloc@GLIBC_2.17 + 0\n",
+        )
+        .unwrap();
+        assert_eq!(relocs[0].symbol.as_deref(), Some("malloc"));
+        let program = Program::from_words(&[
+            0xD503245F, // bti c
+            0xB0000010, // adrp x16, .+0x1000   (0x11000)
+            0xF9400E11, // ldr x17, [x16, #0x18]
+            0x91006210, // add x16, x16, #0x18
+            0xD61F0220, // br x17
+        ], 0x10000);
+        let stubs = find_plt_stubs(&program, &got_symbols(&relocs));
+        assert_eq!(stubs, vec![PltStub { addr: 0x10000, got_slot: 0x11018, symbol: Some("malloc".to_string()) }]);
+        assert_eq!(stubs[0].name(), "malloc@plt");
+    }
+
+    #[test]
+    fn ifunc_candidates() {
+        let relocs = parse_readelf_relocs("0000000000020020  0000000000000408 R_AARCH64_IRELATIVE                       1000\n").unwrap();
+        let program = Program::from_words(&[
+            0xB4000060, // cbz x0, .+12
+            0x10000080, // adr x0, .+0x10   (0x1014)
+            0xD65F03C0, // ret
+            0x10000060, // adr x0, .+0xc    (0x1018)
+            0xD65F03C0, // ret
+            0xD65F03C0, // ret              generic
+            0xD65F03C0, // ret              simd
+        ], 0x1000);
+        let functions = [(0x1000, 0x1014), (0x1014, 0x1018), (0x1018, 0x101C)];
+        assert_eq!(find_ifuncs(&program, &relocs, &functions), vec![
+            Ifunc { got_slot: 0x20020, resolver: 0x1000, candidates: vec![0x1014, 0x1018] },
+        ]);
+    }
+}
@@ -0,0 +1,288 @@
+//! Synchronous exceptions an instruction may raise, its architectural side
+//! effects besides writing registers, and how much memory it accesses.
+//!
+//! The answers are conservative: an instruction is reported as possibly
+//! trapping whenever some system configuration makes it trap. Traps that any
+//! instruction class can take regardless of the instruction (e.g. FP/SIMD
+//! access traps through CPACR_EL1) are not reported.
+
+use crate::aarch64_defuse::is_memory;
+use crate::aarch64_fpexc::fp_semantics;
+use crate::aarch64_reader::{fad_get_mem_extend, fad_get_prec, fad_get_vec_arrangement, FPSize, Inst, Op, VectorArrangement};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Trap {
+    /// UDF, unallocated and undecodable encodings
+    Undefined,
+    /// SVC, HVC, SMC
+    Call,
+    /// BRK, HLT
+    Debug,
+    /// Any memory access may take a translation or permission fault.
+    DataAbort,
+    /// Always for exclusive, acquire/release and atomic accesses; other
+    /// accesses only with alignment checking enabled (SCTLR_ELx.A).
+    Alignment,
+    /// FP arithmetic with the corresponding FPCR trap enable set
+    FloatingPoint,
+    /// Failed pointer authentication (see Inst::authenticates)
+    PointerAuth,
+    /// System register and system instruction accesses that a higher
+    /// exception level may trap, and DCPS
+    System,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SideEffect {
+    MemoryWrite,
+    /// Exclusive monitor set or cleared (LDXR, STXR, CLREX)
+    ExclusiveMonitor,
+    Barrier,
+    /// DC, IC
+    CacheMaintenance,
+    /// TLBI
+    TlbMaintenance,
+    /// Other SYS/SYSL operations (AT, CFP, ...)
+    SystemOperation,
+    SysRegWrite,
+    /// MSR (immediate) and the flag format conversions CFINV, XAFLAG, AXFLAG
+    PStateWrite,
+    /// An exception is generated.
+    Exception,
+    /// WFE, WFI, SEV and the other hints.
+    Hint,
+}
+
+/// FP data processing that can signal IEEE 754 exceptions.
+pub fn is_fp_arith(op: Op) -> bool {
+    fp_semantics(op).is_some_and(|s| s.exceptions != 0)
+}
+
+fn is_ordered_or_atomic(op: Op) -> bool {
+    matches!(op, Op::A64_LDXR | Op::A64_STXR | Op::A64_LDXP | Op::A64_STXP | Op::A64_LDAPR | Op::A64_LDADD
+        | Op::A64_LDCLR | Op::A64_LDEOR | Op::A64_LDSET | Op::A64_LDSMAX | Op::A64_LDSMIN | Op::A64_LDUMAX
+        | Op::A64_LDUMIN | Op::A64_SWP | Op::A64_CAS | Op::A64_CASP)
+}
+
+fn accesses_memory(op: Op) -> bool {
+    (is_memory(&op) && op != Op::A64_PRFM) || is_ordered_or_atomic(op)
+        || (op >= Op::A64_LD1_MULT && op <= Op::A64_LD4R)
+}
+
+fn writes_memory(op: Op) -> bool {
+    matches!(op, Op::A64_STR | Op::A64_STP | Op::A64_STNP | Op::A64_STR_FP | Op::A64_STP_FP | Op::A64_STNP_FP
+        | Op::A64_ST1_MULT | Op::A64_ST2_MULT | Op::A64_ST3_MULT | Op::A64_ST4_MULT | Op::A64_ST1_SINGLE
+        | Op::A64_ST2_SINGLE | Op::A64_ST3_SINGLE | Op::A64_ST4_SINGLE | Op::A64_STXR | Op::A64_STXP)
+        || (is_ordered_or_atomic(op) && !matches!(op, Op::A64_LDXR | Op::A64_LDXP | Op::A64_LDAPR))
+}
+
+pub fn traps(inst: &Inst) -> Vec<Trap> {
+    let op = inst.op;
+    let mut out = Vec::new();
+    match op {
+        Op::A64_UNKNOWN | Op::A64_ERROR | Op::A64_UDF => out.push(Trap::Undefined),
+        Op::A64_SVC | Op::A64_HVC | Op::A64_SMC => out.push(Trap::Call),
+        Op::A64_BRK | Op::A64_HLT => out.push(Trap::Debug),
+        Op::A64_DCPS1 | Op::A64_DCPS2 | Op::A64_DCPS3 | Op::A64_SYS | Op::A64_SYSL | Op::A64_MSR_REG
+        | Op::A64_MRS | Op::A64_MSR_IMM => out.push(Trap::System),
+        // WFE and WFI may be trapped to EL2/EL3.
+        Op::A64_HINT => out.push(Trap::System),
+        _ => {}
+    }
+    if accesses_memory(op) {
+        out.push(Trap::DataAbort);
+        out.push(Trap::Alignment);
+    }
+    // Cache maintenance by VA faults like a memory access.
+    if op == Op::A64_SYS && inst.sys.crn == 7 {
+        out.push(Trap::DataAbort);
+    }
+    if is_fp_arith(op) {
+        out.push(Trap::FloatingPoint);
+    }
+    if inst.authenticates() {
+        out.push(Trap::PointerAuth);
+    }
+    out
+}
+
+pub fn may_trap(inst: &Inst) -> bool {
+    !traps(inst).is_empty()
+}
+
+pub fn side_effects(inst: &Inst) -> Vec<SideEffect> {
+    let op = inst.op;
+    let mut out = Vec::new();
+    if writes_memory(op) {
+        out.push(SideEffect::MemoryWrite);
+    }
+    match op {
+        Op::A64_LDXR | Op::A64_STXR | Op::A64_LDXP | Op::A64_STXP | Op::A64_CLREX => out.push(SideEffect::ExclusiveMonitor),
+        Op::A64_DMB | Op::A64_DSB | Op::A64_ISB | Op::A64_SB | Op::A64_SSBB | Op::A64_PSSBB => out.push(SideEffect::Barrier),
+        Op::A64_SYS | Op::A64_SYSL => out.push(match inst.sys.crn {
+            7 => SideEffect::CacheMaintenance,
+            8 | 9 => SideEffect::TlbMaintenance,
+            _ => SideEffect::SystemOperation,
+        }),
+        Op::A64_MSR_REG => out.push(SideEffect::SysRegWrite),
+        Op::A64_MSR_IMM | Op::A64_CFINV | Op::A64_XAFlag | Op::A64_AXFlag => out.push(SideEffect::PStateWrite),
+        Op::A64_SVC | Op::A64_HVC | Op::A64_SMC | Op::A64_BRK | Op::A64_HLT | Op::A64_DCPS1 | Op::A64_DCPS2
+        | Op::A64_DCPS3 | Op::A64_UDF => out.push(SideEffect::Exception),
+        Op::A64_HINT => out.push(SideEffect::Hint),
+        _ => {}
+    }
+    out
+}
+
+/// Whether the instruction does anything besides writing its destination
+/// registers (and the flags).
+pub fn has_side_effects(inst: &Inst) -> bool {
+    !side_effects(inst).is_empty()
+}
+
+/// Bytes of one register's worth of access: the access size of loads and
+/// stores, both registers of a pair counted separately.
+fn register_bytes(inst: &Inst) -> u32 {
+    let va = fad_get_vec_arrangement(inst.flags);
+    match inst.op {
+        Op::A64_LDR_FP | Op::A64_STR_FP | Op::A64_LDP_FP | Op::A64_STP_FP | Op::A64_LDNP_FP | Op::A64_STNP_FP => {
+            FPSize::bytes(fad_get_prec(inst.flags))
+        }
+        Op::A64_LD1_MULT | Op::A64_ST1_MULT | Op::A64_LD2_MULT | Op::A64_ST2_MULT | Op::A64_LD3_MULT
+        | Op::A64_ST3_MULT | Op::A64_LD4_MULT | Op::A64_ST4_MULT => VectorArrangement::total_bits(va) / 8,
+        Op::A64_LD1_SINGLE | Op::A64_ST1_SINGLE | Op::A64_LD2_SINGLE | Op::A64_ST2_SINGLE | Op::A64_LD3_SINGLE
+        | Op::A64_ST3_SINGLE | Op::A64_LD4_SINGLE | Op::A64_ST4_SINGLE | Op::A64_LD1R | Op::A64_LD2R
+        | Op::A64_LD3R | Op::A64_LD4R => VectorArrangement::elem_bytes(va),
+        _ => 1 << (fad_get_mem_extend(inst.flags) & 0b11),
+    }
+}
+
+fn registers_accessed(inst: &Inst) -> u32 {
+    match inst.op {
+        Op::A64_LDP | Op::A64_STP | Op::A64_LDNP | Op::A64_STNP | Op::A64_LDP_FP | Op::A64_STP_FP
+        | Op::A64_LDNP_FP | Op::A64_STNP_FP | Op::A64_LDXP | Op::A64_STXP | Op::A64_CASP => 2,
+        _ if inst.op >= Op::A64_LD1_MULT && inst.op <= Op::A64_LD4R => inst.simd_ldst.nreg,
+        _ => 1,
+    }
+}
+
+impl Inst {
+    /// Bytes read from memory; 0 for instructions that don't load. Atomic
+    /// read-modify-write operations count both as a read and as a write.
+    pub fn memory_read_bytes(&self) -> u32 {
+        let store_exclusive = matches!(self.op, Op::A64_STXR | Op::A64_STXP);
+        let rmw = is_ordered_or_atomic(self.op) && writes_memory(self.op) && !store_exclusive;
+        if accesses_memory(self.op) && (rmw || !writes_memory(self.op)) { registers_accessed(self) * register_bytes(self) } else { 0 }
+    }
+
+    /// Bytes written to memory; an upper bound for the conditional writes of
+    /// CAS, CASP and the store-exclusives.
+    ///
+    /// The memory copy and set instructions (CPY*, SET*) access a
+    /// register-dependent amount of memory and aren't decoded yet.
+    pub fn memory_write_bytes(&self) -> u32 {
+        if writes_memory(self.op) { registers_accessed(self) * register_bytes(self) } else { 0 }
+    }
+
+    /// LDNP and STNP: the data is unlikely to be accessed again soon and
+    /// needn't be kept in the caches. This is only a hint; otherwise the
+    /// accesses are those of LDP and STP.
+    pub fn is_non_temporal(&self) -> bool {
+        matches!(self.op, Op::A64_LDNP | Op::A64_STNP | Op::A64_LDNP_FP | Op::A64_STNP_FP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_reader::decode;
+
+    #[test]
+    fn traps_and_side_effects() {
+        use SideEffect::*;
+        use Trap::*;
+        let cases: [(u32, &[Trap], &[SideEffect]); 17] = [
+            (0x00000000, &[Undefined], &[]), // udf #0: not decoded
+            (0xD4000001, &[Call], &[Exception]), // svc #0
+            (0xD4200020, &[Debug], &[Exception]), // brk #1
+            (0xD45E0000, &[Debug], &[Exception]), // hlt #0xf000
+            (0x885FFC01, &[DataAbort, Alignment], &[ExclusiveMonitor]), // ldaxr w1, [x0]
+            (0x88037C04, &[DataAbort, Alignment], &[MemoryWrite, ExclusiveMonitor]), // stxr w3, w4, [x0]
+            (0x88E17C02, &[DataAbort, Alignment], &[MemoryWrite]), // casa w1, w2, [x0]
+            (0xD5033BBF, &[], &[Barrier]), // dmb ish
+            (0xD50B7E20, &[System, DataAbort], &[CacheMaintenance]), // dc civac, x0
+            (0xD508871F, &[System], &[TlbMaintenance]), // tlbi vmalle1
+            (0xD518C000, &[System], &[SysRegWrite]), // msr vbar_el1, x0
+            (0xD50342DF, &[System], &[PStateWrite]), // msr daifset, #2
+            (0xD503205F, &[System], &[Hint]), // wfe
+            (0xD65F0BFF, &[PointerAuth], &[]), // retaa
+            (0xD500401F, &[], &[PStateWrite]), // cfinv
+            (0xD5033F5F, &[], &[ExclusiveMonitor]), // clrex
+            (0x91000420, &[], &[]), // add x0, x1, #1
+        ];
+        for (word, trap, effects) in cases {
+            let inst = decode(word);
+            assert_eq!(traps(&inst), trap, "{:#010x}", word);
+            assert_eq!(may_trap(&inst), !trap.is_empty(), "{:#010x}", word);
+            assert_eq!(side_effects(&inst), effects, "{:#010x}", word);
+            assert_eq!(has_side_effects(&inst), !effects.is_empty(), "{:#010x}", word);
+        }
+    }
+
+    #[test]
+    fn atomic_footprints() {
+        let cases = [
+            (0x88E17C02, 4, 4), // casa w1, w2, [x0]
+            (0xF8E10062, 8, 8), // ldaddal x1, x2, [x3]
+            (0x885FFC01, 4, 0), // ldaxr w1, [x0]
+            (0x88037C04, 0, 4), // stxr w3, w4, [x0]
+            (0xF9800000, 0, 0), // prfm pldl1keep, [x0]
+        ];
+        for (word, read, write) in cases {
+            let inst = decode(word);
+            assert_eq!((inst.memory_read_bytes(), inst.memory_write_bytes()), (read, write), "{:#010x}", word);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn pair_and_structure_footprints() {
+        let stp = decode(0xA9BF7BFD); // stp x29, x30, [sp, #-16]!
+        assert_eq!((stp.memory_read_bytes(), stp.memory_write_bytes()), (0, 16));
+        let ldr = decode(0x3DC00000); // ldr q0, [x0]
+        assert_eq!((ldr.memory_read_bytes(), ldr.memory_write_bytes()), (16, 0));
+        let stnp = decode(0x28000420); // stnp w0, w1, [x1]
+        assert!(stnp.is_non_temporal() && !stp.is_non_temporal());
+        assert_eq!(stnp.memory_write_bytes(), 8);
+        let ld2 = decode(0x4CDF8800); // ld2 {v0.4s, v1.4s}, [x0], #32
+        assert_eq!((ld2.memory_read_bytes(), ld2.memory_write_bytes()), (32, 0));
+        let ld1r = decode(0x4D40C800); // ld1r {v0.4s}, [x0]
+        assert_eq!((ld1r.memory_read_bytes(), ld1r.memory_write_bytes()), (4, 0));
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn non_temporal_pairs() {
+        use crate::aarch64_listing::inst_text;
+        use crate::aarch64_timing::{timing_class, TimingClass};
+        // llvm-mc -triple=aarch64 -disassemble
+        let cases = [
+            (0xA8400440, "ldnp x0, x1, [x2]", true, TimingClass::LoadPairNonTemporal, (16, 0)),
+            (0x287F13E3, "ldnp w3, w4, [sp, #-8]", true, TimingClass::LoadPairNonTemporal, (8, 0)),
+            (0xA81F98E5, "stnp x5, x6, [x7, #0x1f8]", true, TimingClass::StorePairNonTemporal, (0, 16)),
+            (0xAC410440, "ldnp q0, q1, [x2, #0x20]", true, TimingClass::LoadPairNonTemporal, (32, 0)),
+            (0x6C3F0C82, "stnp d2, d3, [x4, #-0x10]", true, TimingClass::StorePairNonTemporal, (0, 16)),
+            (0x2C409805, "ldnp s5, s6, [x0, #4]", true, TimingClass::LoadPairNonTemporal, (8, 0)),
+            (0xA9400440, "ldp x0, x1, [x2]", false, TimingClass::LoadPair, (16, 0)),
+            (0xAD000400, "stp q0, q1, [x0]", false, TimingClass::StorePair, (0, 32)),
+        ];
+        for (word, text, non_temporal, class, footprint) in cases {
+            let inst = decode(word);
+            assert_eq!(inst_text(&inst, 0), text);
+            assert_eq!(inst.is_non_temporal(), non_temporal, "{:#010x}", word);
+            assert_eq!(timing_class(&inst.op), Some(class), "{:#010x}", word);
+            assert_eq!((inst.memory_read_bytes(), inst.memory_write_bytes()), footprint, "{:#010x}", word);
+        }
+        assert!(!decode(0xF9400020).is_non_temporal()); // ldr x0, [x1]
+    }
+}
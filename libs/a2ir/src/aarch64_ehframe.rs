@@ -0,0 +1,333 @@
+//! C++ exception handling metadata: the FDEs of `.eh_frame`, the LSDAs of
+//! `.gcc_except_table` they point to, and the call-site regions of those
+//! with their landing pads. The regions turn into exception edges of the
+//! CFG, so landing pads and cleanups are reachable.
+//!
+//! Sections are given as their bytes and address. Pointers may be absolute
+//! or PC-relative; data-relative, text-relative and indirect encodings are
+//! rejected, since the addresses they need aren't known here. Call frame
+//! instructions are skipped.
+
+use std::collections::BTreeMap;
+
+use crate::aarch64_cfg::Cfg;
+use crate::aarch64_program::Program;
+
+pub const DW_EH_PE_OMIT: u8 = 0xFF;
+
+struct Reader<'a> {
+    data: &'a [u8],
+    base: u64,
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn addr(&self) -> u64 {
+        self.base.wrapping_add(self.pos as u64)
+    }
+
+    /// The position len bytes after the current one, if within the data.
+    fn end_of(&self, len: u64) -> Result<usize, String> {
+        usize::try_from(len).ok()
+            .and_then(|len| self.pos.checked_add(len))
+            .filter(|&end| end <= self.data.len())
+            .ok_or(format!("length {:#x} at {:#x} runs past the section", len, self.addr()))
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&[u8], String> {
+        let end = self.pos.checked_add(n).ok_or(format!("truncated at {:#x}", self.addr()))?;
+        let out = self.data.get(self.pos..end).ok_or(format!("truncated at {:#x}", self.addr()))?;
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn uint(&mut self, n: usize) -> Result<u64, String> {
+        let mut raw = [0u8; 8];
+        raw[..n].copy_from_slice(self.bytes(n)?);
+        Ok(u64::from_le_bytes(raw))
+    }
+
+    fn sint(&mut self, n: usize) -> Result<i64, String> {
+        let unused = 64 - 8 * n as u32;
+        Ok(((self.uint(n)? << unused) as i64) >> unused)
+    }
+
+    fn uleb(&mut self) -> Result<u64, String> {
+        let (mut out, mut shift) = (0u64, 0);
+        loop {
+            let b = self.u8()?;
+            if shift < 64 {
+                out |= ((b & 0x7F) as u64) << shift;
+            }
+            shift += 7;
+            if b & 0x80 == 0 {
+                return Ok(out);
+            }
+        }
+    }
+
+    fn sleb(&mut self) -> Result<i64, String> {
+        let (mut out, mut shift) = (0i64, 0);
+        loop {
+            let b = self.u8()?;
+            if shift < 64 {
+                out |= ((b & 0x7F) as i64) << shift;
+            }
+            shift += 7;
+            if b & 0x80 == 0 {
+                if shift < 64 && b & 0x40 != 0 {
+                    out |= -1 << shift;
+                }
+                return Ok(out);
+            }
+        }
+    }
+
+    fn cstr(&mut self) -> Result<String, String> {
+        let rest = self.data.get(self.pos..).unwrap_or_default();
+        let len = rest.iter().position(|&b| b == 0).ok_or("unterminated string")?;
+        let s = String::from_utf8_lossy(self.bytes(len)?).into_owned();
+        self.pos += 1;
+        Ok(s)
+    }
+
+    /// A pointer of the DW_EH_PE encoding.
+    fn pointer(&mut self, encoding: u8) -> Result<u64, String> {
+        let here = self.addr();
+        let value = match encoding & 0x0F {
+            0x00 => self.uint(8)?,
+            0x01 => self.uleb()?,
+            0x02 => self.uint(2)?,
+            0x03 => self.uint(4)?,
+            0x04 => self.uint(8)?,
+            0x09 => self.sleb()? as u64,
+            0x0A => self.sint(2)? as u64,
+            0x0B => self.sint(4)? as u64,
+            0x0C => self.sint(8)? as u64,
+            _ => return Err(format!("unsupported pointer encoding {:#x}", encoding)),
+        };
+        match encoding & 0xF0 {
+            0x00 => Ok(value),
+            0x10 => Ok(here.wrapping_add(value)),
+            _ => Err(format!("unsupported pointer application {:#x}", encoding)),
+        }
+    }
+}
+
+/// A function's frame description.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fde {
+    pub start: u64,
+    pub end: u64,
+    pub lsda: Option<u64>,
+}
+
+#[derive(Clone, Debug)]
+struct Cie {
+    fde_encoding: u8,
+    lsda_encoding: u8,
+    augmented: bool,
+}
+
+fn parse_cie(r: &mut Reader) -> Result<Cie, String> {
+    let version = r.u8()?;
+    let augmentation = r.cstr()?;
+    if augmentation.contains("eh") {
+        r.uint(8)?;
+    }
+    r.uleb()?; // code alignment
+    r.sleb()?; // data alignment
+    // The return address register
+    if version == 1 {
+        r.u8()?;
+    } else {
+        r.uleb()?;
+    }
+    let mut cie = Cie { fde_encoding: 0, lsda_encoding: DW_EH_PE_OMIT, augmented: augmentation.starts_with('z') };
+    if cie.augmented {
+        r.uleb()?;
+        for c in augmentation.chars().skip(1) {
+            match c {
+                'L' => cie.lsda_encoding = r.u8()?,
+                'R' => cie.fde_encoding = r.u8()?,
+                'P' => {
+                    let encoding = r.u8()?;
+                    r.pointer(encoding & 0x7F)?; // the personality routine
+                }
+                'S' | 'B' | 'G' => {}
+                _ => return Err(format!("unknown augmentation {:?}", augmentation)),
+            }
+        }
+    }
+    Ok(cie)
+}
+
+/// The FDEs of the .eh_frame section at addr.
+pub fn parse_eh_frame(data: &[u8], addr: u64) -> Result<Vec<Fde>, String> {
+    let mut r = Reader { data, base: addr, pos: 0 };
+    let mut cies: BTreeMap<usize, Cie> = BTreeMap::new();
+    let mut out = Vec::new();
+    while r.pos < data.len() {
+        let mut length = r.uint(4)?;
+        if length == 0 {
+            break;
+        }
+        if length == 0xFFFF_FFFF {
+            length = r.uint(8)?;
+        }
+        let id_pos = r.pos;
+        let next = r.end_of(length)?;
+        if length < 4 {
+            return Err(format!("entry at {:#x} too short for its id", r.addr()));
+        }
+        let id = r.uint(4)? as usize;
+        if id == 0 {
+            cies.insert(id_pos - 4, parse_cie(&mut r)?);
+        } else {
+            let cie_pos = id_pos.checked_sub(id).ok_or("bad CIE pointer")?;
+            let cie = match cies.get(&cie_pos) {
+                Some(cie) => cie.clone(),
+                None => {
+                    let mut at = Reader { data, base: addr, pos: cie_pos + 8 };
+                    parse_cie(&mut at)?
+                }
+            };
+            let start = r.pointer(cie.fde_encoding)?;
+            let range = r.pointer(cie.fde_encoding & 0x0F)?;
+            let mut lsda = None;
+            if cie.augmented {
+                let len = r.uleb()?;
+                let aug_end = r.end_of(len)?;
+                if cie.lsda_encoding != DW_EH_PE_OMIT && len > 0 {
+                    lsda = Some(r.pointer(cie.lsda_encoding)?).filter(|&p| p != 0);
+                }
+                r.pos = aug_end;
+            }
+            out.push(Fde { start, end: start.wrapping_add(range), lsda });
+        }
+        r.pos = next;
+    }
+    Ok(out)
+}
+
+/// A call-site region of an LSDA.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EhRegion {
+    pub start: u64,
+    pub end: u64,
+    pub landing_pad: Option<u64>,
+    /// Index + 1 into the action table; 0 for cleanups (destructors run
+    /// before unwinding goes on).
+    pub action: u64,
+}
+
+impl EhRegion {
+    pub fn is_cleanup(&self) -> bool {
+        self.landing_pad.is_some() && self.action == 0
+    }
+}
+
+/// The call-site regions of the LSDA at lsda (within the section data at
+/// addr) of the function at func_start.
+pub fn parse_lsda(data: &[u8], addr: u64, lsda: u64, func_start: u64) -> Result<Vec<EhRegion>, String> {
+    let pos = lsda.checked_sub(addr).filter(|&p| p < data.len() as u64).ok_or(format!("LSDA {:#x} outside the section", lsda))?;
+    let mut r = Reader { data, base: addr, pos: pos as usize };
+    let lp_encoding = r.u8()?;
+    let lp_start = if lp_encoding == DW_EH_PE_OMIT { func_start } else { r.pointer(lp_encoding)? };
+    if r.u8()? != DW_EH_PE_OMIT {
+        r.uleb()?; // the type table
+    }
+    let encoding = r.u8()?;
+    let len = r.uleb()?;
+    let table_end = r.end_of(len)?;
+    let mut out = Vec::new();
+    while r.pos < table_end {
+        let start = func_start.wrapping_add(r.pointer(encoding)?);
+        let len = r.pointer(encoding)?;
+        let pad = r.pointer(encoding)?;
+        let action = r.uleb()?;
+        out.push(EhRegion { start, end: start.wrapping_add(len), landing_pad: (pad != 0).then(|| lp_start.wrapping_add(pad)), action });
+    }
+    Ok(out)
+}
+
+impl Cfg {
+    /// Adds an exception edge from every call in the regions to its landing
+    /// pad, which starts a block.
+    pub fn add_exception_edges(&mut self, program: &Program, regions: &[EhRegion]) {
+        for region in regions {
+            let Some(pad) = region.landing_pad else { continue };
+            self.split_at(pad);
+            for (pc, inst) in program.range(region.start..region.end) {
+                if inst.branch_kind().is_some_and(|k| k.is_call()) {
+                    self.exception_edges.insert(pc, pad);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_cfg::Terminator;
+
+    #[test]
+    fn landing_pad_edge() {
+        // CIE "zLR" with pcrel|sdata4 pointers, then an FDE for 0x1000..0x1014.
+        let mut eh = vec![0x10, 0, 0, 0, 0, 0, 0, 0, 1, b'z', b'L', b'R', 0, 4, 0x78, 30, 2, 0x1B, 0x1B, 0];
+        let fde_at = eh.len() as u64;
+        let pcrel = |target: u64, field: u64| ((target as i64 - (0x3000 + field) as i64) as i32).to_le_bytes();
+        eh.extend([0x14, 0, 0, 0]);
+        eh.extend((fde_at as u32 + 4).to_le_bytes());
+        eh.extend(pcrel(0x1000, fde_at + 8));
+        eh.extend(0x14u32.to_le_bytes());
+        eh.push(4);
+        eh.extend(pcrel(0x4000, fde_at + 17));
+        eh.extend([0, 0, 0]);
+        eh.extend([0, 0, 0, 0]);
+        let fdes = parse_eh_frame(&eh, 0x3000).unwrap();
+        assert_eq!(fdes, vec![Fde { start: 0x1000, end: 0x1014, lsda: Some(0x4000) }]);
+
+        // Calls in [0x1000, 0x1008) land at 0x1010 as a cleanup.
+        let lsda = [0xFF, 0xFF, 0x01, 4, 0x00, 0x08, 0x10, 0x00];
+        let regions = parse_lsda(&lsda, 0x4000, 0x4000, 0x1000).unwrap();
+        assert_eq!(regions, vec![EhRegion { start: 0x1000, end: 0x1008, landing_pad: Some(0x1010), action: 0 }]);
+        assert!(regions[0].is_cleanup());
+
+        let program = Program::from_words(&[
+            0x94000100, // bl
+            0xD503201F, // nop
+            0xD65F03C0, // ret
+            0xD503201F, // nop
+            0xD65F03C0, // ret              landing pad
+        ], 0x1000);
+        let mut cfg = Cfg::build(&program);
+        cfg.add_exception_edges(&program, &regions);
+        assert_eq!(cfg.exception_edges.get(&0x1000), Some(&0x1010));
+        assert_eq!(cfg.blocks[&0x100C].terminator, Terminator::FallThrough(0x1010));
+        assert_eq!(cfg.blocks[&0x1010].terminator, Terminator::Return);
+    }
+
+    #[test]
+    fn oversized_lengths_are_errors() {
+        // A 64-bit CIE length of usize::MAX - 11, then one past the section.
+        let mut eh = vec![0xFF, 0xFF, 0xFF, 0xFF];
+        eh.extend((usize::MAX as u64 - 11).to_le_bytes());
+        eh.extend([0, 0, 0, 0, 1, 0]);
+        assert!(parse_eh_frame(&eh, 0x3000).is_err());
+        assert!(parse_eh_frame(&[0x10, 0, 0, 0, 0, 0, 0, 0, 1, 0], 0x3000).is_err());
+        assert!(parse_eh_frame(&[0x02, 0, 0, 0, 0, 0], 0x3000).is_err());
+        assert_eq!(parse_eh_frame(&[0, 0, 0, 0], 0x3000), Ok(vec![]));
+
+        // A call-site table longer than the section, as a 10-byte ULEB128.
+        let mut lsda = vec![0xFF, 0xFF, 0x01];
+        lsda.extend([0xF4, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01]);
+        assert!(parse_lsda(&lsda, 0x4000, 0x4000, 0x1000).is_err());
+        assert!(parse_lsda(&[0xFF, 0xFF, 0x01, 4, 0x00], 0x4000, 0x4000, 0x1000).is_err());
+    }
+}
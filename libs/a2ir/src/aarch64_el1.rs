@@ -0,0 +1,278 @@
+//! EL1 setup in kernels and boot stubs: the values written to the system
+//! registers that configure translation and exceptions, the vector table
+//! VBAR_EL1 points to, where ERET drops to, and stage-1 translation through
+//! the tables of TTBR0_EL1 and TTBR1_EL1.
+//!
+//! ```text
+//! adr  x0, vectors
+//! msr  vbar_el1, x0        // SysRegWrite { reg: VBAR_EL1, value: Some(vectors) }
+//! mov  x1, #0x3c5          // EL1h, DAIF masked
+//! msr  spsr_el2, x1
+//! adr  x2, el1_entry
+//! msr  elr_el2, x2
+//! eret                     // ExceptionReturn { target: Some(el1_entry), mode: Some((1, true)) }
+//! ```
+//!
+//! Translation walks only the 4KB granule, and ignores the hierarchical
+//! permissions (APTable, PXNTable, UXNTable), the access flag and the
+//! contiguous bit.
+
+use crate::aarch64_constprop::{propagate, ConstOptions};
+use crate::aarch64_reader::{Inst, Op};
+use crate::aarch64_sysreg::SysReg;
+
+/// An MSR (register).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SysRegWrite {
+    pub pc: u64,
+    pub reg: SysReg,
+    /// Xt, if constant propagation recovers it.
+    pub value: Option<u64>,
+}
+
+/// The system register writes of straight-line code.
+pub fn sysreg_writes(block: &[(u64, Inst)]) -> Vec<SysRegWrite> {
+    let resolved = propagate(block, &ConstOptions::default());
+    block
+        .iter()
+        .filter(|(_, inst)| inst.op == Op::A64_MSR_REG)
+        .filter_map(|(pc, inst)| Some(SysRegWrite { pc: *pc, reg: inst.sysreg()?, value: resolved.sysreg_writes.get(pc).copied() }))
+        .collect()
+}
+
+/// The exception level an SPSR returns to and whether it selects SP_ELx
+/// (EL1h rather than EL1t); None for AArch32 and reserved modes.
+pub fn spsr_mode(spsr: u64) -> Option<(u8, bool)> {
+    if spsr & 0b10010 != 0 {
+        return None;
+    }
+    Some((((spsr >> 2) & 0b11) as u8, spsr & 1 == 1))
+}
+
+/// An ERET, with what the code before it wrote to ELR_EL1 or ELR_EL2 and
+/// SPSR_EL1 or SPSR_EL2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExceptionReturn {
+    pub pc: u64,
+    pub target: Option<u64>,
+    /// See `spsr_mode`.
+    pub mode: Option<(u8, bool)>,
+}
+
+/// The exception returns of straight-line code. The ELR and SPSR of the
+/// exception level the code runs at aren't known, so the last ones of
+/// EL1 or EL2 written count.
+pub fn exception_returns(block: &[(u64, Inst)]) -> Vec<ExceptionReturn> {
+    let resolved = propagate(block, &ConstOptions::default());
+    let (mut target, mut spsr) = (None, None);
+    let mut out = Vec::new();
+    for (pc, inst) in block {
+        let value = resolved.sysreg_writes.get(pc).copied();
+        match inst.sysreg().filter(|_| inst.op == Op::A64_MSR_REG) {
+            Some(SysReg::ELR_EL1 | SysReg::ELR_EL2) => target = value,
+            Some(SysReg::SPSR_EL1 | SysReg::SPSR_EL2) => spsr = value,
+            _ => {}
+        }
+        if matches!(inst.op, Op::A64_ERET | Op::A64_ERETA) {
+            out.push(ExceptionReturn { pc: *pc, target, mode: spsr.and_then(spsr_mode) });
+        }
+    }
+    out
+}
+
+/// Where the exception came from, in the order of the vector table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VectorSource {
+    /// The current exception level, with SP_EL0.
+    CurrentSp0,
+    /// The current exception level, with SP_ELx.
+    CurrentSpx,
+    /// A lower exception level in AArch64.
+    Lower64,
+    /// A lower exception level in AArch32.
+    Lower32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VectorKind {
+    Sync,
+    Irq,
+    Fiq,
+    SError,
+}
+
+/// The 16 entries of the vector table at vbar, 0x80 bytes apart; the low
+/// 11 bits of VBAR_EL1 are RES0.
+pub fn vector_entries(vbar: u64) -> Vec<(u64, VectorSource, VectorKind)> {
+    use VectorKind::*;
+    use VectorSource::*;
+    let base = vbar & !0x7FF;
+    let mut out = Vec::new();
+    for (i, source) in [CurrentSp0, CurrentSpx, Lower64, Lower32].into_iter().enumerate() {
+        for (j, kind) in [Sync, Irq, Fiq, SError].into_iter().enumerate() {
+            out.push((base + 0x200 * i as u64 + 0x80 * j as u64, source, kind));
+        }
+    }
+    out
+}
+
+/// The stage-1 translation registers of EL1.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stage1 {
+    pub ttbr0: u64,
+    pub ttbr1: u64,
+    pub tcr: u64,
+}
+
+/// The mapping of a virtual address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Translation {
+    pub pa: u64,
+    /// The size of the block or page.
+    pub size: u64,
+    pub level: u8,
+    /// The index of the attributes in MAIR_EL1.
+    pub attr: u8,
+    /// AP[2:1]: bit 0 gives EL0 access, bit 1 makes it read-only.
+    pub ap: u8,
+    pub pxn: bool,
+    pub uxn: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// The address is in neither half of the address space, or the walks
+    /// of its half are disabled (TCR_EL1.EPD0, EPD1).
+    Address,
+    /// An invalid descriptor.
+    Translation { level: u8 },
+    /// The descriptor at addr can't be read.
+    External { addr: u64 },
+    /// A granule other than 4KB, or a T0SZ or T1SZ outside 16...39.
+    Unsupported,
+}
+
+/// The output address bits of descriptors, 47:12.
+const OA_MASK: u64 = 0x0000_FFFF_FFFF_F000;
+
+impl Stage1 {
+    /// The registers as the known values of writes set them, last first.
+    pub fn from_writes(writes: &[SysRegWrite]) -> Stage1 {
+        let mut stage1 = Stage1::default();
+        for write in writes {
+            let Some(value) = write.value else { continue };
+            match write.reg {
+                SysReg::TTBR0_EL1 => stage1.ttbr0 = value,
+                SysReg::TTBR1_EL1 => stage1.ttbr1 = value,
+                SysReg::TCR_EL1 => stage1.tcr = value,
+                _ => {}
+            }
+        }
+        stage1
+    }
+
+    /// Walks the tables for va, reading their 64-bit descriptors by
+    /// physical address with read.
+    pub fn translate(&self, va: u64, read: impl Fn(u64) -> Option<u64>) -> Result<Translation, Fault> {
+        let tcr = self.tcr;
+        let upper = (va >> 55) & 1 == 1;
+        let (ttbr, tsz, disabled, granule_4k) = if upper {
+            (self.ttbr1, (tcr >> 16) & 0x3F, (tcr >> 23) & 1 == 1, (tcr >> 30) & 0b11 == 0b10)
+        } else {
+            (self.ttbr0, tcr & 0x3F, (tcr >> 7) & 1 == 1, (tcr >> 14) & 0b11 == 0b00)
+        };
+        if !granule_4k || !(16..=39).contains(&tsz) {
+            return Err(Fault::Unsupported);
+        }
+        let bits = 64 - tsz;
+        if disabled || va >> bits != if upper { (1 << tsz) - 1 } else { 0 } {
+            return Err(Fault::Address);
+        }
+
+        // 9 bits a level, ending with level 3 on bits 20:12.
+        let mut level = (4 - (bits - 12).div_ceil(9)) as u8;
+        let mut table = ttbr & 0x0000_FFFF_FFFF_FFFE;
+        loop {
+            let shift = 12 + 9 * (3 - level as u64);
+            let index = (va >> shift) & ((1 << (bits - shift).min(9)) - 1);
+            let addr = table + 8 * index;
+            let desc = read(addr).ok_or(Fault::External { addr })?;
+            match (desc & 0b11, level) {
+                (0b11, 0..=2) => {
+                    table = desc & OA_MASK;
+                    level += 1;
+                }
+                // Pages at level 3, blocks at levels 1 and 2.
+                (0b11, 3) | (0b01, 1 | 2) => {
+                    let size = 1 << shift;
+                    return Ok(Translation {
+                        pa: (desc & OA_MASK & !(size - 1)) | (va & (size - 1)),
+                        size,
+                        level,
+                        attr: ((desc >> 2) & 0b111) as u8,
+                        ap: ((desc >> 6) & 0b11) as u8,
+                        pxn: (desc >> 53) & 1 == 1,
+                        uxn: (desc >> 54) & 1 == 1,
+                    });
+                }
+                _ => return Err(Fault::Translation { level }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_buffer::decode_words;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn drop_to_el1() {
+        let block = decode_words(&[
+            0x10004000, // adr x0, .+0x800
+            0xD518C000, // msr vbar_el1, x0
+            0xD28078A1, // mov x1, #0x3c5
+            0xD51C4001, // msr spsr_el2, x1
+            0x10000202, // adr x2, .+0x40
+            0xD51C4022, // msr elr_el2, x2
+            0xD5181003, // msr sctlr_el1, x3
+            0xD69F03E0, // eret
+        ], 0x1000);
+        let writes = sysreg_writes(&block);
+        assert_eq!(writes[0], SysRegWrite { pc: 0x1004, reg: SysReg::VBAR_EL1, value: Some(0x1800) });
+        assert_eq!(writes[3], SysRegWrite { pc: 0x1018, reg: SysReg::SCTLR_EL1, value: None });
+        assert_eq!(exception_returns(&block), vec![ExceptionReturn { pc: 0x101C, target: Some(0x1050), mode: Some((1, true)) }]);
+        assert_eq!(spsr_mode(0x10), None);
+
+        let vectors = vector_entries(0x1800);
+        assert_eq!(vectors[0], (0x1800, VectorSource::CurrentSp0, VectorKind::Sync));
+        assert_eq!(vectors[5], (0x1A80, VectorSource::CurrentSpx, VectorKind::Irq));
+        assert_eq!(vectors[15], (0x1F80, VectorSource::Lower32, VectorKind::SError));
+    }
+
+    #[test]
+    fn stage1_walk() {
+        // T0SZ 25: 39-bit addresses from level 1; TTBR0 with ASID 1.
+        let stage1 = Stage1 { ttbr0: 0x0001_0000_8000_0000, ttbr1: 0, tcr: 25 | (25 << 16) | (1 << 23) | (0b10 << 30) };
+        let tables = BTreeMap::from([
+            (0x8000_0000, 0x4000_0401),                 // 1GB block
+            (0x8000_0008, 0x8000_1003),                 // table
+            (0x8000_0010, 0),
+            (0x8000_1008, 0x8000_2003),                 // table
+            (0x8000_2018, 0x0060_0000_9000_0447), // page: UXN, PXN, AP 0b01, attr 1
+        ]);
+        let read = |addr| tables.get(&addr).copied();
+        assert_eq!(stage1.translate(0x1234_5678, read), Ok(Translation {
+            pa: 0x5234_5678, size: 1 << 30, level: 1, attr: 0, ap: 0, pxn: false, uxn: false,
+        }));
+        assert_eq!(stage1.translate(0x4020_3123, read), Ok(Translation {
+            pa: 0x9000_0123, size: 0x1000, level: 3, attr: 1, ap: 1, pxn: true, uxn: true,
+        }));
+        assert_eq!(stage1.translate(0x8000_0000, read), Err(Fault::Translation { level: 1 }));
+        assert_eq!(stage1.translate(0x4040_0000, read), Err(Fault::External { addr: 0x8000_1010 }));
+        assert_eq!(stage1.translate(0x80_0000_0000, read), Err(Fault::Address));
+        assert_eq!(stage1.translate(0xFFFF_FF80_0000_0000, read), Err(Fault::Address)); // EPD1
+        assert_eq!(Stage1 { tcr: 25 | (0b01 << 14), ..stage1 }.translate(0, read), Err(Fault::Unsupported));
+    }
+}
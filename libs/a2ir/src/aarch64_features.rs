@@ -0,0 +1,259 @@
+//! Architecture extensions the decoded instructions depend on, decoding for
+//! a given set of them, and comparing the decodings of one buffer under
+//! several profiles.
+//!
+//! `decode` accepts everything it knows. With a DecoderConfig, encodings of
+//! extensions the profile lacks are unallocated instead, except for the hint
+//! space: PACIASP and friends execute as NOPs without FEAT_PAuth.
+//!
+//! A config can also decode for streaming SVE mode, the code between
+//! SMSTART and SMSTOP of SME-enabled binaries. Without FEAT_SME_FA64 the
+//! instructions that are illegal in that mode (they trap) are rejected like
+//! unallocated ones: see `legal_in_streaming_mode`.
+
+use std::collections::BTreeSet;
+
+use crate::aarch64_defuse::is_sve_memory;
+use crate::aarch64_group::Group;
+use crate::aarch64_operand::{operands, Operand, Reg};
+use crate::aarch64_reader::{decode, fad_get_addrmode, fad_get_cond, unallocated, AddrMode, Cond, FPSize, Inst,
+    MemOrdering, Op, PStateField};
+
+/// Only the extensions that change the instructions decoded so far.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Feature {
+    /// Pointer authentication (BRA*, RETA*, PAC hints)
+    PAuth,
+    /// Large System Extensions: CAS, CASP and the atomics
+    Lse,
+    /// Limited ordering regions: LDLAR, STLLR
+    Lor,
+    /// CFINV and flag manipulation
+    FlagM,
+    /// XAFLAG, AXFLAG
+    FlagM2,
+    Pan,
+    Uao,
+    Dit,
+    Ssbs,
+    /// Memory tagging (PSTATE.TCO)
+    Mte,
+    /// Non-maskable interrupts (PSTATE.ALLINT)
+    Nmi,
+    /// PMU exception based event profiling (PSTATE.PM)
+    Ebep,
+    /// Scalable matrix extension (SMSTART, SMSTOP and the ZA instructions)
+    Sme,
+    /// Scalable vector extension
+    Sve,
+    Sve2,
+    /// BEXT, BDEP and BGRP
+    SveBitPerm,
+    /// AESE, AESD, AESMC and AESIMC on Z registers
+    SveAes,
+    /// SM4E, SM4EKEY
+    SveSm4,
+    /// RAX1 on Z registers
+    SveSha3,
+    /// The full A64 instruction set in streaming SVE mode
+    SmeFa64,
+    /// FMOPA and FMOPS into double precision tiles
+    SmeF64F64,
+    /// The integer outer products of halfwords into D tiles
+    SmeI16I64,
+}
+
+pub const ALL_FEATURES: [Feature; 22] = [
+    Feature::PAuth, Feature::Lse, Feature::Lor, Feature::FlagM, Feature::FlagM2, Feature::Pan, Feature::Uao,
+    Feature::Dit, Feature::Ssbs, Feature::Mte, Feature::Nmi, Feature::Ebep, Feature::Sme, Feature::Sve, Feature::Sve2,
+    Feature::SveBitPerm, Feature::SveAes, Feature::SveSm4, Feature::SveSha3, Feature::SmeFa64, Feature::SmeF64F64,
+    Feature::SmeI16I64,
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DecoderConfig {
+    pub features: BTreeSet<Feature>,
+    /// PSTATE.SM: decode for streaming SVE mode.
+    pub streaming: bool,
+}
+
+impl DecoderConfig {
+    /// Armv8.0-A: none of the extensions.
+    pub fn base() -> DecoderConfig {
+        DecoderConfig::default()
+    }
+
+    /// Every extension the decoder knows; decodes like `decode`.
+    pub fn all() -> DecoderConfig {
+        DecoderConfig { features: ALL_FEATURES.into_iter().collect(), streaming: false }
+    }
+
+    /// The config in streaming SVE mode.
+    pub fn streaming(mut self) -> DecoderConfig {
+        self.streaming = true;
+        self
+    }
+
+    pub fn with(mut self, feature: Feature) -> DecoderConfig {
+        self.features.insert(feature);
+        self
+    }
+
+    pub fn has(&self, feature: Feature) -> bool {
+        self.features.contains(&feature)
+    }
+}
+
+/// The extension the instruction belongs to; None for Armv8.0-A.
+pub fn required_feature(inst: &Inst) -> Option<Feature> {
+    use Feature::*;
+    let lo = inst.ldst_order.load == MemOrdering::MO_LO_ACQUIRE as u16
+        || inst.ldst_order.store == MemOrdering::MO_LO_RELEASE as u16;
+    match inst.op {
+        Op::A64_BRA | Op::A64_BLRA | Op::A64_RETA | Op::A64_ERETA => Some(PAuth),
+        Op::A64_HINT if inst.pac_hint().is_some() => Some(PAuth),
+        Op::A64_CAS | Op::A64_CASP | Op::A64_LDADD | Op::A64_LDCLR | Op::A64_LDEOR | Op::A64_LDSET
+        | Op::A64_LDSMAX | Op::A64_LDSMIN | Op::A64_LDUMAX | Op::A64_LDUMIN | Op::A64_SWP => Some(Lse),
+        Op::A64_LDR | Op::A64_STR if fad_get_addrmode(inst.flags) == AddrMode::AM_SIMPLE && lo => Some(Lor),
+        Op::A64_CFINV | Op::A64_RMIF | Op::A64_SETF8 | Op::A64_SETF16 => Some(FlagM),
+        Op::A64_XAFlag | Op::A64_AXFlag => Some(FlagM2),
+        Op::A64_MSR_IMM => match PStateField::from_u32(inst.msr_imm.psfld)? {
+            PStateField::PSF_PAN => Some(Pan),
+            PStateField::PSF_UAO => Some(Uao),
+            PStateField::PSF_DIT => Some(Dit),
+            PStateField::PSF_SSBS => Some(Ssbs),
+            PStateField::PSF_TCO => Some(Mte),
+            PStateField::PSF_ALLINT => Some(Nmi),
+            PStateField::PSF_PM => Some(Ebep),
+            PStateField::PSF_SVCRSM | PStateField::PSF_SVCRZA | PStateField::PSF_SVCRSMZA => Some(Sme),
+            PStateField::PSF_SPSel | PStateField::PSF_DAIFSet | PStateField::PSF_DAIFClr => None,
+        },
+        Op::A64_BEXT_Z | Op::A64_BDEP_Z | Op::A64_BGRP_Z => Some(SveBitPerm),
+        Op::A64_AESE_Z | Op::A64_AESD_Z | Op::A64_AESMC_Z | Op::A64_AESIMC_Z => Some(SveAes),
+        // FEAT_SVE_PMULL128 comes with FEAT_SVE_AES.
+        Op::A64_PMULLB_Z | Op::A64_PMULLT_Z if inst.sve.esize == FPSize::FSZ_Q => Some(SveAes),
+        Op::A64_SM4E_Z | Op::A64_SM4EKEY_Z => Some(SveSm4),
+        Op::A64_RAX1_Z => Some(SveSha3),
+        Op::A64_FMOPA_ZA | Op::A64_FMOPS_ZA if inst.sve.esize == FPSize::FSZ_D => Some(SmeF64F64),
+        _ if inst.op >= Op::A64_SMOPA_ZA && inst.sve.esize == FPSize::FSZ_D => Some(SmeI16I64),
+        _ if inst.op >= Op::A64_LDR_ZA => Some(Sme),
+        _ if inst.op >= Op::A64_PMUL_Z => Some(Sve2),
+        // The unpredicated forms, and the WHILEs counting down.
+        Op::A64_MUL_Z | Op::A64_MULH_Z if inst.sve.pg.is_none() => Some(Sve2),
+        Op::A64_WHILE if !matches!(fad_get_cond(inst.flags), Cond::COND_LT | Cond::COND_LE | Cond::COND_LO | Cond::COND_LS) => {
+            Some(Sve2)
+        }
+        _ if inst.group() == Group::Sve => Some(Sve),
+        _ => None,
+    }
+}
+
+/// Whether the instruction executes in streaming SVE mode without
+/// FEAT_SME_FA64. Illegal are the Advanced SIMD instructions on vectors
+/// (told apart by their operands: the scalar SIMD&FP loads and stores are
+/// legal), the SVE gathers, scatters, first-fault and non-fault loads, the
+/// FFR, FADDA, ADR, and HISTCNT, HISTSEG, the bit permutes and the crypto
+/// of SVE2, the 128-bit PMULLB and PMULLT included.
+pub fn legal_in_streaming_mode(inst: &Inst) -> bool {
+    match inst.op {
+        Op::A64_LDFF1_Z | Op::A64_LDNF1_Z | Op::A64_RDFFR | Op::A64_SETFFR | Op::A64_WRFFR | Op::A64_HISTCNT_Z
+        | Op::A64_HISTSEG_Z | Op::A64_BEXT_Z | Op::A64_BDEP_Z | Op::A64_BGRP_Z | Op::A64_FADDA_Z | Op::A64_ADR_Z => false,
+        op if op >= Op::A64_AESE_Z && op <= Op::A64_RAX1_Z => false,
+        Op::A64_PMULLB_Z | Op::A64_PMULLT_Z => inst.sve.esize != FPSize::FSZ_Q,
+        op if is_sve_memory(op) => !inst.sve_ldst.vector && inst.sve_ldst.mode != AddrMode::AM_OFF_EXT,
+        _ => !operands(inst).iter().any(|o| matches!(o, Operand::List { .. } | Operand::Reg(Reg::Vec { .. }))),
+    }
+}
+
+/// What an encoding means under a profile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Meaning {
+    Unallocated,
+    /// A hint that does nothing without its extension (allocated hints
+    /// without an extension are Op(A64_HINT)).
+    Nop,
+    Op(Op),
+}
+
+pub fn meaning(binst: u32, config: &DecoderConfig) -> Meaning {
+    let inst = decode(binst);
+    match (inst.op, required_feature(&inst)) {
+        (Op::A64_UNKNOWN | Op::A64_ERROR, _) => Meaning::Unallocated,
+        (_, Some(feature)) if !config.has(feature) => {
+            if inst.op == Op::A64_HINT { Meaning::Nop } else { Meaning::Unallocated }
+        }
+        _ if config.streaming && !config.has(Feature::SmeFa64) && !legal_in_streaming_mode(&inst) => Meaning::Unallocated,
+        (op, _) => Meaning::Op(op),
+    }
+}
+
+/// The decoding of binst under config: A64_UNKNOWN for the instructions of
+/// extensions it lacks (hints stay hints).
+pub fn decode_with(binst: u32, config: &DecoderConfig) -> Inst {
+    match meaning(binst, config) {
+        Meaning::Unallocated if decode(binst).op != Op::A64_ERROR => unallocated(binst),
+        _ => decode(binst),
+    }
+}
+
+/// A word whose meaning depends on the profile.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProfileDifference {
+    pub addr: u64,
+    pub word: u32,
+    /// One per profile, in the order given.
+    pub meanings: Vec<Meaning>,
+    pub feature: Option<Feature>,
+}
+
+/// The words of a buffer (at base) whose meaning differs between the
+/// profiles.
+pub fn compare_profiles(words: &[u32], base: u64, profiles: &[DecoderConfig]) -> Vec<ProfileDifference> {
+    let mut out = Vec::new();
+    for (i, &word) in words.iter().enumerate() {
+        let meanings: Vec<Meaning> = profiles.iter().map(|p| meaning(word, p)).collect();
+        if meanings.windows(2).any(|w| w[0] != w[1]) {
+            out.push(ProfileDifference {
+                addr: base + 4 * i as u64,
+                word,
+                meanings,
+                feature: required_feature(&decode(word)),
+            });
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pauth_and_lse_differ() {
+        // paciasp; casp x2, x3, x4, x5, [x0]; ret
+        let words = [0xD503233F, 0x48227C04, 0xD65F03C0];
+        let diffs = compare_profiles(&words, 0, &[DecoderConfig::base(), DecoderConfig::all()]);
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].meanings, vec![Meaning::Nop, Meaning::Op(Op::A64_HINT)]);
+        assert_eq!(diffs[1].meanings, vec![Meaning::Unallocated, Meaning::Op(Op::A64_CASP)]);
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn streaming_mode() {
+        use crate::aarch64_listing::inst_text;
+        let smstart = decode(0xD503477F);
+        assert_eq!((inst_text(&smstart, 0), required_feature(&smstart)), ("smstart".to_string(), Some(Feature::Sme)));
+        assert_eq!(inst_text(&decode(0xD503447F), 0), "smstop za");
+        assert_eq!(inst_text(&decode(0xD50343DF), 0), "msr daifset, #3");
+
+        // tbl v0.16b, {v1.16b}, v2.16b; add z0.s, z1.s, z2.s; ldr q0, [x0]
+        let words = [0x4E020020, 0x04A20020, 0x3DC00000];
+        let mut streaming = DecoderConfig::all().streaming();
+        streaming.features.remove(&Feature::SmeFa64);
+        let diffs = compare_profiles(&words, 0, &[DecoderConfig::all(), streaming.clone()]);
+        assert_eq!(diffs.iter().map(|d| d.addr).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(diffs[0].meanings[1], Meaning::Unallocated);
+        assert_eq!(meaning(words[0], &streaming.with(Feature::SmeFa64)), Meaning::Op(Op::A64_TBL));
+    }
+}
@@ -0,0 +1,121 @@
+//! Pairing of condition flag consumers (B.cond, CSEL, CCMP, ADC, ...) with
+//! the instructions that produce the flags they read.
+//!
+//! The search goes backwards from the consumer along the direct control flow
+//! of a Program: fallthrough and the targets of B, B.cond, CBZ and TBZ.
+//! Calls and words the decoder doesn't know may set the flags, and the flags
+//! at a function entry or after an indirect branch are unknown; all of them
+//! make the result incomplete.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::aarch64_defuse::{def_use, Loc};
+use crate::aarch64_program::{Program, INST_SIZE};
+use crate::aarch64_reader::Op;
+use crate::aarch64_relocate::target_address;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FlagSources {
+    /// Addresses of the instructions whose flags may reach the consumer.
+    pub producers: Vec<u64>,
+    /// Some path reaches the consumer without a known producer.
+    pub incomplete: bool,
+}
+
+impl FlagSources {
+    /// The single producer of all paths, if there is one.
+    pub fn unique(&self) -> Option<u64> {
+        match self.producers[..] {
+            [pc] if !self.incomplete => Some(pc),
+            _ => None,
+        }
+    }
+}
+
+/// Direct predecessors of every instruction in the program.
+pub fn predecessors(program: &Program) -> BTreeMap<u64, Vec<u64>> {
+    let mut out: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+    for (pc, inst) in program.iter() {
+        let kind = inst.branch_kind();
+        if kind.is_none_or(|k| k.falls_through()) && program.get(pc + INST_SIZE).is_some() {
+            out.entry(pc + INST_SIZE).or_default().push(pc);
+        }
+        if kind.is_some_and(|k| !k.is_call()) {
+            if let Some(target) = target_address(inst, pc) {
+                out.entry(target).or_default().push(pc);
+            }
+        }
+    }
+    out
+}
+
+fn reads_flags(program: &Program, pc: u64) -> bool {
+    program.get(pc).is_some_and(|inst| def_use(inst).uses.contains(&Loc::NZCV))
+}
+
+/// The producers of the flags read by the instruction at pc; None if it isn't
+/// in the program or doesn't read the flags.
+pub fn flag_sources(program: &Program, preds: &BTreeMap<u64, Vec<u64>>, pc: u64) -> Option<FlagSources> {
+    if !reads_flags(program, pc) {
+        return None;
+    }
+    let mut out = FlagSources::default();
+    let mut producers = BTreeSet::new();
+    let mut seen = BTreeSet::new();
+    let mut worklist = vec![pc];
+
+    while let Some(at) = worklist.pop() {
+        let Some(from) = preds.get(&at).filter(|p| !p.is_empty()) else {
+            out.incomplete = true;
+            continue;
+        };
+        for &p in from {
+            if !seen.insert(p) {
+                continue;
+            }
+            let inst = program.get(p).unwrap();
+            if inst.op == Op::A64_UNKNOWN || inst.branch_kind().is_some_and(|k| k.is_call()) {
+                out.incomplete = true;
+            } else if def_use(inst).defs.contains(&Loc::NZCV) {
+                producers.insert(p);
+            } else {
+                worklist.push(p);
+            }
+        }
+    }
+
+    out.producers = producers.into_iter().collect();
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn producers_across_join() {
+        let program = Program::from_words(&[
+            0xB4000060, // 0x0: cbz x0, 0xc
+            0xF100043F, // 0x4: cmp x1, #1
+            0x14000002, // 0x8: b 0x10
+            0xF100085F, // 0xc: cmp x2, #2
+            0x54000040, // 0x10: b.eq 0x18
+        ], 0);
+        let preds = predecessors(&program);
+        let sources = flag_sources(&program, &preds, 0x10).unwrap();
+        assert_eq!(sources, FlagSources { producers: vec![0x4, 0xc], incomplete: false });
+        assert_eq!(sources.unique(), None);
+        assert!(flag_sources(&program, &preds, 0x8).is_none());
+    }
+
+    #[test]
+    fn undecoded_words_stop_the_search() {
+        let program = Program::from_words(&[
+            0xF100043F, // cmp x1, #1
+            0xEB02001F, // cmp x0, x2: not decoded
+            0x54000040, // b.eq .+8
+        ], 0);
+        let sources = flag_sources(&program, &predecessors(&program), 0x8).unwrap();
+        assert_eq!(sources, FlagSources { producers: vec![], incomplete: true });
+    }
+}
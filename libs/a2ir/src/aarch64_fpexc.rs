@@ -0,0 +1,191 @@
+//! IEEE 754 behaviour of floating-point opcodes: the exceptions they can
+//! raise and how they treat NaN inputs.
+//!
+//! Exceptions are given as the cumulative bits of FPSR, so they can be
+//! compared with FPSR and FPCR values directly. Flush-to-zero and alternate
+//! handling (FPCR.AH) only ever remove possible exceptions, and aren't
+//! modelled.
+
+use crate::aarch64_reader::Op::{self, *};
+
+/// FPSR cumulative exception bits (the trap enables of FPCR are these << 8)
+#[allow(non_snake_case)]
+pub mod FpException {
+    /// Invalid operation
+    pub const IOC: u8 = 1 << 0;
+    /// Divide by zero
+    pub const DZC: u8 = 1 << 1;
+    /// Overflow
+    pub const OFC: u8 = 1 << 2;
+    /// Underflow
+    pub const UFC: u8 = 1 << 3;
+    /// Inexact
+    pub const IXC: u8 = 1 << 4;
+    /// Input denormal
+    pub const IDC: u8 = 1 << 7;
+}
+
+use FpException::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NanBehavior {
+    /// NaN operands produce a (quieted) NaN; signalling NaNs raise Invalid.
+    Propagate,
+    /// FMAXNM and friends: a quiet NaN loses against a number.
+    NumberPreferred,
+    /// Unordered compare result; only signalling NaNs raise Invalid.
+    QuietCompare,
+    /// Unordered compare result; any NaN raises Invalid.
+    SignalingCompare,
+    /// Conversion to integer: any NaN raises Invalid and yields 0.
+    ToInteger,
+    /// The bits are moved as they are (FMOV, FABS, FNEG, FCSEL).
+    Bitwise,
+    /// There are no FP inputs (SCVTF, UCVTF).
+    NoInput,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FpSemantics {
+    /// FpException bits the instruction can set.
+    pub exceptions: u8,
+    pub nan: NanBehavior,
+}
+
+const ARITH: u8 = IOC | OFC | UFC | IXC | IDC;
+
+/// None for opcodes that aren't floating-point data processing.
+pub fn fp_semantics(op: Op) -> Option<FpSemantics> {
+    use NanBehavior::*;
+    let (exceptions, nan) = match op {
+        A64_FADD | A64_FSUB | A64_FMUL | A64_FMULX | A64_FNMUL | A64_FMADD | A64_FMSUB | A64_FNMADD
+        | A64_FNMSUB | A64_FADD_VEC | A64_FSUB_VEC | A64_FMUL_VEC | A64_FMUL_ELEM | A64_FMULX_VEC
+        | A64_FMULX_ELEM | A64_FABD_VEC | A64_FMLA_VEC | A64_FMLA_ELEM | A64_FMLS_VEC | A64_FMLS_ELEM
+        | A64_FMLAL_VEC | A64_FMLAL_ELEM | A64_FMLAL2_VEC | A64_FMLAL2_ELEM | A64_FMLSL_VEC
+        | A64_FMLSL_ELEM | A64_FMLSL2_VEC | A64_FMLSL2_ELEM | A64_FCMLA_VEC | A64_FCMLA_ELEM | A64_FCADD
+        | A64_FADDP | A64_FADDP_VEC | A64_FRECPS | A64_FRECPS_VEC | A64_FRSQRTS | A64_FRSQRTS_VEC => (ARITH, Propagate),
+        A64_FDIV | A64_FDIV_VEC => (ARITH | DZC, Propagate),
+        A64_FSQRT | A64_FSQRT_VEC => (IOC | IXC | IDC, Propagate),
+        A64_FRECPE | A64_FRECPE_VEC => (IOC | DZC | OFC | UFC | IDC, Propagate),
+        A64_FRSQRTE | A64_FRSQRTE_VEC => (IOC | DZC | IDC, Propagate),
+        A64_FRECPX => (IOC | IDC, Propagate),
+        A64_FMAX | A64_FMIN | A64_FMAX_VEC | A64_FMIN_VEC | A64_FMAXP | A64_FMAXP_VEC | A64_FMINP
+        | A64_FMINP_VEC | A64_FMAXV | A64_FMINV => (IOC | IDC, Propagate),
+        A64_FMAXNM | A64_FMINNM | A64_FMAXNM_VEC | A64_FMINNM_VEC | A64_FMAXNMP | A64_FMAXNMP_VEC
+        | A64_FMINNMP | A64_FMINNMP_VEC | A64_FMAXNMV | A64_FMINNMV => (IOC | IDC, NumberPreferred),
+        A64_FCMP_REG | A64_FCMP_ZERO | A64_FCCMP | A64_FCMEQ_REG | A64_FCMEQ_ZERO => (IOC | IDC, QuietCompare),
+        A64_FCMPE_REG | A64_FCMPE_ZERO | A64_FCCMPE | A64_FCMGE_REG | A64_FCMGE_ZERO | A64_FCMGT_REG
+        | A64_FCMGT_ZERO | A64_FCMLE_ZERO | A64_FCMLT_ZERO | A64_FACGE | A64_FACGT => (IOC | IDC, SignalingCompare),
+        A64_FCVT_GPR | A64_FCVT_VEC | A64_FJCVTZS => (IOC | IXC | IDC, ToInteger),
+        A64_CVTF | A64_CVTF_VEC => (IXC, NoInput),
+        // Narrowing conversions round; widening ones are exact.
+        A64_FCVT_H | A64_FCVT_S | A64_FCVT_D | A64_FCVTN | A64_FCVTXN => (ARITH, Propagate),
+        A64_FCVTL => (IOC | IDC, Propagate),
+        A64_FRINT | A64_FRINT_VEC => (IOC | IDC, Propagate),
+        A64_FRINTX | A64_FRINTX_VEC => (IOC | IXC | IDC, Propagate),
+        A64_FABS | A64_FNEG | A64_FABS_VEC | A64_FNEG_VEC | A64_FMOV_VEC2GPR | A64_FMOV_GPR2VEC
+        | A64_FMOV_TOP2GPR | A64_FMOV_GPR2TOP | A64_FMOV_REG | A64_FMOV_IMM | A64_FMOV_VEC
+        | A64_FCSEL => (0, Bitwise),
+        _ => return None,
+    };
+    Some(FpSemantics { exceptions, nan })
+}
+
+/// Whether the opcode can raise the given FpException bits.
+pub fn can_raise(op: Op, exceptions: u8) -> bool {
+    fp_semantics(op).is_some_and(|s| s.exceptions & exceptions != 0)
+}
+
+/// Result of FJCVTZS on one double.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct JsConversion {
+    /// The value written to Wd.
+    pub result: u32,
+    /// PSTATE.Z; N, C and V are cleared.
+    pub z: bool,
+    pub exceptions: u8,
+}
+
+/// FJCVTZS, the JavaScript ToInt32: the double given by its bits, truncated
+/// towards zero, modulo 2^32. NaNs and infinities yield 0. Z is set iff the
+/// conversion is exact and in the range of a signed 32-bit integer, -0.0
+/// excluded; otherwise Invalid (out of range, NaN, infinity) or Inexact is
+/// raised. This differs from FCVTZS, which saturates.
+pub fn fjcvtzs(bits: u64) -> JsConversion {
+    let sign = bits >> 63 == 1;
+    let exp = ((bits >> 52) & 0x7FF) as i32;
+    let frac = bits & ((1 << 52) - 1);
+    if exp == 0x7FF {
+        return JsConversion { result: 0, z: false, exceptions: IOC };
+    }
+
+    // |value| = mant * 2^e
+    let mant = if exp == 0 { frac } else { frac | (1 << 52) };
+    let e = exp.max(1) - 1075;
+    let (magnitude, inexact, too_large) = if e >= 0 {
+        // Everything from 2^32 up only has zeros in the low 32 bits.
+        let low = if e < 64 { mant << e } else { 0 };
+        (low, false, exp - 1023 >= 32)
+    } else {
+        let shift = -e as u32;
+        let int = if shift >= 64 { 0 } else { mant >> shift };
+        let rest = if shift >= 64 { mant } else { mant & ((1 << shift) - 1) };
+        (int, rest != 0, false)
+    };
+    let limit = if sign { 1 << 31 } else { (1 << 31) - 1 };
+    let invalid = too_large || magnitude > limit;
+    let low = magnitude as u32;
+
+    let exceptions = if invalid { IOC } else if inexact { IXC } else { 0 };
+    let negative_zero = sign && mant == 0;
+    JsConversion {
+        result: if sign { low.wrapping_neg() } else { low },
+        z: exceptions == 0 && !negative_zero,
+        exceptions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exceptions_and_nans() {
+        use NanBehavior::*;
+        let cases = [
+            (A64_FADD, ARITH, Propagate),
+            (A64_FDIV_VEC, ARITH | DZC, Propagate),
+            (A64_FSQRT, IOC | IXC | IDC, Propagate),
+            (A64_FMAX_VEC, IOC | IDC, Propagate),
+            (A64_FMAXNM, IOC | IDC, NumberPreferred),
+            (A64_FCMP_REG, IOC | IDC, QuietCompare),
+            (A64_FCMPE_ZERO, IOC | IDC, SignalingCompare),
+            (A64_FACGT, IOC | IDC, SignalingCompare),
+            (A64_FJCVTZS, IOC | IXC | IDC, ToInteger),
+            (A64_CVTF, IXC, NoInput),
+            (A64_FCVTN, ARITH, Propagate),
+            (A64_FCVTL, IOC | IDC, Propagate),
+            (A64_FCSEL, 0, Bitwise),
+        ];
+        for (op, exceptions, nan) in cases {
+            assert_eq!(fp_semantics(op), Some(FpSemantics { exceptions, nan }), "{:?}", op);
+        }
+        assert_eq!(fp_semantics(A64_ADD_IMM), None);
+
+        assert!(can_raise(A64_FDIV, DZC) && !can_raise(A64_FMUL, DZC));
+        assert!(can_raise(A64_FRINTX, IXC) && !can_raise(A64_FRINT, IXC));
+        assert!(!can_raise(A64_FMOV_REG, IOC | DZC | OFC | UFC | IXC | IDC));
+        assert!(!can_raise(A64_LDR, IOC));
+    }
+
+    #[test]
+    fn js_conversion() {
+        let js = |x: f64| fjcvtzs(x.to_bits());
+        assert_eq!(js(-1.5), JsConversion { result: -1i32 as u32, z: false, exceptions: IXC });
+        assert_eq!(js(4294967297.0), JsConversion { result: 1, z: false, exceptions: IOC });
+        assert_eq!(js(-2147483648.0), JsConversion { result: 0x8000_0000, z: true, exceptions: 0 });
+        assert_eq!(js(-0.0), JsConversion { result: 0, z: false, exceptions: 0 });
+        assert_eq!(js(f64::NAN).result, 0);
+        assert_eq!(js(1e300).result, 0);
+    }
+}
@@ -0,0 +1,107 @@
+//! Functions as the blocks reachable from their entries, rather than as
+//! [entry, next entry) ranges. Identical code folding and handwritten
+//! assembly make functions share epilogues or interleave:
+//!
+//! ```text
+//! f:    ...
+//!       b    out                // f's epilogue is g's
+//! g:    ...
+//! out:  ldp  x29, x30, [sp], #16
+//!       ret
+//! ```
+//!
+//! A range split gives `out` to g alone and cuts f short; here `out`
+//! belongs to both, and `shared_blocks` lists its parents.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::aarch64_cfg::Cfg;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Function {
+    pub entry: u64,
+    /// Starts of the blocks of the function.
+    pub blocks: BTreeSet<u64>,
+}
+
+impl Function {
+    /// The [start, end) ranges the blocks cover, adjacent ones merged.
+    pub fn ranges(&self, cfg: &Cfg) -> Vec<(u64, u64)> {
+        let mut out: Vec<(u64, u64)> = Vec::new();
+        for block in self.blocks.iter().filter_map(|b| cfg.blocks.get(b)) {
+            match out.last_mut() {
+                Some(last) if last.1 == block.start => last.1 = block.end,
+                _ => out.push((block.start, block.end)),
+            }
+        }
+        out
+    }
+
+    pub fn contains(&self, cfg: &Cfg, addr: u64) -> bool {
+        cfg.blocks.range(..=addr).next_back().is_some_and(|(start, b)| addr < b.end && self.blocks.contains(start))
+    }
+}
+
+/// The functions at entries: the blocks reachable from each through the
+/// CFG and its exception edges, stopping at other entries (falling or
+/// jumping into one isn't part of the function). cfg is split at the
+/// entries first.
+pub fn functions(cfg: &mut Cfg, entries: &BTreeSet<u64>) -> Vec<Function> {
+    for &entry in entries {
+        cfg.split_at(entry);
+    }
+    let mut out = Vec::new();
+    for &entry in entries.iter().filter(|e| cfg.blocks.contains_key(e)) {
+        let (mut blocks, mut work) = (BTreeSet::new(), vec![entry]);
+        while let Some(start) = work.pop() {
+            if !blocks.insert(start) {
+                continue;
+            }
+            let end = cfg.blocks[&start].end;
+            let pads = cfg.exception_edges.range(start..end).map(|(_, pad)| *pad);
+            for next in cfg.successors(start).into_iter().chain(pads) {
+                if cfg.blocks.contains_key(&next) && !entries.contains(&next) {
+                    work.push(next);
+                }
+            }
+        }
+        out.push(Function { entry, blocks });
+    }
+    out
+}
+
+/// The blocks of more than one function, with the entries of those.
+pub fn shared_blocks(functions: &[Function]) -> BTreeMap<u64, Vec<u64>> {
+    let mut parents: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+    for function in functions {
+        for &block in &function.blocks {
+            parents.entry(block).or_default().push(function.entry);
+        }
+    }
+    parents.retain(|_, entries| entries.len() > 1);
+    parents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_program::Program;
+
+    #[test]
+    fn shared_epilogue() {
+        let program = Program::from_words(&[
+            0xD503201F, // nop                f
+            0x14000003, // b .+12             (0x1010)
+            0xD503201F, // nop                g
+            0x14000001, // b .+4
+            0xD503201F, // nop                shared epilogue
+            0xD65F03C0, // ret
+        ], 0x1000);
+        let mut cfg = Cfg::build(&program);
+        let funcs = functions(&mut cfg, &BTreeSet::from([0x1000, 0x1008]));
+        assert_eq!(funcs[0].ranges(&cfg), vec![(0x1000, 0x1008), (0x1010, 0x1018)]);
+        assert_eq!(funcs[1].ranges(&cfg), vec![(0x1008, 0x1018)]);
+        assert!(funcs[0].contains(&cfg, 0x1014) && !funcs[0].contains(&cfg, 0x100C));
+        assert_eq!(shared_blocks(&funcs), BTreeMap::from([(0x1010, vec![0x1000, 0x1008])]));
+    }
+}
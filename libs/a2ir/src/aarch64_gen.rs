@@ -0,0 +1,136 @@
+//! Random generation of valid encodings under constraints on the opcode
+//! group, the opcode, the architecture extensions and the decoded operands,
+//! for fuzzing CPUs, emulators and other disassemblers.
+//!
+//! Words are drawn at random, with the top-level encoding class (op0, bits
+//! 28:25) restricted to those of the wanted groups and with any fixed bits
+//! the constraints give, and kept if the decoding under the profile
+//! satisfies the rest. Fixing the bits of an encoding class makes rare
+//! opcodes cheap to hit:
+//!
+//! ```text
+//! // LSE atomics with acquire semantics: size 111 0 00 A=1 R 1 ... 00
+//! fixed: (0x3FA00C00, 0x38A00000)
+//! ```
+
+use crate::aarch64_features::{meaning, DecoderConfig, Meaning};
+use crate::aarch64_group::Group;
+use crate::aarch64_reader::{decode, Inst, Op};
+
+/// Attempts per generated word before giving up.
+pub const MAX_TRIES: u32 = 1 << 20;
+
+/// xorshift64*, so the sequences are reproducible from the seed.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Constraints {
+    /// Any group if empty.
+    pub groups: Vec<Group>,
+    /// Any opcode if empty.
+    pub ops: Vec<Op>,
+    /// Instructions of extensions outside the profile are rejected.
+    pub config: DecoderConfig,
+    /// (mask, value): bits every word has.
+    pub fixed: (u32, u32),
+}
+
+/// The op0 values (bits 28:25) that encode the group.
+fn op0_values(group: Group) -> &'static [u32] {
+    match group {
+        Group::Invalid => &[0b0000, 0b0001, 0b0010, 0b0011],
+        Group::Reserved => &[0b0000],
+        Group::DataProcImm => &[0b1000, 0b1001],
+        Group::BranchSys => &[0b1010, 0b1011],
+        Group::LoadStore => &[0b0100, 0b0110, 0b1100, 0b1110],
+        Group::DataProcReg => &[0b0101, 0b1101],
+        Group::SimdFp => &[0b0111, 0b1111],
+        Group::Sve => &[0b0010],
+    }
+}
+
+impl Constraints {
+    fn draw(&self, rng: &mut Rng) -> u32 {
+        let mut word = rng.next_u32();
+        if !self.groups.is_empty() {
+            let group = self.groups[rng.next_u32() as usize % self.groups.len()];
+            let values = op0_values(group);
+            word = (word & !(0xF << 25)) | (values[rng.next_u32() as usize % values.len()] << 25);
+        }
+        (word & !self.fixed.0) | (self.fixed.1 & self.fixed.0)
+    }
+
+    pub fn accepts(&self, word: u32, inst: &Inst) -> bool {
+        let valid = match meaning(word, &self.config) {
+            Meaning::Op(_) => true,
+            Meaning::Unallocated | Meaning::Nop => self.groups.contains(&Group::Invalid),
+        };
+        valid
+            && word & self.fixed.0 == self.fixed.1 & self.fixed.0
+            && (self.groups.is_empty() || self.groups.contains(&inst.group()))
+            && (self.ops.is_empty() || self.ops.contains(&inst.op))
+    }
+}
+
+/// count words satisfying the constraints and pred on their decoding.
+pub fn generate(
+    rng: &mut Rng,
+    constraints: &Constraints,
+    pred: impl Fn(&Inst) -> bool,
+    count: usize,
+) -> Result<Vec<u32>, String> {
+    let mut out = Vec::with_capacity(count);
+    while out.len() < count {
+        let found = (0..MAX_TRIES).map(|_| constraints.draw(rng)).find(|&word| {
+            let inst = decode(word);
+            constraints.accepts(word, &inst) && pred(&inst)
+        });
+        match found {
+            Some(word) => out.push(word),
+            None => return Err(format!("no encoding satisfies the constraints in {} tries", MAX_TRIES)),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_features::Feature;
+    use crate::aarch64_reader::MemOrdering;
+
+    #[test]
+    fn acquiring_atomics() {
+        let constraints = Constraints {
+            ops: vec![Op::A64_LDADD, Op::A64_LDCLR, Op::A64_LDEOR, Op::A64_LDSET, Op::A64_SWP],
+            config: DecoderConfig::base().with(Feature::Lse),
+            fixed: (0x3FA00C00, 0x38A00000),
+            ..Constraints::default()
+        };
+        let acquire = |inst: &Inst| inst.ldst_order.load == MemOrdering::MO_ACQUIRE as u16;
+        let words = generate(&mut Rng::new(1), &constraints, acquire, 16).unwrap();
+        assert_eq!(words.len(), 16);
+        assert!(words.iter().all(|&w| constraints.ops.contains(&decode(w).op) && acquire(&decode(w))));
+
+        let base = Constraints { config: DecoderConfig::base(), ..constraints };
+        assert!(generate(&mut Rng::new(1), &base, |_| true, 1).is_err());
+    }
+}
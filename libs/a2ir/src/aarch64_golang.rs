@@ -0,0 +1,235 @@
+//! Go binaries: function names and boundaries from the pclntab, which Go
+//! keeps even in stripped binaries, and the shape of Go's prologues.
+//!
+//! ```text
+//! ldr  x16, [x28, #16]      // g.stackguard0
+//! cmp  sp, x16
+//! b.ls 1f                   // stack_check: the slow path at 1f
+//! ...
+//! 1:   mov  x3, x30
+//!      bl   runtime.morestack_noctxt
+//!      b    entry
+//! ```
+//!
+//! The pclntab formats of Go 1.2, 1.16, 1.18 and 1.20 are read; the crate
+//! doesn't read ELF or Mach-O, so the caller finds the table: the
+//! .gopclntab section, or `find_pclntab` over the data.
+//! Go's internal ABI differs from AAPCS64: arguments and results go in
+//! X0...X15 and F0...F15, X26 holds the closure context and X28 the
+//! current goroutine, and no register is callee-saved.
+
+use crate::aarch64_program::{Program, INST_SIZE};
+use crate::aarch64_reader::{fad_get_addrmode, fad_get_cond, AddrMode, Cond, Op};
+use crate::aarch64_relocate::target_address;
+
+/// The goroutine register, g.
+pub const G_REG: u8 = 28;
+/// The closure context register.
+pub const CONTEXT_REG: u8 = 26;
+/// Integer argument and result registers of ABIInternal, X0...X15.
+pub const ARGUMENT_REGS: u8 = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PclntabVersion {
+    Go12,
+    Go116,
+    Go118,
+    Go120,
+}
+
+fn version(magic: u32) -> Option<PclntabVersion> {
+    match magic {
+        0xFFFF_FFFB => Some(PclntabVersion::Go12),
+        0xFFFF_FFFA => Some(PclntabVersion::Go116),
+        0xFFFF_FFF0 => Some(PclntabVersion::Go118),
+        0xFFFF_FFF1 => Some(PclntabVersion::Go120),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GoFunc {
+    pub entry: u64,
+    /// The entry of the next function.
+    pub end: u64,
+    /// e.g. "main.main" or "runtime.(*mheap).alloc".
+    pub name: String,
+}
+
+/// The offset of the first plausible arm64 pclntab header in data.
+pub fn find_pclntab(data: &[u8]) -> Option<usize> {
+    (0..data.len().saturating_sub(8)).step_by(4).find(|&i| {
+        let magic = u32::from_le_bytes(data[i..i + 4].try_into().expect("4 bytes"));
+        version(magic).is_some() && data[i + 4..i + 6] == [0, 0] && data[i + 6] == INST_SIZE as u8 && data[i + 7] == 8
+    })
+}
+
+struct Table<'a> {
+    bytes: &'a [u8],
+}
+
+/// a + b, for offsets and addresses read from the table.
+fn sum(a: u64, b: u64) -> Result<u64, String> {
+    a.checked_add(b).ok_or(format!("pclntab: {:#x} + {:#x} overflows", a, b))
+}
+
+fn to_offset(v: u64) -> Result<usize, String> {
+    usize::try_from(v).map_err(|_| format!("pclntab: offset {:#x} past the table", v))
+}
+
+impl Table<'_> {
+    fn at<const N: usize>(&self, offset: usize) -> Result<[u8; N], String> {
+        offset.checked_add(N)
+            .and_then(|end| self.bytes.get(offset..end))
+            .map(|bytes| bytes.try_into().expect("N bytes"))
+            .ok_or(format!("pclntab: offset {:#x} past the table", offset))
+    }
+
+    fn u32_at(&self, offset: usize) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.at(offset)?))
+    }
+
+    fn u64_at(&self, offset: usize) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.at(offset)?))
+    }
+
+    fn string(&self, offset: usize) -> Result<String, String> {
+        let rest = self.bytes.get(offset..).ok_or(format!("pclntab: name at {:#x} past the table", offset))?;
+        let len = rest.iter().position(|&b| b == 0).ok_or(format!("pclntab: unterminated name at {:#x}", offset))?;
+        Ok(String::from_utf8_lossy(&rest[..len]).into_owned())
+    }
+}
+
+/// The functions of the pclntab in table, in entry order.
+pub fn parse_pclntab(table: &[u8]) -> Result<Vec<GoFunc>, String> {
+    let t = Table { bytes: table };
+    let version = version(t.u32_at(0)?).ok_or("pclntab: bad magic")?;
+    if table.get(7) != Some(&8) {
+        return Err("pclntab: only 64-bit tables are read".to_string());
+    }
+    let nfunc = t.u64_at(8)?;
+    // Where the functab starts, and where the name offsets count from.
+    let (functab, names) = match version {
+        PclntabVersion::Go12 => (16, 0),
+        PclntabVersion::Go116 => (t.u64_at(56)?, to_offset(t.u64_at(24)?)?),
+        PclntabVersion::Go118 | PclntabVersion::Go120 => (t.u64_at(64)?, to_offset(t.u64_at(32)?)?),
+    };
+    let relative = matches!(version, PclntabVersion::Go118 | PclntabVersion::Go120);
+    let text_start = if relative { t.u64_at(24)? } else { 0 };
+    // Functab entries: (entry, offset of _func), as u32 offsets from the
+    // start of the text (runtime.text) from 1.18 on and as uintptrs before;
+    // one more entry ends the last function.
+    let entry_size: u64 = if relative { 8 } else { 16 };
+    // The functab, with the entry ending the last function, is in the table.
+    let functab_end = nfunc.checked_add(1).and_then(|n| n.checked_mul(entry_size)).map(|len| sum(functab, len));
+    if !functab_end.is_some_and(|end| end.is_ok_and(|end| end <= table.len() as u64)) {
+        return Err(format!("pclntab: {} functions at {:#x} past the table", nfunc, functab));
+    }
+    // In bounds by the check above.
+    let field = |i: u64, second: bool| -> Result<u64, String> {
+        let offset = (functab + i * entry_size + if second { entry_size / 2 } else { 0 }) as usize;
+        if relative { t.u32_at(offset).map(|v| v as u64) } else { t.u64_at(offset) }
+    };
+    // _func offsets count from the functab (1.16 on) or the table.
+    let func_base = if version == PclntabVersion::Go12 { 0 } else { functab };
+    let mut out = Vec::new();
+    for i in 0..nfunc {
+        let entry = if relative { sum(text_start, field(i, false)?)? } else { field(i, false)? };
+        let end = if relative { sum(text_start, field(i + 1, false)?)? } else { field(i + 1, false)? };
+        let func = sum(func_base, field(i, true)?)?;
+        // _func: the entry (u32 offset or uintptr), then the name offset.
+        let name_off = t.u32_at(to_offset(sum(func, if relative { 4 } else { 8 })?)?)? as i32;
+        let name = t.string(names.checked_add_signed(name_off as isize).ok_or("pclntab: bad name offset")?)?;
+        out.push(GoFunc { entry, end, name });
+    }
+    Ok(out)
+}
+
+/// The target of the branch to the stack-growth slow path, if the function
+/// at entry starts with Go's stack-split check: a load of g.stackguard0
+/// (or stackguard1) followed within a few instructions by B.LS. The slow
+/// path belongs to the function; it calls runtime.morestack and jumps back
+/// to the entry.
+pub fn stack_check(program: &Program, entry: u64) -> Option<u64> {
+    let (_, load) = program.range(entry..entry + INST_SIZE).next()?;
+    let guard = load.op == Op::A64_LDR
+        && load.rn == G_REG
+        && fad_get_addrmode(load.flags) == AddrMode::AM_OFF_IMM
+        && matches!(load.offset, 16 | 24);
+    if !guard {
+        return None;
+    }
+    program.range(entry + INST_SIZE..entry + 4 * INST_SIZE)
+        .find(|(_, i)| i.op == Op::A64_BCOND && Cond::equivalent(fad_get_cond(i.flags), Cond::COND_LS))
+        .and_then(|(pc, i)| target_address(i, pc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn go120_table() {
+        let mut table = Vec::new();
+        table.extend(0xFFFF_FFF1u32.to_le_bytes());
+        table.extend([0, 0, 4, 8]);
+        // nfunc, nfiles, textStart, funcnametab, cutab, filetab, pctab, functab.
+        for word in [2u64, 0, 0x10000, 0x70, 0, 0, 0, 0x48] {
+            table.extend(word.to_le_bytes());
+        }
+        // 0x48: functab; 0x60: the _funcs; 0x70: the names.
+        for word in [0u32, 0x18, 0x40, 0x20, 0x60] {
+            table.extend(word.to_le_bytes());
+        }
+        table.extend([0; 4]);
+        for word in [0u32, 0, 0x40, 10] {
+            table.extend(word.to_le_bytes());
+        }
+        table.extend(b"main.init\0main.main\0");
+        assert_eq!(find_pclntab(&[&[0u8; 8][..], &table].concat()), Some(8));
+        let funcs = parse_pclntab(&table).unwrap();
+        assert_eq!(funcs, vec![
+            GoFunc { entry: 0x10000, end: 0x10040, name: "main.init".to_string() },
+            GoFunc { entry: 0x10040, end: 0x10060, name: "main.main".to_string() },
+        ]);
+
+        let program = Program::from_words(&[
+            0xF9400B90, // ldr x16, [x28, #16]
+            0xEB3063FF, // cmp sp, x16
+            0x54000049, // b.ls .+8
+            0xD65F03C0, // ret
+        ], 0x10000);
+        assert_eq!(stack_check(&program, 0x10000), Some(0x10010));
+    }
+
+    #[test]
+    fn malformed_tables_are_errors() {
+        let header = |nfunc: u64, text_start: u64, functab: u64| {
+            let mut table = Vec::new();
+            table.extend(0xFFFF_FFF1u32.to_le_bytes());
+            table.extend([0, 0, 4, 8]);
+            for word in [nfunc, 0, text_start, 0x70, 0, 0, 0, functab] {
+                table.extend(word.to_le_bytes());
+            }
+            table
+        };
+        // More functions than the table holds, one that overflows the size
+        // of the functab, and a functab offset that wraps around.
+        assert!(parse_pclntab(&header(2, 0x10000, 0x48)).is_err());
+        assert!(parse_pclntab(&header(u64::MAX / 4, 0x10000, 0x48)).is_err());
+        assert!(parse_pclntab(&header(1, 0x10000, u64::MAX - 3)).is_err());
+        // An entry past the end of the address space, and a _func offset
+        // past the table.
+        let mut table = header(1, u64::MAX - 0x10, 0x48);
+        for word in [0x20u32, 0x10, 0x40, 0] {
+            table.extend(word.to_le_bytes());
+        }
+        assert!(parse_pclntab(&table).is_err());
+        let mut table = header(1, 0x10000, 0x48);
+        for word in [0u32, 0xFFFF_FFFF, 0x40, 0] {
+            table.extend(word.to_le_bytes());
+        }
+        assert!(parse_pclntab(&table).is_err());
+        assert!(parse_pclntab(&[0xF1, 0xFF, 0xFF]).is_err());
+    }
+}
@@ -0,0 +1,69 @@
+//! Instruction groups, mirroring the top-level encoding classes of A64.
+
+use crate::aarch64_reader::Op::{self, *};
+use crate::aarch64_reader::Inst;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Group {
+    /// A64_UNKNOWN and A64_ERROR
+    Invalid,
+    /// Permanently undefined (UDF)
+    Reserved,
+    DataProcImm,
+    BranchSys,
+    LoadStore,
+    DataProcReg,
+    SimdFp,
+    /// Scalable vectors: the data processing on Z and P registers, and the
+    /// ZA array of SME
+    Sve,
+}
+
+pub const ALL_GROUPS: [Group; 8] = [
+    Group::Invalid,
+    Group::Reserved,
+    Group::DataProcImm,
+    Group::BranchSys,
+    Group::LoadStore,
+    Group::DataProcReg,
+    Group::SimdFp,
+    Group::Sve,
+];
+
+impl Op {
+    /// Opcodes are declared group by group, so this only compares against
+    /// the first opcode of each group.
+    pub fn group(self) -> Group {
+        match self {
+            A64_UNKNOWN | A64_ERROR => Group::Invalid,
+            A64_UDF => Group::Reserved,
+            _ if self < A64_BCOND => Group::DataProcImm,
+            _ if self < A64_UDIV => Group::BranchSys,
+            _ if self < A64_LD1_MULT => Group::DataProcReg,
+            _ if self < A64_FCVT_GPR => Group::LoadStore,
+            _ if self < A64_ADD_Z => Group::SimdFp,
+            _ => Group::Sve,
+        }
+    }
+}
+
+impl Inst {
+    pub fn group(&self) -> Group {
+        self.op.group()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_boundaries() {
+        assert_eq!(A64_ROR_IMM.group(), Group::DataProcImm);
+        assert_eq!(A64_TBNZ.group(), Group::BranchSys);
+        assert_eq!(A64_UMULH.group(), Group::DataProcReg);
+        assert_eq!(A64_CASP.group(), Group::LoadStore);
+        assert_eq!(A64_PMULL.group(), Group::SimdFp);
+        assert_eq!(A64_ADD_Z.group(), Group::Sve);
+    }
+}
@@ -0,0 +1,149 @@
+//! Heat reports: every instruction of a program with its share of the
+//! samples, normalized to the hottest instruction, and the counts of other
+//! events (branch mispredictions, cache misses) when profiled, as text or
+//! JSON for dashboards that render annotated assembly.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::aarch64_listing::inst_text;
+use crate::aarch64_profile::Profile;
+use crate::aarch64_program::Program;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeatInst {
+    pub addr: u64,
+    pub text: String,
+    /// Start of the basic block.
+    pub block: u64,
+    pub samples: u64,
+    /// samples relative to the hottest instruction, 0.0 to 1.0.
+    pub hotness: f64,
+    /// Counts of the other events, by event name; only the nonzero ones.
+    pub events: BTreeMap<String, u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HeatReport {
+    pub insts: Vec<HeatInst>,
+    pub total: u64,
+    /// Totals of the other events, by event name.
+    pub event_totals: BTreeMap<String, u64>,
+}
+
+/// The report of the whole program, with the hotness from samples and the
+/// other events from their own profiles.
+pub fn heat_report(program: &Program, samples: &Profile, events: &[(&str, &Profile)]) -> HeatReport {
+    let max = samples.insts.values().copied().max().unwrap_or(0);
+    let mut report = HeatReport { total: samples.total, ..HeatReport::default() };
+    for (name, profile) in events {
+        report.event_totals.insert(name.to_string(), profile.total);
+    }
+    for (block, end) in program.basic_blocks() {
+        for (addr, inst) in program.range(block..end) {
+            let count = samples.inst_count(addr);
+            report.insts.push(HeatInst {
+                addr,
+                text: inst_text(inst, addr),
+                block,
+                samples: count,
+                hotness: if max == 0 { 0.0 } else { count as f64 / max as f64 },
+                events: events
+                    .iter()
+                    .filter(|(_, p)| p.inst_count(addr) > 0)
+                    .map(|(name, p)| (name.to_string(), p.inst_count(addr)))
+                    .collect(),
+            });
+        }
+    }
+    report
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl HeatReport {
+    /// One line per instruction: hotness as a bar of up to 10 '#', then the
+    /// samples and events of the instructions that have any.
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let mut block = None;
+        for inst in &self.insts {
+            if block.is_some_and(|b| b != inst.block) {
+                out.push('\n');
+            }
+            block = Some(inst.block);
+            let bar = "#".repeat((inst.hotness * 10.0).round() as usize);
+            write!(out, "{:<10} {:>12x}:  {:<32}", bar, inst.addr, inst.text).unwrap();
+            if inst.samples > 0 || !inst.events.is_empty() {
+                write!(out, " // {} samples", inst.samples).unwrap();
+                for (name, n) in &inst.events {
+                    write!(out, ", {} {}", n, name).unwrap();
+                }
+            }
+            out.truncate(out.trim_end().len());
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        write!(out, "{{\"total\":{},\"events\":{{", self.total).unwrap();
+        for (i, (name, n)) in self.event_totals.iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(out, "{}{}:{}", sep, json_string(name), n).unwrap();
+        }
+        out.push_str("},\"insts\":[");
+        for (i, inst) in self.insts.iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(
+                out,
+                "{}{{\"addr\":{},\"block\":{},\"text\":{},\"samples\":{},\"hotness\":{:.4},\"events\":{{",
+                sep, inst.addr, inst.block, json_string(&inst.text), inst.samples, inst.hotness
+            )
+            .unwrap();
+            for (j, (name, n)) in inst.events.iter().enumerate() {
+                let sep = if j == 0 { "" } else { "," };
+                write!(out, "{}{}:{}", sep, json_string(name), n).unwrap();
+            }
+            out.push_str("}}");
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_profile::parse_samples;
+
+    #[test]
+    fn hot_loop() {
+        let program = Program::from_words(&[
+            0xF1000400, // subs x0, x0, #1
+            0x54FFFFE1, // b.ne .-4
+            0xD65F03C0, // ret
+        ], 0x1000);
+        let cycles = Profile::new(&program, &parse_samples("1000 8\n1004 4\n").unwrap());
+        let mispredicts = Profile::new(&program, &parse_samples("1004 1\n").unwrap());
+        let report = heat_report(&program, &cycles, &[("branch-misses", &mispredicts)]);
+        let heat: Vec<(u64, f64)> = report.insts.iter().map(|i| (i.addr, i.hotness)).collect();
+        assert_eq!(heat, vec![(0x1000, 1.0), (0x1004, 0.5), (0x1008, 0.0)]);
+        assert_eq!(report.insts[1].events["branch-misses"], 1);
+        assert!(report.to_json().starts_with("{\"total\":12,\"events\":{\"branch-misses\":1},\"insts\":[{\"addr\":4096,"));
+        assert!(report.to_text().lines().nth(1).unwrap().ends_with("// 4 samples, 1 branch-misses"));
+    }
+}
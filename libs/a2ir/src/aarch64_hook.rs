@@ -0,0 +1,170 @@
+//! Function hooking: redirect a function's entry to a hook while keeping the
+//! original callable through a trampoline.
+//!
+//! The first instructions of the function are overwritten by a jump to the
+//! hook. Those "stolen" instructions are relocated into the trampoline,
+//! followed by a jump back to the first instruction after the patch.
+
+use crate::aarch64_reader::{decode, ExtendType, Op};
+use crate::aarch64_relocate::{absolute_jump, relocate_all, target_address, SCRATCH_REG};
+use crate::aarch64_writer;
+
+/// A ready-to-apply detour. All byte vectors are little-endian encoded.
+pub struct Detour {
+    /// Bytes to write over the function entry.
+    pub patch: Vec<u8>,
+    /// Bytes to write at the trampoline address; calling the trampoline
+    /// behaves like calling the original function.
+    pub trampoline: Vec<u8>,
+    /// Number of bytes of the original function the patch overwrites.
+    pub stolen: usize,
+}
+
+pub fn words_to_bytes(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|w| w.to_le_bytes()).collect()
+}
+
+pub fn bytes_to_words(bytes: &[u8]) -> Vec<u32> {
+    bytes.chunks_exact(4).map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect()
+}
+
+/// Jump from pc to target: a single B if in range, otherwise
+/// LDR X16, #8; BR X16; .quad target.
+pub fn jump_patch(pc: u64, target: u64) -> Vec<u32> {
+    if let Ok(b) = aarch64_writer::b(target.wrapping_sub(pc) as i64) {
+        return vec![b];
+    }
+    vec![
+        aarch64_writer::ldr_literal(ExtendType::UXTX, SCRATCH_REG, 8).unwrap(),
+        aarch64_writer::br(SCRATCH_REG),
+        target as u32,
+        (target >> 32) as u32,
+    ]
+}
+
+/// Builds a detour of the function at address func, whose code (at least the
+/// prologue, ideally the whole function) is given in code, to hook. The
+/// trampoline will be placed at address trampoline.
+pub fn detour(code: &[u8], func: u64, hook: u64, trampoline: u64) -> Result<Detour, String> {
+    let words = bytes_to_words(code);
+    let patch = jump_patch(func, hook);
+    let n = patch.len();
+    if words.len() < n {
+        return Err(format!("detour: need {} bytes of code, got {}", 4 * n, code.len()));
+    }
+
+    // The function must not end inside the patch window: the bytes after an
+    // unconditional transfer may belong to someone else.
+    for (i, &binst) in words[..n - 1].iter().enumerate() {
+        let inst = decode(binst);
+        if inst.op == Op::A64_UDF || inst.branch_kind().is_some_and(|kind| !kind.falls_through()) {
+            return Err(format!("detour: function ends at {:#x}, inside the patch window", func + 4 * i as u64));
+        }
+    }
+
+    // Nothing in the known code may branch into the middle of the window,
+    // since it would land in the middle of the patch.
+    let window = func + 4..func + 4 * n as u64;
+    for (i, &binst) in words.iter().enumerate() {
+        let pc = func + 4 * i as u64;
+        let inst = decode(binst);
+        if let Some(target) = target_address(&inst, pc) {
+            if window.contains(&target) && inst.op != Op::A64_ADRP {
+                return Err(format!("detour: instruction at {:#x} references {:#x}, inside the patch window", pc, target));
+            }
+        }
+    }
+
+    let mut tramp = relocate_all(&words[..n], func, trampoline)?;
+    let resume = func + 4 * n as u64;
+    let back_pc = trampoline + 4 * tramp.len() as u64;
+    match aarch64_writer::b(resume.wrapping_sub(back_pc) as i64) {
+        Ok(b) => tramp.push(b),
+        Err(_) => tramp.extend(absolute_jump(resume, false)),
+    }
+
+    Ok(Detour {
+        patch: words_to_bytes(&patch),
+        trampoline: words_to_bytes(&tramp),
+        stolen: 4 * n,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn near_hook_steals_one_instruction() {
+        let code = words_to_bytes(&[
+            0xA9BF7BFD, // stp x29, x30, [sp, #-16]!
+            0x910003FD, // mov x29, sp
+            0xD65F03C0, // ret
+        ]);
+        let d = detour(&code, 0x10000, 0x20000, 0x30000).unwrap();
+        assert_eq!(d.stolen, 4);
+        assert_eq!(bytes_to_words(&d.patch), vec![0x14004000]); // b #+0x10000
+        assert_eq!(bytes_to_words(&d.trampoline), vec![0xA9BF7BFD, 0x17FF8000]); // b #-0x20000
+    }
+
+    #[test]
+    fn far_hook_rejects_short_function() {
+        let code = words_to_bytes(&[0xD2800000, 0xD65F03C0]); // mov x0, #0; ret
+        assert!(detour(&code, 0x10000, 0x1_0000_0000, 0x30000).is_err());
+    }
+
+    #[test]
+    fn far_hook_jumps_through_a_literal() {
+        let code = words_to_bytes(&[
+            0xA9BF7BFD, // stp x29, x30, [sp, #-16]!
+            0x910003FD, // mov x29, sp
+            0xB0000000, // adrp x0, .+0x1000
+            0xF9400001, // ldr x1, [x0]
+            0xD65F03C0, // ret
+        ]);
+        let d = detour(&code, 0x10000, 0x1_0000_0000, 0x20000).unwrap();
+        assert_eq!(d.stolen, 16);
+        // ldr x16, .+8; br x16; .quad 0x1_0000_0000
+        assert_eq!(bytes_to_words(&d.patch), vec![0x58000050, 0xD61F0200, 0, 1]);
+        assert_eq!(bytes_to_words(&d.trampoline), vec![
+            0xA9BF7BFD,
+            0x910003FD,
+            0xB0FFFF80, // adrp x0, .-0xf000: still the page 0x11000
+            0xF9400001,
+            0x17FFC000, // b .-0x10000, to 0x10010
+        ]);
+    }
+
+    #[test]
+    fn far_trampoline_expands_stolen_branch() {
+        let code = words_to_bytes(&[
+            0x54000041, // b.ne .+8
+            0xD65F03C0, // ret
+            0xD2800000, // mov x0, #0
+        ]);
+        let func = 0x1_0000_0000;
+        let d = detour(&code, func, func + 0x1000, 0x1000).unwrap();
+        assert_eq!(bytes_to_words(&d.patch), vec![0x14000400]); // b .+0x1000
+        assert_eq!(bytes_to_words(&d.trampoline), vec![
+            0x54000080, 0xD2800110, 0xF2C00030, 0xD61F0200, // b.eq .+16 over a jump to func + 8
+            0xD2800090, 0xF2C00030, 0xD61F0200, // and back to func + 4
+        ]);
+    }
+
+    #[test]
+    fn unsafe_windows_are_rejected() {
+        let far = 0x1_0000_0000;
+        // ret; nop; nop; nop: the function ends inside the window.
+        let code = words_to_bytes(&[0xD65F03C0, 0xD503201F, 0xD503201F, 0xD503201F]);
+        assert!(detour(&code, 0x10000, far, 0x20000).is_err());
+        // ...; b .-8, into the third instruction of the window
+        let code = words_to_bytes(&[0xD503201F, 0xD503201F, 0xD503201F, 0xD503201F, 0x17FFFFFE]);
+        assert!(detour(&code, 0x10000, far, 0x20000).is_err());
+    }
+
+    #[test]
+    fn jump_patches() {
+        assert_eq!(jump_patch(0x1000, 0x1000 + 0x7FFFFFC), vec![0x15FFFFFF]); // b .+0x7fffffc
+        assert_eq!(jump_patch(0x1000, 0x1000 + 0x8000000), vec![0x58000050, 0xD61F0200, 0x0800_1000, 0]);
+    }
+}
@@ -0,0 +1,321 @@
+//! Live-patching plans: how to replace instructions of running code so that a
+//! concurrently executing core observes either the old or the new sequence,
+//! never a mix.
+//!
+//! The architecture guarantees single-copy atomicity only for aligned 4-byte
+//! instruction fetches, and concurrent modification is only permitted when
+//! the old and the new instruction are both B, BL, BRK, NOP, ISB or in the
+//! hint space (ARM ARM B2.2.5, "Concurrent modification and execution of
+//! instructions"). Hence, while other threads run:
+//!
+//! - a single instruction is written directly if the old and new
+//!   instruction are both among the above;
+//! - longer patches go through a BRK window: trap the first instruction,
+//!   write the tail, then replace the BRK by the first new instruction. Both
+//!   the old and the new first instruction must be among the above, and no
+//!   thread may be inside the tail. The embedder's BRK handler must make
+//!   trapped threads wait for (or skip to) the patched code.
+//!
+//! Anything else, including pairs written by one aligned 8-byte store, is
+//! only planned when the embedder has stopped the other threads.
+//!
+//! Every write is followed by the cache maintenance needed to make the new
+//! instructions visible to instruction fetch on all cores.
+//!
+//! `check_entry_patch` tells whether the entry of a function may be
+//! overwritten at all, before planning the patch.
+
+use crate::aarch64_defuse::{def_use, Loc};
+use crate::aarch64_pac::{PacHint, PacModifier};
+use crate::aarch64_program::{Program, INST_SIZE};
+use crate::aarch64_reader::{decode, Inst, Op, Registries};
+use crate::aarch64_relocate::{absolute_jump, is_pc_relative, target_address};
+use crate::aarch64_writer;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    SingleWord,
+    BrkWindow,
+    /// Written in place while no other thread runs.
+    Stopped,
+}
+
+/// Whether other threads may execute the patched instructions meanwhile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Threads {
+    Running,
+    Stopped,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchStep {
+    /// Write bytes at addr with a single store of bytes.len() bytes.
+    Write { addr: u64, bytes: Vec<u8> },
+    /// DC CVAU over [addr, addr+len): clean data cache to the point of unification.
+    DcCvau { addr: u64, len: u64 },
+    /// DSB ISH: wait for the maintenance to complete in the inner shareable domain.
+    DsbIsh,
+    /// IC IVAU over [addr, addr+len): invalidate instruction cache to the point of unification.
+    IcIvau { addr: u64, len: u64 },
+    /// ISB: context synchronization; other cores only need it if they must
+    /// observe the change at a precise point.
+    Isb,
+}
+
+impl PatchStep {
+    pub fn describe(&self) -> String {
+        match self {
+            PatchStep::Write { addr, bytes } => {
+                let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                format!("write {} bytes at {:#x}: {}", bytes.len(), addr, hex.join(" "))
+            }
+            PatchStep::DcCvau { addr, len } => format!("dc cvau for each line in [{:#x}, {:#x})", addr, addr + len),
+            PatchStep::DsbIsh => "dsb ish".to_string(),
+            PatchStep::IcIvau { addr, len } => format!("ic ivau for each line in [{:#x}, {:#x})", addr, addr + len),
+            PatchStep::Isb => "isb".to_string(),
+        }
+    }
+}
+
+pub struct PatchPlan {
+    pub strategy: Strategy,
+    pub steps: Vec<PatchStep>,
+}
+
+impl PatchPlan {
+    pub fn describe(&self) -> String {
+        let mut out = format!("strategy: {:?}\n", self.strategy);
+        for (i, step) in self.steps.iter().enumerate() {
+            out += &format!("{:2}. {}\n", i + 1, step.describe());
+        }
+        out
+    }
+}
+
+/// Immediate of the BRK placed during a BrkWindow patch.
+pub const PATCH_BRK_IMM: u16 = 0x7AC;
+
+fn write_and_sync(steps: &mut Vec<PatchStep>, addr: u64, words: &[u32]) {
+    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_le_bytes()).collect();
+    let len = bytes.len() as u64;
+    steps.push(PatchStep::Write { addr, bytes });
+    steps.push(PatchStep::DcCvau { addr, len });
+    steps.push(PatchStep::DsbIsh);
+    steps.push(PatchStep::IcIvau { addr, len });
+    steps.push(PatchStep::DsbIsh);
+    steps.push(PatchStep::Isb);
+}
+
+/// Whether word may be modified while another core executes it (B2.2.5).
+pub fn is_concurrently_modifiable(word: u32) -> bool {
+    matches!(decode(word).op, Op::A64_B | Op::A64_BL | Op::A64_BRK | Op::A64_ISB | Op::A64_HINT)
+}
+
+/// Plans replacing the instructions at pc, the first of which is old, by
+/// words.
+pub fn plan_patch(pc: u64, old: u32, words: &[u32], threads: Threads) -> Result<PatchPlan, String> {
+    if !pc.is_multiple_of(4) {
+        return Err(format!("plan_patch: {:#x} is not instruction-aligned", pc));
+    }
+    let first = *words.first().ok_or("plan_patch: nothing to write")?;
+    let sanctioned = is_concurrently_modifiable(old) && is_concurrently_modifiable(first);
+    let strategy = match (words.len(), sanctioned, threads) {
+        (1, true, _) => Strategy::SingleWord,
+        (_, true, Threads::Running) => Strategy::BrkWindow,
+        (_, _, Threads::Stopped) => Strategy::Stopped,
+        (_, false, Threads::Running) => {
+            return Err(format!("plan_patch: {:#010x} -> {:#010x} at {:#x} needs the other threads stopped", old, first, pc));
+        }
+    };
+
+    let mut steps = Vec::new();
+    if strategy == Strategy::BrkWindow {
+        write_and_sync(&mut steps, pc, &[aarch64_writer::brk(PATCH_BRK_IMM)]);
+        write_and_sync(&mut steps, pc + 4, &words[1..]);
+        write_and_sync(&mut steps, pc, &words[..1]);
+    } else {
+        write_and_sync(&mut steps, pc, words);
+    }
+
+    Ok(PatchPlan { strategy, steps })
+}
+
+/// Plans redirecting execution at pc, where old is, to dest: a single B if
+/// in range, an absolute jump through X16 otherwise.
+pub fn plan_redirect(pc: u64, old: u32, dest: u64, threads: Threads) -> Result<PatchPlan, String> {
+    match aarch64_writer::b(dest.wrapping_sub(pc) as i64) {
+        Ok(b) => plan_patch(pc, old, &[b], threads),
+        Err(_) => plan_patch(pc, old, &absolute_jump(dest, false), threads),
+    }
+}
+
+/// Why overwriting the entry of a function may break it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PatchHazard {
+    /// The window runs past the function or into a gap.
+    WindowTooLong { end: u64 },
+    /// A PC-relative instruction is overwritten; a trampoline running it
+    /// elsewhere must relocate it.
+    PcRelative { pc: u64 },
+    /// A branch lands inside the window, after its first instruction, and
+    /// would execute a part of the patch.
+    BranchIntoWindow { from: u64, to: u64 },
+    /// The entry is a BTI landing pad for calls (BTI c, BTI jc, PACIASP,
+    /// PACIBSP); indirect calls fault in guarded pages unless the patch
+    /// starts with one too.
+    LandingPad { pc: u64 },
+    /// Frame setup (saving FP and LR, adjusting SP, signing LR) that
+    /// unwinders expect at the offsets the unwind info gives.
+    Prologue { pc: u64 },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatchVerdict {
+    pub entry: u64,
+    /// Instructions the patch overwrites.
+    pub words: usize,
+    pub hazards: Vec<PatchHazard>,
+}
+
+impl PatchVerdict {
+    pub fn is_safe(&self) -> bool {
+        self.hazards.is_empty()
+    }
+}
+
+fn is_call_landing_pad(inst: &Inst) -> bool {
+    // BTI c (34) and BTI jc (38); PACIASP and PACIBSP are implicit BTI c.
+    inst.op == Op::A64_HINT
+        && (matches!(inst.imm, 34 | 38)
+            || matches!(inst.pac_hint(), Some(PacHint::Sign { modifier: PacModifier::Sp, .. })))
+}
+
+fn is_frame_setup(inst: &Inst) -> bool {
+    let du = def_use(inst);
+    let stores_to_stack = matches!(inst.op, Op::A64_STR | Op::A64_STP) && inst.rn == Registries::STACK_POINTER;
+    let signs_lr = matches!(inst.pac_hint(), Some(PacHint::Sign { modifier: PacModifier::Sp, .. }));
+    stores_to_stack || signs_lr || du.defs.contains(&Loc::SP) || du.defs.contains(&Loc::X(29))
+}
+
+/// Checks overwriting the first `words` instructions of the function at
+/// [entry, end) of program, e.g. 1 for a B and 4 for an absolute jump. All
+/// branches of program are checked for landing inside the window.
+pub fn check_entry_patch(program: &Program, entry: u64, end: u64, words: usize) -> PatchVerdict {
+    let mut hazards = Vec::new();
+    let window_end = entry + words as u64 * INST_SIZE;
+    let window: Vec<(u64, &Inst)> = program.range(entry..window_end.min(end)).collect();
+    if window_end > end || window.len() != words {
+        hazards.push(PatchHazard::WindowTooLong { end: window_end });
+    }
+    for (pc, inst) in &window {
+        if is_pc_relative(inst) {
+            hazards.push(PatchHazard::PcRelative { pc: *pc });
+        }
+        if *pc == entry && is_call_landing_pad(inst) {
+            hazards.push(PatchHazard::LandingPad { pc: *pc });
+        }
+        if is_frame_setup(inst) {
+            hazards.push(PatchHazard::Prologue { pc: *pc });
+        }
+    }
+    for (from, inst) in program.iter() {
+        if inst.branch_kind().is_none() {
+            continue;
+        }
+        if let Some(to) = target_address(inst, from).filter(|&to| to > entry && to < window_end) {
+            hazards.push(PatchHazard::BranchIntoWindow { from, to });
+        }
+    }
+    PatchVerdict { entry, words, hazards }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOP: u32 = 0xD503201F;
+
+    #[test]
+    fn near_redirect_is_single_word() {
+        let plan = plan_redirect(0x1000, NOP, 0x2000, Threads::Running).unwrap();
+        assert_eq!(plan.strategy, Strategy::SingleWord);
+        assert_eq!(plan.steps[0], PatchStep::Write { addr: 0x1000, bytes: vec![0x00, 0x04, 0x00, 0x14] });
+        // stp x29, x30, [sp, #-16]! may not be modified concurrently.
+        assert!(plan_redirect(0x1000, 0xA9BF7BFD, 0x2000, Threads::Running).is_err());
+        assert_eq!(plan_redirect(0x1000, 0xA9BF7BFD, 0x2000, Threads::Stopped).unwrap().strategy, Strategy::Stopped);
+    }
+
+    #[test]
+    fn far_redirect_needs_stopped_threads() {
+        // The jump starts with an LDR (literal), which is not sanctioned.
+        assert!(plan_redirect(0x1000, NOP, 0x12_3456_7890, Threads::Running).is_err());
+        let plan = plan_redirect(0x1000, NOP, 0x12_3456_7890, Threads::Stopped).unwrap();
+        assert_eq!(plan.strategy, Strategy::Stopped);
+        assert_eq!(plan.steps.len(), 6);
+        // Pairs get no special treatment, aligned or not.
+        let pair = [0xF9400020, 0xD503201F]; // ldr x0, [x1]; nop
+        assert!(plan_patch(0x1000, NOP, &pair, Threads::Running).is_err());
+        assert_eq!(plan_patch(0x1000, NOP, &pair, Threads::Stopped).unwrap().strategy, Strategy::Stopped);
+    }
+
+    #[test]
+    fn brk_window_ends_with_a_sanctioned_word() {
+        let words = [
+            0x14000003, // b .+12
+            0xF9400020, // ldr x0, [x1]
+            0xD65F03C0, // ret
+        ];
+        let plan = plan_patch(0x1000, NOP, &words, Threads::Running).unwrap();
+        assert_eq!(plan.strategy, Strategy::BrkWindow);
+        assert_eq!(plan.steps.len(), 18);
+        assert_eq!(plan.steps[0], PatchStep::Write { addr: 0x1000, bytes: aarch64_writer::brk(PATCH_BRK_IMM).to_le_bytes().to_vec() });
+        assert_eq!(plan.steps[12], PatchStep::Write { addr: 0x1000, bytes: vec![0x03, 0x00, 0x00, 0x14] });
+        // The BRK may not become an LDR.
+        assert!(plan_patch(0x1000, NOP, &words[1..], Threads::Running).is_err());
+        assert!(plan_patch(0x1001, NOP, &words, Threads::Stopped).is_err());
+        assert!(plan_patch(0x1000, NOP, &[], Threads::Stopped).is_err());
+    }
+
+    #[test]
+    fn concurrently_modifiable_words() {
+        for word in [
+            0x14000000, // b .
+            0x97FFFFFF, // bl .-4
+            0xD4200000, // brk #0
+            0xD503201F, // nop
+            0xD5033FDF, // isb
+            0xD503233F, // paciasp
+        ] {
+            assert!(is_concurrently_modifiable(word), "{:#010x}", word);
+        }
+        for word in [
+            0xB4000000, // cbz x0, .
+            0xD61F0200, // br x16
+            0xD4000001, // svc #0
+            0xAA0103E0, // mov x0, x1
+        ] {
+            assert!(!is_concurrently_modifiable(word), "{:#010x}", word);
+        }
+    }
+
+    #[test]
+    fn entry_patch_hazards() {
+        let program = Program::from_words(&[
+            0xD503233F, // paciasp
+            0xA9BF7BFD, // stp x29, x30, [sp, #-16]!
+            0x90000008, // adrp x8, .
+            0xB4FFFFE0, // cbz x0, .-4
+            0xD65F03C0, // ret
+        ], 0x1000);
+        let hazards = check_entry_patch(&program, 0x1000, 0x1014, 3).hazards;
+        assert_eq!(hazards, vec![
+            PatchHazard::LandingPad { pc: 0x1000 },
+            PatchHazard::Prologue { pc: 0x1000 },
+            PatchHazard::Prologue { pc: 0x1004 },
+            PatchHazard::PcRelative { pc: 0x1008 },
+            PatchHazard::BranchIntoWindow { from: 0x100C, to: 0x1008 },
+        ]);
+        assert!(check_entry_patch(&program, 0x1010, 0x1014, 1).is_safe());
+        assert_eq!(check_entry_patch(&program, 0x1010, 0x1014, 2).hazards, vec![PatchHazard::WindowTooLong { end: 0x1018 }]);
+    }
+}
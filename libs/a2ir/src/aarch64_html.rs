@@ -0,0 +1,184 @@
+//! Self-contained HTML reports of decoded programs for code reviews of
+//! binary-only components: an index of the functions, then a section per
+//! function with its listing, where branch targets and references link to
+//! the instructions they address, every referenced instruction lists its
+//! references (xrefs), and addresses built by ADR(P)/ADD or loaded from
+//! that point to NUL-terminated strings show the string.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::aarch64_constprop::{propagate, ConstOptions};
+use crate::aarch64_listing::operand_text;
+use crate::aarch64_mnemonic::inst_mnemonic;
+use crate::aarch64_operand::{operands, Operand};
+use crate::aarch64_program::Program;
+use crate::aarch64_reader::Inst;
+use crate::aarch64_relocate::target_address;
+
+/// Strings are only shown from this many printable characters on.
+pub const MIN_STRING: usize = 4;
+
+#[derive(Clone, Debug, Default)]
+pub struct HtmlOptions {
+    pub title: String,
+    /// Function names by entry; the others are named sub_<address>.
+    pub names: BTreeMap<u64, String>,
+    /// Memory the strings are looked up in, as (address, bytes).
+    pub data: Vec<(u64, Vec<u8>)>,
+}
+
+impl HtmlOptions {
+    fn name(&self, entry: u64) -> String {
+        self.names.get(&entry).cloned().unwrap_or_else(|| format!("sub_{:x}", entry))
+    }
+
+    /// The NUL-terminated printable string at addr.
+    fn string_at(&self, addr: u64) -> Option<String> {
+        let (base, bytes) = self.data.iter().find(|(base, bytes)| addr >= *base && addr - base < bytes.len() as u64)?;
+        let tail = &bytes[(addr - base) as usize..];
+        let len = tail.iter().position(|&b| b == 0)?;
+        let s = &tail[..len];
+        let printable = s.iter().all(|&b| b.is_ascii_graphic() || b == b' ' || b == b'\t' || b == b'\n');
+        (len >= MIN_STRING && printable).then(|| String::from_utf8_lossy(s).into_owned())
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn link(addr: u64) -> String {
+    format!("<a href=\"#a_{:x}\">{:#x}</a>", addr, addr)
+}
+
+/// The listing text of inst, with its label operands linked to the
+/// instructions they address.
+fn inst_html(inst: &Inst, pc: u64, program: &Program) -> String {
+    let ops: Vec<String> = operands(inst)
+        .into_iter()
+        .map(|operand| {
+            let target = match operand {
+                Operand::Label(offset) => Some(pc.wrapping_add(offset as u64)),
+                Operand::PageLabel(offset) => Some((pc & !0xFFF).wrapping_add(offset as u64)),
+                _ => None,
+            };
+            match target {
+                Some(target) if program.get(target).is_some() => link(target),
+                _ => escape(&operand_text(operand, pc)),
+            }
+        })
+        .collect();
+    let mnemonic = escape(&inst_mnemonic(inst));
+    if ops.is_empty() { mnemonic } else { format!("{} {}", mnemonic, ops.join(", ")) }
+}
+
+const STYLE: &str = "body{font-family:sans-serif}pre{font-family:monospace;line-height:1.3}\
+a{text-decoration:none}:target{background:#ff8}.c{color:#777}.x{color:#36a}";
+
+/// The report of the functions, as (entry, end) ranges with their
+/// instructions in program.
+pub fn html_report(program: &Program, functions: &[(u64, u64)], options: &HtmlOptions) -> String {
+    // The addresses each instruction references, by pc.
+    let mut refs: BTreeMap<u64, BTreeSet<u64>> = BTreeMap::new();
+    for &(entry, end) in functions {
+        let block: Vec<_> = program.range(entry..end).map(|(a, i)| (a, i.clone())).collect();
+        let resolved = propagate(&block, &ConstOptions::default());
+        for (pc, inst) in &block {
+            let direct = target_address(inst, *pc);
+            let found = [direct, resolved.branch_targets.get(pc).copied(), resolved.data_addresses.get(pc).copied(),
+                resolved.values.get(pc).copied()];
+            refs.entry(*pc).or_default().extend(found.into_iter().flatten());
+        }
+    }
+    let mut xrefs: BTreeMap<u64, BTreeSet<u64>> = BTreeMap::new();
+    for (pc, targets) in &refs {
+        for &target in targets.iter().filter(|t| program.get(**t).is_some()) {
+            xrefs.entry(target).or_default().insert(*pc);
+        }
+    }
+    let entries: BTreeMap<u64, u64> = functions.iter().copied().collect();
+
+    let mut out = String::new();
+    let title = escape(&options.title);
+    write!(out, "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body>\n",
+        title, STYLE).unwrap();
+    write!(out, "<h1>{}</h1>\n<ul>\n", title).unwrap();
+    for &(entry, end) in functions {
+        let name = escape(&options.name(entry));
+        writeln!(out, "<li><a href=\"#f_{:x}\">{}</a> {:#x}-{:#x}</li>", entry, name, entry, end).unwrap();
+    }
+    out.push_str("</ul>\n");
+
+    for &(entry, end) in functions {
+        writeln!(out, "<hr><section id=\"f_{:x}\"><h2>{}</h2>", entry, escape(&options.name(entry))).unwrap();
+        if let Some(callers) = xrefs.get(&entry) {
+            let callers: Vec<String> = callers.iter().map(|&pc| link(pc)).collect();
+            writeln!(out, "<p class=\"x\">referenced from {}</p>", callers.join(", ")).unwrap();
+        }
+        out.push_str("<pre>\n");
+        for (pc, inst) in program.range(entry..end) {
+            let text = inst_html(inst, pc, program);
+            let targets = refs.get(&pc).cloned().unwrap_or_default();
+            let mut comments = Vec::new();
+            for &target in &targets {
+                if let Some(dest) = entries.get(&target).filter(|_| target != entry).map(|_| options.name(target)) {
+                    comments.push(format!("<a href=\"#f_{:x}\">{}</a>", target, escape(&dest)));
+                } else if let Some(s) = options.string_at(target) {
+                    comments.push(escape(&format!("{:?}", s)));
+                }
+            }
+            if let Some(from) = xrefs.get(&pc).filter(|_| pc != entry) {
+                let from: Vec<String> = from.iter().map(|&f| link(f)).collect();
+                comments.push(format!("<span class=\"x\">xref {}</span>", from.join(" ")));
+            }
+            write!(out, "<span id=\"a_{:x}\">{:>12x}:</span>  {}", pc, pc, text).unwrap();
+            if !comments.is_empty() {
+                write!(out, "  <span class=\"c\">// {}</span>", comments.join("; ")).unwrap();
+            }
+            out.push('\n');
+        }
+        out.push_str("</pre></section>\n");
+    }
+    out.push_str("</body></html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn call_and_string() {
+        let program = Program::from_words(&[
+            0x10000080, // adr x0, .+0x10
+            0x94000002, // bl .+8
+            0xD65F03C0, // ret
+            0xD65F03C0, // ret
+        ], 0x1000);
+        let options = HtmlOptions {
+            title: "a <test>".to_string(),
+            names: BTreeMap::from([(0x100C, "puts".to_string())]),
+            data: vec![(0x1010, b"hello\0".to_vec())],
+        };
+        let html = html_report(&program, &[(0x1000, 0x100C), (0x100C, 0x1010)], &options);
+        assert!(html.contains("<title>a &lt;test&gt;</title>"));
+        assert!(html.contains("bl <a href=\"#a_100c\">0x100c</a>  <span class=\"c\">// <a href=\"#f_100c\">puts</a>"));
+        assert!(html.contains("// &quot;hello&quot;"));
+        assert!(html.contains("<p class=\"x\">referenced from <a href=\"#a_1004\">0x1004</a></p>"));
+    }
+
+    #[test]
+    fn only_labels_are_linked() {
+        let program = Program::from_words(&[
+            0xD2820101, // mov x1, #0x1008: a constant, not a reference
+            0x90000002, // adrp x2, .
+            0x17FFFFFE, // b .-8
+            0xD65F03C0, // ret
+        ], 0x1000);
+        let html = html_report(&program, &[(0x1000, 0x1010)], &HtmlOptions::default());
+        assert!(html.contains("1000:</span>  mov x1, #0x1008\n"));
+        assert!(html.contains("adrp x2, <a href=\"#a_1000\">0x1000</a>"));
+        assert!(html.contains("b <a href=\"#a_1000\">0x1000</a>"));
+    }
+}
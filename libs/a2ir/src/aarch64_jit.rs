@@ -0,0 +1,152 @@
+//! A decoded view of code generated at run time, kept up to date as a
+//! tracer observes executable pages: JIT compilers of JavaScript and
+//! WebAssembly engines write code, run it, and overwrite it with other
+//! code at the same addresses.
+//!
+//! ```text
+//! observe(0x7f00_0000, page, 1)  // added:    0x7f00_0000...
+//! observe(0x7f00_0000, page, 2)  // replaced: the words that changed
+//! observe(0x7f00_0000, old,  1)  // stale: generation 2 is newer
+//! ```
+//!
+//! Each observation carries a generation counter from the host tool (a
+//! sequence number, a timestamp); words are only replaced by observations
+//! at least as new as the one that last set them, so captures that arrive
+//! out of order don't roll the view back. Analyses of the view are
+//! invalidated per word: see `Update::replaced` and `dirty_blocks`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::aarch64_cfg::Cfg;
+use crate::aarch64_program::{Program, INST_SIZE};
+use crate::aarch64_reader::decode;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Word {
+    /// The newest generation that observed the word.
+    seen: u64,
+    /// The generation that last changed it.
+    changed: u64,
+}
+
+/// What an observation changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Update {
+    /// Words decoded for the first time.
+    pub added: Vec<u64>,
+    /// Words whose contents changed; what was computed from them is stale.
+    pub replaced: Vec<u64>,
+    /// Words left alone because a newer generation had set them.
+    pub stale: usize,
+}
+
+impl Update {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.replaced.is_empty()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct JitView {
+    program: Program,
+    words: BTreeMap<u64, Word>,
+}
+
+impl JitView {
+    pub fn new() -> JitView {
+        JitView::default()
+    }
+
+    /// The instructions currently in the view.
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Decodes the little-endian words of bytes at addr, observed at
+    /// generation; trailing bytes short of a word are ignored.
+    pub fn observe(&mut self, addr: u64, bytes: &[u8], generation: u64) -> Result<Update, String> {
+        if !addr.is_multiple_of(INST_SIZE) {
+            return Err(format!("jit: unaligned observation at {:#x}", addr));
+        }
+        let mut update = Update::default();
+        for (i, chunk) in bytes.chunks_exact(INST_SIZE as usize).enumerate() {
+            let pc = addr + i as u64 * INST_SIZE;
+            let word = u32::from_le_bytes(chunk.try_into().expect("4 bytes"));
+            match self.words.get_mut(&pc) {
+                Some(w) if w.seen > generation => update.stale += 1,
+                Some(w) if self.program.get(pc).is_some_and(|inst| inst.raw == word) => w.seen = generation,
+                known => {
+                    if known.is_some() {
+                        update.replaced.push(pc);
+                    } else {
+                        update.added.push(pc);
+                    }
+                    self.words.insert(pc, Word { seen: generation, changed: generation });
+                    self.program.insert(pc, decode(word));
+                }
+            }
+        }
+        Ok(update)
+    }
+
+    /// Drops the words of [start, end), e.g. after the engine unmapped or
+    /// freed the code, and returns their addresses.
+    pub fn unmap(&mut self, start: u64, end: u64) -> Vec<u64> {
+        let gone: Vec<u64> = self.words.range(start..end).map(|(a, _)| *a).collect();
+        for addr in &gone {
+            self.words.remove(addr);
+            self.program.remove(*addr);
+        }
+        gone
+    }
+
+    /// The generation that set the word at addr.
+    pub fn generation(&self, addr: u64) -> Option<u64> {
+        self.words.get(&addr).map(|w| w.changed)
+    }
+
+    /// Words whose contents changed after generation, in address order.
+    pub fn changed_since(&self, generation: u64) -> Vec<u64> {
+        self.words.iter().filter(|(_, w)| w.changed > generation).map(|(a, _)| *a).collect()
+    }
+}
+
+/// The blocks of cfg, built from an earlier view, that contain a word the
+/// update replaced: the ones to rebuild.
+pub fn dirty_blocks(cfg: &Cfg, update: &Update) -> BTreeSet<u64> {
+    update.replaced.iter()
+        .filter_map(|&pc| cfg.blocks.range(..=pc).next_back().filter(|(_, b)| pc < b.end).map(|(start, _)| *start))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_reader::Op;
+
+    fn bytes(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn overwrite_and_stale_observations() {
+        let mut view = JitView::new();
+        let first = view.observe(0x1000, &bytes(&[0xD503201F, 0xD65F03C0]), 1).unwrap();
+        assert_eq!(first.added, vec![0x1000, 0x1004]);
+        let cfg = Cfg::build(view.program());
+
+        // The engine patches the nop into a ret.
+        let patch = view.observe(0x1000, &bytes(&[0xD65F03C0, 0xD65F03C0]), 3).unwrap();
+        assert_eq!(patch, Update { added: vec![], replaced: vec![0x1000], stale: 0 });
+        assert_eq!(dirty_blocks(&cfg, &patch), BTreeSet::from([0x1000]));
+        assert_eq!(view.program().get(0x1000).map(|i| i.op), Some(Op::A64_RET));
+
+        // A late capture of the old page doesn't undo it.
+        let late = view.observe(0x1000, &bytes(&[0xD503201F]), 2).unwrap();
+        assert_eq!((late.stale, late.is_empty()), (1, true));
+        assert_eq!(view.changed_since(1), vec![0x1000]);
+        assert_eq!(view.generation(0x1004), Some(1));
+        assert_eq!(view.unmap(0x1000, 0x2000), vec![0x1000, 0x1004]);
+        assert!(view.program().is_empty());
+    }
+}
@@ -0,0 +1,285 @@
+//! Linux kernel images and modules: the tables through which the kernel
+//! patches or redirects its own code at run time, so that analyses see the
+//! code that executes rather than the bytes in the file.
+//!
+//! ```text
+//! .altinstructions   orig, alt, cpucap, lengths   // patched in for a CPU capability
+//! __jump_table       code, target, key            // NOP <-> B on static key flips
+//! __bug_table        addr, file, line, flags      // BRK #0x800: BUG() and WARN()
+//! __ex_table         insn, fixup[, type, data]    // where a faulting uaccess resumes
+//! ```
+//!
+//! The crate doesn't read ELF, so the caller supplies each section's data
+//! and address, with a module's relocations applied. Entries use the
+//! relative layouts of arm64 (offsets from the field to its target).
+//! __bug_table entries are read in the CONFIG_DEBUG_BUGVERBOSE layout.
+
+use crate::aarch64_cfg::{Cfg, Terminator};
+use crate::aarch64_listing::Annotate;
+use crate::aarch64_program::{Program, INST_SIZE};
+use crate::aarch64_reader::{decode, Inst};
+use crate::aarch64_relocate::{relocate, target_address};
+
+/// The immediate of the BRK that BUG() and WARN() emit.
+pub const BUG_BRK_IMM: u16 = 0x800;
+/// cpucap bit of alternatives whose alt points to a patching callback.
+const ALT_CALLBACK: u16 = 1 << 15;
+/// bug_entry flag of WARN(), which continues after the BRK.
+const BUGFLAG_WARNING: u16 = 1 << 0;
+
+struct Section<'a> {
+    data: &'a [u8],
+    base: u64,
+}
+
+impl Section<'_> {
+    fn bytes(&self, offset: usize, len: usize) -> Result<&[u8], String> {
+        self.data.get(offset..offset + len).ok_or(format!("kernel: {:#x} past the section", self.base + offset as u64))
+    }
+
+    fn u16_at(&self, offset: usize) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.bytes(offset, 2)?.try_into().expect("2 bytes")))
+    }
+
+    /// The address a 32-bit relative field at offset refers to.
+    fn rel32(&self, offset: usize) -> Result<u64, String> {
+        let disp = i32::from_le_bytes(self.bytes(offset, 4)?.try_into().expect("4 bytes"));
+        Ok((self.base + offset as u64).wrapping_add(disp as i64 as u64))
+    }
+
+    fn rel64(&self, offset: usize) -> Result<u64, String> {
+        let disp = i64::from_le_bytes(self.bytes(offset, 8)?.try_into().expect("8 bytes"));
+        Ok((self.base + offset as u64).wrapping_add(disp as u64))
+    }
+
+    fn entries(&self, size: usize) -> Result<impl Iterator<Item = usize>, String> {
+        if !self.data.len().is_multiple_of(size) {
+            return Err(format!("kernel: section of {:#x} bytes isn't a table of {}-byte entries", self.data.len(), size));
+        }
+        Ok((0..self.data.len()).step_by(size))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alternative {
+    pub orig: u64,
+    /// The replacement, or the callback that patches orig if callback.
+    pub alt: u64,
+    pub cpucap: u16,
+    pub callback: bool,
+    pub orig_len: u8,
+    pub alt_len: u8,
+}
+
+/// The struct alt_instr entries of .altinstructions at base.
+pub fn alternatives(data: &[u8], base: u64) -> Result<Vec<Alternative>, String> {
+    let s = Section { data, base };
+    s.entries(12)?.map(|o| {
+        let cap = s.u16_at(o + 8)?;
+        let lens = s.bytes(o + 10, 2)?;
+        Ok(Alternative {
+            orig: s.rel32(o)?,
+            alt: s.rel32(o + 4)?,
+            cpucap: cap & !ALT_CALLBACK,
+            callback: cap & ALT_CALLBACK != 0,
+            orig_len: lens[0],
+            alt_len: lens[1],
+        })
+    }).collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JumpLabel {
+    /// The NOP or B that is patched.
+    pub code: u64,
+    pub target: u64,
+    pub key: u64,
+    /// The site branches while the key has its default value.
+    pub branch: bool,
+}
+
+/// The struct jump_entry entries of __jump_table at base.
+pub fn jump_labels(data: &[u8], base: u64) -> Result<Vec<JumpLabel>, String> {
+    let s = Section { data, base };
+    s.entries(16)?.map(|o| {
+        // The low bits of the key are flags: branch, init.
+        let key = s.rel64(o + 8)?;
+        Ok(JumpLabel { code: s.rel32(o)?, target: s.rel32(o + 4)?, key: key & !0b11, branch: key & 1 != 0 })
+    }).collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bug {
+    /// The BRK #0x800.
+    pub addr: u64,
+    /// Address of the source file name.
+    pub file: u64,
+    pub line: u16,
+    pub warning: bool,
+}
+
+/// The struct bug_entry entries of __bug_table at base.
+pub fn bugs(data: &[u8], base: u64) -> Result<Vec<Bug>, String> {
+    let s = Section { data, base };
+    s.entries(12)?.map(|o| {
+        Ok(Bug { addr: s.rel32(o)?, file: s.rel32(o + 4)?, line: s.u16_at(o + 8)?, warning: s.u16_at(o + 10)? & BUGFLAG_WARNING != 0 })
+    }).collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fixup {
+    /// The instruction that may fault.
+    pub insn: u64,
+    pub fixup: u64,
+    /// EX_TYPE_* and its data, in the 12-byte entries of 5.16 and later.
+    pub kind: Option<(u16, u16)>,
+}
+
+/// The struct exception_table_entry entries of __ex_table at base, of
+/// entry_size 8 (before 5.16) or 12 bytes.
+pub fn exception_table(data: &[u8], base: u64, entry_size: usize) -> Result<Vec<Fixup>, String> {
+    if entry_size != 8 && entry_size != 12 {
+        return Err(format!("kernel: bad exception table entry size {}", entry_size));
+    }
+    let s = Section { data, base };
+    s.entries(entry_size)?.map(|o| {
+        let kind = if entry_size == 12 { Some((s.u16_at(o + 8)?, s.u16_at(o + 10)?)) } else { None };
+        Ok(Fixup { insn: s.rel32(o)?, fixup: s.rel32(o + 4)?, kind })
+    }).collect()
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KernelTables {
+    pub alternatives: Vec<Alternative>,
+    pub jump_labels: Vec<JumpLabel>,
+    pub bugs: Vec<Bug>,
+    pub fixups: Vec<Fixup>,
+}
+
+impl KernelTables {
+    /// Patches the alternatives of the capabilities enabled has into
+    /// program, as the kernel does at boot: branches out of the replacement
+    /// are retargeted, branches within it kept. The replacements are
+    /// removed from program either way, being only ever copied. Callback
+    /// alternatives are left alone. Returns the number patched.
+    pub fn apply_alternatives(&self, program: &mut Program, enabled: &dyn Fn(u16) -> bool) -> Result<usize, String> {
+        let mut patched = 0;
+        let mut replacements = Vec::new();
+        for alt in self.alternatives.iter().filter(|a| !a.callback) {
+            let (orig_len, alt_len) = (alt.orig_len as u64, alt.alt_len as u64);
+            let words: Vec<u32> = (alt.alt..alt.alt + alt_len).step_by(INST_SIZE as usize)
+                .map(|a| program.get(a).map(Inst::raw).ok_or(format!("kernel: no replacement code at {:#x}", a)))
+                .collect::<Result<_, _>>()?;
+            replacements.push((alt.alt, alt.alt + alt_len));
+            if !enabled(alt.cpucap) {
+                continue;
+            }
+            if alt_len != orig_len {
+                return Err(format!("kernel: alternative at {:#x} replaces {} bytes with {}", alt.orig, orig_len, alt_len));
+            }
+            for (i, &word) in words.iter().enumerate() {
+                let (from, to) = (alt.alt + i as u64 * INST_SIZE, alt.orig + i as u64 * INST_SIZE);
+                let internal = target_address(&decode(word), from).is_some_and(|t| (alt.alt..alt.alt + alt_len).contains(&t));
+                let word = match relocate(word, from, to)?.as_slice() {
+                    _ if internal => word,
+                    [moved] => *moved,
+                    _ => return Err(format!("kernel: replacement at {:#x} can't reach its target from {:#x}", from, to)),
+                };
+                program.insert(to, decode(word));
+            }
+            patched += 1;
+        }
+        for (start, end) in replacements {
+            (start..end).step_by(INST_SIZE as usize).for_each(|a| { program.remove(a); });
+        }
+        Ok(patched)
+    }
+
+    /// Adds the control flow the tables imply to cfg: jump label sites
+    /// branch both ways, BUG() ends its block (WARN() doesn't), and an
+    /// instruction with a fixup has an exception edge to it.
+    pub fn apply(&self, cfg: &mut Cfg) {
+        for label in &self.jump_labels {
+            for addr in [label.code, label.code + INST_SIZE, label.target] {
+                cfg.split_at(addr);
+            }
+            if let Some(block) = cfg.blocks.get_mut(&label.code) {
+                block.terminator = Terminator::Branch { taken: label.target, fallthrough: label.code + INST_SIZE };
+            }
+        }
+        for bug in self.bugs.iter().filter(|b| !b.warning) {
+            cfg.split_at(bug.addr + INST_SIZE);
+            if let Some((_, block)) = cfg.blocks.range_mut(..=bug.addr).next_back().filter(|(_, b)| bug.addr < b.end) {
+                block.terminator = Terminator::End;
+            }
+        }
+        for fixup in &self.fixups {
+            cfg.split_at(fixup.fixup);
+            cfg.exception_edges.insert(fixup.insn, fixup.fixup);
+        }
+    }
+}
+
+/// Marks the patched and trapping instructions in listings.
+impl Annotate for KernelTables {
+    fn comment(&self, addr: u64, _inst: &Inst) -> Option<String> {
+        if let Some(label) = self.jump_labels.iter().find(|l| l.code == addr) {
+            return Some(format!("jump label {:#x}: {:#x}", label.key, label.target));
+        }
+        if let Some(bug) = self.bugs.iter().find(|b| b.addr == addr) {
+            return Some(format!("{}, line {}", if bug.warning { "WARN" } else { "BUG" }, bug.line));
+        }
+        if let Some(alt) = self.alternatives.iter().find(|a| (a.orig..a.orig + a.orig_len as u64).contains(&addr)) {
+            return Some(format!("alternative for cpucap {}", alt.cpucap));
+        }
+        self.fixups.iter().find(|f| f.insn == addr).map(|f| format!("fixup at {:#x}", f.fixup))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The 32-bit relative field at field referring to target.
+    fn rel(field: u64, target: u64) -> [u8; 4] {
+        (target.wrapping_sub(field) as i32).to_le_bytes()
+    }
+
+    #[test]
+    fn patched_and_trapping_code() {
+        let mut program = Program::from_words(&[
+            0xD503201F, // 0x1000: nop              (jump label to 0x100c)
+            0xF9400020, //         ldr x0, [x1]     (fixup at 0x1010)
+            0xD4210000, //         brk #0x800       (BUG)
+            0xD65F03C0, // 0x100c: ret
+            0xD2800000, // 0x1010: mov x0, #0
+            0xD65F03C0, //         ret
+            0xD503201F, // 0x1018: nop              (alternative)
+        ], 0x1000);
+        program.extend(Program::from_words(&[0x17FFFC03], 0x2000).iter().map(|(a, i)| (a, i.clone()))); // b 0x100c
+
+        let alt = [&rel(0x8000, 0x1018)[..], &rel(0x8004, 0x2000), &5u16.to_le_bytes(), &[4, 4]].concat();
+        let jump = [&rel(0x8100, 0x1000)[..], &rel(0x8104, 0x100C), &0x800i64.to_le_bytes()].concat();
+        let bug = [&rel(0x8200, 0x1008)[..], &rel(0x8204, 0x9000), &42u16.to_le_bytes(), &0u16.to_le_bytes()].concat();
+        let extable = [rel(0x8300, 0x1004), rel(0x8304, 0x1010)].concat();
+        let tables = KernelTables {
+            alternatives: alternatives(&alt, 0x8000).unwrap(),
+            jump_labels: jump_labels(&jump, 0x8100).unwrap(),
+            bugs: bugs(&bug, 0x8200).unwrap(),
+            fixups: exception_table(&extable, 0x8300, 8).unwrap(),
+        };
+        assert_eq!(tables.jump_labels, vec![JumpLabel { code: 0x1000, target: 0x100C, key: 0x8908, branch: false }]);
+        assert_eq!(tables.bugs, vec![Bug { addr: 0x1008, file: 0x9000, line: 42, warning: false }]);
+
+        assert_eq!(tables.apply_alternatives(&mut program, &|cap| cap == 5), Ok(1));
+        assert_eq!(program.get(0x1018).unwrap().raw(), 0x17FFFFFD); // b 0x100c
+        assert!(program.get(0x2000).is_none());
+
+        let mut cfg = Cfg::build(&program);
+        tables.apply(&mut cfg);
+        assert_eq!(cfg.blocks[&0x1000].terminator, Terminator::Branch { taken: 0x100C, fallthrough: 0x1004 });
+        assert_eq!(cfg.blocks[&0x1004].terminator, Terminator::End);
+        assert_eq!(cfg.exception_edges[&0x1004], 0x1010);
+        assert_eq!(tables.comment(0x1008, program.get(0x1008).unwrap()), Some("BUG, line 42".to_string()));
+    }
+}
@@ -0,0 +1,129 @@
+//! Programs decoded on demand, for images too large to decode up front.
+//!
+//! A `LazyProgram` reads and decodes a page of the image the first time an
+//! address in it is looked up, and keeps the most recently used pages up to
+//! a capacity. Opening it only reads the length of the file. Any `Read +
+//! Seek` source works; there is no memory mapping, since the standard
+//! library has none, but page-sized reads of a file stay in the OS cache
+//! much like a mapping would.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::aarch64_program::{Program, INST_SIZE};
+use crate::aarch64_reader::{decode, Inst};
+
+/// Bytes decoded at a time.
+pub const PAGE_SIZE: u64 = 0x1000;
+
+struct Page {
+    insts: Vec<Inst>,
+    last_used: u64,
+}
+
+pub struct LazyProgram<R: Read + Seek = File> {
+    source: R,
+    /// Address of the first byte of the source.
+    pub base: u64,
+    len: u64,
+    capacity: usize,
+    pages: BTreeMap<u64, Page>,
+    clock: u64,
+}
+
+impl LazyProgram<File> {
+    /// The file at path, loaded at base, keeping up to capacity pages.
+    pub fn open(path: &str, base: u64, capacity: usize) -> Result<LazyProgram<File>, String> {
+        let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+        LazyProgram::new(file, base, capacity)
+    }
+}
+
+impl<R: Read + Seek> LazyProgram<R> {
+    pub fn new(mut source: R, base: u64, capacity: usize) -> Result<LazyProgram<R>, String> {
+        if !base.is_multiple_of(INST_SIZE) {
+            return Err(format!("LazyProgram: unaligned base {:#x}", base));
+        }
+        let len = source.seek(SeekFrom::End(0)).map_err(|e| e.to_string())?;
+        Ok(LazyProgram { source, base, len, capacity: capacity.max(1), pages: BTreeMap::new(), clock: 0 })
+    }
+
+    /// The end of the image: base plus its length in whole words.
+    pub fn end(&self) -> u64 {
+        self.base + self.len / INST_SIZE * INST_SIZE
+    }
+
+    /// Number of pages currently decoded.
+    pub fn decoded_pages(&self) -> usize {
+        self.pages.len()
+    }
+
+    fn page(&mut self, page: u64) -> Result<&[Inst], String> {
+        self.clock += 1;
+        if !self.pages.contains_key(&page) {
+            if self.pages.len() >= self.capacity {
+                let oldest = self.pages.iter().min_by_key(|(_, p)| p.last_used).map(|(a, _)| *a);
+                self.pages.remove(&oldest.expect("capacity is at least 1"));
+            }
+            let offset = page - self.base;
+            let mut bytes = vec![0; PAGE_SIZE.min(self.len - offset) as usize];
+            self.source.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+            self.source.read_exact(&mut bytes).map_err(|e| format!("page {:#x}: {}", page, e))?;
+            let insts = bytes.chunks_exact(4).map(|w| decode(u32::from_le_bytes([w[0], w[1], w[2], w[3]]))).collect();
+            self.pages.insert(page, Page { insts, last_used: 0 });
+        }
+        let entry = self.pages.get_mut(&page).expect("page just decoded");
+        entry.last_used = self.clock;
+        Ok(&entry.insts)
+    }
+
+    /// The instruction at addr; None outside the image or off alignment.
+    pub fn get(&mut self, addr: u64) -> Result<Option<Inst>, String> {
+        if addr < self.base || addr >= self.end() || !addr.is_multiple_of(INST_SIZE) {
+            return Ok(None);
+        }
+        let page = addr - (addr - self.base) % PAGE_SIZE;
+        let index = ((addr - page) / INST_SIZE) as usize;
+        Ok(self.page(page)?.get(index).cloned())
+    }
+
+    /// The instructions of [start, end) as a Program, for the analyses
+    /// working on those.
+    pub fn program(&mut self, start: u64, end: u64) -> Result<Program, String> {
+        let mut out = Program::new();
+        let mut addr = start.max(self.base).next_multiple_of(INST_SIZE);
+        while addr < end.min(self.end()) {
+            if let Some(inst) = self.get(addr)? {
+                out.insert(addr, inst);
+            }
+            addr += INST_SIZE;
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_reader::Op;
+    use std::io::Cursor;
+
+    #[test]
+    fn pages_on_demand() {
+        let mut bytes = Vec::new();
+        for _ in 0..3 * PAGE_SIZE / INST_SIZE {
+            bytes.extend(0xD503201Fu32.to_le_bytes()); // nop
+        }
+        bytes.extend(0xD65F03C0u32.to_le_bytes()); // ret
+        let mut lazy = LazyProgram::new(Cursor::new(bytes), 0x10000, 2).unwrap();
+        assert_eq!(lazy.decoded_pages(), 0);
+        assert_eq!(lazy.end(), 0x13004);
+        assert!(lazy.get(0x13000).unwrap().is_some_and(|i| i.op == Op::A64_RET));
+        lazy.get(0x10000).unwrap();
+        lazy.get(0x11000).unwrap();
+        assert_eq!(lazy.decoded_pages(), 2); // 0x13000 was evicted
+        assert_eq!(lazy.get(0x13004).unwrap(), None);
+        assert_eq!(lazy.program(0x12FF8, 0x14000).unwrap().len(), 3);
+    }
+}
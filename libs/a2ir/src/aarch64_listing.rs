@@ -0,0 +1,309 @@
+//! Text listings of decoded programs, one instruction per line, with the
+//! annotations of the caller: header lines above an instruction (a source
+//! location, a block label) and comments after it (a sample count).
+//!
+//! ```text
+//!     1000:  stp x29, x30, [sp, #-0x10]!      // 12 samples
+//! ```
+
+use crate::aarch64_mnemonic::inst_mnemonic;
+use crate::aarch64_operand::{barrier_name, hint_operand, operands, pattern_name, Operand, Predication, Reg};
+use crate::aarch64_program::Program;
+use crate::aarch64_reader::{AddrMode, FPSize, Inst};
+
+/// Supplies the annotations of a listing.
+pub trait Annotate {
+    /// Lines printed above the instruction at addr.
+    fn header(&self, _addr: u64, _inst: &Inst) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Comment printed after the instruction at addr.
+    fn comment(&self, _addr: u64, _inst: &Inst) -> Option<String> {
+        None
+    }
+}
+
+const ELEM: [&str; 4] = ["b", "h", "s", "d"];
+
+fn elem_name(elem: u8) -> &'static str {
+    if elem == FPSize::FSZ_Q { "q" } else { ELEM[(elem & 0b11) as usize] }
+}
+
+fn elem_suffix(elem: Option<u8>) -> String {
+    elem.map_or(String::new(), |e| format!(".{}", elem_name(e)))
+}
+
+/// The tiles of a ZERO mask, the widest first: {za}, {za0.h}, {za1.s, za0.d}.
+fn za_tiles_text(mask: u8) -> String {
+    if mask == 0xFF {
+        return "{za}".to_string();
+    }
+    let mut left = mask;
+    let mut tiles = Vec::new();
+    // A tile of esize bytes covers the 64-bit tiles n, n + 8 / esize, ...
+    for (elem, step, count) in [("h", 2u32, 2), ("s", 4, 4), ("d", 8, 8)] {
+        for n in 0..count {
+            let cover = (0..8 / step).fold(0u8, |m, i| m | 1 << (n + i * count));
+            if left & cover == cover {
+                tiles.push(format!("za{}.{}", n, elem));
+                left &= !cover;
+            }
+        }
+    }
+    format!("{{{}}}", tiles.join(", "))
+}
+
+fn arrangement(va: u8) -> String {
+    let elem = va >> 1;
+    let bytes = if va & 1 == 1 { 16 } else { 8 };
+    format!("{}{}", bytes / FPSize::bytes(elem), ELEM[(elem & 0b11) as usize])
+}
+
+pub fn reg_text(reg: Reg) -> String {
+    match reg {
+        Reg::Gpr { n, w32 } => format!("{}{}", if w32 { "w" } else { "x" }, n),
+        Reg::Sp { w32 } => if w32 { "wsp" } else { "sp" }.to_string(),
+        Reg::Zr { w32 } => if w32 { "wzr" } else { "xzr" }.to_string(),
+        Reg::Fp { n, prec } => format!("{}{}", if prec == FPSize::FSZ_Q { "q" } else { ELEM[(prec & 0b11) as usize] }, n),
+        Reg::Vec { n, va } => format!("v{}.{}", n, arrangement(va)),
+        Reg::Z { n, elem } => format!("z{}{}", n, elem_suffix(elem)),
+        Reg::P { n, elem } => format!("p{}{}", n, elem_suffix(elem)),
+        Reg::Ffr => "ffr".to_string(),
+    }
+}
+
+/// What follows the index of a MemIndex: ", lsl #3", ", sxtw", ", uxtw #2"
+/// or nothing.
+pub fn index_modifier(extend: Option<u8>, shift: u8) -> String {
+    let amount = if shift == 0 { String::new() } else { format!(" #{}", shift) };
+    match extend {
+        Some(e) => {
+            let sign = if e & 0b100 != 0 { 's' } else { 'u' };
+            format!(", {}xt{}{}", sign, ["b", "h", "w", "x"][(e & 0b11) as usize], amount)
+        }
+        None if shift == 0 => String::new(),
+        None => format!(", lsl{}", amount),
+    }
+}
+
+fn imm_text(v: i64) -> String {
+    if (-9..=9).contains(&v) { format!("#{}", v) } else if v < 0 { format!("#-{:#x}", v.unsigned_abs()) } else { format!("#{:#x}", v) }
+}
+
+/// An operand in assembly syntax; labels print as absolute addresses.
+pub fn operand_text(operand: Operand, pc: u64) -> String {
+    match operand {
+        Operand::Reg(reg) => reg_text(reg),
+        Operand::Governing { n, mode } => {
+            let mode = match mode {
+                Some(Predication::Merging) => "/m",
+                Some(Predication::Zeroing) => "/z",
+                None => "",
+            };
+            format!("p{}{}", n, mode)
+        }
+        Operand::Imm(v) => imm_text(v as i64),
+        Operand::Mask(v) => format!("#{:#x}", v),
+        Operand::FImm(v) => format!("#{:?}", v),
+        Operand::Pattern(p) => pattern_name(p).unwrap_or_else(|| format!("#{}", p)),
+        Operand::Mul(imm) => format!("mul #{}", imm),
+        Operand::Rotation(degrees) => format!("#{}", degrees),
+        Operand::ZElem { n, elem, index } => format!("{}[{}]", reg_text(Reg::Z { n, elem: Some(elem) }), index),
+        Operand::Hint(imm) => hint_operand(imm).map_or_else(|| format!("#{}", imm), str::to_string),
+        Operand::Barrier(crm) => barrier_name(crm).map_or_else(|| format!("#{}", crm), str::to_string),
+        Operand::PState(field) => field.name().to_string(),
+        Operand::Shift { typ, amount } => {
+            format!("{} #{}", ["lsl", "lsr", "asr", "ror", "msl"].get(typ as usize).unwrap_or(&"lsl"), amount)
+        }
+        Operand::Label(offset) => format!("{:#x}", pc.wrapping_add(offset as u64)),
+        Operand::PageLabel(offset) => format!("{:#x}", (pc & !0xFFF).wrapping_add(offset as u64)),
+        Operand::Mem { base, offset, mode } => {
+            let base = reg_text(base);
+            match mode {
+                AddrMode::AM_SIMPLE => format!("[{}]", base),
+                AddrMode::AM_PRE => format!("[{}, {}]!", base, imm_text(offset)),
+                AddrMode::AM_POST => format!("[{}], {}", base, imm_text(offset)),
+                _ if offset == 0 => format!("[{}]", base),
+                _ => format!("[{}, {}]", base, imm_text(offset)),
+            }
+        }
+        Operand::MemPostReg { base, index } => format!("[{}], {}", reg_text(base), reg_text(index)),
+        Operand::List { first, len, va, lane } => {
+            // The single structure forms name the element size only, e.g. {v1.s, v2.s}[3].
+            let suffix = if lane.is_some() { ELEM[((va >> 1) & 0b11) as usize].to_string() } else { arrangement(va) };
+            let regs: Vec<String> = (0..len).map(|i| format!("v{}.{}", (first + i) % 32, suffix)).collect();
+            let lane = lane.map_or(String::new(), |l| format!("[{}]", l));
+            format!("{{{}}}{}", regs.join(", "), lane)
+        }
+        Operand::ZList { first, len, elem } => {
+            let regs: Vec<String> = (0..len).map(|i| reg_text(Reg::Z { n: (first + i) % 32, elem: Some(elem) })).collect();
+            format!("{{{}}}", regs.join(", "))
+        }
+        Operand::MemVl { base, offset: 0 } => format!("[{}]", reg_text(base)),
+        Operand::MemVl { base, offset } => format!("[{}, {}, mul vl]", reg_text(base), imm_text(offset)),
+        Operand::MemIndex { base, index, extend, shift } => {
+            format!("[{}, {}{}]", reg_text(base), reg_text(index), index_modifier(extend, shift))
+        }
+        Operand::ZaSlice { tile, elem, vertical, index, offset, braced } => {
+            let slice = format!("za{}{}.{}[w{}, {}]", tile, if vertical { "v" } else { "h" }, elem_name(elem), index, offset);
+            if braced { format!("{{{}}}", slice) } else { slice }
+        }
+        Operand::ZaArray { index, offset } => format!("za[w{}, {}]", index, offset),
+        Operand::ZaTiles(mask) => za_tiles_text(mask),
+        Operand::ZaTile { tile, elem } => format!("za{}.{}", tile, elem_name(elem)),
+        Operand::SysReg(reg) => reg.name(),
+        Operand::SysOp { op1, crn, crm, op2 } => format!("#{}, c{}, c{}, #{}", op1, crn, crm, op2),
+        Operand::SysOperation(name) => name.to_string(),
+    }
+}
+
+/// The instruction at pc in assembly syntax.
+pub fn inst_text(inst: &Inst, pc: u64) -> String {
+    let ops: Vec<String> = operands(inst).into_iter().map(|o| operand_text(o, pc)).collect();
+    if ops.is_empty() { inst_mnemonic(inst) } else { format!("{} {}", inst_mnemonic(inst), ops.join(", ")) }
+}
+
+/// The listing of the whole program; regions are separated by an empty
+/// line, and the comments of several annotators joined by "; ".
+pub fn listing(program: &Program, annotators: &[&dyn Annotate]) -> String {
+    let mut out = String::new();
+    let mut prev: Option<u64> = None;
+    for (addr, inst) in program.iter() {
+        if prev.is_some_and(|p| p.wrapping_add(4) != addr) {
+            out.push('\n');
+        }
+        prev = Some(addr);
+        for line in annotators.iter().flat_map(|a| a.header(addr, inst)) {
+            out.push_str(&line);
+            out.push('\n');
+        }
+        let text = inst_text(inst, addr);
+        let comments: Vec<String> = annotators.iter().filter_map(|a| a.comment(addr, inst)).collect();
+        if comments.is_empty() {
+            out.push_str(&format!("{:>12x}:  {}\n", addr, text));
+        } else {
+            out.push_str(&format!("{:>12x}:  {:<32} // {}\n", addr, text, comments.join("; ")));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Entry;
+
+    impl Annotate for Entry {
+        fn comment(&self, addr: u64, _inst: &Inst) -> Option<String> {
+            (addr == 0x1000).then(|| "entry".to_string())
+        }
+    }
+
+    #[test]
+    fn prologue() {
+        let program = Program::from_words(&[
+            0xA9BF7BFD, // stp x29, x30, [sp, #-16]!
+            0xD65F03C0, // ret
+        ], 0x1000);
+        assert_eq!(listing(&program, &[&Entry]), format!(
+            "{:>12x}:  {:<32} // entry\n{:>12x}:  ret x30\n", 0x1000, "stp x29, x30, [sp, #-0x10]!", 0x1004
+        ));
+    }
+
+    #[test]
+    fn adrp_targets_are_pages() {
+        use crate::aarch64_reader::decode;
+        // As llvm-objdump prints them at these addresses.
+        assert_eq!(inst_text(&decode(0xF06C144A), 0x40100C), "adrp x10, 0xd868c000");
+        assert_eq!(inst_text(&decode(0x90000041), 0x401010), "adrp x1, 0x409000");
+        assert_eq!(inst_text(&decode(0x90000041), 0x401FFC), "adrp x1, 0x409000");
+        assert_eq!(inst_text(&decode(0x10000040), 0x401014), "adr x0, 0x40101c");
+    }
+
+    #[test]
+    fn immediates_and_aliases_reassemble() {
+        use crate::aarch64_reader::decode;
+        // llvm-mc -triple=aarch64 -show-encoding assembles each text back to
+        // its word; llvm-mc -disassemble prints the values in decimal.
+        let mut cases = vec![
+            (0x12BC1B17, "mov w23, #0x1f27ffff"), // movn w23, #0xe0d8, lsl #16
+            (0x12800000, "mov w0, #-1"),
+            (0x52B00000, "mov w0, #-0x80000000"),
+            (0x320003E0, "orr w0, wzr, #0x1"),
+            (0x32003FE0, "orr w0, wzr, #0xffff"),
+            (0x320F7BE0, "orr w0, wzr, #0xfffeffff"),
+            (0x320107E0, "mov w0, #0x80000001"),
+            (0xB200F3E0, "mov x0, #0x5555555555555555"),
+            (0xB2607FE0, "mov x0, #0xffffffff00000000"),
+            (0x920F7800, "and x0, x0, #0xfffefffffffeffff"),
+            (0xB20F7800, "orr x0, x0, #0xfffefffffffeffff"),
+            (0x121F7800, "and w0, w0, #0xfffffffe"),
+            (0xF240003F, "tst x1, #0x1"),
+            (0xF2800001, "movk x1, #0"),
+            (0x72800001, "movk w1, #0"),
+            (0xF2A24680, "movk x0, #0x1234, lsl #16"),
+        ];
+        if cfg!(feature = "simd") {
+            cases.extend([
+                (0xA43F6000, "ldff1b {z0.h}, p0/z, [x0]"),
+                (0xA4BF6000, "ldff1h {z0.h}, p0/z, [x0]"),
+                (0xA4A16000, "ldff1h {z0.h}, p0/z, [x0, x1, lsl #1]"),
+                (0x05C00000, "dupm z0.s, #0x1"),
+                (0x05C3FF00, "mov z0.d, #0x3fffffffffffffe"),
+                (0xC008003C, "zero {za2.d, za3.d, za4.d, za5.d}"),
+            ]);
+        }
+        for (word, text) in cases {
+            assert_eq!(inst_text(&decode(word), 0), text, "{:#010x}", word);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn structure_lists() {
+        use crate::aarch64_reader::decode;
+        // llvm-mc -triple=aarch64 -show-encoding
+        for (word, text) in [
+            (0x4D608417, "ld2 {v23.d, v24.d}[1], [x0]"),
+            (0x4DDF1C20, "ld1 {v0.b}[15], [x1], #1"),
+            (0x4D20B3E4, "st4 {v4.s, v5.s, v6.s, v7.s}[3], [sp]"),
+            (0x4DC3685F, "ld3 {v31.h, v0.h, v1.h}[5], [x2], x3"),
+            (0x4D40C802, "ld1r {v2.4s}, [x0]"),
+        ] {
+            assert_eq!(inst_text(&decode(word), 0), text, "{:#010x}", word);
+        }
+    }
+
+    #[test]
+    fn hints_and_barriers() {
+        use crate::aarch64_reader::decode;
+        // llvm-mc -triple=aarch64 -mattr=+v8.5a -disassemble
+        for (word, text) in [
+            (0xD503201F, "nop"),
+            (0xD503203F, "yield"),
+            (0xD503221F, "esb"),
+            (0xD503223F, "psb csync"),
+            (0xD503229F, "csdb"),
+            (0xD503233F, "paciasp"),
+            (0xD503241F, "bti"),
+            (0xD503245F, "bti c"),
+            (0xD50324DF, "bti jc"),
+            (0xD503251F, "hint #40"),
+            (0xD50321FF, "hint #15"),
+            (0xD5033BBF, "dmb ish"),
+            (0xD50339BF, "dmb ishld"),
+            (0xD50330BF, "dmb #0"),
+            (0xD5033F9F, "dsb sy"),
+            (0xD503379F, "dsb nsh"),
+            (0xD503309F, "ssbb"),
+            (0xD5033FDF, "isb"),
+            (0xD5033ADF, "isb #10"),
+            (0xD5033F5F, "clrex"),
+            (0xD503305F, "clrex #0"),
+        ] {
+            assert_eq!(inst_text(&decode(word), 0), text, "{:#010x}", word);
+        }
+    }
+}
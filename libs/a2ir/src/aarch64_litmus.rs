@@ -0,0 +1,309 @@
+//! Litmus tests: the outcomes the ordering rules allow for a few threads of
+//! loads, stores and barriers, explored exhaustively or by random runs.
+//!
+//! Memory is a single copy that all threads see (the Armv8 model is other-
+//! multicopy atomic), and each thread performs its instructions in any order
+//! that keeps
+//!
+//! - register dependencies (including address and data dependencies, and
+//!   without renaming: reuse of a register orders too),
+//! - accesses to the same location,
+//! - everything after an acquire (LDAR, LDAPR) and before a release (STLR),
+//!   and a release before a later LDAR (but not LDAPR),
+//! - the accesses a DMB or DSB orders: all for the full barriers, loads
+//!   before the barrier for the LD forms, stores either side for the ST forms.
+//!
+//! Loads don't read from a store of their own thread before it's visible to
+//! the others, so a few outcomes the architecture allows (forwarding from the
+//! store buffer) don't show. The supported instructions are MOV (wide
+//! immediate), ADD and SUB (immediate), LDR and STR with an immediate offset
+//! or none (including the ordered forms and LDAPR), DMB, DSB and the hints.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::aarch64_defuse::{def_use, Loc};
+use crate::aarch64_gen::Rng;
+use crate::aarch64_reader::FlagMasks::W32;
+use crate::aarch64_reader::{decode, fad_get_addrmode, fad_get_mem_extend, AddrMode, Inst, MemOrdering, Op};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Thread {
+    pub code: Vec<u32>,
+    /// Initial register values, e.g. the addresses of the locations; the
+    /// others start at zero.
+    pub regs: Vec<(u8, u64)>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Litmus {
+    pub threads: Vec<Thread>,
+    /// Initial memory, by address; other locations start at zero.
+    pub memory: Vec<(u64, u64)>,
+}
+
+/// Final state of one execution.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Outcome {
+    /// Per thread, the final values of the registers its loads write.
+    pub regs: Vec<Vec<(u8, u64)>>,
+    pub memory: Vec<(u64, u64)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Load { acquire: bool, rcsc: bool },
+    Store { release: bool },
+    /// DMB and DSB: (orders earlier loads, orders earlier stores, orders
+    /// later loads)
+    Barrier { loads: bool, stores: bool, later_loads: bool },
+    Other,
+}
+
+struct Prepared {
+    insts: Vec<Inst>,
+    kinds: Vec<Kind>,
+    uses: Vec<Vec<u8>>,
+    defs: Vec<Vec<u8>>,
+    observed: BTreeSet<u8>,
+}
+
+fn kind(inst: &Inst) -> Result<Kind, String> {
+    let mode = fad_get_addrmode(inst.flags);
+    let offset_ok = matches!(mode, AddrMode::AM_SIMPLE | AddrMode::AM_OFF_IMM) && inst.rn < 31;
+    let load = inst.ldst_order.load;
+    Ok(match inst.op {
+        Op::A64_LDR | Op::A64_LDAPR if offset_ok => Kind::Load {
+            acquire: load != MemOrdering::MO_NONE as u16,
+            rcsc: load == MemOrdering::MO_ACQUIRE as u16 || load == MemOrdering::MO_LO_ACQUIRE as u16,
+        },
+        Op::A64_STR if offset_ok => Kind::Store { release: inst.ldst_order.store != MemOrdering::MO_NONE as u16 },
+        Op::A64_DMB | Op::A64_DSB => match inst.imm & 0b11 {
+            0b01 => Kind::Barrier { loads: true, stores: false, later_loads: true },
+            0b10 => Kind::Barrier { loads: false, stores: true, later_loads: false },
+            _ => Kind::Barrier { loads: true, stores: true, later_loads: true },
+        },
+        Op::A64_MOV_IMM | Op::A64_ADD_IMM | Op::A64_SUB_IMM if inst.rd < 31 && inst.rn <= 31 => Kind::Other,
+        Op::A64_HINT | Op::A64_ISB => Kind::Other,
+        _ => return Err(format!("unsupported instruction {:?} ({:#010x})", inst.op, inst.raw)),
+    })
+}
+
+fn regs(locs: Vec<Loc>) -> Vec<u8> {
+    locs.into_iter().filter_map(|loc| if let Loc::X(r) = loc { Some(r) } else { None }).collect()
+}
+
+fn prepare(thread: &Thread) -> Result<Prepared, String> {
+    let insts: Vec<Inst> = thread.code.iter().map(|&w| decode(w)).collect();
+    let kinds = insts.iter().map(kind).collect::<Result<Vec<_>, _>>()?;
+    let (mut uses, mut defs, mut observed) = (Vec::new(), Vec::new(), BTreeSet::new());
+    for (inst, kind) in insts.iter().zip(&kinds) {
+        let du = def_use(inst);
+        uses.push(regs(du.uses));
+        let d = regs(du.defs);
+        if matches!(kind, Kind::Load { .. }) {
+            observed.extend(d.iter().copied());
+        }
+        defs.push(d);
+    }
+    Ok(Prepared { insts, kinds, uses, defs, observed })
+}
+
+/// Whether the order of a before b (in program order) is kept regardless of
+/// registers and addresses.
+fn ordered(a: Kind, b: Kind) -> bool {
+    use Kind::*;
+    match (a, b) {
+        (Other, _) | (_, Other) => false,
+        (Load { .. }, Barrier { loads, .. }) => loads,
+        (Store { .. }, Barrier { stores, .. }) => stores,
+        (Barrier { .. }, Barrier { .. }) => true,
+        (Barrier { later_loads, .. }, Load { .. }) => later_loads,
+        (Barrier { .. }, Store { .. }) => true,
+        (Load { acquire, .. }, _) if acquire => true,
+        (_, Store { release }) if release => true,
+        (Store { release: true }, Load { rcsc: true, .. }) => true,
+        _ => false,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct State {
+    done: Vec<Vec<bool>>,
+    regs: Vec<[u64; 31]>,
+    memory: BTreeMap<u64, u64>,
+}
+
+fn get(regs: &[u64; 31], r: u8) -> u64 {
+    if r < 31 { regs[r as usize] } else { 0 }
+}
+
+fn address(inst: &Inst, regs: &[u64; 31]) -> u64 {
+    let offset = if fad_get_addrmode(inst.flags) == AddrMode::AM_OFF_IMM { inst.offset } else { 0 };
+    get(regs, inst.rn).wrapping_add(offset as u64)
+}
+
+struct Explorer {
+    threads: Vec<Prepared>,
+}
+
+impl Explorer {
+    /// Whether instruction i of thread t can be performed next.
+    fn ready(&self, state: &State, t: usize, i: usize) -> bool {
+        let p = &self.threads[t];
+        let done = &state.done[t];
+        if done[i] {
+            return false;
+        }
+        let is_access = |k: Kind| matches!(k, Kind::Load { .. } | Kind::Store { .. });
+        let conflict = |j: usize| {
+            p.defs[j].iter().any(|r| p.uses[i].contains(r) || p.defs[i].contains(r))
+                || p.uses[j].iter().any(|r| p.defs[i].contains(r))
+        };
+        // Register dependencies first: then i's address is known.
+        if (0..i).any(|j| !done[j] && (conflict(j) || ordered(p.kinds[j], p.kinds[i]))) {
+            return false;
+        }
+        if !is_access(p.kinds[i]) {
+            return true;
+        }
+        let addr = address(&p.insts[i], &state.regs[t]);
+        (0..i).all(|j| {
+            done[j]
+                || !is_access(p.kinds[j])
+                || (p.uses[j].iter().all(|r| (0..j).all(|k| done[k] || !p.defs[k].contains(r)))
+                    && address(&p.insts[j], &state.regs[t]) != addr)
+        })
+    }
+
+    fn step(&self, state: &State, t: usize, i: usize) -> State {
+        let mut next = state.clone();
+        next.done[t][i] = true;
+        let inst = &self.threads[t].insts[i];
+        let regs = &mut next.regs[t];
+        let bytes = 1u32 << (fad_get_mem_extend(inst.flags) & 0b11);
+        let mask = if bytes == 8 { u64::MAX } else { (1u64 << (8 * bytes)) - 1 };
+        let w32 = |v: u64| if inst.flags & W32 != 0 { v & 0xFFFF_FFFF } else { v };
+        match self.threads[t].kinds[i] {
+            Kind::Load { .. } if inst.rd < 31 => {
+                let value = next.memory.get(&address(inst, regs)).copied().unwrap_or(0);
+                regs[inst.rd as usize] = value & mask;
+            }
+            Kind::Store { .. } => {
+                let value = get(regs, inst.rd) & mask;
+                next.memory.insert(address(inst, regs), value);
+            }
+            Kind::Other => match inst.op {
+                Op::A64_MOV_IMM => regs[inst.rd as usize] = w32(inst.imm),
+                Op::A64_ADD_IMM => regs[inst.rd as usize] = w32(get(regs, inst.rn).wrapping_add(inst.imm)),
+                Op::A64_SUB_IMM => regs[inst.rd as usize] = w32(get(regs, inst.rn).wrapping_sub(inst.imm)),
+                _ => {}
+            },
+            _ => {}
+        }
+        next
+    }
+
+    fn moves(&self, state: &State) -> Vec<(usize, usize)> {
+        let mut out = Vec::new();
+        for (t, p) in self.threads.iter().enumerate() {
+            out.extend((0..p.insts.len()).filter(|&i| self.ready(state, t, i)).map(|i| (t, i)));
+        }
+        out
+    }
+
+    fn outcome(&self, state: &State) -> Outcome {
+        let regs = self
+            .threads
+            .iter()
+            .zip(&state.regs)
+            .map(|(p, regs)| p.observed.iter().map(|&r| (r, regs[r as usize])).collect())
+            .collect();
+        Outcome { regs, memory: state.memory.iter().map(|(&a, &v)| (a, v)).collect() }
+    }
+}
+
+fn start(test: &Litmus) -> Result<(Explorer, State), String> {
+    let threads = test.threads.iter().map(prepare).collect::<Result<Vec<_>, _>>()?;
+    let mut state = State {
+        done: threads.iter().map(|p| vec![false; p.insts.len()]).collect(),
+        regs: vec![[0; 31]; threads.len()],
+        memory: test.memory.iter().copied().collect(),
+    };
+    for (t, thread) in test.threads.iter().enumerate() {
+        for &(r, value) in &thread.regs {
+            if r < 31 {
+                state.regs[t][r as usize] = value;
+            }
+        }
+    }
+    Ok((Explorer { threads }, state))
+}
+
+/// All the outcomes of the test.
+pub fn explore(test: &Litmus) -> Result<BTreeSet<Outcome>, String> {
+    let (explorer, init) = start(test)?;
+    let mut out = BTreeSet::new();
+    let mut seen = HashSet::new();
+    let mut worklist = vec![init];
+    while let Some(state) = worklist.pop() {
+        if !seen.insert(state.clone()) {
+            continue;
+        }
+        let moves = explorer.moves(&state);
+        if moves.is_empty() {
+            out.insert(explorer.outcome(&state));
+        }
+        worklist.extend(moves.into_iter().map(|(t, i)| explorer.step(&state, t, i)));
+    }
+    Ok(out)
+}
+
+/// The outcomes of runs random executions, with how often each happened.
+pub fn explore_random(test: &Litmus, rng: &mut Rng, runs: usize) -> Result<BTreeMap<Outcome, usize>, String> {
+    let (explorer, init) = start(test)?;
+    let mut out = BTreeMap::new();
+    for _ in 0..runs {
+        let mut state = init.clone();
+        loop {
+            let moves = explorer.moves(&state);
+            if moves.is_empty() {
+                break;
+            }
+            let (t, i) = moves[rng.next_u32() as usize % moves.len()];
+            state = explorer.step(&state, t, i);
+        }
+        *out.entry(explorer.outcome(&state)).or_insert(0) += 1;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The load of (r0, r2) = (1, 0) in message passing.
+    fn stale_read(outcomes: &BTreeSet<Outcome>) -> bool {
+        outcomes.iter().any(|o| o.regs[1] == [(0, 1), (2, 0)])
+    }
+
+    #[test]
+    fn message_passing() {
+        // P0: str x2, [x0]; str x2, [x1]    P1: ldr x0, [x1]; ldr x2, [x3]
+        let mp = |p0: Vec<u32>, p1: Vec<u32>| Litmus {
+            threads: vec![
+                Thread { code: p0, regs: vec![(0, 0x100), (1, 0x200), (2, 1)] },
+                Thread { code: p1, regs: vec![(1, 0x200), (3, 0x100)] },
+            ],
+            memory: vec![],
+        };
+        let relaxed = explore(&mp(vec![0xF9000002, 0xF9000022], vec![0xF9400020, 0xF9400062])).unwrap();
+        assert!(stale_read(&relaxed));
+        // stlr x2, [x1] and ldar x0, [x1]
+        let ordered = explore(&mp(vec![0xF9000002, 0xC89FFC22], vec![0xC8DFFC20, 0xF9400062])).unwrap();
+        assert!(!stale_read(&ordered));
+        assert_eq!(ordered.len(), 3);
+        // dmb ishst and dmb ishld
+        let fenced = explore(&mp(vec![0xF9000002, 0xD5033ABF, 0xF9000022], vec![0xF9400020, 0xD50339BF, 0xF9400062]));
+        assert!(!stale_read(&fenced.unwrap()));
+    }
+}
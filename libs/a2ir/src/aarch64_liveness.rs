@@ -0,0 +1,146 @@
+//! Global liveness of registers over a CFG, and the register pressure it
+//! implies at every instruction.
+//!
+//! The crate has no lifted IR, so this runs on the decoded instructions and
+//! their `def_use` sets rather than on SSA values. Calls follow AAPCS64:
+//! they read the argument registers and clobber the caller-saved ones;
+//! returns, tail calls and unknown indirect branches read the result and
+//! argument registers.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::aarch64_abi::{with_role, Role};
+use crate::aarch64_branch::BranchKind;
+use crate::aarch64_cfg::{Cfg, Terminator};
+use crate::aarch64_defuse::{def_use, Loc};
+use crate::aarch64_program::Program;
+use crate::aarch64_reader::Inst;
+
+/// What the instruction reads and writes, calls included.
+pub(crate) fn effect(inst: &Inst) -> (BTreeSet<Loc>, BTreeSet<Loc>) {
+    let du = def_use(inst);
+    let (mut defs, mut uses): (BTreeSet<Loc>, BTreeSet<Loc>) = (du.defs.into_iter().collect(), du.uses.into_iter().collect());
+    if inst.branch_kind().is_some_and(BranchKind::is_call) {
+        uses.extend(with_role(Role::Argument));
+        defs.extend(with_role(Role::CallerSaved));
+    }
+    (defs, uses)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Pressure {
+    /// Live X registers (SP excluded).
+    pub gpr: usize,
+    /// Live V and Z registers.
+    pub simd: usize,
+}
+
+impl Pressure {
+    fn of(live: &BTreeSet<Loc>) -> Pressure {
+        let gpr = live.iter().filter(|l| matches!(l, Loc::X(_))).count();
+        let simd = live.iter().filter(|l| matches!(l, Loc::V(_) | Loc::Z(_))).count();
+        Pressure { gpr, simd }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Liveness {
+    /// The registers live at the start and end of each block, by start.
+    pub live_in: BTreeMap<u64, BTreeSet<Loc>>,
+    pub live_out: BTreeMap<u64, BTreeSet<Loc>>,
+}
+
+impl Liveness {
+    pub fn new(program: &Program, cfg: &Cfg) -> Liveness {
+        let mut summaries = BTreeMap::new();
+        for (&start, block) in &cfg.blocks {
+            let (mut gen, mut kill) = (BTreeSet::new(), BTreeSet::new());
+            for (_, inst) in program.range(start..block.end).rev() {
+                let (defs, uses) = effect(inst);
+                for d in defs {
+                    gen.remove(&d);
+                    kill.insert(d);
+                }
+                gen.extend(uses);
+            }
+            let exit: BTreeSet<Loc> = match block.terminator {
+                Terminator::Return | Terminator::TailCall(_) | Terminator::Indirect => with_role(Role::Result).into_iter().collect(),
+                _ => BTreeSet::new(),
+            };
+            let mut successors = cfg.successors(start);
+            successors.extend(cfg.exception_edges.range(start..block.end).map(|(_, pad)| *pad));
+            summaries.insert(start, (gen, kill, exit, successors));
+        }
+
+        let mut out = Liveness::default();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (&start, (gen, kill, exit, successors)) in summaries.iter().rev() {
+                let mut live_out = exit.clone();
+                for s in successors {
+                    live_out.extend(out.live_in.get(s).into_iter().flatten().copied());
+                }
+                let mut live_in: BTreeSet<Loc> = live_out.difference(kill).copied().collect();
+                live_in.extend(gen.iter().copied());
+                if out.live_in.get(&start) != Some(&live_in) {
+                    out.live_in.insert(start, live_in);
+                    changed = true;
+                }
+                out.live_out.insert(start, live_out);
+            }
+        }
+        out
+    }
+
+    /// The registers live before each instruction of the block at start.
+    pub fn live_before(&self, program: &Program, cfg: &Cfg, start: u64) -> BTreeMap<u64, BTreeSet<Loc>> {
+        let mut out = BTreeMap::new();
+        let (Some(block), Some(mut live)) = (cfg.blocks.get(&start), self.live_out.get(&start).cloned()) else {
+            return out;
+        };
+        for (pc, inst) in program.range(start..block.end).rev() {
+            let (defs, uses) = effect(inst);
+            for d in &defs {
+                live.remove(d);
+            }
+            live.extend(uses);
+            out.insert(pc, live.clone());
+        }
+        out
+    }
+
+    /// The register pressure before every instruction of the CFG.
+    pub fn pressure(&self, program: &Program, cfg: &Cfg) -> BTreeMap<u64, Pressure> {
+        let mut out = BTreeMap::new();
+        for &start in cfg.blocks.keys() {
+            out.extend(self.live_before(program, cfg, start).iter().map(|(&pc, live)| (pc, Pressure::of(live))));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loop_carried() {
+        let program = Program::from_words(&[
+            0xD2800041, // mov x1, #2
+            0x91000400, // add x0, x0, #1
+            0xD1000421, // sub x1, x1, #1
+            0xB5FFFFC1, // cbnz x1, .-8
+            0xD65F03C0, // ret
+        ], 0x1000);
+        let cfg = Cfg::build(&program);
+        let liveness = Liveness::new(&program, &cfg);
+        // x1 is carried around the loop; x30 and the result registers reach the ret.
+        assert!(liveness.live_in[&0x1004].contains(&Loc::X(1)));
+        assert!(liveness.live_in[&0x1000].contains(&Loc::X(30)));
+        assert!(!liveness.live_in[&0x1000].contains(&Loc::X(1)));
+        let pressure = liveness.pressure(&program, &cfg);
+        assert_eq!(pressure[&0x1010], Pressure { gpr: 9, simd: 8 });
+        assert_eq!(pressure[&0x1000].gpr, 8);
+    }
+}
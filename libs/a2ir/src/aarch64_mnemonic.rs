@@ -0,0 +1,1957 @@
+//! Mnemonics of opcodes.
+//!
+//! Several opcodes stand for a family of instructions whose mnemonics differ
+//! by what Inst.flags select (condition, signedness, rounding, access size).
+//! Their canonical mnemonic is the one the family shares, e.g. "b.cond" for
+//! A64_BCOND or "abd" for SABD and UABD; `inst_mnemonic` resolves the member
+//! for the instructions the decoder produces. Reverse lookups accept both.
+
+use crate::aarch64_defuse::is_sve_memory;
+use crate::aarch64_pac::{PacHint, PacKey, PacModifier};
+use crate::aarch64_reader::FlagMasks::{SET_FLAGS, SIMD_ROUND, SIMD_SIGNED, W32};
+use crate::aarch64_reader::Op::{self, *};
+use crate::aarch64_reader::{fad_get_addrmode, fad_get_cond, fad_get_mem_extend, AddrMode, Cond, FPRounding, FPSize, Inst,
+    MemOrdering, PStateField, Registries};
+
+/// Every opcode, in declaration order.
+pub const ALL_OPS: &[Op] = &[
+    A64_UNKNOWN,
+    A64_ERROR,
+    A64_UDF,
+    A64_ADR,
+    A64_ADRP,
+    A64_ADD_IMM,
+    A64_CMN_IMM,
+    A64_MOV_SP,
+    A64_SUB_IMM,
+    A64_CMP_IMM,
+    A64_AND_IMM,
+    A64_ORR_IMM,
+    A64_EOR_IMM,
+    A64_TST_IMM,
+    A64_MOVK,
+    A64_MOV_IMM,
+    A64_SBFM,
+    A64_ASR_IMM,
+    A64_SBFIZ,
+    A64_SBFX,
+    A64_BFM,
+    A64_BFC,
+    A64_BFI,
+    A64_BFXIL,
+    A64_UBFM,
+    A64_LSL_IMM,
+    A64_LSR_IMM,
+    A64_UBFIZ,
+    A64_UBFX,
+    A64_EXTEND,
+    A64_EXTR,
+    A64_ROR_IMM,
+    A64_BCOND,
+    A64_SVC,
+    A64_HVC,
+    A64_SMC,
+    A64_BRK,
+    A64_HLT,
+    A64_DCPS1,
+    A64_DCPS2,
+    A64_DCPS3,
+    A64_HINT,
+    A64_CLREX,
+    A64_DMB,
+    A64_ISB,
+    A64_SB,
+    A64_DSB,
+    A64_SSBB,
+    A64_PSSBB,
+    A64_MSR_IMM,
+    A64_CFINV,
+    A64_XAFlag,
+    A64_AXFlag,
+    A64_SYS,
+    A64_SYSL,
+    A64_MSR_REG,
+    A64_MRS,
+    A64_BR,
+    A64_BLR,
+    A64_RET,
+    A64_BRA,
+    A64_BLRA,
+    A64_RETA,
+    A64_ERET,
+    A64_ERETA,
+    A64_DRPS,
+    A64_B,
+    A64_BL,
+    A64_CBZ,
+    A64_CBNZ,
+    A64_TBZ,
+    A64_TBNZ,
+    A64_UDIV,
+    A64_SDIV,
+    A64_LSLV,
+    A64_LSRV,
+    A64_ASRV,
+    A64_RORV,
+    A64_CRC32B,
+    A64_CRC32H,
+    A64_CRC32W,
+    A64_CRC32X,
+    A64_CRC32CB,
+    A64_CRC32CH,
+    A64_CRC32CW,
+    A64_CRC32CX,
+    A64_SUBP,
+    A64_RBIT,
+    A64_REV16,
+    A64_REV,
+    A64_REV32,
+    A64_CLZ,
+    A64_CLS,
+    A64_AND_SHIFTED,
+    A64_TST_SHIFTED,
+    A64_BIC,
+    A64_ORR_SHIFTED,
+    A64_MOV_REG,
+    A64_ORN,
+    A64_MVN,
+    A64_EOR_SHIFTED,
+    A64_EON,
+    A64_ADD_SHIFTED,
+    A64_CMN_SHIFTED,
+    A64_SUB_SHIFTED,
+    A64_NEG,
+    A64_CMP_SHIFTED,
+    A64_ADD_EXT,
+    A64_CMN_EXT,
+    A64_SUB_EXT,
+    A64_CMP_EXT,
+    A64_ADC,
+    A64_SBC,
+    A64_NGC,
+    A64_RMIF,
+    A64_SETF8,
+    A64_SETF16,
+    A64_CCMN_REG,
+    A64_CCMP_REG,
+    A64_CCMN_IMM,
+    A64_CCMP_IMM,
+    A64_CSEL,
+    A64_CSINC,
+    A64_CINC,
+    A64_CSET,
+    A64_CSINV,
+    A64_CINV,
+    A64_CSETM,
+    A64_CSNEG,
+    A64_CNEG,
+    A64_MADD,
+    A64_MUL,
+    A64_MSUB,
+    A64_MNEG,
+    A64_SMADDL,
+    A64_SMULL,
+    A64_SMSUBL,
+    A64_SMNEGL,
+    A64_SMULH,
+    A64_UMADDL,
+    A64_UMULL,
+    A64_UMSUBL,
+    A64_UMNEGL,
+    A64_UMULH,
+    A64_LD1_MULT,
+    A64_ST1_MULT,
+    A64_LD2_MULT,
+    A64_ST2_MULT,
+    A64_LD3_MULT,
+    A64_ST3_MULT,
+    A64_LD4_MULT,
+    A64_ST4_MULT,
+    A64_LD1_SINGLE,
+    A64_ST1_SINGLE,
+    A64_LD2_SINGLE,
+    A64_ST2_SINGLE,
+    A64_LD3_SINGLE,
+    A64_ST3_SINGLE,
+    A64_LD4_SINGLE,
+    A64_ST4_SINGLE,
+    A64_LD1R,
+    A64_LD2R,
+    A64_LD3R,
+    A64_LD4R,
+    A64_LDXR,
+    A64_STXR,
+    A64_LDXP,
+    A64_STXP,
+    A64_LDAPR,
+    A64_LDNP,
+    A64_STNP,
+    A64_LDNP_FP,
+    A64_STNP_FP,
+    A64_LDP,
+    A64_STP,
+    A64_LDP_FP,
+    A64_STP_FP,
+    A64_LDR,
+    A64_STR,
+    A64_LDR_FP,
+    A64_STR_FP,
+    A64_PRFM,
+    A64_LDADD,
+    A64_LDCLR,
+    A64_LDEOR,
+    A64_LDSET,
+    A64_LDSMAX,
+    A64_LDSMIN,
+    A64_LDUMAX,
+    A64_LDUMIN,
+    A64_SWP,
+    A64_CAS,
+    A64_CASP,
+    A64_FCVT_GPR,
+    A64_FCVT_VEC,
+    A64_CVTF,
+    A64_CVTF_VEC,
+    A64_FJCVTZS,
+    A64_FRINT,
+    A64_FRINT_VEC,
+    A64_FRINTX,
+    A64_FRINTX_VEC,
+    A64_FCVT_H,
+    A64_FCVT_S,
+    A64_FCVT_D,
+    A64_FCVTL,
+    A64_FCVTN,
+    A64_FCVTXN,
+    A64_FABS,
+    A64_FNEG,
+    A64_FSQRT,
+    A64_FMUL,
+    A64_FMULX,
+    A64_FDIV,
+    A64_FADD,
+    A64_FSUB,
+    A64_FMAX,
+    A64_FMAXNM,
+    A64_FMIN,
+    A64_FMINNM,
+    A64_FRECPE,
+    A64_FRECPS,
+    A64_FRECPX,
+    A64_FRSQRTE,
+    A64_FRSQRTS,
+    A64_FNMUL,
+    A64_FMADD,
+    A64_FMSUB,
+    A64_FNMADD,
+    A64_FNMSUB,
+    A64_FCMP_REG,
+    A64_FCMP_ZERO,
+    A64_FCMPE_REG,
+    A64_FCMPE_ZERO,
+    A64_FCCMP,
+    A64_FCCMPE,
+    A64_FCSEL,
+    A64_FMOV_VEC2GPR,
+    A64_FMOV_GPR2VEC,
+    A64_FMOV_TOP2GPR,
+    A64_FMOV_GPR2TOP,
+    A64_FMOV_REG,
+    A64_FMOV_IMM,
+    A64_FMOV_VEC,
+    A64_FCMEQ_REG,
+    A64_FCMEQ_ZERO,
+    A64_FCMGE_REG,
+    A64_FCMGE_ZERO,
+    A64_FCMGT_REG,
+    A64_FCMGT_ZERO,
+    A64_FCMLE_ZERO,
+    A64_FCMLT_ZERO,
+    A64_FACGE,
+    A64_FACGT,
+    A64_FABS_VEC,
+    A64_FABD_VEC,
+    A64_FNEG_VEC,
+    A64_FSQRT_VEC,
+    A64_FMUL_ELEM,
+    A64_FMUL_VEC,
+    A64_FMULX_ELEM,
+    A64_FMULX_VEC,
+    A64_FDIV_VEC,
+    A64_FADD_VEC,
+    A64_FCADD,
+    A64_FSUB_VEC,
+    A64_FMAX_VEC,
+    A64_FMAXNM_VEC,
+    A64_FMIN_VEC,
+    A64_FMINNM_VEC,
+    A64_FRECPE_VEC,
+    A64_FRECPS_VEC,
+    A64_FRSQRTE_VEC,
+    A64_FRSQRTS_VEC,
+    A64_FMLA_ELEM,
+    A64_FMLA_VEC,
+    A64_FMLAL_ELEM,
+    A64_FMLAL_VEC,
+    A64_FMLAL2_ELEM,
+    A64_FMLAL2_VEC,
+    A64_FCMLA_ELEM,
+    A64_FCMLA_VEC,
+    A64_FMLS_ELEM,
+    A64_FMLS_VEC,
+    A64_FMLSL_ELEM,
+    A64_FMLSL_VEC,
+    A64_FMLSL2_ELEM,
+    A64_FMLSL2_VEC,
+    A64_FADDP,
+    A64_FADDP_VEC,
+    A64_FMAXP,
+    A64_FMAXP_VEC,
+    A64_FMAXV,
+    A64_FMAXNMP,
+    A64_FMAXNMP_VEC,
+    A64_FMAXNMV,
+    A64_FMINP,
+    A64_FMINP_VEC,
+    A64_FMINV,
+    A64_FMINNMP,
+    A64_FMINNMP_VEC,
+    A64_FMINNMV,
+    A64_AND_VEC,
+    A64_BCAX,
+    A64_BIC_VEC_IMM,
+    A64_BIC_VEC_REG,
+    A64_BIF,
+    A64_BIT,
+    A64_BSL,
+    A64_CLS_VEC,
+    A64_CLZ_VEC,
+    A64_CNT,
+    A64_EOR_VEC,
+    A64_EOR3,
+    A64_NOT_VEC,
+    A64_ORN_VEC,
+    A64_ORR_VEC_IMM,
+    A64_ORR_VEC_REG,
+    A64_MOV_VEC,
+    A64_RAX1,
+    A64_RBIT_VEC,
+    A64_REV16_VEC,
+    A64_REV32_VEC,
+    A64_REV64_VEC,
+    A64_SHL_IMM,
+    A64_SHL_REG,
+    A64_SHLL,
+    A64_SHR,
+    A64_SHRN,
+    A64_SRA,
+    A64_SLI,
+    A64_SRI,
+    A64_XAR,
+    A64_DUP_ELEM,
+    A64_DUP_GPR,
+    A64_EXT,
+    A64_INS_ELEM,
+    A64_INS_GPR,
+    A64_MOVI,
+    A64_SMOV,
+    A64_UMOV,
+    A64_TBL,
+    A64_TBX,
+    A64_TRN1,
+    A64_TRN2,
+    A64_UZP1,
+    A64_UZP2,
+    A64_XTN,
+    A64_ZIP1,
+    A64_ZIP2,
+    A64_CMEQ_REG,
+    A64_CMEQ_ZERO,
+    A64_CMGE_REG,
+    A64_CMGE_ZERO,
+    A64_CMGT_REG,
+    A64_CMGT_ZERO,
+    A64_CMHI_REG,
+    A64_CMHS_REG,
+    A64_CMLE_ZERO,
+    A64_CMLT_ZERO,
+    A64_CMTST,
+    A64_ABS_VEC,
+    A64_ABD,
+    A64_ABDL,
+    A64_ABA,
+    A64_ABAL,
+    A64_NEG_VEC,
+    A64_MUL_ELEM,
+    A64_MUL_VEC,
+    A64_MULL_ELEM,
+    A64_MULL_VEC,
+    A64_ADD_VEC,
+    A64_ADDHN,
+    A64_ADDL,
+    A64_ADDW,
+    A64_HADD,
+    A64_SUB_VEC,
+    A64_SUBHN,
+    A64_SUBL,
+    A64_SUBW,
+    A64_HSUB,
+    A64_MAX_VEC,
+    A64_MIN_VEC,
+    A64_DOT_ELEM,
+    A64_DOT_VEC,
+    A64_URECPE,
+    A64_URSQRTE,
+    A64_MLA_ELEM,
+    A64_MLA_VEC,
+    A64_MLS_ELEM,
+    A64_MLS_VEC,
+    A64_MLAL_ELEM,
+    A64_MLAL_VEC,
+    A64_MLSL_ELEM,
+    A64_MLSL_VEC,
+    A64_ADDP,
+    A64_ADDP_VEC,
+    A64_ADDV,
+    A64_ADALP,
+    A64_ADDLP,
+    A64_ADDLV,
+    A64_MAXP,
+    A64_MAXV,
+    A64_MINP,
+    A64_MINV,
+    A64_QADD,
+    A64_QABS,
+    A64_SUQADD,
+    A64_USQADD,
+    A64_QSHL_IMM,
+    A64_QSHL_REG,
+    A64_QSHRN,
+    A64_QSUB,
+    A64_QXTN,
+    A64_SQABS,
+    A64_SQADD,
+    A64_SQDMLAL_ELEM,
+    A64_SQDMLAL_VEC,
+    A64_SQDMLSL_ELEM,
+    A64_SQDMLSL_VEC,
+    A64_SQDMULH_ELEM,
+    A64_SQDMULH_VEC,
+    A64_SQDMULL_ELEM,
+    A64_SQDMULL_VEC,
+    A64_SQNEG,
+    A64_SQRDMLAH_ELEM,
+    A64_SQRDMLAH_VEC,
+    A64_SQRDMLSH_ELEM,
+    A64_SQRDMLSH_VEC,
+    A64_SQSHLU,
+    A64_SQSHRUN,
+    A64_SQXTUN,
+    A64_PMUL,
+    A64_PMULL,
+    A64_ADD_Z,
+    A64_SUB_Z,
+    A64_SUBR_Z,
+    A64_MAX_Z,
+    A64_MIN_Z,
+    A64_ABD_Z,
+    A64_MUL_Z,
+    A64_MULH_Z,
+    A64_DIV_Z,
+    A64_DIVR_Z,
+    A64_AND_Z,
+    A64_ORR_Z,
+    A64_EOR_Z,
+    A64_BIC_Z,
+    A64_QADD_Z,
+    A64_QSUB_Z,
+    A64_MLA_Z,
+    A64_MLS_Z,
+    A64_MAD_Z,
+    A64_MSB_Z,
+    A64_ASR_Z,
+    A64_LSR_Z,
+    A64_LSL_Z,
+    A64_ASRR_Z,
+    A64_LSRR_Z,
+    A64_LSLR_Z,
+    A64_ASR_WIDE_Z,
+    A64_LSR_WIDE_Z,
+    A64_LSL_WIDE_Z,
+    A64_ASR_IMM_Z,
+    A64_LSR_IMM_Z,
+    A64_LSL_IMM_Z,
+    A64_ASRD_Z,
+    A64_EXTEND_Z,
+    A64_ABS_Z,
+    A64_NEG_Z,
+    A64_CLS_Z,
+    A64_CLZ_Z,
+    A64_CNT_Z,
+    A64_CNOT_Z,
+    A64_NOT_Z,
+    A64_ADDV_Z,
+    A64_MAXV_Z,
+    A64_MINV_Z,
+    A64_ORV_Z,
+    A64_EORV_Z,
+    A64_ANDV_Z,
+    A64_CMP_Z,
+    A64_CMP_WIDE_Z,
+    A64_CMP_IMM_Z,
+    A64_INDEX_IMM_Z,
+    A64_INDEX_IMM_REG_Z,
+    A64_INDEX_REG_IMM_Z,
+    A64_INDEX_REG_Z,
+    A64_ADR_Z,
+    A64_ORR_IMM_Z,
+    A64_EOR_IMM_Z,
+    A64_AND_IMM_Z,
+    A64_DUPM_Z,
+    A64_ADD_IMM_Z,
+    A64_SUB_IMM_Z,
+    A64_SUBR_IMM_Z,
+    A64_QADD_IMM_Z,
+    A64_QSUB_IMM_Z,
+    A64_MAX_IMM_Z,
+    A64_MIN_IMM_Z,
+    A64_MUL_IMM_Z,
+    A64_DUP_IMM_Z,
+    A64_CPY_IMM_Z,
+    A64_DUP_REG_Z,
+    A64_CPY_REG_Z,
+    A64_CPY_FP_Z,
+    A64_DUP_ELEM_Z,
+    A64_SEL_Z,
+    A64_MOVPRFX_Z,
+    A64_CNT_ELEMS,
+    A64_INC_ELEMS,
+    A64_DEC_ELEMS,
+    A64_QINC_ELEMS,
+    A64_QDEC_ELEMS,
+    A64_INC_ELEMS_Z,
+    A64_DEC_ELEMS_Z,
+    A64_QINC_ELEMS_Z,
+    A64_QDEC_ELEMS_Z,
+    A64_ZIP1_Z,
+    A64_ZIP2_Z,
+    A64_UZP1_Z,
+    A64_UZP2_Z,
+    A64_TRN1_Z,
+    A64_TRN2_Z,
+    A64_REV_Z,
+    A64_EXT_Z,
+    A64_TBL_Z,
+    A64_LASTA_REG_Z,
+    A64_LASTB_REG_Z,
+    A64_LASTA_FP_Z,
+    A64_LASTB_FP_Z,
+    A64_CLASTA_Z,
+    A64_CLASTB_Z,
+    A64_CLASTA_REG_Z,
+    A64_CLASTB_REG_Z,
+    A64_CLASTA_FP_Z,
+    A64_CLASTB_FP_Z,
+    A64_DOT_Z,
+    A64_DOT_ELEM_Z,
+    A64_FADD_Z,
+    A64_FSUB_Z,
+    A64_FMUL_Z,
+    A64_FSUBR_Z,
+    A64_FMAXNM_Z,
+    A64_FMINNM_Z,
+    A64_FMAX_Z,
+    A64_FMIN_Z,
+    A64_FABD_Z,
+    A64_FSCALE_Z,
+    A64_FMULX_Z,
+    A64_FDIVR_Z,
+    A64_FDIV_Z,
+    A64_FTSMUL_Z,
+    A64_FRECPS_Z,
+    A64_FRSQRTS_Z,
+    A64_FMLA_Z,
+    A64_FMLS_Z,
+    A64_FNMLA_Z,
+    A64_FNMLS_Z,
+    A64_FMAD_Z,
+    A64_FMSB_Z,
+    A64_FNMAD_Z,
+    A64_FNMSB_Z,
+    A64_FABS_Z,
+    A64_FNEG_Z,
+    A64_FRINT_Z,
+    A64_FRINTX_Z,
+    A64_FRECPX_Z,
+    A64_FSQRT_Z,
+    A64_FCVT_Z,
+    A64_FCVTZ_Z,
+    A64_CVTF_Z,
+    A64_FADDV_Z,
+    A64_FMAXNMV_Z,
+    A64_FMINNMV_Z,
+    A64_FMAXV_Z,
+    A64_FMINV_Z,
+    A64_FADDA_Z,
+    A64_FCM_Z,
+    A64_FAC_Z,
+    A64_FCM_ZERO_Z,
+    A64_FADD_IMM_Z,
+    A64_FSUB_IMM_Z,
+    A64_FMUL_IMM_Z,
+    A64_FSUBR_IMM_Z,
+    A64_FMAXNM_IMM_Z,
+    A64_FMINNM_IMM_Z,
+    A64_FMAX_IMM_Z,
+    A64_FMIN_IMM_Z,
+    A64_FDUP_Z,
+    A64_FCPY_Z,
+    A64_FMLA_ELEM_Z,
+    A64_FMLS_ELEM_Z,
+    A64_FMUL_ELEM_Z,
+    A64_FCMLA_Z,
+    A64_FCMLA_ELEM_Z,
+    A64_FCADD_Z,
+    A64_LD1_Z,
+    A64_LDFF1_Z,
+    A64_LDNF1_Z,
+    A64_LDNT1_Z,
+    A64_LD2_Z,
+    A64_LD3_Z,
+    A64_LD4_Z,
+    A64_ST1_Z,
+    A64_STNT1_Z,
+    A64_ST2_Z,
+    A64_ST3_Z,
+    A64_ST4_Z,
+    A64_PTRUE,
+    A64_PFALSE,
+    A64_PFIRST,
+    A64_PNEXT,
+    A64_PTEST,
+    A64_BRKA,
+    A64_BRKB,
+    A64_WHILE,
+    A64_CNTP,
+    A64_RDFFR,
+    A64_SETFFR,
+    A64_WRFFR,
+    A64_AND_P,
+    A64_BIC_P,
+    A64_EOR_P,
+    A64_SEL_P,
+    A64_ORR_P,
+    A64_ORN_P,
+    A64_NOR_P,
+    A64_NAND_P,
+    A64_BRKN,
+    A64_BRKPA,
+    A64_BRKPB,
+    A64_INCP,
+    A64_DECP,
+    A64_QINCP,
+    A64_QDECP,
+    A64_INCP_Z,
+    A64_DECP_Z,
+    A64_QINCP_Z,
+    A64_QDECP_Z,
+    A64_ZIP1_P,
+    A64_ZIP2_P,
+    A64_UZP1_P,
+    A64_UZP2_P,
+    A64_TRN1_P,
+    A64_TRN2_P,
+    A64_REV_P,
+    A64_PUNPKLO,
+    A64_PUNPKHI,
+    A64_CTERMEQ,
+    A64_CTERMNE,
+    A64_PMUL_Z,
+    A64_SQDMULH_Z,
+    A64_SQRDMULH_Z,
+    A64_EOR3_Z,
+    A64_BCAX_Z,
+    A64_BSL_Z,
+    A64_BSL1N_Z,
+    A64_BSL2N_Z,
+    A64_NBSL_Z,
+    A64_CMLA_Z,
+    A64_CMLA_ELEM_Z,
+    A64_ADDLB_Z,
+    A64_ADDLT_Z,
+    A64_SUBLB_Z,
+    A64_SUBLT_Z,
+    A64_ABDLB_Z,
+    A64_ABDLT_Z,
+    A64_ADDWB_Z,
+    A64_ADDWT_Z,
+    A64_SUBWB_Z,
+    A64_SUBWT_Z,
+    A64_MULLB_Z,
+    A64_MULLT_Z,
+    A64_SQDMULLB_Z,
+    A64_SQDMULLT_Z,
+    A64_PMULLB_Z,
+    A64_PMULLT_Z,
+    A64_SHLLB_Z,
+    A64_SHLLT_Z,
+    A64_SHRNB_Z,
+    A64_SHRNT_Z,
+    A64_QSHRNB_Z,
+    A64_QSHRNT_Z,
+    A64_SQSHRUNB_Z,
+    A64_SQSHRUNT_Z,
+    A64_QXTNB_Z,
+    A64_QXTNT_Z,
+    A64_SQXTUNB_Z,
+    A64_SQXTUNT_Z,
+    A64_ADDHNB_Z,
+    A64_ADDHNT_Z,
+    A64_SUBHNB_Z,
+    A64_SUBHNT_Z,
+    A64_XAR_Z,
+    A64_BEXT_Z,
+    A64_BDEP_Z,
+    A64_BGRP_Z,
+    A64_HISTCNT_Z,
+    A64_HISTSEG_Z,
+    A64_MATCH_Z,
+    A64_NMATCH_Z,
+    A64_AESE_Z,
+    A64_AESD_Z,
+    A64_AESMC_Z,
+    A64_AESIMC_Z,
+    A64_SM4E_Z,
+    A64_SM4EKEY_Z,
+    A64_RAX1_Z,
+    A64_LDR_ZA,
+    A64_STR_ZA,
+    A64_LD1_ZA,
+    A64_ST1_ZA,
+    A64_ZERO_ZA,
+    A64_MOVA_Z,
+    A64_MOVA_ZA,
+    A64_FMOPA_ZA,
+    A64_FMOPS_ZA,
+    A64_BFMOPA_ZA,
+    A64_BFMOPS_ZA,
+    A64_SMOPA_ZA,
+    A64_SMOPS_ZA,
+    A64_UMOPA_ZA,
+    A64_UMOPS_ZA,
+    A64_SUMOPA_ZA,
+    A64_SUMOPS_ZA,
+    A64_USMOPA_ZA,
+    A64_USMOPS_ZA,
+];
+
+/// Mnemonics that aren't the canonical one of their opcode.
+const ALIASES: &[(&str, &[Op])] = &[
+    ("ldrb", &[A64_LDR]),
+    ("ldrh", &[A64_LDR]),
+    ("ldrsb", &[A64_LDR]),
+    ("ldrsh", &[A64_LDR]),
+    ("ldrsw", &[A64_LDR]),
+    ("ldur", &[A64_LDR, A64_LDR_FP]),
+    ("ldurb", &[A64_LDR]),
+    ("ldurh", &[A64_LDR]),
+    ("ldursb", &[A64_LDR]),
+    ("ldursh", &[A64_LDR]),
+    ("ldursw", &[A64_LDR]),
+    ("strb", &[A64_STR]),
+    ("strh", &[A64_STR]),
+    ("stur", &[A64_STR, A64_STR_FP]),
+    ("sturb", &[A64_STR]),
+    ("sturh", &[A64_STR]),
+    ("ldpsw", &[A64_LDP]),
+    ("ptrues", &[A64_PTRUE]),
+    ("brkas", &[A64_BRKA]),
+    ("brkbs", &[A64_BRKB]),
+    ("rdffrs", &[A64_RDFFR]),
+    ("ands", &[A64_AND_P]),
+    ("bics", &[A64_BIC_P]),
+    ("eors", &[A64_EOR_P]),
+    ("orrs", &[A64_ORR_P]),
+    ("orns", &[A64_ORN_P]),
+    ("nors", &[A64_NOR_P]),
+    ("nands", &[A64_NAND_P]),
+    ("movs", &[A64_AND_P, A64_ORR_P]),
+    ("not", &[A64_EOR_P]),
+    ("nots", &[A64_EOR_P]),
+    ("brkns", &[A64_BRKN]),
+    ("brkpas", &[A64_BRKPA]),
+    ("brkpbs", &[A64_BRKPB]),
+    ("sxtb", &[A64_EXTEND, A64_EXTEND_Z]),
+    ("sxth", &[A64_EXTEND, A64_EXTEND_Z]),
+    ("sxtw", &[A64_EXTEND, A64_EXTEND_Z]),
+    ("uxtb", &[A64_EXTEND, A64_EXTEND_Z]),
+    ("uxth", &[A64_EXTEND, A64_EXTEND_Z]),
+    ("uxtw", &[A64_EXTEND_Z]),
+    ("mov", &[A64_DUPM_Z, A64_DUP_IMM_Z, A64_CPY_IMM_Z, A64_DUP_REG_Z, A64_CPY_REG_Z, A64_CPY_FP_Z, A64_DUP_ELEM_Z, A64_SEL_Z,
+        A64_AND_P, A64_ORR_P, A64_SEL_P]),
+    ("fmov", &[A64_FDUP_Z, A64_FCPY_Z]),
+    ("scvtf", &[A64_CVTF, A64_CVTF_VEC]),
+    ("ucvtf", &[A64_CVTF, A64_CVTF_VEC]),
+    ("braa", &[A64_BRA]),
+    ("brab", &[A64_BRA]),
+    ("braaz", &[A64_BRA]),
+    ("brabz", &[A64_BRA]),
+    ("blraa", &[A64_BLRA]),
+    ("blrab", &[A64_BLRA]),
+    ("blraaz", &[A64_BLRA]),
+    ("blrabz", &[A64_BLRA]),
+    ("retaa", &[A64_RETA]),
+    ("retab", &[A64_RETA]),
+    ("eretaa", &[A64_ERETA]),
+    ("eretab", &[A64_ERETA]),
+    ("paciasp", &[A64_HINT]),
+    ("pacibsp", &[A64_HINT]),
+    ("autiasp", &[A64_HINT]),
+    ("autibsp", &[A64_HINT]),
+    ("paciaz", &[A64_HINT]),
+    ("pacibz", &[A64_HINT]),
+    ("autiaz", &[A64_HINT]),
+    ("autibz", &[A64_HINT]),
+    ("pacia1716", &[A64_HINT]),
+    ("pacib1716", &[A64_HINT]),
+    ("autia1716", &[A64_HINT]),
+    ("autib1716", &[A64_HINT]),
+    ("xpaclri", &[A64_HINT]),
+    ("ldar", &[A64_LDR]),
+    ("ldaxr", &[A64_LDXR]),
+    ("ldlar", &[A64_LDR]),
+    ("stlr", &[A64_STR]),
+    ("stlxr", &[A64_STXR]),
+    ("stllr", &[A64_STR]),
+    ("ldaxp", &[A64_LDXP]),
+    ("stlxp", &[A64_STXP]),
+    ("ldapur", &[A64_LDAPR]),
+    ("at", &[A64_SYS]),
+    ("dc", &[A64_SYS]),
+    ("ic", &[A64_SYS]),
+    ("tlbi", &[A64_SYS]),
+];
+
+/// The aliases of HINT #imm other than the pointer authentication ones
+/// (see `PacHint`), by imm; BTI and PSB take an operand (see
+/// `hint_operand`).
+const HINTS: &[(u64, &str)] = &[
+    (0, "nop"), (1, "yield"), (2, "wfe"), (3, "wfi"), (4, "sev"), (5, "sevl"), (6, "dgh"), (16, "esb"), (17, "psb"),
+    (20, "csdb"), (32, "bti"), (34, "bti"), (36, "bti"), (38, "bti"),
+];
+
+/// The alias of HINT #imm; None for the hints without one.
+pub fn hint_name(imm: u64) -> Option<&'static str> {
+    HINTS.iter().find(|(i, _)| *i == imm).map(|(_, name)| *name)
+}
+
+/// Opcodes whose members are prefixed by S or U (and possibly R for
+/// rounding), e.g. SABD, UABD, SRHADD.
+const SIGNED_FAMILIES: &[Op] = &[
+    A64_ABD, A64_ABDL, A64_ABA, A64_ABAL, A64_MULL_ELEM, A64_MULL_VEC, A64_ADDL, A64_ADDW, A64_HADD,
+    A64_SUBL, A64_SUBW, A64_HSUB, A64_MAX_VEC, A64_MIN_VEC, A64_DOT_ELEM, A64_DOT_VEC, A64_MLAL_ELEM,
+    A64_MLAL_VEC, A64_MLSL_ELEM, A64_MLSL_VEC, A64_ADALP, A64_ADDLP, A64_ADDLV, A64_MAXP, A64_MAXV,
+    A64_MINP, A64_MINV, A64_QADD, A64_QSHL_IMM, A64_QSHL_REG, A64_QSHRN, A64_QSUB, A64_QXTN, A64_SHLL,
+    A64_SHR, A64_SRA, A64_SHL_REG, A64_MAX_Z, A64_MIN_Z, A64_ABD_Z, A64_MULH_Z, A64_DIV_Z, A64_DIVR_Z, A64_QADD_Z,
+    A64_QSUB_Z, A64_ADDV_Z, A64_MAXV_Z, A64_MINV_Z, A64_CVTF_Z, A64_QADD_IMM_Z, A64_QSUB_IMM_Z, A64_MAX_IMM_Z,
+    A64_MIN_IMM_Z, A64_DOT_Z, A64_DOT_ELEM_Z, A64_QINCP, A64_QDECP, A64_QINCP_Z, A64_QDECP_Z, A64_ADDLB_Z, A64_ADDLT_Z,
+    A64_SUBLB_Z, A64_SUBLT_Z, A64_ABDLB_Z, A64_ABDLT_Z, A64_ADDWB_Z, A64_ADDWT_Z, A64_SUBWB_Z, A64_SUBWT_Z, A64_MULLB_Z,
+    A64_MULLT_Z, A64_SHLLB_Z, A64_SHLLT_Z, A64_QXTNB_Z, A64_QXTNT_Z,
+];
+
+/// SVE2's narrowing shifts and adds, whose rounding forms put the R after
+/// the Q of the saturating ones (SQRSHRNB, RADDHNB); QSHRNB is prefixed by
+/// S or U.
+const NARROWING: &[Op] = &[
+    A64_SHRNB_Z, A64_SHRNT_Z, A64_QSHRNB_Z, A64_QSHRNT_Z, A64_SQSHRUNB_Z, A64_SQSHRUNT_Z, A64_ADDHNB_Z, A64_ADDHNT_Z,
+    A64_SUBHNB_Z, A64_SUBHNT_Z,
+];
+
+/// The SVE element counts, whose mnemonics end in the element size (CNTB,
+/// CNTH, ...); the saturating ones are prefixed by S or U too.
+const ELEMENT_COUNTS: &[Op] = &[
+    A64_CNT_ELEMS, A64_INC_ELEMS, A64_DEC_ELEMS, A64_QINC_ELEMS, A64_QDEC_ELEMS, A64_INC_ELEMS_Z, A64_DEC_ELEMS_Z,
+    A64_QINC_ELEMS_Z, A64_QDEC_ELEMS_Z,
+];
+
+/// Atomic memory operations, whose members are suffixed by the ordering
+/// (A, AL, L) and the access size (B, H), e.g. LDADDALB.
+const ATOMICS: &[Op] = &[
+    A64_LDADD, A64_LDCLR, A64_LDEOR, A64_LDSET, A64_LDSMAX, A64_LDSMIN, A64_LDUMAX, A64_LDUMIN, A64_SWP, A64_CAS, A64_CASP,
+];
+
+impl Op {
+    /// The canonical, lower-case mnemonic of the opcode.
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            A64_UNKNOWN => "unknown",
+            A64_ERROR => "error",
+            A64_UDF => "udf",
+            A64_ADR => "adr",
+            A64_ADRP => "adrp",
+            A64_ADD_IMM => "add",
+            A64_CMN_IMM => "cmn",
+            A64_MOV_SP => "mov",
+            A64_SUB_IMM => "sub",
+            A64_CMP_IMM => "cmp",
+            A64_AND_IMM => "and",
+            A64_ORR_IMM => "orr",
+            A64_EOR_IMM => "eor",
+            A64_TST_IMM => "tst",
+            A64_MOVK => "movk",
+            A64_MOV_IMM => "mov",
+            A64_SBFM => "sbfm",
+            A64_ASR_IMM => "asr",
+            A64_SBFIZ => "sbfiz",
+            A64_SBFX => "sbfx",
+            A64_BFM => "bfm",
+            A64_BFC => "bfc",
+            A64_BFI => "bfi",
+            A64_BFXIL => "bfxil",
+            A64_UBFM => "ubfm",
+            A64_LSL_IMM => "lsl",
+            A64_LSR_IMM => "lsr",
+            A64_UBFIZ => "ubfiz",
+            A64_UBFX => "ubfx",
+            A64_EXTEND => "extend",
+            A64_EXTR => "extr",
+            A64_ROR_IMM => "ror",
+            A64_BCOND => "b.cond",
+            A64_SVC => "svc",
+            A64_HVC => "hvc",
+            A64_SMC => "smc",
+            A64_BRK => "brk",
+            A64_HLT => "hlt",
+            A64_DCPS1 => "dcps1",
+            A64_DCPS2 => "dcps2",
+            A64_DCPS3 => "dcps3",
+            A64_HINT => "hint",
+            A64_CLREX => "clrex",
+            A64_DMB => "dmb",
+            A64_ISB => "isb",
+            A64_SB => "sb",
+            A64_DSB => "dsb",
+            A64_SSBB => "ssbb",
+            A64_PSSBB => "pssbb",
+            A64_MSR_IMM => "msr",
+            A64_CFINV => "cfinv",
+            A64_XAFlag => "xaflag",
+            A64_AXFlag => "axflag",
+            A64_SYS => "sys",
+            A64_SYSL => "sysl",
+            A64_MSR_REG => "msr",
+            A64_MRS => "mrs",
+            A64_BR => "br",
+            A64_BLR => "blr",
+            A64_RET => "ret",
+            A64_BRA => "bra",
+            A64_BLRA => "blra",
+            A64_RETA => "reta",
+            A64_ERET => "eret",
+            A64_ERETA => "ereta",
+            A64_DRPS => "drps",
+            A64_B => "b",
+            A64_BL => "bl",
+            A64_CBZ => "cbz",
+            A64_CBNZ => "cbnz",
+            A64_TBZ => "tbz",
+            A64_TBNZ => "tbnz",
+            A64_UDIV => "udiv",
+            A64_SDIV => "sdiv",
+            A64_LSLV => "lslv",
+            A64_LSRV => "lsrv",
+            A64_ASRV => "asrv",
+            A64_RORV => "rorv",
+            A64_CRC32B => "crc32b",
+            A64_CRC32H => "crc32h",
+            A64_CRC32W => "crc32w",
+            A64_CRC32X => "crc32x",
+            A64_CRC32CB => "crc32cb",
+            A64_CRC32CH => "crc32ch",
+            A64_CRC32CW => "crc32cw",
+            A64_CRC32CX => "crc32cx",
+            A64_SUBP => "subp",
+            A64_RBIT => "rbit",
+            A64_REV16 => "rev16",
+            A64_REV => "rev",
+            A64_REV32 => "rev32",
+            A64_CLZ => "clz",
+            A64_CLS => "cls",
+            A64_AND_SHIFTED => "and",
+            A64_TST_SHIFTED => "tst",
+            A64_BIC => "bic",
+            A64_ORR_SHIFTED => "orr",
+            A64_MOV_REG => "mov",
+            A64_ORN => "orn",
+            A64_MVN => "mvn",
+            A64_EOR_SHIFTED => "eor",
+            A64_EON => "eon",
+            A64_ADD_SHIFTED => "add",
+            A64_CMN_SHIFTED => "cmn",
+            A64_SUB_SHIFTED => "sub",
+            A64_NEG => "neg",
+            A64_CMP_SHIFTED => "cmp",
+            A64_ADD_EXT => "add",
+            A64_CMN_EXT => "cmn",
+            A64_SUB_EXT => "sub",
+            A64_CMP_EXT => "cmp",
+            A64_ADC => "adc",
+            A64_SBC => "sbc",
+            A64_NGC => "ngc",
+            A64_RMIF => "rmif",
+            A64_SETF8 => "setf8",
+            A64_SETF16 => "setf16",
+            A64_CCMN_REG => "ccmn",
+            A64_CCMP_REG => "ccmp",
+            A64_CCMN_IMM => "ccmn",
+            A64_CCMP_IMM => "ccmp",
+            A64_CSEL => "csel",
+            A64_CSINC => "csinc",
+            A64_CINC => "cinc",
+            A64_CSET => "cset",
+            A64_CSINV => "csinv",
+            A64_CINV => "cinv",
+            A64_CSETM => "csetm",
+            A64_CSNEG => "csneg",
+            A64_CNEG => "cneg",
+            A64_MADD => "madd",
+            A64_MUL => "mul",
+            A64_MSUB => "msub",
+            A64_MNEG => "mneg",
+            A64_SMADDL => "smaddl",
+            A64_SMULL => "smull",
+            A64_SMSUBL => "smsubl",
+            A64_SMNEGL => "smnegl",
+            A64_SMULH => "smulh",
+            A64_UMADDL => "umaddl",
+            A64_UMULL => "umull",
+            A64_UMSUBL => "umsubl",
+            A64_UMNEGL => "umnegl",
+            A64_UMULH => "umulh",
+            A64_LD1_MULT => "ld1",
+            A64_ST1_MULT => "st1",
+            A64_LD2_MULT => "ld2",
+            A64_ST2_MULT => "st2",
+            A64_LD3_MULT => "ld3",
+            A64_ST3_MULT => "st3",
+            A64_LD4_MULT => "ld4",
+            A64_ST4_MULT => "st4",
+            A64_LD1_SINGLE => "ld1",
+            A64_ST1_SINGLE => "st1",
+            A64_LD2_SINGLE => "ld2",
+            A64_ST2_SINGLE => "st2",
+            A64_LD3_SINGLE => "ld3",
+            A64_ST3_SINGLE => "st3",
+            A64_LD4_SINGLE => "ld4",
+            A64_ST4_SINGLE => "st4",
+            A64_LD1R => "ld1r",
+            A64_LD2R => "ld2r",
+            A64_LD3R => "ld3r",
+            A64_LD4R => "ld4r",
+            A64_LDXR => "ldxr",
+            A64_STXR => "stxr",
+            A64_LDXP => "ldxp",
+            A64_STXP => "stxp",
+            A64_LDAPR => "ldapr",
+            A64_LDNP => "ldnp",
+            A64_STNP => "stnp",
+            A64_LDNP_FP => "ldnp",
+            A64_STNP_FP => "stnp",
+            A64_LDP => "ldp",
+            A64_STP => "stp",
+            A64_LDP_FP => "ldp",
+            A64_STP_FP => "stp",
+            A64_LDR => "ldr",
+            A64_STR => "str",
+            A64_LDR_FP => "ldr",
+            A64_STR_FP => "str",
+            A64_PRFM => "prfm",
+            A64_LDADD => "ldadd",
+            A64_LDCLR => "ldclr",
+            A64_LDEOR => "ldeor",
+            A64_LDSET => "ldset",
+            A64_LDSMAX => "ldsmax",
+            A64_LDSMIN => "ldsmin",
+            A64_LDUMAX => "ldumax",
+            A64_LDUMIN => "ldumin",
+            A64_SWP => "swp",
+            A64_CAS => "cas",
+            A64_CASP => "casp",
+            A64_FCVT_GPR => "fcvt",
+            A64_FCVT_VEC => "fcvt",
+            A64_CVTF => "cvtf",
+            A64_CVTF_VEC => "cvtf",
+            A64_FJCVTZS => "fjcvtzs",
+            A64_FRINT => "frint",
+            A64_FRINT_VEC => "frint",
+            A64_FRINTX => "frintx",
+            A64_FRINTX_VEC => "frintx",
+            A64_FCVT_H => "fcvt",
+            A64_FCVT_S => "fcvt",
+            A64_FCVT_D => "fcvt",
+            A64_FCVTL => "fcvtl",
+            A64_FCVTN => "fcvtn",
+            A64_FCVTXN => "fcvtxn",
+            A64_FABS => "fabs",
+            A64_FNEG => "fneg",
+            A64_FSQRT => "fsqrt",
+            A64_FMUL => "fmul",
+            A64_FMULX => "fmulx",
+            A64_FDIV => "fdiv",
+            A64_FADD => "fadd",
+            A64_FSUB => "fsub",
+            A64_FMAX => "fmax",
+            A64_FMAXNM => "fmaxnm",
+            A64_FMIN => "fmin",
+            A64_FMINNM => "fminnm",
+            A64_FRECPE => "frecpe",
+            A64_FRECPS => "frecps",
+            A64_FRECPX => "frecpx",
+            A64_FRSQRTE => "frsqrte",
+            A64_FRSQRTS => "frsqrts",
+            A64_FNMUL => "fnmul",
+            A64_FMADD => "fmadd",
+            A64_FMSUB => "fmsub",
+            A64_FNMADD => "fnmadd",
+            A64_FNMSUB => "fnmsub",
+            A64_FCMP_REG => "fcmp",
+            A64_FCMP_ZERO => "fcmp",
+            A64_FCMPE_REG => "fcmpe",
+            A64_FCMPE_ZERO => "fcmpe",
+            A64_FCCMP => "fccmp",
+            A64_FCCMPE => "fccmpe",
+            A64_FCSEL => "fcsel",
+            A64_FMOV_VEC2GPR => "fmov",
+            A64_FMOV_GPR2VEC => "fmov",
+            A64_FMOV_TOP2GPR => "fmov",
+            A64_FMOV_GPR2TOP => "fmov",
+            A64_FMOV_REG => "fmov",
+            A64_FMOV_IMM => "fmov",
+            A64_FMOV_VEC => "fmov",
+            A64_FCMEQ_REG => "fcmeq",
+            A64_FCMEQ_ZERO => "fcmeq",
+            A64_FCMGE_REG => "fcmge",
+            A64_FCMGE_ZERO => "fcmge",
+            A64_FCMGT_REG => "fcmgt",
+            A64_FCMGT_ZERO => "fcmgt",
+            A64_FCMLE_ZERO => "fcmle",
+            A64_FCMLT_ZERO => "fcmlt",
+            A64_FACGE => "facge",
+            A64_FACGT => "facgt",
+            A64_FABS_VEC => "fabs",
+            A64_FABD_VEC => "fabd",
+            A64_FNEG_VEC => "fneg",
+            A64_FSQRT_VEC => "fsqrt",
+            A64_FMUL_ELEM => "fmul",
+            A64_FMUL_VEC => "fmul",
+            A64_FMULX_ELEM => "fmulx",
+            A64_FMULX_VEC => "fmulx",
+            A64_FDIV_VEC => "fdiv",
+            A64_FADD_VEC => "fadd",
+            A64_FCADD => "fcadd",
+            A64_FSUB_VEC => "fsub",
+            A64_FMAX_VEC => "fmax",
+            A64_FMAXNM_VEC => "fmaxnm",
+            A64_FMIN_VEC => "fmin",
+            A64_FMINNM_VEC => "fminnm",
+            A64_FRECPE_VEC => "frecpe",
+            A64_FRECPS_VEC => "frecps",
+            A64_FRSQRTE_VEC => "frsqrte",
+            A64_FRSQRTS_VEC => "frsqrts",
+            A64_FMLA_ELEM => "fmla",
+            A64_FMLA_VEC => "fmla",
+            A64_FMLAL_ELEM => "fmlal",
+            A64_FMLAL_VEC => "fmlal",
+            A64_FMLAL2_ELEM => "fmlal2",
+            A64_FMLAL2_VEC => "fmlal2",
+            A64_FCMLA_ELEM => "fcmla",
+            A64_FCMLA_VEC => "fcmla",
+            A64_FMLS_ELEM => "fmls",
+            A64_FMLS_VEC => "fmls",
+            A64_FMLSL_ELEM => "fmlsl",
+            A64_FMLSL_VEC => "fmlsl",
+            A64_FMLSL2_ELEM => "fmlsl2",
+            A64_FMLSL2_VEC => "fmlsl2",
+            A64_FADDP => "faddp",
+            A64_FADDP_VEC => "faddp",
+            A64_FMAXP => "fmaxp",
+            A64_FMAXP_VEC => "fmaxp",
+            A64_FMAXV => "fmaxv",
+            A64_FMAXNMP => "fmaxnmp",
+            A64_FMAXNMP_VEC => "fmaxnmp",
+            A64_FMAXNMV => "fmaxnmv",
+            A64_FMINP => "fminp",
+            A64_FMINP_VEC => "fminp",
+            A64_FMINV => "fminv",
+            A64_FMINNMP => "fminnmp",
+            A64_FMINNMP_VEC => "fminnmp",
+            A64_FMINNMV => "fminnmv",
+            A64_AND_VEC => "and",
+            A64_BCAX => "bcax",
+            A64_BIC_VEC_IMM => "bic",
+            A64_BIC_VEC_REG => "bic",
+            A64_BIF => "bif",
+            A64_BIT => "bit",
+            A64_BSL => "bsl",
+            A64_CLS_VEC => "cls",
+            A64_CLZ_VEC => "clz",
+            A64_CNT => "cnt",
+            A64_EOR_VEC => "eor",
+            A64_EOR3 => "eor3",
+            A64_NOT_VEC => "not",
+            A64_ORN_VEC => "orn",
+            A64_ORR_VEC_IMM => "orr",
+            A64_ORR_VEC_REG => "orr",
+            A64_MOV_VEC => "mov",
+            A64_RAX1 => "rax1",
+            A64_RBIT_VEC => "rbit",
+            A64_REV16_VEC => "rev16",
+            A64_REV32_VEC => "rev32",
+            A64_REV64_VEC => "rev64",
+            A64_SHL_IMM => "shl",
+            A64_SHL_REG => "shl",
+            A64_SHLL => "shll",
+            A64_SHR => "shr",
+            A64_SHRN => "shrn",
+            A64_SRA => "sra",
+            A64_SLI => "sli",
+            A64_SRI => "sri",
+            A64_XAR => "xar",
+            A64_DUP_ELEM => "dup",
+            A64_DUP_GPR => "dup",
+            A64_EXT => "ext",
+            A64_INS_ELEM => "ins",
+            A64_INS_GPR => "ins",
+            A64_MOVI => "movi",
+            A64_SMOV => "smov",
+            A64_UMOV => "umov",
+            A64_TBL => "tbl",
+            A64_TBX => "tbx",
+            A64_TRN1 => "trn1",
+            A64_TRN2 => "trn2",
+            A64_UZP1 => "uzp1",
+            A64_UZP2 => "uzp2",
+            A64_XTN => "xtn",
+            A64_ZIP1 => "zip1",
+            A64_ZIP2 => "zip2",
+            A64_CMEQ_REG => "cmeq",
+            A64_CMEQ_ZERO => "cmeq",
+            A64_CMGE_REG => "cmge",
+            A64_CMGE_ZERO => "cmge",
+            A64_CMGT_REG => "cmgt",
+            A64_CMGT_ZERO => "cmgt",
+            A64_CMHI_REG => "cmhi",
+            A64_CMHS_REG => "cmhs",
+            A64_CMLE_ZERO => "cmle",
+            A64_CMLT_ZERO => "cmlt",
+            A64_CMTST => "cmtst",
+            A64_ABS_VEC => "abs",
+            A64_ABD => "abd",
+            A64_ABDL => "abdl",
+            A64_ABA => "aba",
+            A64_ABAL => "abal",
+            A64_NEG_VEC => "neg",
+            A64_MUL_ELEM => "mul",
+            A64_MUL_VEC => "mul",
+            A64_MULL_ELEM => "mull",
+            A64_MULL_VEC => "mull",
+            A64_ADD_VEC => "add",
+            A64_ADDHN => "addhn",
+            A64_ADDL => "addl",
+            A64_ADDW => "addw",
+            A64_HADD => "hadd",
+            A64_SUB_VEC => "sub",
+            A64_SUBHN => "subhn",
+            A64_SUBL => "subl",
+            A64_SUBW => "subw",
+            A64_HSUB => "hsub",
+            A64_MAX_VEC => "max",
+            A64_MIN_VEC => "min",
+            A64_DOT_ELEM => "dot",
+            A64_DOT_VEC => "dot",
+            A64_URECPE => "urecpe",
+            A64_URSQRTE => "ursqrte",
+            A64_MLA_ELEM => "mla",
+            A64_MLA_VEC => "mla",
+            A64_MLS_ELEM => "mls",
+            A64_MLS_VEC => "mls",
+            A64_MLAL_ELEM => "mlal",
+            A64_MLAL_VEC => "mlal",
+            A64_MLSL_ELEM => "mlsl",
+            A64_MLSL_VEC => "mlsl",
+            A64_ADDP => "addp",
+            A64_ADDP_VEC => "addp",
+            A64_ADDV => "addv",
+            A64_ADALP => "adalp",
+            A64_ADDLP => "addlp",
+            A64_ADDLV => "addlv",
+            A64_MAXP => "maxp",
+            A64_MAXV => "maxv",
+            A64_MINP => "minp",
+            A64_MINV => "minv",
+            A64_QADD => "qadd",
+            A64_QABS => "qabs",
+            A64_SUQADD => "suqadd",
+            A64_USQADD => "usqadd",
+            A64_QSHL_IMM => "qshl",
+            A64_QSHL_REG => "qshl",
+            A64_QSHRN => "qshrn",
+            A64_QSUB => "qsub",
+            A64_QXTN => "qxtn",
+            A64_SQABS => "sqabs",
+            A64_SQADD => "sqadd",
+            A64_SQDMLAL_ELEM => "sqdmlal",
+            A64_SQDMLAL_VEC => "sqdmlal",
+            A64_SQDMLSL_ELEM => "sqdmlsl",
+            A64_SQDMLSL_VEC => "sqdmlsl",
+            A64_SQDMULH_ELEM => "sqdmulh",
+            A64_SQDMULH_VEC => "sqdmulh",
+            A64_SQDMULL_ELEM => "sqdmull",
+            A64_SQDMULL_VEC => "sqdmull",
+            A64_SQNEG => "sqneg",
+            A64_SQRDMLAH_ELEM => "sqrdmlah",
+            A64_SQRDMLAH_VEC => "sqrdmlah",
+            A64_SQRDMLSH_ELEM => "sqrdmlsh",
+            A64_SQRDMLSH_VEC => "sqrdmlsh",
+            A64_SQSHLU => "sqshlu",
+            A64_SQSHRUN => "sqshrun",
+            A64_SQXTUN => "sqxtun",
+            A64_PMUL => "pmul",
+            A64_PMULL => "pmull",
+            A64_ADD_Z => "add",
+            A64_SUB_Z => "sub",
+            A64_SUBR_Z => "subr",
+            A64_MAX_Z => "max",
+            A64_MIN_Z => "min",
+            A64_ABD_Z => "abd",
+            A64_MUL_Z => "mul",
+            A64_MULH_Z => "mulh",
+            A64_DIV_Z => "div",
+            A64_DIVR_Z => "divr",
+            A64_AND_Z => "and",
+            A64_ORR_Z => "orr",
+            A64_EOR_Z => "eor",
+            A64_BIC_Z => "bic",
+            A64_QADD_Z => "qadd",
+            A64_QSUB_Z => "qsub",
+            A64_MLA_Z => "mla",
+            A64_MLS_Z => "mls",
+            A64_MAD_Z => "mad",
+            A64_MSB_Z => "msb",
+            A64_ASR_Z => "asr",
+            A64_LSR_Z => "lsr",
+            A64_LSL_Z => "lsl",
+            A64_ASRR_Z => "asrr",
+            A64_LSRR_Z => "lsrr",
+            A64_LSLR_Z => "lslr",
+            A64_ASR_WIDE_Z => "asr",
+            A64_LSR_WIDE_Z => "lsr",
+            A64_LSL_WIDE_Z => "lsl",
+            A64_ASR_IMM_Z => "asr",
+            A64_LSR_IMM_Z => "lsr",
+            A64_LSL_IMM_Z => "lsl",
+            A64_ASRD_Z => "asrd",
+            A64_EXTEND_Z => "extend",
+            A64_ABS_Z => "abs",
+            A64_NEG_Z => "neg",
+            A64_CLS_Z => "cls",
+            A64_CLZ_Z => "clz",
+            A64_CNT_Z => "cnt",
+            A64_CNOT_Z => "cnot",
+            A64_NOT_Z => "not",
+            A64_ADDV_Z => "addv",
+            A64_MAXV_Z => "maxv",
+            A64_MINV_Z => "minv",
+            A64_ORV_Z => "orv",
+            A64_EORV_Z => "eorv",
+            A64_ANDV_Z => "andv",
+            A64_CMP_Z => "cmpeq",
+            A64_CMP_WIDE_Z => "cmpeq",
+            A64_CMP_IMM_Z => "cmpeq",
+            A64_INDEX_IMM_Z => "index",
+            A64_INDEX_IMM_REG_Z => "index",
+            A64_INDEX_REG_IMM_Z => "index",
+            A64_INDEX_REG_Z => "index",
+            A64_ADR_Z => "adr",
+            A64_ORR_IMM_Z => "orr",
+            A64_EOR_IMM_Z => "eor",
+            A64_AND_IMM_Z => "and",
+            A64_DUPM_Z => "dupm",
+            A64_ADD_IMM_Z => "add",
+            A64_SUB_IMM_Z => "sub",
+            A64_SUBR_IMM_Z => "subr",
+            A64_QADD_IMM_Z => "qadd",
+            A64_QSUB_IMM_Z => "qsub",
+            A64_MAX_IMM_Z => "max",
+            A64_MIN_IMM_Z => "min",
+            A64_MUL_IMM_Z => "mul",
+            A64_DUP_IMM_Z => "dup",
+            A64_CPY_IMM_Z => "cpy",
+            A64_DUP_REG_Z => "dup",
+            A64_CPY_REG_Z => "cpy",
+            A64_CPY_FP_Z => "cpy",
+            A64_DUP_ELEM_Z => "dup",
+            A64_SEL_Z => "sel",
+            A64_MOVPRFX_Z => "movprfx",
+            A64_CNT_ELEMS => "cntb",
+            A64_INC_ELEMS => "incb",
+            A64_DEC_ELEMS => "decb",
+            A64_QINC_ELEMS => "qincb",
+            A64_QDEC_ELEMS => "qdecb",
+            A64_INC_ELEMS_Z => "inch",
+            A64_DEC_ELEMS_Z => "dech",
+            A64_QINC_ELEMS_Z => "qinch",
+            A64_QDEC_ELEMS_Z => "qdech",
+            A64_ZIP1_Z => "zip1",
+            A64_ZIP2_Z => "zip2",
+            A64_UZP1_Z => "uzp1",
+            A64_UZP2_Z => "uzp2",
+            A64_TRN1_Z => "trn1",
+            A64_TRN2_Z => "trn2",
+            A64_REV_Z => "rev",
+            A64_EXT_Z => "ext",
+            A64_TBL_Z => "tbl",
+            A64_LASTA_REG_Z => "lasta",
+            A64_LASTB_REG_Z => "lastb",
+            A64_LASTA_FP_Z => "lasta",
+            A64_LASTB_FP_Z => "lastb",
+            A64_CLASTA_Z => "clasta",
+            A64_CLASTB_Z => "clastb",
+            A64_CLASTA_REG_Z => "clasta",
+            A64_CLASTB_REG_Z => "clastb",
+            A64_CLASTA_FP_Z => "clasta",
+            A64_CLASTB_FP_Z => "clastb",
+            A64_DOT_Z => "dot",
+            A64_DOT_ELEM_Z => "dot",
+            A64_FADD_Z => "fadd",
+            A64_FSUB_Z => "fsub",
+            A64_FMUL_Z => "fmul",
+            A64_FSUBR_Z => "fsubr",
+            A64_FMAXNM_Z => "fmaxnm",
+            A64_FMINNM_Z => "fminnm",
+            A64_FMAX_Z => "fmax",
+            A64_FMIN_Z => "fmin",
+            A64_FABD_Z => "fabd",
+            A64_FSCALE_Z => "fscale",
+            A64_FMULX_Z => "fmulx",
+            A64_FDIVR_Z => "fdivr",
+            A64_FDIV_Z => "fdiv",
+            A64_FTSMUL_Z => "ftsmul",
+            A64_FRECPS_Z => "frecps",
+            A64_FRSQRTS_Z => "frsqrts",
+            A64_FMLA_Z => "fmla",
+            A64_FMLS_Z => "fmls",
+            A64_FNMLA_Z => "fnmla",
+            A64_FNMLS_Z => "fnmls",
+            A64_FMAD_Z => "fmad",
+            A64_FMSB_Z => "fmsb",
+            A64_FNMAD_Z => "fnmad",
+            A64_FNMSB_Z => "fnmsb",
+            A64_FABS_Z => "fabs",
+            A64_FNEG_Z => "fneg",
+            A64_FRINT_Z => "frint",
+            A64_FRINTX_Z => "frintx",
+            A64_FRECPX_Z => "frecpx",
+            A64_FSQRT_Z => "fsqrt",
+            A64_FCVT_Z => "fcvt",
+            A64_FCVTZ_Z => "fcvtz",
+            A64_CVTF_Z => "cvtf",
+            A64_FADDV_Z => "faddv",
+            A64_FMAXNMV_Z => "fmaxnmv",
+            A64_FMINNMV_Z => "fminnmv",
+            A64_FMAXV_Z => "fmaxv",
+            A64_FMINV_Z => "fminv",
+            A64_FADDA_Z => "fadda",
+            A64_FCM_Z => "fcmeq",
+            A64_FAC_Z => "facge",
+            A64_FCM_ZERO_Z => "fcmeq",
+            A64_FADD_IMM_Z => "fadd",
+            A64_FSUB_IMM_Z => "fsub",
+            A64_FMUL_IMM_Z => "fmul",
+            A64_FSUBR_IMM_Z => "fsubr",
+            A64_FMAXNM_IMM_Z => "fmaxnm",
+            A64_FMINNM_IMM_Z => "fminnm",
+            A64_FMAX_IMM_Z => "fmax",
+            A64_FMIN_IMM_Z => "fmin",
+            A64_FDUP_Z => "fdup",
+            A64_FCPY_Z => "fcpy",
+            A64_FMLA_ELEM_Z => "fmla",
+            A64_FMLS_ELEM_Z => "fmls",
+            A64_FMUL_ELEM_Z => "fmul",
+            A64_FCMLA_Z => "fcmla",
+            A64_FCMLA_ELEM_Z => "fcmla",
+            A64_FCADD_Z => "fcadd",
+            A64_LD1_Z => "ld1",
+            A64_LDFF1_Z => "ldff1",
+            A64_LDNF1_Z => "ldnf1",
+            A64_LDNT1_Z => "ldnt1",
+            A64_LD2_Z => "ld2",
+            A64_LD3_Z => "ld3",
+            A64_LD4_Z => "ld4",
+            A64_ST1_Z => "st1",
+            A64_STNT1_Z => "stnt1",
+            A64_ST2_Z => "st2",
+            A64_ST3_Z => "st3",
+            A64_ST4_Z => "st4",
+            A64_PTRUE => "ptrue",
+            A64_PFALSE => "pfalse",
+            A64_PFIRST => "pfirst",
+            A64_PNEXT => "pnext",
+            A64_PTEST => "ptest",
+            A64_BRKA => "brka",
+            A64_BRKB => "brkb",
+            A64_WHILE => "whilelt",
+            A64_CNTP => "cntp",
+            A64_RDFFR => "rdffr",
+            A64_SETFFR => "setffr",
+            A64_WRFFR => "wrffr",
+            A64_AND_P => "and",
+            A64_BIC_P => "bic",
+            A64_EOR_P => "eor",
+            A64_SEL_P => "sel",
+            A64_ORR_P => "orr",
+            A64_ORN_P => "orn",
+            A64_NOR_P => "nor",
+            A64_NAND_P => "nand",
+            A64_BRKN => "brkn",
+            A64_BRKPA => "brkpa",
+            A64_BRKPB => "brkpb",
+            A64_INCP => "incp",
+            A64_DECP => "decp",
+            A64_QINCP => "qincp",
+            A64_QDECP => "qdecp",
+            A64_INCP_Z => "incp",
+            A64_DECP_Z => "decp",
+            A64_QINCP_Z => "qincp",
+            A64_QDECP_Z => "qdecp",
+            A64_ZIP1_P => "zip1",
+            A64_ZIP2_P => "zip2",
+            A64_UZP1_P => "uzp1",
+            A64_UZP2_P => "uzp2",
+            A64_TRN1_P => "trn1",
+            A64_TRN2_P => "trn2",
+            A64_REV_P => "rev",
+            A64_PUNPKLO => "punpklo",
+            A64_PUNPKHI => "punpkhi",
+            A64_CTERMEQ => "ctermeq",
+            A64_CTERMNE => "ctermne",
+            A64_PMUL_Z => "pmul",
+            A64_SQDMULH_Z => "sqdmulh",
+            A64_SQRDMULH_Z => "sqrdmulh",
+            A64_EOR3_Z => "eor3",
+            A64_BCAX_Z => "bcax",
+            A64_BSL_Z => "bsl",
+            A64_BSL1N_Z => "bsl1n",
+            A64_BSL2N_Z => "bsl2n",
+            A64_NBSL_Z => "nbsl",
+            A64_CMLA_Z => "cmla",
+            A64_CMLA_ELEM_Z => "cmla",
+            A64_ADDLB_Z => "addlb",
+            A64_ADDLT_Z => "addlt",
+            A64_SUBLB_Z => "sublb",
+            A64_SUBLT_Z => "sublt",
+            A64_ABDLB_Z => "abdlb",
+            A64_ABDLT_Z => "abdlt",
+            A64_ADDWB_Z => "addwb",
+            A64_ADDWT_Z => "addwt",
+            A64_SUBWB_Z => "subwb",
+            A64_SUBWT_Z => "subwt",
+            A64_MULLB_Z => "mullb",
+            A64_MULLT_Z => "mullt",
+            A64_SQDMULLB_Z => "sqdmullb",
+            A64_SQDMULLT_Z => "sqdmullt",
+            A64_PMULLB_Z => "pmullb",
+            A64_PMULLT_Z => "pmullt",
+            A64_SHLLB_Z => "shllb",
+            A64_SHLLT_Z => "shllt",
+            A64_SHRNB_Z => "shrnb",
+            A64_SHRNT_Z => "shrnt",
+            A64_QSHRNB_Z => "qshrnb",
+            A64_QSHRNT_Z => "qshrnt",
+            A64_SQSHRUNB_Z => "sqshrunb",
+            A64_SQSHRUNT_Z => "sqshrunt",
+            A64_QXTNB_Z => "qxtnb",
+            A64_QXTNT_Z => "qxtnt",
+            A64_SQXTUNB_Z => "sqxtunb",
+            A64_SQXTUNT_Z => "sqxtunt",
+            A64_ADDHNB_Z => "addhnb",
+            A64_ADDHNT_Z => "addhnt",
+            A64_SUBHNB_Z => "subhnb",
+            A64_SUBHNT_Z => "subhnt",
+            A64_XAR_Z => "xar",
+            A64_BEXT_Z => "bext",
+            A64_BDEP_Z => "bdep",
+            A64_BGRP_Z => "bgrp",
+            A64_HISTCNT_Z => "histcnt",
+            A64_HISTSEG_Z => "histseg",
+            A64_MATCH_Z => "match",
+            A64_NMATCH_Z => "nmatch",
+            A64_AESE_Z => "aese",
+            A64_AESD_Z => "aesd",
+            A64_AESMC_Z => "aesmc",
+            A64_AESIMC_Z => "aesimc",
+            A64_SM4E_Z => "sm4e",
+            A64_SM4EKEY_Z => "sm4ekey",
+            A64_RAX1_Z => "rax1",
+            A64_LDR_ZA => "ldr",
+            A64_STR_ZA => "str",
+            A64_LD1_ZA => "ld1",
+            A64_ST1_ZA => "st1",
+            A64_ZERO_ZA => "zero",
+            A64_MOVA_Z | A64_MOVA_ZA => "mova",
+            A64_FMOPA_ZA => "fmopa",
+            A64_FMOPS_ZA => "fmops",
+            A64_BFMOPA_ZA => "bfmopa",
+            A64_BFMOPS_ZA => "bfmops",
+            A64_SMOPA_ZA => "smopa",
+            A64_SMOPS_ZA => "smops",
+            A64_UMOPA_ZA => "umopa",
+            A64_UMOPS_ZA => "umops",
+            A64_SUMOPA_ZA => "sumopa",
+            A64_SUMOPS_ZA => "sumops",
+            A64_USMOPA_ZA => "usmopa",
+            A64_USMOPS_ZA => "usmops",
+        }
+    }
+
+    /// The opcodes an upper- or lower-case mnemonic may decode to, aliases
+    /// included. Empty if the mnemonic is unknown.
+    pub fn from_mnemonic(name: &str) -> Vec<Op> {
+        let name = name.to_ascii_lowercase();
+        let mut out: Vec<Op> = ALL_OPS.iter().copied().filter(|op| op.mnemonic() == name).collect();
+        let mut add = |ops: &[Op]| {
+            for &op in ops {
+                if !out.contains(&op) {
+                    out.push(op);
+                }
+            }
+        };
+
+        if let Some((_, ops)) = ALIASES.iter().find(|(alias, _)| *alias == name) {
+            add(ops);
+        }
+        if HINTS.iter().any(|(_, hint)| *hint == name) {
+            add(&[A64_HINT]);
+        }
+        if let Some(cond) = name.strip_prefix("b.") {
+            if Cond::from_name(cond).is_some() {
+                add(&[A64_BCOND]);
+            }
+        }
+        if let Some(rest) = name.strip_prefix("fcvt") {
+            if rest.len() == 2 && "namzp".contains(&rest[..1]) && "su".contains(&rest[1..]) {
+                add(&[A64_FCVT_GPR, A64_FCVT_VEC]);
+                if rest.starts_with('z') {
+                    add(&[A64_FCVTZ_Z]);
+                }
+            }
+        }
+        if let Some(mode) = name.strip_prefix("frint") {
+            if mode.len() == 1 && "namzpi".contains(mode) {
+                add(&[A64_FRINT, A64_FRINT_VEC, A64_FRINT_Z]);
+            }
+        }
+        if let Some(rest) = name.strip_prefix('s').or_else(|| name.strip_prefix('u')) {
+            let stem = rest.strip_prefix('r').unwrap_or(rest);
+            for r in [rest, stem] {
+                add(&SIGNED_FAMILIES.iter().copied().filter(|op| op.mnemonic() == r).collect::<Vec<_>>());
+            }
+        }
+        if let Some(cond) = name.strip_prefix("while") {
+            if ["lt", "le", "lo", "ls", "ge", "gt", "hs", "hi"].contains(&cond) {
+                add(&[A64_WHILE]);
+            }
+        }
+        // cmphs p0.b, ..., fcmuo p0.h, ..., facgt p0.s, ...
+        if let Some(cond) = name.strip_prefix("cmp") {
+            if ["eq", "ne", "lt", "le", "lo", "ls", "ge", "gt", "hs", "hi"].contains(&cond) {
+                add(&[A64_CMP_Z, A64_CMP_WIDE_Z, A64_CMP_IMM_Z]);
+            }
+        }
+        if let Some(cond) = name.strip_prefix("fcm") {
+            if ["eq", "ne", "lt", "le", "ge", "gt", "uo"].contains(&cond) {
+                add(&[A64_FCM_Z, A64_FCM_ZERO_Z]);
+            }
+        }
+        if name == "facgt" {
+            add(&[A64_FAC_Z]);
+        }
+        // cnth x0, ..., sqdecw x0, w0, ..., uqincd z0.d, ...
+        for &op in ELEMENT_COUNTS {
+            let stem = &op.mnemonic()[..op.mnemonic().len() - 1];
+            let unsigned = name.strip_prefix('s').or_else(|| name.strip_prefix('u')).filter(|_| stem.starts_with('q'));
+            if let Some(size) = unsigned.unwrap_or(&name).strip_prefix(stem) {
+                if ["b", "h", "w", "d"].contains(&size) && !(size == "b" && op >= A64_INC_ELEMS_Z) {
+                    add(&[op]);
+                }
+            }
+        }
+        // rshrnb z0.b, ..., sqrshrnt z0.h, ..., raddhnb z0.s, ...
+        for &op in NARROWING {
+            let (head, tail) = narrowing_split(op);
+            let signs: &[&str] = if head == "q" { &["s", "u"] } else { &[""] };
+            if signs.iter().any(|sign| [format!("{}{}{}", sign, head, tail), format!("{}{}r{}", sign, head, tail)].contains(&name)) {
+                add(&[op]);
+            }
+        }
+        // ld1sb z0.h, ..., st4d {z0.d-z3.d}, ...
+        for op in ALL_OPS.iter().copied().filter(|&op| is_sve_memory(op)) {
+            if let Some(rest) = name.strip_prefix(op.mnemonic()) {
+                let rest = rest.strip_prefix('s').unwrap_or(rest);
+                if ["b", "h", "w", "d"].contains(&rest) {
+                    add(&[op]);
+                }
+            }
+        }
+        // ld1b {za0h.b[w12, 0]}, ..., st1q {za15v.q[w12, 0]}, ...
+        if let Some(rest) = name.strip_prefix("ld1").or_else(|| name.strip_prefix("st1")) {
+            if ["b", "h", "w", "d", "q"].contains(&rest) {
+                add(&[if name.starts_with('l') { A64_LD1_ZA } else { A64_ST1_ZA }]);
+            }
+        }
+        for &op in ATOMICS {
+            let st = op.mnemonic().strip_prefix("ld").filter(|_| op != A64_SWP).map(|m| format!("st{}", m));
+            if let Some(rest) = name.strip_prefix(op.mnemonic()).or_else(|| st.as_deref().and_then(|st| name.strip_prefix(st))) {
+                let rest = rest.strip_suffix(['b', 'h']).unwrap_or(rest);
+                if ["", "a", "al", "l"].contains(&rest) {
+                    add(&[op]);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// The mnemonic of the family member a decoded instruction is, for the
+/// opcodes whose members the flags select; the canonical one otherwise.
+pub fn inst_mnemonic(inst: &Inst) -> String {
+    let memext = fad_get_mem_extend(inst.flags);
+    let (signed, size) = (memext >> 2 != 0, memext & 0b11);
+    let (acquire, release) = (inst.ldst_order.load != MemOrdering::MO_NONE as u16, inst.ldst_order.store != MemOrdering::MO_NONE as u16);
+    let bh = if size < 2 { ["b", "h"][size as usize] } else { "" };
+    match inst.op {
+        A64_BCOND => format!("b.{}", Cond::name(fad_get_cond(inst.flags))),
+        A64_LDR | A64_STR if fad_get_addrmode(inst.flags) == AddrMode::AM_SIMPLE => {
+            let lo = inst.ldst_order.load == MemOrdering::MO_LO_ACQUIRE as u16
+                || inst.ldst_order.store == MemOrdering::MO_LO_RELEASE as u16;
+            let name = match (inst.op, lo) {
+                (A64_LDR, false) => "ldar",
+                (A64_LDR, true) => "ldlar",
+                (_, false) => "stlr",
+                (_, true) => "stllr",
+            };
+            format!("{}{}", name, bh)
+        }
+        A64_LDXR | A64_STXR | A64_LDXP | A64_STXP => {
+            let (prefix, rest) = inst.op.mnemonic().split_at(2);
+            let ordered = if acquire { "a" } else if release { "l" } else { "" };
+            let suffix = if matches!(inst.op, A64_LDXR | A64_STXR) { bh } else { "" };
+            format!("{}{}{}{}", prefix, ordered, rest, suffix)
+        }
+        _ if inst.atomic_store_alias() => {
+            format!("st{}{}{}", &inst.op.mnemonic()[2..], if release { "l" } else { "" }, bh)
+        }
+        A64_LDADD | A64_LDCLR | A64_LDEOR | A64_LDSET | A64_LDSMAX | A64_LDSMIN | A64_LDUMAX | A64_LDUMIN | A64_SWP => {
+            format!("{}{}{}{}", inst.op.mnemonic(), if acquire { "a" } else { "" }, if release { "l" } else { "" }, bh)
+        }
+        A64_LDAPR => format!("ldapr{}", bh),
+        A64_CAS | A64_CASP => {
+            let suffix = if inst.op == A64_CAS { bh } else { "" };
+            format!("{}{}{}{}", inst.op.mnemonic(), if acquire { "a" } else { "" }, if release { "l" } else { "" }, suffix)
+        }
+        A64_LDR | A64_STR if size < 2 || (signed && size == 2 && inst.flags & 1 == 0) => {
+            let suffix = ["b", "h", "w", ""][size as usize];
+            format!("{}{}{}", inst.op.mnemonic(), if signed { "s" } else { "" }, suffix)
+        }
+        A64_LDP if signed => "ldpsw".to_string(),
+        A64_MSR_IMM if PStateField::from_u32(inst.msr_imm.psfld).is_some_and(PStateField::is_svcr) => {
+            (if inst.msr_imm.imm == 1 { "smstart" } else { "smstop" }).to_string()
+        }
+        A64_ADD_IMM | A64_SUB_IMM | A64_AND_IMM if inst.flags & SET_FLAGS != 0 => format!("{}s", inst.op.mnemonic()),
+        A64_BRA | A64_BLRA | A64_RETA | A64_ERETA => {
+            let zero = inst.rm == Registries::ZERO_REG;
+            format!("{}{}{}", inst.op.mnemonic(), ["a", "b"][(inst.imm & 1) as usize], if zero { "z" } else { "" })
+        }
+        A64_SYS => inst.sys_alias().map_or("sys", |(mnemonic, ..)| mnemonic).to_string(),
+        A64_HINT => match inst.pac_hint() {
+            Some(PacHint::StripLr) => "xpaclri".to_string(),
+            Some(hint @ (PacHint::Sign { key, modifier } | PacHint::Auth { key, modifier })) => {
+                let modifier = match modifier {
+                    PacModifier::Sp => "sp",
+                    PacModifier::Zero => "z",
+                    PacModifier::X16 => "1716",
+                };
+                let sign = matches!(hint, PacHint::Sign { .. });
+                format!("{}i{}{}", if sign { "pac" } else { "aut" }, if key == PacKey::A { "a" } else { "b" }, modifier)
+            }
+            None => hint_name(inst.imm).unwrap_or("hint").to_string(),
+        },
+        A64_EXTEND | A64_EXTEND_Z => {
+            let typ = inst.extend.typ;
+            format!("{}xt{}", if typ & 0b100 != 0 { 's' } else { 'u' }, ["b", "h", "w", "x"][(typ & 0b11) as usize])
+        }
+        // MOV, MOVS, NOT and NOTS: AND with Pn as Pm, ORR with Pn as Pg and
+        // Pm, EOR with Pg as Pm, and SEL with Pd as Pm.
+        A64_AND_P | A64_ORR_P | A64_EOR_P | A64_SEL_P if predicate_move(inst) => {
+            let stem = if inst.op == A64_EOR_P { "not" } else { "mov" };
+            format!("{}{}", stem, if inst.flags & SET_FLAGS != 0 { "s" } else { "" })
+        }
+        A64_PTRUE | A64_BRKA | A64_BRKB | A64_RDFFR | A64_AND_P | A64_BIC_P | A64_EOR_P | A64_ORR_P | A64_ORN_P
+        | A64_NOR_P | A64_NAND_P | A64_BRKN | A64_BRKPA | A64_BRKPB
+            if inst.flags & SET_FLAGS != 0 =>
+        {
+            format!("{}s", inst.op.mnemonic())
+        }
+        A64_WHILE => format!("while{}", sve_cond_name(fad_get_cond(inst.flags))),
+        A64_CMP_Z | A64_CMP_WIDE_Z | A64_CMP_IMM_Z => format!("cmp{}", sve_cond_name(fad_get_cond(inst.flags))),
+        A64_FCM_Z | A64_FCM_ZERO_Z => format!("fcm{}", sve_cond_name(fad_get_cond(inst.flags))),
+        A64_FAC_Z => format!("fac{}", sve_cond_name(fad_get_cond(inst.flags))),
+        A64_FRINT_Z => {
+            let mode = match inst.frint.mode {
+                m if m == FPRounding::FPR_TIE_EVEN as u32 => "n",
+                m if m == FPRounding::FPR_POS_INF as u32 => "p",
+                m if m == FPRounding::FPR_NEG_INF as u32 => "m",
+                m if m == FPRounding::FPR_ZERO as u32 => "z",
+                m if m == FPRounding::FPR_TIE_AWAY as u32 => "a",
+                _ => "i",
+            };
+            format!("frint{}", mode)
+        }
+        A64_FCVTZ_Z => format!("fcvtz{}", if inst.flags & SIMD_SIGNED != 0 { "s" } else { "u" }),
+        _ if ELEMENT_COUNTS.contains(&inst.op) => {
+            let mnemonic = inst.op.mnemonic();
+            let sign = match inst.flags & SIMD_SIGNED {
+                _ if !mnemonic.starts_with('q') => "",
+                0 => "u",
+                _ => "s",
+            };
+            format!("{}{}{}", sign, &mnemonic[..mnemonic.len() - 1], ["b", "h", "w", "d"][(inst.sve.esize & 0b11) as usize])
+        }
+        // The preferred disassembly of the broadcasts and copies, and of SEL
+        // with the inactive elements taken from the destination.
+        A64_DUP_IMM_Z | A64_CPY_IMM_Z | A64_DUP_REG_Z | A64_CPY_REG_Z | A64_CPY_FP_Z | A64_DUP_ELEM_Z => "mov".to_string(),
+        A64_SEL_Z if inst.rd == inst.rm => "mov".to_string(),
+        A64_FDUP_Z | A64_FCPY_Z => "fmov".to_string(),
+        // The bitmask immediates DUP cannot encode, and those MOVZ and MOVN
+        // cannot.
+        A64_DUPM_Z if !dup_encodable(inst.imm, inst.sve.esize) => "mov".to_string(),
+        A64_ORR_IMM if inst.rn == Registries::ZERO_REG && !move_wide_preferred(inst.imm, inst.flags & W32 != 0) => {
+            "mov".to_string()
+        }
+        _ if is_sve_memory(inst.op) => {
+            let signed = if inst.sve_ldst.signed { "s" } else { "" };
+            format!("{}{}{}", inst.op.mnemonic(), signed, ["b", "h", "w", "d"][(inst.sve_ldst.msz & 0b11) as usize])
+        }
+        A64_LD1_ZA | A64_ST1_ZA => {
+            let esize = inst.sve.esize;
+            format!("{}{}", inst.op.mnemonic(), if esize == FPSize::FSZ_Q { "q" } else { ["b", "h", "w", "d"][esize as usize & 0b11] })
+        }
+        _ if NARROWING.contains(&inst.op) => {
+            let (head, tail) = narrowing_split(inst.op);
+            let sign = match inst.flags & SIMD_SIGNED {
+                _ if head != "q" => "",
+                0 => "u",
+                _ => "s",
+            };
+            format!("{}{}{}{}", sign, head, if inst.flags & SIMD_ROUND != 0 { "r" } else { "" }, tail)
+        }
+        _ if SIGNED_FAMILIES.contains(&inst.op) => {
+            let sign = if inst.flags & SIMD_SIGNED != 0 { "s" } else { "u" };
+            format!("{}{}{}", sign, if inst.flags & SIMD_ROUND != 0 { "r" } else { "" }, inst.op.mnemonic())
+        }
+        _ => inst.op.mnemonic().to_string(),
+    }
+}
+
+/// The mnemonic of a NARROWING op split where the R of its rounding form
+/// goes: after the Q, if any.
+fn narrowing_split(op: Op) -> (&'static str, &'static str) {
+    let mnemonic = op.mnemonic();
+    mnemonic.split_at(mnemonic.find('q').map_or(0, |q| q + 1))
+}
+
+/// The condition of WHILE and the SVE compares, with HS and LO rather than
+/// CS and CC, and UO for the unordered VS.
+fn sve_cond_name(cond: u8) -> &'static str {
+    match cond {
+        Cond::COND_LO => "lo",
+        Cond::COND_HS => "hs",
+        Cond::COND_VS => "uo",
+        cond => Cond::name(cond),
+    }
+}
+
+/// Whether a predicate logical op is printed as MOV, MOVS, NOT or NOTS.
+pub(crate) fn predicate_move(inst: &Inst) -> bool {
+    let pg = inst.sve.pg.unwrap_or(u8::MAX);
+    match inst.op {
+        A64_AND_P => inst.rn == inst.rm,
+        A64_ORR_P => inst.rn == inst.rm && inst.rn == pg,
+        A64_EOR_P => inst.rm == pg,
+        A64_SEL_P => inst.rd == inst.rm,
+        _ => false,
+    }
+}
+
+/// Whether MOVZ or MOVN can load imm, which ORR (immediate) then doesn't
+/// print as MOV (the MoveWidePreferred of the ARM ARM).
+pub(crate) fn move_wide_preferred(imm: u64, w32: bool) -> bool {
+    let (imm, chunks) = if w32 { (imm & 0xFFFF_FFFF, 2) } else { (imm, 4) };
+    let ones = if w32 { 0xFFFF_FFFF } else { u64::MAX };
+    let set = |v: u64| (0..chunks).filter(|i| (v >> (16 * i)) & 0xFFFF != 0).count();
+    set(imm) <= 1 || set(!imm & ones) <= 1
+}
+
+/// Whether DUP (immediate) can set the elements of the FPSize esize to imm:
+/// a signed byte, shifted left by 8 for the wider elements.
+fn dup_encodable(imm: u64, esize: u8) -> bool {
+    let bits = 8 * FPSize::bytes(esize);
+    let value = if bits == 64 { imm as i64 } else { ((imm << (64 - bits)) as i64) >> (64 - bits) };
+    (-128..=127).contains(&value) || esize != FPSize::FSZ_B && value & 0xFF == 0 && (-128..=127).contains(&(value >> 8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_reader::decode;
+
+    #[test]
+    fn mnemonics_round_trip() {
+        assert_eq!(ALL_OPS.len(), A64_USMOPS_ZA as usize + 1);
+        for &op in ALL_OPS {
+            assert!(Op::from_mnemonic(op.mnemonic()).contains(&op), "{:?}", op);
+        }
+        assert_eq!(Op::from_mnemonic("LDADDALB"), vec![A64_LDADD]);
+        assert_eq!(Op::from_mnemonic("staddlh"), vec![A64_LDADD]);
+        assert_eq!(Op::from_mnemonic("stswp"), Vec::<Op>::new());
+        assert_eq!(Op::from_mnemonic("uabd"), vec![A64_ABD, A64_ABD_Z]);
+        // ldrsw x1, [x0, #4]
+        assert_eq!(inst_mnemonic(&decode(0xB9800401)), "ldrsw");
+        assert_eq!(inst_mnemonic(&decode(0x885FFC01)), "ldaxr"); // ldaxr w1, [x0]
+        assert_eq!(inst_mnemonic(&decode(0x089FFC01)), "stlrb"); // stlrb w1, [x0]
+        assert_eq!(inst_mnemonic(&decode(0xD503237F)), "pacibsp");
+        assert_eq!(inst_mnemonic(&decode(0xB1004023)), "adds"); // adds x3, x1, #0x10
+    }
+
+    #[test]
+    fn condition_codes() {
+        assert_eq!(Op::from_mnemonic("b.hs"), vec![A64_BCOND]);
+        assert_eq!(Cond::from_name("lo"), Some(Cond::COND_CC));
+        assert!(Cond::equivalent(Cond::COND_HS, Cond::COND_CS) && Cond::equivalent(Cond::COND_NV, Cond::COND_AL));
+        assert_eq!(Cond::invert(Cond::COND_GE), Some(Cond::COND_LT));
+        assert_eq!(Cond::invert(Cond::COND_NV), None);
+        assert_eq!(Cond::negate_for_swap(Cond::COND_HI), Some(Cond::COND_LO));
+        assert_eq!(Cond::negate_for_swap(Cond::COND_MI), None);
+        // For every pair of operands, a cond b iff b swapped(cond) a.
+        let nzcv = |a: i8, b: i8| {
+            let (r, v) = a.overflowing_sub(b);
+            (((r < 0) as u8) << 3) | (((r == 0) as u8) << 2) | (((a as u8 >= b as u8) as u8) << 1) | v as u8
+        };
+        for cond in 0..16 {
+            let Some(swapped) = Cond::negate_for_swap(cond) else { continue };
+            for (a, b) in [(0, 0), (1, -1), (-128, 127), (5, 3), (3, 5), (-2, -7)] {
+                assert_eq!(Cond::holds(cond, nzcv(a, b)), Cond::holds(swapped, nzcv(b, a)), "{}", Cond::name(cond));
+            }
+        }
+    }
+}
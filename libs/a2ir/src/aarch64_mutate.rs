@@ -0,0 +1,179 @@
+//! Semantic mutations of decoded instructions, for differential fuzzing of
+//! emulators and JITs with inputs that are mostly valid: inverting the
+//! condition, swapping registers of the same class and perturbing
+//! immediates within their encodable range.
+//!
+//! Instructions the writer can encode are mutated on the Inst and encoded
+//! again. For the others the mutation rewrites the fields of Inst.raw; either
+//! way the result is decoded and only kept if it is still the same opcode.
+
+use crate::aarch64_gen::Rng;
+use crate::aarch64_operand::{operands, Operand, Reg};
+use crate::aarch64_reader::{decode, fad_get_addrmode, AddrMode, Inst, Op};
+use crate::aarch64_writer::encode;
+
+/// Attempts per mutation before giving up on the instruction.
+pub const MUTATION_TRIES: u32 = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mutation {
+    FlipCondition,
+    SwapRegisters,
+    PerturbImmediate,
+}
+
+pub const ALL_MUTATIONS: [Mutation; 3] = [Mutation::FlipCondition, Mutation::SwapRegisters, Mutation::PerturbImmediate];
+
+/// Position of the 4-bit condition field.
+fn cond_field(op: Op) -> Option<u32> {
+    use Op::*;
+    match op {
+        A64_BCOND => Some(0),
+        A64_CSEL | A64_CSINC | A64_CINC | A64_CSET | A64_CSINV | A64_CINV | A64_CSETM | A64_CSNEG | A64_CNEG
+        | A64_CCMN_REG | A64_CCMP_REG | A64_CCMN_IMM | A64_CCMP_IMM | A64_FCCMP | A64_FCCMPE | A64_FCSEL => Some(12),
+        _ => None,
+    }
+}
+
+/// Position and width of the immediate field, for the instructions the
+/// writer doesn't encode.
+fn imm_field(inst: &Inst) -> Option<(u32, u32)> {
+    use Op::*;
+    match inst.op {
+        A64_ADD_IMM | A64_SUB_IMM | A64_CMN_IMM | A64_CMP_IMM => Some((10, 12)),
+        A64_MOV_IMM | A64_SVC | A64_HVC | A64_SMC | A64_HLT => Some((5, 16)),
+        A64_LDP | A64_STP | A64_LDNP | A64_STNP | A64_LDP_FP | A64_STP_FP | A64_LDNP_FP | A64_STNP_FP => Some((15, 7)),
+        A64_LDR | A64_STR | A64_LDR_FP | A64_STR_FP | A64_PRFM => match fad_get_addrmode(inst.flags) {
+            AddrMode::AM_OFF_IMM if (inst.raw >> 24) & 1 == 1 => Some((10, 12)),
+            AddrMode::AM_OFF_IMM | AddrMode::AM_PRE | AddrMode::AM_POST => Some((12, 9)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn with_field(word: u32, pos: u32, width: u32, value: u32) -> u32 {
+    let mask = ((1u32 << width) - 1) << pos;
+    (word & !mask) | ((value << pos) & mask)
+}
+
+fn field(word: u32, pos: u32, width: u32) -> u32 {
+    (word >> pos) & ((1u32 << width) - 1)
+}
+
+/// A small step either way, or now and then anywhere in the range.
+fn perturb(rng: &mut Rng, value: i64, range: i64) -> i64 {
+    if rng.next_u32().is_multiple_of(4) {
+        return (rng.next_u64() % range as u64) as i64;
+    }
+    let step = 1 + (rng.next_u32() % 8) as i64;
+    if rng.next_u32().is_multiple_of(2) { value + step } else { value - step }
+}
+
+/// The operand with its register numbers cleared, so that operands compare
+/// equal if their registers are of the same class.
+fn class(operand: Operand) -> Operand {
+    let clear = |reg: Reg| match reg {
+        Reg::Gpr { w32, .. } => Reg::Gpr { n: 0, w32 },
+        Reg::Fp { prec, .. } => Reg::Fp { n: 0, prec },
+        Reg::Vec { va, .. } => Reg::Vec { n: 0, va },
+        Reg::Z { elem, .. } => Reg::Z { n: 0, elem },
+        Reg::P { elem, .. } => Reg::P { n: 0, elem },
+        other => other,
+    };
+    match operand {
+        Operand::Reg(reg) => Operand::Reg(clear(reg)),
+        other => other,
+    }
+}
+
+fn try_mutation(rng: &mut Rng, inst: &Inst, mutation: Mutation) -> Option<u32> {
+    let word = inst.raw;
+    match mutation {
+        Mutation::FlipCondition => {
+            let pos = cond_field(inst.op)?;
+            Some(word ^ (1 << pos))
+        }
+        Mutation::SwapRegisters => {
+            // Rd/Rt, Rn, Ra/Rt2, Rm
+            const FIELDS: [u32; 4] = [0, 5, 10, 16];
+            let a = FIELDS[rng.next_u32() as usize % 4];
+            let b = FIELDS[rng.next_u32() as usize % 4];
+            let (x, y) = (field(word, a, 5), field(word, b, 5));
+            if x == y {
+                return None;
+            }
+            let swapped = with_field(with_field(word, a, 5, y), b, 5, x);
+            let before: Vec<Operand> = operands(inst).into_iter().map(class).collect();
+            let after: Vec<Operand> = operands(&decode(swapped)).into_iter().map(class).collect();
+            (before == after).then_some(swapped)
+        }
+        Mutation::PerturbImmediate => {
+            if encode(inst).is_ok() {
+                let mut mutated = inst.clone();
+                match inst.op {
+                    Op::A64_MOVK => mutated.movk.imm16 = perturb(rng, inst.movk.imm16 as i64, 1 << 16) as _,
+                    Op::A64_TBZ | Op::A64_TBNZ => {
+                        mutated.tbz.offset = (perturb(rng, inst.tbz.offset as i64 / 4, 1 << 13) * 4) as _;
+                    }
+                    Op::A64_ADR => mutated.offset = perturb(rng, inst.offset, 1 << 20),
+                    Op::A64_ADRP => mutated.offset = perturb(rng, inst.offset >> 12, 1 << 20) << 12,
+                    Op::A64_BRK => mutated.imm = perturb(rng, inst.imm as i64, 1 << 16) as u64,
+                    Op::A64_BR | Op::A64_BLR | Op::A64_RET => return None,
+                    // B, BL, B.cond, CBZ, CBNZ and the literal loads
+                    _ => mutated.offset = perturb(rng, inst.offset / 4, 1 << 18) * 4,
+                }
+                return encode(&mutated).ok();
+            }
+            let (pos, width) = imm_field(inst)?;
+            let value = perturb(rng, field(word, pos, width) as i64, 1 << width);
+            Some(with_field(word, pos, width, value as u32))
+        }
+    }
+}
+
+/// A mutated encoding of inst with the same opcode; None if the mutation
+/// doesn't apply to it or keeps failing.
+pub fn mutate(rng: &mut Rng, inst: &Inst, mutation: Mutation) -> Option<u32> {
+    (0..MUTATION_TRIES).find_map(|_| {
+        let word = try_mutation(rng, inst, mutation)?;
+        (word != inst.raw && decode(word).op == inst.op).then_some(word)
+    })
+}
+
+/// The first of the mutations, in random order, that applies to inst.
+pub fn mutate_any(rng: &mut Rng, inst: &Inst) -> Option<(Mutation, u32)> {
+    let start = rng.next_u32() as usize;
+    (0..ALL_MUTATIONS.len()).find_map(|i| {
+        let mutation = ALL_MUTATIONS[(start + i) % ALL_MUTATIONS.len()];
+        mutate(rng, inst, mutation).map(|word| (mutation, word))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_reader::fad_get_cond;
+
+    #[test]
+    fn mutations_keep_opcode() {
+        let mut rng = Rng::new(7);
+        let beq = decode(0x54000040); // b.eq .+8
+        let bne = decode(mutate(&mut rng, &beq, Mutation::FlipCondition).unwrap());
+        assert_eq!(fad_get_cond(bne.flags), fad_get_cond(beq.flags) ^ 1);
+
+        let ldp = decode(0xA9400440); // ldp x0, x1, [x2]
+        let swapped = decode(mutate(&mut rng, &ldp, Mutation::SwapRegisters).unwrap());
+        assert_eq!(swapped.op, Op::A64_LDP);
+        let mut regs = [swapped.rd, swapped.rt2, swapped.rn];
+        regs.sort();
+        assert_eq!(regs, [0, 1, 2]);
+
+        let add = decode(0x91000420); // add x0, x1, #1
+        let perturbed = decode(mutate(&mut rng, &add, Mutation::PerturbImmediate).unwrap());
+        assert_eq!((perturbed.op, perturbed.rd, perturbed.rn), (Op::A64_ADD_IMM, 0, 1));
+        assert_ne!(perturbed.imm, 1);
+
+        assert_eq!(mutate(&mut rng, &add, Mutation::FlipCondition), None);
+    }
+}
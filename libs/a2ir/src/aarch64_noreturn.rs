@@ -0,0 +1,125 @@
+//! Functions that don't return (abort, __stack_chk_fail, panic handlers):
+//! the blocks calling them end at the call instead of falling through into
+//! whatever code follows, often the next function or a literal pool.
+//!
+//! The set is seeded from symbol names and the embedder's own entries, and
+//! grows by propagation: a function none of whose paths reach a return,
+//! an unknown indirect branch or a tail call to a returning function
+//! doesn't return either.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::aarch64_cfg::{Cfg, Terminator};
+use crate::aarch64_dynlink::PltStub;
+use crate::aarch64_program::{Program, INST_SIZE};
+use crate::aarch64_reader::Op;
+use crate::aarch64_relocate::target_address;
+
+/// Names of C, C++ and Rust runtime functions that never return.
+pub const NORETURN_NAMES: [&str; 18] = [
+    "abort", "exit", "_exit", "_Exit", "quick_exit", "pthread_exit", "longjmp", "siglongjmp", "__longjmp_chk",
+    "__stack_chk_fail", "__assert_fail", "__fortify_fail", "__chk_fail", "__cxa_throw", "__cxa_rethrow",
+    "_Unwind_Resume", "std::terminate", "rust_panic",
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NoReturn {
+    pub entries: BTreeSet<u64>,
+}
+
+impl NoReturn {
+    /// The functions and PLT stubs named in NORETURN_NAMES or extra.
+    pub fn from_symbols(names: &BTreeMap<u64, String>, stubs: &[PltStub], extra: &[&str]) -> NoReturn {
+        let listed = |name: &str| NORETURN_NAMES.contains(&name) || extra.contains(&name);
+        let mut entries: BTreeSet<u64> = names.iter().filter(|(_, n)| listed(n)).map(|(a, _)| *a).collect();
+        entries.extend(stubs.iter().filter(|s| s.symbol.as_deref().is_some_and(listed)).map(|s| s.addr));
+        NoReturn { entries }
+    }
+
+    /// Adds the functions, as (entry, end) ranges, that can't return given
+    /// the others, until there are no more; then ends the blocks of cfg at
+    /// their calls.
+    pub fn propagate(&mut self, program: &Program, cfg: &mut Cfg, functions: &[(u64, u64)]) {
+        for &(entry, _) in functions {
+            cfg.split_at(entry);
+        }
+        loop {
+            self.apply(program, cfg);
+            let before = self.entries.len();
+            for &(entry, end) in functions {
+                if !self.entries.contains(&entry) && !self.may_return(cfg, entry, end) {
+                    self.entries.insert(entry);
+                }
+            }
+            if self.entries.len() == before {
+                return;
+            }
+        }
+    }
+
+    fn may_return(&self, cfg: &Cfg, entry: u64, end: u64) -> bool {
+        let (mut seen, mut work) = (BTreeSet::new(), vec![entry]);
+        while let Some(start) = work.pop() {
+            if !seen.insert(start) {
+                continue;
+            }
+            let Some(block) = cfg.blocks.get(&start) else { return true };
+            let leaves = match block.terminator {
+                Terminator::Return | Terminator::Indirect | Terminator::End => true,
+                Terminator::TailCall(target) => !self.entries.contains(&target),
+                _ => false,
+            };
+            if leaves {
+                return true;
+            }
+            for next in cfg.successors(start) {
+                // Control leaving the function without a call or return.
+                if next < entry || next >= end {
+                    return true;
+                }
+                work.push(next);
+            }
+        }
+        false
+    }
+
+    /// Ends the block of every BL to a non-returning function at the call.
+    pub fn apply(&self, program: &Program, cfg: &mut Cfg) {
+        for (pc, inst) in program.iter() {
+            let Some(target) = target_address(inst, pc).filter(|t| inst.op == Op::A64_BL && self.entries.contains(t)) else {
+                continue;
+            };
+            cfg.split_at(pc + INST_SIZE);
+            if let Some((_, block)) = cfg.blocks.range_mut(..=pc).next_back() {
+                if block.end == pc + INST_SIZE {
+                    block.terminator = Terminator::NoReturnCall(target);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapper_of_abort() {
+        let program = Program::from_words(&[
+            0x94000004, // bl .+16           (die)
+            0xD503201F, // nop               (would fall into the next function)
+            0x94000003, // bl .+12           (abort)
+            0xD503201F, // nop
+            0x94000001, // bl .+4            (abort)
+            0xD65F03C0, // ret               abort
+        ], 0x1000);
+        let names = BTreeMap::from([(0x1014, "abort".to_string())]);
+        let mut noreturn = NoReturn::from_symbols(&names, &[], &[]);
+        let mut cfg = Cfg::build(&program);
+        noreturn.propagate(&program, &mut cfg, &[(0x1000, 0x1008), (0x1008, 0x1010), (0x1010, 0x1014)]);
+        // die at 0x1010 only calls abort, and main only calls die.
+        assert_eq!(noreturn.entries.into_iter().collect::<Vec<_>>(), vec![0x1000, 0x1008, 0x1010, 0x1014]);
+        assert_eq!(cfg.blocks[&0x1000].terminator, Terminator::NoReturnCall(0x1010));
+        assert!(cfg.successors(0x1000).is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+//! Normalized instructions, for fuzzy code search: the assembly text with
+//! register numbers, immediates and branch targets replaced by `?`, and
+//! byte signatures with the encoding bits of those fields wildcarded.
+//!
+//! ```text
+//! add x3, x1, #0x10      =>  add x?, x?, #?
+//! bl  0x4010             =>  bl ?                { ?? ?? ?? 9? }
+//! ```
+//!
+//! The wildcard bits of a word are the ones that can flip without changing
+//! its normalized form, which keeps the signatures exact for every encoding
+//! the decoder knows. SP, the zero registers, shifts and conditions are
+//! structural and stay; so does whatever makes the decoder pick a different
+//! alias or opcode.
+
+use crate::aarch64_listing::{index_modifier, operand_text, reg_text};
+use crate::aarch64_mnemonic::inst_mnemonic;
+use crate::aarch64_operand::{operands, Operand, Predication, Reg};
+use crate::aarch64_program::Program;
+use crate::aarch64_reader::{decode, AddrMode, Inst};
+
+/// What normalization masks out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    /// Numbers of general, FP/SIMD, SVE and predicate registers.
+    pub registers: bool,
+    /// Immediates and memory offsets.
+    pub immediates: bool,
+    /// PC-relative targets; kept ones print relative to the instruction.
+    pub labels: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> NormalizeOptions {
+        NormalizeOptions { registers: true, immediates: true, labels: true }
+    }
+}
+
+fn reg_pattern(reg: Reg, options: &NormalizeOptions) -> String {
+    let zeroed = match reg {
+        _ if !options.registers => return reg_text(reg),
+        Reg::Gpr { w32, .. } => Reg::Gpr { n: 0, w32 },
+        Reg::Fp { prec, .. } => Reg::Fp { n: 0, prec },
+        Reg::Vec { va, .. } => Reg::Vec { n: 0, va },
+        Reg::Z { elem, .. } => Reg::Z { n: 0, elem },
+        Reg::P { elem, .. } => Reg::P { n: 0, elem },
+        Reg::Sp { .. } | Reg::Zr { .. } | Reg::Ffr => return reg_text(reg),
+    };
+    reg_text(zeroed).replacen('0', "?", 1)
+}
+
+fn operand_pattern(operand: Operand, options: &NormalizeOptions) -> String {
+    let imm = |v: i64| if options.immediates { "#?".to_string() } else { format!("#{:#x}", v) };
+    match operand {
+        Operand::Reg(reg) => reg_pattern(reg, options),
+        Operand::Governing { n, mode } => {
+            let rendered = reg_pattern(Reg::P { n, elem: None }, options);
+            match mode {
+                Some(Predication::Merging) => rendered + "/m",
+                Some(Predication::Zeroing) => rendered + "/z",
+                None => rendered,
+            }
+        }
+        Operand::Imm(v) | Operand::Mask(v) => imm(v as i64),
+        Operand::Mul(v) => format!("mul {}", imm(v as i64)),
+        Operand::ZElem { n, elem, index } => {
+            let index = if options.immediates { "?".to_string() } else { index.to_string() };
+            format!("{}[{}]", reg_pattern(Reg::Z { n, elem: Some(elem) }, options), index)
+        }
+        Operand::Shift { .. } | Operand::FImm(_) | Operand::Pattern(_) | Operand::Rotation(_) | Operand::Hint(_) | Operand::Barrier(_) | Operand::PState(_) | Operand::ZaSlice { .. } | Operand::ZaArray { .. }
+        | Operand::ZaTiles(_) | Operand::ZaTile { .. } | Operand::SysReg(_) | Operand::SysOp { .. }
+        | Operand::SysOperation(_) => operand_text(operand, 0),
+        Operand::Label(offset) => if options.labels { "?".to_string() } else { format!(".{:+#x}", offset) },
+        Operand::PageLabel(offset) => if options.labels { "?".to_string() } else { format!("page{:+#x}", offset) },
+        Operand::Mem { base, offset, mode } => {
+            let base = reg_pattern(base, options);
+            match mode {
+                AddrMode::AM_SIMPLE => format!("[{}]", base),
+                AddrMode::AM_PRE => format!("[{}, {}]!", base, imm(offset)),
+                AddrMode::AM_POST => format!("[{}], {}", base, imm(offset)),
+                _ => format!("[{}, {}]", base, imm(offset)),
+            }
+        }
+        Operand::MemPostReg { base, index } => format!("[{}], {}", reg_pattern(base, options), reg_pattern(index, options)),
+        Operand::List { first, len, va, lane } => {
+            let mut regs: Vec<String> = (0..len).map(|i| reg_pattern(Reg::Vec { n: (first + i) % 32, va }, options)).collect();
+            // As in the listing, v?.s rather than v?.4s for a single structure.
+            if lane.is_some() {
+                for reg in &mut regs {
+                    let dot = reg.rfind('.').map_or(0, |d| d + 1);
+                    let count = reg[dot..].bytes().take_while(u8::is_ascii_digit).count();
+                    reg.replace_range(dot..dot + count, "");
+                }
+            }
+            let lane = match lane {
+                Some(_) if options.immediates => "[?]".to_string(),
+                Some(l) => format!("[{}]", l),
+                None => String::new(),
+            };
+            format!("{{{}}}{}", regs.join(", "), lane)
+        }
+        Operand::ZList { first, len, elem } => {
+            let regs: Vec<String> = (0..len).map(|i| reg_pattern(Reg::Z { n: (first + i) % 32, elem: Some(elem) }, options)).collect();
+            format!("{{{}}}", regs.join(", "))
+        }
+        Operand::MemVl { base, offset } => format!("[{}, {}, mul vl]", reg_pattern(base, options), imm(offset)),
+        Operand::MemIndex { base, index, extend, shift } => {
+            format!("[{}, {}{}]", reg_pattern(base, options), reg_pattern(index, options), index_modifier(extend, shift))
+        }
+    }
+}
+
+/// The normalized text of inst.
+pub fn normalize(inst: &Inst, options: &NormalizeOptions) -> String {
+    let ops: Vec<String> = operands(inst).into_iter().map(|o| operand_pattern(o, options)).collect();
+    if ops.is_empty() { inst_mnemonic(inst) } else { format!("{} {}", inst_mnemonic(inst), ops.join(", ")) }
+}
+
+/// A word with wildcard bits: a word w matches if w & mask == value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Signature {
+    pub value: u32,
+    pub mask: u32,
+}
+
+impl Signature {
+    /// The signature of inst: its word, with the bits that don't change its
+    /// normalized form masked out.
+    pub fn of(inst: &Inst, options: &NormalizeOptions) -> Signature {
+        let word = inst.raw();
+        let text = normalize(inst, options);
+        let mask = (0..32).map(|b| 1u32 << b).filter(|&bit| normalize(&decode(word ^ bit), options) != text).fold(0, |m, b| m | b);
+        Signature { value: word & mask, mask }
+    }
+
+    pub fn matches(&self, word: u32) -> bool {
+        word & self.mask == self.value
+    }
+}
+
+/// The signatures in YARA's hex string syntax, little-endian, a nibble
+/// being `?` if any of its bits is a wildcard.
+pub fn yara_hex(signatures: &[Signature]) -> String {
+    let mut bytes = Vec::new();
+    for sig in signatures {
+        for i in 0..4 {
+            let nibble = |shift: u32| {
+                let bits = 8 * i + shift;
+                if (sig.mask >> bits) & 0xF == 0xF { format!("{:X}", (sig.value >> bits) & 0xF) } else { "?".to_string() }
+            };
+            bytes.push(format!("{}{}", nibble(4), nibble(0)));
+        }
+    }
+    format!("{{ {} }}", bytes.join(" "))
+}
+
+/// Addresses where the consecutive instructions of program match the
+/// signatures.
+pub fn find_signature(program: &Program, signatures: &[Signature]) -> Vec<u64> {
+    let mut out = Vec::new();
+    for (start, end) in program.regions() {
+        let words: Vec<(u64, u32)> = program.range(start..end).map(|(a, i)| (a, i.raw())).collect();
+        for window in words.windows(signatures.len().max(1)) {
+            if window.iter().zip(signatures).all(|((_, w), sig)| sig.matches(*w)) {
+                out.push(window[0].0);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masked_registers_and_targets() {
+        let options = NormalizeOptions::default();
+        let (a, b) = (decode(0x91004023), decode(0x910100A7)); // add x3, x1, #0x10; add x7, x5, #0x40
+        assert_eq!(normalize(&a, &options), "add x?, x?, #?");
+        assert_eq!(normalize(&a, &options), normalize(&b, &options));
+        assert_eq!(normalize(&a, &NormalizeOptions { registers: false, ..options }), "add x3, x1, #?");
+
+        let bl = Signature::of(&decode(0x94000004), &options);
+        assert_eq!(bl, Signature { value: 0x94000000, mask: 0xFC000000 });
+        let sig = [Signature::of(&a, &options), bl];
+        assert_eq!(yara_hex(&sig), "{ ?? ?? ?? 91 ?? ?? ?? 9? }");
+
+        let program = Program::from_words(&[0xD503201F, 0x910100A7, 0x97FFFFFF, 0xD65F03C0], 0x1000);
+        assert_eq!(find_signature(&program, &sig), vec![0x1004]);
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn single_structure_lists() {
+        let options = NormalizeOptions::default();
+        // ld2 {v23.d, v24.d}[1], [x0]; ld1r {v2.4s}, [x0]
+        assert_eq!(normalize(&decode(0x4D608417), &options), "ld2 {v?.d, v?.d}[?], [x?]");
+        assert_eq!(normalize(&decode(0x4D40C802), &options), "ld1r {v?.4s}, [x?]");
+    }
+}
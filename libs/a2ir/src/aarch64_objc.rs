@@ -0,0 +1,169 @@
+//! Objective-C metadata: method lists, for naming the functions that
+//! implement methods, and the selectors of objc_msgSend calls, for
+//! resolving them to those implementations.
+//!
+//! ```text
+//! adrp x8, selref@PAGE
+//! ldr  x1, [x8, selref@PAGEOFF]   // the selector, e.g. "initWithFrame:"
+//! bl   _objc_msgSend              // -[UIView initWithFrame:], ...
+//! ```
+//!
+//! The crate doesn't read Mach-O, so the data comes from the caller with
+//! chained fixups applied: the method list addresses from the class data
+//! in __objc_classlist, and the objc_msgSend stubs. Both the pointer
+//! layout and the relative one of recent toolchains (12-byte entries of
+//! offsets, names through selector references) are read. Swift type
+//! metadata isn't parsed.
+
+use std::collections::BTreeMap;
+
+use crate::aarch64_constprop::{propagate, ConstOptions};
+use crate::aarch64_defuse::{def_use, Loc};
+use crate::aarch64_program::Program;
+use crate::aarch64_reader::Op;
+use crate::aarch64_relocate::target_address;
+
+/// method_list_t flags, in the high bits of entsize.
+const RELATIVE: u32 = 0x8000_0000;
+/// Relative names point at the selector instead of a selector reference.
+const UNIQUED: u32 = 0x4000_0000;
+
+fn bytes(data: &[u8], data_base: u64, addr: u64, len: usize) -> Result<&[u8], String> {
+    let offset = addr.checked_sub(data_base).ok_or(format!("objc: {:#x} before the data", addr))? as usize;
+    data.get(offset..offset + len).ok_or(format!("objc: {:#x} past the data", addr))
+}
+
+fn u32_at(data: &[u8], data_base: u64, addr: u64) -> Result<u32, String> {
+    Ok(u32::from_le_bytes(bytes(data, data_base, addr, 4)?.try_into().expect("4 bytes")))
+}
+
+fn u64_at(data: &[u8], data_base: u64, addr: u64) -> Result<u64, String> {
+    Ok(u64::from_le_bytes(bytes(data, data_base, addr, 8)?.try_into().expect("8 bytes")))
+}
+
+/// The NUL-terminated string at addr.
+pub fn c_string(data: &[u8], data_base: u64, addr: u64) -> Result<String, String> {
+    bytes(data, data_base, addr, 0)?;
+    let rest = &data[(addr - data_base) as usize..];
+    let len = rest.iter().position(|&b| b == 0).ok_or(format!("objc: unterminated string at {:#x}", addr))?;
+    Ok(String::from_utf8_lossy(&rest[..len]).into_owned())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Method {
+    pub selector: String,
+    pub imp: u64,
+}
+
+/// The methods of the method_list_t at addr.
+pub fn method_list(data: &[u8], data_base: u64, addr: u64) -> Result<Vec<Method>, String> {
+    let entsize_flags = u32_at(data, data_base, addr)?;
+    let count = u32_at(data, data_base, addr + 4)?;
+    let entsize = (entsize_flags & 0xFFFC) as u64;
+    let relative = entsize_flags & RELATIVE != 0;
+    if entsize < if relative { 12 } else { 24 } {
+        return Err(format!("objc: bad method entry size {} at {:#x}", entsize, addr));
+    }
+    let mut out = Vec::new();
+    for i in 0..count as u64 {
+        let entry = addr + 8 + i * entsize;
+        let method = if relative {
+            let field = |n: u64| -> Result<u64, String> {
+                let offset = u32_at(data, data_base, entry + 4 * n)? as i32;
+                Ok((entry + 4 * n).wrapping_add(offset as i64 as u64))
+            };
+            let name = field(0)?;
+            let selector = if entsize_flags & UNIQUED != 0 { name } else { u64_at(data, data_base, name)? };
+            Method { selector: c_string(data, data_base, selector)?, imp: field(2)? }
+        } else {
+            let selector = u64_at(data, data_base, entry)?;
+            Method { selector: c_string(data, data_base, selector)?, imp: u64_at(data, data_base, entry + 16)? }
+        };
+        out.push(method);
+    }
+    Ok(out)
+}
+
+/// "-[class selector]", or "+[...]" for the methods of a metaclass.
+pub fn method_name(class: &str, meta: bool, selector: &str) -> String {
+    format!("{}[{} {}]", if meta { '+' } else { '-' }, class, selector)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageSend {
+    pub pc: u64,
+    pub selector: String,
+    /// The implementations of the selector among the known methods; any
+    /// may run, depending on the class of the receiver.
+    pub candidates: Vec<u64>,
+}
+
+/// The calls to objc_msgSend (at any address in msg_send) in the
+/// functions, as (entry, end) ranges, whose selector is loaded from a
+/// constant selector reference; methods are the implementations by
+/// selector.
+pub fn message_sends(program: &Program, functions: &[(u64, u64)], msg_send: &[u64], data: &[u8], data_base: u64,
+                     methods: &[Method]) -> Vec<MessageSend> {
+    let mut out = Vec::new();
+    for &(entry, end) in functions {
+        let block: Vec<_> = program.range(entry..end).map(|(a, i)| (a, i.clone())).collect();
+        let resolved = propagate(&block, &ConstOptions::default());
+        let mut selref = None;
+        for (pc, inst) in &block {
+            if inst.op == Op::A64_BL && target_address(inst, *pc).is_some_and(|t| msg_send.contains(&t)) {
+                let selector = selref.and_then(|r| u64_at(data, data_base, r).ok()).and_then(|s| c_string(data, data_base, s).ok());
+                if let Some(selector) = selector {
+                    let mut candidates: Vec<u64> = methods.iter().filter(|m| m.selector == selector).map(|m| m.imp).collect();
+                    candidates.sort();
+                    candidates.dedup();
+                    out.push(MessageSend { pc: *pc, selector, candidates });
+                }
+            }
+            // The last load into x1, the selector argument.
+            if inst.op == Op::A64_LDR && inst.rd == 1 {
+                selref = resolved.data_addresses.get(pc).copied();
+            } else if def_use(inst).defs.contains(&Loc::X(1)) {
+                selref = None;
+            }
+        }
+    }
+    out
+}
+
+/// Names for the implementations of the methods of a class.
+pub fn method_symbols(class: &str, meta: bool, methods: &[Method]) -> BTreeMap<u64, String> {
+    methods.iter().map(|m| (m.imp, method_name(class, meta, &m.selector))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_methods_and_sends() {
+        // 0x5000: a relative method list of one method, then its selector
+        // reference and the selector.
+        let mut data = Vec::new();
+        data.extend((RELATIVE | 12).to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        for (field, target) in [(0x5008i64, 0x5020i64), (0x500C, 0x5028), (0x5010, 0x1010)] {
+            data.extend(((target - field) as i32).to_le_bytes());
+        }
+        data.extend([0; 12]);
+        data.extend(0x5028u64.to_le_bytes());
+        data.extend(b"init\0");
+        let methods = method_list(&data, 0x5000, 0x5000).unwrap();
+        assert_eq!(methods, vec![Method { selector: "init".to_string(), imp: 0x1010 }]);
+        assert_eq!(method_symbols("Foo", false, &methods)[&0x1010], "-[Foo init]");
+
+        let program = Program::from_words(&[
+            0x90000028, // 0x1000: adrp x8, 0x5000
+            0xF9401101, //         ldr x1, [x8, #0x20]
+            0x97FFFFFC, //         bl 0xff8          (objc_msgSend)
+            0xD65F03C0, //         ret
+            0xD65F03C0, // 0x1010: -[Foo init]
+        ], 0x1000);
+        let sends = message_sends(&program, &[(0x1000, 0x1010)], &[0xFF8], &data, 0x5000, &methods);
+        assert_eq!(sends, vec![MessageSend { pc: 0x1008, selector: "init".to_string(), candidates: vec![0x1010] }]);
+    }
+}
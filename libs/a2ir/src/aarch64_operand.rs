@@ -0,0 +1,1479 @@
+//! Typed operands of decoded instructions, in assembly order.
+//!
+//! Inst keeps register numbers in flat u8 fields whose meaning depends on the
+//! opcode; `operands` turns them into registers that know their file and
+//! facet, immediates, labels and memory references. Besides the general
+//! purpose and SIMD&FP files, the model has the SVE Z and P registers, and
+//! distinguishes a predicate that governs an operation (Pg/M, Pg/Z) from a
+//! predicate that is an ordinary source or destination.
+
+use crate::aarch64_defuse::{is_memory, is_sve_memory, Loc};
+use crate::aarch64_group::Group;
+use crate::aarch64_mnemonic::{hint_name, move_wide_preferred, predicate_move};
+use crate::aarch64_reader::FlagMasks::{SIMD_SIGNED, W32};
+use crate::aarch64_reader::{fad_get_addrmode, fad_get_prec, fad_get_vec_arrangement, AddrMode, ExtendType, FPSize, Inst,
+    MemOrdering, Op, PStateField, Registries, VectorArrangement};
+use crate::aarch64_sysreg::SysReg;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Reg {
+    /// X0...X30, or W0...W30 if w32
+    Gpr { n: u8, w32: bool },
+    Sp { w32: bool },
+    Zr { w32: bool },
+    /// Scalar facet of V0...V31; prec is an FPSize.
+    Fp { n: u8, prec: u8 },
+    /// V0...V31 with a VectorArrangement.
+    Vec { n: u8, va: u8 },
+    /// SVE Z0...Z31; elem is the FPSize of the elements, if qualified.
+    Z { n: u8, elem: Option<u8> },
+    /// SVE P0...P15 as a data operand; elem as for Z.
+    P { n: u8, elem: Option<u8> },
+    /// The SVE first-fault register
+    Ffr,
+}
+
+impl Reg {
+    /// General purpose register from our numbering (see Registries).
+    pub fn gpr(r: u8, w32: bool) -> Reg {
+        match r {
+            Registries::ZERO_REG => Reg::Zr { w32 },
+            Registries::STACK_POINTER => Reg::Sp { w32 },
+            _ => Reg::Gpr { n: r, w32 },
+        }
+    }
+
+    /// The location the register is a facet of. V registers are the bottom
+    /// 128 bits of the Z registers, but are tracked as separate locations.
+    pub fn loc(self) -> Option<Loc> {
+        match self {
+            Reg::Gpr { n, .. } => Some(Loc::X(n)),
+            Reg::Sp { .. } => Some(Loc::SP),
+            Reg::Zr { .. } => None,
+            Reg::Fp { n, .. } | Reg::Vec { n, .. } => Some(Loc::V(n)),
+            Reg::Z { n, .. } => Some(Loc::Z(n)),
+            Reg::P { n, .. } => Some(Loc::P(n)),
+            Reg::Ffr => Some(Loc::FFR),
+        }
+    }
+}
+
+pub use crate::aarch64_reader::Predication;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operand {
+    Reg(Reg),
+    /// Governing predicate Pg; mode is None for instructions without a
+    /// /M or /Z qualifier (e.g. the governing predicate of stores).
+    Governing { n: u8, mode: Option<Predication> },
+    Imm(u64),
+    /// The bitmask immediate of a logical instruction, at the width of the
+    /// register or element; printed unsigned and in hex.
+    Mask(u64),
+    /// Floating-point immediate, e.g. the #0.0 of FCMEQ (zero)
+    FImm(f64),
+    /// The element count pattern of PTRUE, e.g. VL4 or POW2; see
+    /// `pattern_name`.
+    Pattern(u8),
+    /// The multiplier of an element count, e.g. the MUL #3 of CNTW.
+    Mul(u8),
+    /// The rotation of a complex number in degrees, e.g. the #90 of FCADD.
+    Rotation(u16),
+    /// An element of a Z register with the FPSize elem, by index: the
+    /// Zm.T[imm] of the SVE multiplies by element and of DUP.
+    ZElem { n: u8, elem: u8, index: u8 },
+    /// The immediate of HINT, ISB and CLREX, or the operand of the hint
+    /// aliases that take one, e.g. the C of BTI C; see `hint_operand`.
+    Hint(u8),
+    /// The CRm option of DMB and DSB, e.g. ISH; see `barrier_name`.
+    Barrier(u8),
+    /// The PSTATE field MSR (immediate) writes, or the SVCR field of
+    /// SMSTART and SMSTOP.
+    PState(PStateField),
+    /// Shift applied to the preceding immediate, e.g. the LSL #16 of MOVK.
+    Shift { typ: u8, amount: u8 },
+    /// PC-relative target, as offset from the instruction.
+    Label(i64),
+    /// The page target of ADRP, as offset from the 4KB page of the
+    /// instruction.
+    PageLabel(i64),
+    /// Memory reference; mode is an AddrMode and offset applies to
+    /// AM_OFF_IMM, AM_PRE and AM_POST by an immediate.
+    Mem { base: Reg, offset: i64, mode: u8 },
+    /// [base], Xm: AM_POST by a register, only for the LDx and STx structure
+    /// loads and stores.
+    MemPostReg { base: Reg, index: Reg },
+    /// len consecutive V registers starting at first, wrapping around from
+    /// V31 to V0, with the VectorArrangement va; lane is the element of the
+    /// single structure forms, e.g. {V1.S, V2.S}[3].
+    List { first: u8, len: u8, va: u8, lane: Option<u8> },
+    /// len consecutive Z registers, wrapping around like List, with the
+    /// FPSize elem: the registers of the SVE loads and stores.
+    ZList { first: u8, len: u8, elem: u8 },
+    /// [base, #offset, MUL VL]: offset in multiples of the vector length.
+    MemVl { base: Reg, offset: i64 },
+    /// [base, index, LSL #shift], or with the ExtendType extend of 32-bit
+    /// offsets, e.g. [X0, Z1.D, SXTW #3].
+    MemIndex { base: Reg, index: Reg, extend: Option<u8>, shift: u8 },
+    /// SME: a horizontal or vertical slice of the ZA tile with FPSize elem,
+    /// selected by W(index) plus offset, e.g. ZA1V.S[W13, 3]; in braces for
+    /// LD1 and ST1.
+    ZaSlice { tile: u8, elem: u8, vertical: bool, index: u8, offset: u8, braced: bool },
+    /// SME: the vector ZA[W(index), offset] of the ZA array (LDR and STR).
+    ZaArray { index: u8, offset: u8 },
+    /// SME: the tiles ZA0.D...ZA7.D that ZERO clears, a bit each.
+    ZaTiles(u8),
+    /// SME: a whole ZA tile with FPSize elem, e.g. ZA3.S.
+    ZaTile { tile: u8, elem: u8 },
+    /// The system register of MRS and MSR (register).
+    SysReg(SysReg),
+    /// The #op1, Cn, Cm, #op2 of SYS and SYSL.
+    SysOp { op1: u8, crn: u8, crm: u8, op2: u8 },
+    /// The operation of an architectural SYS alias, e.g. CIVAC of DC CIVAC.
+    SysOperation(&'static str),
+}
+
+impl Operand {
+    /// The register numbers of a List, in order; empty for other operands.
+    pub fn list_regs(self) -> Vec<u8> {
+        match self {
+            Operand::List { first, len, .. } | Operand::ZList { first, len, .. } => (0..len).map(|i| (first + i) % 32).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// What AM_POST adds to the base register after the access.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PostIndex {
+    Imm(i64),
+    Reg(Reg),
+}
+
+impl Inst {
+    /// Whether an atomic memory operation is its ST* alias (STADD, STSETL,
+    /// ...): the old value goes to XZR, and there is no acquire.
+    pub fn atomic_store_alias(&self) -> bool {
+        matches!(self.op, Op::A64_LDADD | Op::A64_LDCLR | Op::A64_LDEOR | Op::A64_LDSET | Op::A64_LDSMAX | Op::A64_LDSMIN
+            | Op::A64_LDUMAX | Op::A64_LDUMIN)
+            && self.rd == Registries::ZERO_REG
+            && self.ldst_order.load == MemOrdering::MO_NONE as u16
+    }
+
+    /// The post-increment of post-indexed loads and stores; None for other
+    /// addressing modes and instructions.
+    pub fn post_index(&self) -> Option<PostIndex> {
+        if !(is_memory(&self.op) || is_structure(self.op)) || fad_get_addrmode(self.flags) != AddrMode::AM_POST {
+            return None;
+        }
+        Some(if !is_structure(self.op) {
+            PostIndex::Imm(self.offset)
+        } else if self.rm != Registries::ZERO_REG {
+            PostIndex::Reg(Reg::gpr(self.rm, false))
+        } else {
+            PostIndex::Imm(self.simd_ldst.offset as i64)
+        })
+    }
+}
+
+/// The name of a PTRUE pattern, e.g. "vl16"; None for the unnamed
+/// encodings, which assemble as immediates.
+pub fn pattern_name(pattern: u8) -> Option<String> {
+    match pattern {
+        0 => Some("pow2".to_string()),
+        1..=8 => Some(format!("vl{}", pattern)),
+        9..=13 => Some(format!("vl{}", 16 << (pattern - 9))),
+        29 => Some("mul4".to_string()),
+        30 => Some("mul3".to_string()),
+        31 => Some("all".to_string()),
+        _ => None,
+    }
+}
+
+/// The operand of the hint aliases that take one, by the HINT immediate.
+pub fn hint_operand(imm: u8) -> Option<&'static str> {
+    match imm {
+        17 => Some("csync"),
+        34 => Some("c"),
+        36 => Some("j"),
+        38 => Some("jc"),
+        _ => None,
+    }
+}
+
+/// The name of the DMB and DSB option CRm; None for the reserved ones.
+pub fn barrier_name(crm: u8) -> Option<&'static str> {
+    ["", "oshld", "oshst", "osh", "", "nshld", "nshst", "nsh", "", "ishld", "ishst", "ish", "", "ld", "st", "sy"]
+        .get(crm as usize).copied().filter(|name| !name.is_empty())
+}
+
+/// The SVE data processing instructions without a Zm operand: the unary
+/// ones and the shifts by an immediate.
+pub fn is_sve_unary(op: Op) -> bool {
+    use Op::*;
+    matches!(op, A64_ASR_IMM_Z | A64_LSR_IMM_Z | A64_LSL_IMM_Z | A64_ASRD_Z | A64_EXTEND_Z | A64_ABS_Z | A64_NEG_Z
+        | A64_CLS_Z | A64_CLZ_Z | A64_CNT_Z | A64_CNOT_Z | A64_NOT_Z | A64_FABS_Z | A64_FNEG_Z | A64_FRINT_Z
+        | A64_FRINTX_Z | A64_FRECPX_Z | A64_FSQRT_Z | A64_FCVT_Z | A64_FCVTZ_Z | A64_CVTF_Z)
+}
+
+fn sve_data_operands(inst: &Inst) -> Vec<Operand> {
+    use Op::*;
+    let esize = inst.sve.esize;
+    let z = |n| Operand::Reg(Reg::Z { n, elem: Some(esize) });
+    let governing = inst.sve.pg.map(|n| Operand::Governing { n, mode: inst.sve.mode });
+    let gpr = |n| Operand::Reg(Reg::gpr(n, inst.flags & W32 != 0));
+    let fp = |n| Operand::Reg(Reg::Fp { n, prec: esize });
+    let zm_elem = |elem| Operand::ZElem { n: inst.rm, elem, index: inst.offset as u8 };
+    let rotation = Operand::Rotation(inst.imm as u16);
+    match inst.op {
+        // The shifts by an immediate and by a vector may be unpredicated.
+        A64_ASR_IMM_Z | A64_LSR_IMM_Z | A64_LSL_IMM_Z | A64_ASRD_Z => {
+            [z(inst.rd)].into_iter().chain(governing).chain([z(inst.rn), Operand::Imm(inst.imm)]).collect()
+        }
+        A64_ASR_WIDE_Z | A64_LSR_WIDE_Z | A64_LSL_WIDE_Z => {
+            let zm = Operand::Reg(Reg::Z { n: inst.rm, elem: Some(FPSize::FSZ_D) });
+            [z(inst.rd)].into_iter().chain(governing).chain([z(inst.rn), zm]).collect()
+        }
+        A64_MAD_Z | A64_MSB_Z | A64_FMAD_Z | A64_FMSB_Z | A64_FNMAD_Z | A64_FNMSB_Z => {
+            vec![z(inst.rd), governing.expect("pg"), z(inst.rm), z(inst.ra)]
+        }
+        A64_FCVT_Z | A64_FCVTZ_Z | A64_CVTF_Z => {
+            vec![z(inst.rd), governing.expect("pg"), Operand::Reg(Reg::Z { n: inst.rn, elem: Some(inst.sve.src) })]
+        }
+        op if is_sve_unary(op) => vec![z(inst.rd), governing.expect("pg"), z(inst.rn)],
+        A64_ADDV_Z | A64_MAXV_Z | A64_MINV_Z | A64_ORV_Z | A64_EORV_Z | A64_ANDV_Z | A64_FADDV_Z | A64_FMAXNMV_Z
+        | A64_FMINNMV_Z | A64_FMAXV_Z | A64_FMINV_Z => {
+            let prec = if inst.op == A64_ADDV_Z { FPSize::FSZ_D } else { esize };
+            vec![Operand::Reg(Reg::Fp { n: inst.rd, prec }), governing.expect("pg"), z(inst.rn)]
+        }
+        A64_FADDA_Z => {
+            let v = Operand::Reg(Reg::Fp { n: inst.rd, prec: esize });
+            vec![v, governing.expect("pg"), v, z(inst.rm)]
+        }
+        A64_CMP_Z | A64_CMP_WIDE_Z | A64_CMP_IMM_Z | A64_FCM_Z | A64_FAC_Z | A64_FCM_ZERO_Z => {
+            let second = match inst.op {
+                A64_CMP_WIDE_Z => Operand::Reg(Reg::Z { n: inst.rm, elem: Some(FPSize::FSZ_D) }),
+                A64_CMP_IMM_Z => Operand::Imm(inst.imm),
+                A64_FCM_ZERO_Z => Operand::FImm(0.0),
+                _ => z(inst.rm),
+            };
+            vec![Operand::Reg(Reg::P { n: inst.rd, elem: Some(esize) }), governing.expect("pg"), z(inst.rn), second]
+        }
+        A64_INDEX_IMM_Z => vec![z(inst.rd), Operand::Imm(inst.imm), Operand::Imm(inst.offset as u64)],
+        A64_INDEX_IMM_REG_Z => vec![z(inst.rd), Operand::Imm(inst.imm), gpr(inst.rm)],
+        A64_INDEX_REG_IMM_Z => vec![z(inst.rd), gpr(inst.rn), Operand::Imm(inst.imm)],
+        A64_INDEX_REG_Z => vec![z(inst.rd), gpr(inst.rn), gpr(inst.rm)],
+        A64_ADR_Z => {
+            let extend = match inst.extend.typ as u8 {
+                ExtendType::UXTX => None,
+                typ => Some(typ),
+            };
+            let index = Reg::Z { n: inst.rm, elem: Some(esize) };
+            vec![z(inst.rd), Operand::MemIndex { base: Reg::Z { n: inst.rn, elem: Some(esize) }, index, extend, shift: inst.extend.lsl as u8 }]
+        }
+        A64_ORR_IMM_Z | A64_EOR_IMM_Z | A64_AND_IMM_Z => vec![z(inst.rd), z(inst.rn), Operand::Mask(inst.imm)],
+        A64_DUPM_Z => vec![z(inst.rd), Operand::Mask(inst.imm)],
+        A64_ADD_IMM_Z | A64_SUB_IMM_Z | A64_SUBR_IMM_Z | A64_QADD_IMM_Z | A64_QSUB_IMM_Z | A64_MAX_IMM_Z | A64_MIN_IMM_Z
+        | A64_MUL_IMM_Z => vec![z(inst.rd), z(inst.rn), Operand::Imm(inst.imm)],
+        A64_DUP_IMM_Z => vec![z(inst.rd), Operand::Imm(inst.imm)],
+        A64_CPY_IMM_Z => vec![z(inst.rd), governing.expect("pg"), Operand::Imm(inst.imm)],
+        A64_DUP_REG_Z => vec![z(inst.rd), gpr(inst.rn)],
+        A64_CPY_REG_Z => vec![z(inst.rd), governing.expect("pg"), gpr(inst.rn)],
+        A64_CPY_FP_Z => vec![z(inst.rd), governing.expect("pg"), fp(inst.rn)],
+        // Element 0 is the scalar register.
+        A64_DUP_ELEM_Z if inst.offset == 0 => vec![z(inst.rd), fp(inst.rn)],
+        A64_DUP_ELEM_Z => vec![z(inst.rd), Operand::ZElem { n: inst.rn, elem: esize, index: inst.offset as u8 }],
+        // MOV Zd.T, Pg/M, Zn.T
+        A64_SEL_Z if inst.rd == inst.rm => {
+            vec![z(inst.rd), Operand::Governing { n: inst.sve.pg.unwrap_or(0), mode: Some(Predication::Merging) }, z(inst.rn)]
+        }
+        A64_MOVPRFX_Z => match governing {
+            Some(governing) => vec![z(inst.rd), governing, z(inst.rn)],
+            None => vec![Operand::Reg(Reg::Z { n: inst.rd, elem: None }), Operand::Reg(Reg::Z { n: inst.rn, elem: None })],
+        },
+        // ALL and MUL #1 are the defaults.
+        A64_CNT_ELEMS | A64_INC_ELEMS | A64_DEC_ELEMS | A64_QINC_ELEMS | A64_QDEC_ELEMS | A64_INC_ELEMS_Z | A64_DEC_ELEMS_Z
+        | A64_QINC_ELEMS_Z | A64_QDEC_ELEMS_Z => {
+            let mut out = match inst.op {
+                A64_INC_ELEMS_Z | A64_DEC_ELEMS_Z | A64_QINC_ELEMS_Z | A64_QDEC_ELEMS_Z => vec![z(inst.rd)],
+                // SQINCB Xdn, Wdn sign-extends the 32-bit result.
+                _ if inst.flags & W32 != 0 && inst.flags & SIMD_SIGNED != 0 => {
+                    vec![Operand::Reg(Reg::gpr(inst.rd, false)), gpr(inst.rd)]
+                }
+                _ => vec![gpr(inst.rd)],
+            };
+            if inst.imm != 31 || inst.offset != 1 {
+                out.push(Operand::Pattern(inst.imm as u8));
+            }
+            if inst.offset != 1 {
+                out.push(Operand::Mul(inst.offset as u8));
+            }
+            out
+        }
+        A64_REV_Z => vec![z(inst.rd), z(inst.rn)],
+        A64_EXT_Z => vec![z(inst.rd), z(inst.rn), z(inst.rm), Operand::Imm(inst.imm)],
+        A64_TBL_Z => vec![z(inst.rd), Operand::ZList { first: inst.rn, len: 1, elem: esize }, z(inst.rm)],
+        A64_LASTA_REG_Z | A64_LASTB_REG_Z => vec![gpr(inst.rd), governing.expect("pg"), z(inst.rn)],
+        A64_LASTA_FP_Z | A64_LASTB_FP_Z => vec![fp(inst.rd), governing.expect("pg"), z(inst.rn)],
+        A64_CLASTA_REG_Z | A64_CLASTB_REG_Z => vec![gpr(inst.rd), governing.expect("pg"), gpr(inst.rn), z(inst.rm)],
+        A64_CLASTA_FP_Z | A64_CLASTB_FP_Z => vec![fp(inst.rd), governing.expect("pg"), fp(inst.rn), z(inst.rm)],
+        A64_DOT_Z | A64_DOT_ELEM_Z => {
+            let src = |n| Operand::Reg(Reg::Z { n, elem: Some(inst.sve.src) });
+            let zm = if inst.op == A64_DOT_Z { src(inst.rm) } else { zm_elem(inst.sve.src) };
+            vec![z(inst.rd), src(inst.rn), zm]
+        }
+        A64_FADD_IMM_Z | A64_FSUB_IMM_Z | A64_FMUL_IMM_Z | A64_FSUBR_IMM_Z | A64_FMAXNM_IMM_Z | A64_FMINNM_IMM_Z
+        | A64_FMAX_IMM_Z | A64_FMIN_IMM_Z => vec![z(inst.rd), governing.expect("pg"), z(inst.rn), Operand::FImm(inst.fimm)],
+        A64_FDUP_Z => vec![z(inst.rd), Operand::FImm(inst.fimm)],
+        A64_FCPY_Z => vec![z(inst.rd), governing.expect("pg"), Operand::FImm(inst.fimm)],
+        A64_FMLA_ELEM_Z | A64_FMLS_ELEM_Z | A64_FMUL_ELEM_Z => vec![z(inst.rd), z(inst.rn), zm_elem(esize)],
+        A64_FCMLA_Z | A64_FCADD_Z => vec![z(inst.rd), governing.expect("pg"), z(inst.rn), z(inst.rm), rotation],
+        A64_CMLA_Z => vec![z(inst.rd), z(inst.rn), z(inst.rm), rotation],
+        A64_FCMLA_ELEM_Z | A64_CMLA_ELEM_Z => vec![z(inst.rd), z(inst.rn), zm_elem(esize), rotation],
+        _ => match governing {
+            Some(governing) => vec![z(inst.rd), governing, z(inst.rn), z(inst.rm)],
+            None => vec![z(inst.rd), z(inst.rn), z(inst.rm)],
+        },
+    }
+}
+
+fn sve_predicate_operands(inst: &Inst) -> Vec<Operand> {
+    use Op::*;
+    let p = |n, elem| Operand::Reg(Reg::P { n, elem: Some(elem) });
+    let bytes = FPSize::FSZ_B;
+    let governing = inst.sve.pg.map(|n| Operand::Governing { n, mode: inst.sve.mode });
+    match inst.op {
+        // ALL is the default.
+        A64_PTRUE if inst.imm == 31 => vec![p(inst.rd, inst.sve.esize)],
+        A64_PTRUE => vec![p(inst.rd, inst.sve.esize), Operand::Pattern(inst.imm as u8)],
+        A64_PFALSE => vec![p(inst.rd, bytes)],
+        A64_PFIRST => vec![p(inst.rd, bytes), governing.expect("pg"), p(inst.rd, bytes)],
+        A64_PNEXT => vec![p(inst.rd, inst.sve.esize), Operand::Reg(Reg::P { n: inst.rn, elem: None }), p(inst.rd, inst.sve.esize)],
+        A64_PTEST => vec![governing.expect("pg"), p(inst.rn, bytes)],
+        A64_BRKA | A64_BRKB => vec![p(inst.rd, bytes), governing.expect("pg"), p(inst.rn, bytes)],
+        A64_WHILE => {
+            let w32 = inst.flags & W32 != 0;
+            vec![p(inst.rd, inst.sve.esize), Operand::Reg(Reg::gpr(inst.rn, w32)), Operand::Reg(Reg::gpr(inst.rm, w32))]
+        }
+        A64_CNTP => vec![Operand::Reg(Reg::gpr(inst.rd, false)), governing.expect("pg"), p(inst.rn, inst.sve.esize)],
+        A64_RDFFR => [p(inst.rd, bytes)].into_iter().chain(governing).collect(),
+        A64_WRFFR => vec![p(inst.rn, bytes)],
+        A64_AND_P | A64_ORR_P | A64_EOR_P | A64_SEL_P if predicate_move(inst) => match inst.op {
+            A64_ORR_P => vec![p(inst.rd, bytes), p(inst.rn, bytes)],
+            A64_SEL_P => {
+                let pg = Operand::Governing { n: inst.sve.pg.expect("pg"), mode: Some(Predication::Merging) };
+                vec![p(inst.rd, bytes), pg, p(inst.rn, bytes)]
+            }
+            _ => vec![p(inst.rd, bytes), governing.expect("pg"), p(inst.rn, bytes)],
+        },
+        A64_AND_P | A64_BIC_P | A64_EOR_P | A64_SEL_P | A64_ORR_P | A64_ORN_P | A64_NOR_P | A64_NAND_P | A64_BRKN
+        | A64_BRKPA | A64_BRKPB => vec![p(inst.rd, bytes), governing.expect("pg"), p(inst.rn, bytes), p(inst.rm, bytes)],
+        A64_INCP_Z | A64_DECP_Z | A64_QINCP_Z | A64_QDECP_Z => {
+            vec![Operand::Reg(Reg::Z { n: inst.rd, elem: Some(inst.sve.esize) }), p(inst.rn, inst.sve.esize)]
+        }
+        // SQINCP Xdn, Pm.T, Wdn sign-extends the 32-bit result.
+        A64_INCP | A64_DECP | A64_QINCP | A64_QDECP => {
+            let w32 = inst.flags & W32 != 0;
+            let count = p(inst.rn, inst.sve.esize);
+            if w32 && inst.flags & SIMD_SIGNED != 0 {
+                vec![Operand::Reg(Reg::gpr(inst.rd, false)), count, Operand::Reg(Reg::gpr(inst.rd, true))]
+            } else {
+                vec![Operand::Reg(Reg::gpr(inst.rd, w32)), count]
+            }
+        }
+        A64_ZIP1_P | A64_ZIP2_P | A64_UZP1_P | A64_UZP2_P | A64_TRN1_P | A64_TRN2_P => {
+            vec![p(inst.rd, inst.sve.esize), p(inst.rn, inst.sve.esize), p(inst.rm, inst.sve.esize)]
+        }
+        A64_REV_P => vec![p(inst.rd, inst.sve.esize), p(inst.rn, inst.sve.esize)],
+        A64_PUNPKLO | A64_PUNPKHI => vec![p(inst.rd, FPSize::FSZ_H), p(inst.rn, bytes)],
+        A64_CTERMEQ | A64_CTERMNE => {
+            let w32 = inst.flags & W32 != 0;
+            vec![Operand::Reg(Reg::gpr(inst.rn, w32)), Operand::Reg(Reg::gpr(inst.rm, w32))]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// The widening and narrowing ops of SVE2, whose Zn (and Zm) elements are
+/// Inst.sve.src, and XAR.
+fn sve2_width_operands(inst: &Inst) -> Vec<Operand> {
+    use Op::*;
+    let z = |n, elem| Operand::Reg(Reg::Z { n, elem: Some(elem) });
+    let (esize, src) = (inst.sve.esize, inst.sve.src);
+    match inst.op {
+        A64_ADDWB_Z | A64_ADDWT_Z | A64_SUBWB_Z | A64_SUBWT_Z => vec![z(inst.rd, esize), z(inst.rn, esize), z(inst.rm, src)],
+        A64_SHLLB_Z | A64_SHLLT_Z | A64_SHRNB_Z | A64_SHRNT_Z | A64_QSHRNB_Z | A64_QSHRNT_Z | A64_SQSHRUNB_Z
+        | A64_SQSHRUNT_Z => vec![z(inst.rd, esize), z(inst.rn, src), Operand::Imm(inst.imm)],
+        A64_QXTNB_Z | A64_QXTNT_Z | A64_SQXTUNB_Z | A64_SQXTUNT_Z => vec![z(inst.rd, esize), z(inst.rn, src)],
+        A64_XAR_Z => vec![z(inst.rd, esize), z(inst.rn, esize), z(inst.rm, esize), Operand::Imm(inst.imm)],
+        _ => vec![z(inst.rd, esize), z(inst.rn, src), z(inst.rm, src)],
+    }
+}
+
+fn za_slice(inst: &Inst, braced: bool) -> Operand {
+    let za = &inst.za;
+    Operand::ZaSlice { tile: za.tile, elem: inst.sve.esize, vertical: za.vertical, index: 12 + za.rv, offset: za.offset, braced }
+}
+
+/// LDx and STx, multiple and single structure, and LDxR
+fn is_structure(op: Op) -> bool {
+    op >= Op::A64_LD1_MULT && op <= Op::A64_LD4R
+}
+
+/// Where one element of a SIMD structure load or store goes: the byte
+/// offset from the base address, and the register and lane it is transferred
+/// to or from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ListElement {
+    pub offset: u32,
+    pub reg: u8,
+    pub lane: u8,
+}
+
+/// Element-wise layout of the LDx and STx structure loads and stores, in
+/// memory order; empty for other instructions. LD2...LD4 (multiple
+/// structures) interleave the registers, LD1 doesn't. The LDxR replicating
+/// loads list every lane of each register, all with the same offset.
+pub fn structure_elements(inst: &Inst) -> Vec<ListElement> {
+    use Op::*;
+    let va = fad_get_vec_arrangement(inst.flags);
+    let esize = VectorArrangement::elem_bytes(va);
+    let nreg = inst.simd_ldst.nreg;
+    let reg = |r: u32| ((inst.rd as u32 + r) % 32) as u8;
+    let mut out = Vec::new();
+    match inst.op {
+        A64_LD1_MULT | A64_ST1_MULT | A64_LD2_MULT | A64_ST2_MULT | A64_LD3_MULT | A64_ST3_MULT
+        | A64_LD4_MULT | A64_ST4_MULT => {
+            let lanes = VectorArrangement::lanes(va);
+            let interleaved = !matches!(inst.op, A64_LD1_MULT | A64_ST1_MULT);
+            for r in 0..nreg {
+                for e in 0..lanes {
+                    let i = if interleaved { e * nreg + r } else { r * lanes + e };
+                    out.push(ListElement { offset: i * esize, reg: reg(r), lane: e as u8 });
+                }
+            }
+            out.sort_by_key(|el| el.offset);
+        }
+        A64_LD1_SINGLE | A64_ST1_SINGLE | A64_LD2_SINGLE | A64_ST2_SINGLE | A64_LD3_SINGLE
+        | A64_ST3_SINGLE | A64_LD4_SINGLE | A64_ST4_SINGLE => {
+            for r in 0..nreg {
+                out.push(ListElement { offset: r * esize, reg: reg(r), lane: inst.simd_ldst.index as u8 });
+            }
+        }
+        A64_LD1R | A64_LD2R | A64_LD3R | A64_LD4R => {
+            for r in 0..nreg {
+                for e in 0..VectorArrangement::lanes(va) {
+                    out.push(ListElement { offset: r * esize, reg: reg(r), lane: e as u8 });
+                }
+            }
+        }
+        _ => {}
+    }
+    out
+}
+
+fn fp_or_gpr(inst: &Inst, r: u8, fp: bool) -> Operand {
+    if fp {
+        Operand::Reg(Reg::Fp { n: r, prec: fad_get_prec(inst.flags) })
+    } else {
+        Operand::Reg(Reg::gpr(r, inst.flags & W32 != 0))
+    }
+}
+
+/// The operands of the instructions the decoder produces; empty for the
+/// others.
+pub fn operands(inst: &Inst) -> Vec<Operand> {
+    use Op::*;
+    let w32 = inst.flags & W32 != 0;
+    let rd = Operand::Reg(Reg::gpr(inst.rd, w32));
+    let rn = Operand::Reg(Reg::gpr(inst.rn, w32));
+    let rm = Operand::Reg(Reg::gpr(inst.rm, w32));
+
+    match inst.op {
+        A64_ADR => vec![Operand::Reg(Reg::gpr(inst.rd, false)), Operand::Label(inst.offset)],
+        A64_ADRP => vec![Operand::Reg(Reg::gpr(inst.rd, false)), Operand::PageLabel(inst.offset)],
+        A64_ADD_IMM | A64_SUB_IMM => vec![rd, rn, Operand::Imm(inst.imm)],
+        // MOV (bitmask immediate)
+        A64_ORR_IMM if inst.rn == Registries::ZERO_REG && !move_wide_preferred(inst.imm, w32) => vec![rd, Operand::Mask(inst.imm)],
+        A64_AND_IMM | A64_ORR_IMM | A64_EOR_IMM => vec![rd, rn, Operand::Mask(inst.imm)],
+        A64_CMN_IMM | A64_CMP_IMM => vec![rn, Operand::Imm(inst.imm)],
+        A64_TST_IMM => vec![rn, Operand::Mask(inst.imm)],
+        A64_MOV_SP | A64_EXTEND => vec![rd, rn],
+        // A W register holds the value of MOVN in the low 32 bits, which
+        // print as a signed 32-bit value.
+        A64_MOV_IMM if w32 => vec![rd, Operand::Imm(inst.imm as u32 as i32 as i64 as u64)],
+        A64_MOV_IMM => vec![rd, Operand::Imm(inst.imm)],
+        A64_MOVK if inst.movk.lsl == 0 => vec![rd, Operand::Imm(inst.movk.imm16 as u64)],
+        A64_MOVK => vec![rd, Operand::Imm(inst.movk.imm16 as u64), Operand::Shift { typ: inst.shift, amount: inst.movk.lsl as u8 }],
+        A64_ASR_IMM | A64_LSL_IMM | A64_LSR_IMM | A64_ROR_IMM => vec![rd, rn, Operand::Imm(inst.imm)],
+        A64_BFI | A64_BFXIL | A64_SBFIZ | A64_SBFX | A64_UBFIZ | A64_UBFX => {
+            vec![rd, rn, Operand::Imm(inst.bfm.lsb as u64), Operand::Imm(inst.bfm.width as u64)]
+        }
+        A64_BFC => vec![rd, Operand::Imm(inst.bfm.lsb as u64), Operand::Imm(inst.bfm.width as u64)],
+        A64_EXTR => vec![rd, rn, rm, Operand::Imm(inst.imm)],
+
+        A64_HINT if hint_operand(inst.imm as u8).is_some() || hint_name(inst.imm).is_none() && inst.pac_hint().is_none() => {
+            vec![Operand::Hint(inst.imm as u8)]
+        }
+        A64_DMB | A64_DSB => vec![Operand::Barrier(inst.imm as u8)],
+        // SY is the default.
+        A64_ISB | A64_CLREX if inst.imm != 15 => vec![Operand::Hint(inst.imm as u8)],
+        // The immediate of DCPS is optional and 0 by default.
+        A64_DCPS1 | A64_DCPS2 | A64_DCPS3 if inst.imm == 0 => Vec::new(),
+        A64_SVC | A64_HVC | A64_SMC | A64_BRK | A64_HLT | A64_DCPS1 | A64_DCPS2 | A64_DCPS3 => {
+            vec![Operand::Imm(inst.imm)]
+        }
+        // SMSTART and SMSTOP name the field unless they set both.
+        A64_MSR_IMM => match PStateField::from_u32(inst.msr_imm.psfld) {
+            Some(PStateField::PSF_SVCRSMZA) | None => Vec::new(),
+            Some(field) if field.is_svcr() => vec![Operand::PState(field)],
+            Some(field) => vec![Operand::PState(field), Operand::Imm(inst.msr_imm.imm as u64)],
+        },
+        A64_MRS => vec![Operand::Reg(Reg::gpr(inst.rd, false)), Operand::SysReg(SysReg(inst.imm as u16))],
+        A64_MSR_REG => vec![Operand::SysReg(SysReg(inst.imm as u16)), Operand::Reg(Reg::gpr(inst.rd, false))],
+        // SYS leaves out Xt if it is XZR.
+        A64_SYS | A64_SYSL => {
+            let sys = &inst.sys;
+            let op = Operand::SysOp { op1: sys.op1 as u8, crn: sys.crn as u8, crm: sys.crm as u8, op2: sys.op2 as u8 };
+            let xt = Operand::Reg(Reg::gpr(inst.rd, false));
+            match (inst.op, inst.sys_alias()) {
+                (A64_SYSL, _) => vec![xt, op],
+                // The aliases that take Xt print it, even XZR.
+                (_, Some((_, operation, true))) => vec![Operand::SysOperation(operation), xt],
+                (_, Some((_, operation, false))) => vec![Operand::SysOperation(operation)],
+                _ if inst.rd == Registries::ZERO_REG => vec![op],
+                _ => vec![op, xt],
+            }
+        }
+        A64_B | A64_BL | A64_BCOND => vec![Operand::Label(inst.offset)],
+        A64_CBZ | A64_CBNZ => vec![rd, Operand::Label(inst.offset)],
+        A64_TBZ | A64_TBNZ => vec![rd, Operand::Imm(inst.tbz.bit as u64), Operand::Label(inst.tbz.offset as i64)],
+        A64_BR | A64_BLR => vec![Operand::Reg(Reg::gpr(inst.rn, false))],
+        // RET's operand is only written out if it isn't X30, but is there.
+        A64_RET => vec![Operand::Reg(Reg::gpr(inst.rn, false))],
+        A64_BRA | A64_BLRA if inst.rm != Registries::ZERO_REG => {
+            vec![Operand::Reg(Reg::gpr(inst.rn, false)), Operand::Reg(Reg::gpr(inst.rm, false))]
+        }
+        A64_BRA | A64_BLRA => vec![Operand::Reg(Reg::gpr(inst.rn, false))],
+
+        A64_LDR | A64_STR | A64_LDR_FP | A64_STR_FP | A64_LDP | A64_STP | A64_LDNP | A64_STNP | A64_LDP_FP
+        | A64_STP_FP | A64_LDNP_FP | A64_STNP_FP | A64_PRFM => {
+            let fp = matches!(inst.op, A64_LDR_FP | A64_STR_FP | A64_LDP_FP | A64_STP_FP | A64_LDNP_FP | A64_STNP_FP);
+            let pair = matches!(inst.op, A64_LDP | A64_STP | A64_LDNP | A64_STNP | A64_LDP_FP | A64_STP_FP
+                | A64_LDNP_FP | A64_STNP_FP);
+            // The prefetch operation is in the Rt field.
+            let mut out = if inst.op == A64_PRFM { vec![Operand::Imm(inst.rd as u64)] } else { vec![fp_or_gpr(inst, inst.rd, fp)] };
+            if pair {
+                out.push(fp_or_gpr(inst, inst.rt2, fp));
+            }
+            let mode = fad_get_addrmode(inst.flags);
+            out.push(if mode == AddrMode::AM_LITERAL {
+                Operand::Label(inst.offset)
+            } else {
+                Operand::Mem { base: Reg::gpr(inst.rn, false), offset: inst.offset, mode }
+            });
+            out
+        }
+        A64_LD1_MULT | A64_ST1_MULT | A64_LD2_MULT | A64_ST2_MULT | A64_LD3_MULT | A64_ST3_MULT
+        | A64_LD4_MULT | A64_ST4_MULT | A64_LD1_SINGLE | A64_ST1_SINGLE | A64_LD2_SINGLE | A64_ST2_SINGLE
+        | A64_LD3_SINGLE | A64_ST3_SINGLE | A64_LD4_SINGLE | A64_ST4_SINGLE | A64_LD1R | A64_LD2R | A64_LD3R
+        | A64_LD4R => {
+            let single = inst.op >= A64_LD1_SINGLE && inst.op <= A64_ST4_SINGLE;
+            let base = Reg::gpr(inst.rn, false);
+            vec![
+                Operand::List {
+                    first: inst.rd,
+                    len: inst.simd_ldst.nreg as u8,
+                    va: fad_get_vec_arrangement(inst.flags),
+                    lane: if single { Some(inst.simd_ldst.index as u8) } else { None },
+                },
+                match inst.post_index() {
+                    Some(PostIndex::Reg(index)) => Operand::MemPostReg { base, index },
+                    _ => Operand::Mem { base, offset: inst.simd_ldst.offset as i64, mode: fad_get_addrmode(inst.flags) },
+                },
+            ]
+        }
+        A64_TBL | A64_TBX => {
+            let va = fad_get_vec_arrangement(inst.flags);
+            vec![
+                Operand::Reg(Reg::Vec { n: inst.rd, va }),
+                Operand::List { first: inst.rn, len: inst.imm as u8, va: VectorArrangement::VA_16B, lane: None },
+                Operand::Reg(Reg::Vec { n: inst.rm, va }),
+            ]
+        }
+        // The status register of the stores comes first.
+        A64_LDXR | A64_STXR | A64_LDXP | A64_STXP => {
+            let mut out = Vec::new();
+            if matches!(inst.op, A64_STXR | A64_STXP) {
+                out.push(Operand::Reg(Reg::gpr(inst.rs, true)));
+            }
+            out.push(rd);
+            if matches!(inst.op, A64_LDXP | A64_STXP) {
+                out.push(Operand::Reg(Reg::gpr(inst.rt2, w32)));
+            }
+            out.push(Operand::Mem { base: Reg::gpr(inst.rn, false), offset: 0, mode: AddrMode::AM_SIMPLE });
+            out
+        }
+        A64_LDADD | A64_LDCLR | A64_LDEOR | A64_LDSET | A64_LDSMAX | A64_LDSMIN | A64_LDUMAX | A64_LDUMIN | A64_SWP => {
+            let mem = Operand::Mem { base: Reg::gpr(inst.rn, false), offset: 0, mode: AddrMode::AM_SIMPLE };
+            let rs = Operand::Reg(Reg::gpr(inst.rs, w32));
+            if inst.atomic_store_alias() { vec![rs, mem] } else { vec![rs, rd, mem] }
+        }
+        A64_LDAPR => vec![rd, Operand::Mem { base: Reg::gpr(inst.rn, false), offset: 0, mode: AddrMode::AM_SIMPLE }],
+        A64_CAS => vec![Operand::Reg(Reg::gpr(inst.rs, w32)), rd, Operand::Mem {
+            base: Reg::gpr(inst.rn, false), offset: 0, mode: AddrMode::AM_SIMPLE }],
+        // Both pairs are written out in full.
+        A64_CASP => vec![
+            Operand::Reg(Reg::gpr(inst.rs, w32)),
+            Operand::Reg(Reg::gpr(inst.rs + 1, w32)),
+            rd,
+            Operand::Reg(Reg::gpr(inst.rt2, w32)),
+            Operand::Mem { base: Reg::gpr(inst.rn, false), offset: 0, mode: AddrMode::AM_SIMPLE },
+        ],
+        _ if is_sve_memory(inst.op) => {
+            let base = Reg::gpr(inst.rn, false);
+            let z = |n| Reg::Z { n, elem: Some(inst.sve.esize) };
+            let ldst = &inst.sve_ldst;
+            let address = match ldst.mode {
+                // LDFF1 (scalar plus scalar) with XZR, the default offset
+                AddrMode::AM_OFF_REG if inst.rm == Registries::ZERO_REG => Operand::Mem { base, offset: 0, mode: AddrMode::AM_SIMPLE },
+                AddrMode::AM_OFF_REG => Operand::MemIndex { base, index: Reg::gpr(inst.rm, false), extend: None, shift: ldst.msz },
+                AddrMode::AM_OFF_EXT => Operand::MemIndex {
+                    base,
+                    index: z(inst.rm),
+                    extend: (ldst.extend != ExtendType::UXTX).then_some(ldst.extend),
+                    shift: if ldst.scaled { ldst.msz } else { 0 },
+                },
+                _ if ldst.vector => Operand::Mem { base: z(inst.rn), offset: inst.offset, mode: AddrMode::AM_OFF_IMM },
+                _ => Operand::MemVl { base, offset: inst.offset },
+            };
+            vec![
+                Operand::ZList { first: inst.rd, len: inst.sve_ldst.nreg, elem: inst.sve.esize },
+                Operand::Governing { n: inst.sve.pg.unwrap_or(0), mode: inst.sve.mode },
+                address,
+            ]
+        }
+        A64_LDR_ZA | A64_STR_ZA => vec![
+            Operand::ZaArray { index: 12 + inst.za.rv, offset: inst.za.offset },
+            Operand::MemVl { base: Reg::gpr(inst.rn, false), offset: inst.offset },
+        ],
+        A64_LD1_ZA | A64_ST1_ZA => {
+            let base = Reg::gpr(inst.rn, false);
+            let address = match inst.rm {
+                Registries::ZERO_REG => Operand::Mem { base, offset: 0, mode: AddrMode::AM_SIMPLE },
+                rm => {
+                    let shift = FPSize::bytes(inst.sve.esize).trailing_zeros() as u8;
+                    Operand::MemIndex { base, index: Reg::gpr(rm, false), extend: None, shift }
+                }
+            };
+            vec![za_slice(inst, true), Operand::Governing { n: inst.sve.pg.unwrap_or(0), mode: inst.sve.mode }, address]
+        }
+        A64_ZERO_ZA => vec![Operand::ZaTiles(inst.imm as u8)],
+        A64_MOVA_Z | A64_MOVA_ZA => {
+            let governing = Operand::Governing { n: inst.sve.pg.unwrap_or(0), mode: inst.sve.mode };
+            match inst.op {
+                A64_MOVA_Z => vec![Operand::Reg(Reg::Z { n: inst.rd, elem: Some(inst.sve.esize) }), governing, za_slice(inst, false)],
+                _ => vec![za_slice(inst, false), governing, Operand::Reg(Reg::Z { n: inst.rn, elem: Some(inst.sve.esize) })],
+            }
+        }
+        // Pn governs the Zn elements, Pm the Zm ones.
+        _ if inst.op >= A64_FMOPA_ZA => {
+            let z = |n| Operand::Reg(Reg::Z { n, elem: Some(inst.mop.elem) });
+            vec![
+                Operand::ZaTile { tile: inst.mop.tile, elem: inst.sve.esize },
+                Operand::Governing { n: inst.sve.pg.unwrap_or(0), mode: inst.sve.mode },
+                Operand::Governing { n: inst.mop.pm, mode: inst.sve.mode },
+                z(inst.rn),
+                z(inst.rm),
+            ]
+        }
+        _ if (A64_PTRUE..=A64_CTERMNE).contains(&inst.op) => sve_predicate_operands(inst),
+        A64_EOR3_Z | A64_BCAX_Z | A64_BSL_Z | A64_BSL1N_Z | A64_BSL2N_Z | A64_NBSL_Z => {
+            let z = |n| Operand::Reg(Reg::Z { n, elem: Some(inst.sve.esize) });
+            vec![z(inst.rd), z(inst.rn), z(inst.rm), z(inst.ra)]
+        }
+        A64_MATCH_Z | A64_NMATCH_Z => {
+            let z = |n| Operand::Reg(Reg::Z { n, elem: Some(inst.sve.esize) });
+            vec![
+                Operand::Reg(Reg::P { n: inst.rd, elem: Some(inst.sve.esize) }),
+                Operand::Governing { n: inst.sve.pg.unwrap_or(0), mode: inst.sve.mode },
+                z(inst.rn),
+                z(inst.rm),
+            ]
+        }
+        A64_AESMC_Z | A64_AESIMC_Z => {
+            let z = |n| Operand::Reg(Reg::Z { n, elem: Some(inst.sve.esize) });
+            vec![z(inst.rd), z(inst.rn)]
+        }
+        A64_CMLA_Z | A64_CMLA_ELEM_Z => sve_data_operands(inst),
+        _ if (A64_ADDLB_Z..=A64_XAR_Z).contains(&inst.op) => sve2_width_operands(inst),
+        _ if inst.group() == Group::Sve && inst.op < A64_LD1_Z => sve_data_operands(inst),
+        _ if inst.group() == Group::Sve => {
+            let z = |n| Operand::Reg(Reg::Z { n, elem: Some(inst.sve.esize) });
+            match inst.sve.pg {
+                Some(n) => vec![z(inst.rd), Operand::Governing { n, mode: inst.sve.mode }, z(inst.rn), z(inst.rm)],
+                None => vec![z(inst.rd), z(inst.rn), z(inst.rm)],
+            }
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_defuse::def_use;
+    use crate::aarch64_listing::inst_text;
+    #[cfg(feature = "simd")]
+    use crate::aarch64_mnemonic::inst_mnemonic;
+    use crate::aarch64_reader::decode;
+    #[cfg(feature = "simd")]
+    use crate::aarch64_reader::FPSize;
+
+    #[test]
+    fn pair_operands() {
+        // ldp x29, x30, [sp], #16
+        assert_eq!(operands(&decode(0xA8C17BFD)), vec![
+            Operand::Reg(Reg::Gpr { n: 29, w32: false }),
+            Operand::Reg(Reg::Gpr { n: 30, w32: false }),
+            Operand::Mem { base: Reg::Sp { w32: false }, offset: 16, mode: AddrMode::AM_POST },
+        ]);
+        // casp x2, x3, x4, x5, [x0]
+        let gpr = |n| Operand::Reg(Reg::Gpr { n, w32: false });
+        assert_eq!(operands(&decode(0x48227C04)), vec![
+            gpr(2), gpr(3), gpr(4), gpr(5),
+            Operand::Mem { base: Reg::Gpr { n: 0, w32: false }, offset: 0, mode: AddrMode::AM_SIMPLE },
+        ]);
+        assert_eq!(decode(0x48237C04).op, Op::A64_ERROR); // casp x3, x4, ...
+    }
+
+    #[test]
+    fn exclusive_and_cas_pairs() {
+        // llvm-mc -triple=aarch64 -mattr=+lse -disassemble
+        for (word, text) in [
+            (0x08207C82, "casp w0, w1, w2, w3, [x4]"),
+            (0x4866FFE8, "caspal x6, x7, x8, x9, [sp]"),
+            (0x08647C26, "caspa w4, w5, w6, w7, [x1]"),
+            (0x4820FD42, "caspl x0, x1, x2, x3, [x10]"),
+            (0xC87F0440, "ldxp x0, x1, [x2]"),
+            (0x887F93E3, "ldaxp w3, w4, [sp]"),
+            (0xC8250440, "stxp w5, x0, x1, [x2]"),
+            (0x8826A127, "stlxp w6, w7, w8, [x9]"),
+        ] {
+            assert_eq!(inst_text(&decode(word), 0), text, "{:#010x}", word);
+        }
+        // Both pairs of CASP are compared, and the first receives the old value.
+        let du = def_use(&decode(0x08207C82));
+        assert_eq!(du.defs, vec![Loc::X(0), Loc::X(1)]);
+        assert_eq!(du.uses, vec![Loc::X(0), Loc::X(2), Loc::X(1), Loc::X(3), Loc::X(4)]);
+        let du = def_use(&decode(0xC87F0440));
+        assert_eq!((du.defs, du.uses), (vec![Loc::X(0), Loc::X(1)], vec![Loc::X(2)]));
+        // The status register of STXP is a def, the pair a use.
+        let du = def_use(&decode(0x8826A127));
+        assert_eq!((du.defs, du.uses), (vec![Loc::X(6)], vec![Loc::X(7), Loc::X(8), Loc::X(9)]));
+        // casp w1, w2, ... and casp ..., w3, w4, [x4]: odd first registers
+        for word in [0x08217C82, 0x08207C83] {
+            assert_eq!(decode(word).op, Op::A64_ERROR, "{:#010x}", word);
+        }
+    }
+
+    #[test]
+    fn lse_atomics() {
+        // llvm-mc -triple=aarch64 -mattr=+lse,+rcpc -disassemble
+        for (word, text) in [
+            (0xB8200041, "ldadd w0, w1, [x2]"),
+            (0xB83F0041, "ldadd wzr, w1, [x2]"),
+            (0xB8BF0041, "ldadda wzr, w1, [x2]"),
+            (0xF8E08041, "swpal x0, x1, [x2]"),
+            (0x78608041, "swplh w0, w1, [x2]"),
+            (0x78FF7041, "lduminalh wzr, w1, [x2]"),
+            (0x387F3041, "ldsetlb wzr, w1, [x2]"),
+            (0xB820005F, "stadd w0, [x2]"),
+            (0xB860005F, "staddl w0, [x2]"),
+            (0xB8A0005F, "ldadda w0, wzr, [x2]"),
+            (0xF820805F, "swp x0, xzr, [x2]"),
+            (0x38BFC020, "ldaprb w0, [x1]"),
+            (0xF8BFC020, "ldapr x0, [x1]"),
+        ] {
+            assert_eq!(inst_text(&decode(word), 0), text, "{:#010x}", word);
+        }
+        assert_eq!(decode(0xB8A0C020).op, Op::A64_UNKNOWN); // ldapr with Rs != 31
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn structure_lists() {
+        // ld3 {v30.4s, v31.4s, v0.4s}, [x0]
+        let ld3 = decode(0x4C40481E);
+        assert_eq!(operands(&ld3)[0].list_regs(), vec![30, 31, 0]);
+        let elements = structure_elements(&ld3);
+        assert_eq!(elements.len(), 12);
+        assert_eq!(elements[1], ListElement { offset: 4, reg: 31, lane: 0 });
+        assert_eq!(elements[3], ListElement { offset: 12, reg: 30, lane: 1 });
+        // st2 {v4.s, v5.s}[3], [x1]
+        let st2 = decode(0x4D209024);
+        assert_eq!(structure_elements(&st2), vec![
+            ListElement { offset: 0, reg: 4, lane: 3 },
+            ListElement { offset: 4, reg: 5, lane: 3 },
+        ]);
+        // ld1 {v0.16b}, [x0], x2 and ld1 {v0.16b}, [x0], #16
+        let (by_reg, by_imm) = (decode(0x4CC27000), decode(0x4CDF7000));
+        assert_eq!(by_reg.post_index(), Some(PostIndex::Reg(Reg::Gpr { n: 2, w32: false })));
+        assert_eq!(operands(&by_reg)[1], Operand::MemPostReg { base: Reg::Gpr { n: 0, w32: false }, index: Reg::Gpr { n: 2, w32: false } });
+        assert_eq!(by_imm.post_index(), Some(PostIndex::Imm(16)));
+        // tbl v0.16b, {v31.16b, v0.16b}, v2.16b
+        assert_eq!(operands(&decode(0x4E0223E0))[1].list_regs(), vec![31, 0]);
+    }
+
+    #[test]
+    fn post_index_increments() {
+        // llvm-mc -triple=aarch64 -disassemble
+        for (word, text, post) in [
+            (0xF8408420, "ldr x0, [x1], #8", Some(8)),
+            (0xB8500462, "ldr w2, [x3], #-0x100", Some(-256)),
+            (0xF80107E4, "str x4, [sp], #0x10", Some(16)),
+            (0xA8FE18E5, "ldp x5, x6, [x7], #-0x20", Some(-32)),
+            (0xF8408C20, "ldr x0, [x1, #8]!", None),
+            (0xF9400420, "ldr x0, [x1, #8]", None),
+        ] {
+            let inst = decode(word);
+            assert_eq!(inst_text(&inst, 0), text);
+            assert_eq!(inst.post_index(), post.map(PostIndex::Imm), "{:#010x}", word);
+        }
+        assert_eq!(decode(0x91002020).post_index(), None); // add x0, x1, #8
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn simd_post_increments() {
+        let x = |n| Reg::Gpr { n, w32: false };
+        // llvm-mc -triple=aarch64 -disassemble; the immediate of the structure
+        // loads and stores is the size of the transfer, Rm = 31.
+        for (word, text, post) in [
+            (0x3CC10420, "ldr q0, [x1], #0x10", PostIndex::Imm(16)),
+            (0x4CC27000, "ld1 {v0.16b}, [x0], x2", PostIndex::Reg(x(2))),
+            (0x4CDF7000, "ld1 {v0.16b}, [x0], #0x10", PostIndex::Imm(16)),
+            (0x4CDF8861, "ld2 {v1.4s, v2.4s}, [x3], #0x20", PostIndex::Imm(32)),
+            (0x0C890104, "st4 {v4.8b, v5.8b, v6.8b, v7.8b}, [x8], x9", PostIndex::Reg(x(9))),
+            (0x0DFF9061, "ld2 {v1.s, v2.s}[1], [x3], #8", PostIndex::Imm(8)),
+            (0x0DDFC7E0, "ld1r {v0.4h}, [sp], #2", PostIndex::Imm(2)),
+            (0x4DDEEC00, "ld3r {v0.2d, v1.2d, v2.2d}, [x0], x30", PostIndex::Reg(x(30))),
+            (0x4D818400, "st1 {v0.d}[1], [x0], x1", PostIndex::Reg(x(1))),
+        ] {
+            let inst = decode(word);
+            assert_eq!(inst_text(&inst, 0), text);
+            assert_eq!(inst.post_index(), Some(post), "{:#010x}", word);
+            let mem = operands(&inst).pop().unwrap();
+            match post {
+                PostIndex::Reg(index) => assert_eq!(mem, Operand::MemPostReg { base: Reg::gpr(inst.rn, false), index }),
+                PostIndex::Imm(offset) => assert!(matches!(mem, Operand::Mem { offset: o, mode: AddrMode::AM_POST, .. } if o == offset)),
+            }
+        }
+        // The base is written back; the index register is only read.
+        let du = def_use(&decode(0x4D818400));
+        assert_eq!((du.defs, du.uses), (vec![Loc::X(0)], vec![Loc::V(0), Loc::X(0), Loc::X(1)]));
+        let du = def_use(&decode(0x4CDF7000));
+        assert_eq!((du.defs, du.uses), (vec![Loc::V(0), Loc::X(0)], vec![Loc::X(0)]));
+        assert_eq!(decode(0x4C407000).post_index(), None); // ld1 {v0.16b}, [x0]
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn table_lists() {
+        // llvm-mc -triple=aarch64 -disassemble, with the listing's braces
+        let cases = [
+            (0x0E020020, "tbl v0.8b, {v1.16b}, v2.8b", vec![1]),
+            (0x4E0443C3, "tbl v3.16b, {v30.16b, v31.16b, v0.16b}, v4.16b", vec![30, 31, 0]),
+            (0x0E0673A5, "tbx v5.8b, {v29.16b, v30.16b, v31.16b, v0.16b}, v6.8b", vec![29, 30, 31, 0]),
+            (0x4E073107, "tbx v7.16b, {v8.16b, v9.16b}, v7.16b", vec![8, 9]),
+            (0x4E0163E1, "tbl v1.16b, {v31.16b, v0.16b, v1.16b, v2.16b}, v1.16b", vec![31, 0, 1, 2]),
+        ];
+        for (word, text, table) in cases {
+            let inst = decode(word);
+            assert_eq!(inst_text(&inst, 0), text);
+            assert_eq!(operands(&inst)[1].list_regs(), table, "{:#010x}", word);
+        }
+        // The table and the index are read; TBX also reads the elements it keeps.
+        let du = def_use(&decode(0x4E0443C3));
+        assert_eq!((du.defs, du.uses), (vec![Loc::V(3)], vec![Loc::V(30), Loc::V(31), Loc::V(0), Loc::V(4)]));
+        let du = def_use(&decode(0x4E073107));
+        assert_eq!((du.defs, du.uses), (vec![Loc::V(7)], vec![Loc::V(8), Loc::V(9), Loc::V(7)]));
+        // op2 != 00 and bit 10 set
+        for word in [0x4E420020, 0x0E020820] {
+            assert_eq!(decode(word).op, Op::A64_UNKNOWN, "{:#010x}", word);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn sve_arithmetic() {
+        let z = |n| Operand::Reg(Reg::Z { n, elem: Some(FPSize::FSZ_S) });
+        // sdiv z0.s, p3/m, z0.s, z1.s
+        let sdiv = decode(0x04940C20);
+        assert_eq!(inst_mnemonic(&sdiv), "sdiv");
+        assert_eq!(operands(&sdiv), vec![z(0), Operand::Governing { n: 3, mode: Some(Predication::Merging) }, z(0), z(1)]);
+        assert_eq!(def_use(&sdiv).uses, vec![Loc::Z(0), Loc::Z(1), Loc::P(3)]);
+        // fmla z0.s, p0/m, z1.s, z2.s and the unpredicated eor z0.d, z1.d, z2.d
+        assert_eq!(inst_text(&decode(0x65A20020), 0), "fmla z0.s, p0/m, z1.s, z2.s");
+        assert_eq!(inst_text(&decode(0x04A23020), 0), "eor z0.d, z1.d, z2.d");
+        assert_eq!(inst_text(&decode(0x04A21420), 0), "uqadd z0.s, z1.s, z2.s");
+        assert_eq!(decode(0x04540C20).op, Op::A64_UNKNOWN); // sdiv z0.h: no H elements
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn sve_data_processing_groups() {
+        // llvm-mc -triple=aarch64 -mattr=+sve -disassemble, with immediates
+        // printed the listing's way.
+        for (word, text) in [
+            (0x040085A0, "asr z0.b, p1/m, z0.b, #3"),
+            (0x04018A02, "lsr z2.h, p2/m, z2.h, #0x10"),
+            (0x04439FE3, "lsl z3.s, p7/m, z3.s, #0x1f"),
+            (0x04848004, "asrd z4.d, p0/m, z4.d, #0x40"),
+            (0x041084C5, "asr z5.b, p1/m, z5.b, z6.b"),
+            (0x045184C5, "lsr z5.h, p1/m, z5.h, z6.h"),
+            (0x049384C5, "lsl z5.s, p1/m, z5.s, z6.s"),
+            (0x04D484C5, "asrr z5.d, p1/m, z5.d, z6.d"),
+            (0x041584C5, "lsrr z5.b, p1/m, z5.b, z6.b"),
+            (0x041784C5, "lslr z5.b, p1/m, z5.b, z6.b"),
+            (0x041884C5, "asr z5.b, p1/m, z5.b, z6.d"),
+            (0x045984C5, "lsr z5.h, p1/m, z5.h, z6.d"),
+            (0x049B84C5, "lsl z5.s, p1/m, z5.s, z6.d"),
+            (0x0450A861, "sxtb z1.h, p2/m, z3.h"),
+            (0x04D1A861, "uxtb z1.d, p2/m, z3.d"),
+            (0x0492A861, "sxth z1.s, p2/m, z3.s"),
+            (0x04D3A861, "uxth z1.d, p2/m, z3.d"),
+            (0x04D4A861, "sxtw z1.d, p2/m, z3.d"),
+            (0x04D5A861, "uxtw z1.d, p2/m, z3.d"),
+            (0x0416A861, "abs z1.b, p2/m, z3.b"),
+            (0x0457A861, "neg z1.h, p2/m, z3.h"),
+            (0x0498A861, "cls z1.s, p2/m, z3.s"),
+            (0x04D9A861, "clz z1.d, p2/m, z3.d"),
+            (0x041AA861, "cnt z1.b, p2/m, z3.b"),
+            (0x045BA861, "cnot z1.h, p2/m, z3.h"),
+            (0x045CA861, "fabs z1.h, p2/m, z3.h"),
+            (0x04DDA861, "fneg z1.d, p2/m, z3.d"),
+            (0x049EA861, "not z1.s, p2/m, z3.s"),
+            (0x04002440, "saddv d0, p1, z2.b"),
+            (0x04C12440, "uaddv d0, p1, z2.d"),
+            (0x04082440, "smaxv b0, p1, z2.b"),
+            (0x04492440, "umaxv h0, p1, z2.h"),
+            (0x048A2440, "sminv s0, p1, z2.s"),
+            (0x04CB2440, "uminv d0, p1, z2.d"),
+            (0x04182440, "orv b0, p1, z2.b"),
+            (0x04592440, "eorv h0, p1, z2.h"),
+            (0x049A2440, "andv s0, p1, z2.s"),
+            (0x65402440, "faddv h0, p1, z2.h"),
+            (0x65842440, "fmaxnmv s0, p1, z2.s"),
+            (0x65C52440, "fminnmv d0, p1, z2.d"),
+            (0x65462440, "fmaxv h0, p1, z2.h"),
+            (0x65872440, "fminv s0, p1, z2.s"),
+            (0x65D82440, "fadda d0, p1, d0, z2.d"),
+            (0x0402C460, "mad z0.b, p1/m, z2.b, z3.b"),
+            (0x04C2E460, "msb z0.d, p1/m, z2.d, z3.d"),
+            (0x65638440, "fmad z0.h, p1/m, z2.h, z3.h"),
+            (0x65A3A440, "fmsb z0.s, p1/m, z2.s, z3.s"),
+            (0x65E3C440, "fnmad z0.d, p1/m, z2.d, z3.d"),
+            (0x6563E440, "fnmsb z0.h, p1/m, z2.h, z3.h"),
+            (0x6540A440, "frintn z0.h, p1/m, z2.h"),
+            (0x6581A440, "frintp z0.s, p1/m, z2.s"),
+            (0x65C2A440, "frintm z0.d, p1/m, z2.d"),
+            (0x6543A440, "frintz z0.h, p1/m, z2.h"),
+            (0x6544A440, "frinta z0.h, p1/m, z2.h"),
+            (0x6546A440, "frintx z0.h, p1/m, z2.h"),
+            (0x6547A440, "frinti z0.h, p1/m, z2.h"),
+            (0x658CA440, "frecpx z0.s, p1/m, z2.s"),
+            (0x65CDA440, "fsqrt z0.d, p1/m, z2.d"),
+            (0x6588A440, "fcvt z0.h, p1/m, z2.s"),
+            (0x6589A440, "fcvt z0.s, p1/m, z2.h"),
+            (0x65C8A440, "fcvt z0.h, p1/m, z2.d"),
+            (0x65C9A440, "fcvt z0.d, p1/m, z2.h"),
+            (0x65CAA440, "fcvt z0.s, p1/m, z2.d"),
+            (0x65CBA440, "fcvt z0.d, p1/m, z2.s"),
+            (0x655AA440, "fcvtzs z0.h, p1/m, z2.h"),
+            (0x655CA440, "fcvtzs z0.s, p1/m, z2.h"),
+            (0x655EA440, "fcvtzs z0.d, p1/m, z2.h"),
+            (0x659CA440, "fcvtzs z0.s, p1/m, z2.s"),
+            (0x65DCA440, "fcvtzs z0.d, p1/m, z2.s"),
+            (0x65D8A440, "fcvtzs z0.s, p1/m, z2.d"),
+            (0x65DEA440, "fcvtzs z0.d, p1/m, z2.d"),
+            (0x655BA440, "fcvtzu z0.h, p1/m, z2.h"),
+            (0x65DFA440, "fcvtzu z0.d, p1/m, z2.d"),
+            (0x6552A440, "scvtf z0.h, p1/m, z2.h"),
+            (0x6554A440, "scvtf z0.h, p1/m, z2.s"),
+            (0x6556A440, "scvtf z0.h, p1/m, z2.d"),
+            (0x6594A440, "scvtf z0.s, p1/m, z2.s"),
+            (0x65D0A440, "scvtf z0.d, p1/m, z2.s"),
+            (0x65D4A440, "scvtf z0.s, p1/m, z2.d"),
+            (0x65D6A440, "scvtf z0.d, p1/m, z2.d"),
+            (0x6553A440, "ucvtf z0.h, p1/m, z2.h"),
+            (0x65D7A440, "ucvtf z0.d, p1/m, z2.d"),
+            (0x24030440, "cmphs p0.b, p1/z, z2.b, z3.b"),
+            (0x24430450, "cmphi p0.h, p1/z, z2.h, z3.h"),
+            (0x24838440, "cmpge p0.s, p1/z, z2.s, z3.s"),
+            (0x24C38450, "cmpgt p0.d, p1/z, z2.d, z3.d"),
+            (0x2403A440, "cmpeq p0.b, p1/z, z2.b, z3.b"),
+            (0x2403A450, "cmpne p0.b, p1/z, z2.b, z3.b"),
+            (0x24032440, "cmpeq p0.b, p1/z, z2.b, z3.d"),
+            (0x24432450, "cmpne p0.h, p1/z, z2.h, z3.d"),
+            (0x24034440, "cmpge p0.b, p1/z, z2.b, z3.d"),
+            (0x24034450, "cmpgt p0.b, p1/z, z2.b, z3.d"),
+            (0x24036440, "cmplt p0.b, p1/z, z2.b, z3.d"),
+            (0x24036450, "cmple p0.b, p1/z, z2.b, z3.d"),
+            (0x2403C440, "cmphs p0.b, p1/z, z2.b, z3.d"),
+            (0x2403C450, "cmphi p0.b, p1/z, z2.b, z3.d"),
+            (0x2403E440, "cmplo p0.b, p1/z, z2.b, z3.d"),
+            (0x2403E450, "cmpls p0.b, p1/z, z2.b, z3.d"),
+            (0x25100440, "cmpge p0.b, p1/z, z2.b, #-0x10"),
+            (0x254F0450, "cmpgt p0.h, p1/z, z2.h, #0xf"),
+            (0x25802440, "cmplt p0.s, p1/z, z2.s, #0"),
+            (0x25C12450, "cmple p0.d, p1/z, z2.d, #1"),
+            (0x251F8440, "cmpeq p0.b, p1/z, z2.b, #-1"),
+            (0x25078450, "cmpne p0.b, p1/z, z2.b, #7"),
+            (0x24200440, "cmphs p0.b, p1/z, z2.b, #0"),
+            (0x247FC450, "cmphi p0.h, p1/z, z2.h, #0x7f"),
+            (0x24B02440, "cmplo p0.s, p1/z, z2.s, #0x40"),
+            (0x24E0E450, "cmpls p0.d, p1/z, z2.d, #3"),
+            (0x65434440, "fcmge p0.h, p1/z, z2.h, z3.h"),
+            (0x65834450, "fcmgt p0.s, p1/z, z2.s, z3.s"),
+            (0x65C36440, "fcmeq p0.d, p1/z, z2.d, z3.d"),
+            (0x65436450, "fcmne p0.h, p1/z, z2.h, z3.h"),
+            (0x6543C440, "fcmuo p0.h, p1/z, z2.h, z3.h"),
+            (0x6543C450, "facge p0.h, p1/z, z2.h, z3.h"),
+            (0x6543E450, "facgt p0.h, p1/z, z2.h, z3.h"),
+            (0x65502440, "fcmge p0.h, p1/z, z2.h, #0.0"),
+            (0x65902450, "fcmgt p0.s, p1/z, z2.s, #0.0"),
+            (0x65D12440, "fcmlt p0.d, p1/z, z2.d, #0.0"),
+            (0x65512450, "fcmle p0.h, p1/z, z2.h, #0.0"),
+            (0x65522440, "fcmeq p0.h, p1/z, z2.h, #0.0"),
+            (0x65532440, "fcmne p0.h, p1/z, z2.h, #0.0"),
+            (0x042F4200, "index z0.b, #-0x10, #0xf"),
+            (0x04A24BE0, "index z0.s, #-1, w2"),
+            (0x04E24C20, "index z0.d, x1, x2"),
+            (0x04A24C20, "index z0.s, w1, w2"),
+            (0x0422A020, "adr z0.d, [z1.d, z2.d, sxtw]"),
+            (0x0462AC20, "adr z0.d, [z1.d, z2.d, uxtw #3]"),
+            (0x04A2A020, "adr z0.s, [z1.s, z2.s]"),
+            (0x04E2A820, "adr z0.d, [z1.d, z2.d, lsl #2]"),
+            (0x05000000, "orr z0.s, z0.s, #0x1"),
+            (0x05400640, "eor z0.b, z0.b, #0x7"),
+            (0x058044E0, "and z0.h, z0.h, #0xff00"),
+            (0x0583E420, "and z0.d, z0.d, #0x3ffffffff0"),
+            (0x05C000E0, "mov z0.s, #0xff"),
+        ] {
+            assert_eq!(inst_text(&decode(word), 0), text, "{:#010x}", word);
+        }
+        let mad = decode(0x0402C460);
+        assert_eq!((mad.rm, mad.ra), (2, 3));
+        assert_eq!(def_use(&mad).uses, vec![Loc::Z(0), Loc::Z(2), Loc::Z(3), Loc::P(1)]);
+        let fmad = decode(0x65638440);
+        assert_eq!((fmad.rm, fmad.ra), (2, 3));
+        assert_eq!(def_use(&decode(0x24030440)).defs, vec![Loc::P(0), Loc::NZCV]);
+        assert_eq!(def_use(&decode(0x65434440)).defs, vec![Loc::P(0)]);
+        assert_eq!(def_use(&decode(0x0450A861)).uses, vec![Loc::Z(3), Loc::P(2), Loc::Z(1)]);
+        assert_eq!(def_use(&decode(0x04A24BE0)).uses, vec![Loc::X(2)]);
+        assert_eq!(def_use(&decode(0x65D82440)).defs, vec![Loc::V(0)]);
+        // Wide shifts of D elements and sign extensions from W into S.
+        assert_eq!(decode(0x04D884C5).op, Op::A64_UNKNOWN);
+        assert_eq!(decode(0x0494A861).op, Op::A64_UNKNOWN);
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn sve_moves_and_counts() {
+        // llvm-mc -triple=aarch64 -mattr=+sve -disassemble, with immediates
+        // printed the listing's way.
+        for (word, text) in [
+            (0x2538D000, "mov z0.b, #-0x80"),
+            (0x2578EFE1, "mov z1.h, #0x7f00"),
+            (0x25B8DFE2, "mov z2.s, #-1"),
+            (0x25F8E023, "mov z3.d, #0x100"),
+            (0x059140A4, "mov z4.s, p1/m, #5"),
+            (0x05523FA5, "mov z5.h, p2/z, #-0x300"),
+            (0x05A03907, "mov z7.s, w8"),
+            (0x05E03BE9, "mov z9.d, sp"),
+            (0x0568A5AC, "mov z12.h, p1/m, w13"),
+            (0x05A08E0F, "mov z15.s, p3/m, s16"),
+            (0x05209251, "mov z17.b, p4/m, b18"),
+            (0x2579CE15, "fmov z21.h, #1.0"),
+            (0x25B9DC16, "fmov z22.s, #-0.5"),
+            (0x25F9C7F7, "fmov z23.d, #31.0"),
+            (0x05D7C819, "fmov z25.d, p7/m, #0.125"),
+            (0x0420BC20, "movprfx z0, z1"),
+            (0x04912462, "movprfx z2.s, p1/m, z3.s"),
+            (0x04D028A4, "movprfx z4.d, p2/z, z5.d"),
+            (0x0528CCE6, "sel z6.b, p3, z7.b, z8.b"),
+            (0x05A9D149, "mov z9.s, p4/m, z10.s"),
+            (0x052C2020, "mov z0.s, z1.s[1]"),
+            (0x05FF2020, "mov z0.b, z1.b[63]"),
+            (0x05F02020, "mov z0.q, z1.q[3]"),
+            (0x05282020, "mov z0.d, d1"),
+            (0x0420E3E0, "cntb x0"),
+            (0x0460E101, "cnth x1, vl8"),
+            (0x04A2E002, "cntw x2, pow2, mul #3"),
+            (0x04EFE3E3, "cntd x3, all, mul #16"),
+            (0x0430E3E4, "incb x4"),
+            (0x04B1E425, "decw x5, vl1, mul #2"),
+            (0x0470C3E6, "inch z6.h"),
+            (0x0420F3E0, "sqincb x0, w0"),
+            (0x0430F3E0, "sqincb x0"),
+            (0x04A0F7E1, "uqincw w1"),
+            (0x04F0FC42, "uqdecd x2, vl2"),
+            (0x0460CBE3, "sqdech z3.h"),
+            (0x04E1C7C4, "uqincd z4.d, mul3, mul #2"),
+        ] {
+            assert_eq!(inst_text(&decode(word), 0), text, "{:#010x}", word);
+        }
+        assert_eq!(def_use(&decode(0x0420E3E0)).defs, vec![Loc::X(0)]);
+        let incb = def_use(&decode(0x0430E3E4));
+        assert_eq!((incb.uses, incb.defs), (vec![Loc::X(4)], vec![Loc::X(4)]));
+        assert_eq!(def_use(&decode(0x05A03907)).uses, vec![Loc::X(8)]);
+        // Merging movprfx reads its destination; zeroing doesn't.
+        assert_eq!(def_use(&decode(0x04912462)).uses, vec![Loc::Z(3), Loc::P(1), Loc::Z(2)]);
+        assert_eq!(def_use(&decode(0x04D028A4)).uses, vec![Loc::Z(5), Loc::P(2)]);
+        assert_eq!(Op::from_mnemonic("sqdecw"), vec![Op::A64_QDEC_ELEMS, Op::A64_QDEC_ELEMS_Z]);
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn sve_immediates_and_permutes() {
+        for (word, text) in [
+            (0x2520DFE0, "add z0.b, z0.b, #0xff"),
+            (0x2560E021, "add z1.h, z1.h, #0x100"),
+            (0x25A1C062, "sub z2.s, z2.s, #3"),
+            (0x25E3E043, "subr z3.d, z3.d, #0x200"),
+            (0x2524C024, "sqadd z4.b, z4.b, #1"),
+            (0x2565C045, "uqadd z5.h, z5.h, #2"),
+            (0x25A6C066, "sqsub z6.s, z6.s, #3"),
+            (0x25E7C087, "uqsub z7.d, z7.d, #4"),
+            (0x2528D008, "smax z8.b, z8.b, #-0x80"),
+            (0x2569DFE9, "umax z9.h, z9.h, #0xff"),
+            (0x25AACFEA, "smin z10.s, z10.s, #0x7f"),
+            (0x25EBC00B, "umin z11.d, z11.d, #0"),
+            (0x2530DFEC, "mul z12.b, z12.b, #-1"),
+            (0x042F9020, "asr z0.b, z1.b, #1"),
+            (0x04309462, "lsr z2.h, z3.h, #0x10"),
+            (0x047F9CA4, "lsl z4.s, z5.s, #0x1f"),
+            (0x042A8128, "asr z8.b, z9.b, z10.d"),
+            (0x04B08DEE, "lsl z14.s, z15.s, z16.d"),
+            (0x65588000, "fadd z0.h, p0/m, z0.h, #0.5"),
+            (0x65D98802, "fsub z2.d, p2/m, z2.d, #0.5"),
+            (0x655A8C23, "fmul z3.h, p3/m, z3.h, #2.0"),
+            (0x659B9024, "fsubr z4.s, p4/m, z4.s, #1.0"),
+            (0x65DC9405, "fmaxnm z5.d, p5/m, z5.d, #0.0"),
+            (0x655D9826, "fminnm z6.h, p6/m, z6.h, #1.0"),
+            (0x659E9C07, "fmax z7.s, p7/m, z7.s, #0.0"),
+            (0x65DF8028, "fmin z8.d, p0/m, z8.d, #1.0"),
+            (0x05226020, "zip1 z0.b, z1.b, z2.b"),
+            (0x05656483, "zip2 z3.h, z4.h, z5.h"),
+            (0x05A868E6, "uzp1 z6.s, z7.s, z8.s"),
+            (0x05EB6D49, "uzp2 z9.d, z10.d, z11.d"),
+            (0x052E71AC, "trn1 z12.b, z13.b, z14.b"),
+            (0x0571760F, "trn2 z15.h, z16.h, z17.h"),
+            (0x05F83862, "rev z2.d, z3.d"),
+            (0x053F1CA4, "ext z4.b, z4.b, z5.b, #0xff"),
+            (0x05AA3128, "tbl z8.s, {z9.s}, z10.s"),
+            (0x05A0A020, "lasta w0, p0, z1.s"),
+            (0x05E1A462, "lastb x2, p1, z3.d"),
+            (0x052388A4, "lastb b4, p2, z5.b"),
+            (0x0570B128, "clasta w8, p4, w8, z9.h"),
+            (0x05EB9DEE, "clastb d14, p7, d14, z15.d"),
+            (0x05A98672, "clastb z18.s, p1, z18.s, z19.s"),
+        ] {
+            assert_eq!(inst_text(&decode(word), 0), text, "{:#010x}", word);
+        }
+        let clasta = def_use(&decode(0x0570B128));
+        assert_eq!((clasta.uses, clasta.defs), (vec![Loc::X(8), Loc::Z(9), Loc::P(4)], vec![Loc::X(8)]));
+        assert_eq!(def_use(&decode(0x05A0A020)).uses, vec![Loc::Z(1), Loc::P(0)]);
+        assert_eq!(def_use(&decode(0x2520DFE0)).uses, vec![Loc::Z(0)]);
+        // SVE2 forms of these groups aren't decoded yet.
+        assert_eq!(decode(0x05703820).op, Op::A64_UNKNOWN); // sunpklo z0.h, z1.b
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn sve_indexed_multiplies() {
+        use crate::aarch64_features::{required_feature, Feature};
+        for (word, text) in [
+            (0x647A0020, "fmla z0.h, z1.h, z2.h[7]"),
+            (0x64BD0083, "fmla z3.s, z4.s, z5.s[3]"),
+            (0x64FF00E6, "fmla z6.d, z7.d, z15.d[1]"),
+            (0x64230528, "fmls z8.h, z9.h, z3.h[0]"),
+            (0x64EE05AC, "fmls z12.d, z13.d, z14.d[0]"),
+            (0x64AD2083, "fmul z3.s, z4.s, z5.s[1]"),
+            (0x44820020, "sdot z0.s, z1.b, z2.b"),
+            (0x44C50483, "udot z3.d, z4.h, z5.h"),
+            (0x44BB00E6, "sdot z6.s, z7.b, z3.b[3]"),
+            (0x44FF0528, "udot z8.d, z9.h, z15.h[1]"),
+            (0x64420020, "fcmla z0.h, p0/m, z1.h, z2.h, #0"),
+            (0x64C868E6, "fcmla z6.d, p2/m, z7.d, z8.d, #270"),
+            (0x64BB1949, "fcmla z9.h, z10.h, z3.h[3], #180"),
+            (0x64FF158B, "fcmla z11.s, z12.s, z15.s[1], #90"),
+            (0x44022020, "cmla z0.b, z1.b, z2.b, #0"),
+            (0x44C52C83, "cmla z3.d, z4.d, z5.d, #270"),
+            (0x44BF64E6, "cmla z6.h, z7.h, z7.h[3], #90"),
+            (0x44FF6928, "cmla z8.s, z9.s, z15.s[1], #180"),
+            (0x64408020, "fcadd z0.h, p0/m, z0.h, z1.h, #90"),
+            (0x64C18462, "fcadd z2.d, p1/m, z2.d, z3.d, #270"),
+        ] {
+            assert_eq!(inst_text(&decode(word), 0), text, "{:#010x}", word);
+        }
+        let sdot = def_use(&decode(0x44BB00E6));
+        assert_eq!((sdot.uses, sdot.defs), (vec![Loc::Z(7), Loc::Z(3), Loc::Z(6)], vec![Loc::Z(6)]));
+        assert_eq!(required_feature(&decode(0x44022020)), Some(Feature::Sve2));
+        assert_eq!(required_feature(&decode(0x44820020)), Some(Feature::Sve));
+        // Indexed multiplies have no byte form.
+        assert_eq!(decode(0x64030528).op, Op::A64_UNKNOWN);
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn sve_loads_and_stores() {
+        assert_eq!(inst_text(&decode(0xA541A000), 0), "ld1w {z0.s}, p0/z, [x0, #1, mul vl]");
+        assert_eq!(inst_text(&decode(0xA5CFE7FF), 0), "ld3d {z31.d, z0.d, z1.d}, p1/z, [sp, #-3, mul vl]");
+        let st1d = decode(0xE5E44861);
+        assert_eq!(inst_text(&st1d, 0), "st1d {z1.d}, p2, [x3, x4, lsl #3]");
+        assert_eq!(def_use(&st1d).uses, vec![Loc::Z(1), Loc::P(2), Loc::X(3), Loc::X(4)]);
+        assert!(def_use(&st1d).defs.is_empty());
+        assert_eq!(Op::from_mnemonic("ld1sb"), vec![Op::A64_LD1_Z]);
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn sve_gathers_and_scatters() {
+        assert_eq!(inst_text(&decode(0x85214000), 0), "ld1w {z0.s}, p0/z, [x0, z1.s, uxtw #2]");
+        assert_eq!(inst_text(&decode(0xC5A1E462), 0), "ldff1d {z2.d}, p1/z, [z3.d, #8]");
+        assert_eq!(inst_text(&decode(0xE401A000), 0), "st1b {z0.d}, p0, [x0, z1.d]");
+        let ld1sh = decode(0xC4E608A4);
+        assert_eq!(inst_text(&ld1sh, 0), "ld1sh {z4.d}, p2/z, [x5, z6.d, sxtw #1]");
+        assert_eq!(def_use(&ld1sh).uses, vec![Loc::P(2), Loc::X(5), Loc::Z(6)]);
+        // ldff1d reads and clears FFR; a signed doubleword gather doesn't exist.
+        assert!(def_use(&decode(0xC5A1E462)).defs.contains(&Loc::FFR));
+        assert_eq!(decode(0xC5A1A462).op, Op::A64_UNKNOWN);
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn sve_predicates() {
+        assert_eq!(inst_text(&decode(0x2598E080), 0), "ptrue p0.s, vl4");
+        let whilelo = decode(0x25E91D01);
+        assert_eq!(inst_text(&whilelo, 0), "whilelo p1.d, x8, x9");
+        assert_eq!(def_use(&whilelo).defs, vec![Loc::P(1), Loc::NZCV]);
+        assert_eq!(inst_text(&decode(0x25A08440), 0), "cntp x0, p1, p2.s");
+        assert_eq!(inst_text(&decode(0x25904440), 0), "brkb p0.b, p1/z, p2.b");
+        assert_eq!(inst_text(&decode(0x252C9000), 0), "setffr");
+        assert_eq!(Op::from_mnemonic("whilele"), vec![Op::A64_WHILE]);
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn sve_predicate_logic_and_loops() {
+        // llvm-mc -triple=aarch64 -mattr=+sve -disassemble
+        for (word, text) in [
+            (0x25034440, "and p0.b, p1/z, p2.b, p3.b"),
+            (0x250754D4, "bic p4.b, p5/z, p6.b, p7.b"),
+            (0x250B6748, "eor p8.b, p9/z, p10.b, p11.b"),
+            (0x250F77DC, "sel p12.b, p13, p14.b, p15.b"),
+            (0x25834440, "orr p0.b, p1/z, p2.b, p3.b"),
+            (0x258754D4, "orn p4.b, p5/z, p6.b, p7.b"),
+            (0x258B6748, "nor p8.b, p9/z, p10.b, p11.b"),
+            (0x258F77DC, "nand p12.b, p13/z, p14.b, p15.b"),
+            (0x25434440, "ands p0.b, p1/z, p2.b, p3.b"),
+            (0x254754D4, "bics p4.b, p5/z, p6.b, p7.b"),
+            (0x25C754D4, "orns p4.b, p5/z, p6.b, p7.b"),
+            (0x25CF77DC, "nands p12.b, p13/z, p14.b, p15.b"),
+            (0x25024440, "mov p0.b, p1/z, p2.b"),
+            (0x25424440, "movs p0.b, p1/z, p2.b"),
+            (0x25824840, "mov p0.b, p2.b"),
+            (0x25C24840, "movs p0.b, p2.b"),
+            (0x25014640, "not p0.b, p1/z, p2.b"),
+            (0x25414640, "nots p0.b, p1/z, p2.b"),
+            (0x25034653, "mov p3.b, p1/m, p2.b"),
+            (0x25184440, "brkn p0.b, p1/z, p2.b, p0.b"),
+            (0x255850A3, "brkns p3.b, p4/z, p5.b, p3.b"),
+            (0x2503C440, "brkpa p0.b, p1/z, p2.b, p3.b"),
+            (0x2547D4D4, "brkpbs p4.b, p5/z, p6.b, p7.b"),
+            (0x252C8820, "incp x0, p1.b"),
+            (0x25ED8862, "decp x2, p3.d"),
+            (0x256C80A4, "incp z4.h, p5.h"),
+            (0x25A88820, "sqincp x0, p1.s, w0"),
+            (0x25A88C20, "sqincp x0, p1.s"),
+            (0x25698841, "uqincp w1, p2.h"),
+            (0x252A8883, "sqdecp x3, p4.b, w3"),
+            (0x25EB8149, "uqdecp z9.d, p10.d"),
+            (0x05224020, "zip1 p0.b, p1.b, p2.b"),
+            (0x05654483, "zip2 p3.h, p4.h, p5.h"),
+            (0x05A848E6, "uzp1 p6.s, p7.s, p8.s"),
+            (0x05EB4D49, "uzp2 p9.d, p10.d, p11.d"),
+            (0x052E51AC, "trn1 p12.b, p13.b, p14.b"),
+            (0x0561540F, "trn2 p15.h, p0.h, p1.h"),
+            (0x05B44062, "rev p2.s, p3.s"),
+            (0x053040A4, "punpklo p4.h, p5.b"),
+            (0x053140E6, "punpkhi p6.h, p7.b"),
+            (0x25A12000, "ctermeq w0, w1"),
+            (0x25E32050, "ctermne x2, x3"),
+        ] {
+            assert_eq!(inst_text(&decode(word), 0), text, "{:#010x}", word);
+        }
+        let ands = def_use(&decode(0x25434440));
+        assert_eq!(ands.uses, vec![Loc::P(2), Loc::P(3), Loc::P(1)]);
+        assert_eq!(ands.defs, vec![Loc::P(0), Loc::NZCV]);
+        assert_eq!(def_use(&decode(0x25184440)).uses, vec![Loc::P(2), Loc::P(0), Loc::P(1)]);
+        let incp = def_use(&decode(0x252C8820));
+        assert_eq!((incp.uses, incp.defs), (vec![Loc::P(1), Loc::X(0)], vec![Loc::X(0)]));
+        let cterm = def_use(&decode(0x25E32050));
+        assert_eq!((cterm.uses, cterm.defs), (vec![Loc::X(2), Loc::X(3), Loc::NZCV], vec![Loc::NZCV]));
+        assert_eq!(def_use(&decode(0x05224020)).defs, vec![Loc::P(0)]);
+        assert_eq!(Op::from_mnemonic("uqdecp"), vec![Op::A64_QDECP, Op::A64_QDECP_Z]);
+        assert_eq!(Op::from_mnemonic("nots"), vec![Op::A64_EOR_P]);
+        // SELS, byte vector INCP and PUNPKLO of H elements don't exist.
+        for word in [0x254F77DC, 0x252C80A4, 0x057040A4] {
+            assert_eq!(decode(word).op, Op::A64_UNKNOWN, "{:#010x}", word);
+        }
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn sve2_integer() {
+        use crate::aarch64_features::{required_feature, Feature};
+        assert_eq!(inst_text(&decode(0x04A27020), 0), "sqdmulh z0.s, z1.s, z2.s");
+        assert_eq!(inst_text(&decode(0x04A26820), 0), "smulh z0.s, z1.s, z2.s");
+        let nbsl = decode(0x04E13C40);
+        assert_eq!(inst_text(&nbsl, 0), "nbsl z0.d, z0.d, z1.d, z2.d");
+        assert_eq!(def_use(&nbsl).uses, vec![Loc::Z(0), Loc::Z(1), Loc::Z(2)]);
+        let bdep = decode(0x4582B420);
+        assert_eq!(inst_text(&bdep, 0), "bdep z0.s, z1.s, z2.s");
+        assert_eq!(required_feature(&bdep), Some(Feature::SveBitPerm));
+        let matched = decode(0x45228420);
+        assert_eq!(inst_text(&matched, 0), "match p0.b, p1/z, z1.b, z2.b");
+        assert_eq!(def_use(&matched).defs, vec![Loc::P(0), Loc::NZCV]);
+        assert_eq!(inst_text(&decode(0x45A2C420), 0), "histcnt z0.s, p1/z, z1.s, z2.s");
+        assert_eq!(required_feature(&decode(0x04A26020)), Some(Feature::Sve2)); // mul z0.s, z1.s, z2.s
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn sve2_widening_and_narrowing() {
+        use crate::aarch64_features::{legal_in_streaming_mode, required_feature, Feature};
+        // llvm-mc -triple=aarch64 -mattr=+sve2,+sve2-aes -disassemble, with
+        // immediates printed the listing's way.
+        for (word, text) in [
+            (0x45420020, "saddlb z0.h, z1.b, z2.b"),
+            (0x45850483, "saddlt z3.s, z4.h, z5.h"),
+            (0x45C808E6, "uaddlb z6.d, z7.s, z8.s"),
+            (0x45821420, "ssublt z0.s, z1.h, z2.h"),
+            (0x45421C20, "usublt z0.h, z1.b, z2.b"),
+            (0x45423020, "sabdlb z0.h, z1.b, z2.b"),
+            (0x45C23820, "uabdlb z0.d, z1.s, z2.s"),
+            (0x45424020, "saddwb z0.h, z1.h, z2.b"),
+            (0x45C24820, "uaddwb z0.d, z1.d, z2.s"),
+            (0x45825420, "ssubwt z0.s, z1.s, z2.h"),
+            (0x45426020, "sqdmullb z0.h, z1.b, z2.b"),
+            (0x45C26C20, "pmullt z0.d, z1.s, z2.s"),
+            (0x45026820, "pmullb z0.q, z1.d, z2.d"),
+            (0x45827420, "smullt z0.s, z1.h, z2.h"),
+            (0x45C27820, "umullb z0.d, z1.s, z2.s"),
+            (0x4508A020, "sshllb z0.h, z1.b, #0"),
+            (0x450FA420, "sshllt z0.h, z1.b, #7"),
+            (0x455FAC20, "ushllt z0.d, z1.s, #0x1f"),
+            (0x452F1020, "shrnb z0.b, z1.h, #1"),
+            (0x45281420, "shrnt z0.b, z1.h, #8"),
+            (0x45601C20, "rshrnt z0.s, z1.d, #0x20"),
+            (0x452F2020, "sqshrnb z0.b, z1.h, #1"),
+            (0x453E2820, "sqrshrnb z0.h, z1.s, #2"),
+            (0x457D3420, "uqshrnt z0.s, z1.d, #3"),
+            (0x452C3C20, "uqrshrnt z0.b, z1.h, #4"),
+            (0x452B0020, "sqshrunb z0.b, z1.h, #5"),
+            (0x457A0C20, "sqrshrunt z0.s, z1.d, #6"),
+            (0x45284020, "sqxtnb z0.b, z1.h"),
+            (0x45604820, "uqxtnb z0.s, z1.d"),
+            (0x45284C20, "uqxtnt z0.b, z1.h"),
+            (0x45605420, "sqxtunt z0.s, z1.d"),
+            (0x45626020, "addhnb z0.b, z1.h, z2.h"),
+            (0x45E26820, "raddhnb z0.s, z1.d, z2.d"),
+            (0x45A27020, "subhnb z0.h, z1.s, z2.s"),
+            (0x45A27C20, "rsubhnt z0.h, z1.s, z2.s"),
+            (0x042F3420, "xar z0.b, z0.b, z1.b, #1"),
+            (0x04A034E6, "xar z6.d, z6.d, z7.d, #0x40"),
+        ] {
+            assert_eq!(inst_text(&decode(word), 0), text, "{:#010x}", word);
+        }
+        assert_eq!(def_use(&decode(0x452F1020)).uses, vec![Loc::Z(1)]);
+        assert_eq!(def_use(&decode(0x45281420)).uses, vec![Loc::Z(1), Loc::Z(0)]);
+        assert_eq!(def_use(&decode(0x45A27C20)).uses, vec![Loc::Z(1), Loc::Z(2), Loc::Z(0)]);
+        assert_eq!(def_use(&decode(0x042F3420)).uses, vec![Loc::Z(0), Loc::Z(1)]);
+        let pmull128 = decode(0x45026820);
+        assert_eq!(required_feature(&pmull128), Some(Feature::SveAes));
+        assert!(!legal_in_streaming_mode(&pmull128));
+        assert_eq!(required_feature(&decode(0x45C26C20)), Some(Feature::Sve2));
+        assert_eq!(Op::from_mnemonic("uqrshrnb"), vec![Op::A64_QSHRNB_Z]);
+        assert_eq!(Op::from_mnemonic("raddhnt"), vec![Op::A64_ADDHNT_Z]);
+        assert_eq!(Op::from_mnemonic("usublb"), vec![Op::A64_SUBLB_Z]);
+        // SADDLB of byte elements, PMULLB of S elements and SQXTNB with tsz
+        // 0b011.
+        for word in [0x45020020, 0x45826820, 0x45384020] {
+            assert_eq!(decode(word).op, Op::A64_UNKNOWN, "{:#010x}", word);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn sve2_crypto() {
+        use crate::aarch64_features::{required_feature, Feature};
+        let aesd = decode(0x4522E462);
+        assert_eq!(inst_text(&aesd, 0), "aesd z2.b, z2.b, z3.b");
+        assert_eq!(def_use(&aesd).uses, vec![Loc::Z(2), Loc::Z(3)]);
+        let aesmc = decode(0x4520E004);
+        assert_eq!(inst_text(&aesmc, 0), "aesmc z4.b, z4.b");
+        assert_eq!((def_use(&aesmc).uses, def_use(&aesmc).defs), (vec![Loc::Z(4)], vec![Loc::Z(4)]));
+        assert_eq!(inst_text(&decode(0x4523E0E6), 0), "sm4e z6.s, z6.s, z7.s");
+        assert_eq!(inst_text(&decode(0x452AF128), 0), "sm4ekey z8.s, z9.s, z10.s");
+        let rax1 = decode(0x452DF58B);
+        assert_eq!(inst_text(&rax1, 0), "rax1 z11.d, z12.d, z13.d");
+        assert_eq!(required_feature(&rax1), Some(Feature::SveSha3));
+        assert_eq!(required_feature(&aesmc), Some(Feature::SveAes));
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn sme_za() {
+        assert_eq!(inst_text(&decode(0xE1000000), 0), "ldr za[w12, 0], [x0]");
+        assert_eq!(inst_text(&decode(0xE1202021), 0), "str za[w13, 1], [x1, #1, mul vl]");
+        assert_eq!(inst_text(&decode(0xE01F800F), 0), "ld1b {za0v.b[w12, 15]}, p0/z, [x0]");
+        assert_eq!(inst_text(&decode(0xE085488E), 0), "ld1w {za3h.s[w14, 2]}, p2/z, [x4, x5, lsl #2]");
+        assert_eq!(inst_text(&decode(0xE0DF7FEF), 0), "ld1d {za7h.d[w15, 1]}, p7/z, [sp]");
+        assert_eq!(inst_text(&decode(0xE1C78CCF), 0), "ld1q {za15v.q[w12, 0]}, p3/z, [x6, x7, lsl #4]");
+        let st1w = decode(0xE0A3A447);
+        assert_eq!(inst_text(&st1w, 0), "st1w {za1v.s[w13, 3]}, p1, [x2, x3, lsl #2]");
+        assert_eq!((def_use(&st1w).uses, def_use(&st1w).defs), (vec![Loc::X(13), Loc::P(1), Loc::X(2), Loc::X(3), Loc::ZA], vec![]));
+        assert_eq!(inst_text(&decode(0xC00800FF), 0), "zero {za}");
+        assert_eq!(inst_text(&decode(0xC0080055), 0), "zero {za0.h}");
+        assert_eq!(inst_text(&decode(0xC0080000), 0), "zero {}");
+        let mova = decode(0xC082E9E1);
+        assert_eq!(inst_text(&mova, 0), "mova z1.s, p2/m, za3v.s[w15, 3]");
+        assert_eq!(def_use(&mova).defs, vec![Loc::Z(1)]);
+        assert_eq!(inst_text(&decode(0xC0C10462), 0), "mova za2h.q[w12, 0], p1/m, z3.q");
+        assert_eq!(Op::from_mnemonic("ld1q"), vec![Op::A64_LD1_ZA]);
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn sme_outer_products() {
+        use crate::aarch64_features::{required_feature, Feature};
+        let fmops = decode(0x80856893);
+        assert_eq!(inst_text(&fmops, 0), "fmops za3.s, p2/m, p3/m, z4.s, z5.s");
+        assert_eq!(def_use(&fmops).uses, vec![Loc::Z(4), Loc::Z(5), Loc::P(2), Loc::P(3), Loc::ZA]);
+        assert_eq!(inst_text(&decode(0x81A32041), 0), "fmopa za1.s, p0/m, p1/m, z2.h, z3.h");
+        assert_eq!(inst_text(&decode(0x8189F912), 0), "bfmops za2.s, p6/m, p7/m, z8.h, z9.h");
+        assert_eq!(inst_text(&decode(0xA0A32042), 0), "sumopa za2.s, p0/m, p1/m, z2.b, z3.b");
+        assert_eq!(inst_text(&decode(0xA1832043), 0), "usmopa za3.s, p0/m, p1/m, z2.b, z3.b");
+        let fmopa_d = decode(0x80C7B0C7);
+        assert_eq!(inst_text(&fmopa_d, 0), "fmopa za7.d, p4/m, p5/m, z6.d, z7.d");
+        assert_eq!(required_feature(&fmopa_d), Some(Feature::SmeF64F64));
+        let usmops = decode(0xA1DEDFF7);
+        assert_eq!(inst_text(&usmops, 0), "usmops za7.d, p7/m, p6/m, z31.h, z30.h");
+        assert_eq!(required_feature(&usmops), Some(Feature::SmeI16I64));
+        assert_eq!(required_feature(&decode(0xA0832040)), Some(Feature::Sme)); // smopa za0.s
+        assert_eq!(decode(0x80832044).op, Op::A64_UNKNOWN); // za0.s with bit 2 set
+    }
+}
@@ -0,0 +1,208 @@
+//! Pointer authentication codes: where they sit in a pointer, removing them
+//! like XPACI and XPACD do, and the instructions of the hint space that sign
+//! and authenticate the return address.
+//!
+//! The PAC occupies the bits between the top of the virtual address and bit
+//! 54, plus bits 63:56 when the top byte isn't ignored (TBI). Bit 55 selects
+//! the upper or lower address range and is what the PAC bits are restored to.
+
+use crate::aarch64_reader::{Inst, Op};
+
+/// Address layout of the translation regime the pointers belong to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PacConfig {
+    /// Virtual address size: 64 - TCR_ELx.TnSZ
+    pub va_bits: u8,
+    /// Top byte ignored for data pointers (TCR_ELx.TBIn)
+    pub tbi_data: bool,
+    /// Top byte ignored for code pointers (TBIn and not TBIDn)
+    pub tbi_code: bool,
+}
+
+/// 48-bit addresses, with the top byte ignored for data only, as on Linux and
+/// the Apple platforms.
+impl Default for PacConfig {
+    fn default() -> PacConfig {
+        PacConfig { va_bits: 48, tbi_data: true, tbi_code: false }
+    }
+}
+
+impl PacConfig {
+    /// Mask of the bits that hold the PAC of a code or data pointer.
+    pub fn pac_mask(&self, code: bool) -> u64 {
+        let tbi = if code { self.tbi_code } else { self.tbi_data };
+        let top = if tbi { 55 } else { 64 };
+        let below_55 = (1u64 << 55) - (1u64 << self.va_bits.min(55));
+        if top == 64 { below_55 | (0xFF << 56) } else { below_55 }
+    }
+
+    /// The pointer with its PAC replaced by copies of bit 55 (XPACI if code,
+    /// XPACD otherwise).
+    pub fn strip(&self, ptr: u64, code: bool) -> u64 {
+        let mask = self.pac_mask(code);
+        if (ptr >> 55) & 1 == 1 { ptr | mask } else { ptr & !mask }
+    }
+
+    /// Whether stripping would change the pointer. Plain pointers have no
+    /// PAC, but a corrupted or foreign pointer looks the same as a signed one.
+    pub fn has_pac(&self, ptr: u64, code: bool) -> bool {
+        self.strip(ptr, code) != ptr
+    }
+}
+
+/// Instruction key; the data keys DA and DB aren't used by branches or hints.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PacKey {
+    A,
+    B,
+}
+
+/// Modifier of the PAC hints.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PacModifier {
+    /// *SP: the stack pointer, for return addresses
+    Sp,
+    /// *Z: zero
+    Zero,
+    /// *1716: X16, for the pointer in X17
+    X16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PacHint {
+    /// PACIASP, PACIBSP, PACIAZ, PACIBZ, PACIA1716, PACIB1716
+    Sign { key: PacKey, modifier: PacModifier },
+    /// AUTIASP, AUTIBSP, AUTIAZ, AUTIBZ, AUTIA1716, AUTIB1716
+    Auth { key: PacKey, modifier: PacModifier },
+    /// XPACLRI
+    StripLr,
+}
+
+impl PacHint {
+    /// The register holding the pointer: X17 for the *1716 forms, X30 (LR)
+    /// otherwise.
+    pub fn pointer(self) -> u8 {
+        match self {
+            PacHint::Sign { modifier: PacModifier::X16, .. } | PacHint::Auth { modifier: PacModifier::X16, .. } => 17,
+            _ => 30,
+        }
+    }
+}
+
+impl Inst {
+    /// The pointer authentication instructions of the hint space; None for
+    /// other instructions. They execute as NOPs without FEAT_PAuth.
+    pub fn pac_hint(&self) -> Option<PacHint> {
+        use PacKey::*;
+        use PacModifier::*;
+        if self.op != Op::A64_HINT {
+            return None;
+        }
+        Some(match self.imm {
+            7 => PacHint::StripLr,
+            8 => PacHint::Sign { key: A, modifier: X16 },
+            10 => PacHint::Sign { key: B, modifier: X16 },
+            12 => PacHint::Auth { key: A, modifier: X16 },
+            14 => PacHint::Auth { key: B, modifier: X16 },
+            24 => PacHint::Sign { key: A, modifier: Zero },
+            25 => PacHint::Sign { key: A, modifier: Sp },
+            26 => PacHint::Sign { key: B, modifier: Zero },
+            27 => PacHint::Sign { key: B, modifier: Sp },
+            28 => PacHint::Auth { key: A, modifier: Zero },
+            29 => PacHint::Auth { key: A, modifier: Sp },
+            30 => PacHint::Auth { key: B, modifier: Zero },
+            31 => PacHint::Auth { key: B, modifier: Sp },
+            _ => return None,
+        })
+    }
+
+    /// The key of the authenticating branches (BRA*, BLRA*, RETA*, ERETA*)
+    /// and of the PAC hints.
+    pub fn pac_key(&self) -> Option<PacKey> {
+        match self.op {
+            Op::A64_BRA | Op::A64_BLRA | Op::A64_RETA | Op::A64_ERETA => {
+                Some(if self.imm & 1 == 0 { PacKey::A } else { PacKey::B })
+            }
+            _ => match self.pac_hint()? {
+                PacHint::Sign { key, .. } | PacHint::Auth { key, .. } => Some(key),
+                PacHint::StripLr => None,
+            },
+        }
+    }
+
+    /// Whether the instruction authenticates a pointer, which fails for a
+    /// corrupted one: with FEAT_FPAC it traps, without it the pointer is made
+    /// invalid and the branch to it (or its next use) faults.
+    pub fn authenticates(&self) -> bool {
+        matches!(self.op, Op::A64_BRA | Op::A64_BLRA | Op::A64_RETA | Op::A64_ERETA)
+            || matches!(self.pac_hint(), Some(PacHint::Auth { .. }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_reader::decode;
+
+    #[test]
+    fn masks_and_stripping() {
+        let linux = PacConfig::default();
+        assert_eq!(linux.pac_mask(true), 0xFF7F_0000_0000_0000);
+        assert_eq!(linux.pac_mask(false), 0x007F_0000_0000_0000);
+        let small = PacConfig { va_bits: 39, tbi_data: false, tbi_code: false };
+        assert_eq!(small.pac_mask(false), 0xFF7F_FF80_0000_0000);
+        let full = PacConfig { va_bits: 55, tbi_data: true, tbi_code: false };
+        assert_eq!((full.pac_mask(true), full.pac_mask(false)), (0xFF00_0000_0000_0000, 0));
+
+        // Lower range: the PAC bits become zeros, and the top byte of data
+        // pointers is kept. Upper range: they become ones.
+        assert_eq!(linux.strip(0x002A_0000_1234_0000, true), 0x1234_0000);
+        assert_eq!(linux.strip(0x5A2A_0000_1234_0000, false), 0x5A00_0000_1234_0000);
+        assert_eq!(linux.strip(0x00A5_8000_1234_5678, true), 0xFFFF_8000_1234_5678);
+        assert_eq!(small.strip(0x0000_1234_5678_9ABC, true), 0x0000_0034_5678_9ABC);
+        assert!(linux.has_pac(0x002A_0000_1234_0000, true));
+        assert!(!linux.has_pac(0xFFFF_8000_1234_5678, true) && !linux.has_pac(0x5A00_0000_1234_0000, false));
+    }
+
+    #[test]
+    fn hints_and_keys() {
+        use PacKey::*;
+        use PacModifier::*;
+        // llvm-mc -triple=aarch64 -mattr=+pauth -disassemble
+        let cases = [
+            (0xD503233F, PacHint::Sign { key: A, modifier: Sp }, 30), // paciasp
+            (0xD503237F, PacHint::Sign { key: B, modifier: Sp }, 30), // pacibsp
+            (0xD503231F, PacHint::Sign { key: A, modifier: Zero }, 30), // paciaz
+            (0xD503235F, PacHint::Sign { key: B, modifier: Zero }, 30), // pacibz
+            (0xD503211F, PacHint::Sign { key: A, modifier: X16 }, 17), // pacia1716
+            (0xD503215F, PacHint::Sign { key: B, modifier: X16 }, 17), // pacib1716
+            (0xD50323BF, PacHint::Auth { key: A, modifier: Sp }, 30), // autiasp
+            (0xD50323FF, PacHint::Auth { key: B, modifier: Sp }, 30), // autibsp
+            (0xD503239F, PacHint::Auth { key: A, modifier: Zero }, 30), // autiaz
+            (0xD50323DF, PacHint::Auth { key: B, modifier: Zero }, 30), // autibz
+            (0xD503219F, PacHint::Auth { key: A, modifier: X16 }, 17), // autia1716
+            (0xD50321DF, PacHint::Auth { key: B, modifier: X16 }, 17), // autib1716
+            (0xD50320FF, PacHint::StripLr, 30), // xpaclri
+        ];
+        for (word, hint, pointer) in cases {
+            let inst = decode(word);
+            assert_eq!(inst.pac_hint(), Some(hint), "{:#010x}", word);
+            assert_eq!(hint.pointer(), pointer, "{:#010x}", word);
+            assert_eq!(inst.authenticates(), matches!(hint, PacHint::Auth { .. }), "{:#010x}", word);
+        }
+        assert_eq!(decode(0xD503237F).pac_key(), Some(B));
+        assert_eq!(decode(0xD50320FF).pac_key(), None);
+
+        // brab x1, x2; blraa x3, sp; blrabz x4; braaz x5; retab; eretaa; eretab
+        for (word, key) in [(0xD71F0C22, B), (0xD73F087F, A), (0xD63F0C9F, B), (0xD61F08BF, A), (0xD65F0FFF, B),
+            (0xD69F0BFF, A), (0xD69F0FFF, B)] {
+            let inst = decode(word);
+            assert_eq!((inst.pac_key(), inst.authenticates(), inst.pac_hint()), (Some(key), true, None), "{:#010x}", word);
+        }
+        // bti c; nop; ret
+        for word in [0xD503245F, 0xD503201F, 0xD65F03C0] {
+            let inst = decode(word);
+            assert_eq!((inst.pac_hint(), inst.pac_key(), inst.authenticates()), (None, None, false), "{:#010x}", word);
+        }
+    }
+}
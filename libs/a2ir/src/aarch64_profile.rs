@@ -0,0 +1,127 @@
+//! Sample counts of a profiler attributed to the instructions and basic
+//! blocks of a program, and printed by the listing generator.
+//!
+//! The crate doesn't read perf.data; the samples come as text, either an
+//! address per sample (`perf script -F ip`, also `-F ip,sym`) or an address
+//! and a count per line:
+//!
+//! ```text
+//! 0x1000 12
+//! 0x1008 3
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::aarch64_listing::Annotate;
+use crate::aarch64_program::Program;
+use crate::aarch64_reader::Inst;
+
+/// Parses lines of `<hex address> [<count>]`; a missing or non-numeric
+/// count (a symbol name) is a single sample. '#' starts a comment line.
+pub fn parse_samples(text: &str) -> Result<BTreeMap<u64, u64>, String> {
+    let mut out = BTreeMap::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        let mut fields = line.split_whitespace();
+        let addr = fields.next().unwrap();
+        let addr = u64::from_str_radix(addr.strip_prefix("0x").unwrap_or(addr), 16)
+            .map_err(|e| format!("bad address in {:?}: {}", line, e))?;
+        let count = fields.next().and_then(|c| c.parse().ok()).unwrap_or(1);
+        *out.entry(addr).or_insert(0) += count;
+    }
+    Ok(out)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockSamples {
+    pub start: u64,
+    pub end: u64,
+    pub count: u64,
+}
+
+/// Samples by instruction and by basic block.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Profile {
+    /// By instruction address; instructions without samples are absent.
+    pub insts: BTreeMap<u64, u64>,
+    /// By block start, every block of the program.
+    pub blocks: BTreeMap<u64, BlockSamples>,
+    /// All samples, including the unattributed ones.
+    pub total: u64,
+    /// Samples outside of the program.
+    pub unattributed: u64,
+}
+
+impl Profile {
+    /// Attributes each sample to the instruction containing its address.
+    pub fn new(program: &Program, samples: &BTreeMap<u64, u64>) -> Profile {
+        let mut profile = Profile::default();
+        for (&addr, &count) in samples {
+            profile.total += count;
+            match program.containing(addr) {
+                Some((pc, _)) => *profile.insts.entry(pc).or_insert(0) += count,
+                None => profile.unattributed += count,
+            }
+        }
+        for (start, end) in program.basic_blocks() {
+            let count = profile.insts.range(start..end).map(|(_, c)| c).sum();
+            profile.blocks.insert(start, BlockSamples { start, end, count });
+        }
+        profile
+    }
+
+    pub fn inst_count(&self, addr: u64) -> u64 {
+        self.insts.get(&addr).copied().unwrap_or(0)
+    }
+
+    /// Blocks with samples, the hottest first.
+    pub fn hottest_blocks(&self) -> Vec<&BlockSamples> {
+        let mut out: Vec<&BlockSamples> = self.blocks.values().filter(|b| b.count > 0).collect();
+        out.sort_by_key(|b| (std::cmp::Reverse(b.count), b.start));
+        out
+    }
+
+    /// Share of all samples, in percent.
+    pub fn percent(&self, count: u64) -> f64 {
+        if self.total == 0 { 0.0 } else { 100.0 * count as f64 / self.total as f64 }
+    }
+}
+
+/// Prints the block totals above the blocks with samples and the count of
+/// every instruction with samples.
+impl Annotate for Profile {
+    fn header(&self, addr: u64, _inst: &Inst) -> Vec<String> {
+        match self.blocks.get(&addr) {
+            Some(b) if b.count > 0 => {
+                vec![format!("// block {:#x}-{:#x}: {} samples ({:.1}%)", b.start, b.end, b.count, self.percent(b.count))]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn comment(&self, addr: u64, _inst: &Inst) -> Option<String> {
+        let count = self.insts.get(&addr)?;
+        Some(format!("{} samples ({:.1}%)", count, self.percent(*count)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loop_samples() {
+        let program = Program::from_words(&[
+            0xD2800140, // mov x0, #10
+            0xF1000400, // subs x0, x0, #1
+            0x54FFFFE1, // b.ne .-4
+            0xD65F03C0, // ret
+        ], 0x1000);
+        let samples = parse_samples("0x1004 6\n1008 main\n1008 main\n0x2000 2\n").unwrap();
+        let profile = Profile::new(&program, &samples);
+        assert_eq!((profile.total, profile.unattributed, profile.inst_count(0x1008)), (10, 2, 2));
+        let hottest: Vec<(u64, u64, u64)> = profile.hottest_blocks().iter().map(|b| (b.start, b.end, b.count)).collect();
+        assert_eq!(hottest, vec![(0x1004, 0x100C, 8)]);
+        assert_eq!(profile.blocks.len(), 3);
+        assert_eq!(profile.comment(0x1004, program.get(0x1004).unwrap()).unwrap(), "6 samples (60.0%)");
+    }
+}
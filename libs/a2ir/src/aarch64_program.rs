@@ -0,0 +1,163 @@
+//! Decoded programs indexed by address.
+//!
+//! A `Program` holds the decoded instructions of one or more code regions,
+//! possibly with gaps between them (data, padding, undecoded words).
+//! Lookups are O(log n) in the number of instructions.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::RangeBounds;
+
+use crate::aarch64_buffer::{decode_words, decode_words_with, DecodedBuffer};
+use crate::aarch64_decoder::Decoder;
+use crate::aarch64_reader::Inst;
+use crate::aarch64_relocate::target_address;
+
+/// Size in bytes of every A64 instruction.
+pub const INST_SIZE: u64 = 4;
+
+#[derive(Clone, Default)]
+pub struct Program {
+    insts: BTreeMap<u64, Inst>,
+}
+
+impl Program {
+    pub fn new() -> Program {
+        Program::default()
+    }
+
+    pub fn from_words(words: &[u32], base: u64) -> Program {
+        let mut program = Program::new();
+        program.extend(decode_words(words, base));
+        program
+    }
+
+    pub fn from_words_with(words: &[u32], base: u64, decoder: &dyn Decoder) -> Program {
+        let mut program = Program::new();
+        program.extend(decode_words_with(words, base, decoder));
+        program
+    }
+
+    pub fn from_buffer(buffer: DecodedBuffer) -> Program {
+        let mut program = Program::new();
+        program.extend(buffer.insts);
+        program
+    }
+
+    pub fn extend<I: IntoIterator<Item = (u64, Inst)>>(&mut self, insts: I) {
+        self.insts.extend(insts);
+    }
+
+    /// Adds or replaces the instruction at addr, returning the previous one.
+    pub fn insert(&mut self, addr: u64, inst: Inst) -> Option<Inst> {
+        self.insts.insert(addr, inst)
+    }
+
+    pub fn remove(&mut self, addr: u64) -> Option<Inst> {
+        self.insts.remove(&addr)
+    }
+
+    pub fn len(&self) -> usize {
+        self.insts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.insts.is_empty()
+    }
+
+    /// The instruction starting at addr.
+    pub fn get(&self, addr: u64) -> Option<&Inst> {
+        self.insts.get(&addr)
+    }
+
+    /// The instruction whose bytes include addr, with its start address.
+    pub fn containing(&self, addr: u64) -> Option<(u64, &Inst)> {
+        let (&start, inst) = self.insts.range(..=addr).next_back()?;
+        if addr - start < INST_SIZE {
+            Some((start, inst))
+        } else {
+            None
+        }
+    }
+
+    /// The first instruction after addr.
+    pub fn next(&self, addr: u64) -> Option<(u64, &Inst)> {
+        self.insts.range(addr.saturating_add(1)..).next().map(|(&a, i)| (a, i))
+    }
+
+    /// The last instruction before addr.
+    pub fn prev(&self, addr: u64) -> Option<(u64, &Inst)> {
+        self.insts.range(..addr).next_back().map(|(&a, i)| (a, i))
+    }
+
+    pub fn first(&self) -> Option<(u64, &Inst)> {
+        self.insts.iter().next().map(|(&a, i)| (a, i))
+    }
+
+    pub fn last(&self) -> Option<(u64, &Inst)> {
+        self.insts.iter().next_back().map(|(&a, i)| (a, i))
+    }
+
+    /// Instructions in address order; reversible.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (u64, &Inst)> {
+        self.insts.iter().map(|(&a, i)| (a, i))
+    }
+
+    /// Instructions starting within the given address range, in address order.
+    pub fn range<R: RangeBounds<u64>>(&self, range: R) -> impl DoubleEndedIterator<Item = (u64, &Inst)> {
+        self.insts.range(range).map(|(&a, i)| (a, i))
+    }
+
+    /// Contiguous runs of instructions, as (start, end) address pairs.
+    pub fn regions(&self) -> Vec<(u64, u64)> {
+        let mut out: Vec<(u64, u64)> = Vec::new();
+        for &addr in self.insts.keys() {
+            match out.last_mut() {
+                Some((_, end)) if *end == addr => *end = addr + INST_SIZE,
+                _ => out.push((addr, addr + INST_SIZE)),
+            }
+        }
+        out
+    }
+
+    /// Basic blocks as (start, end) address pairs. A block ends after a
+    /// branch other than a call, at a gap, or before the target of a direct
+    /// branch.
+    pub fn basic_blocks(&self) -> Vec<(u64, u64)> {
+        let mut leaders = BTreeSet::new();
+        for (addr, inst) in self.iter() {
+            let Some(kind) = inst.branch_kind() else { continue };
+            if let Some(target) = target_address(inst, addr).filter(|t| self.insts.contains_key(t)) {
+                leaders.insert(target);
+            }
+            if !kind.is_call() {
+                leaders.insert(addr + INST_SIZE);
+            }
+        }
+        let mut out = Vec::new();
+        for (start, end) in self.regions() {
+            let mut block = start;
+            for &leader in leaders.range(start + INST_SIZE..end) {
+                out.push((block, leader));
+                block = leader;
+            }
+            out.push((block, end));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookups_across_gaps() {
+        let mut program = Program::from_words(&[0xD503201F; 2], 0x1000);
+        program.extend(decode_words(&[0xD65F03C0], 0x2000));
+        assert_eq!(program.containing(0x1006).map(|(a, _)| a), Some(0x1004));
+        assert!(program.containing(0x1008).is_none());
+        assert_eq!(program.next(0x1004).map(|(a, _)| a), Some(0x2000));
+        assert_eq!(program.prev(0x2000).map(|(a, _)| a), Some(0x1004));
+        assert_eq!(program.regions(), vec![(0x1000, 0x1008), (0x2000, 0x2004)]);
+    }
+}
@@ -0,0 +1,275 @@
+//! Recovered prototypes: the argument registers a function reads before
+//! writing them, and the result registers it sets on every path to its
+//! returns, with a rough kind for each.
+//!
+//! ```text
+//! 1000:  ldr  w8, [x0]            // x0 is read first as a base: ptr
+//! 1004:  cmp  w1, #0              // w1: i32
+//! 1008:  add  w0, w8, #1          // w0 is set before the ret
+//! 100c:  ret                      // (x0: ptr, x1: i32) -> x0: i32
+//! ```
+//!
+//! The analysis is a forward must-definition pass over the blocks of each
+//! function, using the AAPCS64 roles of `aarch64_abi`. A call reads the
+//! arguments of the callee and defines its results once those are known,
+//! and clobbers the other caller-saved registers, so the prototypes are
+//! iterated until they stop changing. Arguments are counted up to the
+//! highest one read, the skipped ones being Unknown; results are the set
+//! result registers from X0 and V0 up, without gaps.
+//! Reading a register to spill it, or setting one as a temporary just before
+//! returning, is indistinguishable from a real argument or result.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::aarch64_abi::{has_role, with_role, Role};
+use crate::aarch64_branch::BranchKind;
+use crate::aarch64_cfg::{Cfg, Terminator};
+use crate::aarch64_defuse::{def_use, gpr, is_memory, Loc};
+use crate::aarch64_functions::Function;
+use crate::aarch64_program::{Program, INST_SIZE};
+use crate::aarch64_reader::FlagMasks::W32;
+use crate::aarch64_reader::{Inst, Op, Registries};
+use crate::aarch64_relocate::target_address;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ValueKind {
+    Int32,
+    Int64,
+    /// Used as the base of a memory access, or an address computation.
+    Pointer,
+    /// Held in a SIMD&FP register.
+    Fp,
+    Unknown,
+}
+
+impl ValueKind {
+    /// The kind of a value seen as both a and b.
+    pub fn join(a: ValueKind, b: ValueKind) -> ValueKind {
+        use ValueKind::*;
+        match (a, b) {
+            _ if a == b => a,
+            (Unknown, k) | (k, Unknown) => k,
+            (Pointer, Int32 | Int64) | (Int32 | Int64, Pointer) => Pointer,
+            (Int32, Int64) | (Int64, Int32) => Int64,
+            _ => Unknown,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ValueKind::Int32 => "i32",
+            ValueKind::Int64 => "i64",
+            ValueKind::Pointer => "ptr",
+            ValueKind::Fp => "fp",
+            ValueKind::Unknown => "?",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Prototype {
+    /// X registers first, then V registers.
+    pub arguments: Vec<(Loc, ValueKind)>,
+    pub results: Vec<(Loc, ValueKind)>,
+}
+
+fn loc_text(loc: Loc) -> String {
+    match loc {
+        Loc::X(n) => format!("x{}", n),
+        Loc::V(n) => format!("v{}", n),
+        _ => format!("{:?}", loc),
+    }
+}
+
+fn list_text(values: &[(Loc, ValueKind)]) -> String {
+    values.iter().map(|(l, k)| format!("{}: {}", loc_text(*l), k.name())).collect::<Vec<_>>().join(", ")
+}
+
+impl Prototype {
+    /// The prototype as text, e.g. `(x0: ptr, x1: i32) -> x0: i32`.
+    pub fn text(&self) -> String {
+        match self.results.len() {
+            0 => format!("({})", list_text(&self.arguments)),
+            1 => format!("({}) -> {}", list_text(&self.arguments), list_text(&self.results)),
+            _ => format!("({}) -> ({})", list_text(&self.arguments), list_text(&self.results)),
+        }
+    }
+}
+
+/// The kind of loc as inst reads it.
+fn use_kind(inst: &Inst, loc: Loc) -> ValueKind {
+    match loc {
+        Loc::V(_) => ValueKind::Fp,
+        _ if is_memory(&inst.op) && gpr(inst.rn) == Some(loc) => ValueKind::Pointer,
+        _ if is_memory(&inst.op) => ValueKind::Unknown,
+        _ if inst.flags & W32 != 0 => ValueKind::Int32,
+        _ => ValueKind::Int64,
+    }
+}
+
+/// The kind of the value inst writes to loc.
+fn def_kind(inst: &Inst, loc: Loc) -> ValueKind {
+    match inst.op {
+        _ if matches!(loc, Loc::V(_)) => ValueKind::Fp,
+        Op::A64_ADR | Op::A64_ADRP | Op::A64_MOV_SP => ValueKind::Pointer,
+        Op::A64_ADD_IMM | Op::A64_SUB_IMM if inst.rn == Registries::STACK_POINTER => ValueKind::Pointer,
+        _ if is_memory(&inst.op) => ValueKind::Unknown,
+        _ if inst.flags & W32 != 0 => ValueKind::Int32,
+        _ => ValueKind::Int64,
+    }
+}
+
+/// Registers defined on every path so far, and the kinds of the result
+/// registers among them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct State {
+    defined: BTreeSet<Loc>,
+    results: BTreeMap<Loc, ValueKind>,
+}
+
+impl State {
+    fn meet(&mut self, other: &State) {
+        self.defined.retain(|l| other.defined.contains(l));
+        self.results = self.results.iter()
+            .filter_map(|(l, k)| other.results.get(l).map(|o| (*l, ValueKind::join(*k, *o))))
+            .collect();
+    }
+}
+
+/// The prototype of the callee of a BL or B at pc, if known.
+fn callee<'a>(inst: &Inst, pc: u64, known: &'a BTreeMap<u64, Prototype>) -> Option<&'a Prototype> {
+    target_address(inst, pc).and_then(|t| known.get(&t))
+}
+
+/// The registers in order from first up to the first gap.
+fn prefix(values: &BTreeMap<Loc, ValueKind>, first: fn(u8) -> Loc) -> Vec<(Loc, ValueKind)> {
+    (0..8).map(first).map_while(|l| values.get(&l).map(|k| (l, *k))).collect()
+}
+
+fn recover(program: &Program, cfg: &Cfg, function: &Function, known: &BTreeMap<u64, Prototype>) -> Prototype {
+    let mut preds: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+    for &start in &function.blocks {
+        let end = cfg.blocks[&start].end;
+        let pads = cfg.exception_edges.range(start..end).map(|(_, pad)| *pad);
+        for next in cfg.successors(start).into_iter().chain(pads).filter(|n| function.blocks.contains(n)) {
+            preds.entry(next).or_default().push(start);
+        }
+    }
+
+    let mut reads: BTreeMap<Loc, ValueKind> = BTreeMap::new();
+    let mut exits: Option<BTreeMap<Loc, ValueKind>> = None;
+    let mut outs: BTreeMap<u64, State> = BTreeMap::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        (reads, exits) = (BTreeMap::new(), None);
+        for &start in &function.blocks {
+            let mut state = if start == function.entry {
+                State::default()
+            } else {
+                let mut incoming = preds.get(&start).into_iter().flatten().filter_map(|p| outs.get(p));
+                let Some(first) = incoming.next() else { continue };
+                let mut state = first.clone();
+                incoming.for_each(|s| state.meet(s));
+                state
+            };
+
+            let block = &cfg.blocks[&start];
+            for (pc, inst) in program.range(start..block.end) {
+                let du = def_use(inst);
+                let is_call = inst.branch_kind().is_some_and(BranchKind::is_call);
+                let tail = pc + INST_SIZE == block.end && matches!(block.terminator, Terminator::TailCall(_));
+                let mut used: Vec<(Loc, ValueKind)> = du.uses.iter().map(|&l| (l, use_kind(inst, l))).collect();
+                if is_call || tail {
+                    used.extend(callee(inst, pc, known).into_iter().flat_map(|p| p.arguments.iter().copied()));
+                }
+                for (l, kind) in used.into_iter().filter(|(l, _)| has_role(*l, Role::Argument) && !state.defined.contains(l)) {
+                    let kind = reads.get(&l).map_or(kind, |k| ValueKind::join(*k, kind));
+                    reads.insert(l, kind);
+                }
+                for &l in &du.defs {
+                    state.defined.insert(l);
+                    if has_role(l, Role::Result) {
+                        state.results.insert(l, def_kind(inst, l));
+                    }
+                }
+                if is_call {
+                    state.defined.extend(with_role(Role::CallerSaved));
+                    state.results.clear();
+                    state.results.extend(callee(inst, pc, known).into_iter().flat_map(|p| p.results.iter().copied()));
+                }
+            }
+
+            let returned = match block.terminator {
+                Terminator::Return => Some(state.results.clone()),
+                Terminator::TailCall(_) => program.prev(block.end)
+                    .and_then(|(pc, inst)| callee(inst, pc, known))
+                    .map(|p| p.results.iter().copied().collect()),
+                _ => None,
+            };
+            if let Some(returned) = returned {
+                let mut all = State { defined: BTreeSet::new(), results: returned };
+                if let Some(seen) = &exits {
+                    all.meet(&State { defined: BTreeSet::new(), results: seen.clone() });
+                }
+                exits = Some(all.results);
+            }
+            if outs.get(&start) != Some(&state) {
+                outs.insert(start, state);
+                changed = true;
+            }
+        }
+    }
+
+    let mut arguments = Vec::new();
+    for first in [Loc::X as fn(u8) -> Loc, Loc::V] {
+        let count = (0..8).rev().find(|&n| reads.contains_key(&first(n))).map_or(0, |n| n + 1);
+        arguments.extend((0..count).map(|n| (first(n), reads.get(&first(n)).copied().unwrap_or(ValueKind::Unknown))));
+    }
+    let exits = exits.unwrap_or_default();
+    let mut results = prefix(&exits, Loc::X);
+    results.extend(prefix(&exits, Loc::V));
+    Prototype { arguments, results }
+}
+
+/// The prototypes of the functions, by entry.
+pub fn prototypes(program: &Program, cfg: &Cfg, functions: &[Function]) -> BTreeMap<u64, Prototype> {
+    let mut known = BTreeMap::new();
+    // Results only feed the callers' results, so the chains settle after
+    // as many rounds as the call graph is deep; recursion may not settle.
+    for _ in 0..=functions.len() {
+        let next: BTreeMap<u64, Prototype> = functions.iter().map(|f| (f.entry, recover(program, cfg, f, &known))).collect();
+        if next == known {
+            break;
+        }
+        known = next;
+    }
+    known
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_functions::functions;
+
+    #[test]
+    fn arguments_and_results() {
+        let program = Program::from_words(&[
+            0xB9400008, // 0x1000: ldr w8, [x0]
+            0x7100003F, //         cmp w1, #0
+            0x11000500, //         add w0, w8, #1
+            0xD65F03C0, //         ret
+            0xA9BF7BFD, // 0x1010: stp x29, x30, [sp, #-16]!
+            0x97FFFFFB, //         bl 0x1000
+            0xA8C17BFD, //         ldp x29, x30, [sp], #16
+            0xD65F03C0, //         ret
+        ], 0x1000);
+        let mut cfg = Cfg::build(&program);
+        let functions = functions(&mut cfg, &BTreeSet::from([0x1000, 0x1010]));
+        let prototypes = prototypes(&program, &cfg, &functions);
+        assert_eq!(prototypes[&0x1000].arguments, vec![(Loc::X(0), ValueKind::Pointer), (Loc::X(1), ValueKind::Int32)]);
+        assert_eq!(prototypes[&0x1000].text(), "(x0: ptr, x1: i32) -> x0: i32");
+        // The wrapper passes its arguments through and returns the result.
+        assert_eq!(prototypes[&0x1010].text(), "(x0: ptr, x1: i32) -> x0: i32");
+    }
+}
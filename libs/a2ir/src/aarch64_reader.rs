@@ -1,6 +1,6 @@
 use crate::aarch64_reader::ExtendType::{SXTB, SXTH, SXTW, UXTB, UXTH};
 use crate::aarch64_reader::FlagMasks::{SET_FLAGS, W32};
-use crate::aarch64_reader::Op::{A64_ADD_IMM, A64_ADR, A64_ADRP, A64_AND_IMM, A64_ASR_IMM, A64_BFC, A64_BFI, A64_BFM, A64_BFXIL, A64_CMN_IMM, A64_CMP_IMM, A64_EOR_IMM, A64_EXTEND, A64_EXTR, A64_LSL_IMM, A64_LSR_IMM, A64_MOV_IMM, A64_MOV_SP, A64_MOVK, A64_ORR_IMM, A64_ROR_IMM, A64_SBFIZ, A64_SBFM, A64_SBFX, A64_SUB_IMM, A64_TST_IMM, A64_UBFIZ, A64_UBFM, A64_UBFX, A64_UNKNOWN};
+use crate::aarch64_reader::Op::{A64_ADD_IMM, A64_ADR, A64_ADRP, A64_AND_IMM, A64_ASR_IMM, A64_BFC, A64_BFI, A64_BFM, A64_BFXIL, A64_CMN_IMM, A64_CMP_IMM, A64_EOR_IMM, A64_EXTEND, A64_EXTR, A64_LSL_IMM, A64_LSR_IMM, A64_MOV_IMM, A64_MOV_SP, A64_MOVK, A64_ORR_IMM, A64_ROR_IMM, A64_SBFIZ, A64_SBFM, A64_SBFX, A64_SUB_IMM, A64_TST_IMM, A64_UBFIZ, A64_UBFM, A64_UBFX};
 use crate::aarch64_reader::OpKind::{AddSub, AddSubTags, Bitfield, Extract, Logic, Move, PCRelAddr, Unknown};
 use crate::aarch64_reader::Registries::{STACK_POINTER, ZERO_REG};
 
@@ -11,7 +11,7 @@ use crate::aarch64_reader::Registries::{STACK_POINTER, ZERO_REG};
 ///We split up this overloaded register: when we encounter R31 and interpret it as
 ///the stack pointer, we assign a different number. This way, the user does not
 ///need to know which instructions use the SP and which use the ZR.
-mod Registries {
+pub mod Registries {
     pub const ZERO_REG: u8 = 31;
     pub const STACK_POINTER: u8 = 100;
 }
@@ -32,7 +32,7 @@ mod Registries {
 /// condition encoded in the Inst.flags field. The various addressing
 /// modes of loads and stores are encoded similarly. See the Inst
 /// structure for more detail.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Op {
     A64_UNKNOWN,
     /// unknown instruction (or Op field not set, by accident), Inst.imm contains raw binary instruction
@@ -107,7 +107,7 @@ pub enum Op {
 
     A64_BCOND,
 
-    /// Exception generation
+    /// Exception generation -- Inst.imm := imm16, see Inst::exception_imm
     ///
     /// With the exception of SVC, they are not interesting for lifting
     /// userspace programs, but were included since they are trivial.
@@ -123,9 +123,14 @@ pub enum Op {
 
     /// Hints -- we treat all allocated hints as NOP and don't decode to the "aliases"
     /// NOP, YIELD, ...
+    ///
+    /// Inst.imm := CRm:op2. The pointer authentication hints (PACIASP, ...)
+    /// aren't NOPs; see Inst::pac_hint.
     A64_HINT,
 
     /// Barriers
+    ///
+    /// Inst.imm := CRm, the option of DMB and DSB (e.g. 0b1011 for ISH).
     A64_CLREX,
     A64_DMB,
     A64_ISB,
@@ -143,13 +148,14 @@ pub enum Op {
     A64_AXFlag,
     /// ------
 
-    /// System instructions -- Inst.ldst.rt := Xt
+    /// System instructions -- Inst.rd := Xt; Inst.sys := op1, CRn, CRm, op2
     A64_SYS,
     /// SYS #op1, Cn, Cm, #op2(, Xt)
     A64_SYSL,
     /// SYSL Xt, #op1, Cn, Cm, #op2
 
-    /// System register move -- Inst.ldst.rt := Xt; Inst.imm := sysreg
+    /// System register move -- Inst.rd := Xt; Inst.imm := sysreg
+    /// (op0:op1:CRn:CRm:op2), see SysReg
     A64_MSR_REG,
     /// MSR <sysreg>, Xt
     A64_MRS,
@@ -160,6 +166,17 @@ pub enum Op {
     A64_BLR,
     A64_RET,
 
+    /// Pointer authenticating branches -- Inst.imm := key (0 = A, 1 = B),
+    /// Inst.rm := modifier (ZERO_REG for the Z variants, SP for RETA*, ERETA*)
+    A64_BRA,
+    A64_BLRA,
+    A64_RETA,
+
+    /// Exception return
+    A64_ERET,
+    A64_ERETA,
+    A64_DRPS,
+
     /// Unconditional branch (immediate)
     A64_B,
     A64_BL,
@@ -301,6 +318,9 @@ pub enum Op {
 
     /// Advanced SIMD load/store multiple structures
     /// Advanced SIMD load/store multiple structures (post-indexed)
+    ///
+    /// The registers are consecutive modulo 32, starting at Inst.rd; see
+    /// simd_load_store for the other fields.
     A64_LD1_MULT,
     A64_ST1_MULT,
     A64_LD2_MULT,
@@ -326,6 +346,9 @@ pub enum Op {
     A64_LD4R,
 
     /// Load/store exclusive
+    ///
+    /// Inst.rs := status register of the stores, Inst.rt2 := second register
+    /// of the pairs. The mem_extend size is that of one register.
     A64_LDXR,
     /// includes Load-acquire variants
     A64_STXR,
@@ -405,6 +428,9 @@ pub enum Op {
     /// Compare and Swap (actually from Exclusive group)
     A64_CASP,
     /// Compare and Swap Pair of (double)words (actually from Exclusive group)
+    ///
+    /// Rs and Rt are even and name the pairs Rs:Rs+1 and Rt:Rt+1; Inst.rt2 :=
+    /// Rt+1. The decoder rejects odd registers.
 
     /*** Data Processing -- Scalar Floating-Point and Advanced SIMD ***/
 
@@ -650,7 +676,8 @@ pub enum Op {
     A64_UMOV,
     /// Xd ← Src[i]
     A64_TBL,
-    /// Inst.imm := #regs of table ∈ {1,2,3,4}
+    /// Inst.imm := #regs of table ∈ {1,2,3,4}, consecutive modulo 32 from
+    /// Inst.rn
     A64_TBX,
     /// ---
     A64_TRN1,
@@ -792,6 +819,484 @@ pub enum Op {
     /// SIMD Polynomial Multiply
     A64_PMUL,
     A64_PMULL,
+
+    /// SVE Integer Arithmetic (Z registers, predicated and unpredicated)
+    ///
+    /// Signedness (e.g. SMAX vs UMAX) is encoded via the SIMD_SIGNED flag,
+    /// the element size and governing predicate in Inst.sve.
+    A64_ADD_Z,
+    A64_SUB_Z,
+    /// Reversed: Zdn := Zm - Zdn
+    A64_SUBR_Z,
+    A64_MAX_Z,
+    A64_MIN_Z,
+    A64_ABD_Z,
+    A64_MUL_Z,
+    A64_MULH_Z,
+    A64_DIV_Z,
+    A64_DIVR_Z,
+    A64_AND_Z,
+    A64_ORR_Z,
+    A64_EOR_Z,
+    A64_BIC_Z,
+    /// SQADD, UQADD
+    A64_QADD_Z,
+    /// SQSUB, UQSUB
+    A64_QSUB_Z,
+    A64_MLA_Z,
+    A64_MLS_Z,
+    /// MAD Zdn.T, Pg/M, Zm.T, Za.T: Zdn := Za + Zdn * Zm, with Inst.rn :=
+    /// Zdn, Inst.rm := Zm and Inst.ra := Za
+    A64_MAD_Z,
+    A64_MSB_Z,
+    /// Shifts, predicated: ASR Zdn.T, Pg/M, Zdn.T, Zm.T; the ...R forms
+    /// shift Zm by Zdn
+    A64_ASR_Z,
+    A64_LSR_Z,
+    A64_LSL_Z,
+    A64_ASRR_Z,
+    A64_LSRR_Z,
+    A64_LSLR_Z,
+    /// By the D elements of Zm: ASR Zdn.T, Pg/M, Zdn.T, Zm.D
+    A64_ASR_WIDE_Z,
+    A64_LSR_WIDE_Z,
+    A64_LSL_WIDE_Z,
+    /// By an immediate: ASR Zdn.T, Pg/M, Zdn.T, #imm with Inst.imm := the
+    /// shift
+    A64_ASR_IMM_Z,
+    A64_LSR_IMM_Z,
+    A64_LSL_IMM_Z,
+    /// Arithmetic shift right for divide, rounding towards zero
+    A64_ASRD_Z,
+    /// Unary, predicated: ABS Zd.T, Pg/M, Zn.T with Inst.rn := Zn
+    ///
+    /// SXTB ... UXTW: Inst.extend.typ := the ExtendType
+    A64_EXTEND_Z,
+    A64_ABS_Z,
+    A64_NEG_Z,
+    A64_CLS_Z,
+    A64_CLZ_Z,
+    A64_CNT_Z,
+    A64_CNOT_Z,
+    A64_NOT_Z,
+    /// Reductions: SADDV Dd, Pg, Zn.T and UADDV (by SIMD_SIGNED) into a D
+    /// scalar, the others into one of the element size; Inst.rn := Zn
+    A64_ADDV_Z,
+    A64_MAXV_Z,
+    A64_MINV_Z,
+    A64_ORV_Z,
+    A64_EORV_Z,
+    A64_ANDV_Z,
+    /// Compares: CMP<cc> Pd.T, Pg/Z, Zn.T, Zm.T, with the condition in
+    /// the flags as for B.cond. They set the flags as PTEST does.
+    A64_CMP_Z,
+    /// With the D elements of Zm
+    A64_CMP_WIDE_Z,
+    /// With Inst.imm, signed for the signed conditions
+    A64_CMP_IMM_Z,
+    /// INDEX Zd.T, #imm, #imm: Inst.imm := the start, Inst.offset := the
+    /// step
+    A64_INDEX_IMM_Z,
+    /// INDEX Zd.T, #imm, Rm: Inst.imm := the start
+    A64_INDEX_IMM_REG_Z,
+    /// INDEX Zd.T, Rn, #imm: Inst.imm := the step
+    A64_INDEX_REG_IMM_Z,
+    /// INDEX Zd.T, Rn, Rm; W32 marks W registers in all four forms.
+    A64_INDEX_REG_Z,
+    /// ADR Zd.T, [Zn.T, Zm.T{, mod #amount}]: Inst.extend.typ := the
+    /// ExtendType of Zm (UXTX for LSL), Inst.extend.lsl := the amount
+    A64_ADR_Z,
+    /// Logical immediates: ORR Zdn.T, Zdn.T, #imm with Inst.imm := the
+    /// mask of an element
+    A64_ORR_IMM_Z,
+    A64_EOR_IMM_Z,
+    A64_AND_IMM_Z,
+    /// DUPM Zd.T, #imm
+    A64_DUPM_Z,
+    /// Unpredicated, with an immediate: ADD Zdn.T, Zdn.T, #imm{, LSL #8}
+    /// with Inst.rn := Zdn and Inst.imm := the shifted immediate, signed
+    /// for SMAX, SMIN and MUL
+    A64_ADD_IMM_Z,
+    A64_SUB_IMM_Z,
+    A64_SUBR_IMM_Z,
+    A64_QADD_IMM_Z,
+    A64_QSUB_IMM_Z,
+    A64_MAX_IMM_Z,
+    A64_MIN_IMM_Z,
+    A64_MUL_IMM_Z,
+    /// DUP Zd.T, #imm{, LSL #8}: Inst.imm := the element, sign-extended
+    A64_DUP_IMM_Z,
+    /// CPY Zd.T, Pg/M|Z, #imm{, LSL #8}, with a 4-bit Pg
+    A64_CPY_IMM_Z,
+    /// DUP Zd.T, Rn|SP: W32 marks the elements narrower than D
+    A64_DUP_REG_Z,
+    /// CPY Zd.T, Pg/M, Rn|SP
+    A64_CPY_REG_Z,
+    /// CPY Zd.T, Pg/M, Vn: Inst.rn := Vn, a scalar of the element size
+    A64_CPY_FP_Z,
+    /// DUP Zd.T, Zn.T[imm]: Inst.offset := the index
+    A64_DUP_ELEM_Z,
+    /// SEL Zd.T, Pg, Zn.T, Zm.T: Zn where Pg is active, Zm elsewhere
+    A64_SEL_Z,
+    /// MOVPRFX Zd, Zn and MOVPRFX Zd.T, Pg/M|Z, Zn.T, which prefix a
+    /// destructive instruction
+    A64_MOVPRFX_Z,
+    /// Element counts: CNTB Xd{, pattern{, MUL #imm}} ... CNTD by the
+    /// element size, with Inst.imm := the pattern, Inst.offset := the
+    /// multiplier
+    A64_CNT_ELEMS,
+    /// INCB Xdn{, pattern{, MUL #imm}}, as A64_CNT_ELEMS
+    A64_INC_ELEMS,
+    A64_DEC_ELEMS,
+    /// SQINCB and UQINCB (by SIMD_SIGNED) of Xdn, and with W32 SQINCB Xdn,
+    /// Wdn and UQINCB Wdn
+    A64_QINC_ELEMS,
+    A64_QDEC_ELEMS,
+    /// Of the elements of Zdn: INCH Zdn.H{, pattern{, MUL #imm}}
+    A64_INC_ELEMS_Z,
+    A64_DEC_ELEMS_Z,
+    A64_QINC_ELEMS_Z,
+    A64_QDEC_ELEMS_Z,
+    /// Permutes: ZIP1 Zd.T, Zn.T, Zm.T
+    A64_ZIP1_Z,
+    A64_ZIP2_Z,
+    A64_UZP1_Z,
+    A64_UZP2_Z,
+    A64_TRN1_Z,
+    A64_TRN2_Z,
+    /// REV Zd.T, Zn.T
+    A64_REV_Z,
+    /// EXT Zdn.B, Zdn.B, Zm.B, #imm: Inst.rn := Zdn, Inst.rm := Zm,
+    /// Inst.imm := the byte offset
+    A64_EXT_Z,
+    /// TBL Zd.T, {Zn.T}, Zm.T
+    A64_TBL_Z,
+    /// LASTA Rd, Pg, Zn.T: the element after the last active one (LASTB:
+    /// the last active one); W32 marks the elements narrower than D.
+    A64_LASTA_REG_Z,
+    A64_LASTB_REG_Z,
+    /// LASTA Vd, Pg, Zn.T
+    A64_LASTA_FP_Z,
+    A64_LASTB_FP_Z,
+    /// CLASTA Zdn.T, Pg, Zdn.T, Zm.T: as LASTA of Zm, broadcast, but Zdn
+    /// is kept if no element is active; Inst.rn := Zdn, Inst.rm := Zm
+    A64_CLASTA_Z,
+    A64_CLASTB_Z,
+    /// CLASTA Rdn, Pg, Rdn, Zm.T
+    A64_CLASTA_REG_Z,
+    A64_CLASTB_REG_Z,
+    /// CLASTA Vdn, Pg, Vdn, Zm.T
+    A64_CLASTA_FP_Z,
+    A64_CLASTB_FP_Z,
+    /// SDOT Zda.T, Zn.Tb, Zm.Tb and UDOT (by SIMD_SIGNED): Inst.sve.src :=
+    /// Tb, a quarter of T
+    A64_DOT_Z,
+    /// SDOT Zda.T, Zn.Tb, Zm.Tb[imm]: Inst.offset := the index
+    A64_DOT_ELEM_Z,
+
+    /// SVE Floating-Point Arithmetic
+    A64_FADD_Z,
+    A64_FSUB_Z,
+    A64_FMUL_Z,
+    A64_FSUBR_Z,
+    A64_FMAXNM_Z,
+    A64_FMINNM_Z,
+    A64_FMAX_Z,
+    A64_FMIN_Z,
+    A64_FABD_Z,
+    A64_FSCALE_Z,
+    A64_FMULX_Z,
+    A64_FDIVR_Z,
+    A64_FDIV_Z,
+    /// Trigonometric starting value multiply; unpredicated only
+    A64_FTSMUL_Z,
+    A64_FRECPS_Z,
+    A64_FRSQRTS_Z,
+    A64_FMLA_Z,
+    A64_FMLS_Z,
+    A64_FNMLA_Z,
+    A64_FNMLS_Z,
+    /// FMAD Zdn.T, Pg/M, Zm.T, Za.T, as A64_MAD_Z
+    A64_FMAD_Z,
+    A64_FMSB_Z,
+    A64_FNMAD_Z,
+    A64_FNMSB_Z,
+    /// Unary, predicated, as A64_ABS_Z
+    A64_FABS_Z,
+    A64_FNEG_Z,
+    /// Inst.frint.mode := the FPRounding (FPR_CURRENT for FRINTI)
+    A64_FRINT_Z,
+    A64_FRINTX_Z,
+    A64_FRECPX_Z,
+    A64_FSQRT_Z,
+    /// Conversions: FCVT Zd.T, Pg/M, Zn.Tb with Inst.sve.src := Tb
+    A64_FCVT_Z,
+    /// FCVTZS and FCVTZU, by SIMD_SIGNED
+    A64_FCVTZ_Z,
+    /// SCVTF and UCVTF, by SIMD_SIGNED
+    A64_CVTF_Z,
+    /// Reductions: FADDV Vd, Pg, Zn.T with Inst.rn := Zn
+    A64_FADDV_Z,
+    A64_FMAXNMV_Z,
+    A64_FMINNMV_Z,
+    A64_FMAXV_Z,
+    A64_FMINV_Z,
+    /// Strictly ordered: FADDA Vdn, Pg, Vdn, Zm.T with Inst.rn := Vdn
+    A64_FADDA_Z,
+    /// Compares: FCM<cc> Pd.T, Pg/Z, Zn.T, Zm.T, condition as A64_CMP_Z;
+    /// COND_VS is FCMUO (unordered).
+    A64_FCM_Z,
+    /// Of the absolute values: FACGE, FACGT
+    A64_FAC_Z,
+    /// FCM<cc> Pd.T, Pg/Z, Zn.T, #0.0
+    A64_FCM_ZERO_Z,
+    /// With an immediate: FADD Zdn.T, Pg/M, Zdn.T, #imm, Inst.rn := Zdn
+    /// and Inst.fimm := 0.5 or 1.0 (FMUL: 0.5 or 2.0, the maximums and
+    /// minimums: 0.0 or 1.0)
+    A64_FADD_IMM_Z,
+    A64_FSUB_IMM_Z,
+    A64_FMUL_IMM_Z,
+    A64_FSUBR_IMM_Z,
+    A64_FMAXNM_IMM_Z,
+    A64_FMINNM_IMM_Z,
+    A64_FMAX_IMM_Z,
+    A64_FMIN_IMM_Z,
+    /// FDUP Zd.T, #imm and FCPY Zd.T, Pg/M, #imm: Inst.fimm := the
+    /// immediate (see VFPExpandImm)
+    A64_FDUP_Z,
+    A64_FCPY_Z,
+    /// By element: FMLA Zda.T, Zn.T, Zm.T[imm] with Inst.offset := the
+    /// index
+    A64_FMLA_ELEM_Z,
+    A64_FMLS_ELEM_Z,
+    A64_FMUL_ELEM_Z,
+    /// Complex: FCMLA Zda.T, Pg/M, Zn.T, Zm.T, #rot with Inst.imm := the
+    /// rotation in degrees (0, 90, 180, 270)
+    A64_FCMLA_Z,
+    /// FCMLA Zda.T, Zn.T, Zm.T[imm], #rot, with Inst.offset := the index
+    A64_FCMLA_ELEM_Z,
+    /// FCADD Zdn.T, Pg/M, Zdn.T, Zm.T, #rot: Inst.rn := Zdn, Inst.rm :=
+    /// Zm, Inst.imm := the rotation (90, 270)
+    A64_FCADD_Z,
+
+    /// SVE Contiguous Loads and Stores
+    ///
+    /// The access size and signedness are in Inst.sve_ldst, the element
+    /// size and governing predicate in Inst.sve.
+    A64_LD1_Z,
+    /// First-fault
+    A64_LDFF1_Z,
+    /// Non-fault
+    A64_LDNF1_Z,
+    /// Non-temporal
+    A64_LDNT1_Z,
+    A64_LD2_Z,
+    A64_LD3_Z,
+    A64_LD4_Z,
+    A64_ST1_Z,
+    A64_STNT1_Z,
+    A64_ST2_Z,
+    A64_ST3_Z,
+    A64_ST4_Z,
+
+    /// SVE Predicate Manipulation
+    ///
+    /// The element size (a Size) is in Inst.sve.esize and the governing
+    /// predicate in Inst.sve.pg; SET_FLAGS marks the flag-setting forms
+    /// (PTRUES, BRKAS, RDFFRS), which set NZCV from the result as PTEST
+    /// does.
+    ///
+    /// PTRUE Pd.T{, pattern}: Inst.imm := the pattern
+    A64_PTRUE,
+    A64_PFALSE,
+    /// PFIRST Pdn.B, Pg, Pdn.B
+    A64_PFIRST,
+    /// PNEXT Pdn.T, Pv, Pdn.T: Inst.rn := Pv
+    A64_PNEXT,
+    /// PTEST Pg, Pn.B
+    A64_PTEST,
+    /// BRKA Pd.B, Pg/M|Z, Pn.B
+    A64_BRKA,
+    A64_BRKB,
+    /// WHILE<cond> Pd.T, Rn, Rm: the condition is in the flags, as for
+    /// B.cond, and W32 marks W registers.
+    A64_WHILE,
+    /// CNTP Xd, Pg, Pn.T
+    A64_CNTP,
+    /// RDFFR Pd.B{, Pg/Z}
+    A64_RDFFR,
+    A64_SETFFR,
+    /// WRFFR Pn.B: Inst.rn := Pn
+    A64_WRFFR,
+    /// The predicate logical ops, Pd.B, Pg/Z, Pn.B, Pm.B (SEL Pd.B, Pg,
+    /// Pn.B, Pm.B). SET_FLAGS marks ANDS, BICS and the others but SEL.
+    A64_AND_P,
+    A64_BIC_P,
+    A64_EOR_P,
+    A64_SEL_P,
+    A64_ORR_P,
+    A64_ORN_P,
+    A64_NOR_P,
+    A64_NAND_P,
+    /// BRKN Pdm.B, Pg/Z, Pn.B, Pdm.B
+    A64_BRKN,
+    /// BRKPA Pd.B, Pg/Z, Pn.B, Pm.B
+    A64_BRKPA,
+    A64_BRKPB,
+    /// INCP Xdn, Pm.T: Inst.rn := Pm. The saturating SQINCP, UQINCP,
+    /// SQDECP and UQDECP are A64_QINCP and A64_QDECP, SIMD_SIGNED marking
+    /// the signed ones and W32 the 32-bit forms.
+    A64_INCP,
+    A64_DECP,
+    A64_QINCP,
+    A64_QDECP,
+    /// INCP Zdn.T, Pm.T
+    A64_INCP_Z,
+    A64_DECP_Z,
+    A64_QINCP_Z,
+    A64_QDECP_Z,
+    /// ZIP1 Pd.T, Pn.T, Pm.T
+    A64_ZIP1_P,
+    A64_ZIP2_P,
+    A64_UZP1_P,
+    A64_UZP2_P,
+    A64_TRN1_P,
+    A64_TRN2_P,
+    /// REV Pd.T, Pn.T
+    A64_REV_P,
+    /// PUNPKLO Pd.H, Pn.B
+    A64_PUNPKLO,
+    A64_PUNPKHI,
+    /// CTERMEQ Rn, Rm: sets N and V, V from C when the condition doesn't
+    /// hold, and leaves Z and C. W32 marks W registers.
+    A64_CTERMEQ,
+    A64_CTERMNE,
+
+    /// SVE2 Integer
+    ///
+    /// The unpredicated MUL, SMULH and UMULH of SVE2 are A64_MUL_Z and
+    /// A64_MULH_Z.
+    A64_PMUL_Z,
+    A64_SQDMULH_Z,
+    A64_SQRDMULH_Z,
+    /// Bitwise ternary: EOR3 Zdn.D, Zdn.D, Zm.D, Zk.D with Inst.ra := Zk
+    A64_EOR3_Z,
+    A64_BCAX_Z,
+    A64_BSL_Z,
+    A64_BSL1N_Z,
+    A64_BSL2N_Z,
+    A64_NBSL_Z,
+    /// Complex integer multiply-add: CMLA Zda.T, Zn.T, Zm.T, #rot with
+    /// Inst.imm := the rotation in degrees
+    A64_CMLA_Z,
+    /// CMLA Zda.T, Zn.T, Zm.T[imm], #rot with Inst.offset := the index
+    A64_CMLA_ELEM_Z,
+    /// Widening: SADDLB Zd.T, Zn.Tb, Zm.Tb with Inst.sve.src := Tb, half
+    /// the size of T. The B forms read the even (bottom) elements of the
+    /// sources, the T forms the odd (top) ones.
+    A64_ADDLB_Z,
+    A64_ADDLT_Z,
+    A64_SUBLB_Z,
+    A64_SUBLT_Z,
+    A64_ABDLB_Z,
+    A64_ABDLT_Z,
+    /// SADDWB Zd.T, Zn.T, Zm.Tb
+    A64_ADDWB_Z,
+    A64_ADDWT_Z,
+    A64_SUBWB_Z,
+    A64_SUBWT_Z,
+    /// SMULLB Zd.T, Zn.Tb, Zm.Tb
+    A64_MULLB_Z,
+    A64_MULLT_Z,
+    A64_SQDMULLB_Z,
+    A64_SQDMULLT_Z,
+    /// PMULLB Zd.T, Zn.Tb, Zm.Tb; Zd.Q, Zn.D, Zm.D is FEAT_SVE_PMULL128's.
+    A64_PMULLB_Z,
+    A64_PMULLT_Z,
+    /// SSHLLB Zd.T, Zn.Tb, #imm
+    A64_SHLLB_Z,
+    A64_SHLLT_Z,
+    /// Narrowing: SHRNB Zd.T, Zn.Tb, #imm with Inst.sve.src := Tb, twice
+    /// the size of T. The B forms write the even elements of Zd and zero
+    /// the odd ones, the T forms write the odd ones and keep the even.
+    /// SIMD_ROUND marks RSHRNB, SQRSHRNB and the others rounding.
+    A64_SHRNB_Z,
+    A64_SHRNT_Z,
+    /// SQSHRNB and UQSHRNB
+    A64_QSHRNB_Z,
+    A64_QSHRNT_Z,
+    A64_SQSHRUNB_Z,
+    A64_SQSHRUNT_Z,
+    /// SQXTNB Zd.T, Zn.Tb and UQXTNB
+    A64_QXTNB_Z,
+    A64_QXTNT_Z,
+    A64_SQXTUNB_Z,
+    A64_SQXTUNT_Z,
+    /// ADDHNB Zd.T, Zn.Tb, Zm.Tb
+    A64_ADDHNB_Z,
+    A64_ADDHNT_Z,
+    A64_SUBHNB_Z,
+    A64_SUBHNT_Z,
+    /// XAR Zdn.T, Zdn.T, Zm.T, #imm: Inst.imm := the rotation
+    A64_XAR_Z,
+    /// Bit permute (FEAT_SVE_BitPerm)
+    A64_BEXT_Z,
+    A64_BDEP_Z,
+    A64_BGRP_Z,
+    /// HISTCNT Zd.T, Pg/Z, Zn.T, Zm.T
+    A64_HISTCNT_Z,
+    A64_HISTSEG_Z,
+    /// MATCH Pd.T, Pg/Z, Zn.T, Zm.T: Inst.rd := Pd; sets the flags.
+    A64_MATCH_Z,
+    A64_NMATCH_Z,
+    /// SVE2 crypto: AESE Zdn.B, Zdn.B, Zm.B (FEAT_SVE_AES)
+    A64_AESE_Z,
+    A64_AESD_Z,
+    /// AESMC Zdn.B, Zdn.B
+    A64_AESMC_Z,
+    A64_AESIMC_Z,
+    /// SM4E Zdn.S, Zdn.S, Zm.S (FEAT_SVE_SM4)
+    A64_SM4E_Z,
+    A64_SM4EKEY_Z,
+    /// RAX1 Zd.D, Zn.D, Zm.D (FEAT_SVE_SHA3)
+    A64_RAX1_Z,
+
+    /// SME: the ZA array and its tiles, addressed by Inst.za
+    ///
+    /// LDR ZA[Wv, #imm], [Xn{, #imm, MUL VL}]: Inst.rn := Xn, Inst.offset :=
+    /// imm, the same as the slice offset.
+    A64_LDR_ZA,
+    A64_STR_ZA,
+    /// LD1B {ZAtH.B[Wv, #imm]}, Pg/Z, [Xn{, Xm}], ... LD1Q: Inst.sve.esize
+    /// := the element size (FSZ_Q for LD1Q), Inst.rn := Xn, Inst.rm := Xm.
+    A64_LD1_ZA,
+    A64_ST1_ZA,
+    /// ZERO {mask}: Inst.imm := the tiles ZA0.D...ZA7.D cleared, a bit each
+    A64_ZERO_ZA,
+    /// MOVA Zd.T, Pg/M, ZAnH.T[Wv, #imm]: tile slice to vector
+    A64_MOVA_Z,
+    /// MOVA ZAdH.T[Wv, #imm], Pg/M, Zn.T: vector to tile slice, Inst.rn := Zn
+    A64_MOVA_ZA,
+
+    /// SME outer products -- FMOPA ZAda.T, Pn/M, Pm/M, Zn.Tb, Zm.Tb:
+    /// Inst.sve.esize := T, Inst.sve.pg := Pn, Inst.rn := Zn, Inst.rm := Zm,
+    /// Inst.mop := the tile, Pm and Tb. The ...S forms subtract.
+    A64_FMOPA_ZA,
+    A64_FMOPS_ZA,
+    /// BF16 into single precision tiles
+    A64_BFMOPA_ZA,
+    A64_BFMOPS_ZA,
+    /// The sums of four products of bytes (halfwords into D tiles),
+    /// signed or unsigned by mnemonic: SUMOPA multiplies signed Zn elements
+    /// by unsigned Zm ones, USMOPA the other way round.
+    A64_SMOPA_ZA,
+    A64_SMOPS_ZA,
+    A64_UMOPA_ZA,
+    A64_UMOPS_ZA,
+    A64_SUMOPA_ZA,
+    A64_SUMOPS_ZA,
+    A64_USMOPA_ZA,
+    A64_USMOPS_ZA,
 }
 
 /// The condition bits used by conditial branches, selects and compares, stored in the
@@ -834,6 +1339,75 @@ pub mod Cond {
     pub const COND_AL: u8 = 0b1110;
     /// Always true (not "never" as in A32!)
     pub const COND_NV: u8 = 0b1111;
+
+    /// Assembler names by encoding; HS and LO print as CS and CC.
+    pub const NAMES: [&str; 16] = ["eq", "ne", "cs", "cc", "mi", "pl", "vs", "vc", "hi", "ls", "ge", "lt", "gt", "le", "al", "nv"];
+
+    pub fn name(cond: u8) -> &'static str {
+        NAMES[(cond & 0xF) as usize]
+    }
+
+    /// The condition named name, aliases included.
+    pub fn from_name(name: &str) -> Option<u8> {
+        match name {
+            "hs" => Some(COND_HS),
+            "lo" => Some(COND_LO),
+            _ => NAMES.iter().position(|n| *n == name).map(|c| c as u8),
+        }
+    }
+
+    pub fn is_always(cond: u8) -> bool {
+        cond & 0xE == COND_AL
+    }
+
+    /// The encoding a condition is compared by: NV behaves as AL.
+    pub fn canonical(cond: u8) -> u8 {
+        if is_always(cond) { COND_AL } else { cond & 0xF }
+    }
+
+    /// Whether a and b hold for the same flags.
+    pub fn equivalent(a: u8, b: u8) -> bool {
+        canonical(a) == canonical(b)
+    }
+
+    /// The condition that holds exactly when cond doesn't; AL and NV have
+    /// none.
+    pub fn invert(cond: u8) -> Option<u8> {
+        (!is_always(cond)).then_some((cond & 0xF) ^ 0b001)
+    }
+
+    /// The condition that holds after `cmp b, a` exactly when cond holds
+    /// after `cmp a, b`. MI, PL, VS and VC have none.
+    pub fn negate_for_swap(cond: u8) -> Option<u8> {
+        match canonical(cond) {
+            COND_HI => Some(COND_LO),
+            COND_LO => Some(COND_HI),
+            COND_HS => Some(COND_LS),
+            COND_LS => Some(COND_HS),
+            COND_GE => Some(COND_LE),
+            COND_LE => Some(COND_GE),
+            COND_GT => Some(COND_LT),
+            COND_LT => Some(COND_GT),
+            c @ (COND_EQ | COND_NE | COND_AL) => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Whether cond holds for the flags nzcv, N being bit 3 and V bit 0.
+    pub fn holds(cond: u8, nzcv: u8) -> bool {
+        let (n, z, c, v) = (nzcv & 8 != 0, nzcv & 4 != 0, nzcv & 2 != 0, nzcv & 1 != 0);
+        let base = match (cond >> 1) & 0b111 {
+            0 => z,
+            1 => c,
+            2 => n,
+            3 => v,
+            4 => c && !z,
+            5 => n == v,
+            6 => n == v && !z,
+            _ => return true,
+        };
+        base != (cond & 1 != 0)
+    }
 }
 
 pub mod Shift {
@@ -855,9 +1429,10 @@ pub mod Shift {
 /// (#4 for 128 bits (SIMD), #3 for 64 bits, #2 for 32 bits, #1 for
 /// 16 bits, #0 for 8 bits) and is used for array indexing:
 ///
-///     u64 a[128];
-///     u64 x0 = a[i]; → ldr x0, [a, i, LSL #3]
-///
+/// ```text
+/// u64 a[128];
+/// u64 x0 = a[i]; → ldr x0, [a, i, LSL #3]
+/// ```
 pub mod AddrMode {
     /// [base] -- used by atomics, exclusive, ordered load/stores → check Inst.ldst_order
     pub const AM_SIMPLE: u8 = 0;
@@ -876,8 +1451,8 @@ pub mod AddrMode {
 }
 
 /// Memory ordering semantics for Atomic instructions and the Load/Stores in the
-/// Exclusive group.
-#[derive(Clone)]
+/// Exclusive group. Stored in Inst.ldst_order.{load,store} as u16.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum MemOrdering {
     MO_NONE,
     /// Load-Acquire -- sequentially consistent Acquire
@@ -902,6 +1477,14 @@ pub mod Size {
     pub const SZ_W: u8 = 0b10;
     /// Extended - 64 bit
     pub const SZ_X: u8 = 0b11;
+
+    pub const fn bytes(sz: u8) -> u32 {
+        1 << (sz & 0b11)
+    }
+
+    pub const fn bits(sz: u8) -> u32 {
+        8 * bytes(sz)
+    }
 }
 
 /// Floating-point size, encoded in three bits. Mostly synonymous to Size, but
@@ -922,6 +1505,23 @@ pub mod FPSize {
     // There, Quad precision is encoded in various incoherent ways.
     /// Quad   - 128 bits
     pub const FSZ_Q: u8 = 0b111;
+
+    pub const fn bytes(fsz: u8) -> u32 {
+        if fsz == FSZ_Q { 16 } else { 1 << (fsz & 0b11) }
+    }
+
+    pub const fn bits(fsz: u8) -> u32 {
+        8 * bytes(fsz)
+    }
+
+    /// The Size of the same width; None for FSZ_Q, which has no Size.
+    pub const fn to_size(fsz: u8) -> Option<u8> {
+        if fsz <= FSZ_D { Some(fsz) } else { None }
+    }
+
+    pub const fn from_size(sz: u8) -> u8 {
+        sz & 0b11
+    }
 }
 
 /// The three-bit Vector Arrangement specifier determines the structure of the
@@ -949,6 +1549,34 @@ pub mod VectorArrangement {
     pub const VA_1D: u8 = (FPSize::FSZ_D << 1) | 0;
     /// 128 bit
     pub const VA_2D: u8 = (FPSize::FSZ_D << 1) | 1;
+
+    /// Arrangement of elements of the given FPSize (B, H, S or D) filling the
+    /// bottom 64 bits (q = false) or the whole 128-bit register (q = true).
+    pub const fn from_elem(fsz: u8, q: bool) -> u8 {
+        ((fsz & 0b11) << 1) | q as u8
+    }
+
+    /// Element size, as FPSize.
+    pub const fn elem_size(va: u8) -> u8 {
+        (va >> 1) & 0b11
+    }
+
+    pub const fn elem_bytes(va: u8) -> u32 {
+        FPSize::bytes(elem_size(va))
+    }
+
+    /// Whether the arrangement uses all 128 bits (the Q bit).
+    pub const fn is_q(va: u8) -> bool {
+        va & 1 != 0
+    }
+
+    pub const fn total_bits(va: u8) -> u32 {
+        if is_q(va) { 128 } else { 64 }
+    }
+
+    pub const fn lanes(va: u8) -> u32 {
+        total_bits(va) / (8 * elem_bytes(va))
+    }
 }
 
 /// Floating-point rounding mode. See shared/functions/float/fprounding/FPRounding
@@ -987,7 +1615,8 @@ pub mod ExtendType {
 }
 
 /// PstateField: encodes which PSTATE bits the MSR_IMM instruction modifies.
-#[derive(Clone)]
+/// Stored in Inst.msr_imm.psfld as u32, with the value in Inst.msr_imm.imm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum PStateField {
     PSF_UAO,
     PSF_PAN,
@@ -996,6 +1625,66 @@ pub enum PStateField {
     PSF_DIT,
     PSF_DAIFSet,
     PSF_DAIFClr,
+    /// MTE tag check override
+    PSF_TCO,
+    /// NMI: all interrupts mask
+    PSF_ALLINT,
+    /// EBEP: PMU exception mask
+    PSF_PM,
+    /// SME streaming mode (SMSTART SM, SMSTOP SM)
+    PSF_SVCRSM,
+    /// SME ZA storage (SMSTART ZA, SMSTOP ZA)
+    PSF_SVCRZA,
+    /// Both (SMSTART, SMSTOP)
+    PSF_SVCRSMZA,
+}
+
+impl PStateField {
+    pub const ALL: [PStateField; 13] = [
+        PStateField::PSF_UAO,
+        PStateField::PSF_PAN,
+        PStateField::PSF_SPSel,
+        PStateField::PSF_SSBS,
+        PStateField::PSF_DIT,
+        PStateField::PSF_DAIFSet,
+        PStateField::PSF_DAIFClr,
+        PStateField::PSF_TCO,
+        PStateField::PSF_ALLINT,
+        PStateField::PSF_PM,
+        PStateField::PSF_SVCRSM,
+        PStateField::PSF_SVCRZA,
+        PStateField::PSF_SVCRSMZA,
+    ];
+
+    /// Inverse of `as u32`, for reading Inst.msr_imm.psfld back.
+    pub fn from_u32(psfld: u32) -> Option<PStateField> {
+        PStateField::ALL.get(psfld as usize).copied()
+    }
+
+    /// The operand name in assembly; SMSTART and SMSTOP call the SVCR
+    /// fields sm and za.
+    pub fn name(self) -> &'static str {
+        match self {
+            PStateField::PSF_UAO => "uao",
+            PStateField::PSF_PAN => "pan",
+            PStateField::PSF_SPSel => "spsel",
+            PStateField::PSF_SSBS => "ssbs",
+            PStateField::PSF_DIT => "dit",
+            PStateField::PSF_DAIFSet => "daifset",
+            PStateField::PSF_DAIFClr => "daifclr",
+            PStateField::PSF_TCO => "tco",
+            PStateField::PSF_ALLINT => "allint",
+            PStateField::PSF_PM => "pm",
+            PStateField::PSF_SVCRSM => "sm",
+            PStateField::PSF_SVCRZA => "za",
+            PStateField::PSF_SVCRSMZA => "svcrsmza",
+        }
+    }
+
+    /// The fields of SVCR, which SMSTART and SMSTOP set and clear.
+    pub fn is_svcr(self) -> bool {
+        matches!(self, PStateField::PSF_SVCRSM | PStateField::PSF_SVCRZA | PStateField::PSF_SVCRSMZA)
+    }
 }
 
 pub mod FlagMasks {
@@ -1011,130 +1700,341 @@ pub mod FlagMasks {
     pub const SIMD_ROUND: u8 = 1 << 7;
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Movk {
-    imm16: u32,
-    lsl: u32,
+    pub(crate) imm16: u32,
+    pub(crate) lsl: u32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Bfm {
-    lsb: u32,
-    width: u32,
+    pub(crate) lsb: u32,
+    pub(crate) width: u32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Ccmp {
-    nzcv: u32,
-    imm5: u32,
+    pub(crate) nzcv: u32,
+    pub(crate) imm5: u32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Sys {
-    op1: u16,
-    op2: u16,
-    crn: u16,
-    crm: u16,
+    pub(crate) op1: u16,
+    pub(crate) op2: u16,
+    pub(crate) crn: u16,
+    pub(crate) crm: u16,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct MsrImm {
-    psfld: u32,
-    imm: u32,
+    pub(crate) psfld: u32,
+    pub(crate) imm: u32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Tbz {
-    offset: i32,
-    bit: u32,
+    pub(crate) offset: i32,
+    pub(crate) bit: u32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct InstShift {
-    typ: u32,
-    amount: u32,
+    pub(crate) typ: u32,
+    pub(crate) amount: u32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Rmif {
-    mask: u32,
-    ror: u32,
+    pub(crate) mask: u32,
+    pub(crate) ror: u32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Extend {
-    typ: u32,
-    lsl: u32,
+    pub(crate) typ: u32,
+    pub(crate) lsl: u32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct LdstOrder {
-    load: u16,
-    store: u16,
-    rs: u8,
+    pub(crate) load: u16,
+    pub(crate) store: u16,
+    pub(crate) rs: u8,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct SimdLdst {
-    nreg: u32,
-    index: u16,
-    offset: i16,
+    pub(crate) nreg: u32,
+    pub(crate) index: u16,
+    pub(crate) offset: i16,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Fcvt {
-    mode: u32,
-    fbits: u16,
-    sgn: u16,
+    pub(crate) mode: u32,
+    pub(crate) fbits: u16,
+    pub(crate) sgn: u16,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Frint {
-    mode: u32,
-    bits: u32,
+    pub(crate) mode: u32,
+    pub(crate) bits: u32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct InsElem {
-    dst: u32,
-    src: u32,
+    pub(crate) dst: u32,
+    pub(crate) src: u32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct FcmlaElem {
-    idx: u32,
-    rot: u32,
+    pub(crate) idx: u32,
+    pub(crate) rot: u32,
+}
+
+/// How inactive elements of the destination are treated under a governing
+/// predicate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Predication {
+    /// /M: keep their previous value
+    Merging,
+    /// /Z: set them to zero
+    Zeroing,
+}
+
+/// SVE: the element size of the Z registers as FPSize, and the governing
+/// predicate P0...P7 if the form is predicated, with its mode (/M or /Z)
+/// if it has one; the predicate of stores has none. The conversions have
+/// the elements of their source in src.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Sve {
+    pub(crate) esize: u8,
+    pub(crate) pg: Option<u8>,
+    pub(crate) mode: Option<Predication>,
+    pub(crate) src: u8,
+}
+
+/// SVE loads and stores: the access size msz (a Size, which may be smaller
+/// than the element size), whether loaded elements are sign-extended, the
+/// number of registers transferred, and the AddrMode: AM_OFF_IMM with
+/// Inst.offset in multiples of the vector length ([Xn, #imm, MUL VL]), or
+/// AM_OFF_REG with Inst.rm scaled by the access size ([Xn, Xm, LSL #msz]).
+///
+/// Gathers and scatters (vector) address an element each: AM_OFF_IMM with
+/// the base Inst.rn a Z register and Inst.offset in bytes ([Zn.D, #imm]),
+/// or AM_OFF_EXT with the offsets in the Z register Inst.rm, extended by
+/// the ExtendType extend (UXTX for 64-bit offsets) and, if scaled, shifted
+/// by the access size ([Xn, Zm.S, SXTW #2]).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SveLdst {
+    pub(crate) msz: u8,
+    pub(crate) signed: bool,
+    pub(crate) nreg: u8,
+    pub(crate) mode: u8,
+    pub(crate) vector: bool,
+    pub(crate) extend: u8,
+    pub(crate) scaled: bool,
+}
+
+/// SME: a slice of a ZA tile, or (LDR and STR) a vector of the ZA array.
+/// The slice is selected by the register W12...W15, as rv := 0...3, plus
+/// offset; tiles are numbered within their element size (ZA0.B; ZA0.H and
+/// ZA1.H; ... ZA0.Q-ZA15.Q).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Za {
+    pub(crate) tile: u8,
+    pub(crate) vertical: bool,
+    pub(crate) rv: u8,
+    pub(crate) offset: u8,
+}
+
+/// SME: the operands of an outer product besides Zn, Zm and Pn.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Mop {
+    /// ZA0...ZA3 of S tiles, ZA0...ZA7 of D tiles
+    pub(crate) tile: u8,
+    /// The predicate of the Zm elements
+    pub(crate) pm: u8,
+    /// The FPSize of the elements of Zn and Zm, narrower than those of the
+    /// tile for the widening forms
+    pub(crate) elem: u8,
 }
 
 #[derive(Clone)]
 pub struct Inst {
-    op: Op,
-    flags: u8,
-    rd: u8,
-    rn: u8,
-    rm: u8,
-    rt2: u8,
-    rs: u8,
-    imm: u64,
-    fimm: f64,
-    offset: i64,
-    ra: u8,
-    error: String,
-    movk: Movk,
-    bfm: Bfm,
-    ccmp: Ccmp,
-    sys: Sys,
-    msr_imm: MsrImm,
-    tbz: Tbz,
-    shift: u8,
-    rmif: Rmif,
-    extend: Extend,
-    ldst_order: LdstOrder,
-    simd_ldst: SimdLdst,
-    fcvt: Fcvt,
-    frint: Frint,
-    ins_elem: InsElem,
-    fcmla_elem: FcmlaElem,
+    pub(crate) op: Op,
+    pub(crate) flags: u8,
+    pub(crate) rd: u8,
+    pub(crate) rn: u8,
+    pub(crate) rm: u8,
+    pub(crate) rt2: u8,
+    pub(crate) rs: u8,
+    pub(crate) imm: u64,
+    pub(crate) fimm: f64,
+    pub(crate) offset: i64,
+    pub(crate) ra: u8,
+    pub(crate) error: String,
+    pub(crate) movk: Movk,
+    pub(crate) bfm: Bfm,
+    pub(crate) ccmp: Ccmp,
+    pub(crate) sys: Sys,
+    pub(crate) msr_imm: MsrImm,
+    pub(crate) tbz: Tbz,
+    pub(crate) shift: u8,
+    pub(crate) rmif: Rmif,
+    pub(crate) extend: Extend,
+    pub(crate) ldst_order: LdstOrder,
+    pub(crate) simd_ldst: SimdLdst,
+    pub(crate) fcvt: Fcvt,
+    pub(crate) frint: Frint,
+    pub(crate) ins_elem: InsElem,
+    pub(crate) fcmla_elem: FcmlaElem,
+    pub(crate) sve: Sve,
+    pub(crate) sve_ldst: SveLdst,
+    pub(crate) za: Za,
+    pub(crate) mop: Mop,
+    /// The encoding the instruction was decoded from.
+    pub(crate) raw: u32,
+}
+
+impl Inst {
+    pub fn raw(&self) -> u32 {
+        self.raw
+    }
+
+    /// The encoding as it is laid out in memory.
+    pub fn bytes(&self) -> [u8; 4] {
+        self.raw.to_le_bytes()
+    }
+
+    /// The 16-bit immediate of SVC, HVC, SMC, BRK, HLT and DCPS1-3; the
+    /// system call number for SVC on most systems is in a register instead.
+    pub fn exception_imm(&self) -> Option<u16> {
+        match self.op {
+            Op::A64_SVC | Op::A64_HVC | Op::A64_SMC | Op::A64_BRK | Op::A64_HLT | Op::A64_DCPS1 | Op::A64_DCPS2
+            | Op::A64_DCPS3 => Some(self.imm as u16),
+            _ => None,
+        }
+    }
+}
+
+/// The opcode-specific part of an Inst. Decoders only fill in the one that
+/// applies to the opcode, so the others are ignored by comparisons.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum Payload<'a> {
+    None,
+    Movk(&'a Movk),
+    Bfm(&'a Bfm),
+    Ccmp(&'a Ccmp),
+    Sys(&'a Sys),
+    MsrImm(&'a MsrImm),
+    Tbz(&'a Tbz),
+    Rmif(&'a Rmif),
+    Extend(&'a Extend),
+    LdstOrder(&'a LdstOrder),
+    SimdLdst(&'a SimdLdst),
+    Fcvt(&'a Fcvt),
+    Frint(&'a Frint),
+    InsElem(&'a InsElem),
+    FcmlaElem(&'a FcmlaElem),
+    Sve(&'a Sve),
+    SveLdst(&'a Sve, &'a SveLdst),
+    Za(&'a Sve, &'a Za),
+    Mop(&'a Sve, &'a Mop),
+    /// The SVE ops with a floating-point immediate, as for Fimm.
+    SveFimm(&'a Sve, u64),
+    /// Bit pattern of Inst.fimm, so that equality is reflexive.
+    Fimm(u64),
+    Error(&'a str),
+}
+
+impl Inst {
+    fn payload(&self) -> Payload<'_> {
+        use Op::*;
+        match self.op {
+            A64_ERROR => Payload::Error(&self.error),
+            A64_MOVK => Payload::Movk(&self.movk),
+            A64_SBFM | A64_SBFIZ | A64_SBFX | A64_BFM | A64_BFC | A64_BFI | A64_BFXIL | A64_UBFM
+            | A64_UBFIZ | A64_UBFX => Payload::Bfm(&self.bfm),
+            A64_CCMN_REG | A64_CCMP_REG | A64_CCMN_IMM | A64_CCMP_IMM | A64_FCCMP | A64_FCCMPE => Payload::Ccmp(&self.ccmp),
+            A64_SYS | A64_SYSL | A64_MSR_REG | A64_MRS => Payload::Sys(&self.sys),
+            A64_MSR_IMM => Payload::MsrImm(&self.msr_imm),
+            A64_TBZ | A64_TBNZ => Payload::Tbz(&self.tbz),
+            A64_RMIF => Payload::Rmif(&self.rmif),
+            A64_EXTEND | A64_ADD_EXT | A64_CMN_EXT | A64_SUB_EXT | A64_CMP_EXT => Payload::Extend(&self.extend),
+            A64_LDXR | A64_STXR | A64_LDXP | A64_STXP | A64_LDAPR | A64_LDADD | A64_LDCLR | A64_LDEOR
+            | A64_LDSET | A64_LDSMAX | A64_LDSMIN | A64_LDUMAX | A64_LDUMIN | A64_SWP | A64_CAS
+            | A64_CASP => Payload::LdstOrder(&self.ldst_order),
+            A64_LDR | A64_STR if fad_get_addrmode(self.flags) == AddrMode::AM_SIMPLE => Payload::LdstOrder(&self.ldst_order),
+            A64_LD1_MULT | A64_ST1_MULT | A64_LD2_MULT | A64_ST2_MULT | A64_LD3_MULT | A64_ST3_MULT
+            | A64_LD4_MULT | A64_ST4_MULT | A64_LD1_SINGLE | A64_ST1_SINGLE | A64_LD2_SINGLE
+            | A64_ST2_SINGLE | A64_LD3_SINGLE | A64_ST3_SINGLE | A64_LD4_SINGLE | A64_ST4_SINGLE
+            | A64_LD1R | A64_LD2R | A64_LD3R | A64_LD4R => Payload::SimdLdst(&self.simd_ldst),
+            A64_FCVT_GPR | A64_FCVT_VEC | A64_CVTF | A64_CVTF_VEC => Payload::Fcvt(&self.fcvt),
+            A64_FRINT | A64_FRINT_VEC | A64_FRINTX | A64_FRINTX_VEC => Payload::Frint(&self.frint),
+            A64_INS_ELEM | A64_DUP_ELEM => Payload::InsElem(&self.ins_elem),
+            A64_FCMLA_ELEM => Payload::FcmlaElem(&self.fcmla_elem),
+            A64_FMOV_IMM => Payload::Fimm(self.fimm.to_bits()),
+            _ if (A64_LD1_Z..=A64_ST4_Z).contains(&self.op) => Payload::SveLdst(&self.sve, &self.sve_ldst),
+            _ if (A64_FADD_IMM_Z..=A64_FCPY_Z).contains(&self.op) => Payload::SveFimm(&self.sve, self.fimm.to_bits()),
+            _ if self.op >= A64_FMOPA_ZA => Payload::Mop(&self.sve, &self.mop),
+            _ if self.op >= A64_LDR_ZA => Payload::Za(&self.sve, &self.za),
+            _ if self.op >= A64_ADD_Z => Payload::Sve(&self.sve),
+            _ => Payload::None,
+        }
+    }
+
+    fn key(&self) -> (Op, u8, [u8; 6], u64, i64, u8, Payload<'_>) {
+        let regs = [self.rd, self.rn, self.rm, self.rt2, self.rs, self.ra];
+        (self.op, self.flags, regs, self.imm, self.offset, self.shift, self.payload())
+    }
+}
+
+/// Instructions are equal if they mean the same: the raw encoding and the
+/// payloads that don't apply to the opcode are ignored.
+impl PartialEq for Inst {
+    fn eq(&self, other: &Inst) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Inst {}
+
+impl std::hash::Hash for Inst {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl std::fmt::Debug for Inst {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut d = f.debug_struct("Inst");
+        d.field("op", &self.op)
+            .field("raw", &format_args!("{:#010x}", self.raw))
+            .field("flags", &format_args!("{:#010b}", self.flags))
+            .field("rd", &self.rd)
+            .field("rn", &self.rn)
+            .field("rm", &self.rm)
+            .field("rt2", &self.rt2)
+            .field("rs", &self.rs)
+            .field("ra", &self.ra)
+            .field("imm", &self.imm)
+            .field("offset", &self.offset)
+            .field("shift", &self.shift);
+        match self.payload() {
+            Payload::None => {}
+            Payload::Fimm(_) => {
+                d.field("fimm", &self.fimm);
+            }
+            payload => {
+                d.field("payload", &payload);
+            }
+        }
+        d.finish()
+    }
 }
 
 const UNKNOWN_INST: Inst = Inst {
@@ -1182,6 +2082,24 @@ const UNKNOWN_INST: Inst = Inst {
     frint: Frint { mode: 0, bits: 0 },
     ins_elem: InsElem { dst: 0, src: 0 },
     fcmla_elem: FcmlaElem { idx: 0, rot: 0 },
+    sve: Sve {
+        esize: 0,
+        pg: None,
+        mode: None,
+        src: 0,
+    },
+    sve_ldst: SveLdst {
+        msz: 0,
+        signed: false,
+        nreg: 0,
+        mode: 0,
+        vector: false,
+        extend: 0,
+        scaled: false,
+    },
+    za: Za { tile: 0, vertical: false, rv: 0, offset: 0 },
+    mop: Mop { tile: 0, pm: 0, elem: 0 },
+    raw: 0,
 };
 
 pub fn errinst(err: String) -> Inst {
@@ -1203,7 +2121,7 @@ fn set_cond(flags: u8, cond: u8) -> u8 {
 
 pub fn invert_cond(flags: u8) -> u8 {
     let cond = fad_get_cond(flags);
-    return set_cond(flags, cond as u8 ^ 0b001); // invert LSB
+    return set_cond(flags, Cond::invert(cond).unwrap_or(cond));
 }
 
 // Addressing mode, for Loads and Stores.
@@ -1242,7 +2160,7 @@ pub fn set_prec(flags: u8, prec: u8) -> u8 {
 }
 
 pub fn fad_size_from_vec_arrangement(va: u8) -> u8 {
-    return va >> 1;
+    return VectorArrangement::elem_size(va);
 }
 
 // The destination register Rd, if present, occupies bits 0..4.
@@ -1349,7 +2267,7 @@ pub fn data_proc_imm(binst: u32) -> Inst {
 
             inst.rd = regRd(binst);
         }
-        AddSubTags => panic!("ADDG, SUBG not supported"),
+        AddSubTags => return UNKNOWN_INST, // ADDG, SUBG aren't decoded yet
         AddSub => {
             let is_add = (top3 & 0b010) == 0;
             inst.op = if is_add { A64_ADD_IMM } else { A64_SUB_IMM };
@@ -1385,13 +2303,16 @@ pub fn data_proc_imm(binst: u32) -> Inst {
                     inst.op = if regRd(binst) == ZERO_REG { A64_TST_IMM } else { A64_AND_IMM };
                     inst.flags |= SET_FLAGS;
                 }
-                _ => panic!("Unexpected Logic Operator {}", top3 & 0b011),
+                _ => return UNKNOWN_INST,
             }
 
             let immr: u8 = ((binst >> 16) & 0b111111) as u8;
             let imms: u8 = ((binst >> 10) & 0b111111) as u8;
-            let N: u8 = if inst.flags & W32 != 0 { 0 } else { ((binst >> 22) & 1) as u8 }; // N is part of imm for 64-bit variants
-            inst.imm = decode_bitmask(N, imms, immr, inst.flags & W32 != 0);
+            let N: u8 = ((binst >> 22) & 1) as u8; // 64-bit elements: unallocated for 32-bit variants
+            inst.imm = match decode_bitmask(N, imms, immr, inst.flags & W32 != 0) {
+                Some(mask) => mask,
+                None => return UNKNOWN_INST,
+            };
 
             // ANDS and by extension TST interpret R31 as the zero register, while
             // regular immediate AND interprets it as the stack pointer.
@@ -1402,6 +2323,9 @@ pub fn data_proc_imm(binst: u32) -> Inst {
             let hw: u8 = ((binst >> 21) & 0b11) as u8;
             let shift: u8 = (16 * hw) as u8;
             let imm16: u64 = ((binst >> 5) & 0xFFFF) as u64;
+            if (inst.flags & W32) != 0 && hw >= 2 { // a W register has two halfwords
+                return UNKNOWN_INST;
+            }
 
             match top3 & 0b011 {
                 0b00 => { // MOVN: Move with NOT
@@ -1428,17 +2352,27 @@ pub fn data_proc_imm(binst: u32) -> Inst {
                 0b00 => A64_SBFM,
                 0b01 => A64_BFM,
                 0b10 => A64_UBFM,
-                _ => panic!("data_proc_imm/Bitfield: neither SBFM, BFM or UBFM")
+                _ => return UNKNOWN_INST,
             };
 
             let w32 = (inst.flags & W32) != 0;
             let immr: u8 = ((binst >> 16) & 0b111111) as u8;
             let imms: u8 = ((binst >> 10) & 0b111111) as u8;
+            // N must match sf, and 32-bit variants only have 5-bit fields.
+            if ((binst >> 22) & 1 == 0) != w32 || (w32 && (immr | imms) >= 32) {
+                return UNKNOWN_INST;
+            }
             let rd = regRd(binst);
             let rn = regRn(binst);
             inst = find_bfm_alias(op, w32, rd, rn, immr, imms);
         }
         Extract => {
+            // op21 and o0 are zero, N matches sf, and imms is < 32 for W.
+            let w32 = (inst.flags & W32) != 0;
+            if (binst >> 29) & 0b11 != 0 || (binst >> 21) & 1 != 0 || ((binst >> 22) & 1 == 0) != w32
+                || (w32 && (binst >> 15) & 1 != 0) {
+                return UNKNOWN_INST;
+            }
             inst.op = A64_EXTR;
             inst.imm = ((binst >> 10) & 0b111111) as u64;
             inst.rd = regRd(binst);
@@ -1459,6 +2393,9 @@ pub fn data_proc_imm(binst: u32) -> Inst {
 /// Returns the 0-based index of the highest bit. Should be compiled down
 /// to a single native instruction.
 fn highest_bit(mut x: u32) -> i32 {
+    if x == 0 {
+        return -1;
+    }
     let mut n = 0;
     while x != 1 {
         x >>= 1;
@@ -1471,11 +2408,12 @@ fn highest_bit(mut x: u32) -> i32 {
 /// example at https://en.wikipedia.org/wiki/Bitwise_operation#Circular_shifts
 /// (except turned around, to make it rotate right).
 fn ror(x: u64, n: u32, len: u32) -> u64 {
-    let raw = (x >> n) | (x << (len - n));
-    if len == 64 {
-        return raw;
+    let mask = if len == 64 { u64::MAX } else { (1u64 << len) - 1 };
+    let x = x & mask;
+    if n == 0 {
+        return x; // x << len would overflow
     }
-    return raw & ((1u64 << len) - 1); // truncate left side to len bits
+    return ((x >> n) | (x << (len - n))) & mask;
 }
 
 
@@ -1483,8 +2421,9 @@ fn ror(x: u64, n: u32, len: u32) -> u64 {
 ///
 /// The logical immediate instructions encode 32-bit or 64-bit masks using merely
 /// 12 or 13 bits. We want the decoded mask in our Inst.imm field. We only need
-/// the "wmask" of DecodeBitMasks, so return only that.
-fn decode_bitmask(immN: u8, imms: u8, immr: u8, w32: bool) -> u64 {
+/// the "wmask" of DecodeBitMasks, so return only that; None for the reserved
+/// encodings (no element size, all ones, or N set for 32 bits).
+fn decode_bitmask(immN: u8, imms: u8, immr: u8, w32: bool) -> Option<u64> {
     let M: u32 = if w32 { 32 } else { 64 };
 
     // Guarantee it's only the number of bits in the pseudocode signature.
@@ -1494,32 +2433,32 @@ fn decode_bitmask(immN: u8, imms: u8, immr: u8, w32: bool) -> u64 {
 
     // length of bitmask (1..6)
     let len = highest_bit(((immN << 6) | ((!imms) & 0b111111)) as u32);
+    if len < 1 || (1u32 << len) > M {
+        return None;
+    }
 
     // 1..6 consecutive ones, basis of pattern
-    let mut levels = 0;
-    for i in 0..len {
-        levels = (levels << 1) | 1;
-    }
+    let levels: u8 = (1 << len) - 1;
 
     let S: u32 = (imms & levels) as u32;
     let R: u32 = (immr & levels) as u32;
-    let esize = 1 << len; // 2, 4, 8, 16, 32, 64
+    if S == levels as u32 {
+        return None;
+    }
+    let esize: u32 = 1 << len; // 2, 4, 8, 16, 32, 64
 
     // welem: pattern of 1s then zero-extended to esize
     // e.g. esize = 8; S+1 = 4 → welem = 0b00001111
-    let mut welem = 0;
-    for _ in 0..(S + 1) {
-        welem = (welem << 1) | 1;
-    }
+    let welem: u64 = (1u64 << (S + 1)) - 1;
 
     // wmask = Replicate(ROR(welem, R));
-    welem = ror(welem, R, esize);
-    let mut wmask = 0;
-    for i in (0..M).step_by(esize as usize) {
-        wmask = (wmask << esize) | welem;
+    let welem = ror(welem, R, esize);
+    let mut wmask: u64 = 0;
+    for _ in (0..M).step_by(esize as usize) {
+        wmask = if esize == 64 { welem } else { (wmask << esize) | welem };
     }
 
-    return wmask;
+    return Some(wmask);
 }
 
 fn find_bfm_alias(op: Op, w32: bool, rd: u8, rn: u8, immr: u8, imms: u8) -> Inst {
@@ -1580,10 +2519,11 @@ fn find_bfm_alias(op: Op, w32: bool, rd: u8, rn: u8, immr: u8, imms: u8) -> Inst
                 inst.extend.typ = if sign { SXTH } else { UXTH } as u32;
                 return inst;
             }
-            31 => {
+            // There is no UXTW; UBFX Xd, Xn, #0, #32 zero-extends.
+            31 if sign => {
                 inst.op = A64_EXTEND;
                 inst.extend.typ = SXTW as u32;
-                return if sign { inst } else { panic!("find_bfm_alias: there is no UXTW instruction") };
+                return inst;
             }
             _ => {}
         }
@@ -1593,4 +2533,2425 @@ fn find_bfm_alias(op: Op, w32: bool, rd: u8, rn: u8, immr: u8, imms: u8) -> Inst
     inst.bfm.lsb = immr as u32;
     inst.bfm.width = (imms - immr + 1) as u32;
     inst
-}
\ No newline at end of file
+}
+/// Decodes a single instruction word. Encoding groups that are not supported
+/// (yet) decode to A64_UNKNOWN with the raw word stored in Inst.imm, as do
+/// the SIMD&FP and SVE instructions without the "simd" feature.
+pub fn decode(binst: u32) -> Inst {
+    let op0 = (binst >> 25) & 0b1111;
+
+    let mut inst = match op0 {
+        0b1000 | 0b1001 => data_proc_imm(binst),
+        0b1010 | 0b1011 => branches(binst),
+        0b0100 | 0b0110 | 0b1100 | 0b1110 => loads_and_stores(binst),
+        0b0111 | 0b1111 if cfg!(feature = "simd") => data_proc_simd_fp(binst),
+        0b0010 if cfg!(feature = "simd") => sve(binst),
+        0b0000 if cfg!(feature = "simd") && binst >> 31 == 1 => sme(binst),
+        _ => UNKNOWN_INST,
+    };
+
+    if inst.op == Op::A64_UNKNOWN {
+        return unallocated(binst);
+    }
+
+    inst.raw = binst;
+    inst
+}
+
+/// The A64_UNKNOWN decoding of binst, with Inst.imm := binst.
+pub(crate) fn unallocated(binst: u32) -> Inst {
+    let mut inst = UNKNOWN_INST;
+    inst.imm = binst as u64;
+    inst.raw = binst;
+    inst
+}
+
+/// Data Processing -- Scalar Floating-Point and Advanced SIMD.
+///
+/// Only the table lookups are decoded so far.
+pub fn data_proc_simd_fp(binst: u32) -> Inst {
+    let op0 = (binst >> 28) & 0b1111;
+    let op1 = (binst >> 23) & 0b11;
+    let op2 = (binst >> 19) & 0b1111;
+    let op3 = (binst >> 10) & 0b111111111;
+
+    match (op0 & 0b1011, op1 >> 1, op2 & 0b0100, op3 & 0b000100011) {
+        (0b0000, 0, 0, 0) => table_lookup(binst),
+        _ => UNKNOWN_INST,
+    }
+}
+
+/// TBL, TBX: Inst.rd := Vd, Inst.rn := Vn, the first table register,
+/// Inst.rm := Vm, the indices, Inst.imm := #table registers.
+/// Inst.flags.vec := arrangement of Vd and Vm (8B or 16B); the table
+/// registers are always 16B.
+fn table_lookup(binst: u32) -> Inst {
+    let mut inst = UNKNOWN_INST;
+
+    if (binst >> 22) & 0b11 != 0 {
+        return UNKNOWN_INST;
+    }
+    let q = (binst >> 30) & 1 == 1;
+    inst.op = if (binst >> 12) & 1 == 1 { Op::A64_TBX } else { Op::A64_TBL };
+    inst.imm = (((binst >> 13) & 0b11) + 1) as u64;
+    inst.flags = set_vec_arrangement(inst.flags, VectorArrangement::from_elem(FPSize::FSZ_B, q));
+    inst.rd = regRd(binst);
+    inst.rn = regRn(binst);
+    inst.rm = regRm(binst);
+
+    inst
+}
+
+/// The ZA tile and slice of bits 3:0 (bits 8:5 for MOVA to a vector): the
+/// tile number takes the bits above the slice offset, as many as there are
+/// tiles of the element size.
+fn za_slice(bits: u32, esize: u8, vertical: bool, rv: u8) -> Za {
+    let tile_bits = if esize == FPSize::FSZ_Q { 4 } else { esize as u32 };
+    let offset_bits = 4 - tile_bits;
+    Za { tile: (bits >> offset_bits) as u8, vertical, rv, offset: (bits & ((1 << offset_bits) - 1)) as u8 }
+}
+
+/// SME outer products (bits 31:25 1000000, float, or 1010000, integer),
+/// the non-widening F16 and SME2 forms excepted.
+fn outer_product(binst: u32) -> Inst {
+    use FPSize::*;
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let integer = (binst >> 29) & 1 == 1;
+    let (u0, u1, d, subtract) = ((binst >> 24) & 1, (binst >> 21) & 1, (binst >> 22) & 1 == 1, (binst >> 4) & 1 == 1);
+    // The tile number takes bits 1:0 (S) or 2:0 (D), the rest are zero.
+    if (binst >> 23) & 1 == 0 || binst & if d { 0b1000 } else { 0b1100 } != 0 {
+        return UNKNOWN_INST;
+    }
+    let (ops, elem) = match (integer, u0, u1, d) {
+        (false, 0, 0, _) => ([A64_FMOPA_ZA, A64_FMOPS_ZA], if d { FSZ_D } else { FSZ_S }),
+        (false, 1, 1, false) => ([A64_FMOPA_ZA, A64_FMOPS_ZA], FSZ_H),
+        (false, 1, 0, false) => ([A64_BFMOPA_ZA, A64_BFMOPS_ZA], FSZ_H),
+        (false, ..) => return UNKNOWN_INST,
+        (true, 0, 0, _) => ([A64_SMOPA_ZA, A64_SMOPS_ZA], if d { FSZ_H } else { FSZ_B }),
+        (true, 1, 1, _) => ([A64_UMOPA_ZA, A64_UMOPS_ZA], if d { FSZ_H } else { FSZ_B }),
+        (true, 0, _, _) => ([A64_SUMOPA_ZA, A64_SUMOPS_ZA], if d { FSZ_H } else { FSZ_B }),
+        (true, ..) => ([A64_USMOPA_ZA, A64_USMOPS_ZA], if d { FSZ_H } else { FSZ_B }),
+    };
+    inst.op = ops[subtract as usize];
+    inst.sve.esize = if d { FSZ_D } else { FSZ_S };
+    inst.sve.pg = Some(((binst >> 10) & 0b111) as u8);
+    inst.sve.mode = Some(Predication::Merging);
+    inst.rn = regRn(binst);
+    inst.rm = regRm(binst);
+    inst.mop = Mop { tile: (binst & if d { 0b111 } else { 0b11 }) as u8, pm: ((binst >> 13) & 0b111) as u8, elem };
+    inst
+}
+
+/// SME (bit 31 set, bits 28:25 0000): the loads and stores of ZA, ZERO and
+/// MOVA, and the outer products. SME2's multi-vector forms aren't
+/// decoded yet.
+fn sme(binst: u32) -> Inst {
+    use Op::*;
+    if binst & 0xDE000000 == 0x80000000 {
+        return outer_product(binst);
+    }
+    let mut inst = UNKNOWN_INST;
+    let rv = ((binst >> 13) & 0b11) as u8;
+    let vertical = (binst >> 15) & 1 == 1;
+    let pg = Some(((binst >> 10) & 0b111) as u8);
+    let size = ((binst >> 22) & 0b11) as u8;
+    if binst & 0xFFDF9C10 == 0xE1000000 {
+        inst.op = if (binst >> 21) & 1 == 0 { A64_LDR_ZA } else { A64_STR_ZA };
+        inst.rn = regRnSP(binst);
+        inst.rm = Registries::ZERO_REG;
+        inst.offset = (binst & 0xF) as i64;
+        inst.za = Za { tile: 0, vertical: false, rv, offset: (binst & 0xF) as u8 };
+    } else if binst & 0xFE000010 == 0xE0000000 {
+        // LD1Q and ST1Q have bit 24 set.
+        let q = (binst >> 24) & 1 == 1;
+        if q && size != Size::SZ_X {
+            return UNKNOWN_INST;
+        }
+        let load = (binst >> 21) & 1 == 0;
+        inst.op = if load { A64_LD1_ZA } else { A64_ST1_ZA };
+        inst.sve.esize = if q { FPSize::FSZ_Q } else { size };
+        inst.sve.pg = pg;
+        inst.sve.mode = load.then_some(Predication::Zeroing);
+        inst.rn = regRnSP(binst);
+        inst.rm = regRm(binst);
+        inst.za = za_slice(binst & 0xF, inst.sve.esize, vertical, rv);
+    } else if binst & 0xFFFFFF00 == 0xC0080000 {
+        inst.op = A64_ZERO_ZA;
+        inst.imm = (binst & 0xFF) as u64;
+    } else if binst & 0xFF3C0000 == 0xC0000000 {
+        let (to_vector, q) = ((binst >> 17) & 1 == 1, (binst >> 16) & 1 == 1);
+        if (q && size != Size::SZ_X) || (binst >> if to_vector { 9 } else { 4 }) & 1 == 1 {
+            return UNKNOWN_INST;
+        }
+        inst.sve.esize = if q { FPSize::FSZ_Q } else { size };
+        inst.sve.pg = pg;
+        inst.sve.mode = Some(Predication::Merging);
+        if to_vector {
+            inst.op = A64_MOVA_Z;
+            inst.rd = regRd(binst);
+            inst.za = za_slice((binst >> 5) & 0xF, inst.sve.esize, vertical, rv);
+        } else {
+            inst.op = A64_MOVA_ZA;
+            inst.rn = regRn(binst);
+            inst.za = za_slice(binst & 0xF, inst.sve.esize, vertical, rv);
+        }
+    } else {
+        return UNKNOWN_INST;
+    }
+    inst
+}
+
+/// SVE, with the class in bits 31:29.
+///
+/// Decoded are the data processing (with some of SVE2's), the element
+/// counts, the permutes, the compares, the predicate manipulation, the
+/// contiguous loads and stores, and the gathers and scatters. Not yet: the
+/// unpacks, INSR, REVB/REVH/REVW, RBIT, SPLICE and COMPACT among the
+/// permutes, ADDVL, ADDPL and RDVL, FEXPA, FTSSEL and FTMAD.
+pub fn sve(binst: u32) -> Inst {
+    match binst >> 29 {
+        0b000 | 0b011 => sve_data_proc(binst),
+        0b001 if binst >> 24 == 0x24 || binst & 0xFF204000 == 0x25000000 => sve_compare(binst),
+        0b001 if binst & 0xFF20C000 == 0x2520C000 => sve_int_imm(binst),
+        0b001 => sve_predicate(binst),
+        0b010 if binst >> 24 == 0x44 => sve_int_multiply_add(binst),
+        0b010 => sve2_data_proc(binst),
+        0b100 => sve_gather(binst, FPSize::FSZ_S),
+        0b101 => sve_load(binst),
+        0b110 => sve_gather(binst, FPSize::FSZ_D),
+        0b111 => sve_store(binst),
+        _ => UNKNOWN_INST,
+    }
+}
+
+/// SVE -- the integer and floating-point arithmetic on Z registers.
+///
+/// Inst.rd := Zd, Inst.rn := Zn, Inst.rm := Zm, and Inst.sve := the element
+/// size and the governing predicate. The destructive predicated forms
+/// (ADD Zdn, Pg/M, Zdn, Zm) have Inst.rn = Inst.rd; the multiply-adds
+/// accumulate into Zda = Inst.rd, and MAD and friends multiply Zdn by
+/// Inst.rm and add Inst.ra. Predicated forms merge, except those of
+/// MOVPRFX and CPY that zero.
+pub fn sve_data_proc(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let field = |pos: u32, width: u32| (binst >> pos) & ((1 << width) - 1);
+    let size = field(22, 2) as u8;
+
+    // (opcode, predicated, destructive)
+    let (op, predicated, destructive) = match (binst >> 24, field(21, 1), field(13, 3)) {
+        // Integer binary arithmetic, predicated: bits 20:19 select the
+        // class, 18:16 the operation (with U in bit 16).
+        (0x04, 0, 0b000) => {
+            let op = match (field(19, 2), field(16, 3)) {
+                (0b00, 0b000) => A64_ADD_Z,
+                (0b00, 0b001) => A64_SUB_Z,
+                (0b00, 0b011) => A64_SUBR_Z,
+                (0b01, 0b000 | 0b001) => A64_MAX_Z,
+                (0b01, 0b010 | 0b011) => A64_MIN_Z,
+                (0b01, 0b100 | 0b101) => A64_ABD_Z,
+                (0b10, 0b000) => A64_MUL_Z,
+                (0b10, 0b010 | 0b011) => A64_MULH_Z,
+                (0b10, 0b100 | 0b101) if size >= Size::SZ_W => A64_DIV_Z,
+                (0b10, 0b110 | 0b111) if size >= Size::SZ_W => A64_DIVR_Z,
+                (0b11, 0b000) => A64_ORR_Z,
+                (0b11, 0b001) => A64_EOR_Z,
+                (0b11, 0b010) => A64_AND_Z,
+                (0b11, 0b011) => A64_BIC_Z,
+                _ => return UNKNOWN_INST,
+            };
+            (op, true, true)
+        }
+        (0x04, 0, 0b010 | 0b011) => (if field(13, 1) == 0 { A64_MLA_Z } else { A64_MLS_Z }, true, false),
+        (0x04, 0, 0b110 | 0b111) => (if field(13, 1) == 0 { A64_MAD_Z } else { A64_MSB_Z }, true, true),
+        (0x04, 0, 0b100) => return sve_shift(binst),
+        (0x04, 0, 0b101) => return sve_int_unary(binst),
+        (0x04, 0, 0b001) if field(17, 4) == 0b1000 => return sve_movprfx(binst),
+        (0x04, 0, 0b001) => return sve_int_reduction(binst),
+        (0x04, 1, 0b010) if field(12, 1) == 0 => return sve_index(binst),
+        (0x04, 1, 0b101) if field(12, 1) == 0 => return sve_adr(binst),
+        (0x04, 1, 0b101) if size == 0 && field(16, 5) == 0 && field(10, 3) == 0b111 => return sve_movprfx(binst),
+        (0x04, 1, 0b100) => return sve_shift_unpredicated(binst),
+        (0x04, 1, 0b110 | 0b111) => return sve_element_count(binst),
+        (0x05, 0, _) if field(18, 3) == 0 => return sve_logical_imm(binst),
+        (0x05, 0, _) if field(20, 1) == 1 => return sve_copy_imm(binst),
+        (0x05, 1, _) => return sve_permute(binst),
+        // SVE2 multiplies, unpredicated.
+        (0x04, 1, 0b011) => {
+            let op = match field(10, 3) {
+                0b000 => A64_MUL_Z,
+                0b001 if size == Size::SZ_B => A64_PMUL_Z,
+                0b010 | 0b011 => A64_MULH_Z,
+                0b100 => A64_SQDMULH_Z,
+                0b101 => A64_SQRDMULH_Z,
+                _ => return UNKNOWN_INST,
+            };
+            (op, false, false)
+        }
+        // SVE2 bitwise ternary: bit 10 and bits 23:22 are the operation.
+        (0x04, 1, 0b001) if field(10, 3) == 0b101 => return sve2_xar(binst),
+        (0x04, 1, 0b001) if field(11, 2) == 0b11 => {
+            let op = match (field(10, 1), size) {
+                (0, 0b00) => A64_EOR3_Z,
+                (0, 0b01) => A64_BCAX_Z,
+                (1, 0b00) => A64_BSL_Z,
+                (1, 0b01) => A64_BSL1N_Z,
+                (1, 0b10) => A64_BSL2N_Z,
+                (1, _) => A64_NBSL_Z,
+                _ => return UNKNOWN_INST,
+            };
+            (op, false, true)
+        }
+        (0x04, 1, 0b000) => {
+            let op = match field(10, 3) {
+                0b000 => A64_ADD_Z,
+                0b001 => A64_SUB_Z,
+                0b100 | 0b101 => A64_QADD_Z,
+                0b110 | 0b111 => A64_QSUB_Z,
+                _ => return UNKNOWN_INST,
+            };
+            (op, false, false)
+        }
+        // Bitwise logical, unpredicated: bits 23:22 are the operation, the
+        // elements are always D.
+        (0x04, 1, 0b001) if field(10, 3) == 0b100 => {
+            (match size { 0b00 => A64_AND_Z, 0b01 => A64_ORR_Z, 0b10 => A64_EOR_Z, _ => A64_BIC_Z }, false, false)
+        }
+        // Floating-point: there are no byte elements.
+        (0x65, 0, 0b000) if size != FPSize::FSZ_B => {
+            let op = match field(10, 3) {
+                0b000 => A64_FADD_Z,
+                0b001 => A64_FSUB_Z,
+                0b010 => A64_FMUL_Z,
+                0b011 => A64_FTSMUL_Z,
+                0b110 => A64_FRECPS_Z,
+                0b111 => A64_FRSQRTS_Z,
+                _ => return UNKNOWN_INST,
+            };
+            (op, false, false)
+        }
+        (0x65, 0, 0b100) if size != FPSize::FSZ_B && field(20, 1) == 0 => {
+            let op = match field(16, 4) {
+                0b0000 => A64_FADD_Z,
+                0b0001 => A64_FSUB_Z,
+                0b0010 => A64_FMUL_Z,
+                0b0011 => A64_FSUBR_Z,
+                0b0100 => A64_FMAXNM_Z,
+                0b0101 => A64_FMINNM_Z,
+                0b0110 => A64_FMAX_Z,
+                0b0111 => A64_FMIN_Z,
+                0b1000 => A64_FABD_Z,
+                0b1001 => A64_FSCALE_Z,
+                0b1010 => A64_FMULX_Z,
+                0b1100 => A64_FDIVR_Z,
+                0b1101 => A64_FDIV_Z,
+                _ => return UNKNOWN_INST,
+            };
+            (op, true, true)
+        }
+        (0x65, 0, 0b100) if size != FPSize::FSZ_B && field(19, 2) == 0b11 && field(6, 4) == 0 => {
+            return sve_fp_arith_imm(binst);
+        }
+        (0x65, 1, 0b000..=0b011) if size != FPSize::FSZ_B => {
+            ([A64_FMLA_Z, A64_FMLS_Z, A64_FNMLA_Z, A64_FNMLS_Z][field(13, 2) as usize], true, false)
+        }
+        (0x65, 1, 0b100..=0b111) if size != FPSize::FSZ_B => {
+            ([A64_FMAD_Z, A64_FMSB_Z, A64_FNMAD_Z, A64_FNMSB_Z][field(13, 2) as usize], true, true)
+        }
+        (0x65, 0, 0b101) if size != FPSize::FSZ_B => return sve_fp_unary(binst),
+        (0x65, 0, 0b001) if size != FPSize::FSZ_B => return sve_fp_reduction(binst),
+        (0x65, 0, 0b010 | 0b011 | 0b110 | 0b111) if size != FPSize::FSZ_B => return sve_fp_compare(binst),
+        (0x64, ..) => return sve_fp_complex_indexed(binst),
+        _ => return UNKNOWN_INST,
+    };
+
+    inst.op = op;
+    let signed = match op {
+        A64_MULH_Z if !predicated => field(10, 1) == 0,
+        A64_MAX_Z | A64_MIN_Z | A64_ABD_Z | A64_MULH_Z | A64_DIV_Z | A64_DIVR_Z => field(16, 1) == 0,
+        A64_QADD_Z | A64_QSUB_Z => field(10, 1) == 0,
+        _ => false,
+    };
+    if signed {
+        inst.flags |= FlagMasks::SIMD_SIGNED;
+    }
+    let ternary = matches!(op, A64_EOR3_Z | A64_BCAX_Z | A64_BSL_Z | A64_BSL1N_Z | A64_BSL2N_Z | A64_NBSL_Z);
+    let logical = matches!(op, A64_AND_Z | A64_ORR_Z | A64_EOR_Z | A64_BIC_Z) && !predicated;
+    inst.sve.esize = if logical || ternary { FPSize::FSZ_D } else { size };
+    if predicated {
+        inst.sve.pg = Some(field(10, 3) as u8);
+        inst.sve.mode = Some(Predication::Merging);
+    }
+    inst.rd = regRd(binst);
+    if ternary {
+        inst.rn = regRd(binst);
+        inst.rm = regRm(binst);
+        inst.ra = regRn(binst);
+    } else if matches!(op, A64_MAD_Z | A64_MSB_Z) {
+        inst.rn = regRd(binst);
+        inst.rm = regRm(binst);
+        inst.ra = regRn(binst);
+    } else if matches!(op, A64_FMAD_Z | A64_FMSB_Z | A64_FNMAD_Z | A64_FNMSB_Z) {
+        // Unlike MAD, Zm is in bits 9:5 and Za in bits 20:16.
+        inst.rn = regRd(binst);
+        inst.rm = regRn(binst);
+        inst.ra = regRm(binst);
+    } else if destructive {
+        inst.rn = regRd(binst);
+        inst.rm = regRn(binst);
+    } else {
+        inst.rn = regRn(binst);
+        inst.rm = regRm(binst);
+    }
+
+    inst
+}
+
+/// The governing predicate Pg in bits 12:10, merging.
+fn sve_merging(inst: &mut Inst, binst: u32) {
+    inst.sve.pg = Some(((binst >> 10) & 0b111) as u8);
+    inst.sve.mode = Some(Predication::Merging);
+}
+
+/// SVE -- the predicated shifts: by vector, by the D elements of a vector,
+/// and by an immediate, whose element size is the highest bit of tsz in
+/// bits 23:22 and 9:8.
+fn sve_shift(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let size = ((binst >> 22) & 0b11) as u8;
+    inst.rd = regRd(binst);
+    inst.rn = regRd(binst);
+    sve_merging(&mut inst, binst);
+    if (binst >> 20) & 1 == 0 {
+        let tsz = ((binst >> 20) & 0b1100) | ((binst >> 8) & 0b11);
+        if tsz == 0 {
+            return UNKNOWN_INST;
+        }
+        let esize = highest_bit(tsz) as u8;
+        let bits = 8u64 << esize;
+        let value = ((tsz << 3) | ((binst >> 5) & 0b111)) as u64;
+        inst.op = match (binst >> 16) & 0b1111 {
+            0b0000 => A64_ASR_IMM_Z,
+            0b0001 => A64_LSR_IMM_Z,
+            0b0011 => A64_LSL_IMM_Z,
+            0b0100 => A64_ASRD_Z,
+            _ => return UNKNOWN_INST,
+        };
+        inst.imm = if inst.op == A64_LSL_IMM_Z { value - bits } else { 2 * bits - value };
+        inst.sve.esize = esize;
+        return inst;
+    }
+    let wide = (binst >> 19) & 1 == 1;
+    inst.op = match ((binst >> 16) & 0b111, wide) {
+        (0b000, false) => A64_ASR_Z,
+        (0b001, false) => A64_LSR_Z,
+        (0b011, false) => A64_LSL_Z,
+        (0b100, false) => A64_ASRR_Z,
+        (0b101, false) => A64_LSRR_Z,
+        (0b111, false) => A64_LSLR_Z,
+        (0b000, true) if size != Size::SZ_X => A64_ASR_WIDE_Z,
+        (0b001, true) if size != Size::SZ_X => A64_LSR_WIDE_Z,
+        (0b011, true) if size != Size::SZ_X => A64_LSL_WIDE_Z,
+        _ => return UNKNOWN_INST,
+    };
+    inst.rm = regRn(binst);
+    inst.sve.esize = size;
+    inst
+}
+
+/// SVE -- the predicated integer unary operations (and FABS, FNEG).
+fn sve_int_unary(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let size = ((binst >> 22) & 0b11) as u8;
+    let opc = (binst >> 16) & 0b111;
+    inst.op = match ((binst >> 19) & 0b11, opc) {
+        // The source must be narrower than the elements.
+        (0b10, 0b000..=0b101) if size > (opc >> 1) as u8 => A64_EXTEND_Z,
+        (0b10, 0b110) => A64_ABS_Z,
+        (0b10, 0b111) => A64_NEG_Z,
+        (0b11, 0b000) => A64_CLS_Z,
+        (0b11, 0b001) => A64_CLZ_Z,
+        (0b11, 0b010) => A64_CNT_Z,
+        (0b11, 0b011) => A64_CNOT_Z,
+        (0b11, 0b100) if size != FPSize::FSZ_B => A64_FABS_Z,
+        (0b11, 0b101) if size != FPSize::FSZ_B => A64_FNEG_Z,
+        (0b11, 0b110) => A64_NOT_Z,
+        _ => return UNKNOWN_INST,
+    };
+    if inst.op == A64_EXTEND_Z {
+        // U is bit 16.
+        inst.extend.typ = ((opc & 1) ^ 1) << 2 | (opc >> 1);
+    }
+    inst.rd = regRd(binst);
+    inst.rn = regRn(binst);
+    inst.sve.esize = size;
+    sve_merging(&mut inst, binst);
+    inst
+}
+
+/// SVE -- the integer reductions into Vd: Inst.rd := Vd, Inst.rn := Zn.
+fn sve_int_reduction(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let size = ((binst >> 22) & 0b11) as u8;
+    let (op, signed) = match ((binst >> 19) & 0b11, (binst >> 16) & 0b111) {
+        (0b00, 0b000) if size != Size::SZ_X => (A64_ADDV_Z, true),
+        (0b00, 0b001) => (A64_ADDV_Z, false),
+        (0b01, 0b000) => (A64_MAXV_Z, true),
+        (0b01, 0b001) => (A64_MAXV_Z, false),
+        (0b01, 0b010) => (A64_MINV_Z, true),
+        (0b01, 0b011) => (A64_MINV_Z, false),
+        (0b11, 0b000) => (A64_ORV_Z, false),
+        (0b11, 0b001) => (A64_EORV_Z, false),
+        (0b11, 0b010) => (A64_ANDV_Z, false),
+        _ => return UNKNOWN_INST,
+    };
+    inst.op = op;
+    if signed {
+        inst.flags |= FlagMasks::SIMD_SIGNED;
+    }
+    inst.rd = regRd(binst);
+    inst.rn = regRn(binst);
+    inst.sve.esize = size;
+    inst.sve.pg = Some(((binst >> 10) & 0b111) as u8);
+    inst
+}
+
+/// SVE -- INDEX: the start in bits 9:5 and the step in bits 20:16, each an
+/// immediate or a general purpose register by bits 11:10.
+fn sve_index(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let size = ((binst >> 22) & 0b11) as u8;
+    let (start, step) = ((binst >> 5) & 0b11111, (binst >> 16) & 0b11111);
+    inst.op = [A64_INDEX_IMM_Z, A64_INDEX_REG_IMM_Z, A64_INDEX_IMM_REG_Z, A64_INDEX_REG_Z][((binst >> 10) & 0b11) as usize];
+    match inst.op {
+        A64_INDEX_IMM_Z => {
+            inst.imm = sext(start as u64, 5) as u64;
+            inst.offset = sext(step as u64, 5);
+        }
+        A64_INDEX_REG_IMM_Z => inst.imm = sext(step as u64, 5) as u64,
+        A64_INDEX_IMM_REG_Z => inst.imm = sext(start as u64, 5) as u64,
+        _ => {}
+    }
+    inst.rd = regRd(binst);
+    inst.rn = regRn(binst);
+    inst.rm = regRm(binst);
+    inst.sve.esize = size;
+    if size != Size::SZ_X {
+        inst.flags |= W32;
+    }
+    inst
+}
+
+/// SVE -- ADR: vector addresses, Zn plus Zm extended and shifted by msz.
+fn sve_adr(binst: u32) -> Inst {
+    let mut inst = UNKNOWN_INST;
+    inst.op = Op::A64_ADR_Z;
+    let (esize, extend) = match (binst >> 22) & 0b11 {
+        0b00 => (FPSize::FSZ_D, ExtendType::SXTW),
+        0b01 => (FPSize::FSZ_D, ExtendType::UXTW),
+        0b10 => (FPSize::FSZ_S, ExtendType::UXTX),
+        _ => (FPSize::FSZ_D, ExtendType::UXTX),
+    };
+    inst.rd = regRd(binst);
+    inst.rn = regRn(binst);
+    inst.rm = regRm(binst);
+    inst.sve.esize = esize;
+    inst.extend.typ = extend as u32;
+    inst.extend.lsl = (binst >> 10) & 0b11;
+    inst
+}
+
+/// SVE -- MOVPRFX: unpredicated (bit 21 set), or with Pg/M or Pg/Z by
+/// bit 16.
+fn sve_movprfx(binst: u32) -> Inst {
+    let mut inst = UNKNOWN_INST;
+    inst.op = Op::A64_MOVPRFX_Z;
+    inst.rd = regRd(binst);
+    inst.rn = regRn(binst);
+    if (binst >> 21) & 1 == 0 {
+        inst.sve.esize = ((binst >> 22) & 0b11) as u8;
+        inst.sve.pg = Some(((binst >> 10) & 0b111) as u8);
+        inst.sve.mode = Some(if (binst >> 16) & 1 == 1 { Predication::Merging } else { Predication::Zeroing });
+    }
+    inst
+}
+
+/// SVE -- the unpredicated shifts, by bits 11:10: by an immediate if bit 12
+/// is set, with tsz in bits 23:22 and 20:19 and the rest in 18:16, and by
+/// the D elements of Zm otherwise.
+fn sve_shift_unpredicated(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let size = ((binst >> 22) & 0b11) as u8;
+    let opc = (binst >> 10) & 0b11;
+    inst.rd = regRd(binst);
+    inst.rn = regRn(binst);
+    if (binst >> 12) & 1 == 1 {
+        let tsz = ((binst >> 20) & 0b1100) | ((binst >> 19) & 0b11);
+        if tsz == 0 {
+            return UNKNOWN_INST;
+        }
+        let esize = highest_bit(tsz) as u8;
+        let bits = 8u64 << esize;
+        let value = ((tsz << 3) | ((binst >> 16) & 0b111)) as u64;
+        inst.op = match opc {
+            0b00 => A64_ASR_IMM_Z,
+            0b01 => A64_LSR_IMM_Z,
+            0b11 => A64_LSL_IMM_Z,
+            _ => return UNKNOWN_INST,
+        };
+        inst.imm = if inst.op == A64_LSL_IMM_Z { value - bits } else { 2 * bits - value };
+        inst.sve.esize = esize;
+        return inst;
+    }
+    inst.op = match opc {
+        _ if size == Size::SZ_X => return UNKNOWN_INST,
+        0b00 => A64_ASR_WIDE_Z,
+        0b01 => A64_LSR_WIDE_Z,
+        0b11 => A64_LSL_WIDE_Z,
+        _ => return UNKNOWN_INST,
+    };
+    inst.rm = regRm(binst);
+    inst.sve.esize = size;
+    inst
+}
+
+/// SVE2 -- XAR, with the element size and the rotation encoded as those
+/// of the unpredicated right shifts, tszh:tszl in bits 23:22 and 20:19.
+fn sve2_xar(binst: u32) -> Inst {
+    let mut inst = UNKNOWN_INST;
+    let tsz = ((binst >> 20) & 0b1100) | ((binst >> 19) & 0b11);
+    if tsz == 0 {
+        return UNKNOWN_INST;
+    }
+    inst.op = Op::A64_XAR_Z;
+    inst.sve.esize = highest_bit(tsz) as u8;
+    inst.imm = (16u64 << inst.sve.esize) - (((tsz << 3) | ((binst >> 16) & 0b111)) as u64);
+    inst.rd = regRd(binst);
+    inst.rn = inst.rd;
+    inst.rm = regRn(binst);
+    inst
+}
+
+/// SVE -- the element counts: CNT<T>, INC<T> and DEC<T> of Xdn or of the
+/// elements of Zdn, and the saturating SQINC<T> and friends, whose
+/// 32-bit forms (bit 20 clear) count in Wdn. The pattern is in bits 9:5
+/// and the multiplier minus one in bits 19:16; Inst.rn := Inst.rd.
+fn sve_element_count(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let size = ((binst >> 22) & 0b11) as u8;
+    let sf = (binst >> 20) & 1;
+    // D is bit 10 of INC<T> and DEC<T>, and bit 11 of the saturating ones,
+    // whose U is bit 10.
+    let (bit11, bit10) = ((binst >> 11) & 1 == 1, (binst >> 10) & 1 == 1);
+    inst.op = match (sf, (binst >> 10) & 0b111111) {
+        (0, 0b111000) => A64_CNT_ELEMS,
+        (1, 0b111000 | 0b111001) => if bit10 { A64_DEC_ELEMS } else { A64_INC_ELEMS },
+        (1, 0b110000 | 0b110001) if size != Size::SZ_B => if bit10 { A64_DEC_ELEMS_Z } else { A64_INC_ELEMS_Z },
+        (_, 0b111100..=0b111111) => if bit11 { A64_QDEC_ELEMS } else { A64_QINC_ELEMS },
+        (0, 0b110000..=0b110011) if size != Size::SZ_B => if bit11 { A64_QDEC_ELEMS_Z } else { A64_QINC_ELEMS_Z },
+        _ => return UNKNOWN_INST,
+    };
+    if matches!(inst.op, A64_QINC_ELEMS | A64_QDEC_ELEMS | A64_QINC_ELEMS_Z | A64_QDEC_ELEMS_Z) {
+        if !bit10 {
+            inst.flags |= FlagMasks::SIMD_SIGNED;
+        }
+        if matches!(inst.op, A64_QINC_ELEMS | A64_QDEC_ELEMS) && sf == 0 {
+            inst.flags |= W32;
+        }
+    }
+    inst.rd = regRd(binst);
+    if inst.op != A64_CNT_ELEMS {
+        inst.rn = inst.rd;
+    }
+    inst.imm = ((binst >> 5) & 0b11111) as u64;
+    inst.offset = ((binst >> 16) & 0b1111) as i64 + 1;
+    inst.sve.esize = size;
+    inst
+}
+
+/// SVE -- ORR, EOR, AND and DUPM with a bitmask immediate in bits 17:5.
+/// The element size is that of the pattern, at least a byte.
+fn sve_logical_imm(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let (n, immr, imms) = (((binst >> 17) & 1) as u8, ((binst >> 11) & 0b111111) as u8, ((binst >> 5) & 0b111111) as u8);
+    let Some(mask) = decode_bitmask(n, imms, immr, false) else {
+        return UNKNOWN_INST;
+    };
+    let len = highest_bit(((n as u32) << 6) | (!imms as u32 & 0b111111));
+    inst.sve.esize = len.max(3) as u8 - 3;
+    let bits = 8 * FPSize::bytes(inst.sve.esize);
+    inst.imm = if bits == 64 { mask } else { mask & ((1u64 << bits) - 1) };
+    inst.op = [A64_ORR_IMM_Z, A64_EOR_IMM_Z, A64_AND_IMM_Z, A64_DUPM_Z][((binst >> 22) & 0b11) as usize];
+    inst.rd = regRd(binst);
+    inst.rn = regRd(binst);
+    inst
+}
+
+/// The value of the 8-bit floating-point immediate abcdefgh (VFPExpandImm):
+/// (-1)^a * (16 + efgh) / 16 * 2^e, e being cd + 1 if b is clear and
+/// cd - 3 if set.
+fn vfp_expand_imm(imm8: u32) -> f64 {
+    let cd = ((imm8 >> 4) & 0b11) as i32;
+    let exp = if (imm8 >> 6) & 1 == 1 { cd - 3 } else { cd + 1 };
+    let value = (16 + (imm8 & 0b1111)) as f64 / 16.0 * 2f64.powi(exp);
+    if imm8 >> 7 == 1 { -value } else { value }
+}
+
+/// SVE -- the unpredicated integer operations with an immediate (bits 31:24
+/// 0x25, bits 21 and 15:14 set), by bits 20:16: ADD, SUB, SUBR and the
+/// saturating ones of an unsigned imm8, shifted left by 8 if bit 13 is
+/// set; MAX and MIN of a signed (SMAX, SMIN) or unsigned imm8; MUL; and DUP
+/// of a signed, possibly shifted one, and FDUP.
+fn sve_int_imm(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let size = ((binst >> 22) & 0b11) as u8;
+    let (opc, sh) = ((binst >> 16) & 0b111, (binst >> 13) & 1);
+    let imm8 = (binst >> 5) & 0xFF;
+    if sh == 1 && size == Size::SZ_B {
+        return UNKNOWN_INST;
+    }
+    // U is bit 16.
+    let signed = opc & 1 == 0;
+    let (op, signed_imm) = match ((binst >> 19) & 0b11, opc) {
+        (0b00, 0b000) => (A64_ADD_IMM_Z, false),
+        (0b00, 0b001) => (A64_SUB_IMM_Z, false),
+        (0b00, 0b011) => (A64_SUBR_IMM_Z, false),
+        (0b00, 0b100 | 0b101) => (A64_QADD_IMM_Z, false),
+        (0b00, 0b110 | 0b111) => (A64_QSUB_IMM_Z, false),
+        (0b01, 0b000 | 0b001) if sh == 0 => (A64_MAX_IMM_Z, signed),
+        (0b01, 0b010 | 0b011) if sh == 0 => (A64_MIN_IMM_Z, signed),
+        (0b10, 0b000) if sh == 0 => (A64_MUL_IMM_Z, true),
+        (0b11, 0b000) => (A64_DUP_IMM_Z, true),
+        (0b11, 0b001) if sh == 0 && size != FPSize::FSZ_B => (A64_FDUP_Z, false),
+        _ => return UNKNOWN_INST,
+    };
+    inst.op = op;
+    if signed && matches!(op, A64_QADD_IMM_Z | A64_QSUB_IMM_Z | A64_MAX_IMM_Z | A64_MIN_IMM_Z) {
+        inst.flags |= FlagMasks::SIMD_SIGNED;
+    }
+    let value = if signed_imm { sext(imm8 as u64, 8) } else { imm8 as i64 };
+    if op == A64_FDUP_Z {
+        inst.fimm = vfp_expand_imm(imm8);
+    } else {
+        inst.imm = (value << (8 * sh)) as u64;
+    }
+    inst.rd = regRd(binst);
+    if !matches!(op, A64_DUP_IMM_Z | A64_FDUP_Z) {
+        inst.rn = regRd(binst);
+    }
+    inst.sve.esize = size;
+    inst
+}
+
+/// SVE -- CPY (immediate) and FCPY, under a 4-bit Pg in bits 19:16. CPY
+/// zeroes the inactive elements unless M (bit 14) is set, and shifts its
+/// signed imm8 left by 8 if bit 13 is.
+fn sve_copy_imm(binst: u32) -> Inst {
+    let mut inst = UNKNOWN_INST;
+    let size = ((binst >> 22) & 0b11) as u8;
+    let imm8 = (binst >> 5) & 0xFF;
+    let merging = match (binst >> 13) & 0b111 {
+        0b110 if size != FPSize::FSZ_B => {
+            inst.op = Op::A64_FCPY_Z;
+            inst.fimm = vfp_expand_imm(imm8);
+            true
+        }
+        0b000..=0b011 => {
+            let sh = (binst >> 13) & 1;
+            if sh == 1 && size == Size::SZ_B {
+                return UNKNOWN_INST;
+            }
+            inst.op = Op::A64_CPY_IMM_Z;
+            inst.imm = (sext(imm8 as u64, 8) << (8 * sh)) as u64;
+            (binst >> 14) & 1 == 1
+        }
+        _ => return UNKNOWN_INST,
+    };
+    inst.rd = regRd(binst);
+    inst.sve.esize = size;
+    inst.sve.pg = Some(((binst >> 16) & 0b1111) as u8);
+    inst.sve.mode = Some(if merging { Predication::Merging } else { Predication::Zeroing });
+    inst
+}
+
+/// SVE -- the permutes (bits 31:24 0x05, bit 21 set), by bits 15:13: EXT;
+/// DUP (indexed and scalar), TBL and REV; ZIP, UZP and TRN; the moves of
+/// an element between Z and the scalar registers (CPY, LASTA and LASTB,
+/// CLASTA and CLASTB); and SEL, whose Pg is in bits 13:10. DUP (indexed)
+/// takes the element size from the lowest set bit of tsz (bits 20:16) and
+/// the index from the bits above it and imm2 (bits 23:22).
+fn sve_permute(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let size = ((binst >> 22) & 0b11) as u8;
+    let opc = (binst >> 16) & 0b11111;
+    // LASTB and CLASTB have bit 16 set.
+    let b = opc & 1 == 1;
+    let w32 = size != Size::SZ_X;
+    inst.rd = regRd(binst);
+    inst.rn = regRn(binst);
+    inst.rm = regRm(binst);
+    inst.sve.esize = size;
+    let pg = Some(((binst >> 10) & 0b111) as u8);
+    match (binst >> 13) & 0b111 {
+        0b000 if size == 0 => {
+            inst.op = A64_EXT_Z;
+            inst.rn = regRd(binst);
+            inst.rm = regRn(binst);
+            inst.imm = ((opc << 3) | ((binst >> 10) & 0b111)) as u64;
+        }
+        0b001 => match ((binst >> 10) & 0b111, opc) {
+            (0b000, 1..) => {
+                let low = opc.trailing_zeros();
+                inst.op = A64_DUP_ELEM_Z;
+                inst.sve.esize = if low == 4 { FPSize::FSZ_Q } else { low as u8 };
+                inst.offset = ((size as u32) << 5 | opc) as i64 >> (low + 1);
+            }
+            (0b100, _) => inst.op = A64_TBL_Z,
+            (0b110, 0b00000) => {
+                inst.op = A64_DUP_REG_Z;
+                inst.rn = regRnSP(binst);
+                if w32 {
+                    inst.flags |= W32;
+                }
+            }
+            (0b110, 0b11000) => inst.op = A64_REV_Z,
+            _ => return UNKNOWN_INST,
+        },
+        0b010 if binst & 0x210 == 0 => {
+            inst.op = match (opc >> 4, (binst >> 10) & 0b111) {
+                (0, 0b000) => A64_ZIP1_P,
+                (0, 0b001) => A64_ZIP2_P,
+                (0, 0b010) => A64_UZP1_P,
+                (0, 0b011) => A64_UZP2_P,
+                (0, 0b100) => A64_TRN1_P,
+                (0, 0b101) => A64_TRN2_P,
+                (1, 0b000) if opc == 0b10100 => A64_REV_P,
+                (1, 0b000) if opc == 0b10000 && size == 0 => A64_PUNPKLO,
+                (1, 0b000) if opc == 0b10001 && size == 0 => A64_PUNPKHI,
+                _ => return UNKNOWN_INST,
+            };
+        }
+        0b011 => {
+            inst.op = match (binst >> 10) & 0b111 {
+                0b000 => A64_ZIP1_Z,
+                0b001 => A64_ZIP2_Z,
+                0b010 => A64_UZP1_Z,
+                0b011 => A64_UZP2_Z,
+                0b100 => A64_TRN1_Z,
+                0b101 => A64_TRN2_Z,
+                _ => return UNKNOWN_INST,
+            };
+        }
+        0b100 => {
+            inst.op = match opc {
+                0b00000 => A64_CPY_FP_Z,
+                0b00010 | 0b00011 => if b { A64_LASTB_FP_Z } else { A64_LASTA_FP_Z },
+                0b01000 | 0b01001 => if b { A64_CLASTB_Z } else { A64_CLASTA_Z },
+                0b01010 | 0b01011 => if b { A64_CLASTB_FP_Z } else { A64_CLASTA_FP_Z },
+                _ => return UNKNOWN_INST,
+            };
+            inst.sve.pg = pg;
+        }
+        0b101 => {
+            inst.op = match opc {
+                0b00000 | 0b00001 => if b { A64_LASTB_REG_Z } else { A64_LASTA_REG_Z },
+                0b01000 => A64_CPY_REG_Z,
+                0b10000 | 0b10001 => if b { A64_CLASTB_REG_Z } else { A64_CLASTA_REG_Z },
+                _ => return UNKNOWN_INST,
+            };
+            if inst.op == A64_CPY_REG_Z {
+                inst.rn = regRnSP(binst);
+            }
+            if w32 {
+                inst.flags |= W32;
+            }
+            inst.sve.pg = pg;
+        }
+        0b110 | 0b111 => {
+            inst.op = A64_SEL_Z;
+            inst.sve.pg = Some(((binst >> 10) & 0b1111) as u8);
+        }
+        _ => return UNKNOWN_INST,
+    }
+    if matches!(inst.op, A64_CPY_FP_Z | A64_CPY_REG_Z) {
+        inst.sve.mode = Some(Predication::Merging);
+    }
+    // The conditional ones keep Zdn, Rdn or Vdn if no element is active.
+    if matches!(inst.op, A64_CLASTA_Z | A64_CLASTB_Z | A64_CLASTA_REG_Z | A64_CLASTB_REG_Z | A64_CLASTA_FP_Z | A64_CLASTB_FP_Z) {
+        inst.rn = regRd(binst);
+        inst.rm = regRn(binst);
+    }
+    inst
+}
+
+/// SVE -- the predicated floating-point unary operations: FRINT<r>,
+/// FRECPX, FSQRT and the conversions, whose source and destination sizes
+/// are set by bits 23:22 and 18:16.
+fn sve_fp_unary(binst: u32) -> Inst {
+    use FPSize::*;
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let size = ((binst >> 22) & 0b11) as u8;
+    let opc = (binst >> 16) & 0b11111;
+    inst.sve.esize = size;
+    inst.sve.src = size;
+    match opc {
+        0b00000..=0b00111 if opc != 0b00101 => {
+            use FPRounding::*;
+            inst.op = if opc == 0b00110 { A64_FRINTX_Z } else { A64_FRINT_Z };
+            let mode = [FPR_TIE_EVEN, FPR_POS_INF, FPR_NEG_INF, FPR_ZERO, FPR_TIE_AWAY, FPR_CURRENT, FPR_CURRENT, FPR_CURRENT];
+            inst.frint.mode = mode[opc as usize].clone() as u32;
+        }
+        0b01100 => inst.op = A64_FRECPX_Z,
+        0b01101 => inst.op = A64_FSQRT_Z,
+        _ => {
+            // (destination, source)
+            let sizes = match (opc >> 3, size, (opc >> 1) & 0b11, opc & 1) {
+                (0b01, 0b10, 0b00, 0) => (FSZ_H, FSZ_S),
+                (0b01, 0b10, 0b00, 1) => (FSZ_S, FSZ_H),
+                (0b01, 0b11, 0b00, 0) => (FSZ_H, FSZ_D),
+                (0b01, 0b11, 0b00, 1) => (FSZ_D, FSZ_H),
+                (0b01, 0b11, 0b01, 0) => (FSZ_S, FSZ_D),
+                (0b01, 0b11, 0b01, 1) => (FSZ_D, FSZ_S),
+                (0b10, 0b01, 0b01, _) => (FSZ_H, FSZ_H),
+                (0b10, 0b01, 0b10, _) => (FSZ_H, FSZ_S),
+                (0b10, 0b01, 0b11, _) => (FSZ_H, FSZ_D),
+                (0b10, 0b10, 0b10, _) => (FSZ_S, FSZ_S),
+                (0b10, 0b11, 0b00, _) => (FSZ_D, FSZ_S),
+                (0b10, 0b11, 0b10, _) => (FSZ_S, FSZ_D),
+                (0b10, 0b11, 0b11, _) => (FSZ_D, FSZ_D),
+                (0b11, 0b01, 0b01, _) => (FSZ_H, FSZ_H),
+                (0b11, 0b01, 0b10, _) => (FSZ_S, FSZ_H),
+                (0b11, 0b01, 0b11, _) => (FSZ_D, FSZ_H),
+                (0b11, 0b10, 0b10, _) => (FSZ_S, FSZ_S),
+                (0b11, 0b11, 0b00, _) => (FSZ_S, FSZ_D),
+                (0b11, 0b11, 0b10, _) => (FSZ_D, FSZ_S),
+                (0b11, 0b11, 0b11, _) => (FSZ_D, FSZ_D),
+                _ => return UNKNOWN_INST,
+            };
+            inst.op = [A64_FCVT_Z, A64_FCVT_Z, A64_CVTF_Z, A64_FCVTZ_Z][(opc >> 3) as usize];
+            // U is bit 16.
+            if inst.op != A64_FCVT_Z && opc & 1 == 0 {
+                inst.flags |= FlagMasks::SIMD_SIGNED;
+            }
+            (inst.sve.esize, inst.sve.src) = sizes;
+        }
+    }
+    inst.rd = regRd(binst);
+    inst.rn = regRn(binst);
+    sve_merging(&mut inst, binst);
+    inst
+}
+
+/// SVE -- the floating-point reductions into Vd (Inst.rn := Zn, or Zm of
+/// FADDA) and the compares with zero.
+fn sve_fp_reduction(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let opc = (binst >> 16) & 0b11111;
+    inst.op = match opc {
+        0b00000 => A64_FADDV_Z,
+        0b00100 => A64_FMAXNMV_Z,
+        0b00101 => A64_FMINNMV_Z,
+        0b00110 => A64_FMAXV_Z,
+        0b00111 => A64_FMINV_Z,
+        0b11000 => A64_FADDA_Z,
+        0b10000..=0b10011 => return sve_fp_compare(binst),
+        _ => return UNKNOWN_INST,
+    };
+    inst.rd = regRd(binst);
+    if inst.op == A64_FADDA_Z {
+        inst.rn = regRd(binst);
+        inst.rm = regRn(binst);
+    } else {
+        inst.rn = regRn(binst);
+    }
+    inst.sve.esize = ((binst >> 22) & 0b11) as u8;
+    inst.sve.pg = Some(((binst >> 10) & 0b111) as u8);
+    inst
+}
+
+/// SVE -- FADD, FSUB, FMUL, FSUBR and the maximums and minimums, predicated,
+/// of Zdn and the one of two immediates bit 5 selects.
+fn sve_fp_arith_imm(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let (op, values) = match (binst >> 16) & 0b111 {
+        0b000 => (A64_FADD_IMM_Z, [0.5, 1.0]),
+        0b001 => (A64_FSUB_IMM_Z, [0.5, 1.0]),
+        0b010 => (A64_FMUL_IMM_Z, [0.5, 2.0]),
+        0b011 => (A64_FSUBR_IMM_Z, [0.5, 1.0]),
+        0b100 => (A64_FMAXNM_IMM_Z, [0.0, 1.0]),
+        0b101 => (A64_FMINNM_IMM_Z, [0.0, 1.0]),
+        0b110 => (A64_FMAX_IMM_Z, [0.0, 1.0]),
+        _ => (A64_FMIN_IMM_Z, [0.0, 1.0]),
+    };
+    inst.op = op;
+    inst.fimm = values[((binst >> 5) & 1) as usize];
+    inst.rd = regRd(binst);
+    inst.rn = regRd(binst);
+    inst.sve.esize = ((binst >> 22) & 0b11) as u8;
+    sve_merging(&mut inst, binst);
+    inst
+}
+
+/// The index and Zm of the multiplies by element, by bits 23:22: with H
+/// elements (0x) bits 22 and 20:19 index and Zm is Z0...Z7, with 10 bits
+/// 20:19 and Z0...Z7, and with 11 bit 20 and Z0...Z15.
+fn sve_elem_index(binst: u32) -> (i64, u8) {
+    let (index, zm) = match (binst >> 22) & 0b11 {
+        0b00 | 0b01 => (((binst >> 20) & 0b100) | ((binst >> 19) & 0b11), (binst >> 16) & 0b111),
+        0b10 => ((binst >> 19) & 0b11, (binst >> 16) & 0b111),
+        _ => ((binst >> 20) & 1, (binst >> 16) & 0b1111),
+    };
+    (index as i64, zm as u8)
+}
+
+/// SVE -- the floating-point multiplies by element and the complex
+/// arithmetic (bits 31:24 0x64): FMLA, FMLS and FMUL (indexed), FCMLA by
+/// vectors (predicated) and indexed, whose indexed form has H or S
+/// elements by bits 23:22 = 10 or 11, and FCADD.
+fn sve_fp_complex_indexed(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let size = ((binst >> 22) & 0b11) as u8;
+    inst.rd = regRd(binst);
+    inst.rn = regRn(binst);
+    inst.rm = regRm(binst);
+    inst.sve.esize = size;
+    match ((binst >> 21) & 1, (binst >> 13) & 0b111) {
+        // There are no byte elements, but bit 22 indexes H ones.
+        (0, _) if size == FPSize::FSZ_B => return UNKNOWN_INST,
+        (0, 0b000..=0b011) => {
+            inst.op = A64_FCMLA_Z;
+            inst.imm = 90 * ((binst >> 13) & 0b11) as u64;
+            sve_merging(&mut inst, binst);
+        }
+        (0, 0b100) if (binst >> 17) & 0b1111 == 0 => {
+            inst.op = A64_FCADD_Z;
+            inst.imm = if (binst >> 16) & 1 == 0 { 90 } else { 270 };
+            inst.rn = regRd(binst);
+            inst.rm = regRn(binst);
+            sve_merging(&mut inst, binst);
+        }
+        (1, 0b000 | 0b001) => {
+            (inst.offset, inst.rm) = sve_elem_index(binst);
+            inst.sve.esize = size.max(FPSize::FSZ_H);
+            match (binst >> 10) & 0b111111 {
+                0b000000 => inst.op = A64_FMLA_ELEM_Z,
+                0b000001 => inst.op = A64_FMLS_ELEM_Z,
+                0b001000 => inst.op = A64_FMUL_ELEM_Z,
+                0b000100..=0b000111 if size >= FPSize::FSZ_S => {
+                    inst.op = A64_FCMLA_ELEM_Z;
+                    inst.imm = 90 * ((binst >> 10) & 0b11) as u64;
+                    inst.sve.esize = size - 1;
+                }
+                _ => return UNKNOWN_INST,
+            }
+        }
+        _ => return UNKNOWN_INST,
+    }
+    inst
+}
+
+/// The governing predicate Pg in bits 12:10, zeroing, the condition and
+/// the registers of a compare into Pd.
+fn sve_compare_into(inst: &mut Inst, binst: u32, cond: u8) {
+    inst.flags = set_cond(inst.flags, cond);
+    inst.rd = (binst & 0b1111) as u8;
+    inst.rn = regRn(binst);
+    inst.rm = regRm(binst);
+    inst.sve.esize = ((binst >> 22) & 0b11) as u8;
+    inst.sve.pg = Some(((binst >> 10) & 0b111) as u8);
+    inst.sve.mode = Some(Predication::Zeroing);
+}
+
+/// SVE -- the floating-point compares of vectors, and with zero.
+fn sve_fp_compare(binst: u32) -> Inst {
+    use Cond::*;
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let ne = (binst >> 4) & 1;
+    let (op, cond) = if (binst >> 13) & 0b111 == 0b001 {
+        let cond = match ((binst >> 16) & 0b11, ne) {
+            (0b00, 0) => COND_GE,
+            (0b00, 1) => COND_GT,
+            (0b01, 0) => COND_LT,
+            (0b01, 1) => COND_LE,
+            (0b10, 0) => COND_EQ,
+            (0b11, 0) => COND_NE,
+            _ => return UNKNOWN_INST,
+        };
+        (A64_FCM_ZERO_Z, cond)
+    } else {
+        match ((binst >> 15) & 1, (binst >> 13) & 1, ne) {
+            (0, 0, 0) => (A64_FCM_Z, COND_GE),
+            (0, 0, 1) => (A64_FCM_Z, COND_GT),
+            (0, 1, 0) => (A64_FCM_Z, COND_EQ),
+            (0, 1, 1) => (A64_FCM_Z, COND_NE),
+            (1, 0, 0) => (A64_FCM_Z, COND_VS),
+            (1, 0, 1) => (A64_FAC_Z, COND_GE),
+            (1, 1, 1) => (A64_FAC_Z, COND_GT),
+            _ => return UNKNOWN_INST,
+        }
+    };
+    inst.op = op;
+    sve_compare_into(&mut inst, binst, cond);
+    inst
+}
+
+/// SVE -- the integer compares (bits 31:24 0x24, and 0x25 with bits 21 and
+/// 14 clear): of vectors, with the D elements of Zm, and with a signed
+/// (imm5) or unsigned (imm7) immediate.
+fn sve_compare(binst: u32) -> Inst {
+    use Cond::*;
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let size = ((binst >> 22) & 0b11) as u8;
+    let ne = (binst >> 4) & 1 == 1;
+    let pick = |a, b| if ne { b } else { a };
+    let (op, cond) = if binst >> 24 == 0x25 {
+        inst.imm = sext(((binst >> 16) & 0b11111) as u64, 5) as u64;
+        let cond = match ((binst >> 15) & 1, (binst >> 13) & 1) {
+            (0, 0) => pick(COND_GE, COND_GT),
+            (0, 1) => pick(COND_LT, COND_LE),
+            (1, 0) => pick(COND_EQ, COND_NE),
+            _ => return UNKNOWN_INST,
+        };
+        (A64_CMP_IMM_Z, cond)
+    } else if (binst >> 21) & 1 == 1 {
+        inst.imm = ((binst >> 14) & 0b1111111) as u64;
+        (A64_CMP_IMM_Z, if (binst >> 13) & 1 == 0 { pick(COND_HS, COND_HI) } else { pick(COND_LO, COND_LS) })
+    } else {
+        match (binst >> 13) & 0b111 {
+            0b000 => (A64_CMP_Z, pick(COND_HS, COND_HI)),
+            0b100 => (A64_CMP_Z, pick(COND_GE, COND_GT)),
+            0b101 => (A64_CMP_Z, pick(COND_EQ, COND_NE)),
+            _ if size == Size::SZ_X => return UNKNOWN_INST,
+            0b001 => (A64_CMP_WIDE_Z, pick(COND_EQ, COND_NE)),
+            0b010 => (A64_CMP_WIDE_Z, pick(COND_GE, COND_GT)),
+            0b011 => (A64_CMP_WIDE_Z, pick(COND_LT, COND_LE)),
+            0b110 => (A64_CMP_WIDE_Z, pick(COND_HS, COND_HI)),
+            _ => (A64_CMP_WIDE_Z, pick(COND_LO, COND_LS)),
+        }
+    };
+    inst.op = op;
+    // Unlike the floating-point ones, the integer compares set the flags.
+    inst.flags = SET_FLAGS;
+    sve_compare_into(&mut inst, binst, cond);
+    inst
+}
+
+/// SVE -- the integer dot products (bits 31:24 0x44) of the elements of Zn
+/// and Zm a quarter as wide as Zda's, by vectors and indexed, and SVE2's
+/// complex CMLA, whose indexed form has H or S elements by bits 23:22 =
+/// 10 or 11.
+fn sve_int_multiply_add(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let size = ((binst >> 22) & 0b11) as u8;
+    inst.rd = regRd(binst);
+    inst.rn = regRn(binst);
+    inst.rm = regRm(binst);
+    inst.sve.esize = size;
+    match ((binst >> 21) & 1, (binst >> 10) & 0b111111) {
+        (_, 0b000000 | 0b000001) if size >= Size::SZ_W => {
+            inst.op = if (binst >> 21) & 1 == 0 { A64_DOT_Z } else { A64_DOT_ELEM_Z };
+            if inst.op == A64_DOT_ELEM_Z {
+                (inst.offset, inst.rm) = sve_elem_index(binst);
+            }
+            // U is bit 10.
+            if (binst >> 10) & 1 == 0 {
+                inst.flags |= FlagMasks::SIMD_SIGNED;
+            }
+            inst.sve.src = size - 2;
+        }
+        (0, 0b001000..=0b001011) => {
+            inst.op = A64_CMLA_Z;
+            inst.imm = 90 * ((binst >> 10) & 0b11) as u64;
+        }
+        (1, 0b011000..=0b011011) if size >= Size::SZ_W => {
+            inst.op = A64_CMLA_ELEM_Z;
+            inst.imm = 90 * ((binst >> 10) & 0b11) as u64;
+            (inst.offset, inst.rm) = sve_elem_index(binst);
+            inst.sve.esize = size - 1;
+        }
+        _ => return UNKNOWN_INST,
+    }
+    inst
+}
+
+/// SVE2 -- the widening and narrowing integer ops, bit permute,
+/// histograms, MATCH/NMATCH and crypto (bits 31:24 0x45). The destructive
+/// crypto forms have Inst.rn := Zdn. Not yet: the interleaved SADDLBT and
+/// SSUBLBT, EORBT, the long absolute difference and accumulates, ADCLB,
+/// the shift and accumulates, SRI, SLI, CADD and SQCADD.
+fn sve2_data_proc(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let size = ((binst >> 22) & 0b11) as u8;
+    let pg = Some(((binst >> 10) & 0b111) as u8);
+    // The bottom and top forms of the widening and narrowing ops.
+    let bt = |b: Op, t: Op| if (binst >> 10) & 1 == 0 { b } else { t };
+    // U of the long ops, R of the narrowing ones.
+    let bit11 = (binst >> 11) & 1 == 1;
+    // tszh:tszl of the shifts and extracts, the size of the narrower.
+    let tsz = ((binst >> 20) & 0b100) | ((binst >> 19) & 0b11);
+    let shift = ((tsz << 3) | ((binst >> 16) & 0b111)) as u64;
+    inst.sve.esize = size;
+    inst.rd = regRd(binst);
+    inst.rn = regRn(binst);
+    inst.rm = regRm(binst);
+    if binst & 0xFF208000 == 0x45000000 {
+        // Long and wide: bits 15:12 are the operation, U bit 11 (PMULL
+        // against SQDMULL) and T bit 10.
+        inst.op = match (binst >> 12) & 0b111 {
+            0b000 => bt(A64_ADDLB_Z, A64_ADDLT_Z),
+            0b001 => bt(A64_SUBLB_Z, A64_SUBLT_Z),
+            0b011 => bt(A64_ABDLB_Z, A64_ABDLT_Z),
+            0b100 => bt(A64_ADDWB_Z, A64_ADDWT_Z),
+            0b101 => bt(A64_SUBWB_Z, A64_SUBWT_Z),
+            0b110 if bit11 => bt(A64_PMULLB_Z, A64_PMULLT_Z),
+            0b110 => bt(A64_SQDMULLB_Z, A64_SQDMULLT_Z),
+            0b111 => bt(A64_MULLB_Z, A64_MULLT_Z),
+            _ => return UNKNOWN_INST,
+        };
+        match size {
+            Size::SZ_B if matches!(inst.op, A64_PMULLB_Z | A64_PMULLT_Z) => {
+                inst.sve.esize = FPSize::FSZ_Q;
+                inst.sve.src = FPSize::FSZ_D;
+            }
+            Size::SZ_B => return UNKNOWN_INST,
+            Size::SZ_W if matches!(inst.op, A64_PMULLB_Z | A64_PMULLT_Z) => return UNKNOWN_INST,
+            _ => inst.sve.src = size - 1,
+        }
+        if !bit11 && !matches!(inst.op, A64_SQDMULLB_Z | A64_SQDMULLT_Z) {
+            inst.flags |= FlagMasks::SIMD_SIGNED;
+        }
+    } else if binst & 0xFFA0F000 == 0x4500A000 && tsz != 0 {
+        inst.op = bt(A64_SHLLB_Z, A64_SHLLT_Z);
+        inst.sve.src = highest_bit(tsz) as u8;
+        inst.sve.esize = inst.sve.src + 1;
+        inst.imm = shift - (8 << inst.sve.src);
+        if !bit11 {
+            inst.flags |= FlagMasks::SIMD_SIGNED;
+        }
+    } else if binst & 0xFFA0C000 == 0x45200000 && tsz != 0 {
+        // op bit 13, U bit 12, R bit 11 and T bit 10.
+        inst.op = match ((binst >> 13) & 1, (binst >> 12) & 1) {
+            (0, 0) => bt(A64_SQSHRUNB_Z, A64_SQSHRUNT_Z),
+            (0, _) => bt(A64_SHRNB_Z, A64_SHRNT_Z),
+            (_, _) => bt(A64_QSHRNB_Z, A64_QSHRNT_Z),
+        };
+        inst.sve.esize = highest_bit(tsz) as u8;
+        inst.sve.src = inst.sve.esize + 1;
+        inst.imm = (16 << inst.sve.esize) - shift;
+        if binst & 0x3000 == 0x2000 {
+            inst.flags |= FlagMasks::SIMD_SIGNED;
+        }
+        if bit11 {
+            inst.flags |= FlagMasks::SIMD_ROUND;
+        }
+    } else if binst & 0xFFA7E000 == 0x45204000 && tsz.is_power_of_two() {
+        inst.op = match (binst >> 11) & 0b11 {
+            0b00 | 0b01 => bt(A64_QXTNB_Z, A64_QXTNT_Z),
+            0b10 => bt(A64_SQXTUNB_Z, A64_SQXTUNT_Z),
+            _ => return UNKNOWN_INST,
+        };
+        if binst & 0x1800 == 0 {
+            inst.flags |= FlagMasks::SIMD_SIGNED;
+        }
+        inst.sve.esize = highest_bit(tsz) as u8;
+        inst.sve.src = inst.sve.esize + 1;
+    } else if binst & 0xFF20E000 == 0x45206000 && size != Size::SZ_B {
+        // S bit 12, R bit 11 and T bit 10.
+        inst.op = if (binst >> 12) & 1 == 0 { bt(A64_ADDHNB_Z, A64_ADDHNT_Z) } else { bt(A64_SUBHNB_Z, A64_SUBHNT_Z) };
+        inst.sve.esize = size - 1;
+        inst.sve.src = size;
+        if bit11 {
+            inst.flags |= FlagMasks::SIMD_ROUND;
+        }
+    } else if binst & 0xFF20F000 == 0x4500B000 {
+        inst.op = match (binst >> 10) & 0b11 {
+            0b00 => A64_BEXT_Z,
+            0b01 => A64_BDEP_Z,
+            0b10 => A64_BGRP_Z,
+            _ => return UNKNOWN_INST,
+        };
+    } else if binst & 0xFF20FC00 == 0x4520A000 && size == Size::SZ_B {
+        inst.op = A64_HISTSEG_Z;
+    } else if binst & 0xFF20E000 == 0x4520C000 && size >= Size::SZ_W {
+        inst.op = A64_HISTCNT_Z;
+        inst.sve.pg = pg;
+        inst.sve.mode = Some(Predication::Zeroing);
+    } else if binst & 0xFF20E000 == 0x45208000 && size <= Size::SZ_H {
+        inst.op = if (binst >> 4) & 1 == 0 { A64_MATCH_Z } else { A64_NMATCH_Z };
+        inst.rd = (binst & 0b1111) as u8;
+        inst.sve.pg = pg;
+        inst.sve.mode = Some(Predication::Zeroing);
+        inst.flags |= SET_FLAGS;
+    } else if binst & 0xFFFFF800 == 0x4522E000 {
+        inst.op = if (binst >> 10) & 1 == 0 { A64_AESE_Z } else { A64_AESD_Z };
+        inst.rn = inst.rd;
+        inst.rm = regRn(binst);
+    } else if binst & 0xFFFFFBE0 == 0x4520E000 {
+        inst.op = if (binst >> 10) & 1 == 0 { A64_AESMC_Z } else { A64_AESIMC_Z };
+        inst.rn = inst.rd;
+    } else if binst & 0xFFFFFC00 == 0x4523E000 {
+        inst.op = A64_SM4E_Z;
+        inst.sve.esize = Size::SZ_W;
+        inst.rn = inst.rd;
+        inst.rm = regRn(binst);
+    } else if binst & 0xFFE0F800 == 0x4520F000 {
+        inst.op = if (binst >> 10) & 1 == 0 { A64_SM4EKEY_Z } else { A64_RAX1_Z };
+        inst.sve.esize = if inst.op == A64_RAX1_Z { Size::SZ_X } else { Size::SZ_W };
+    } else {
+        return UNKNOWN_INST;
+    }
+    inst
+}
+
+/// SVE Predicate Manipulation: PTRUE, PFALSE, PFIRST, PNEXT, PTEST, the
+/// logical ops, BRKA, BRKB, BRKN, BRKPA, BRKPB, WHILE, CTERMEQ, CTERMNE,
+/// CNTP, INCP and DECP and the FFR moves. Inst.rd := Pd (Rdn or Zdn of
+/// CNTP, INCP and DECP), Inst.rn := Pn (Rn of WHILE and CTERM), and
+/// Inst.rm := Pm (Rm of WHILE and CTERM). WHILEGE, WHILEGT, WHILEHS and
+/// WHILEHI are SVE2's.
+fn sve_predicate(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    let size = ((binst >> 22) & 0b11) as u8;
+    let pd = (binst & 0b1111) as u8;
+    let pn = ((binst >> 5) & 0b1111) as u8;
+    let pg_hi = ((binst >> 10) & 0b1111) as u8;
+    let s = (binst >> 16) & 1 == 1;
+    if binst & 0xFF3EFC10 == 0x2518E000 {
+        inst.op = A64_PTRUE;
+        inst.rd = pd;
+        inst.imm = ((binst >> 5) & 0b11111) as u64;
+        inst.sve.esize = size;
+        if s {
+            inst.flags |= SET_FLAGS;
+        }
+    } else if binst & 0xFFFFFFF0 == 0x2518E400 {
+        inst.op = A64_PFALSE;
+        inst.rd = pd;
+    } else if binst & 0xFFFFFE10 == 0x2558C000 {
+        inst.op = A64_PFIRST;
+        inst.rd = pd;
+        inst.sve.pg = Some(pn);
+        inst.flags |= SET_FLAGS;
+    } else if binst & 0xFF3FFE10 == 0x2519C400 {
+        inst.op = A64_PNEXT;
+        inst.rd = pd;
+        inst.rn = pn;
+        inst.sve.esize = size;
+        inst.flags |= SET_FLAGS;
+    } else if binst & 0xFFFFC21F == 0x2550C000 {
+        inst.op = A64_PTEST;
+        inst.sve.pg = Some(pg_hi);
+        inst.rn = pn;
+        inst.flags |= SET_FLAGS;
+    } else if binst & 0xFF3FC200 == 0x25104000 {
+        let (merging, flags) = ((binst >> 4) & 1 == 1, (binst >> 22) & 1 == 1);
+        if merging && flags {
+            return UNKNOWN_INST;
+        }
+        inst.op = if (binst >> 23) & 1 == 1 { A64_BRKB } else { A64_BRKA };
+        inst.rd = pd;
+        inst.rn = pn;
+        inst.sve.pg = Some(pg_hi);
+        inst.sve.mode = Some(if merging { Predication::Merging } else { Predication::Zeroing });
+        if flags {
+            inst.flags |= SET_FLAGS;
+        }
+    } else if binst & 0xFF20E000 == 0x25200000 {
+        use Cond::*;
+        // By U, lt and eq.
+        const CONDS: [u8; 8] = [COND_GE, COND_GT, COND_LT, COND_LE, COND_HS, COND_HI, COND_LO, COND_LS];
+        let key = ((binst >> 11) & 1) << 2 | ((binst >> 10) & 1) << 1 | ((binst >> 4) & 1);
+        inst.op = A64_WHILE;
+        inst.rd = pd;
+        inst.rn = regRn(binst);
+        inst.rm = regRm(binst);
+        inst.sve.esize = size;
+        inst.flags = set_cond(SET_FLAGS, CONDS[key as usize]);
+        if (binst >> 12) & 1 == 0 {
+            inst.flags |= W32;
+        }
+    } else if binst & 0xFF3FC200 == 0x25208000 {
+        inst.op = A64_CNTP;
+        inst.rd = regRd(binst);
+        inst.rn = pn;
+        inst.sve.pg = Some(pg_hi);
+        inst.sve.esize = size;
+    } else if binst & 0xFFFFFFF0 == 0x2519F000 || binst & 0xFFBFFE10 == 0x2518F000 {
+        inst.op = A64_RDFFR;
+        inst.rd = pd;
+        if binst & 0x10000 == 0 {
+            inst.sve.pg = Some(pn);
+            inst.sve.mode = Some(Predication::Zeroing);
+            if (binst >> 22) & 1 == 1 {
+                inst.flags |= SET_FLAGS;
+            }
+        }
+    } else if binst == 0x252C9000 {
+        inst.op = A64_SETFFR;
+    } else if binst & 0xFFFFFE1F == 0x25289000 {
+        inst.op = A64_WRFFR;
+        inst.rn = pn;
+    } else if binst & 0xFF30C000 == 0x25004000 {
+        // By op, S, o2 and o3; SEL doesn't set the flags.
+        const OPS: [Op; 8] = [A64_AND_P, A64_BIC_P, A64_EOR_P, A64_SEL_P, A64_ORR_P, A64_ORN_P, A64_NOR_P, A64_NAND_P];
+        let key = ((binst >> 23) & 1) << 2 | ((binst >> 9) & 1) << 1 | ((binst >> 4) & 1);
+        inst.op = OPS[key as usize];
+        if (binst >> 22) & 1 == 1 {
+            if inst.op == A64_SEL_P {
+                return UNKNOWN_INST;
+            }
+            inst.flags |= SET_FLAGS;
+        }
+        inst.rd = pd;
+        inst.rn = pn;
+        inst.rm = ((binst >> 16) & 0b1111) as u8;
+        inst.sve.pg = Some(pg_hi);
+        if inst.op != A64_SEL_P {
+            inst.sve.mode = Some(Predication::Zeroing);
+        }
+    } else if binst & 0xFFBFC210 == 0x25184000 || binst & 0xFFB0C200 == 0x2500C000 {
+        inst.op = match (binst >> 15) & 1 {
+            0 => A64_BRKN,
+            _ if (binst >> 4) & 1 == 0 => A64_BRKPA,
+            _ => A64_BRKPB,
+        };
+        inst.rd = pd;
+        inst.rn = pn;
+        inst.rm = if inst.op == A64_BRKN { pd } else { ((binst >> 16) & 0b1111) as u8 };
+        inst.sve.pg = Some(pg_hi);
+        inst.sve.mode = Some(Predication::Zeroing);
+        if (binst >> 22) & 1 == 1 {
+            inst.flags |= SET_FLAGS;
+        }
+    } else if binst & 0xFF38F200 == 0x25288000 {
+        // INCP and DECP have bit 18 set and D in bit 16; the saturating
+        // forms have D in bit 17, U in bit 16 and sf in bit 10.
+        let (scalar, sf) = ((binst >> 11) & 1 == 1, (binst >> 10) & 1 == 1);
+        let (d, u) = ((binst >> 17) & 1 == 1, (binst >> 16) & 1 == 1);
+        inst.op = match ((binst >> 18) & 1, scalar) {
+            (1, _) if d || sf => return UNKNOWN_INST,
+            (0, false) | (1, false) if sf || size == Size::SZ_B => return UNKNOWN_INST,
+            (1, true) => if u { A64_DECP } else { A64_INCP },
+            (1, false) => if u { A64_DECP_Z } else { A64_INCP_Z },
+            (_, true) => if d { A64_QDECP } else { A64_QINCP },
+            (_, false) => if d { A64_QDECP_Z } else { A64_QINCP_Z },
+        };
+        if matches!(inst.op, A64_QINCP | A64_QDECP | A64_QINCP_Z | A64_QDECP_Z) && !u {
+            inst.flags |= FlagMasks::SIMD_SIGNED;
+        }
+        if matches!(inst.op, A64_QINCP | A64_QDECP) && !sf {
+            inst.flags |= W32;
+        }
+        inst.rd = regRd(binst);
+        inst.rn = pn;
+        inst.sve.esize = size;
+    } else if binst & 0xFFA0FC0F == 0x25A02000 {
+        inst.op = if (binst >> 4) & 1 == 1 { A64_CTERMNE } else { A64_CTERMEQ };
+        inst.rn = regRn(binst);
+        inst.rm = regRm(binst);
+        if (binst >> 22) & 1 == 0 {
+            inst.flags |= W32;
+        }
+    }
+    inst
+}
+
+/// The access size, element size and signedness of the dtype field of the
+/// contiguous LD1, LDFF1 and LDNF1.
+fn sve_dtype(dtype: u32) -> (u8, u8, bool) {
+    use Size::*;
+    const DTYPES: [(u8, u8, bool); 16] = [
+        (SZ_B, SZ_B, false), (SZ_B, SZ_H, false), (SZ_B, SZ_W, false), (SZ_B, SZ_X, false),
+        (SZ_W, SZ_X, true), (SZ_H, SZ_H, false), (SZ_H, SZ_W, false), (SZ_H, SZ_X, false),
+        (SZ_H, SZ_X, true), (SZ_H, SZ_W, true), (SZ_W, SZ_W, false), (SZ_W, SZ_X, false),
+        (SZ_B, SZ_X, true), (SZ_B, SZ_W, true), (SZ_B, SZ_H, true), (SZ_X, SZ_X, false),
+    ];
+    DTYPES[dtype as usize & 0b1111]
+}
+
+/// Fills in the operands common to the SVE contiguous loads and stores:
+/// Inst.rd := Zt, the first register, Inst.rn := Xn|SP, Inst.rm := Xm for
+/// the scalar plus scalar forms, where XZR is unallocated except for LDFF1.
+fn sve_ldst(mut inst: Inst, binst: u32, msz: u8, esize: u8, nreg: u8, scalar: bool) -> Inst {
+    if scalar && regRm(binst) == Registries::ZERO_REG && inst.op != Op::A64_LDFF1_Z {
+        return UNKNOWN_INST;
+    }
+    inst.rd = regRd(binst);
+    inst.rn = regRnSP(binst);
+    inst.sve.esize = esize;
+    inst.sve.pg = Some(((binst >> 10) & 0b111) as u8);
+    inst.sve_ldst.msz = msz;
+    inst.sve_ldst.nreg = nreg;
+    if scalar {
+        inst.sve_ldst.mode = AddrMode::AM_OFF_REG;
+        inst.rm = regRm(binst);
+    } else {
+        // imm4, scaled by the number of registers.
+        inst.sve_ldst.mode = AddrMode::AM_OFF_IMM;
+        inst.offset = ((((binst >> 16) & 0b1111) as i64) << 60 >> 60) * nreg as i64;
+    }
+    inst
+}
+
+/// SVE Memory -- Contiguous Load: LD1, LDFF1, LDNF1, LDNT1 and LD2-LD4,
+/// scalar plus immediate and scalar plus scalar. The loads zero the
+/// inactive elements.
+fn sve_load(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    if (binst >> 25) != 0b1010010 {
+        return UNKNOWN_INST;
+    }
+    let (msz, num) = (((binst >> 23) & 0b11) as u8, ((binst >> 21) & 0b11) as u8);
+    let (op, scalar) = match ((binst >> 13) & 0b111, (binst >> 20) & 1) {
+        (0b101, 0) => (A64_LD1_Z, false),
+        (0b101, _) => (A64_LDNF1_Z, false),
+        (0b010, _) => (A64_LD1_Z, true),
+        (0b011, _) => (A64_LDFF1_Z, true),
+        (0b111, 0) => ([A64_LDNT1_Z, A64_LD2_Z, A64_LD3_Z, A64_LD4_Z][num as usize], false),
+        (0b110, _) => ([A64_LDNT1_Z, A64_LD2_Z, A64_LD3_Z, A64_LD4_Z][num as usize], true),
+        _ => return UNKNOWN_INST,
+    };
+    inst.op = op;
+    inst.sve.mode = Some(Predication::Zeroing);
+    match op {
+        A64_LD1_Z | A64_LDFF1_Z | A64_LDNF1_Z => {
+            let (msz, esize, signed) = sve_dtype((binst >> 21) & 0b1111);
+            inst.sve_ldst.signed = signed;
+            sve_ldst(inst, binst, msz, esize, 1, scalar)
+        }
+        A64_LDNT1_Z => sve_ldst(inst, binst, msz, msz, 1, scalar),
+        _ => sve_ldst(inst, binst, msz, msz, num + 1, scalar),
+    }
+}
+
+/// The addressing of a gather or scatter.
+enum SveOffsets {
+    /// [Zn, #imm5 << msz]
+    VectorImm,
+    /// [Xn, Zm, extend] with a 32-bit or (UXTX) 64-bit offset per element.
+    Vector { extend: u8, scaled: bool },
+}
+
+/// Fills in the operands of gathers and scatters, as `sve_ldst` does for
+/// the contiguous forms. Unscaled byte offsets are the only ones of byte
+/// accesses; the scaled encodings of those are prefetches.
+fn sve_vector_ldst(mut inst: Inst, binst: u32, msz: u8, esize: u8, offsets: SveOffsets) -> Inst {
+    if msz > esize {
+        return UNKNOWN_INST;
+    }
+    inst.rd = regRd(binst);
+    inst.sve.esize = esize;
+    inst.sve.pg = Some(((binst >> 10) & 0b111) as u8);
+    inst.sve_ldst.msz = msz;
+    inst.sve_ldst.nreg = 1;
+    inst.sve_ldst.vector = true;
+    match offsets {
+        SveOffsets::VectorImm => {
+            inst.sve_ldst.mode = AddrMode::AM_OFF_IMM;
+            inst.rn = regRn(binst);
+            inst.offset = (((binst >> 16) & 0b11111) as i64) << msz;
+        }
+        SveOffsets::Vector { scaled: true, .. } if msz == Size::SZ_B => return UNKNOWN_INST,
+        SveOffsets::Vector { extend, scaled } => {
+            inst.sve_ldst.mode = AddrMode::AM_OFF_EXT;
+            inst.sve_ldst.extend = extend;
+            inst.sve_ldst.scaled = scaled;
+            inst.rn = regRnSP(binst);
+            inst.rm = regRm(binst);
+        }
+    }
+    inst
+}
+
+/// SVE Memory -- 32-bit and 64-bit Gather (esize FSZ_S or FSZ_D): LD1 and
+/// LDFF1, vector plus immediate and scalar plus vector. U (bit 14) clear
+/// sign-extends, which elements as wide as the access can't be. The
+/// prefetches, broadcasts and LDR of the classes aren't decoded.
+fn sve_gather(binst: u32, esize: u8) -> Inst {
+    use ExtendType::*;
+    let mut inst = UNKNOWN_INST;
+    if (binst >> 25) & 0b1111 != 0b0010 {
+        return UNKNOWN_INST;
+    }
+    let msz = ((binst >> 23) & 0b11) as u8;
+    let wide = esize == FPSize::FSZ_D;
+    let offsets = match ((binst >> 15) & 1, (binst >> 21) & 0b11) {
+        (0, _) => SveOffsets::Vector { extend: [UXTW, SXTW][((binst >> 22) & 1) as usize], scaled: (binst >> 21) & 1 == 1 },
+        (1, 0b01) => SveOffsets::VectorImm,
+        (1, 0b10) if wide => SveOffsets::Vector { extend: UXTX, scaled: false },
+        (1, 0b11) if wide => SveOffsets::Vector { extend: UXTX, scaled: true },
+        _ => return UNKNOWN_INST,
+    };
+    inst.sve_ldst.signed = (binst >> 14) & 1 == 0;
+    if inst.sve_ldst.signed && msz == esize {
+        return UNKNOWN_INST;
+    }
+    inst.op = if (binst >> 13) & 1 == 1 { Op::A64_LDFF1_Z } else { Op::A64_LD1_Z };
+    inst.sve.mode = Some(Predication::Zeroing);
+    sve_vector_ldst(inst, binst, msz, esize, offsets)
+}
+
+/// SVE Memory -- Scatter: ST1 with bit 15 set and not both of bits 14:13;
+/// bit 13 and bits 22:21 select the element size and the offsets, and
+/// bit 14 the extension of 32-bit ones.
+fn sve_scatter(binst: u32) -> Inst {
+    use ExtendType::*;
+    use FPSize::*;
+    let mut inst = UNKNOWN_INST;
+    let msz = ((binst >> 23) & 0b11) as u8;
+    let extend = [UXTW, SXTW][((binst >> 14) & 1) as usize];
+    let (esize, offsets) = match ((binst >> 13) & 1, (binst >> 21) & 0b11) {
+        (1, 0b00) => (FSZ_D, SveOffsets::Vector { extend: UXTX, scaled: false }),
+        (1, 0b01) => (FSZ_D, SveOffsets::Vector { extend: UXTX, scaled: true }),
+        (1, 0b10) => (FSZ_D, SveOffsets::VectorImm),
+        (1, _) => (FSZ_S, SveOffsets::VectorImm),
+        (_, 0b00) => (FSZ_D, SveOffsets::Vector { extend, scaled: false }),
+        (_, 0b01) => (FSZ_D, SveOffsets::Vector { extend, scaled: true }),
+        (_, 0b10) => (FSZ_S, SveOffsets::Vector { extend, scaled: false }),
+        (_, _) => (FSZ_S, SveOffsets::Vector { extend, scaled: true }),
+    };
+    inst.op = Op::A64_ST1_Z;
+    sve_vector_ldst(inst, binst, msz, esize, offsets)
+}
+
+/// SVE Memory -- Store: the contiguous ST1, STNT1 and ST2-ST4, scalar plus
+/// immediate and scalar plus scalar, and the scatters. ST1 may store
+/// narrower than the elements (ST1B Z0.D truncates each element to a byte).
+fn sve_store(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+    if (binst >> 25) != 0b1110010 {
+        return UNKNOWN_INST;
+    }
+    if (binst >> 15) & 1 == 1 && (binst >> 13) & 0b11 != 0b11 {
+        return sve_scatter(binst);
+    }
+    let (msz, low) = (((binst >> 23) & 0b11) as u8, ((binst >> 21) & 0b11) as u8);
+    let (op, scalar) = match ((binst >> 13) & 0b111, (binst >> 20) & 1) {
+        (0b111, 0) => (A64_ST1_Z, false),
+        (0b010, _) => (A64_ST1_Z, true),
+        (0b111, _) => ([A64_STNT1_Z, A64_ST2_Z, A64_ST3_Z, A64_ST4_Z][low as usize], false),
+        (0b011, _) => ([A64_STNT1_Z, A64_ST2_Z, A64_ST3_Z, A64_ST4_Z][low as usize], true),
+        _ => return UNKNOWN_INST,
+    };
+    inst.op = op;
+    match op {
+        // Bits 22:21 are the element size.
+        A64_ST1_Z if low < msz => UNKNOWN_INST,
+        A64_ST1_Z => sve_ldst(inst, binst, msz, low, 1, scalar),
+        A64_STNT1_Z => sve_ldst(inst, binst, msz, msz, 1, scalar),
+        _ => sve_ldst(inst, binst, msz, msz, low + 1, scalar),
+    }
+}
+
+/// Branches, Exception Generating and System Instructions.
+///
+/// Only the branch classes, exception generation and the system instructions
+/// (hints, barriers, PSTATE, SYS and system register moves) are decoded so
+/// far.
+pub fn branches(binst: u32) -> Inst {
+    let mut inst = UNKNOWN_INST;
+
+    let op0 = (binst >> 29) & 0b111;
+    let op1 = (binst >> 12) & 0b11111111111111;
+
+    match op0 {
+        0b000 | 0b100 => { // Unconditional branch (immediate)
+            inst.op = if op0 == 0b000 { Op::A64_B } else { Op::A64_BL };
+            inst.offset = 4 * sext((binst & 0x3FFFFFF) as u64, 26);
+        }
+        0b001 | 0b101 => {
+            // sf bit of CBZ/CBNZ and b5 of TBZ/TBNZ share bit 31.
+            if (binst >> 31) == 0 {
+                inst.flags |= W32;
+            }
+            inst.rd = regRd(binst);
+
+            if (op1 >> 13) == 0 { // Compare and branch (immediate)
+                inst.op = if (binst >> 24) & 1 == 0 { Op::A64_CBZ } else { Op::A64_CBNZ };
+                inst.offset = 4 * sext(((binst >> 5) & 0x7FFFF) as u64, 19);
+            } else { // Test and branch (immediate)
+                inst.op = if (binst >> 24) & 1 == 0 { Op::A64_TBZ } else { Op::A64_TBNZ };
+                let b5 = (binst >> 31) & 1;
+                let b40 = (binst >> 19) & 0b11111;
+                inst.tbz.bit = (b5 << 5) | b40;
+                inst.tbz.offset = 4 * sext(((binst >> 5) & 0x3FFF) as u64, 14) as i32;
+            }
+        }
+        0b010 => { // Conditional branch (immediate)
+            // o1 (bit 24) and o0 (bit 4) set are unallocated or BC.cond
+            // (FEAT_HBC), bit 25 set is unallocated.
+            if (binst >> 25) & 1 != 0 || (binst >> 24) & 1 != 0 || (binst >> 4) & 1 != 0 {
+                return UNKNOWN_INST;
+            }
+            inst.op = Op::A64_BCOND;
+            inst.flags = set_cond(inst.flags, (binst & 0b1111) as u8);
+            inst.offset = 4 * sext(((binst >> 5) & 0x7FFFF) as u64, 19);
+        }
+        0b110 if (op1 >> 12) == 0b00 => { // Exception generation
+            let opc = (binst >> 21) & 0b111;
+            let op2 = (binst >> 2) & 0b111;
+            let ll = binst & 0b11;
+            if op2 != 0 {
+                return UNKNOWN_INST;
+            }
+            inst.op = match (opc, ll) {
+                (0b000, 0b01) => Op::A64_SVC,
+                (0b000, 0b10) => Op::A64_HVC,
+                (0b000, 0b11) => Op::A64_SMC,
+                (0b001, 0b00) => Op::A64_BRK,
+                (0b010, 0b00) => Op::A64_HLT,
+                (0b101, 0b01) => Op::A64_DCPS1,
+                (0b101, 0b10) => Op::A64_DCPS2,
+                (0b101, 0b11) => Op::A64_DCPS3,
+                _ => return UNKNOWN_INST,
+            };
+            inst.imm = ((binst >> 5) & 0xFFFF) as u64;
+        }
+        0b110 if (op1 >> 10) == 0b0100 => return system(binst),
+        0b110 if (op1 >> 13) == 1 => { // Unconditional branch (register)
+            let opc = (binst >> 21) & 0b1111;
+            let op2 = (binst >> 16) & 0b11111;
+            let op3 = (binst >> 10) & 0b111111;
+            let op4 = binst & 0b11111;
+
+            if op2 != 0b11111 {
+                return UNKNOWN_INST;
+            }
+            let rn = regRn(binst);
+
+            // op3 = 00001x are the pointer authentication variants, x the key.
+            inst.op = match (opc, op3, op4) {
+                (0b0000, 0, 0) => Op::A64_BR,
+                (0b0001, 0, 0) => Op::A64_BLR,
+                (0b0010, 0, 0) => Op::A64_RET,
+                (0b0100, 0, 0) if rn == 31 => Op::A64_ERET,
+                (0b0101, 0, 0) if rn == 31 => Op::A64_DRPS,
+                (0b0000, 0b10 | 0b11, 0b11111) => Op::A64_BRA,
+                (0b0001, 0b10 | 0b11, 0b11111) => Op::A64_BLRA,
+                (0b0010, 0b10 | 0b11, 0b11111) if rn == 31 => Op::A64_RETA,
+                (0b0100, 0b10 | 0b11, 0b11111) if rn == 31 => Op::A64_ERETA,
+                (0b1000, 0b10 | 0b11, _) => Op::A64_BRA,
+                (0b1001, 0b10 | 0b11, _) => Op::A64_BLRA,
+                _ => return UNKNOWN_INST,
+            };
+            match inst.op {
+                Op::A64_BR | Op::A64_BLR | Op::A64_RET => inst.rn = rn,
+                Op::A64_BRA | Op::A64_BLRA => {
+                    inst.rn = rn;
+                    inst.imm = (op3 & 1) as u64;
+                    inst.rm = if opc & 0b1000 == 0 { ZERO_REG } else { regRdSP(binst) };
+                }
+                Op::A64_RETA | Op::A64_ERETA => {
+                    inst.imm = (op3 & 1) as u64;
+                    inst.rm = STACK_POINTER;
+                }
+                _ => {}
+            }
+        }
+        _ => return UNKNOWN_INST,
+    }
+
+    inst
+}
+
+/// System instructions (op0 = 110, op1 = 0100xxxxxxxxxx).
+///
+/// Decoded so far: PSTATE (MSR immediate, CFINV, XAFLAG, AXFLAG).
+fn system(binst: u32) -> Inst {
+    let mut inst = UNKNOWN_INST;
+
+    let l = (binst >> 21) & 1;
+    let op0 = (binst >> 19) & 0b11;
+    let op1 = (binst >> 16) & 0b111;
+    let crn = (binst >> 12) & 0b1111;
+    let crm = (binst >> 8) & 0b1111;
+    let op2 = (binst >> 5) & 0b111;
+    let rt = binst & 0b11111;
+
+    if l == 0 && op0 == 0b00 && op1 == 0b011 && crn == 0b0010 && rt == 0b11111 { // Hints
+        inst.op = Op::A64_HINT;
+        inst.imm = ((crm << 3) | op2) as u64;
+        return inst;
+    }
+
+    if l == 0 && op0 == 0b00 && op1 == 0b011 && crn == 0b0011 && rt == 0b11111 { // Barriers
+        inst.op = match (op2, crm) {
+            (0b010, _) => Op::A64_CLREX,
+            (0b100, 0b0000) => Op::A64_SSBB,
+            (0b100, 0b0100) => Op::A64_PSSBB,
+            (0b100, _) => Op::A64_DSB,
+            (0b101, _) => Op::A64_DMB,
+            (0b110, _) => Op::A64_ISB,
+            (0b111, 0b0000) => Op::A64_SB,
+            _ => return UNKNOWN_INST,
+        };
+        inst.imm = crm as u64;
+        return inst;
+    }
+
+    if l == 0 && op0 == 0b00 && crn == 0b0100 && rt == 0b11111 { // PSTATE
+        use PStateField::*;
+        let psfld = match (op1, op2) {
+            (0b000, 0b000) if crm == 0 => { inst.op = Op::A64_CFINV; return inst; }
+            (0b000, 0b001) => { inst.op = Op::A64_XAFlag; return inst; }
+            (0b000, 0b010) => { inst.op = Op::A64_AXFlag; return inst; }
+            (0b000, 0b011) => PSF_UAO,
+            (0b000, 0b100) => PSF_PAN,
+            (0b000, 0b101) => PSF_SPSel,
+            (0b001, 0b000) if crm >> 1 == 0b000 => PSF_ALLINT,
+            (0b001, 0b000) if crm >> 1 == 0b001 => PSF_PM,
+            (0b011, 0b001) => PSF_SSBS,
+            (0b011, 0b010) => PSF_DIT,
+            (0b011, 0b011) if crm >> 1 == 0b001 => PSF_SVCRSM,
+            (0b011, 0b011) if crm >> 1 == 0b010 => PSF_SVCRZA,
+            (0b011, 0b011) if crm >> 1 == 0b011 => PSF_SVCRSMZA,
+            (0b011, 0b100) => PSF_TCO,
+            (0b011, 0b110) => PSF_DAIFSet,
+            (0b011, 0b111) => PSF_DAIFClr,
+            _ => return UNKNOWN_INST,
+        };
+        inst.op = Op::A64_MSR_IMM;
+        inst.msr_imm.psfld = psfld as u32;
+        // Single-bit fields take their value from CRm<0>.
+        inst.msr_imm.imm = match psfld {
+            PSF_ALLINT | PSF_PM | PSF_SVCRSM | PSF_SVCRZA | PSF_SVCRSMZA => crm & 1,
+            _ => crm,
+        };
+        return inst;
+    }
+
+    inst.op = match (op0, l) {
+        (0b01, 0) => Op::A64_SYS,
+        (0b01, _) => Op::A64_SYSL,
+        (0b00, _) => return UNKNOWN_INST,
+        (_, 0) => Op::A64_MSR_REG,
+        (_, _) => Op::A64_MRS,
+    };
+    if op0 >= 0b10 {
+        inst.imm = ((binst >> 5) & 0xFFFF) as u64; // op0:op1:CRn:CRm:op2
+    }
+    inst.sys = Sys { op1: op1 as u16, op2: op2 as u16, crn: crn as u16, crm: crm as u16 };
+    inst.rd = rt as u8;
+    inst
+}
+
+/// Loads and Stores.
+///
+/// Decoded so far: Advanced SIMD load/store multiple and single structures,
+/// Load/store exclusive, ordered and compare and swap, Load register
+/// (literal), Load/store register pair (all addressing modes, including
+/// no-allocate), Load/store register (immediate: unsigned offset,
+/// unscaled, pre- and post-indexed) and Atomic memory operations.
+pub fn loads_and_stores(binst: u32) -> Inst {
+    let op0 = (binst >> 28) & 0b11; // bits 29:28
+    let op2 = (binst >> 23) & 0b11;
+
+    // Bit 26 selects the SIMD&FP registers.
+    if !cfg!(feature = "simd") && (binst >> 26) & 1 == 1 {
+        return UNKNOWN_INST;
+    }
+
+    match op0 {
+        0b00 if op2 >> 1 == 0 && (binst >> 26) & 1 == 0 => load_store_exclusive(binst),
+        0b00 if (binst >> 31) == 0 && (binst >> 26) & 1 == 1 => simd_load_store(binst),
+        0b01 if op2 >> 1 == 0 => load_literal(binst),
+        0b10 => load_store_pair(binst),
+        0b11 if op2 >> 1 == 1 => load_store_reg_imm(binst),
+        0b11 if (binst >> 21) & 1 == 0 => match (binst >> 10) & 0b11 {
+            0b10 => UNKNOWN_INST, // unprivileged
+            _ => load_store_reg_imm(binst),
+        },
+        0b11 if (binst >> 10) & 0b11 == 0b00 && (binst >> 26) & 1 == 0 => atomic_memory(binst),
+        _ => UNKNOWN_INST,
+    }
+}
+
+/// Advanced SIMD load/store multiple and single structures: Inst.rd := Rt,
+/// the first register of the list, Inst.rn := base, Inst.simd_ldst.nreg :=
+/// length of the list, Inst.simd_ldst.index := lane of the single structure
+/// forms. Inst.flags.vec := arrangement; only its element size applies to
+/// the single structure forms other than LDxR.
+///
+/// The post-indexed forms are AM_POST, with Inst.rm := Xm, or ZERO_REG for
+/// the immediate form and Inst.simd_ldst.offset := the immediate (which is
+/// the number of bytes transferred); the others are AM_SIMPLE.
+fn simd_load_store(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+
+    let q = (binst >> 30) & 1;
+    let single = (binst >> 24) & 1 == 1;
+    let post = (binst >> 23) & 1 == 1;
+    let load = (binst >> 22) & 1 == 1;
+    let r = (binst >> 21) & 1;
+    let opcode = (binst >> 12) & 0b1111;
+    let size = ((binst >> 10) & 0b11) as u8;
+
+    if !post && (binst >> 16) & 0b11111 != 0 {
+        return UNKNOWN_INST;
+    }
+
+    let (va, bytes) = if !single {
+        // LD1 with several registers isn't interleaved like LD2...LD4.
+        let (nreg, selem) = match opcode {
+            0b0000 => (4, 4),
+            0b0010 => (4, 1),
+            0b0100 => (3, 3),
+            0b0110 => (3, 1),
+            0b0111 => (1, 1),
+            0b1000 => (2, 2),
+            0b1010 => (2, 1),
+            _ => return UNKNOWN_INST,
+        };
+        if r != 0 || (size == Size::SZ_X && q == 0 && selem != 1) {
+            return UNKNOWN_INST;
+        }
+        inst.op = match (selem, load) {
+            (1, true) => A64_LD1_MULT,
+            (1, false) => A64_ST1_MULT,
+            (2, true) => A64_LD2_MULT,
+            (2, false) => A64_ST2_MULT,
+            (3, true) => A64_LD3_MULT,
+            (3, false) => A64_ST3_MULT,
+            (_, true) => A64_LD4_MULT,
+            (_, false) => A64_ST4_MULT,
+        };
+        inst.simd_ldst.nreg = nreg;
+        (VectorArrangement::from_elem(size, q == 1), nreg * (8 << q))
+    } else {
+        let s = opcode & 1;
+        let nreg = ((((opcode >> 1) & 1) << 1) | r) + 1;
+        let replicate = opcode >> 2 == 0b11;
+        let (scale, index) = match opcode >> 2 {
+            0b00 => (0, (q << 3) | (s << 2) | size as u32),
+            0b01 if size & 1 == 0 => (1, (q << 2) | (s << 1) | (size >> 1) as u32),
+            0b10 if size == 0b00 => (2, (q << 1) | s),
+            0b10 if size == 0b01 && s == 0 => (3, q),
+            0b11 if load && s == 0 => (size, 0),
+            _ => return UNKNOWN_INST,
+        };
+        inst.op = match (replicate, nreg, load) {
+            (true, 1, _) => A64_LD1R,
+            (true, 2, _) => A64_LD2R,
+            (true, 3, _) => A64_LD3R,
+            (true, _, _) => A64_LD4R,
+            (_, 1, true) => A64_LD1_SINGLE,
+            (_, 1, false) => A64_ST1_SINGLE,
+            (_, 2, true) => A64_LD2_SINGLE,
+            (_, 2, false) => A64_ST2_SINGLE,
+            (_, 3, true) => A64_LD3_SINGLE,
+            (_, 3, false) => A64_ST3_SINGLE,
+            (_, _, true) => A64_LD4_SINGLE,
+            (_, _, false) => A64_ST4_SINGLE,
+        };
+        inst.simd_ldst.nreg = nreg;
+        inst.simd_ldst.index = index as u16;
+        let q = if replicate { q == 1 } else { true };
+        (VectorArrangement::from_elem(scale, q), nreg * FPSize::bytes(scale))
+    };
+
+    inst.flags = set_vec_arrangement(inst.flags, va);
+    if post {
+        inst.flags = set_addrmode(inst.flags, AddrMode::AM_POST);
+        inst.rm = regRm(binst);
+        if inst.rm == Registries::ZERO_REG {
+            inst.simd_ldst.offset = bytes as i16;
+        }
+    } else {
+        inst.flags = set_addrmode(inst.flags, AddrMode::AM_SIMPLE);
+    }
+    inst.rd = regRd(binst);
+    inst.rn = regRnSP(binst);
+
+    inst
+}
+
+/// Load/store exclusive, ordered, and compare and swap: Inst.rd := Rt,
+/// Inst.rt2 := Rt2 (also Rt+1 for CASP), Inst.rs := status register of
+/// store exclusives or compare value of CAS, Inst.rn := base. The pairs of
+/// CASP are even/odd registers, Rs:Rs+1 and Rt:Rt+1.
+///
+/// Ordered loads and stores (LDAR, STLR, ...) are A64_LDR and A64_STR with
+/// AM_SIMPLE; everything uses Inst.ldst_order.
+fn load_store_exclusive(binst: u32) -> Inst {
+    let mut inst = UNKNOWN_INST;
+
+    let size = ((binst >> 30) & 0b11) as u8;
+    let o2 = (binst >> 23) & 1;
+    let l = (binst >> 22) & 1 == 1;
+    let o1 = (binst >> 21) & 1;
+    let o0 = (binst >> 15) & 1 == 1;
+    let rs = regRm(binst);
+    let rt2 = ((binst >> 10) & 0b11111) as u8;
+    let rt = regRd(binst);
+
+    let (load, store) = match (o2, o1) {
+        (0, 0) => { // exclusive register
+            inst.op = if l { Op::A64_LDXR } else { Op::A64_STXR };
+            (if l && o0 { MemOrdering::MO_ACQUIRE } else { MemOrdering::MO_NONE },
+             if !l && o0 { MemOrdering::MO_RELEASE } else { MemOrdering::MO_NONE })
+        }
+        (0, 1) if size >> 1 == 1 => { // exclusive pair
+            inst.op = if l { Op::A64_LDXP } else { Op::A64_STXP };
+            inst.rt2 = rt2;
+            (if l && o0 { MemOrdering::MO_ACQUIRE } else { MemOrdering::MO_NONE },
+             if !l && o0 { MemOrdering::MO_RELEASE } else { MemOrdering::MO_NONE })
+        }
+        (_, 1) => { // CASP (o2 = 0, size = 0x) and CAS (o2 = 1)
+            if rt2 != 0b11111 {
+                return UNKNOWN_INST;
+            }
+            if o2 == 0 {
+                if !rs.is_multiple_of(2) || !rt.is_multiple_of(2) {
+                    return errinst(format!("load_store_exclusive: CASP with odd register (Rs = {}, Rt = {})", rs, rt));
+                }
+                inst.op = Op::A64_CASP;
+                inst.rt2 = rt + 1;
+            } else {
+                inst.op = Op::A64_CAS;
+            }
+            (if l { MemOrdering::MO_ACQUIRE } else { MemOrdering::MO_NONE },
+             if o0 { MemOrdering::MO_RELEASE } else { MemOrdering::MO_NONE })
+        }
+        _ => { // ordered; o0 = 0 are the LORegion variants
+            inst.op = if l { Op::A64_LDR } else { Op::A64_STR };
+            let ordering = match (l, o0) {
+                (true, true) => MemOrdering::MO_ACQUIRE,
+                (true, false) => MemOrdering::MO_LO_ACQUIRE,
+                (false, true) => MemOrdering::MO_RELEASE,
+                (false, false) => MemOrdering::MO_LO_RELEASE,
+            };
+            if l { (ordering, MemOrdering::MO_NONE) } else { (MemOrdering::MO_NONE, ordering) }
+        }
+    };
+
+    // For pairs, size only tells W from X registers; mem_extend is the size
+    // of each register's access.
+    let elem = if matches!(inst.op, Op::A64_LDXP | Op::A64_STXP | Op::A64_CASP) { Size::SZ_W | (size & 1) } else { size };
+    if elem != Size::SZ_X {
+        inst.flags |= W32;
+    }
+    inst.flags = set_mem_extend(inst.flags, elem);
+    inst.flags = set_addrmode(inst.flags, AddrMode::AM_SIMPLE);
+    inst.ldst_order.load = load as u16;
+    inst.ldst_order.store = store as u16;
+    if matches!(inst.op, Op::A64_STXR | Op::A64_STXP | Op::A64_CAS | Op::A64_CASP) {
+        inst.rs = rs;
+        inst.ldst_order.rs = rs;
+    }
+    inst.rd = rt;
+    inst.rn = regRnSP(binst);
+
+    inst
+}
+
+/// Atomic memory operations and LDAPR: Inst.rd := Rt, Inst.rs := Rs (the
+/// operand; the old value goes to Rt), Inst.rn := base, AM_SIMPLE. The A and
+/// R bits give ldst_order.load and .store; Rt = XZR is the ST* alias.
+fn atomic_memory(binst: u32) -> Inst {
+    use Op::*;
+    let mut inst = UNKNOWN_INST;
+
+    let size = ((binst >> 30) & 0b11) as u8;
+    let a = (binst >> 23) & 1 == 1;
+    let r = (binst >> 22) & 1 == 1;
+    let o3 = (binst >> 15) & 1;
+    let opc = (binst >> 12) & 0b111;
+    let rs = regRm(binst);
+
+    inst.op = match (o3, opc) {
+        (0, 0b000) => A64_LDADD,
+        (0, 0b001) => A64_LDCLR,
+        (0, 0b010) => A64_LDEOR,
+        (0, 0b011) => A64_LDSET,
+        (0, 0b100) => A64_LDSMAX,
+        (0, 0b101) => A64_LDSMIN,
+        (0, 0b110) => A64_LDUMAX,
+        (0, 0b111) => A64_LDUMIN,
+        (1, 0b000) => A64_SWP,
+        (1, 0b100) if a && !r && rs == 0b11111 => A64_LDAPR,
+        _ => return UNKNOWN_INST,
+    };
+
+    if size != Size::SZ_X {
+        inst.flags |= W32;
+    }
+    inst.flags = set_mem_extend(inst.flags, size);
+    inst.flags = set_addrmode(inst.flags, AddrMode::AM_SIMPLE);
+    let load = match (a, inst.op) {
+        (true, A64_LDAPR) => MemOrdering::MO_ACQUIRE_PC,
+        (true, _) => MemOrdering::MO_ACQUIRE,
+        (false, _) => MemOrdering::MO_NONE,
+    };
+    let store = if r { MemOrdering::MO_RELEASE } else { MemOrdering::MO_NONE };
+    inst.ldst_order.load = load as u16;
+    inst.ldst_order.store = store as u16;
+    if inst.op != A64_LDAPR {
+        inst.rs = rs;
+        inst.ldst_order.rs = rs;
+    }
+    inst.rd = regRd(binst);
+    inst.rn = regRnSP(binst);
+
+    inst
+}
+
+fn load_literal(binst: u32) -> Inst {
+    let mut inst = UNKNOWN_INST;
+
+    let opc = (binst >> 30) & 0b11;
+    let simd = (binst >> 26) & 1 == 1;
+
+    if simd {
+        let prec = match opc {
+            0b00 => FPSize::FSZ_S,
+            0b01 => FPSize::FSZ_D,
+            0b10 => FPSize::FSZ_Q,
+            _ => return UNKNOWN_INST,
+        };
+        inst.op = Op::A64_LDR_FP;
+        inst.flags = set_prec(inst.flags, prec);
+    } else {
+        let memext = match opc {
+            0b00 => ExtendType::UXTW,
+            0b01 => ExtendType::UXTX,
+            0b10 => ExtendType::SXTW, // LDRSW
+            _ => 0,
+        };
+        inst.op = if opc == 0b11 { Op::A64_PRFM } else { Op::A64_LDR };
+        if opc == 0b00 {
+            inst.flags |= W32;
+        }
+        inst.flags = set_mem_extend(inst.flags, memext);
+    }
+
+    inst.flags = set_addrmode(inst.flags, AddrMode::AM_LITERAL);
+    inst.rd = regRd(binst);
+    inst.offset = 4 * sext(((binst >> 5) & 0x7FFFF) as u64, 19);
+
+    inst
+}
+
+/// Load/store register pair: Inst.rd := Rt, Inst.rt2 := Rt2, Inst.rn := base,
+/// Inst.offset := scaled immediate offset.
+fn load_store_pair(binst: u32) -> Inst {
+    let mut inst = UNKNOWN_INST;
+
+    let opc = (binst >> 30) & 0b11;
+    let simd = (binst >> 26) & 1 == 1;
+    let mode = (binst >> 23) & 0b11;
+    let load = (binst >> 22) & 1 == 1;
+
+    let scale = if simd {
+        let prec = match opc {
+            0b00 => FPSize::FSZ_S,
+            0b01 => FPSize::FSZ_D,
+            0b10 => FPSize::FSZ_Q,
+            _ => return UNKNOWN_INST,
+        };
+        inst.flags = set_prec(inst.flags, prec);
+        inst.op = match (mode, load) {
+            (0b00, false) => Op::A64_STNP_FP,
+            (0b00, true) => Op::A64_LDNP_FP,
+            (_, false) => Op::A64_STP_FP,
+            (_, true) => Op::A64_LDP_FP,
+        };
+        2 + opc
+    } else {
+        let memext = match (opc, load) {
+            (0b00, _) => ExtendType::UXTW,
+            (0b01, true) if mode != 0b00 => ExtendType::SXTW, // LDPSW
+            (0b10, _) => ExtendType::UXTX,
+            _ => return UNKNOWN_INST, // STGP, reserved
+        };
+        if opc == 0b00 {
+            inst.flags |= W32;
+        }
+        inst.flags = set_mem_extend(inst.flags, memext);
+        inst.op = match (mode, load) {
+            (0b00, false) => Op::A64_STNP,
+            (0b00, true) => Op::A64_LDNP,
+            (_, false) => Op::A64_STP,
+            (_, true) => Op::A64_LDP,
+        };
+        if opc == 0b10 { 3 } else { 2 }
+    };
+
+    let addrmode = match mode {
+        0b01 => AddrMode::AM_POST,
+        0b11 => AddrMode::AM_PRE,
+        _ => AddrMode::AM_OFF_IMM,
+    };
+    inst.flags = set_addrmode(inst.flags, addrmode);
+
+    inst.rd = regRd(binst);
+    inst.rt2 = ((binst >> 10) & 0b11111) as u8;
+    inst.rn = regRnSP(binst);
+    inst.offset = sext(((binst >> 15) & 0b1111111) as u64, 7) << scale;
+
+    inst
+}
+
+/// Load/store register (immediate): Inst.rd := Rt (or prefetch operation),
+/// Inst.rn := base, Inst.offset := byte offset.
+fn load_store_reg_imm(binst: u32) -> Inst {
+    let mut inst = UNKNOWN_INST;
+
+    let size = ((binst >> 30) & 0b11) as u8;
+    let simd = (binst >> 26) & 1 == 1;
+    let opc = (binst >> 22) & 0b11;
+    let unsigned_offset = (binst >> 24) & 1 == 1;
+    let mode = (binst >> 10) & 0b11; // only for !unsigned_offset
+
+    let addrmode = match (unsigned_offset, mode) {
+        (true, _) | (false, 0b00) => AddrMode::AM_OFF_IMM,
+        (false, 0b01) => AddrMode::AM_POST,
+        _ => AddrMode::AM_PRE,
+    };
+
+    let scale = if simd {
+        let prec = match (size, opc) {
+            (_, 0b00) | (_, 0b01) => size,
+            (0b00, 0b10) | (0b00, 0b11) => FPSize::FSZ_Q,
+            _ => return UNKNOWN_INST,
+        };
+        inst.op = if opc & 1 == 1 { Op::A64_LDR_FP } else { Op::A64_STR_FP };
+        inst.flags = set_prec(inst.flags, prec);
+        if prec == FPSize::FSZ_Q { 4 } else { size }
+    } else {
+        let (op, signed, w32) = match (size, opc) {
+            (_, 0b00) => (Op::A64_STR, false, size != Size::SZ_X),
+            (_, 0b01) => (Op::A64_LDR, false, size != Size::SZ_X),
+            (0b00 | 0b01, 0b10) => (Op::A64_LDR, true, false),
+            (0b00 | 0b01, 0b11) => (Op::A64_LDR, true, true),
+            (0b10, 0b10) => (Op::A64_LDR, true, false), // LDRSW
+            (0b11, 0b10) if addrmode == AddrMode::AM_OFF_IMM => (Op::A64_PRFM, false, false),
+            _ => return UNKNOWN_INST,
+        };
+        inst.op = op;
+        if w32 {
+            inst.flags |= W32;
+        }
+        inst.flags = set_mem_extend(inst.flags, ((signed as u8) << 2) | size);
+        size
+    };
+
+    inst.flags = set_addrmode(inst.flags, addrmode);
+    inst.rd = regRd(binst);
+    inst.rn = regRnSP(binst);
+    inst.offset = if unsigned_offset {
+        (((binst >> 10) & 0xFFF) as i64) << scale
+    } else {
+        sext(((binst >> 12) & 0x1FF) as u64, 9)
+    };
+
+    inst
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_listing::inst_text;
+
+    #[test]
+    fn unallocated_words_are_unknown() {
+        // llvm-mc: invalid instruction encoding, all of them.
+        for word in [
+            0x7311D8A3, // bitfield, opc 11
+            0xF320CD57, // bitfield, opc 11
+            0x9206FCF8, // logical immediate, reserved imms
+            0x53658403, // ubfm w3, w0, #37...
+            0x56CEF8EC, // b.cond with bit 25 set
+            0x52C620F4, // movz w20, hw 10
+            0x12E00000, // movn w0, hw 11
+            0x72C00001, // movk w1, hw 10
+        ] {
+            assert_eq!(decode(word).op, Op::A64_UNKNOWN, "{:#010x}", word);
+        }
+        assert_eq!(inst_text(&decode(0x92400C20), 0), "and x0, x1, #0xf");
+        assert_eq!(inst_text(&decode(0x54000040), 0x1000), "b.eq 0x1008");
+        assert_eq!(inst_text(&decode(0x93C3FC41), 0), "extr x1, x2, x3, #0x3f");
+        assert_eq!(decode(0x72A00001).op, Op::A64_MOVK); // movk w1, #0, lsl #16
+        assert_eq!(decode(0xD2E00001).op, Op::A64_MOV_IMM); // mov x1, #0 (movz, lsl #48)
+
+        // Nothing a random word decodes to panics or hangs the listing.
+        let mut x: u32 = 0x2545F491;
+        for _ in 0..100_000 {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            inst_text(&decode(x), 0x1000);
+        }
+    }
+
+    #[test]
+    fn raw_encoding() {
+        for word in [0xF2A24680, 0xD503201F, 0x0B020820] { // movk x0, #0x1234, lsl #16; nop; not decoded
+            let inst = decode(word);
+            assert_eq!(inst.raw(), word);
+            assert_eq!(u32::from_le_bytes(inst.bytes()), word);
+        }
+        assert_eq!(decode(0xD503201F).bytes(), [0x1F, 0x20, 0x03, 0xD5]);
+    }
+
+    #[test]
+    fn semantic_equality() {
+        use std::collections::HashSet;
+
+        // Neither the encoding nor payloads that don't apply to the opcode
+        // tell instructions apart.
+        let add = decode(0x91000420); // add x0, x1, #1
+        let mut same = add.clone();
+        same.raw = 0;
+        same.movk = Movk { imm16: 7, lsl: 16 };
+        assert_eq!(add, same);
+        assert_eq!(HashSet::from([add.clone(), same]).len(), 1);
+
+        // Those that do, do.
+        let movk = decode(0xF2A24680); // movk x0, #0x1234, lsl #16
+        let mut other = movk.clone();
+        other.movk.lsl = 32;
+        assert_ne!(movk, other);
+        assert_ne!(decode(0x0B020820), decode(0x0B020821)); // unknown words keep theirs
+
+        let mut fmov = UNKNOWN_INST;
+        fmov.op = Op::A64_FMOV_IMM;
+        fmov.fimm = f64::NAN;
+        assert_eq!(fmov, fmov.clone());
+
+        let text = format!("{:?}", movk);
+        assert!(text.starts_with("Inst { op: A64_MOVK, raw: 0xf2a24680,"), "{}", text);
+        assert!(text.contains("payload: Movk(Movk { imm16: 4660, lsl: 16 })"), "{}", text);
+        assert!(!format!("{:?}", add).contains("payload"));
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn arrangements_and_sizes() {
+        use VectorArrangement::*;
+        // ld1 {v0.8b}, [x0]; ld1 {v0.8h}, [x0]; ld1 {v0.2d}, [x0]; ld2 {v0.4s, v1.4s}, [x0], #32
+        let cases = [(0x0C407000, VA_8B, 8, 1, 64), (0x4C407400, VA_8H, 8, 2, 128), (0x4C407C00, VA_2D, 2, 8, 128), (0x4CDF8800, VA_4S, 4, 4, 128)];
+        for (word, va, n, bytes, bits) in cases {
+            let got = fad_get_vec_arrangement(decode(word).flags);
+            assert_eq!(got, va, "{:#010x}", word);
+            assert_eq!((lanes(got), elem_bytes(got), total_bits(got)), (n, bytes, bits), "{:#010x}", word);
+            assert_eq!(from_elem(elem_size(got), is_q(got)), got);
+        }
+        assert_eq!(elem_size(VA_1D), FPSize::FSZ_D);
+
+        assert_eq!((Size::bytes(Size::SZ_H), Size::bits(Size::SZ_X)), (2, 64));
+        assert_eq!((FPSize::bytes(FPSize::FSZ_Q), FPSize::bits(FPSize::FSZ_S)), (16, 32));
+        assert_eq!(FPSize::to_size(FPSize::FSZ_D), Some(Size::SZ_X));
+        assert_eq!(FPSize::to_size(FPSize::FSZ_Q), None);
+        assert_eq!(FPSize::from_size(Size::SZ_W), FPSize::FSZ_S);
+    }
+
+    #[test]
+    fn pstate_fields() {
+        use PStateField::*;
+        // llvm-mc, but for ALLINT and PM, which it doesn't know.
+        let cases = [
+            (0xD500417F, PSF_UAO, 1, "msr uao, #1"),
+            (0xD500409F, PSF_PAN, 0, "msr pan, #0"),
+            (0xD50041BF, PSF_SPSel, 1, "msr spsel, #1"),
+            (0xD503413F, PSF_SSBS, 1, "msr ssbs, #1"),
+            (0xD503415F, PSF_DIT, 1, "msr dit, #1"),
+            (0xD5034FDF, PSF_DAIFSet, 0xF, "msr daifset, #0xf"),
+            (0xD50342FF, PSF_DAIFClr, 2, "msr daifclr, #2"),
+            (0xD503419F, PSF_TCO, 1, "msr tco, #1"),
+            (0xD501411F, PSF_ALLINT, 1, "msr allint, #1"),
+            (0xD501431F, PSF_PM, 1, "msr pm, #1"),
+            (0xD503437F, PSF_SVCRSM, 1, "smstart sm"),
+            (0xD503447F, PSF_SVCRZA, 0, "smstop za"),
+            (0xD503477F, PSF_SVCRSMZA, 1, "smstart"),
+            (0xD503467F, PSF_SVCRSMZA, 0, "smstop"),
+        ];
+        for (word, field, imm, text) in cases {
+            let inst = decode(word);
+            assert_eq!(inst.op, Op::A64_MSR_IMM, "{:#010x}", word);
+            assert_eq!(PStateField::from_u32(inst.msr_imm.psfld), Some(field), "{:#010x}", word);
+            assert_eq!(inst.msr_imm.imm, imm, "{:#010x}", word);
+            assert_eq!(inst_text(&inst, 0), text);
+        }
+        for (word, op, text) in [(0xD500401F, Op::A64_CFINV, "cfinv"), (0xD500403F, Op::A64_XAFlag, "xaflag"), (0xD500405F, Op::A64_AXFlag, "axflag")] {
+            assert_eq!(decode(word).op, op);
+            assert_eq!(inst_text(&decode(word), 0), text);
+        }
+        assert_eq!(decode(0xD503487F).op, Op::A64_UNKNOWN); // SVCR with CRm<3:1> = 100
+        assert_eq!(PStateField::from_u32(PStateField::ALL.len() as u32), None);
+    }
+
+    #[test]
+    fn exception_immediates() {
+        use crate::aarch64_writer::{brk, encode};
+        // llvm-mc -triple=aarch64 -disassemble
+        let cases = [
+            (0xD4000001, Op::A64_SVC, 0, "svc #0"),
+            (0xD41FFFE1, Op::A64_SVC, 0xFFFF, "svc #0xffff"),
+            (0xD4000022, Op::A64_HVC, 1, "hvc #1"),
+            (0xD4024683, Op::A64_SMC, 0x1234, "smc #0x1234"),
+            (0xD4207D00, Op::A64_BRK, 0x3E8, "brk #0x3e8"),
+            (0xD45E0000, Op::A64_HLT, 0xF000, "hlt #0xf000"),
+            (0xD4A00001, Op::A64_DCPS1, 0, "dcps1"),
+            (0xD4A000A2, Op::A64_DCPS2, 5, "dcps2 #5"),
+            (0xD4A00003, Op::A64_DCPS3, 0, "dcps3"),
+        ];
+        for (word, op, imm, text) in cases {
+            let inst = decode(word);
+            assert_eq!(inst.op, op, "{:#010x}", word);
+            assert_eq!(inst.exception_imm(), Some(imm), "{:#010x}", word);
+            assert_eq!(inst_text(&inst, 0), text);
+        }
+        assert_eq!(encode(&decode(0xD4207D00)), Ok(brk(0x3E8)));
+        assert_eq!(decode(0xD503201F).exception_imm(), None); // nop
+        // svc with LL = 00, dcps with LL = 00, brk and hlt with LL != 00
+        for word in [0xD4000000, 0xD4A00000, 0xD4200001, 0xD4400001] {
+            assert_eq!(decode(word).op, Op::A64_UNKNOWN, "{:#010x}", word);
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "simd"))]
+    fn without_simd() {
+        for word in [
+            0x4E020020, // tbl v0.16b, {v1.16b}, v2.16b
+            0x3DC00000, // ldr q0, [x0]
+            0x4C407000, // ld1 {v0.16b}, [x0]
+            0x04A20020, // add z0.s, z1.s, z2.s
+            0xC008000F, // zero {za0.d, za1.d, za2.d, za3.d}
+        ] {
+            assert_eq!(decode(word).op, Op::A64_UNKNOWN, "{:#010x}", word);
+        }
+        // The integer, branch and load/store groups, and SMSTART, are left.
+        for (word, text) in [
+            (0x91000420, "add x0, x1, #1"),
+            (0xF9400020, "ldr x0, [x1]"),
+            (0x88E17C02, "casa w1, w2, [x0]"),
+            (0x14000004, "b 0x10"),
+            (0xD503477F, "smstart"),
+        ] {
+            assert_eq!(inst_text(&decode(word), 0), text);
+        }
+    }
+}
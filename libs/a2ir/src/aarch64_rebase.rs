@@ -0,0 +1,205 @@
+//! Rebasing decoded programs and what was computed from them by a slide,
+//! the distance KASLR (or ASLR) moved the image, so that static results
+//! line up with addresses captured from the running kernel or process.
+//!
+//! ```text
+//! static:  0xffff800080010000  _text
+//! runtime: 0xffffa1b2c0010000  _text, from /proc/kallsyms
+//! slide:   0x000021b240000000  = slide_between(static, runtime)
+//! ```
+//!
+//! A64 addresses code and data relative to the pc (B, BL, ADR, ADRP, LDR
+//! literal), so the instructions themselves don't change as long as the
+//! slide is a multiple of the 4KB page ADRP counts in; nothing is decoded
+//! again. Absolute addresses in data, which the loader relocates, are the
+//! caller's.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::aarch64_callgraph::{CallSite, Callee};
+use crate::aarch64_cfg::{Block, Cfg, Terminator};
+use crate::aarch64_functions::Function;
+use crate::aarch64_program::Program;
+
+/// The granule of ADRP.
+pub const PAGE_SIZE: u64 = 0x1000;
+
+fn check(slide: i64) -> Result<(), String> {
+    if slide.unsigned_abs().is_multiple_of(PAGE_SIZE) {
+        Ok(())
+    } else {
+        Err(format!("rebase: slide {:#x} isn't a multiple of the page size", slide))
+    }
+}
+
+/// The slide moving static_addr to runtime_addr, e.g. of _text.
+pub fn slide_between(static_addr: u64, runtime_addr: u64) -> Result<i64, String> {
+    let slide = runtime_addr.wrapping_sub(static_addr) as i64;
+    check(slide)?;
+    Ok(slide)
+}
+
+/// Moves the instructions of program by slide.
+pub fn rebase_program(program: &mut Program, slide: i64) -> Result<(), String> {
+    check(slide)?;
+    let old = std::mem::take(program);
+    program.extend(old.iter().map(|(addr, inst)| (addr.wrapping_add_signed(slide), inst.clone())));
+    Ok(())
+}
+
+/// Things holding addresses of the program.
+pub trait Rebase {
+    fn rebase(&mut self, slide: i64);
+}
+
+impl Rebase for u64 {
+    fn rebase(&mut self, slide: i64) {
+        *self = self.wrapping_add_signed(slide);
+    }
+}
+
+impl<T: Rebase> Rebase for Option<T> {
+    fn rebase(&mut self, slide: i64) {
+        if let Some(value) = self {
+            value.rebase(slide);
+        }
+    }
+}
+
+impl<T: Rebase> Rebase for Vec<T> {
+    fn rebase(&mut self, slide: i64) {
+        for value in self {
+            value.rebase(slide);
+        }
+    }
+}
+
+impl<A: Rebase, B: Rebase> Rebase for (A, B) {
+    fn rebase(&mut self, slide: i64) {
+        self.0.rebase(slide);
+        self.1.rebase(slide);
+    }
+}
+
+impl Rebase for BTreeSet<u64> {
+    fn rebase(&mut self, slide: i64) {
+        *self = self.iter().map(|a| a.wrapping_add_signed(slide)).collect();
+    }
+}
+
+/// Maps from addresses to addresses, e.g. the exception edges of a Cfg or
+/// xrefs (target to the pcs referring to it).
+impl<V: Rebase> Rebase for BTreeMap<u64, V> {
+    fn rebase(&mut self, slide: i64) {
+        *self = std::mem::take(self).into_iter().map(|(addr, mut value)| {
+            value.rebase(slide);
+            (addr.wrapping_add_signed(slide), value)
+        }).collect();
+    }
+}
+
+/// The map with its keys moved by slide, for maps by address whose values
+/// aren't addresses: symbols, annotations.
+pub fn rebase_keys<V>(map: BTreeMap<u64, V>, slide: i64) -> BTreeMap<u64, V> {
+    map.into_iter().map(|(addr, value)| (addr.wrapping_add_signed(slide), value)).collect()
+}
+
+impl Rebase for Terminator {
+    fn rebase(&mut self, slide: i64) {
+        match self {
+            Terminator::FallThrough(a) | Terminator::Jump(a) | Terminator::TailCall(a) | Terminator::NoReturnCall(a) => {
+                a.rebase(slide)
+            }
+            Terminator::Branch { taken, fallthrough } => {
+                taken.rebase(slide);
+                fallthrough.rebase(slide);
+            }
+            Terminator::Switch { cases, default, .. } => {
+                // The case values aren't addresses.
+                for (_, target) in cases {
+                    target.rebase(slide);
+                }
+                default.rebase(slide);
+            }
+            Terminator::Return | Terminator::Indirect | Terminator::End => {}
+        }
+    }
+}
+
+impl Rebase for Block {
+    fn rebase(&mut self, slide: i64) {
+        self.start.rebase(slide);
+        self.end.rebase(slide);
+        self.terminator.rebase(slide);
+    }
+}
+
+impl Rebase for Cfg {
+    fn rebase(&mut self, slide: i64) {
+        self.blocks.rebase(slide);
+        self.exception_edges.rebase(slide);
+        self.indirect_targets.rebase(slide);
+    }
+}
+
+impl Rebase for Function {
+    fn rebase(&mut self, slide: i64) {
+        self.entry.rebase(slide);
+        self.blocks.rebase(slide);
+    }
+}
+
+impl Rebase for Callee {
+    fn rebase(&mut self, slide: i64) {
+        match self {
+            Callee::Function(entry) => entry.rebase(slide),
+            Callee::Import { stub, .. } => stub.rebase(slide),
+            Callee::Ifunc { stub, resolver, candidates } => {
+                stub.rebase(slide);
+                resolver.rebase(slide);
+                candidates.rebase(slide);
+            }
+            Callee::TlsDescriptor { slot, .. } => slot.rebase(slide),
+            Callee::Candidates(targets) => targets.rebase(slide),
+            Callee::Unknown => {}
+        }
+    }
+}
+
+impl Rebase for CallSite {
+    fn rebase(&mut self, slide: i64) {
+        self.pc.rebase(slide);
+        self.caller.rebase(slide);
+        self.callee.rebase(slide);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_relocate::target_address;
+
+    #[test]
+    fn rebase_program_and_cfg() {
+        let words = [
+            0x90000080, // 0x1000: adrp x0, 0x11000
+            0x54000040, //         b.eq 0x100c
+            0xD65F03C0, //         ret
+            0x97FFFFFD, // 0x100c: bl 0x1000
+        ];
+        let mut program = Program::from_words(&words, 0x1000);
+        let mut cfg = Cfg::build(&program);
+        let slide = slide_between(0x1000, 0xffff_8000_0010_1000).unwrap();
+        assert!(slide_between(0x1000, 0x1010).is_err());
+
+        rebase_program(&mut program, slide).unwrap();
+        let base = 0xffff_8000_0010_1000;
+        assert_eq!(target_address(program.get(base).unwrap(), base), Some(base + 0x10000));
+        assert_eq!(target_address(program.get(base + 12).unwrap(), base + 12), Some(base));
+
+        cfg.rebase(slide);
+        assert_eq!(cfg, Cfg::build(&program));
+        let symbols = rebase_keys(BTreeMap::from([(0x1000, "f".to_string())]), slide);
+        assert_eq!(symbols, BTreeMap::from([(base, "f".to_string())]));
+    }
+}
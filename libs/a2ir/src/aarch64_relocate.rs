@@ -0,0 +1,245 @@
+//! Relocation of instructions to a new address, for out-of-line execution and
+//! detour trampolines.
+//!
+//! Position-independent instructions are copied verbatim. PC-relative ones
+//! (ADR, ADRP, B, BL, B.cond, CBZ/CBNZ, TBZ/TBNZ and the literal loads) are
+//! re-encoded with an adjusted offset if the target is still in range, and
+//! otherwise expanded into a sequence that materializes the absolute address.
+
+use crate::aarch64_reader::{decode, fad_get_addrmode, fad_get_cond, fad_get_mem_extend, fad_get_prec, invert_cond, AddrMode, Cond, Inst, Op};
+use crate::aarch64_writer;
+
+/// Register clobbered by expanded sequences that need an address in a register
+/// but have none of their own: X16 (IP0), which the AAPCS64 reserves for
+/// veneers and may be corrupted by any branch anyway.
+pub const SCRATCH_REG: u8 = 16;
+
+/// Emits an absolute jump (or call, if link is set) to target via SCRATCH_REG.
+pub fn absolute_jump(target: u64, link: bool) -> Vec<u32> {
+    let mut out = aarch64_writer::mov_imm64(SCRATCH_REG, target);
+    out.push(if link { aarch64_writer::blr(SCRATCH_REG) } else { aarch64_writer::br(SCRATCH_REG) });
+    out
+}
+
+/// Returns whether the decoded instruction's behaviour depends on its address.
+pub fn is_pc_relative(inst: &Inst) -> bool {
+    match inst.op {
+        Op::A64_ADR | Op::A64_ADRP | Op::A64_B | Op::A64_BL | Op::A64_BCOND
+        | Op::A64_CBZ | Op::A64_CBNZ | Op::A64_TBZ | Op::A64_TBNZ => true,
+        Op::A64_LDR | Op::A64_LDR_FP | Op::A64_PRFM => fad_get_addrmode(inst.flags) == AddrMode::AM_LITERAL,
+        _ => false,
+    }
+}
+
+/// Absolute address referenced by a PC-relative instruction located at pc.
+pub fn target_address(inst: &Inst, pc: u64) -> Option<u64> {
+    if !is_pc_relative(inst) {
+        return None;
+    }
+    match inst.op {
+        Op::A64_ADRP => Some((pc & !0xFFF).wrapping_add(inst.offset as u64)),
+        Op::A64_TBZ | Op::A64_TBNZ => Some(pc.wrapping_add(inst.tbz.offset as i64 as u64)),
+        _ => Some(pc.wrapping_add(inst.offset as u64)),
+    }
+}
+
+/// Translates the instruction word binst, originally located at from, into an
+/// equivalent sequence of instruction words to be placed at to.
+pub fn relocate(binst: u32, from: u64, to: u64) -> Result<Vec<u32>, String> {
+    match target_address(&decode(binst), from) {
+        None => Ok(vec![binst]),
+        Some(target) => retarget(binst, target, to),
+    }
+}
+
+/// Encodes the PC-relative instruction binst, placed at to, so that it
+/// references target. For ADRP, target is the page address.
+pub fn retarget(binst: u32, target: u64, to: u64) -> Result<Vec<u32>, String> {
+    let inst = decode(binst);
+    if !is_pc_relative(&inst) {
+        return Err(format!("retarget: instruction {:#010x} is not PC-relative", binst));
+    }
+    let offset = target.wrapping_sub(to) as i64;
+
+    // Fast path: same instruction, new offset.
+    let mut moved = inst.clone();
+    match inst.op {
+        Op::A64_ADRP => moved.offset = target.wrapping_sub(to & !0xFFF) as i64,
+        Op::A64_TBZ | Op::A64_TBNZ => moved.tbz.offset = offset as i32,
+        _ => moved.offset = offset,
+    }
+    let in_range = match inst.op {
+        Op::A64_TBZ | Op::A64_TBNZ => offset as i32 as i64 == offset,
+        _ => true,
+    };
+    if in_range {
+        if let Ok(word) = aarch64_writer::encode(&moved) {
+            return Ok(vec![word]);
+        }
+    }
+
+    // Slow path: expand into an absolute sequence.
+    match inst.op {
+        Op::A64_ADR => {
+            let page_offset = (target & !0xFFF).wrapping_sub(to & !0xFFF) as i64;
+            if let Ok(adrp) = aarch64_writer::adrp(inst.rd, page_offset) {
+                return Ok(vec![adrp, aarch64_writer::add_imm(inst.rd, inst.rd, (target & 0xFFF) as u32)?]);
+            }
+            Ok(aarch64_writer::mov_imm64(inst.rd, target))
+        }
+        Op::A64_ADRP => Ok(aarch64_writer::mov_imm64(inst.rd, target)),
+        Op::A64_B => Ok(absolute_jump(target, false)),
+        Op::A64_BL => Ok(absolute_jump(target, true)),
+        Op::A64_BCOND | Op::A64_CBZ | Op::A64_CBNZ | Op::A64_TBZ | Op::A64_TBNZ => {
+            let cond = fad_get_cond(inst.flags);
+            if inst.op == Op::A64_BCOND && Cond::is_always(cond) {
+                return Ok(absolute_jump(target, false));
+            }
+
+            // Branch over the absolute jump if the inverted condition holds.
+            let jump = absolute_jump(target, false);
+            let skip = 4 * (jump.len() as i64 + 1);
+            let mut inverted = inst.clone();
+            match inst.op {
+                Op::A64_BCOND => inverted.flags = invert_cond(inst.flags),
+                Op::A64_CBZ => inverted.op = Op::A64_CBNZ,
+                Op::A64_CBNZ => inverted.op = Op::A64_CBZ,
+                Op::A64_TBZ => inverted.op = Op::A64_TBNZ,
+                _ => inverted.op = Op::A64_TBZ,
+            }
+            inverted.offset = skip;
+            inverted.tbz.offset = skip as i32;
+
+            let mut out = vec![aarch64_writer::encode(&inverted)?];
+            out.extend(jump);
+            Ok(out)
+        }
+        Op::A64_LDR => {
+            let memext = fad_get_mem_extend(inst.flags);
+            let mut out = aarch64_writer::mov_imm64(inst.rd, target);
+            out.push(aarch64_writer::ldr_base(memext, inst.rd, inst.rd)?);
+            Ok(out)
+        }
+        Op::A64_LDR_FP => {
+            let mut out = aarch64_writer::mov_imm64(SCRATCH_REG, target);
+            out.push(aarch64_writer::ldr_fp_base(fad_get_prec(inst.flags), inst.rd, SCRATCH_REG)?);
+            Ok(out)
+        }
+        // A prefetch is only a hint; dropping it is always correct.
+        Op::A64_PRFM => Ok(vec![aarch64_writer::NOP]),
+        _ => Err(format!("retarget: cannot retarget instruction {:#010x}", binst)),
+    }
+}
+
+/// Relocates a sequence of consecutive instruction words from from to to,
+/// returning the concatenated output.
+pub fn relocate_all(words: &[u32], from: u64, to: u64) -> Result<Vec<u32>, String> {
+    let mut out = Vec::new();
+    for (i, &binst) in words.iter().enumerate() {
+        let pc = from + 4 * i as u64;
+        let new_pc = to + 4 * out.len() as u64;
+        out.extend(relocate(binst, pc, new_pc)?);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_independent_is_copied() {
+        let add = 0x91000420; // add x0, x1, #1
+        assert_eq!(relocate(add, 0x1000, 0x7000_0000).unwrap(), vec![add]);
+    }
+
+    #[test]
+    fn branch_in_range_is_reencoded() {
+        let b = 0x14000004; // b #+0x10
+        assert_eq!(relocate(b, 0x1000, 0x2000).unwrap(), vec![0x17FFFC04]); // b #-0xff0
+    }
+
+    #[test]
+    fn adr_out_of_range_becomes_absolute() {
+        let adr = 0x10000080; // adr x0, #+0x10
+        let words = relocate(adr, 0x1_0000_1000, 0x1000).unwrap();
+        assert_eq!(words, vec![
+            0xD2820200, // movz x0, #0x1010
+            0xF2C00020, // movk x0, #0x1, lsl #32
+        ]);
+    }
+
+    #[test]
+    fn cbz_out_of_range_is_inverted() {
+        let cbz = 0xB4000040; // cbz x0, #+8
+        let words = relocate(cbz, 0x1_0000_0000, 0x1000).unwrap();
+        assert_eq!(words[0], 0xB5000080); // cbnz x0, #+16
+        assert_eq!(*words.last().unwrap(), 0xD61F0200); // br x16
+    }
+
+    // Relocations from above 4 GiB to 0x1000 put every target out of range.
+    const FAR: u64 = 0x1_0000_0000;
+
+    #[test]
+    fn conditional_branches_out_of_range() {
+        // b.ne .+8 becomes b.eq over movz x16, #8; movk x16, #0x1, lsl #32; br x16
+        assert_eq!(relocate(0x54000041, FAR, 0x1000).unwrap(), vec![0x54000080, 0xD2800110, 0xF2C00030, 0xD61F0200]);
+        // cbz x0, .+8 becomes cbnz x0, .+16 over the same jump
+        assert_eq!(relocate(0xB4000040, FAR, 0x1000).unwrap(), vec![0xB5000080, 0xD2800110, 0xF2C00030, 0xD61F0200]);
+        // tbz w3, #5, .+8 becomes tbnz w3, #5, .+16 over movz x16, #8; movk x16, #0x1, lsl #16; br x16
+        assert_eq!(relocate(0x36280043, 0x10000, 0x1000_0000).unwrap(), vec![0x37280083, 0xD2800110, 0xF2A00030, 0xD61F0200]);
+        // b.al .+8 needs no condition
+        assert_eq!(relocate(0x5400004E, FAR, 0x1000).unwrap(), vec![0xD2800110, 0xF2C00030, 0xD61F0200]);
+
+        // In range: b.ne .-0xff8 and tbz w3, #5, .-0x7ff8
+        assert_eq!(relocate(0x54000041, 0x1000, 0x2000).unwrap(), vec![0x54FF8041]);
+        assert_eq!(relocate(0x36280043, 0x10000, 0x18000).unwrap(), vec![0x362C0043]);
+    }
+
+    #[test]
+    fn branches_and_calls_out_of_range() {
+        // b .+8 and bl .+8 become a br and a blr through X16
+        assert_eq!(relocate(0x14000002, FAR, 0x1000).unwrap(), vec![0xD2800110, 0xF2C00030, 0xD61F0200]);
+        assert_eq!(relocate(0x94000002, FAR, 0x1000).unwrap(), vec![0xD2800110, 0xF2C00030, 0xD63F0200]);
+        assert_eq!(relocate(0x94000002, 0x1000, 0x2000).unwrap(), vec![0x97FFFC02]); // bl .-0xff8
+        assert_eq!(absolute_jump(0x1234, true), vec![0xD2824690, 0xD63F0200]); // movz x16, #0x1234; blr x16
+    }
+
+    #[test]
+    fn addresses_out_of_range() {
+        // adr x0, .+0x10, moved by 2 MiB, becomes adrp x0, .+0x1ff000; add x0, x0, #0x10
+        assert_eq!(relocate(0x10000080, 0x20_0000, 0x1000).unwrap(), vec![0xF0000FE0, 0x91004000]);
+        // adrp x1, .+0x1000 keeps its page: adrp x1, .-0xfa000
+        assert_eq!(relocate(0xB0000001, 0x5000, 0x10_0000).unwrap(), vec![0xD0FFF821]);
+        // ... or, 4 GiB away, becomes movz x1, #0x1000; movk x1, #0x1, lsl #32
+        assert_eq!(relocate(0xB0000001, FAR, 0x1000).unwrap(), vec![0xD2820001, 0xF2C00021]);
+    }
+
+    #[test]
+    fn literal_loads_out_of_range() {
+        // ldr x2, .+8 becomes movz x2, #8; movk x2, #0x1, lsl #32; ldr x2, [x2]
+        assert_eq!(relocate(0x58000042, FAR, 0x1000).unwrap(), vec![0xD2800102, 0xF2C00022, 0xF9400042]);
+        // ldrsw x3, .+8, with ldrsw x3, [x3]
+        assert_eq!(relocate(0x98000043, FAR, 0x1000).unwrap(), vec![0xD2800103, 0xF2C00023, 0xB9800063]);
+        // prfm pldl1keep, .+8 is dropped
+        assert_eq!(relocate(0xD8000040, FAR, 0x1000).unwrap(), vec![aarch64_writer::NOP]);
+        // ldr q0, .+8 goes through X16: ldr q0, [x16]
+        if cfg!(feature = "simd") {
+            assert_eq!(relocate(0x9C000040, FAR, 0x1000).unwrap(), vec![0xD2800110, 0xF2C00030, 0x3DC00200]);
+        }
+        assert_eq!(relocate(0x58000042, 0x1000, 0x1100).unwrap(), vec![0x58FFF842]); // ldr x2, .-0xf8
+    }
+
+    #[test]
+    fn sequences_and_targets() {
+        // cbz x0, .+8; b .+8: the b moves to 0x1010, after the expanded cbz.
+        assert_eq!(relocate_all(&[0xB4000040, 0x14000002], FAR, 0x1000).unwrap(), vec![
+            0xB5000080, 0xD2800110, 0xF2C00030, 0xD61F0200,
+            0xD2800190, 0xF2C00030, 0xD61F0200, // movz x16, #0xc; movk x16, #0x1, lsl #32; br x16
+        ]);
+        assert!(retarget(0x91000420, 0x2000, 0x1000).is_err()); // add x0, x1, #1
+        assert_eq!(target_address(&decode(0xB0000001), 0x5678), Some(0x6000)); // adrp x1, .+0x1000
+        assert_eq!(target_address(&decode(0x36280043), 0x1000), Some(0x1008)); // tbz w3, #5, .+8
+        assert_eq!(target_address(&decode(0xF9400042), 0x1000), None); // ldr x2, [x2]
+    }
+}
@@ -0,0 +1,187 @@
+//! Binary rewriting: insert, delete and replace instructions of a code region,
+//! then lay the code out again with all PC-relative references fixed up.
+//!
+//! Every edit is attached to an instruction of the original program. Inserted
+//! and replacement words are interpreted as if they were located at the address
+//! of that instruction, so their PC-relative targets can refer to the original
+//! code and are fixed up like everything else. References to the original code
+//! are re-targeted to the new location of the referenced instruction (to code
+//! inserted before it, if any; to the next surviving instruction if deleted).
+//! References leaving the region keep their absolute target.
+//!
+//! Branches that end up out of range are expanded into veneers (see
+//! aarch64_relocate::retarget), which may in turn push other code further
+//! away; layout is repeated until it is stable. ADRP is always treated as an
+//! external reference, since its page can't be related to single instructions.
+
+use std::collections::BTreeMap;
+
+use crate::aarch64_reader::{decode, Op};
+use crate::aarch64_relocate::{retarget, target_address};
+use crate::aarch64_writer::NOP;
+
+struct Slot {
+    original: u32,
+    before: Vec<u32>,
+    body: Vec<u32>,
+    after: Vec<u32>,
+}
+
+pub struct Rewriter {
+    base: u64,
+    slots: Vec<Slot>,
+}
+
+/// Result of a rewrite: the new code and where every original instruction went.
+pub struct Rewritten {
+    pub base: u64,
+    pub words: Vec<u32>,
+    /// Original address → new address of every original instruction (of the
+    /// code inserted before it, or of its successor if it was deleted).
+    pub address_map: BTreeMap<u64, u64>,
+}
+
+impl Rewritten {
+    pub fn bytes(&self) -> Vec<u8> {
+        self.words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+}
+
+impl Rewriter {
+    /// Starts a rewrite of the instruction words located at base.
+    pub fn new(words: &[u32], base: u64) -> Rewriter {
+        Rewriter {
+            base,
+            slots: words.iter().map(|&w| Slot { original: w, before: vec![], body: vec![w], after: vec![] }).collect(),
+        }
+    }
+
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Number of original instructions.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// The original instruction word at pc.
+    pub fn original(&self, pc: u64) -> Result<u32, String> {
+        Ok(self.slots[self.index(pc)?].original)
+    }
+
+    fn index(&self, pc: u64) -> Result<usize, String> {
+        let end = self.base + 4 * self.slots.len() as u64;
+        if pc < self.base || pc >= end || !pc.is_multiple_of(4) {
+            return Err(format!("rewriter: {:#x} is not an instruction of the region", pc));
+        }
+        Ok(((pc - self.base) / 4) as usize)
+    }
+
+    /// Inserts words before the instruction at pc. Branches to pc will execute them.
+    pub fn insert_before(&mut self, pc: u64, words: &[u32]) -> Result<(), String> {
+        let i = self.index(pc)?;
+        self.slots[i].before.extend_from_slice(words);
+        Ok(())
+    }
+
+    /// Inserts words after the instruction at pc.
+    pub fn insert_after(&mut self, pc: u64, words: &[u32]) -> Result<(), String> {
+        let i = self.index(pc)?;
+        self.slots[i].after.extend_from_slice(words);
+        Ok(())
+    }
+
+    /// Replaces the instruction at pc by words (which may be empty).
+    pub fn replace(&mut self, pc: u64, words: &[u32]) -> Result<(), String> {
+        let i = self.index(pc)?;
+        self.slots[i].body = words.to_vec();
+        Ok(())
+    }
+
+    pub fn delete(&mut self, pc: u64) -> Result<(), String> {
+        self.replace(pc, &[])
+    }
+
+    /// Lays out the rewritten code at new_base.
+    pub fn finish(&self, new_base: u64) -> Result<Rewritten, String> {
+        // Flatten into (context pc, word) items; anchors[i] is the item that
+        // references to original instruction i resolve to.
+        let mut items: Vec<(u64, u32)> = Vec::new();
+        let mut anchors = Vec::with_capacity(self.slots.len());
+        for (i, slot) in self.slots.iter().enumerate() {
+            let pc = self.base + 4 * i as u64;
+            anchors.push(items.len());
+            for &w in slot.before.iter().chain(&slot.body).chain(&slot.after) {
+                items.push((pc, w));
+            }
+        }
+
+        let end = self.base + 4 * self.slots.len() as u64;
+        let internal = |target: u64| target >= self.base && target < end && target.is_multiple_of(4);
+
+        // Sizes (in words) only ever grow; shorter encodings are NOP-padded,
+        // which guarantees termination.
+        let mut sizes = vec![1usize; items.len()];
+        loop {
+            let mut addrs = Vec::with_capacity(items.len() + 1);
+            let mut addr = new_base;
+            for &size in &sizes {
+                addrs.push(addr);
+                addr += 4 * size as u64;
+            }
+            addrs.push(addr);
+            let new_addr = |target: u64| addrs[anchors[((target - self.base) / 4) as usize]];
+
+            let mut words = Vec::with_capacity(items.len());
+            let mut changed = false;
+            for (k, &(pc, w)) in items.iter().enumerate() {
+                let inst = decode(w);
+                let mut enc = match target_address(&inst, pc) {
+                    None => vec![w],
+                    Some(target) if inst.op != Op::A64_ADRP && internal(target) => {
+                        retarget(w, new_addr(target), addrs[k])?
+                    }
+                    Some(target) => retarget(w, target, addrs[k])?,
+                };
+                if enc.len() > sizes[k] {
+                    sizes[k] = enc.len();
+                    changed = true;
+                }
+                enc.resize(sizes[k], NOP);
+                words.extend(enc);
+            }
+
+            if !changed {
+                let address_map = (0..self.slots.len())
+                    .map(|i| (self.base + 4 * i as u64, addrs[anchors[i]]))
+                    .collect();
+                return Ok(Rewritten { base: new_base, words, address_map });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insertion_moves_branch_target() {
+        let words = [
+            0x14000002, // b #+8
+            0xD503201F, // nop
+            0xD65F03C0, // ret
+        ];
+        let mut rw = Rewriter::new(&words, 0x1000);
+        rw.insert_before(0x1008, &[0xD2800000]).unwrap(); // mov x0, #0
+        rw.delete(0x1004).unwrap();
+        let out = rw.finish(0x1000).unwrap();
+        assert_eq!(out.words, vec![0x14000001, 0xD2800000, 0xD65F03C0]);
+        assert_eq!(out.address_map[&0x1008], 0x1004);
+    }
+}
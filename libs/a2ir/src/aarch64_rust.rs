@@ -0,0 +1,260 @@
+//! Rust idioms: the calls into the panic machinery, the allocator shims and
+//! drop glue, and the bounds checks (and unwraps) that branch to a panic,
+//! so that listings can name them and later consumers fold them away.
+//!
+//! ```text
+//! cmp  x1, #4
+//! b.hs 1f                  // bounds check, fails to 1f
+//! ...
+//! bl   drop_in_place<String>
+//! ...
+//! 1:   bl   core::panicking::panic_bounds_check
+//! ```
+//!
+//! The runtime functions are recognized by name, demangled or in the
+//! legacy `_ZN` mangling (v0 `_R` names aren't decoded), which Rust
+//! binaries keep in .symtab unless stripped. A check is a conditional
+//! branch one of whose successors is a block calling a panic function.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::aarch64_cfg::{Cfg, Terminator};
+use crate::aarch64_defuse::{def_use, Loc};
+use crate::aarch64_functions::Function;
+use crate::aarch64_listing::Annotate;
+use crate::aarch64_program::{Program, INST_SIZE};
+use crate::aarch64_reader::{Inst, Op};
+use crate::aarch64_relocate::target_address;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Idiom {
+    /// core::panicking::panic and panic_fmt, std's begin_panic, rust_panic
+    Panic,
+    /// panic_bounds_check and the slice and str index failures
+    BoundsCheck,
+    /// Option and Result unwrap_failed, expect_failed
+    Unwrap,
+    /// The panic_const_* panics of overflow checks and division by zero
+    Arithmetic,
+    /// handle_alloc_error, capacity_overflow
+    AllocError,
+    /// __rust_alloc, __rust_alloc_zeroed, __rust_realloc
+    Alloc,
+    Dealloc,
+    /// core::ptr::drop_in_place<T>
+    Drop,
+}
+
+impl Idiom {
+    pub fn name(self) -> &'static str {
+        match self {
+            Idiom::Panic => "panic",
+            Idiom::BoundsCheck => "bounds check",
+            Idiom::Unwrap => "unwrap",
+            Idiom::Arithmetic => "arithmetic check",
+            Idiom::AllocError => "allocation failure",
+            Idiom::Alloc => "alloc",
+            Idiom::Dealloc => "dealloc",
+            Idiom::Drop => "drop",
+        }
+    }
+
+    /// Calls to the function never return.
+    pub fn is_panic(self) -> bool {
+        matches!(self, Idiom::Panic | Idiom::BoundsCheck | Idiom::Unwrap | Idiom::Arithmetic | Idiom::AllocError)
+    }
+}
+
+/// The path of a symbol, e.g. ["core", "panicking", "panic"], without the
+/// generic arguments and the trailing hash.
+fn path(name: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    if let Some(mut rest) = name.strip_prefix("_ZN") {
+        while let Some(digits) = rest.find(|c: char| !c.is_ascii_digit()).filter(|&d| d > 0) {
+            let Ok(len) = rest[..digits].parse::<usize>() else { break };
+            let Some(ident) = rest.get(digits..digits + len) else { break };
+            out.push(ident.to_string());
+            rest = &rest[digits + len..];
+        }
+    } else {
+        let mut depth = 0;
+        let plain: String = name.chars().filter(|&c| {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => return depth == 0,
+            }
+            false
+        }).collect();
+        out = plain.split("::").map(str::to_string).collect();
+    }
+    // $LT$ starts the generic arguments of legacy mangled names.
+    for segment in &mut out {
+        segment.truncate(segment.find('$').unwrap_or(segment.len()));
+    }
+    let hash = |s: &String| s.len() == 17 && s.starts_with('h') && s[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if out.len() > 1 && out.last().is_some_and(hash) {
+        out.pop();
+    }
+    out
+}
+
+/// The idiom of the function of that name, if it is one of the runtime's.
+pub fn classify(name: &str) -> Option<Idiom> {
+    let path = path(name);
+    let last = path.last()?.as_str();
+    let within = |module: &str| path.iter().any(|s| s == module);
+    Some(match last {
+        "__rust_alloc" | "__rust_alloc_zeroed" | "__rust_realloc" => Idiom::Alloc,
+        "__rust_dealloc" => Idiom::Dealloc,
+        "drop_in_place" => Idiom::Drop,
+        "panic_bounds_check" | "slice_start_index_len_fail" | "slice_end_index_len_fail" | "slice_index_order_fail"
+        | "str_index_overflow_fail" | "slice_error_fail" => Idiom::BoundsCheck,
+        "unwrap_failed" | "expect_failed" => Idiom::Unwrap,
+        _ if last.starts_with("panic_const_") => Idiom::Arithmetic,
+        "handle_alloc_error" | "capacity_overflow" => Idiom::AllocError,
+        "handle_error" if within("raw_vec") => Idiom::AllocError,
+        "rust_panic" | "begin_panic" => Idiom::Panic,
+        "panic" | "panic_fmt" | "panic_display" | "panic_str" | "panic_nounwind" | "panic_explicit" if within("panicking") => Idiom::Panic,
+        _ => return None,
+    })
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RustSymbols {
+    /// The runtime functions by entry.
+    pub idioms: BTreeMap<u64, Idiom>,
+}
+
+impl RustSymbols {
+    /// From symbols by address.
+    pub fn new(names: &BTreeMap<u64, String>) -> RustSymbols {
+        RustSymbols { idioms: names.iter().filter_map(|(a, n)| Some((*a, classify(n)?))).collect() }
+    }
+
+    /// The entries of the panic functions, to seed `NoReturn` with.
+    pub fn panics(&self) -> BTreeSet<u64> {
+        self.idioms.iter().filter(|(_, i)| i.is_panic()).map(|(a, _)| *a).collect()
+    }
+}
+
+/// A conditional branch to a block that panics.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Check {
+    /// The instruction setting the flags B.cond tests; None for CBZ, CBNZ,
+    /// TBZ and TBNZ, which compare themselves.
+    pub compare: Option<u64>,
+    pub branch: u64,
+    /// The block that panics.
+    pub fail: u64,
+    pub idiom: Idiom,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RustIdioms {
+    /// Calls (and tail calls) to the runtime functions, by pc.
+    pub calls: BTreeMap<u64, Idiom>,
+    pub checks: Vec<Check>,
+}
+
+/// The panic the block at start calls, if it does.
+fn fails(program: &Program, cfg: &Cfg, calls: &BTreeMap<u64, Idiom>, start: u64) -> Option<Idiom> {
+    let block = cfg.blocks.get(&start)?;
+    program.range(start..block.end).find_map(|(pc, _)| calls.get(&pc).copied().filter(|i| i.is_panic()))
+}
+
+impl RustIdioms {
+    pub fn find(program: &Program, cfg: &Cfg, functions: &[Function], symbols: &RustSymbols) -> RustIdioms {
+        let blocks: BTreeSet<u64> = functions.iter().flat_map(|f| f.blocks.iter().copied()).collect();
+        let mut calls = BTreeMap::new();
+        for &start in &blocks {
+            for (pc, inst) in program.range(start..cfg.blocks[&start].end) {
+                if matches!(inst.op, Op::A64_BL | Op::A64_B) {
+                    if let Some(&idiom) = target_address(inst, pc).and_then(|t| symbols.idioms.get(&t)) {
+                        calls.insert(pc, idiom);
+                    }
+                }
+            }
+        }
+
+        let mut checks = Vec::new();
+        for &start in &blocks {
+            let block = &cfg.blocks[&start];
+            let Terminator::Branch { taken, fallthrough } = block.terminator else { continue };
+            let Some((fail, idiom)) = [taken, fallthrough].into_iter().find_map(|b| Some((b, fails(program, cfg, &calls, b)?))) else {
+                continue;
+            };
+            let branch = block.end - INST_SIZE;
+            let compare = match program.get(branch).map(|i| i.op) {
+                Some(Op::A64_BCOND) => {
+                    program.range(start..branch).rev().find(|(_, i)| def_use(i).defs.contains(&Loc::NZCV)).map(|(pc, _)| pc)
+                }
+                _ => None,
+            };
+            checks.push(Check { compare, branch, fail, idiom });
+        }
+        RustIdioms { calls, checks }
+    }
+
+    /// What a decompiler may fold into the surrounding code: the compares
+    /// and branches of the checks and the blocks they fail to.
+    pub fn foldable(&self, cfg: &Cfg) -> BTreeSet<u64> {
+        let mut out = BTreeSet::new();
+        for check in &self.checks {
+            out.extend(check.compare);
+            out.insert(check.branch);
+            if let Some(block) = cfg.blocks.get(&check.fail) {
+                out.extend((block.start..block.end).step_by(INST_SIZE as usize));
+            }
+        }
+        out
+    }
+}
+
+/// Marks the calls and checks in listings.
+impl Annotate for RustIdioms {
+    fn comment(&self, addr: u64, _inst: &Inst) -> Option<String> {
+        if let Some(idiom) = self.calls.get(&addr) {
+            return Some(format!("rust: {}", idiom.name()));
+        }
+        let check = self.checks.iter().find(|c| c.branch == addr || c.compare == Some(addr))?;
+        Some(format!("rust: {}, fails to {:#x}", check.idiom.name(), check.fail))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_functions::functions;
+
+    #[test]
+    fn bounds_check_and_drop() {
+        let program = Program::from_words(&[
+            0xF100103F, // 0x1000: cmp x1, #4
+            0x54000062, //         b.hs 0x1010
+            0x94000006, //         bl 0x1020
+            0xD65F03C0, //         ret
+            0x94000005, // 0x1010: bl 0x1024
+            0xD4200020, //         brk #1
+            0xD503201F, //         nop
+            0xD503201F, //         nop
+            0xD65F03C0, // 0x1020: drop_in_place<String>
+            0xD65F03C0, // 0x1024: panic_bounds_check
+        ], 0x1000);
+        let names = BTreeMap::from([
+            (0x1020, "core::ptr::drop_in_place<alloc::string::String>".to_string()),
+            (0x1024, "_ZN4core9panicking18panic_bounds_check17h0123456789abcdefE".to_string()),
+        ]);
+        let symbols = RustSymbols::new(&names);
+        assert_eq!(symbols.idioms, BTreeMap::from([(0x1020, Idiom::Drop), (0x1024, Idiom::BoundsCheck)]));
+        assert_eq!(classify("core::panicking::panic_const::panic_const_add_overflow"), Some(Idiom::Arithmetic));
+
+        let mut cfg = Cfg::build(&program);
+        let functions = functions(&mut cfg, &BTreeSet::from([0x1000, 0x1020, 0x1024]));
+        let idioms = RustIdioms::find(&program, &cfg, &functions, &symbols);
+        assert_eq!(idioms.calls, BTreeMap::from([(0x1008, Idiom::Drop), (0x1010, Idiom::BoundsCheck)]));
+        assert_eq!(idioms.checks, vec![Check { compare: Some(0x1000), branch: 0x1004, fail: 0x1010, idiom: Idiom::BoundsCheck }]);
+        assert!(idioms.foldable(&cfg).contains(&0x1010) && !idioms.foldable(&cfg).contains(&0x1008));
+        assert_eq!(idioms.comment(0x1004, program.get(0x1004).unwrap()), Some("rust: bounds check, fails to 0x1010".to_string()));
+    }
+}
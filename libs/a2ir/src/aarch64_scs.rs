@@ -0,0 +1,84 @@
+//! Shadow call stack (SCS) instrumentation, as emitted by Clang and GCC with
+//! -fsanitize=shadow-call-stack: functions push the return address to a
+//! second stack addressed by X18, the platform register, and pop it again
+//! before returning.
+//!
+//! ```text
+//! str x30, [x18], #8     // push, in the prologue
+//! ...
+//! ldr x30, [x18, #-8]!   // pop, in the epilogue
+//! ```
+//!
+//! Where the platform reserves X18 for this, the register isn't a general
+//! purpose one; see DefUse::without_reserved.
+
+use crate::aarch64_defuse::Loc;
+use crate::aarch64_program::Program;
+use crate::aarch64_reader::FlagMasks::W32;
+use crate::aarch64_reader::{fad_get_addrmode, AddrMode, Inst, Op};
+
+/// X18, which holds the shadow call stack pointer.
+pub const SCS_REGISTER: Loc = Loc::X(18);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScsOp {
+    Push,
+    Pop,
+}
+
+impl Inst {
+    /// Whether the instruction is a shadow call stack push or pop of X30.
+    pub fn scs_op(&self) -> Option<ScsOp> {
+        if self.rd != 30 || self.rn != 18 || self.flags & W32 != 0 {
+            return None;
+        }
+        match (self.op, fad_get_addrmode(self.flags), self.offset) {
+            (Op::A64_STR, AddrMode::AM_POST, 8) => Some(ScsOp::Push),
+            (Op::A64_LDR, AddrMode::AM_PRE, -8) => Some(ScsOp::Pop),
+            _ => None,
+        }
+    }
+}
+
+/// Pushes and pops of a function's shadow call stack.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScsSites {
+    pub pushes: Vec<u64>,
+    pub pops: Vec<u64>,
+}
+
+impl ScsSites {
+    /// Whether the function uses the shadow call stack.
+    pub fn is_protected(&self) -> bool {
+        !self.pushes.is_empty()
+    }
+}
+
+/// The SCS pushes and pops among the instructions in [entry, end).
+pub fn scs_sites(program: &Program, entry: u64, end: u64) -> ScsSites {
+    let mut out = ScsSites::default();
+    for (pc, inst) in program.range(entry..end) {
+        match inst.scs_op() {
+            Some(ScsOp::Push) => out.pushes.push(pc),
+            Some(ScsOp::Pop) => out.pops.push(pc),
+            None => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_defuse::def_use;
+
+    #[test]
+    fn push_and_pop() {
+        // str x30, [x18], #8; ldr x30, [x18, #-8]!; ret
+        let program = Program::from_words(&[0xF800865E, 0xF85F8E5E, 0xD65F03C0], 0);
+        let sites = scs_sites(&program, 0, 12);
+        assert_eq!(sites, ScsSites { pushes: vec![0], pops: vec![4] });
+        let du = def_use(program.get(0).unwrap()).without_reserved(&[SCS_REGISTER]);
+        assert_eq!(du.uses, vec![Loc::X(30)]);
+    }
+}
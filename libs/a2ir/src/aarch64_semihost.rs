@@ -0,0 +1,122 @@
+//! Arm semihosting: the call sites of the AArch64 interface (HLT #0xF000,
+//! with the operation in W0 and the parameter block, or the parameter, in
+//! X1) and the operations they request.
+//!
+//! ```text
+//! mov w0, #0x04          // SYS_WRITE0
+//! adr x1, message
+//! hlt #0xf000
+//! ```
+
+use crate::aarch64_constprop::{propagate, ConstOptions};
+use crate::aarch64_reader::{Inst, Op};
+
+/// The HLT immediate of semihosting calls on AArch64.
+pub const SEMIHOSTING_HLT: u16 = 0xF000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SemihostOp {
+    Open = 0x01,
+    Close = 0x02,
+    WriteC = 0x03,
+    Write0 = 0x04,
+    Write = 0x05,
+    Read = 0x06,
+    ReadC = 0x07,
+    IsError = 0x08,
+    IsTty = 0x09,
+    Seek = 0x0A,
+    FLen = 0x0C,
+    TmpNam = 0x0D,
+    Remove = 0x0E,
+    Rename = 0x0F,
+    Clock = 0x10,
+    Time = 0x11,
+    System = 0x12,
+    Errno = 0x13,
+    GetCmdline = 0x15,
+    HeapInfo = 0x16,
+    Exit = 0x18,
+    ExitExtended = 0x20,
+    Elapsed = 0x30,
+    TickFreq = 0x31,
+}
+
+const ALL_OPS: [SemihostOp; 24] = {
+    use SemihostOp::*;
+    [
+        Open, Close, WriteC, Write0, Write, Read, ReadC, IsError, IsTty, Seek, FLen, TmpNam, Remove, Rename, Clock, Time,
+        System, Errno, GetCmdline, HeapInfo, Exit, ExitExtended, Elapsed, TickFreq,
+    ]
+};
+
+impl SemihostOp {
+    pub fn from_u64(n: u64) -> Option<SemihostOp> {
+        ALL_OPS.into_iter().find(|&op| op as u64 == n)
+    }
+
+    /// Words (of 64 bits) in the parameter block X1 points to; None if X1 is
+    /// the parameter itself or unused.
+    pub fn param_words(self) -> Option<usize> {
+        use SemihostOp::*;
+        match self {
+            Close | IsTty | FLen | IsError | HeapInfo | Elapsed => Some(1),
+            Seek | Remove | System | GetCmdline | Exit | ExitExtended => Some(2),
+            Open | Write | Read | TmpNam => Some(3),
+            Rename => Some(4),
+            // X1 points to the character or string, or is unused.
+            WriteC | Write0 | ReadC | Clock | Time | Errno | TickFreq => None,
+        }
+    }
+}
+
+impl Inst {
+    pub fn is_semihosting(&self) -> bool {
+        self.op == Op::A64_HLT && self.imm == SEMIHOSTING_HLT as u64
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SemihostCall {
+    pub pc: u64,
+    /// None if W0 isn't known or isn't an operation.
+    pub op: Option<SemihostOp>,
+    /// X1, if known.
+    pub param: Option<u64>,
+}
+
+/// The semihosting calls of straight-line code, with W0 and X1 recovered
+/// by constant propagation.
+pub fn semihosting_calls(block: &[(u64, Inst)]) -> Vec<SemihostCall> {
+    let resolved = propagate(block, &ConstOptions::default());
+    block
+        .iter()
+        .filter(|(_, inst)| inst.is_semihosting())
+        .map(|&(pc, _)| {
+            let [x0, x1] = resolved.trap_args.get(&pc).copied().unwrap_or([None, None]);
+            SemihostCall { pc, op: x0.and_then(|n| SemihostOp::from_u64(n & 0xFFFF_FFFF)), param: x1 }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_buffer::decode_words;
+
+    #[test]
+    fn write0_and_exit() {
+        let block = decode_words(&[
+            0x52800080, // mov w0, #4
+            0x10000101, // adr x1, .+0x20
+            0xD45E0000, // hlt #0xf000
+            0x52800300, // mov w0, #0x18
+            0xD45E0000, // hlt #0xf000
+        ], 0x1000);
+        let calls = semihosting_calls(&block);
+        assert_eq!(calls, vec![
+            SemihostCall { pc: 0x1008, op: Some(SemihostOp::Write0), param: Some(0x1024) },
+            SemihostCall { pc: 0x1010, op: Some(SemihostOp::Exit), param: Some(0x1024) },
+        ]);
+    }
+}
@@ -0,0 +1,71 @@
+//! Sharing decoded programs and their analyses between threads.
+//!
+//! `Program`, `Cfg`, `CallGraph` and the other results hold plain owned
+//! data, and reading them takes `&self` without interior mutability, so
+//! they are `Send + Sync` and one copy serves any number of threads: borrow
+//! it in `std::thread::scope`, or put it in an `Arc` for threads that
+//! outlive the caller.
+//!
+//! ```text
+//! let program = Arc::new(Program::from_buffer(buffer));
+//! let worker = Arc::clone(&program);
+//! thread::spawn(move || Cfg::build(&worker));
+//! ```
+//!
+//! Mutation (`Cfg::split_at`, `CallGraph::mark_tls_calls`, ...) needs the
+//! only reference; analyses that refine a shared result work on a clone of
+//! the part they change.
+
+use std::thread;
+
+use crate::aarch64_callgraph::CallGraph;
+use crate::aarch64_cfg::Cfg;
+use crate::aarch64_functions::Function;
+use crate::aarch64_program::Program;
+
+const _: () = {
+    const fn shared<T: Send + Sync>() {}
+    shared::<Program>();
+    shared::<Cfg>();
+    shared::<CallGraph>();
+    shared::<Function>();
+};
+
+/// f applied to every function, as (entry, end) ranges, on up to threads
+/// threads; the results are in the order of functions.
+pub fn map_functions<T, F>(program: &Program, functions: &[(u64, u64)], threads: usize, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&Program, u64, u64) -> T + Sync,
+{
+    if functions.is_empty() {
+        return Vec::new();
+    }
+    let chunk = functions.len().div_ceil(threads.max(1));
+    let f = &f;
+    thread::scope(|scope| {
+        let workers: Vec<_> = functions
+            .chunks(chunk)
+            .map(|part| scope.spawn(move || part.iter().map(|&(entry, end)| f(program, entry, end)).collect::<Vec<T>>()))
+            .collect();
+        workers.into_iter().flat_map(|w| w.join().expect("analysis thread panicked")).collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn shared_program() {
+        let program = Arc::new(Program::from_words(&[0xD503201F, 0xD65F03C0, 0xD65F03C0], 0x1000));
+        let functions = [(0x1000, 0x1008), (0x1008, 0x100C)];
+        let sizes = map_functions(&program, &functions, 2, |p, entry, end| p.range(entry..end).count());
+        assert_eq!(sizes, vec![2, 1]);
+
+        let worker = Arc::clone(&program);
+        let blocks = thread::spawn(move || Cfg::build(&worker).blocks.len()).join().unwrap();
+        assert_eq!(blocks, 2);
+    }
+}
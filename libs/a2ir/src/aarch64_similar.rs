@@ -0,0 +1,155 @@
+//! Function fingerprints and the clustering of near-duplicate functions
+//! (template instantiations, inlined or outlined copies) of a binary.
+//!
+//! A fingerprint is the set of opcode trigrams of the function, with the
+//! registers, immediates and addresses left out, so copies that differ only
+//! in register allocation or in what they reference match exactly. The
+//! similarity of two functions is the Jaccard index of their trigram sets.
+
+use std::collections::BTreeMap;
+
+use crate::aarch64_program::Program;
+use crate::aarch64_reader::FlagMasks::W32;
+use crate::aarch64_reader::Inst;
+
+/// Functions shorter than this have too few trigrams to compare.
+pub const MIN_INSTS: usize = 4;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fingerprint {
+    pub entry: u64,
+    pub insts: usize,
+    /// Sorted and deduplicated.
+    pub grams: Vec<u64>,
+}
+
+/// The opcode and operation size, which is all of an instruction a
+/// fingerprint keeps.
+fn shape(inst: &Inst) -> u64 {
+    ((inst.op as u64) << 1) | (inst.flags & W32) as u64
+}
+
+/// The fingerprint of the function at [entry, end).
+pub fn fingerprint(program: &Program, entry: u64, end: u64) -> Fingerprint {
+    let shapes: Vec<u64> = program.range(entry..end).map(|(_, inst)| shape(inst)).collect();
+    let mut grams: Vec<u64> = shapes.windows(3).map(|w| (w[0] << 42) | (w[1] << 21) | w[2]).collect();
+    grams.sort_unstable();
+    grams.dedup();
+    Fingerprint { entry, insts: shapes.len(), grams }
+}
+
+impl Fingerprint {
+    /// Jaccard index of the trigram sets, in [0, 1].
+    pub fn similarity(&self, other: &Fingerprint) -> f32 {
+        if self.grams.is_empty() && other.grams.is_empty() {
+            return 1.0;
+        }
+        let (mut i, mut j, mut common) = (0, 0, 0);
+        while i < self.grams.len() && j < other.grams.len() {
+            match self.grams[i].cmp(&other.grams[j]) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    common += 1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        common as f32 / (self.grams.len() + other.grams.len() - common) as f32
+    }
+}
+
+/// Pairs of functions (by entry) at least threshold similar, with their
+/// similarity. Pairs whose sizes alone rule it out aren't compared.
+pub fn similar_pairs(prints: &[Fingerprint], threshold: f32) -> Vec<(u64, u64, f32)> {
+    let mut sorted: Vec<&Fingerprint> = prints.iter().filter(|p| p.insts >= MIN_INSTS).collect();
+    sorted.sort_by_key(|p| p.grams.len());
+    let mut out = Vec::new();
+    for (i, a) in sorted.iter().enumerate() {
+        for b in &sorted[i + 1..] {
+            // The index is at most |a| / |b| when |a| <= |b|.
+            if (a.grams.len() as f32) < threshold * b.grams.len() as f32 {
+                break;
+            }
+            let score = a.similarity(b);
+            if score >= threshold {
+                out.push((a.entry.min(b.entry), a.entry.max(b.entry), score));
+            }
+        }
+    }
+    out
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cluster {
+    /// Entries of the functions, in address order.
+    pub members: Vec<u64>,
+    /// Lowest similarity of the pairs that joined the cluster.
+    pub min_similarity: f32,
+    /// Instructions in all the members.
+    pub insts: usize,
+}
+
+fn find(parent: &mut BTreeMap<u64, u64>, x: u64) -> u64 {
+    let p = parent[&x];
+    if p == x {
+        return x;
+    }
+    let root = find(parent, p);
+    parent.insert(x, root);
+    root
+}
+
+/// Groups of functions linked by pairs at least threshold similar, largest
+/// (by total instructions) first. Functions without a similar one are left
+/// out.
+pub fn clusters(prints: &[Fingerprint], threshold: f32) -> Vec<Cluster> {
+    let mut parent: BTreeMap<u64, u64> = prints.iter().map(|p| (p.entry, p.entry)).collect();
+    let pairs = similar_pairs(prints, threshold);
+    for &(a, b, _) in &pairs {
+        let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+        parent.insert(ra.max(rb), ra.min(rb));
+    }
+
+    let sizes: BTreeMap<u64, usize> = prints.iter().map(|p| (p.entry, p.insts)).collect();
+    let mut groups: BTreeMap<u64, Cluster> = BTreeMap::new();
+    for &(a, _, score) in &pairs {
+        let root = find(&mut parent, a);
+        let cluster = groups.entry(root).or_insert(Cluster { members: Vec::new(), min_similarity: 1.0, insts: 0 });
+        cluster.min_similarity = cluster.min_similarity.min(score);
+    }
+    for &entry in sizes.keys() {
+        if let Some(cluster) = groups.get_mut(&find(&mut parent, entry)) {
+            cluster.members.push(entry);
+            cluster.insts += sizes[&entry];
+        }
+    }
+
+    let mut out: Vec<Cluster> = groups.into_values().collect();
+    out.sort_by(|a, b| b.insts.cmp(&a.insts).then(a.members.cmp(&b.members)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renamed_copies_cluster() {
+        let program = Program::from_words(&[
+            // 0x0: add x0, x0, #1; mul x0, x0, x1; sub x0, x0, #2; lsl x0, x0, #3; ret
+            0x91000400, 0x9B017C00, 0xD1000800, 0xD37DF000, 0xD65F03C0,
+            // 0x14: the same with x2 and x3
+            0x91000442, 0x9B037C42, 0xD1000842, 0xD37DF042, 0xD65F03C0,
+            // 0x28: mov x0, #0; cbz x1, .+8; ldr x0, [x1]; str x0, [x2]; ret
+            0xD2800000, 0xB4000041, 0xF9400020, 0xF9000040, 0xD65F03C0,
+        ], 0);
+        let prints: Vec<Fingerprint> =
+            [0x0, 0x14, 0x28].iter().map(|&entry| fingerprint(&program, entry, entry + 0x14)).collect();
+        assert_eq!(prints[0].similarity(&prints[1]), 1.0);
+        assert_eq!(prints[0].similarity(&prints[2]), 0.0);
+        let found = clusters(&prints, 0.8);
+        assert_eq!(found, vec![Cluster { members: vec![0x0, 0x14], min_similarity: 1.0, insts: 10 }]);
+    }
+}
@@ -0,0 +1,120 @@
+//! Backward slices: the instructions a register's value at some point was
+//! computed from, within the block and across the CFG, e.g. the chain that
+//! produced the bad pointer of a crash reported by a minidump.
+//!
+//! ```text
+//! 0x1000: mov  x1, #5          // in the slice
+//! 0x1004: mov  x2, #7
+//! 0x1008: cbz  x0, 0x1010
+//! 0x100c: add  x1, x1, #1      // in the slice
+//! 0x1010: ldr  x5, [x1]        // in the slice
+//! 0x1014: ret                  // slice of x5 here
+//! ```
+//!
+//! Slices follow data only, through the same `def_use` sets liveness uses:
+//! calls compute the caller-saved registers from the arguments, and loads
+//! their value from the address registers (memory isn't tracked, so the
+//! store that wrote it is not in the slice). Branches deciding which
+//! definitions reach are not followed either.
+//!
+//! Sampling picks a reproducible subset of a program's instructions from a
+//! seed, to run the expensive analyses on.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::aarch64_cfg::Cfg;
+use crate::aarch64_defuse::Loc;
+use crate::aarch64_gen::Rng;
+use crate::aarch64_liveness::effect;
+use crate::aarch64_program::Program;
+
+/// The blocks flowing into each block, exception edges included.
+fn predecessors(cfg: &Cfg) -> BTreeMap<u64, Vec<u64>> {
+    let mut out: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+    for (&start, block) in &cfg.blocks {
+        let mut successors = cfg.successors(start);
+        successors.extend(cfg.exception_edges.range(start..block.end).map(|(_, pad)| *pad));
+        for s in successors {
+            out.entry(s).or_default().push(start);
+        }
+    }
+    out
+}
+
+/// The instructions the value of loc, as the instruction at addr reads it,
+/// was computed from; empty if addr isn't in a block of cfg.
+pub fn backward_slice(program: &Program, cfg: &Cfg, addr: u64, loc: Loc) -> BTreeSet<u64> {
+    let Some((&start, _)) = cfg.blocks.range(..=addr).next_back().filter(|(_, b)| addr < b.end) else {
+        return BTreeSet::new();
+    };
+    let predecessors = predecessors(cfg);
+    let mut slice = BTreeSet::new();
+    // (block, pc, loc): the definition of loc reaching pc in block.
+    let mut work = vec![(start, addr, loc)];
+    let mut seen = BTreeSet::from([(start, addr, loc)]);
+    while let Some((start, pc, loc)) = work.pop() {
+        let def = program.range(start..pc).rev().find_map(|(at, inst)| {
+            let (defs, uses) = effect(inst);
+            defs.contains(&loc).then_some((at, uses))
+        });
+        match def {
+            Some((at, uses)) => {
+                slice.insert(at);
+                for u in uses {
+                    if seen.insert((start, at, u)) {
+                        work.push((start, at, u));
+                    }
+                }
+            }
+            None => {
+                for &p in predecessors.get(&start).into_iter().flatten() {
+                    let end = cfg.blocks[&p].end;
+                    if seen.insert((p, end, loc)) {
+                        work.push((p, end, loc));
+                    }
+                }
+            }
+        }
+    }
+    slice
+}
+
+/// Picks count addresses of program's instructions, the same for the same seed;
+/// all of them if it has no more.
+pub fn sample(program: &Program, count: usize, seed: u64) -> BTreeSet<u64> {
+    let mut addrs: Vec<u64> = program.iter().map(|(a, _)| a).collect();
+    if count < addrs.len() {
+        let mut rng = Rng::new(seed);
+        for i in 0..count {
+            let j = i + (rng.next_u64() % (addrs.len() - i) as u64) as usize;
+            addrs.swap(i, j);
+        }
+        addrs.truncate(count);
+    }
+    addrs.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slice_across_blocks() {
+        let program = Program::from_words(&[
+            0xD28000A1, // 0x1000: mov x1, #5
+            0xD28000E2, //         mov x2, #7
+            0xB4000040, //         cbz x0, 0x1010
+            0x91000421, // 0x100c: add x1, x1, #1
+            0xF9400025, // 0x1010: ldr x5, [x1]
+            0xD65F03C0, //         ret
+        ], 0x1000);
+        let cfg = Cfg::build(&program);
+        assert_eq!(backward_slice(&program, &cfg, 0x1014, Loc::X(5)), BTreeSet::from([0x1000, 0x100c, 0x1010]));
+        assert_eq!(backward_slice(&program, &cfg, 0x1010, Loc::X(2)), BTreeSet::from([0x1004]));
+        assert!(backward_slice(&program, &cfg, 0x2000, Loc::X(5)).is_empty());
+
+        let picked = sample(&program, 3, 42);
+        assert_eq!((picked.len(), sample(&program, 3, 42)), (3, picked));
+        assert_eq!(sample(&program, 10, 1).len(), 6);
+    }
+}
@@ -0,0 +1,159 @@
+//! SMC and HVC call sites annotated with their SMC Calling Convention
+//! (SMCCC) function ID, when constant propagation recovers W0.
+//!
+//! ```text
+//! mov  w0, #0x3          // 0xC4000003: PSCI CPU_ON, SMC64
+//! movk w0, #0xc400, lsl #16
+//! smc  #0
+//! ```
+
+use std::fmt;
+
+use crate::aarch64_constprop::{propagate, ConstOptions};
+use crate::aarch64_reader::{Inst, Op};
+
+/// The owning entity of a function ID (bits 29:24).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Owner {
+    Arch,
+    Cpu,
+    SiP,
+    Oem,
+    /// PSCI, TRNG, ...
+    Standard,
+    StandardHyp,
+    VendorHyp,
+    TrustedApp,
+    TrustedOs,
+    Reserved,
+}
+
+/// An SMCCC function ID as passed in W0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SmcccId(pub u32);
+
+const NAMES: [(u32, &str); 22] = [
+    (0x8000_0000, "SMCCC_VERSION"),
+    (0x8000_0001, "SMCCC_ARCH_FEATURES"),
+    (0x8000_0002, "SMCCC_ARCH_SOC_ID"),
+    (0x8000_3FFF, "SMCCC_ARCH_WORKAROUND_3"),
+    (0x8000_7FFF, "SMCCC_ARCH_WORKAROUND_2"),
+    (0x8000_8000, "SMCCC_ARCH_WORKAROUND_1"),
+    (0x8400_0000, "PSCI_VERSION"),
+    (0x8400_0001, "PSCI_CPU_SUSPEND"),
+    (0x8400_0002, "PSCI_CPU_OFF"),
+    (0x8400_0003, "PSCI_CPU_ON"),
+    (0x8400_0004, "PSCI_AFFINITY_INFO"),
+    (0x8400_0005, "PSCI_MIGRATE"),
+    (0x8400_0006, "PSCI_MIGRATE_INFO_TYPE"),
+    (0x8400_0007, "PSCI_MIGRATE_INFO_UP_CPU"),
+    (0x8400_0008, "PSCI_SYSTEM_OFF"),
+    (0x8400_0009, "PSCI_SYSTEM_RESET"),
+    (0x8400_000A, "PSCI_FEATURES"),
+    (0x8400_000C, "PSCI_CPU_DEFAULT_SUSPEND"),
+    (0x8400_000E, "PSCI_SYSTEM_SUSPEND"),
+    (0x8400_0012, "PSCI_SYSTEM_RESET2"),
+    (0x8400_0050, "TRNG_VERSION"),
+    (0x8400_0053, "TRNG_RND"),
+];
+
+impl SmcccId {
+    /// Fast call (atomic on the callee side) rather than yielding.
+    pub fn is_fast(self) -> bool {
+        self.0 >> 31 == 1
+    }
+
+    /// SMC64/HVC64 convention, with 64-bit arguments.
+    pub fn is_64(self) -> bool {
+        (self.0 >> 30) & 1 == 1
+    }
+
+    pub fn owner(self) -> Owner {
+        match (self.0 >> 24) & 0x3F {
+            0 => Owner::Arch,
+            1 => Owner::Cpu,
+            2 => Owner::SiP,
+            3 => Owner::Oem,
+            4 => Owner::Standard,
+            5 => Owner::StandardHyp,
+            6 => Owner::VendorHyp,
+            48..=49 => Owner::TrustedApp,
+            50..=63 => Owner::TrustedOs,
+            _ => Owner::Reserved,
+        }
+    }
+
+    pub fn function(self) -> u16 {
+        self.0 as u16
+    }
+
+    /// Whether the bits the convention reserves are zero: 23:17 of fast
+    /// calls (bit 16 is the SVE live state hint).
+    pub fn is_well_formed(self) -> bool {
+        !self.is_fast() || (self.0 >> 17) & 0x7F == 0
+    }
+
+    /// The name of the architecture, PSCI and TRNG functions; the SMC64
+    /// variants have the same name as their SMC32 counterparts.
+    pub fn name(self) -> Option<&'static str> {
+        let id = self.0 & !(1 << 30) & !(1 << 16);
+        NAMES.iter().find(|(n, _)| *n == id).map(|(_, name)| *name)
+    }
+}
+
+impl fmt::Display for SmcccId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = if self.is_64() { 64 } else { 32 };
+        match self.name() {
+            Some(name) => write!(f, "{} ({}{})", name, if self.is_fast() { "fast, " } else { "" }, width),
+            None => write!(f, "{:?} function {:#x} ({})", self.owner(), self.function(), width),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ConduitCall {
+    pub pc: u64,
+    /// A64_SMC or A64_HVC
+    pub conduit: Op,
+    /// The immediate, zero under the convention.
+    pub imm: u16,
+    /// None if W0 isn't known.
+    pub id: Option<SmcccId>,
+}
+
+/// The SMC and HVC instructions of straight-line code, with W0 recovered by
+/// constant propagation.
+pub fn smccc_calls(block: &[(u64, Inst)]) -> Vec<ConduitCall> {
+    let resolved = propagate(block, &ConstOptions::default());
+    block
+        .iter()
+        .filter(|(_, inst)| matches!(inst.op, Op::A64_SMC | Op::A64_HVC))
+        .map(|(pc, inst)| {
+            let x0 = resolved.trap_args.get(pc).and_then(|args| args[0]);
+            ConduitCall { pc: *pc, conduit: inst.op, imm: inst.imm as u16, id: x0.map(|v| SmcccId(v as u32)) }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_buffer::decode_words;
+
+    #[test]
+    fn psci_cpu_on() {
+        let block = decode_words(&[
+            0x52800060, // mov w0, #3
+            0x72B88000, // movk w0, #0xc400, lsl #16
+            0xD4000003, // smc #0
+            0xD4000002, // hvc #0
+        ], 0);
+        let calls = smccc_calls(&block);
+        assert_eq!(calls.len(), 2);
+        let id = calls[0].id.unwrap();
+        assert_eq!((id, id.owner(), id.name()), (SmcccId(0xC400_0003), Owner::Standard, Some("PSCI_CPU_ON")));
+        assert_eq!(id.to_string(), "PSCI_CPU_ON (fast, 64)");
+        assert_eq!(calls[1].id, None); // the SMC clobbered X0
+    }
+}
@@ -0,0 +1,231 @@
+//! SMP boot code and spinlocks: where the cores part on their MPIDR_EL1,
+//! and the loops in which they wait for each other.
+//!
+//! ```text
+//! mrs   x0, mpidr_el1
+//! and   x0, x0, #0xff         // Aff0
+//! cbnz  x0, secondary         // CoreDispatch { mask: 0xff, .. }
+//! ...
+//! 1: ldaxr w1, [x2]           // SpinLoop { kind: Exclusive, .. }
+//!    cbnz  w1, 1b
+//!    stxr  w3, w4, [x2]
+//!    cbnz  w3, 1b
+//! ```
+//!
+//! Nothing runs the cores: the dispatches tell which code each core takes
+//! out of reset, and the spin loops where a trace of one core would wait on
+//! the others.
+
+use std::collections::BTreeMap;
+
+use crate::aarch64_defuse::{def_use, Loc};
+use crate::aarch64_program::{Program, INST_SIZE};
+use crate::aarch64_reader::FlagMasks::{SET_FLAGS, W32};
+use crate::aarch64_reader::{fad_get_cond, Cond, Inst, Op, Registries};
+use crate::aarch64_relocate::target_address;
+use crate::aarch64_sysreg::SysReg;
+
+/// The affinity fields Aff3, Aff2, Aff1 and Aff0 of MPIDR_EL1.
+pub const MPIDR_AFFINITY: u64 = 0xFF_00FF_FFFF;
+
+/// Loops of at most this many instructions can be spin loops.
+const MAX_SPIN: u64 = 16;
+
+/// A branch on the core's affinity: the cores whose MPIDR_EL1, masked,
+/// is zero go to primary, the others to secondary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CoreDispatch {
+    /// The MRS of MPIDR_EL1.
+    pub read: u64,
+    /// The CBZ, CBNZ, TBZ, TBNZ or B.EQ/B.NE after a TST.
+    pub branch: u64,
+    /// The affinity bits tested.
+    pub mask: u64,
+    pub primary: u64,
+    pub secondary: u64,
+}
+
+/// The branches of straight-line code on the affinity bits of MPIDR_EL1,
+/// kept through AND (immediate).
+pub fn core_dispatches(block: &[(u64, Inst)]) -> Vec<CoreDispatch> {
+    // The registers holding MPIDR_EL1 masked, with the pc of the MRS.
+    let mut held: BTreeMap<u8, (u64, u64)> = BTreeMap::new();
+    let mut flags: Option<(u64, u64)> = None;
+    let mut out = Vec::new();
+    for &(pc, ref inst) in block {
+        let next = pc + INST_SIZE;
+        let w32 = if inst.flags & W32 != 0 { 0xFFFF_FFFF } else { u64::MAX };
+        let tested = match inst.op {
+            Op::A64_CBZ | Op::A64_CBNZ => held.get(&inst.rd).map(|&(read, mask)| (read, mask & w32)),
+            Op::A64_TBZ | Op::A64_TBNZ => held.get(&inst.rd).map(|&(read, mask)| (read, mask & (1 << inst.tbz.bit))),
+            Op::A64_BCOND if matches!(fad_get_cond(inst.flags), Cond::COND_EQ | Cond::COND_NE) => flags,
+            _ => None,
+        };
+        if let (Some((read, mask)), Some(target)) = (tested, target_address(inst, pc)) {
+            if mask != 0 {
+                let zero_taken = matches!(inst.op, Op::A64_CBZ | Op::A64_TBZ)
+                    || (inst.op == Op::A64_BCOND && fad_get_cond(inst.flags) == Cond::COND_EQ);
+                let (primary, secondary) = if zero_taken { (target, next) } else { (next, target) };
+                out.push(CoreDispatch { read, branch: pc, mask, primary, secondary });
+            }
+        }
+
+        let source = held.get(&inst.rn).copied();
+        for def in def_use(inst).defs {
+            match def {
+                Loc::X(r) => {
+                    held.remove(&r);
+                }
+                Loc::NZCV => flags = None,
+                _ => {}
+            }
+        }
+        match inst.op {
+            Op::A64_MRS if inst.sysreg() == Some(SysReg::MPIDR_EL1) => {
+                held.insert(inst.rd, (pc, MPIDR_AFFINITY));
+            }
+            // ANDS and TST set the flags too.
+            Op::A64_AND_IMM | Op::A64_TST_IMM => {
+                if let Some((read, mask)) = source {
+                    let masked = (read, mask & inst.imm & w32);
+                    if inst.flags & SET_FLAGS != 0 {
+                        flags = Some(masked);
+                    }
+                    if inst.rd != Registries::ZERO_REG {
+                        held.insert(inst.rd, masked);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SpinKind {
+    /// A load-exclusive and a store-exclusive, retried until the store
+    /// succeeds: a lock or an atomic update without LSE.
+    Exclusive,
+    /// CAS, SWP or another LSE atomic, retried on the value it returns.
+    Atomic,
+    /// Loads only, polling locations that other cores write.
+    Poll,
+    /// WFE or WFI with nothing to poll: a core parked until an event or an
+    /// interrupt.
+    Park,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpinLoop {
+    pub head: u64,
+    /// The last branch back to head.
+    pub back_edge: u64,
+    pub kind: SpinKind,
+    /// Whether the loop waits with WFE or WFI between tries.
+    pub waits: bool,
+}
+
+fn is_lse_atomic(op: Op) -> bool {
+    matches!(op, Op::A64_LDADD | Op::A64_LDCLR | Op::A64_LDEOR | Op::A64_LDSET | Op::A64_LDSMAX | Op::A64_LDSMIN
+        | Op::A64_LDUMAX | Op::A64_LDUMIN | Op::A64_SWP | Op::A64_CAS | Op::A64_CASP)
+}
+
+fn is_wait(inst: &Inst) -> bool {
+    // WFE and WFI are HINT #2 and #3.
+    inst.op == Op::A64_HINT && matches!(inst.imm, 2 | 3)
+}
+
+/// The short loops of program that wait on other cores: those that access
+/// memory only with exclusives, atomics or loads, or that only wait for an
+/// event. Loops with calls or plain stores, and delay loops that don't
+/// access memory, aren't spin loops.
+pub fn spin_loops(program: &Program) -> Vec<SpinLoop> {
+    let mut back_edges: BTreeMap<u64, u64> = BTreeMap::new();
+    for (pc, inst) in program.iter() {
+        let jump = matches!(inst.op, Op::A64_B | Op::A64_BCOND | Op::A64_CBZ | Op::A64_CBNZ | Op::A64_TBZ | Op::A64_TBNZ);
+        match target_address(inst, pc) {
+            Some(head) if jump && head <= pc && pc - head < MAX_SPIN * INST_SIZE => {
+                let edge = back_edges.entry(head).or_insert(pc);
+                *edge = (*edge).max(pc);
+            }
+            _ => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    for (head, back_edge) in back_edges {
+        let body: Vec<&Inst> = program.range(head..=back_edge).map(|(_, inst)| inst).collect();
+        if body.len() as u64 != (back_edge - head) / INST_SIZE + 1 {
+            continue; // a gap
+        }
+        let calls = body.iter().any(|i| matches!(i.op, Op::A64_BL | Op::A64_BLR | Op::A64_BLRA | Op::A64_RET | Op::A64_RETA));
+        let exclusive = body.iter().any(|i| matches!(i.op, Op::A64_STXR | Op::A64_STXP));
+        let atomic = body.iter().any(|i| is_lse_atomic(i.op));
+        let stores = body.iter().any(|i| i.memory_write_bytes() > 0 && !is_lse_atomic(i.op) && !matches!(i.op, Op::A64_STXR | Op::A64_STXP));
+        let loads = body.iter().any(|i| i.memory_read_bytes() > 0);
+        let waits = body.iter().any(|i| is_wait(i));
+        let kind = match () {
+            _ if calls || stores => continue,
+            _ if exclusive => SpinKind::Exclusive,
+            _ if atomic => SpinKind::Atomic,
+            _ if loads => SpinKind::Poll,
+            _ if waits => SpinKind::Park,
+            _ => continue,
+        };
+        out.push(SpinLoop { head, back_edge, kind, waits });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_buffer::decode_words;
+
+    #[test]
+    fn dispatch_on_affinity() {
+        let block = decode_words(&[
+            0xD53800A0, // mrs x0, mpidr_el1
+            0x92401C00, // and x0, x0, #0xff
+            0xB5000200, // cbnz x0, .+0x40
+            0xD53800A1, // mrs x1, mpidr_el1
+            0xF2781C3F, // tst x1, #0xff00
+            0x54000201, // b.ne .+0x40
+            0xD53800A2, // mrs x2, mpidr_el1
+            0x36400202, // tbz w2, #8, .+0x40
+            0xB5000200, // cbnz x0, .+0x40: x0 is still Aff0
+        ], 0x1000);
+        assert_eq!(core_dispatches(&block), vec![
+            CoreDispatch { read: 0x1000, branch: 0x1008, mask: 0xFF, primary: 0x100C, secondary: 0x1048 },
+            CoreDispatch { read: 0x100C, branch: 0x1014, mask: 0xFF00, primary: 0x1018, secondary: 0x1054 },
+            CoreDispatch { read: 0x1018, branch: 0x101C, mask: 0x100, primary: 0x105C, secondary: 0x1020 },
+            CoreDispatch { read: 0x1000, branch: 0x1020, mask: 0xFF, primary: 0x1024, secondary: 0x1060 },
+        ]);
+    }
+
+    #[test]
+    fn locks_polls_and_parking() {
+        let program = Program::from_words(&[
+            0x885FFC01, // 1: ldaxr w1, [x0]
+            0x35FFFFE1, //    cbnz w1, 1b
+            0x88037C04, //    stxr w3, w4, [x0]
+            0x35FFFFA3, //    cbnz w3, 1b
+            0x88E17C02, // 2: casa w1, w2, [x0]
+            0x35FFFFE1, //    cbnz w1, 2b
+            0xD503205F, // 3: wfe
+            0x88DFFC01, //    ldar w1, [x0]
+            0x34FFFFC1, //    cbz w1, 3b
+            0xD503205F, // 4: wfe
+            0x17FFFFFF, //    b 4b
+            0xF1000400, // 5: subs x0, x0, #1: a delay
+            0x54FFFFE1, //    b.ne 5b
+        ], 0x1000);
+        assert_eq!(spin_loops(&program), vec![
+            SpinLoop { head: 0x1000, back_edge: 0x100C, kind: SpinKind::Exclusive, waits: false },
+            SpinLoop { head: 0x1010, back_edge: 0x1014, kind: SpinKind::Atomic, waits: false },
+            SpinLoop { head: 0x1018, back_edge: 0x1020, kind: SpinKind::Poll, waits: true },
+            SpinLoop { head: 0x1024, back_edge: 0x1028, kind: SpinKind::Park, waits: true },
+        ]);
+    }
+}
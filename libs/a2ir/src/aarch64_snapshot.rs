@@ -0,0 +1,233 @@
+//! Memory snapshots of running processes: the executable regions of a
+//! core dump, or of /proc/pid/mem read along /proc/pid/maps, decoded at
+//! the addresses they ran at, so that code generated at run time (JIT
+//! pages, trampolines) is disassembled like code from files.
+//!
+//! ```text
+//! 7f8a200000-7f8a210000 r-xp 00000000 00:00 0          // anonymous: JIT
+//! 7f8b000000-7f8b1c0000 r-xp 00000000 fd:01 1234  /usr/lib/libc.so.6
+//! ```
+//!
+//! Of a core dump only the PT_LOAD segments are read, not the notes; the
+//! kernel leaves the unmodified pages of file mappings out of them (their
+//! file size is short of the memory size), so those regions are shorter or
+//! empty and the code is the file's.
+
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::aarch64_buffer::{decode_buffer, DecodeOptions};
+use crate::aarch64_program::Program;
+
+/// A line of /proc/pid/maps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mapping {
+    pub start: u64,
+    pub end: u64,
+    pub read: bool,
+    pub write: bool,
+    pub exec: bool,
+    /// Offset into the file mapped.
+    pub offset: u64,
+    /// The file, or a name like "[stack]"; None for anonymous memory.
+    pub path: Option<String>,
+}
+
+impl Mapping {
+    /// Anonymous executable memory, where JIT compilers put code.
+    pub fn is_anonymous_code(&self) -> bool {
+        self.exec && self.path.is_none()
+    }
+}
+
+/// The mappings of /proc/pid/maps text.
+pub fn parse_maps(text: &str) -> Result<Vec<Mapping>, String> {
+    let mut out = Vec::new();
+    for (n, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let bad = || format!("snapshot: bad maps line {}: {:?}", n + 1, line);
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 5 {
+            return Err(bad());
+        }
+        let (start, end) = fields[0].split_once('-').ok_or_else(bad)?;
+        let hex = |s: &str| u64::from_str_radix(s, 16).map_err(|_| bad());
+        let perms = fields[1].as_bytes();
+        if perms.len() != 4 {
+            return Err(bad());
+        }
+        // The path may contain spaces, and " (deleted)" follows removed files.
+        let path = (fields.len() > 5).then(|| fields[5..].join(" "));
+        out.push(Mapping {
+            start: hex(start)?,
+            end: hex(end)?,
+            read: perms[0] == b'r',
+            write: perms[1] == b'w',
+            exec: perms[2] == b'x',
+            offset: hex(fields[2])?,
+            path,
+        });
+    }
+    Ok(out)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub mapping: Mapping,
+    /// The bytes from mapping.start; fewer than the mapping spans if the
+    /// snapshot doesn't have them all.
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    /// The executable regions, by address.
+    pub regions: Vec<Region>,
+}
+
+const PT_LOAD: u32 = 1;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+const ET_EXEC: u16 = 2;
+const ET_DYN: u16 = 3;
+const ET_CORE: u16 = 4;
+const EM_AARCH64: u16 = 183;
+
+fn field<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N], String> {
+    data.get(offset..offset + N)
+        .map(|b| b.try_into().expect("N bytes"))
+        .ok_or(format!("snapshot: file truncated at {:#x}", offset))
+}
+
+impl Snapshot {
+    /// The executable mappings of maps, read from mem at their addresses as
+    /// /proc/pid/mem is. A mapping that can't be read (unreadable memory,
+    /// a [vvar] page) is kept with the bytes read before the failure.
+    pub fn from_mem<R: Read + Seek>(maps: &[Mapping], mem: &mut R) -> Snapshot {
+        let mut regions = Vec::new();
+        for mapping in maps.iter().filter(|m| m.exec) {
+            let mut bytes = Vec::new();
+            if mem.seek(SeekFrom::Start(mapping.start)).is_ok() {
+                let _ = mem.by_ref().take(mapping.end - mapping.start).read_to_end(&mut bytes);
+            }
+            regions.push(Region { mapping: mapping.clone(), bytes });
+        }
+        regions.sort_by_key(|r| r.mapping.start);
+        Snapshot { regions }
+    }
+
+    /// The executable PT_LOAD segments of a little-endian ELF64 AArch64
+    /// core dump.
+    pub fn from_core(core: &[u8]) -> Result<Snapshot, String> {
+        Snapshot::from_segments(core, &[ET_CORE], "core dump")
+    }
+
+    /// The executable PT_LOAD segments of a little-endian ELF64 AArch64
+    /// executable or shared object, at their link addresses, and its entry
+    /// point.
+    pub fn from_elf(elf: &[u8]) -> Result<(Snapshot, u64), String> {
+        let snapshot = Snapshot::from_segments(elf, &[ET_EXEC, ET_DYN], "executable")?;
+        Ok((snapshot, field::<8>(elf, 24).map(u64::from_le_bytes)?))
+    }
+
+    fn from_segments(data: &[u8], types: &[u16], what: &str) -> Result<Snapshot, String> {
+        if data.get(..6) != Some(b"\x7fELF\x02\x01") {
+            return Err("snapshot: not a little-endian ELF64 file".to_string());
+        }
+        let u16_at = |o| field::<2>(data, o).map(u16::from_le_bytes);
+        let u32_at = |o| field::<4>(data, o).map(u32::from_le_bytes);
+        let u64_at = |o| field::<8>(data, o).map(u64::from_le_bytes);
+        if !types.contains(&u16_at(16)?) || u16_at(18)? != EM_AARCH64 {
+            return Err(format!("snapshot: not an AArch64 {}", what));
+        }
+        let (phoff, phentsize, phnum) = (u64_at(32)? as usize, u16_at(54)? as usize, u16_at(56)? as usize);
+        let mut regions = Vec::new();
+        for i in 0..phnum {
+            let ph = phoff + i * phentsize;
+            let flags = u32_at(ph + 4)?;
+            if u32_at(ph)? != PT_LOAD || flags & PF_X == 0 {
+                continue;
+            }
+            let (offset, vaddr, filesz, memsz) = (u64_at(ph + 8)? as usize, u64_at(ph + 16)?, u64_at(ph + 32)? as usize, u64_at(ph + 40)?);
+            let bytes = data.get(offset..offset + filesz).ok_or(format!("snapshot: segment {} past the end of the file", i))?;
+            let mapping = Mapping {
+                start: vaddr,
+                end: vaddr + memsz,
+                read: flags & PF_R != 0,
+                write: flags & PF_W != 0,
+                exec: true,
+                offset: 0,
+                path: None,
+            };
+            regions.push(Region { mapping, bytes: bytes.to_vec() });
+        }
+        regions.sort_by_key(|r| r.mapping.start);
+        Ok(Snapshot { regions })
+    }
+
+    /// Takes the paths of the mappings from maps (e.g. of the NT_FILE note
+    /// or a saved /proc/pid/maps), matching them by start address.
+    pub fn name_regions(&mut self, maps: &[Mapping]) {
+        for region in &mut self.regions {
+            if let Some(m) = maps.iter().find(|m| m.start == region.mapping.start) {
+                region.mapping.path = m.path.clone();
+                region.mapping.offset = m.offset;
+            }
+        }
+    }
+
+    /// The regions filter keeps, decoded at their addresses.
+    pub fn program(&self, filter: impl Fn(&Region) -> bool) -> Program {
+        let mut program = Program::new();
+        for region in self.regions.iter().filter(|r| filter(r)) {
+            program.extend(decode_buffer(&region.bytes, &DecodeOptions { base: region.mapping.start, ..Default::default() }).insts);
+        }
+        program
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn jit_region_from_mem() {
+        let maps = parse_maps("\
+1000-1008 r-xp 00000000 00:00 0
+2000-3000 rw-p 00000000 fd:01 1234                       /tmp/my data (deleted)
+").unwrap();
+        assert!(maps[0].is_anonymous_code());
+        assert_eq!(maps[1].path.as_deref(), Some("/tmp/my data (deleted)"));
+
+        // A sparse image of the address space, as /proc/pid/mem reads.
+        let mut mem = vec![0u8; 0x1008];
+        mem[0x1000..].copy_from_slice(&[0x1F, 0x20, 0x03, 0xD5, 0xC0, 0x03, 0x5F, 0xD6]);
+        let snapshot = Snapshot::from_mem(&maps, &mut Cursor::new(mem));
+        assert_eq!(snapshot.regions.len(), 1);
+        let program = snapshot.program(|r| r.mapping.is_anonymous_code());
+        assert_eq!(program.iter().map(|(a, _)| a).collect::<Vec<_>>(), vec![0x1000, 0x1004]);
+        assert!(Snapshot::from_core(b"\x7fELF\x01").is_err());
+    }
+
+    #[test]
+    fn executable_segments() {
+        let mut elf = vec![0u8; 120];
+        elf[..8].copy_from_slice(b"\x7fELF\x02\x01\x01\x00");
+        elf[16..20].copy_from_slice(&[2, 0, 183, 0]); // ET_EXEC, EM_AARCH64
+        elf[24..32].copy_from_slice(&0x40_0004u64.to_le_bytes()); // e_entry
+        elf[32..40].copy_from_slice(&64u64.to_le_bytes()); // e_phoff
+        elf[54..58].copy_from_slice(&[56, 0, 1, 0]); // e_phentsize, e_phnum
+        elf[64..72].copy_from_slice(&[1, 0, 0, 0, 5, 0, 0, 0]); // PT_LOAD, R+X
+        elf[72..80].copy_from_slice(&120u64.to_le_bytes()); // p_offset
+        elf[80..88].copy_from_slice(&0x40_0000u64.to_le_bytes()); // p_vaddr
+        elf[96..104].copy_from_slice(&8u64.to_le_bytes()); // p_filesz
+        elf[104..112].copy_from_slice(&8u64.to_le_bytes()); // p_memsz
+        elf.extend([0x1F, 0x20, 0x03, 0xD5, 0xC0, 0x03, 0x5F, 0xD6]);
+
+        let (snapshot, entry) = Snapshot::from_elf(&elf).unwrap();
+        assert_eq!(entry, 0x40_0004);
+        let program = snapshot.program(|_| true);
+        assert_eq!(program.iter().map(|(a, _)| a).collect::<Vec<_>>(), vec![0x40_0000, 0x40_0004]);
+        assert!(Snapshot::from_core(&elf).is_err());
+    }
+}
@@ -0,0 +1,123 @@
+//! Source locations of instructions, for listings that show the function,
+//! inlining chain and file:line above the code they produced.
+//!
+//! The crate doesn't read DWARF; the locations come from the output of
+//! `addr2line -a -f -i` for the addresses of interest (every instruction,
+//! or the start of every basic block):
+//!
+//! ```text
+//! 0x0000000000001000
+//! inlined_helper
+//! /src/util.h:12
+//! caller
+//! /src/main.c:40
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::aarch64_listing::Annotate;
+use crate::aarch64_reader::Inst;
+
+/// A function of the inlining chain, and the location in it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Frame {
+    pub function: String,
+    pub file: String,
+    /// None if unknown ("??" or 0).
+    pub line: Option<u32>,
+}
+
+/// Locations by address; a location holds up to the next address listed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    /// The inlining chain, innermost first.
+    pub locations: BTreeMap<u64, Vec<Frame>>,
+}
+
+impl SourceMap {
+    /// Parses the output of `addr2line -a -f -i`.
+    pub fn from_addr2line(text: &str) -> Result<SourceMap, String> {
+        let mut map = SourceMap::default();
+        let mut current: Option<u64> = None;
+        let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+        while let Some(line) = lines.next() {
+            if let Some(hex) = line.strip_prefix("0x") {
+                let addr = u64::from_str_radix(hex, 16).map_err(|e| format!("bad address {}: {}", line, e))?;
+                map.locations.entry(addr).or_default();
+                current = Some(addr);
+                continue;
+            }
+            let addr = current.ok_or(format!("location before the first address: {}", line))?;
+            let location = lines.next().ok_or(format!("no location after function {}", line))?;
+            // The file may contain ':', the line never does.
+            let (file, rest) = location.rsplit_once(':').unwrap_or((location, ""));
+            // Discriminators: "main.c:40 (discriminator 2)"
+            let number = rest.split_whitespace().next().unwrap_or("");
+            let frame = Frame {
+                function: line.to_string(),
+                file: file.to_string(),
+                line: number.parse().ok().filter(|&n| n != 0),
+            };
+            map.locations.get_mut(&addr).unwrap().push(frame);
+        }
+        Ok(map)
+    }
+
+    /// The inlining chain at addr, innermost first.
+    pub fn lookup(&self, addr: u64) -> Option<&[Frame]> {
+        let (_, frames) = self.locations.range(..=addr).next_back()?;
+        (!frames.is_empty()).then_some(frames.as_slice())
+    }
+}
+
+fn frame_text(frame: &Frame) -> String {
+    match frame.line {
+        Some(line) => format!("{} {}:{}", frame.function, frame.file, line),
+        None => format!("{} {}", frame.function, frame.file),
+    }
+}
+
+/// Prints the chain above the instructions where it changes, outermost
+/// function first.
+impl Annotate for SourceMap {
+    fn header(&self, addr: u64, _inst: &Inst) -> Vec<String> {
+        let Some(frames) = self.locations.get(&addr) else {
+            return Vec::new();
+        };
+        let prev = self.locations.range(..addr).next_back().map(|(_, f)| f);
+        if frames.is_empty() || prev == Some(frames) {
+            return Vec::new();
+        }
+        let mut out = Vec::new();
+        for (depth, frame) in frames.iter().rev().enumerate() {
+            let prefix = if depth == 0 { "" } else { "inlined: " };
+            out.push(format!("// {}{}{}", "  ".repeat(depth), prefix, frame_text(frame)));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_listing::listing;
+    use crate::aarch64_program::Program;
+
+    #[test]
+    fn inlined_helper() {
+        let map = SourceMap::from_addr2line(
+            "0x0000000000001000\ncaller\n/src/main.c:40\n\
+             0x0000000000001004\ncaller\n/src/main.c:40\n\
+             0x0000000000001008\nhelper\n/src/util.h:12 (discriminator 1)\ncaller\n/src/main.c:41\n",
+        )
+        .unwrap();
+        assert_eq!(map.lookup(0x100C).unwrap()[0], Frame {
+            function: "helper".to_string(), file: "/src/util.h".to_string(), line: Some(12) });
+        assert_eq!(map.lookup(0xFFC), None);
+
+        let program = Program::from_words(&[0xD503201F, 0xD503201F, 0xD503201F], 0x1000); // nop
+        let text = listing(&program, &[&map]);
+        let headers: Vec<&str> = text.lines().filter(|l| l.starts_with("//")).collect();
+        assert_eq!(headers, ["// caller /src/main.c:40", "// caller /src/main.c:41", "//   inlined: helper /src/util.h:12"]);
+    }
+}
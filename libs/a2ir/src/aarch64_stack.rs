@@ -0,0 +1,329 @@
+//! Static stack usage bounds per function and per call path.
+//!
+//! Within a function, the stack depth is propagated along the direct control
+//! flow (fallthrough, B, B.cond, CBZ, TBZ) from the entry, tracking SP
+//! adjustments by immediates (SUB/ADD SP, SP, #imm and pre/post-indexed
+//! accesses with SP as base) and restores of SP from a frame pointer set up
+//! from SP (ADD X29, SP, #imm, then MOV SP, X29 or SUB SP, X29, #imm).
+//! Joins take the maximum. Across functions, the
+//! bound of a function is the maximum over its call sites of the depth at the
+//! call plus the bound of the callee.
+//!
+//! Everything the analysis can't see through is reported as a caveat rather
+//! than silently ignored: recursion, indirect calls, calls to unknown
+//! addresses, and any other instruction that may write SP, including the
+//! ones the decoder doesn't know (dynamic allocations such as SUB SP, SP, X0).
+//!
+//! Functions that sign their return address (PACIASP, PACIBSP) are noted with
+//! the SP the signature is bound to, which an unwinder needs to authenticate
+//! or strip it. A return that would fail authentication (a plain RET of the
+//! signed address, or authentication with the other key) is a caveat too.
+//! Functions using the shadow call stack (see aarch64_scs) are noted too.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::aarch64_defuse::{def_use, Loc};
+use crate::aarch64_pac::{PacHint, PacKey, PacModifier};
+use crate::aarch64_scs::ScsOp;
+use crate::aarch64_reader::{decode, fad_get_addrmode, AddrMode, Op, Registries};
+use crate::aarch64_relocate::target_address;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StackCaveat {
+    /// The function is part of a call cycle; the bound counts one iteration.
+    Recursion { cycle: Vec<u64> },
+    IndirectCall { at: u64 },
+    /// BL to an address that isn't one of the given function entries.
+    UnknownCallee { at: u64, target: u64 },
+    /// SP written by something other than an immediate adjustment or a
+    /// restore from a tracked frame pointer.
+    UntrackedSpWrite { at: u64 },
+    /// The depth kept growing around a loop.
+    UnboundedLoop { at: u64 },
+    /// Return with a signed address that isn't authenticated, or is
+    /// authenticated with a different key than it was signed with.
+    ReturnAuth { at: u64 },
+}
+
+/// Where a function signs its return address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReturnSigning {
+    pub key: PacKey,
+    pub at: u64,
+    /// Stack depth at the PACIxSP; the modifier is the SP at entry minus this.
+    pub depth: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct FunctionStack {
+    pub entry: u64,
+    /// Maximum stack the function allocates itself, in bytes.
+    pub frame: u64,
+    /// Bound including callees, in bytes.
+    pub max_depth: u64,
+    /// Function entries along the call path that reaches max_depth.
+    pub worst_path: Vec<u64>,
+    /// Caveats of this function and of everything it calls.
+    pub caveats: Vec<StackCaveat>,
+    pub return_signing: Option<ReturnSigning>,
+    /// The function pushes its return address to the shadow call stack.
+    pub shadow_call_stack: bool,
+}
+
+struct Local {
+    frame: u64,
+    return_signing: Option<ReturnSigning>,
+    shadow_call_stack: bool,
+    calls: Vec<(u64, u64, u64)>, // (call site, depth at call, target)
+    caveats: Vec<StackCaveat>,
+}
+
+/// How many times the depth of one instruction may grow before giving up.
+const MAX_WIDENING: u32 = 8;
+
+fn local_frame(words: &[u32], base: u64, entry: u64, end: u64) -> Local {
+    let mut out = Local { frame: 0, return_signing: None, shadow_call_stack: false, calls: Vec::new(), caveats: Vec::new() };
+
+    let mut depth: HashMap<u64, u64> = HashMap::new();
+    let mut updates: HashMap<u64, u32> = HashMap::new();
+    // The key X30 is signed with at pc, if it is, and the depth X29 points
+    // at, if it was set from SP.
+    let mut worklist: Vec<(u64, u64, Option<PacKey>, Option<u64>)> = vec![(entry, 0u64, None, None)];
+
+    while let Some((pc, d, signed, fp)) = worklist.pop() {
+        if pc < entry || pc >= end {
+            continue;
+        }
+        match depth.get(&pc) {
+            Some(&known) if known >= d => continue,
+            Some(_) => {
+                let n = updates.entry(pc).or_insert(0);
+                *n += 1;
+                if *n > MAX_WIDENING {
+                    out.caveats.push(StackCaveat::UnboundedLoop { at: pc });
+                    continue;
+                }
+            }
+            None => {}
+        }
+        depth.insert(pc, d);
+        out.frame = out.frame.max(d);
+
+        let inst = decode(words[((pc - base) / 4) as usize]);
+        let sp = Registries::STACK_POINTER;
+        let defs = def_use(&inst).defs;
+        let mut next = d;
+        let mut next_fp = if defs.contains(&Loc::X(29)) { None } else { fp };
+        match inst.op {
+            Op::A64_SUB_IMM if inst.rd == sp && inst.rn == sp => next = d + inst.imm,
+            Op::A64_ADD_IMM if inst.rd == sp && inst.rn == sp => next = d.saturating_sub(inst.imm),
+            // mov x29, sp and add x29, sp, #imm
+            Op::A64_MOV_SP | Op::A64_ADD_IMM if inst.rd == 29 && inst.rn == sp => next_fp = d.checked_sub(inst.imm),
+            // mov sp, x29, add sp, x29, #imm and sub sp, x29, #imm
+            Op::A64_MOV_SP | Op::A64_ADD_IMM | Op::A64_SUB_IMM if inst.rd == sp && inst.rn == 29 && fp.is_some() => {
+                let fp = fp.unwrap();
+                next = if inst.op == Op::A64_SUB_IMM { fp + inst.imm } else { fp.saturating_sub(inst.imm) };
+            }
+            _ if inst.rn == sp && matches!(fad_get_addrmode(inst.flags), AddrMode::AM_PRE | AddrMode::AM_POST)
+                && defs.contains(&Loc::SP) => {
+                next = (d as i64 - inst.offset).max(0) as u64;
+            }
+            _ if defs.contains(&Loc::SP) => out.caveats.push(StackCaveat::UntrackedSpWrite { at: pc }),
+            _ => {}
+        }
+        out.frame = out.frame.max(next);
+
+        let mut signed = signed;
+        match inst.pac_hint() {
+            Some(PacHint::Sign { key, modifier: PacModifier::Sp }) => {
+                if out.return_signing.is_none() {
+                    out.return_signing = Some(ReturnSigning { key, at: pc, depth: d });
+                }
+                signed = Some(key);
+            }
+            Some(PacHint::Auth { key, modifier: PacModifier::Sp }) => {
+                if signed.is_some_and(|k| k != key) {
+                    out.caveats.push(StackCaveat::ReturnAuth { at: pc });
+                }
+                signed = None;
+            }
+            Some(PacHint::StripLr) => signed = None,
+            _ => {}
+        }
+        if inst.scs_op() == Some(ScsOp::Push) {
+            out.shadow_call_stack = true;
+        }
+        let bad_return = match inst.op {
+            Op::A64_RET => signed.is_some() && inst.rn == 30,
+            Op::A64_RETA => signed != inst.pac_key(),
+            _ => false,
+        };
+        if bad_return {
+            out.caveats.push(StackCaveat::ReturnAuth { at: pc });
+        }
+
+        match inst.op {
+            Op::A64_BL => out.calls.push((pc, next, target_address(&inst, pc).unwrap())),
+            Op::A64_BLR | Op::A64_BLRA => out.caveats.push(StackCaveat::IndirectCall { at: pc }),
+            _ => {}
+        }
+
+        let falls_through = inst.branch_kind().is_none_or(|kind| kind.falls_through());
+        if falls_through {
+            worklist.push((pc + 4, next, signed, next_fp));
+        }
+        if inst.op != Op::A64_BL {
+            if let Some(target) = target_address(&inst, pc) {
+                if matches!(inst.op, Op::A64_B | Op::A64_BCOND | Op::A64_CBZ | Op::A64_CBNZ | Op::A64_TBZ | Op::A64_TBNZ) {
+                    worklist.push((target, next, signed, next_fp));
+                }
+            }
+        }
+    }
+
+    out
+}
+
+/// Computes the stack bounds of the functions starting at entries, within the
+/// code words located at base. A function extends up to the next entry.
+pub fn analyze_stack(words: &[u32], base: u64, entries: &[u64]) -> BTreeMap<u64, FunctionStack> {
+    let mut sorted: Vec<u64> = entries.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    let image_end = base + 4 * words.len() as u64;
+
+    let mut locals: BTreeMap<u64, Local> = BTreeMap::new();
+    for (i, &entry) in sorted.iter().enumerate() {
+        let end = sorted.get(i + 1).copied().unwrap_or(image_end).min(image_end);
+        locals.insert(entry, local_frame(words, base, entry, end));
+    }
+
+    let mut done: BTreeMap<u64, FunctionStack> = BTreeMap::new();
+    for &entry in &sorted {
+        let mut stack = Vec::new();
+        bound(entry, &locals, &mut done, &mut stack);
+    }
+    done
+}
+
+fn bound(f: u64, locals: &BTreeMap<u64, Local>, done: &mut BTreeMap<u64, FunctionStack>, stack: &mut Vec<u64>) -> (u64, Vec<u64>, Vec<StackCaveat>) {
+    if let Some(fs) = done.get(&f) {
+        return (fs.max_depth, fs.worst_path.clone(), fs.caveats.clone());
+    }
+    let local = &locals[&f];
+    let mut max_depth = local.frame;
+    let mut worst_path = vec![f];
+    let mut caveats = local.caveats.clone();
+
+    stack.push(f);
+    for &(at, depth, target) in &local.calls {
+        if let Some(pos) = stack.iter().position(|&g| g == target) {
+            caveats.push(StackCaveat::Recursion { cycle: stack[pos..].to_vec() });
+            continue;
+        }
+        if !locals.contains_key(&target) {
+            caveats.push(StackCaveat::UnknownCallee { at, target });
+            continue;
+        }
+        let (callee, path, callee_caveats) = bound(target, locals, done, stack);
+        for c in callee_caveats {
+            if !caveats.contains(&c) {
+                caveats.push(c);
+            }
+        }
+        if depth + callee > max_depth {
+            max_depth = depth + callee;
+            worst_path = std::iter::once(f).chain(path).collect();
+        }
+    }
+    stack.pop();
+
+    // Results computed while part of a cycle depend on the entry point of the
+    // cycle, so only memoize them outside of one.
+    let in_cycle = caveats.iter().any(|c| matches!(c, StackCaveat::Recursion { cycle } if cycle.contains(&f)));
+    if !in_cycle || stack.is_empty() {
+        done.insert(f, FunctionStack {
+            entry: f,
+            frame: local.frame,
+            max_depth,
+            worst_path: worst_path.clone(),
+            caveats: caveats.clone(),
+            return_signing: local.return_signing,
+            shadow_call_stack: local.shadow_call_stack,
+        });
+    }
+    (max_depth, worst_path, caveats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn call_path_adds_frames() {
+        let words = [
+            // 0x0: caller
+            0xA9BF7BFD, // stp x29, x30, [sp, #-16]!
+            0xD10083FF, // sub sp, sp, #32
+            0x94000004, // bl 0x18
+            0x910083FF, // add sp, sp, #32
+            0xA8C17BFD, // ldp x29, x30, [sp], #16
+            0xD65F03C0, // ret
+            // 0x18: callee
+            0xD10043FF, // sub sp, sp, #16
+            0x910043FF, // add sp, sp, #16
+            0xD65F03C0, // ret
+        ];
+        let result = analyze_stack(&words, 0, &[0x0, 0x18]);
+        assert_eq!(result[&0x0].frame, 48);
+        assert_eq!(result[&0x0].max_depth, 64);
+        assert_eq!(result[&0x0].worst_path, vec![0x0, 0x18]);
+        assert!(result[&0x0].caveats.is_empty());
+    }
+
+    #[test]
+    fn frame_pointer_restores_and_untracked_writes() {
+        let words = [
+            0xA9BE7BFD, // stp x29, x30, [sp, #-32]!
+            0x910003FD, // mov x29, sp
+            0xD10043FF, // sub sp, sp, #16
+            0xCB2063FF, // sub sp, sp, x0: not decoded
+            0x910003BF, // mov sp, x29
+            0xA8C27BFD, // ldp x29, x30, [sp], #32
+            0xD65F03C0, // ret
+            // 0x1c
+            0xD10083FF, // sub sp, sp, #32
+            0x910043FD, // add x29, sp, #16
+            0xD10043BF, // sub sp, x29, #16
+            0x910083FF, // add sp, sp, #32
+            0xD65F03C0, // ret
+            // 0x30
+            0x910003BF, // mov sp, x29: not set up here
+            0xD65F03C0, // ret
+        ];
+        let result = analyze_stack(&words, 0, &[0x0, 0x1c, 0x30]);
+        assert_eq!(result[&0x0].frame, 48);
+        // The undecoded word may have written X29 too.
+        assert_eq!(result[&0x0].caveats, vec![StackCaveat::UntrackedSpWrite { at: 0xc }, StackCaveat::UntrackedSpWrite { at: 0x10 }]);
+        assert_eq!(result[&0x1c].frame, 32);
+        assert!(result[&0x1c].caveats.is_empty());
+        assert_eq!(result[&0x30].caveats, vec![StackCaveat::UntrackedSpWrite { at: 0x30 }]);
+    }
+
+    #[test]
+    fn signed_return_address() {
+        let words = [
+            0xD503233F, // paciasp
+            0xA9BF7BFD, // stp x29, x30, [sp, #-16]!
+            0xA8C17BFD, // ldp x29, x30, [sp], #16
+            0xD65F0BFF, // retaa
+            // 0x10
+            0xD503237F, // pacibsp
+            0xD65F03C0, // ret
+        ];
+        let result = analyze_stack(&words, 0, &[0x0, 0x10]);
+        assert_eq!(result[&0x0].return_signing, Some(ReturnSigning { key: PacKey::A, at: 0, depth: 0 }));
+        assert!(result[&0x0].caveats.is_empty());
+        assert_eq!(result[&0x10].caveats, vec![StackCaveat::ReturnAuth { at: 0x14 }]);
+    }
+}
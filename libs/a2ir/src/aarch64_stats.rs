@@ -0,0 +1,186 @@
+//! Statistics over decoded instructions, for characterizing code corpora.
+//!
+//! A `Stats` accumulates opcode and group frequencies, addressing modes and
+//! load extensions of memory accesses, and operand sizes: the access size
+//! for memory accesses, the register width for everything else.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::aarch64_branch::BranchKind;
+use crate::aarch64_defuse::{def_use, is_memory, Loc};
+use crate::aarch64_group::Group;
+use crate::aarch64_reader::{fad_get_addrmode, fad_get_mem_extend, fad_get_prec, FPSize, Inst, Op};
+use crate::aarch64_reader::FlagMasks::W32;
+
+const ADDR_MODE_NAMES: [&str; 7] = ["simple", "offset_imm", "offset_reg", "offset_ext", "pre", "post", "literal"];
+const EXTEND_NAMES: [&str; 8] = ["UXTB", "UXTH", "UXTW", "UXTX", "SXTB", "SXTH", "SXTW", "SXTX"];
+
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    pub total: u64,
+    pub unknown: u64,
+    pub errors: u64,
+    pub opcodes: BTreeMap<Op, u64>,
+    pub groups: BTreeMap<Group, u64>,
+    /// Indexed by AddrMode.
+    pub addr_modes: [u64; 7],
+    /// Indexed by ExtendType; loads only.
+    pub extends: [u64; 8],
+    /// Operand size in bits → count.
+    pub sizes: BTreeMap<u32, u64>,
+}
+
+impl Stats {
+    pub fn add(&mut self, inst: &Inst) {
+        self.total += 1;
+        *self.opcodes.entry(inst.op).or_insert(0) += 1;
+        *self.groups.entry(inst.group()).or_insert(0) += 1;
+        match inst.op {
+            Op::A64_UNKNOWN => self.unknown += 1,
+            Op::A64_ERROR => self.errors += 1,
+            _ => {}
+        }
+
+        if let Some(bits) = operand_size(inst) {
+            *self.sizes.entry(bits).or_insert(0) += 1;
+        }
+        if !is_memory(&inst.op) {
+            return;
+        }
+        if let Some(n) = self.addr_modes.get_mut(fad_get_addrmode(inst.flags) as usize) {
+            *n += 1;
+        }
+        if matches!(inst.op, Op::A64_LDR | Op::A64_LDP | Op::A64_LDNP) {
+            self.extends[fad_get_mem_extend(inst.flags) as usize] += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &Stats) {
+        self.total += other.total;
+        self.unknown += other.unknown;
+        self.errors += other.errors;
+        for (&op, &n) in &other.opcodes {
+            *self.opcodes.entry(op).or_insert(0) += n;
+        }
+        for (&group, &n) in &other.groups {
+            *self.groups.entry(group).or_insert(0) += n;
+        }
+        for (a, b) in self.addr_modes.iter_mut().zip(other.addr_modes) {
+            *a += b;
+        }
+        for (a, b) in self.extends.iter_mut().zip(other.extends) {
+            *a += b;
+        }
+        for (&bits, &n) in &other.sizes {
+            *self.sizes.entry(bits).or_insert(0) += n;
+        }
+    }
+
+    /// Opcodes sorted by decreasing frequency.
+    pub fn most_frequent(&self) -> Vec<(Op, u64)> {
+        let mut ops: Vec<(Op, u64)> = self.opcodes.iter().map(|(&op, &n)| (op, n)).collect();
+        ops.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        ops
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        write!(out, "{{\"total\":{},\"unknown\":{},\"errors\":{},\"opcodes\":{{", self.total, self.unknown, self.errors).unwrap();
+        for (i, (op, n)) in self.most_frequent().iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(out, "{}\"{:?}\":{}", sep, op, n).unwrap();
+        }
+        out.push_str("},\"groups\":{");
+        for (i, (group, n)) in self.groups.iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(out, "{}\"{:?}\":{}", sep, group, n).unwrap();
+        }
+        out.push_str("},\"addressing_modes\":{");
+        for (i, (name, n)) in ADDR_MODE_NAMES.iter().zip(self.addr_modes).enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(out, "{}\"{}\":{}", sep, name, n).unwrap();
+        }
+        out.push_str("},\"extends\":{");
+        for (i, (name, n)) in EXTEND_NAMES.iter().zip(self.extends).enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(out, "{}\"{}\":{}", sep, name, n).unwrap();
+        }
+        out.push_str("},\"sizes\":{");
+        for (i, (bits, n)) in self.sizes.iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            write!(out, "{}\"{}\":{}", sep, bits, n).unwrap();
+        }
+        out.push_str("}}");
+        out
+    }
+}
+
+fn operand_size(inst: &Inst) -> Option<u32> {
+    match inst.op {
+        Op::A64_UNKNOWN | Op::A64_PRFM => None,
+        Op::A64_LDR_FP | Op::A64_LDP_FP | Op::A64_LDNP_FP | Op::A64_STR_FP | Op::A64_STP_FP | Op::A64_STNP_FP => {
+            let prec = fad_get_prec(inst.flags);
+            Some(if prec == FPSize::FSZ_Q { 128 } else { 8 << prec })
+        }
+        _ if is_memory(&inst.op) => Some(8 << (fad_get_mem_extend(inst.flags) & 0b11)),
+        // Only the target register of these is an operand.
+        Op::A64_BCOND => None,
+        _ if inst.branch_kind().is_some_and(|kind| kind != BranchKind::Conditional) => None,
+        _ => {
+            let du = def_use(inst);
+            let gpr = du.defs.iter().chain(&du.uses).any(|l| matches!(l, Loc::X(_) | Loc::SP));
+            if !gpr {
+                None
+            } else if inst.flags & W32 != 0 {
+                Some(32)
+            } else {
+                Some(64)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_buffer::decode_words;
+
+    fn stats(words: &[u32]) -> Stats {
+        let mut stats = Stats::default();
+        for (_, inst) in decode_words(words, 0x1000) {
+            stats.add(&inst);
+        }
+        stats
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn frequencies_modes_and_sizes() {
+        let stats = stats(&[
+            0x91000420, // add x0, x1, #1
+            0x11000420, // add w0, w1, #1
+            0xB9400001, // ldr w1, [x0]
+            0x39C00001, // ldrsb w1, [x0]
+            0x3C810C00, // str q0, [x0, #16]!
+            0x58000040, // ldr x0, .+8
+            0x14000004, // b .+0x10
+            0xF9800000, // prfm pldl1keep, [x0]
+            0x0B020820, // add w0, w1, w2, lsl #2: not decoded
+        ]);
+        assert_eq!((stats.total, stats.unknown, stats.errors), (9, 1, 0));
+        assert_eq!(stats.most_frequent()[..3], [(Op::A64_LDR, 3), (Op::A64_ADD_IMM, 2), (Op::A64_UNKNOWN, 1)]);
+        assert_eq!(stats.sizes, BTreeMap::from([(8, 1), (32, 2), (64, 2), (128, 1)]));
+        assert_eq!(stats.groups.get(&Group::LoadStore), Some(&5));
+        // offset_imm: LDR, LDRSB and PRFM; pre: STR; literal: LDR.
+        assert_eq!(stats.addr_modes, [0, 3, 0, 0, 1, 0, 1]);
+        // UXTW: LDR W, UXTX: LDR X, SXTB: LDRSB.
+        assert_eq!(stats.extends, [0, 0, 1, 1, 1, 0, 0, 0]);
+        assert!(stats.to_json().contains("\"addressing_modes\":{\"simple\":0,\"offset_imm\":3,"));
+
+        let mut merged = stats.clone();
+        merged.merge(&stats);
+        assert_eq!((merged.total, merged.opcodes[&Op::A64_LDR], merged.sizes[&128]), (18, 6, 2));
+        assert_eq!(merged.addr_modes, [0, 6, 0, 0, 2, 0, 2]);
+    }
+}
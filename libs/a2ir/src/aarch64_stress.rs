@@ -0,0 +1,158 @@
+//! Generation of stress sequences for exclusives and atomics: LDXR/STXR
+//! retry loops, CAS, CASP and the LDADD family, with random orderings and a
+//! chosen overlap of registers and memory locations.
+//!
+//! ```text
+//! retry: ldaxr x9, [x0]      // exclusive loop
+//!        add   x10, x9, #1
+//!        stlxr w16, x10, [x0]
+//!        cbnz  w16, retry
+//!        casal x11, x12, [x1]
+//!        ldadda x13, x14, [x0]
+//! ```
+//!
+//! The base registers X0...X7 select the memory locations; fewer locations
+//! mean more accesses to the same one. Data registers are X8...X15 and the
+//! exclusive status is W16, unless the registers may alias, in which case
+//! they are drawn from X0...X16 and may coincide with each other or the base
+//! (the CONSTRAINED UNPREDICTABLE cases of STXR and the atomics included).
+
+use crate::aarch64_gen::Rng;
+use crate::aarch64_reader::{Op, Size};
+use crate::aarch64_writer::{add_imm, atomic, cas, casp, cbz, ldxr, stxr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StressKind {
+    ExclusiveLoop,
+    Cas,
+    CasPair,
+    Atomic,
+}
+
+pub const ALL_STRESS_KINDS: [StressKind; 4] =
+    [StressKind::ExclusiveLoop, StressKind::Cas, StressKind::CasPair, StressKind::Atomic];
+
+const ATOMIC_OPS: [Op; 9] = [
+    Op::A64_LDADD, Op::A64_LDCLR, Op::A64_LDEOR, Op::A64_LDSET, Op::A64_LDSMAX, Op::A64_LDSMIN, Op::A64_LDUMAX,
+    Op::A64_LDUMIN, Op::A64_SWP,
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StressOptions {
+    pub kinds: Vec<StressKind>,
+    /// Number of loops and atomics.
+    pub count: usize,
+    /// Distinct memory locations, 1...8.
+    pub locations: u8,
+    pub alias_registers: bool,
+}
+
+impl Default for StressOptions {
+    fn default() -> StressOptions {
+        StressOptions { kinds: ALL_STRESS_KINDS.to_vec(), count: 16, locations: 2, alias_registers: false }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StressSequence {
+    pub words: Vec<u32>,
+    /// The base registers; the harness points them at the locations.
+    pub bases: Vec<u8>,
+    /// Index of the first word of each loop or atomic.
+    pub starts: Vec<(usize, StressKind)>,
+}
+
+struct Picker<'a> {
+    rng: &'a mut Rng,
+    options: &'a StressOptions,
+}
+
+impl Picker<'_> {
+    fn below(&mut self, n: u32) -> u32 {
+        self.rng.next_u32() % n
+    }
+
+    fn flag(&mut self) -> bool {
+        self.below(2) == 1
+    }
+
+    fn base(&mut self) -> u8 {
+        self.below(self.options.locations as u32) as u8
+    }
+
+    fn data(&mut self) -> u8 {
+        if self.options.alias_registers { self.below(17) as u8 } else { 8 + self.below(8) as u8 }
+    }
+
+    fn pair(&mut self) -> u8 {
+        if self.options.alias_registers { 2 * self.below(8) as u8 } else { 8 + 2 * self.below(4) as u8 }
+    }
+
+    fn status(&mut self) -> u8 {
+        if self.options.alias_registers { self.below(17) as u8 } else { 16 }
+    }
+}
+
+/// A random sequence of count loops and atomics of the given kinds.
+pub fn stress_sequence(rng: &mut Rng, options: &StressOptions) -> Result<StressSequence, String> {
+    if options.kinds.is_empty() || !(1..=8).contains(&options.locations) {
+        return Err(format!("stress_sequence: no kinds or {} locations", options.locations));
+    }
+    let mut out = StressSequence { bases: (0..options.locations).collect(), ..StressSequence::default() };
+    let mut p = Picker { rng, options };
+
+    for _ in 0..options.count {
+        let kind = options.kinds[p.below(options.kinds.len() as u32) as usize];
+        out.starts.push((out.words.len(), kind));
+        let (acquire, release, rn) = (p.flag(), p.flag(), p.base());
+        match kind {
+            StressKind::ExclusiveLoop => {
+                let (rt, rt2, rs) = (p.data(), p.data(), p.status());
+                out.words.push(ldxr(acquire, rt, rn));
+                out.words.push(add_imm(rt2, rt, 1)?);
+                out.words.push(stxr(release, rs, rt2, rn));
+                out.words.push(cbz(true, true, rs, -12)?);
+            }
+            StressKind::Cas => {
+                let (rs, rt) = (p.data(), p.data());
+                out.words.push(cas(acquire, release, rs, rt, rn));
+            }
+            StressKind::CasPair => {
+                let (rs, rt) = (p.pair(), p.pair());
+                out.words.push(casp(acquire, release, rs, rt, rn)?);
+            }
+            StressKind::Atomic => {
+                let op = ATOMIC_OPS[p.below(ATOMIC_OPS.len() as u32) as usize];
+                let (rs, rt) = (p.data(), p.data());
+                out.words.push(atomic(op, Size::SZ_X, acquire, release, rs, rt, rn)?);
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_reader::decode;
+
+    #[test]
+    fn sequences_decode() {
+        let seq = stress_sequence(&mut Rng::new(3), &StressOptions { count: 64, ..StressOptions::default() }).unwrap();
+        assert_eq!(seq.starts.len(), 64);
+        for &(start, kind) in &seq.starts {
+            let inst = decode(seq.words[start]);
+            assert!(inst.rn < 2);
+            match kind {
+                StressKind::ExclusiveLoop => {
+                    assert_eq!(inst.op, Op::A64_LDXR);
+                    let cbnz = decode(seq.words[start + 3]);
+                    assert_eq!((cbnz.op, cbnz.offset), (Op::A64_CBNZ, -12));
+                }
+                StressKind::Cas => assert_eq!(inst.op, Op::A64_CAS),
+                StressKind::CasPair => assert_eq!(inst.op, Op::A64_CASP),
+                StressKind::Atomic => assert!(ATOMIC_OPS.contains(&inst.op)),
+            }
+        }
+    }
+}
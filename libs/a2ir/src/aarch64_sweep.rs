@@ -0,0 +1,121 @@
+//! Linear sweep with recovery: runs of words that don't decode (literal
+//! pools, jump tables, encrypted or compressed blobs) become data regions
+//! instead of thousands of A64_UNKNOWN instructions, and decoding resumes
+//! where instructions are plausible again.
+//!
+//! A64 words are all aligned, so resynchronizing is a matter of finding
+//! the first word of a convincing run: `resync` decodable words in a row.
+//! Isolated undecodable words shorter than `min_data` stay instructions.
+
+use std::collections::BTreeMap;
+
+use crate::aarch64_program::{Program, INST_SIZE};
+use crate::aarch64_reader::{Inst, Op};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SweepOptions {
+    /// Undecodable words in a row that start a data region.
+    pub min_data: usize,
+    /// Decodable words in a row that end one.
+    pub resync: usize,
+    /// Count UDF (notably the zero word, `udf #0`) as undecodable.
+    pub udf_is_data: bool,
+}
+
+impl Default for SweepOptions {
+    fn default() -> SweepOptions {
+        SweepOptions { min_data: 4, resync: 4, udf_is_data: true }
+    }
+}
+
+impl SweepOptions {
+    pub fn is_plausible(&self, inst: &Inst) -> bool {
+        match inst.op {
+            Op::A64_UNKNOWN | Op::A64_ERROR => false,
+            Op::A64_UDF => !self.udf_is_data,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Sweep {
+    /// The instructions outside the data regions.
+    pub code: Program,
+    /// [start, end) ranges of data, by start.
+    pub data: BTreeMap<u64, u64>,
+}
+
+impl Sweep {
+    pub fn is_data(&self, addr: u64) -> bool {
+        self.data.range(..=addr).next_back().is_some_and(|(_, &end)| addr < end)
+    }
+}
+
+/// Splits decoded words, e.g. from `decode_buffer`, into code and data.
+pub fn sweep(insts: &[(u64, Inst)], options: &SweepOptions) -> Sweep {
+    let mut out = Sweep::default();
+    // Start of the current data region, and of the run of plausible words
+    // that may end it.
+    let (mut data, mut good_from): (Option<u64>, Option<usize>) = (None, None);
+    let mut bad_from: Option<usize> = None;
+
+    for (i, (addr, inst)) in insts.iter().enumerate() {
+        let contiguous = i == 0 || insts[i - 1].0 + INST_SIZE == *addr;
+        if !contiguous {
+            if let Some(start) = data.take() {
+                out.data.insert(start, insts[i - 1].0 + INST_SIZE);
+            }
+            (good_from, bad_from) = (None, None);
+        }
+        if options.is_plausible(inst) {
+            bad_from = None;
+            let first = *good_from.get_or_insert(i);
+            if data.is_none() || i + 1 - first < options.resync {
+                if data.is_none() {
+                    out.code.insert(*addr, inst.clone());
+                }
+                continue;
+            }
+            // Resynchronized: the run is code from its first word.
+            out.data.insert(data.take().expect("in data"), insts[first].0);
+            out.code.extend(insts[first..=i].iter().cloned());
+        } else {
+            good_from = None;
+            if data.is_some() {
+                continue;
+            }
+            let first = *bad_from.get_or_insert(i);
+            out.code.insert(*addr, inst.clone());
+            if i + 1 - first >= options.min_data {
+                for (a, _) in &insts[first..=i] {
+                    out.code.remove(*a);
+                }
+                data = Some(insts[first].0);
+            }
+        }
+    }
+    if let (Some(start), Some((last, _))) = (data, insts.last()) {
+        out.data.insert(start, last + INST_SIZE);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_buffer::decode_words;
+
+    #[test]
+    fn literal_pool() {
+        let mut words = vec![0xD503201F, 0x00000000, 0xD503201F]; // a lone udf #0 stays code
+        words.extend([0x00000000, 0xFFFFFFFF, 0x00000000, 0xFFFFFFFF]); // data
+        words.extend([0xD503201F, 0x00000000]); // too short to resync
+        words.extend([0xD503201F; 4]);
+        words.push(0xD65F03C0);
+        let sweep = sweep(&decode_words(&words, 0x1000), &SweepOptions::default());
+        assert_eq!(sweep.data, BTreeMap::from([(0x100C, 0x1024)]));
+        assert_eq!(sweep.code.len(), 3 + 5);
+        assert!(sweep.is_data(0x1020) && !sweep.is_data(0x1024) && !sweep.is_data(0x1004));
+    }
+}
@@ -0,0 +1,436 @@
+//! System registers accessed by MRS and MSR, and the hooks through which an
+//! executor supplies their values (counters, ID registers, thread pointers)
+//! instead of faulting on every access.
+//!
+//! The names of implementation defined registers and SYS operations (those
+//! of Apple, Qualcomm or Ampere cores) can be registered in a SysNames, or
+//! loaded from a table, to format instructions with:
+//!
+//! ```text
+//! # encoding       name, or SYS alias mnemonic and operation
+//! s3_0_c15_c0_0    hid0_el1
+//! s1_0_c15_c2_0    impl flush_l2
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::aarch64_listing::operand_text;
+use crate::aarch64_mnemonic::inst_mnemonic;
+use crate::aarch64_operand::{operands, Operand};
+use crate::aarch64_reader::{Inst, Op, Registries};
+
+/// The encoding op0:op1:CRn:CRm:op2 of a system register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SysReg(pub u16);
+
+impl SysReg {
+    pub const fn new(op0: u16, op1: u16, crn: u16, crm: u16, op2: u16) -> SysReg {
+        SysReg((op0 << 14) | (op1 << 11) | (crn << 7) | (crm << 3) | op2)
+    }
+
+    pub const MIDR_EL1: SysReg = SysReg::new(3, 0, 0, 0, 0);
+    pub const MPIDR_EL1: SysReg = SysReg::new(3, 0, 0, 0, 5);
+    pub const REVIDR_EL1: SysReg = SysReg::new(3, 0, 0, 0, 6);
+    pub const ID_AA64PFR0_EL1: SysReg = SysReg::new(3, 0, 0, 4, 0);
+    pub const ID_AA64PFR1_EL1: SysReg = SysReg::new(3, 0, 0, 4, 1);
+    pub const ID_AA64ISAR0_EL1: SysReg = SysReg::new(3, 0, 0, 6, 0);
+    pub const ID_AA64ISAR1_EL1: SysReg = SysReg::new(3, 0, 0, 6, 1);
+    pub const ID_AA64MMFR0_EL1: SysReg = SysReg::new(3, 0, 0, 7, 0);
+    pub const SCTLR_EL1: SysReg = SysReg::new(3, 0, 1, 0, 0);
+    pub const TTBR0_EL1: SysReg = SysReg::new(3, 0, 2, 0, 0);
+    pub const TTBR1_EL1: SysReg = SysReg::new(3, 0, 2, 0, 1);
+    pub const TCR_EL1: SysReg = SysReg::new(3, 0, 2, 0, 2);
+    pub const SPSR_EL1: SysReg = SysReg::new(3, 0, 4, 0, 0);
+    pub const ELR_EL1: SysReg = SysReg::new(3, 0, 4, 0, 1);
+    pub const ESR_EL1: SysReg = SysReg::new(3, 0, 5, 2, 0);
+    pub const FAR_EL1: SysReg = SysReg::new(3, 0, 6, 0, 0);
+    pub const MAIR_EL1: SysReg = SysReg::new(3, 0, 10, 2, 0);
+    pub const VBAR_EL1: SysReg = SysReg::new(3, 0, 12, 0, 0);
+    pub const SPSR_EL2: SysReg = SysReg::new(3, 4, 4, 0, 0);
+    pub const ELR_EL2: SysReg = SysReg::new(3, 4, 4, 0, 1);
+    pub const CTR_EL0: SysReg = SysReg::new(3, 3, 0, 0, 1);
+    pub const DCZID_EL0: SysReg = SysReg::new(3, 3, 0, 0, 7);
+    pub const NZCV: SysReg = SysReg::new(3, 3, 4, 2, 0);
+    pub const DAIF: SysReg = SysReg::new(3, 3, 4, 2, 1);
+    pub const FPCR: SysReg = SysReg::new(3, 3, 4, 4, 0);
+    pub const FPSR: SysReg = SysReg::new(3, 3, 4, 4, 1);
+    pub const TPIDR_EL0: SysReg = SysReg::new(3, 3, 13, 0, 2);
+    pub const TPIDRRO_EL0: SysReg = SysReg::new(3, 3, 13, 0, 3);
+    pub const CNTFRQ_EL0: SysReg = SysReg::new(3, 3, 14, 0, 0);
+    pub const CNTPCT_EL0: SysReg = SysReg::new(3, 3, 14, 0, 1);
+    pub const CNTVCT_EL0: SysReg = SysReg::new(3, 3, 14, 0, 2);
+
+    const NAMES: [(SysReg, &'static str); 31] = [
+        (SysReg::MIDR_EL1, "midr_el1"),
+        (SysReg::MPIDR_EL1, "mpidr_el1"),
+        (SysReg::REVIDR_EL1, "revidr_el1"),
+        (SysReg::ID_AA64PFR0_EL1, "id_aa64pfr0_el1"),
+        (SysReg::ID_AA64PFR1_EL1, "id_aa64pfr1_el1"),
+        (SysReg::ID_AA64ISAR0_EL1, "id_aa64isar0_el1"),
+        (SysReg::ID_AA64ISAR1_EL1, "id_aa64isar1_el1"),
+        (SysReg::ID_AA64MMFR0_EL1, "id_aa64mmfr0_el1"),
+        (SysReg::SCTLR_EL1, "sctlr_el1"),
+        (SysReg::TTBR0_EL1, "ttbr0_el1"),
+        (SysReg::TTBR1_EL1, "ttbr1_el1"),
+        (SysReg::TCR_EL1, "tcr_el1"),
+        (SysReg::SPSR_EL1, "spsr_el1"),
+        (SysReg::ELR_EL1, "elr_el1"),
+        (SysReg::ESR_EL1, "esr_el1"),
+        (SysReg::FAR_EL1, "far_el1"),
+        (SysReg::MAIR_EL1, "mair_el1"),
+        (SysReg::VBAR_EL1, "vbar_el1"),
+        (SysReg::SPSR_EL2, "spsr_el2"),
+        (SysReg::ELR_EL2, "elr_el2"),
+        (SysReg::CTR_EL0, "ctr_el0"),
+        (SysReg::DCZID_EL0, "dczid_el0"),
+        (SysReg::NZCV, "nzcv"),
+        (SysReg::DAIF, "daif"),
+        (SysReg::FPCR, "fpcr"),
+        (SysReg::FPSR, "fpsr"),
+        (SysReg::TPIDR_EL0, "tpidr_el0"),
+        (SysReg::TPIDRRO_EL0, "tpidrro_el0"),
+        (SysReg::CNTFRQ_EL0, "cntfrq_el0"),
+        (SysReg::CNTPCT_EL0, "cntpct_el0"),
+        (SysReg::CNTVCT_EL0, "cntvct_el0"),
+    ];
+
+    /// The name of the registers listed here; others print as
+    /// s<op0>_<op1>_c<n>_c<m>_<op2>.
+    pub fn name(self) -> String {
+        match SysReg::NAMES.iter().find(|(reg, _)| *reg == self) {
+            Some((_, name)) => name.to_string(),
+            None => {
+                let f = |shift: u16, bits: u16| (self.0 >> shift) & ((1 << bits) - 1);
+                format!("s{}_{}_c{}_c{}_{}", f(14, 2), f(11, 3), f(7, 4), f(3, 4), f(0, 3))
+            }
+        }
+    }
+
+    /// The encoding of a name in the s<op0>_<op1>_c<n>_c<m>_<op2> form, in
+    /// either case; op0 is 1 for the operations of SYS.
+    pub fn parse(name: &str) -> Option<SysReg> {
+        let name = name.to_ascii_lowercase();
+        let fields: Vec<&str> = name.strip_prefix('s')?.split('_').collect();
+        let [op0, op1, crn, crm, op2] = fields[..] else {
+            return None;
+        };
+        let field = |s: &str, max: u16| s.parse::<u16>().ok().filter(|&v| v <= max);
+        Some(SysReg::new(
+            field(op0, 3)?,
+            field(op1, 7)?,
+            field(crn.strip_prefix('c')?, 15)?,
+            field(crm.strip_prefix('c')?, 15)?,
+            field(op2, 7)?,
+        ))
+    }
+
+    /// Whether EL0 can access it without trapping (ignoring the enable bits
+    /// of the counters and of CTR_EL0 and DCZID_EL0).
+    pub fn el0_accessible(self) -> bool {
+        (self.0 >> 11) & 0b111 == 3
+    }
+}
+
+impl Inst {
+    /// The register read by MRS or written by MSR (register).
+    pub fn sysreg(&self) -> Option<SysReg> {
+        matches!(self.op, Op::A64_MRS | Op::A64_MSR_REG).then_some(SysReg(self.imm as u16))
+    }
+
+    /// The operation of SYS and SYSL, as the encoding 1:op1:CRn:CRm:op2.
+    pub fn sys_op(&self) -> Option<SysReg> {
+        let sys = &self.sys;
+        matches!(self.op, Op::A64_SYS | Op::A64_SYSL).then(|| SysReg::new(1, sys.op1, sys.crn, sys.crm, sys.op2))
+    }
+
+    /// The architectural alias of SYS, e.g. ("dc", "civac", true): the
+    /// mnemonic, the operation and whether it takes Xt. The operations
+    /// without Xt are aliases only with XZR.
+    pub fn sys_alias(&self) -> Option<(&'static str, &'static str, bool)> {
+        let op = self.sys_op().filter(|_| self.op == Op::A64_SYS)?;
+        let &(_, mnemonic, operation, xt) = SYS_ALIASES.iter().find(|(o, ..)| *o == op)?;
+        (xt || self.rd == Registries::ZERO_REG).then_some((mnemonic, operation, xt))
+    }
+}
+
+/// A named operation of SYS, e.g. DC CIVAC: mnemonic "dc", operation "civac".
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SysAlias {
+    pub mnemonic: String,
+    pub operation: String,
+}
+
+/// The architectural SYS aliases, IC, DC, AT and TLBI, by SysReg::sys_op
+/// encoding, and whether the operation takes Xt.
+const SYS_ALIASES: [(SysReg, &str, &str, bool); 51] = [
+    (SysReg::new(1, 0, 7, 1, 0), "ic", "ialluis", false),
+    (SysReg::new(1, 0, 7, 5, 0), "ic", "iallu", false),
+    (SysReg::new(1, 3, 7, 5, 1), "ic", "ivau", true),
+    (SysReg::new(1, 0, 7, 6, 1), "dc", "ivac", true),
+    (SysReg::new(1, 0, 7, 6, 2), "dc", "isw", true),
+    (SysReg::new(1, 0, 7, 10, 2), "dc", "csw", true),
+    (SysReg::new(1, 0, 7, 14, 2), "dc", "cisw", true),
+    (SysReg::new(1, 3, 7, 4, 1), "dc", "zva", true),
+    (SysReg::new(1, 3, 7, 10, 1), "dc", "cvac", true),
+    (SysReg::new(1, 3, 7, 11, 1), "dc", "cvau", true),
+    (SysReg::new(1, 3, 7, 12, 1), "dc", "cvap", true),
+    (SysReg::new(1, 3, 7, 13, 1), "dc", "cvadp", true),
+    (SysReg::new(1, 3, 7, 14, 1), "dc", "civac", true),
+    (SysReg::new(1, 0, 7, 8, 0), "at", "s1e1r", true),
+    (SysReg::new(1, 0, 7, 8, 1), "at", "s1e1w", true),
+    (SysReg::new(1, 0, 7, 8, 2), "at", "s1e0r", true),
+    (SysReg::new(1, 0, 7, 8, 3), "at", "s1e0w", true),
+    (SysReg::new(1, 0, 7, 9, 0), "at", "s1e1rp", true),
+    (SysReg::new(1, 0, 7, 9, 1), "at", "s1e1wp", true),
+    (SysReg::new(1, 4, 7, 8, 0), "at", "s1e2r", true),
+    (SysReg::new(1, 4, 7, 8, 1), "at", "s1e2w", true),
+    (SysReg::new(1, 4, 7, 8, 4), "at", "s12e1r", true),
+    (SysReg::new(1, 4, 7, 8, 5), "at", "s12e1w", true),
+    (SysReg::new(1, 4, 7, 8, 6), "at", "s12e0r", true),
+    (SysReg::new(1, 4, 7, 8, 7), "at", "s12e0w", true),
+    (SysReg::new(1, 6, 7, 8, 0), "at", "s1e3r", true),
+    (SysReg::new(1, 6, 7, 8, 1), "at", "s1e3w", true),
+    (SysReg::new(1, 0, 8, 3, 0), "tlbi", "vmalle1is", false),
+    (SysReg::new(1, 0, 8, 3, 1), "tlbi", "vae1is", true),
+    (SysReg::new(1, 0, 8, 3, 2), "tlbi", "aside1is", true),
+    (SysReg::new(1, 0, 8, 3, 3), "tlbi", "vaae1is", true),
+    (SysReg::new(1, 0, 8, 3, 5), "tlbi", "vale1is", true),
+    (SysReg::new(1, 0, 8, 3, 7), "tlbi", "vaale1is", true),
+    (SysReg::new(1, 0, 8, 7, 0), "tlbi", "vmalle1", false),
+    (SysReg::new(1, 0, 8, 7, 1), "tlbi", "vae1", true),
+    (SysReg::new(1, 0, 8, 7, 2), "tlbi", "aside1", true),
+    (SysReg::new(1, 0, 8, 7, 3), "tlbi", "vaae1", true),
+    (SysReg::new(1, 0, 8, 7, 5), "tlbi", "vale1", true),
+    (SysReg::new(1, 0, 8, 7, 7), "tlbi", "vaale1", true),
+    (SysReg::new(1, 4, 8, 3, 0), "tlbi", "alle2is", false),
+    (SysReg::new(1, 4, 8, 3, 1), "tlbi", "vae2is", true),
+    (SysReg::new(1, 4, 8, 7, 0), "tlbi", "alle2", false),
+    (SysReg::new(1, 4, 8, 7, 1), "tlbi", "vae2", true),
+    (SysReg::new(1, 4, 8, 3, 4), "tlbi", "alle1is", false),
+    (SysReg::new(1, 4, 8, 7, 4), "tlbi", "alle1", false),
+    (SysReg::new(1, 4, 8, 3, 6), "tlbi", "vmalls12e1is", false),
+    (SysReg::new(1, 4, 8, 7, 6), "tlbi", "vmalls12e1", false),
+    (SysReg::new(1, 4, 8, 0, 1), "tlbi", "ipas2e1is", true),
+    (SysReg::new(1, 4, 8, 4, 1), "tlbi", "ipas2e1", true),
+    (SysReg::new(1, 6, 8, 3, 0), "tlbi", "alle3is", false),
+    (SysReg::new(1, 6, 8, 7, 0), "tlbi", "alle3", false),
+];
+
+fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!("sysreg: bad name {:?}", name));
+    }
+    Ok(())
+}
+
+/// System register names and SYS aliases on top of the architectural ones,
+/// registered at run time; registered names take precedence.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SysNames {
+    regs: BTreeMap<SysReg, String>,
+    aliases: BTreeMap<SysReg, SysAlias>,
+}
+
+impl SysNames {
+    pub fn new() -> SysNames {
+        SysNames::default()
+    }
+
+    /// Names the system register reg (op0 2 or 3) in MRS and MSR.
+    pub fn add_reg(&mut self, reg: SysReg, name: &str) -> Result<(), String> {
+        if reg.0 >> 14 < 2 {
+            return Err(format!("sysreg: {} is not a system register", reg.name()));
+        }
+        check_name(name)?;
+        self.regs.insert(reg, name.to_ascii_lowercase());
+        Ok(())
+    }
+
+    /// Makes SYS with the operation op (op0 1, see Inst::sys_op) print as
+    /// "mnemonic operation{, Xt}".
+    pub fn add_alias(&mut self, op: SysReg, mnemonic: &str, operation: &str) -> Result<(), String> {
+        if op.0 >> 14 != 1 {
+            return Err(format!("sysreg: {} is not a SYS operation", op.name()));
+        }
+        check_name(mnemonic)?;
+        check_name(operation)?;
+        let alias = SysAlias { mnemonic: mnemonic.to_ascii_lowercase(), operation: operation.to_ascii_lowercase() };
+        self.aliases.insert(op, alias);
+        Ok(())
+    }
+
+    /// Adds the entries of a table: one per line, the encoding and either a
+    /// register name or (for op0 1) a SYS mnemonic and operation; `#` starts
+    /// a comment.
+    pub fn load(&mut self, text: &str) -> Result<(), String> {
+        for (n, line) in text.lines().enumerate() {
+            let fields: Vec<&str> = line.split('#').next().unwrap_or("").split_whitespace().collect();
+            let bad = || format!("sysreg: bad line {}: {:?}", n + 1, line);
+            match fields[..] {
+                [] => {}
+                [enc, name] => self.add_reg(SysReg::parse(enc).ok_or_else(bad)?, name)?,
+                [enc, mnemonic, operation] => self.add_alias(SysReg::parse(enc).ok_or_else(bad)?, mnemonic, operation)?,
+                _ => return Err(bad()),
+            }
+        }
+        Ok(())
+    }
+
+    /// The name of reg: the registered one, else SysReg::name.
+    pub fn reg_name(&self, reg: SysReg) -> String {
+        self.regs.get(&reg).cloned().unwrap_or_else(|| reg.name())
+    }
+
+    /// The alias of the SYS operation op, registered or architectural.
+    pub fn alias(&self, op: SysReg) -> Option<SysAlias> {
+        self.aliases.get(&op).cloned().or_else(|| {
+            SYS_ALIASES.iter().find(|(o, ..)| *o == op).map(|(_, m, operation, _)| SysAlias {
+                mnemonic: m.to_string(),
+                operation: operation.to_string(),
+            })
+        })
+    }
+
+    /// inst in assembly syntax like `inst_text`, with these names for its
+    /// system register or SYS operation; `inst_text` already prints the
+    /// architectural aliases.
+    pub fn inst_text(&self, inst: &Inst, pc: u64) -> String {
+        let mut mnemonic = inst_mnemonic(inst);
+        let mut ops: Vec<String> = Vec::new();
+        for operand in operands(inst) {
+            match operand {
+                Operand::SysReg(reg) => ops.push(self.reg_name(reg)),
+                Operand::SysOp { .. } | Operand::SysOperation(_) if inst.op == Op::A64_SYS => {
+                    match inst.sys_op().and_then(|op| self.aliases.get(&op)) {
+                        Some(alias) => {
+                            mnemonic = alias.mnemonic.clone();
+                            ops.push(alias.operation.clone());
+                        }
+                        None => ops.push(operand_text(operand, pc)),
+                    }
+                }
+                _ => ops.push(operand_text(operand, pc)),
+            }
+        }
+        if ops.is_empty() { mnemonic } else { format!("{} {}", mnemonic, ops.join(", ")) }
+    }
+}
+
+/// Supplies the values of system registers to an executor.
+pub trait SysRegHooks {
+    /// The value MRS reads; None if the access faults.
+    fn read(&mut self, reg: SysReg) -> Option<u64>;
+    /// Whether the MSR is accepted.
+    fn write(&mut self, reg: SysReg, value: u64) -> bool;
+}
+
+/// Fixed values, with the writes to some registers allowed and remembered.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SysRegFile {
+    pub values: BTreeMap<SysReg, u64>,
+    pub writable: BTreeSet<SysReg>,
+}
+
+impl SysRegHooks for SysRegFile {
+    fn read(&mut self, reg: SysReg) -> Option<u64> {
+        self.values.get(&reg).copied()
+    }
+
+    fn write(&mut self, reg: SysReg, value: u64) -> bool {
+        if !self.writable.contains(&reg) {
+            return false;
+        }
+        self.values.insert(reg, value);
+        true
+    }
+}
+
+/// Performs an MRS or MSR (register) on the X registers through hooks.
+/// Ok(false) if inst is neither; Err with the register if the access faults.
+pub fn execute_sysreg(inst: &Inst, regs: &mut [u64; 31], hooks: &mut dyn SysRegHooks) -> Result<bool, SysReg> {
+    let Some(reg) = inst.sysreg() else {
+        return Ok(false);
+    };
+    let rt = inst.rd as usize;
+    if inst.op == Op::A64_MRS {
+        let value = hooks.read(reg).ok_or(reg)?;
+        if rt < 31 {
+            regs[rt] = value;
+        }
+    } else if !hooks.write(reg, if rt < 31 { regs[rt] } else { 0 }) {
+        return Err(reg);
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_reader::decode;
+
+    #[test]
+    fn counter_and_thread_pointer() {
+        let mrs = decode(0xD53BE040); // mrs x0, cntvct_el0
+        let msr = decode(0xD51BD041); // msr tpidr_el0, x1
+        assert_eq!(mrs.sysreg(), Some(SysReg::CNTVCT_EL0));
+        assert_eq!(msr.sysreg().map(SysReg::name), Some("tpidr_el0".to_string()));
+
+        let mut hooks = SysRegFile::default();
+        let mut regs = [0; 31];
+        regs[1] = 0x7000;
+        assert_eq!(execute_sysreg(&mrs, &mut regs, &mut hooks), Err(SysReg::CNTVCT_EL0));
+        hooks.values.insert(SysReg::CNTVCT_EL0, 1234);
+        hooks.writable.insert(SysReg::TPIDR_EL0);
+        assert_eq!(execute_sysreg(&mrs, &mut regs, &mut hooks), Ok(true));
+        assert_eq!(execute_sysreg(&msr, &mut regs, &mut hooks), Ok(true));
+        assert_eq!((regs[0], hooks.values[&SysReg::TPIDR_EL0]), (1234, 0x7000));
+    }
+
+    #[test]
+    fn architectural_aliases() {
+        use crate::aarch64_listing::inst_text;
+        // llvm-mc -triple=aarch64 -mattr=+v8.2a,+ccdp -disassemble
+        for (word, text) in [
+            (0xD508751F, "ic iallu"),
+            (0xD5087101, "sys #0, c7, c1, #0, x1"),
+            (0xD50B743F, "dc zva, xzr"),
+            (0xD50B7D20, "dc cvadp, x0"),
+            (0xD5087800, "at s1e1r, x0"),
+            (0xD50C78E0, "at s12e0w, x0"),
+            (0xD508831F, "tlbi vmalle1is"),
+            (0xD5088323, "tlbi vae1is, x3"),
+            (0xD50E871F, "tlbi alle3"),
+        ] {
+            assert_eq!(inst_text(&decode(word), 0), text, "{:#010x}", word);
+        }
+        assert_eq!(decode(0xD50B7E20).sys_alias(), Some(("dc", "civac", true)));
+        assert_eq!(decode(0xD52B7E20).sys_alias(), None); // sysl x0, #3, c7, c14, #1
+        assert_eq!(Op::from_mnemonic("tlbi"), vec![Op::A64_SYS]);
+
+        // Registered names take precedence.
+        let mut names = SysNames::new();
+        names.load("s1_3_c7_c14_1  dc flush").unwrap();
+        assert_eq!(names.inst_text(&decode(0xD50B7E20), 0), "dc flush, x0");
+        assert_eq!(names.inst_text(&decode(0xD508751F), 0), "ic iallu");
+    }
+
+    #[test]
+    fn vendor_names() {
+        use crate::aarch64_listing::inst_text;
+        let mrs = decode(0xD538F001); // mrs x1, s3_0_c15_c0_0
+        let op = decode(0xD508F21F); // sys #0, c15, c2, #0
+        assert_eq!(inst_text(&mrs, 0), "mrs x1, s3_0_c15_c0_0");
+        assert_eq!(inst_text(&op, 0), "sys #0, c15, c2, #0");
+
+        let mut names = SysNames::new();
+        names.load("# vendor\ns3_0_c15_c0_0  hid0_el1\nS1_0_C15_C2_0  impl flush_l2\n").unwrap();
+        assert_eq!(names.inst_text(&mrs, 0), "mrs x1, hid0_el1");
+        assert_eq!(names.inst_text(&op, 0), "impl flush_l2");
+        assert_eq!(names.inst_text(&decode(0xD50B7E20), 0), "dc civac, x0");
+        assert_eq!(names.inst_text(&decode(0xD51BD041), 0), "msr tpidr_el0, x1");
+        assert_eq!(names.inst_text(&decode(0xD5292382), 0), "sysl x2, #1, c2, c3, #4");
+        assert!(names.add_reg(SysReg::parse("s1_0_c7_c5_0").unwrap(), "x").is_err());
+        assert!(names.load("s3_8_c15_c0_0 bad").is_err());
+        assert!(names.load("s3_0_c15_c0_0 hid0-el1").is_err());
+    }
+}
@@ -0,0 +1,302 @@
+//! Latency and throughput of instructions on selected cores.
+//!
+//! Opcodes are grouped into timing classes whose numbers are taken from the
+//! Arm Software Optimization Guides (Cortex-A76, Cortex-A78, Neoverse N1,
+//! Neoverse V2). Apple does not publish this data; the Firestorm numbers are
+//! community measurements and should be treated as approximate.
+//!
+//! Latencies of variable-latency instructions (divides, square roots) are the
+//! worst case. Opcodes without a class, and classes without data for a core,
+//! yield None.
+//!
+//! Micro-op counts are estimates from the same guides: stores split into
+//! address and data, writeback adds a base update, linking branches write
+//! X30 separately, and structure loads and stores take a transfer per
+//! register plus a permute per register when interleaved. Firestorm doesn't
+//! split stores or linking branches. The MOPS memory copy and set
+//! instructions aren't decoded, so they have no estimate.
+
+use crate::aarch64_reader::{fad_get_addrmode, fad_get_prec, AddrMode, FPSize, Inst, Op};
+use crate::aarch64_reader::FlagMasks::W32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Core {
+    CortexA76,
+    CortexA78,
+    NeoverseN1,
+    NeoverseV2,
+    AppleFirestorm,
+}
+
+pub const ALL_CORES: [Core; 5] = [Core::CortexA76, Core::CortexA78, Core::NeoverseN1, Core::NeoverseV2, Core::AppleFirestorm];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TimingClass {
+    /// Single-cycle integer ALU: add/sub/logical (immediate), moves, ADR(P)
+    Alu,
+    /// ALU with shifted or extended register operand
+    AluShifted,
+    /// UBFM/SBFM aliases and extends
+    Bitfield,
+    /// BFM aliases (insert into existing value)
+    BitfieldInsert,
+    Extract,
+    Branch,
+    BranchReg,
+    Mul,
+    MulLong,
+    Div,
+    Crc,
+    Load,
+    LoadPair,
+    /// LDNP; timed like LDP where the guides don't say otherwise, but kept
+    /// apart for models of cache behaviour.
+    LoadPairNonTemporal,
+    Store,
+    StorePair,
+    /// STNP, as LoadPairNonTemporal
+    StorePairNonTemporal,
+    Atomic,
+    FpAdd,
+    FpMul,
+    FpFma,
+    FpDiv,
+    FpSqrt,
+    FpCvt,
+    FpMov,
+    SimdAlu,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timing {
+    /// Cycles until the result is available to a dependent instruction.
+    pub latency: u8,
+    /// Sustained instructions per cycle for independent instances.
+    pub throughput: f32,
+}
+
+const fn t(latency: u8, throughput: f32) -> Option<Timing> {
+    Some(Timing { latency, throughput })
+}
+
+pub fn timing_class(op: &Op) -> Option<TimingClass> {
+    use Op::*;
+    use TimingClass::*;
+    let class = match op {
+        A64_ADR | A64_ADRP | A64_ADD_IMM | A64_CMN_IMM | A64_MOV_SP | A64_SUB_IMM | A64_CMP_IMM
+        | A64_AND_IMM | A64_ORR_IMM | A64_EOR_IMM | A64_TST_IMM | A64_MOVK | A64_MOV_IMM
+        | A64_LSL_IMM | A64_LSR_IMM | A64_ASR_IMM | A64_ROR_IMM | A64_MOV_REG
+        | A64_ADC | A64_SBC | A64_NGC | A64_CSEL | A64_CSINC | A64_CINC | A64_CSET | A64_CSINV
+        | A64_CINV | A64_CSETM | A64_CSNEG | A64_CNEG | A64_CCMN_REG | A64_CCMP_REG
+        | A64_CCMN_IMM | A64_CCMP_IMM | A64_LSLV | A64_LSRV | A64_ASRV | A64_RORV
+        | A64_RBIT | A64_REV16 | A64_REV | A64_REV32 | A64_CLZ | A64_CLS => Alu,
+        A64_AND_SHIFTED | A64_TST_SHIFTED | A64_BIC | A64_ORR_SHIFTED | A64_ORN | A64_MVN
+        | A64_EOR_SHIFTED | A64_EON | A64_ADD_SHIFTED | A64_CMN_SHIFTED | A64_SUB_SHIFTED
+        | A64_NEG | A64_CMP_SHIFTED | A64_ADD_EXT | A64_CMN_EXT | A64_SUB_EXT | A64_CMP_EXT => AluShifted,
+        A64_SBFM | A64_SBFIZ | A64_SBFX | A64_UBFM | A64_UBFIZ | A64_UBFX | A64_EXTEND => Bitfield,
+        A64_BFM | A64_BFC | A64_BFI | A64_BFXIL => BitfieldInsert,
+        A64_EXTR => Extract,
+        A64_B | A64_BL | A64_BCOND | A64_CBZ | A64_CBNZ | A64_TBZ | A64_TBNZ => Branch,
+        A64_BR | A64_BLR | A64_RET | A64_BRA | A64_BLRA | A64_RETA => BranchReg,
+        A64_MADD | A64_MUL | A64_MSUB | A64_MNEG => Mul,
+        A64_SMADDL | A64_SMULL | A64_SMSUBL | A64_SMNEGL | A64_SMULH | A64_UMADDL | A64_UMULL
+        | A64_UMSUBL | A64_UMNEGL | A64_UMULH => MulLong,
+        A64_UDIV | A64_SDIV => Div,
+        A64_CRC32B | A64_CRC32H | A64_CRC32W | A64_CRC32X | A64_CRC32CB | A64_CRC32CH
+        | A64_CRC32CW | A64_CRC32CX => Crc,
+        A64_LDR | A64_LDR_FP | A64_LDXR | A64_LDAPR | A64_LD1R => Load,
+        A64_LDP | A64_LDP_FP | A64_LDXP => LoadPair,
+        A64_LDNP | A64_LDNP_FP => LoadPairNonTemporal,
+        A64_STR | A64_STR_FP | A64_STXR => Store,
+        A64_STP | A64_STP_FP | A64_STXP => StorePair,
+        A64_STNP | A64_STNP_FP => StorePairNonTemporal,
+        A64_LDADD | A64_LDCLR | A64_LDEOR | A64_LDSET | A64_LDSMAX | A64_LDSMIN | A64_LDUMAX
+        | A64_LDUMIN | A64_SWP | A64_CAS | A64_CASP => Atomic,
+        A64_FADD | A64_FSUB | A64_FABS | A64_FNEG | A64_FMAX | A64_FMAXNM | A64_FMIN | A64_FMINNM
+        | A64_FADD_VEC | A64_FSUB_VEC | A64_FABD_VEC | A64_FMAX_VEC | A64_FMIN_VEC
+        | A64_FMAXNM_VEC | A64_FMINNM_VEC | A64_FCMP_REG | A64_FCMP_ZERO | A64_FCMPE_REG
+        | A64_FCMPE_ZERO | A64_FCCMP | A64_FCCMPE | A64_FCSEL => FpAdd,
+        A64_FMUL | A64_FMULX | A64_FNMUL | A64_FMUL_VEC | A64_FMUL_ELEM | A64_FMULX_VEC
+        | A64_FMULX_ELEM => FpMul,
+        A64_FMADD | A64_FMSUB | A64_FNMADD | A64_FNMSUB | A64_FMLA_VEC | A64_FMLA_ELEM
+        | A64_FMLS_VEC | A64_FMLS_ELEM => FpFma,
+        A64_FDIV | A64_FDIV_VEC => FpDiv,
+        A64_FSQRT | A64_FSQRT_VEC => FpSqrt,
+        A64_FCVT_GPR | A64_FCVT_VEC | A64_CVTF | A64_CVTF_VEC | A64_FJCVTZS | A64_FRINT
+        | A64_FRINTX | A64_FCVT_H | A64_FCVT_S | A64_FCVT_D => FpCvt,
+        A64_FMOV_VEC2GPR | A64_FMOV_GPR2VEC | A64_FMOV_TOP2GPR | A64_FMOV_GPR2TOP
+        | A64_FMOV_REG | A64_FMOV_IMM | A64_FMOV_VEC => FpMov,
+        A64_AND_VEC | A64_BIC_VEC_IMM | A64_BIC_VEC_REG | A64_BIF | A64_BIT | A64_BSL
+        | A64_EOR_VEC | A64_NOT_VEC | A64_ORN_VEC | A64_ORR_VEC_IMM | A64_ORR_VEC_REG
+        | A64_MOV_VEC | A64_ADD_VEC | A64_SUB_VEC | A64_NEG_VEC | A64_ABS_VEC | A64_CMEQ_REG
+        | A64_CMEQ_ZERO | A64_CMGE_REG | A64_CMGE_ZERO | A64_CMGT_REG | A64_CMGT_ZERO
+        | A64_CMHI_REG | A64_CMHS_REG | A64_CMLE_ZERO | A64_CMLT_ZERO | A64_CMTST
+        | A64_MOVI | A64_DUP_ELEM | A64_INS_ELEM | A64_ZIP1 | A64_ZIP2 | A64_UZP1 | A64_UZP2
+        | A64_TRN1 | A64_TRN2 | A64_EXT => SimdAlu,
+        _ => return None,
+    };
+    Some(class)
+}
+
+/// Timing of a class on a core. x64 selects the 64-bit (X-form or double
+/// precision) variant where it differs.
+pub fn class_timing(core: Core, class: TimingClass, x64: bool) -> Option<Timing> {
+    use Core::*;
+    use TimingClass::*;
+    match (core, class) {
+        (CortexA76 | CortexA78 | NeoverseN1, Alu) => t(1, 3.0),
+        (CortexA76 | CortexA78 | NeoverseN1, AluShifted) => t(2, 1.0),
+        (CortexA76 | CortexA78 | NeoverseN1, Bitfield) => t(1, 3.0),
+        (CortexA76 | CortexA78 | NeoverseN1, BitfieldInsert) => t(2, 1.0),
+        (CortexA76 | CortexA78 | NeoverseN1, Extract) => t(3, 1.0),
+        (CortexA76 | CortexA78 | NeoverseN1, Branch | BranchReg) => t(1, 1.0),
+        (CortexA76 | CortexA78 | NeoverseN1, Mul) => t(2, 1.0),
+        (CortexA76 | CortexA78 | NeoverseN1, MulLong) => t(if x64 { 4 } else { 2 }, 1.0),
+        (CortexA76 | CortexA78 | NeoverseN1, Div) => t(if x64 { 20 } else { 12 }, 0.05),
+        (CortexA76 | CortexA78 | NeoverseN1, Crc) => t(2, 1.0),
+        (CortexA76 | CortexA78 | NeoverseN1, Load) => t(4, 2.0),
+        (CortexA76 | CortexA78 | NeoverseN1, LoadPair | LoadPairNonTemporal) => t(4, 1.0),
+        (CortexA76 | CortexA78 | NeoverseN1, Store) => t(1, 2.0),
+        (CortexA76 | CortexA78 | NeoverseN1, StorePair | StorePairNonTemporal) => t(1, 1.0),
+        (CortexA76 | CortexA78 | NeoverseN1, FpAdd) => t(2, 2.0),
+        (CortexA76 | CortexA78 | NeoverseN1, FpMul) => t(3, 2.0),
+        (CortexA76 | CortexA78 | NeoverseN1, FpFma) => t(4, 2.0),
+        (CortexA76 | CortexA78 | NeoverseN1, FpDiv) => t(if x64 { 15 } else { 10 }, if x64 { 1.0 / 14.0 } else { 1.0 / 9.0 }),
+        (CortexA76 | CortexA78 | NeoverseN1, FpSqrt) => t(if x64 { 17 } else { 9 }, if x64 { 1.0 / 16.0 } else { 1.0 / 8.0 }),
+        (CortexA76 | CortexA78 | NeoverseN1, FpCvt) => t(3, 1.0),
+        (CortexA76 | CortexA78 | NeoverseN1, FpMov) => t(3, 1.0),
+        (CortexA76 | CortexA78 | NeoverseN1, SimdAlu) => t(2, 2.0),
+
+        (NeoverseV2, Alu | Bitfield) => t(1, 6.0),
+        (NeoverseV2, AluShifted) => t(2, 4.0),
+        (NeoverseV2, BitfieldInsert | Extract) => t(2, 2.0),
+        (NeoverseV2, Branch | BranchReg) => t(1, 2.0),
+        (NeoverseV2, Mul) => t(2, 2.0),
+        (NeoverseV2, MulLong) => t(if x64 { 3 } else { 2 }, 2.0),
+        (NeoverseV2, Div) => t(if x64 { 20 } else { 12 }, if x64 { 1.0 / 20.0 } else { 1.0 / 12.0 }),
+        (NeoverseV2, Crc) => t(2, 1.0),
+        (NeoverseV2, Load) => t(4, 3.0),
+        (NeoverseV2, LoadPair | LoadPairNonTemporal) => t(4, 1.5),
+        (NeoverseV2, Store) => t(1, 2.0),
+        (NeoverseV2, StorePair | StorePairNonTemporal) => t(1, 1.0),
+        (NeoverseV2, FpAdd) => t(2, 4.0),
+        (NeoverseV2, FpMul) => t(3, 4.0),
+        (NeoverseV2, FpFma) => t(4, 4.0),
+        (NeoverseV2, FpDiv) => t(if x64 { 12 } else { 10 }, if x64 { 1.0 / 5.0 } else { 1.0 / 3.0 }),
+        (NeoverseV2, FpSqrt) => t(if x64 { 16 } else { 10 }, if x64 { 1.0 / 7.0 } else { 1.0 / 4.0 }),
+        (NeoverseV2, FpCvt | FpMov) => t(3, 2.0),
+        (NeoverseV2, SimdAlu) => t(2, 4.0),
+
+        (AppleFirestorm, Alu | Bitfield) => t(1, 6.0),
+        (AppleFirestorm, AluShifted) => t(2, 2.0),
+        (AppleFirestorm, Branch | BranchReg) => t(1, 2.0),
+        (AppleFirestorm, Mul | MulLong) => t(3, 2.0),
+        (AppleFirestorm, Div) => t(if x64 { 9 } else { 7 }, 0.5),
+        (AppleFirestorm, Load) => t(4, 3.0),
+        (AppleFirestorm, Store) => t(1, 2.0),
+        (AppleFirestorm, FpAdd) => t(3, 4.0),
+        (AppleFirestorm, FpMul | FpFma) => t(4, 4.0),
+        (AppleFirestorm, FpDiv) => t(if x64 { 10 } else { 8 }, 1.0),
+        (AppleFirestorm, SimdAlu) => t(2, 4.0),
+
+        _ => None,
+    }
+}
+
+/// Timing of a decoded instruction on core.
+pub fn timing(core: Core, inst: &Inst) -> Option<Timing> {
+    let class = timing_class(&inst.op)?;
+    let x64 = match class {
+        TimingClass::FpDiv | TimingClass::FpSqrt => fad_get_prec(inst.flags) == FPSize::FSZ_D,
+        _ => inst.flags & W32 == 0,
+    };
+    class_timing(core, class, x64)
+}
+
+/// Micro-ops the instruction cracks into on core.
+pub fn uops(core: Core, inst: &Inst) -> Option<u8> {
+    use Op::*;
+    let splits = core != Core::AppleFirestorm;
+    let stores = if splits { 2 } else { 1 };
+    let mode = fad_get_addrmode(inst.flags);
+    let base = match inst.op {
+        A64_LD1_MULT | A64_ST1_MULT | A64_LD1_SINGLE | A64_ST1_SINGLE | A64_LD1R => Some(1),
+        A64_LD2_MULT | A64_ST2_MULT | A64_LD2_SINGLE | A64_ST2_SINGLE | A64_LD2R => Some(2),
+        A64_LD3_MULT | A64_ST3_MULT | A64_LD3_SINGLE | A64_ST3_SINGLE | A64_LD3R => Some(3),
+        A64_LD4_MULT | A64_ST4_MULT | A64_LD4_SINGLE | A64_ST4_SINGLE | A64_LD4R => Some(4),
+        _ => None,
+    };
+    if let Some(structures) = base {
+        let regs = inst.simd_ldst.nreg as u8;
+        let permutes = if structures > 1 { regs } else { 0 };
+        return Some(regs + permutes + (mode == AddrMode::AM_POST) as u8);
+    }
+    let q_pair = matches!(inst.op, A64_LDP_FP | A64_STP_FP | A64_LDNP_FP | A64_STNP_FP) && fad_get_prec(inst.flags) == FPSize::FSZ_Q;
+    let class = timing_class(&inst.op)?;
+    let count = match class {
+        TimingClass::Store => stores,
+        TimingClass::StorePair | TimingClass::StorePairNonTemporal => stores * (1 + q_pair as u8),
+        TimingClass::LoadPair | TimingClass::LoadPairNonTemporal => 1 + q_pair as u8,
+        TimingClass::Atomic => 2,
+        TimingClass::Branch | TimingClass::BranchReg if splits && matches!(inst.op, A64_BL | A64_BLR | A64_BLRA) => 2,
+        _ => 1,
+    };
+    let memory = matches!(class, TimingClass::Load | TimingClass::LoadPair | TimingClass::Store | TimingClass::StorePair);
+    let writeback = memory && matches!(mode, AddrMode::AM_PRE | AddrMode::AM_POST);
+    Some(count + writeback as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_buffer::decode_words;
+
+    fn decode(word: u32) -> Inst {
+        decode_words(&[word], 0x1000).remove(0).1
+    }
+
+    #[test]
+    fn classes_and_widths() {
+        let cases: [(u32, Option<TimingClass>, Core, Option<Timing>); 7] = [
+            (0x91000420, Some(TimingClass::Alu), Core::CortexA76, t(1, 3.0)), // add x0, x1, #1
+            (0xF8008C20, Some(TimingClass::Store), Core::NeoverseN1, t(1, 2.0)), // str x0, [x1, #8]!
+            (0xA8400440, Some(TimingClass::LoadPairNonTemporal), Core::NeoverseV2, t(4, 1.5)), // ldnp x0, x1, [x2]
+            (0xA9400440, Some(TimingClass::LoadPair), Core::AppleFirestorm, None), // ldp x0, x1, [x2]
+            (0xB8200041, Some(TimingClass::Atomic), Core::CortexA76, None), // ldadd w0, w1, [x2]
+            (0x4CDF8800, None, Core::CortexA76, None), // ld2 {v0.4s, v1.4s}, [x0], #32
+            (0xD4000001, None, Core::CortexA76, None), // svc #0
+        ];
+        for (word, class, core, expected) in cases {
+            let inst = decode(word);
+            assert_eq!(timing_class(&inst.op), class, "{:#010x}", word);
+            assert_eq!(timing(core, &inst), expected, "{:#010x}", word);
+        }
+
+        // X and W forms, double and single precision.
+        assert_eq!(class_timing(Core::NeoverseN1, TimingClass::Div, true), t(20, 0.05));
+        assert_eq!(class_timing(Core::NeoverseN1, TimingClass::Div, false), t(12, 0.05));
+        assert_eq!(class_timing(Core::CortexA78, TimingClass::FpDiv, true), t(15, 1.0 / 14.0));
+        assert_eq!(class_timing(Core::CortexA78, TimingClass::FpDiv, false), t(10, 1.0 / 9.0));
+        assert_eq!(class_timing(Core::AppleFirestorm, TimingClass::MulLong, true), t(3, 2.0));
+        assert!(ALL_CORES.iter().all(|&core| class_timing(core, TimingClass::Alu, true).is_some()));
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn micro_ops() {
+        let cases: [(u32, Option<u8>, Option<u8>); 7] = [
+            (0x91000420, Some(1), Some(1)), // add x0, x1, #1
+            (0xF8008C20, Some(3), Some(2)), // str x0, [x1, #8]!: address, data, writeback
+            (0x94000040, Some(2), Some(1)), // bl .+0x100
+            (0x4CDF8800, Some(5), Some(5)), // ld2 {v0.4s, v1.4s}, [x0], #32
+            (0xAD000400, Some(4), Some(2)), // stp q0, q1, [x0]
+            (0xB8200041, Some(2), Some(2)), // ldadd w0, w1, [x2]
+            (0xD4000001, None, None), // svc #0
+        ];
+        for (word, a76, firestorm) in cases {
+            let inst = decode(word);
+            assert_eq!(uops(Core::CortexA76, &inst), a76, "{:#010x}", word);
+            assert_eq!(uops(Core::AppleFirestorm, &inst), firestorm, "{:#010x}", word);
+        }
+    }
+}
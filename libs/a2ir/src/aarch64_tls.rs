@@ -0,0 +1,150 @@
+//! Thread-local storage accesses, recognized by the code the TLS models
+//! produce, so that the call to a TLS descriptor's resolver isn't left as an
+//! opaque indirect call:
+//!
+//! ```text
+//! adrp x0, :tlsdesc:var         // TLSDESC (general/local dynamic)
+//! ldr  x1, [x0, #:tlsdesc_lo12:var]
+//! add  x0, x0, #:tlsdesc_lo12:var
+//! blr  x1                       // returns the offset from TPIDR_EL0
+//!
+//! adrp x0, :gottprel:var        // initial exec
+//! ldr  x0, [x0, #:gottprel_lo12:var]
+//!
+//! mrs  x8, tpidr_el0            // local exec
+//! add  x0, x8, #:tprel_hi12:var, lsl #12
+//! add  x0, x0, #:tprel_lo12_nc:var
+//! ```
+//!
+//! Variables are named by the dynamic relocations of the descriptors
+//! (R_AARCH64_TLSDESC) and GOT slots (R_AARCH64_TLS_TPREL64); local exec
+//! only gives the offset.
+
+use std::collections::BTreeMap;
+
+use crate::aarch64_constprop::{propagate, ConstOptions};
+use crate::aarch64_dynlink::Relocation;
+use crate::aarch64_reader::{Inst, Op};
+use crate::aarch64_sysreg::SysReg;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TlsModel {
+    Descriptor,
+    InitialExec,
+    LocalExec,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TlsAccess {
+    /// The BLR of a descriptor, the LDR of a GOT slot, or the last ADD to
+    /// the thread pointer.
+    pub pc: u64,
+    pub model: TlsModel,
+    /// The descriptor or GOT slot.
+    pub slot: Option<u64>,
+    /// The offset from the thread pointer, for local exec.
+    pub offset: Option<u64>,
+    pub symbol: Option<String>,
+}
+
+fn symbols_of(relocs: &[Relocation], typ: &str) -> BTreeMap<u64, Option<String>> {
+    relocs.iter().filter(|r| r.typ == typ).map(|r| (r.offset, r.symbol.clone())).collect()
+}
+
+/// The TLS accesses of straight-line code.
+pub fn tls_accesses(block: &[(u64, Inst)], relocs: &[Relocation]) -> Vec<TlsAccess> {
+    let descriptors = symbols_of(relocs, "R_AARCH64_TLSDESC");
+    let tprel_slots = symbols_of(relocs, "R_AARCH64_TLS_TPREL64");
+    let resolved = propagate(block, &ConstOptions::default());
+    let mut out = Vec::new();
+
+    for window in block.windows(4) {
+        let [(_, adrp), (ldr_pc, ldr), (_, add), (blr_pc, blr)] = window else { continue };
+        let shape = adrp.op == Op::A64_ADRP
+            && adrp.rd == 0
+            && ldr.op == Op::A64_LDR
+            && ldr.rn == 0
+            && add.op == Op::A64_ADD_IMM
+            && (add.rd, add.rn) == (0, 0)
+            && blr.op == Op::A64_BLR
+            && blr.rn == ldr.rd;
+        if shape {
+            let slot = resolved.data_addresses.get(ldr_pc).copied();
+            let symbol = slot.and_then(|s| descriptors.get(&s).cloned().flatten());
+            out.push(TlsAccess { pc: *blr_pc, model: TlsModel::Descriptor, slot, offset: None, symbol });
+        }
+    }
+
+    // Registers holding the thread pointer plus an offset, and the access
+    // that computed them.
+    let mut tp: BTreeMap<u8, (u64, Option<usize>)> = BTreeMap::new();
+    for (pc, inst) in block {
+        if let Some(slot) = resolved.data_addresses.get(pc).filter(|s| tprel_slots.contains_key(s)) {
+            let symbol = tprel_slots[slot].clone();
+            out.push(TlsAccess { pc: *pc, model: TlsModel::InitialExec, slot: Some(*slot), offset: None, symbol });
+        }
+        let derived = match inst.op {
+            Op::A64_MRS if inst.sysreg() == Some(SysReg::TPIDR_EL0) => Some((0, None)),
+            Op::A64_ADD_IMM => tp.get(&inst.rn).map(|&(offset, access)| {
+                let offset = offset.wrapping_add(inst.imm);
+                // add x0, x0, #lo continues the access of add x0, x8, #hi.
+                match access.filter(|_| inst.rd == inst.rn) {
+                    Some(i) => {
+                        out[i].pc = *pc;
+                        out[i].offset = Some(offset);
+                        (offset, Some(i))
+                    }
+                    None => {
+                        out.push(TlsAccess { pc: *pc, model: TlsModel::LocalExec, slot: None, offset: Some(offset), symbol: None });
+                        (offset, Some(out.len() - 1))
+                    }
+                }
+            }),
+            _ => None,
+        };
+        match derived {
+            Some(value) => {
+                tp.insert(inst.rd, value);
+            }
+            None => {
+                tp.remove(&inst.rd);
+            }
+        }
+    }
+    out.sort_by_key(|a| a.pc);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_buffer::decode_words;
+    use crate::aarch64_dynlink::parse_readelf_relocs;
+
+    #[test]
+    fn three_models() {
+        let relocs = parse_readelf_relocs(
+            "0000000000011008  0000000500000407 R_AARCH64_TLSDESC      0000000000000000 errno@GLIBC_PRIVATE + 0\n\
+             0000000000011010  0000000600000406 R_AARCH64_TLS_TPREL64  0000000000000000 counter + 0\n",
+        )
+        .unwrap();
+        let block = decode_words(&[
+            0xB0000000, // adrp x0, .+0x1000       (0x11000)
+            0xF9400401, // ldr x1, [x0, #8]
+            0x91002000, // add x0, x0, #8
+            0xD63F0020, // blr x1
+            0xB0000000, // adrp x0, .+0x1000
+            0xF9400800, // ldr x0, [x0, #0x10]
+            0xD53BD048, // mrs x8, tpidr_el0
+            0x91400500, // add x0, x8, #1, lsl #12
+            0x91004000, // add x0, x0, #0x10
+        ], 0x10000);
+        let accesses = tls_accesses(&block, &relocs);
+        let summary: Vec<_> = accesses.iter().map(|a| (a.pc, a.model, a.slot, a.offset, a.symbol.as_deref())).collect();
+        assert_eq!(summary, vec![
+            (0x1000C, TlsModel::Descriptor, Some(0x11008), None, Some("errno")),
+            (0x10014, TlsModel::InitialExec, Some(0x11010), None, Some("counter")),
+            (0x10020, TlsModel::LocalExec, None, Some(0x1010), None),
+        ]);
+    }
+}
@@ -0,0 +1,149 @@
+//! The conventions of BRK immediates: compilers, sanitizers and kernels
+//! encode what failed in the immediate of the trap, so a crash at a BRK
+//! can be named after the check instead of `brk #imm`.
+//!
+//! ```text
+//! brk #0x1       // __builtin_trap (LLVM)
+//! brk #0x5500    // -fsanitize-trap: ubsan add overflow
+//! brk #0x8230    // kcfi: bad call through x16 (type in w17)
+//! brk #0x902     // asan: bad 4-byte load
+//! ```
+//!
+//! Semihosting on AArch64 is HLT #0xF000 (see aarch64_semihost), whose
+//! calls are classified too; BRK #0xF000 is a debug trap.
+
+use crate::aarch64_hotpatch::PATCH_BRK_IMM;
+use crate::aarch64_kernel::BUG_BRK_IMM;
+use crate::aarch64_listing::Annotate;
+use crate::aarch64_reader::{Inst, Op};
+use crate::aarch64_semihost::SEMIHOSTING_HLT;
+
+const ASAN_BRK_IMM: u16 = 0x900;
+const UBSAN_BRK_IMM: u16 = 0x5500;
+const KCFI_BRK_IMM: u16 = 0x8000;
+const PTRAUTH_BRK_IMM: u16 = 0xC470;
+
+/// The checks of -fsanitize-trap, in clang's SanitizerHandler order.
+const UBSAN_CHECKS: [&str; 26] = [
+    "add overflow", "builtin unreachable", "cfi check fail", "divrem overflow", "dynamic type cache miss",
+    "float cast overflow", "function type mismatch", "implicit conversion", "invalid builtin", "invalid objc cast",
+    "load invalid value", "missing return", "mul overflow", "negate overflow", "nullability arg",
+    "nullability return", "nonnull arg", "nonnull return", "out of bounds", "pointer overflow",
+    "shift out of bounds", "sub overflow", "type mismatch", "alignment assumption", "vla bound not positive",
+    "bounds safety",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TrapKind {
+    /// BRK #0, the software breakpoint of GDB and LLDB
+    Breakpoint,
+    /// __builtin_trap: BRK #1 from LLVM (also Rust's abort and Swift's
+    /// runtime checks), BRK #0x3E8 from GCC
+    Trap,
+    /// BRK #0xF000: llvm.debugtrap, MSVC's __debugbreak
+    DebugTrap,
+    /// BRK #0xF003: __fastfail on Windows
+    FastFail,
+    /// HLT #0xF000
+    Semihosting,
+    /// BRK #0x800: BUG() and WARN() of Linux
+    Bug,
+    /// KASAN and HWASan reports: BRK #0x900 with the access in the low
+    /// byte; size is None when the access size is in X1.
+    Asan { write: bool, recover: bool, size: Option<u32> },
+    /// BRK #0x55xx: a -fsanitize-trap check, see `ubsan_check`
+    Ubsan(u8),
+    /// BRK #0x8000 | type << 5 | target: a failed KCFI check of an
+    /// indirect call through Xtarget, with the expected type in Wtype
+    Kcfi { target: u8, typ: u8 },
+    /// BRK #0xC470 | key: a failed authentication where there is no
+    /// FEAT_FPAC; key is 0...3 for IA, IB, DA, DB.
+    PtrAuth { key: u8 },
+    /// The BRK window of a hot patch (aarch64_hotpatch)
+    HotPatch,
+}
+
+/// The name of a -fsanitize-trap check.
+pub fn ubsan_check(kind: u8) -> Option<&'static str> {
+    UBSAN_CHECKS.get(kind as usize).copied()
+}
+
+impl TrapKind {
+    pub fn name(self) -> String {
+        match self {
+            TrapKind::Breakpoint => "breakpoint".to_string(),
+            TrapKind::Trap => "trap".to_string(),
+            TrapKind::DebugTrap => "debug trap".to_string(),
+            TrapKind::FastFail => "fastfail".to_string(),
+            TrapKind::Semihosting => "semihosting call".to_string(),
+            TrapKind::Bug => "BUG or WARN".to_string(),
+            TrapKind::Asan { write, size, .. } => {
+                let size = size.map_or(String::new(), |s| format!("{}-byte ", s));
+                format!("asan: bad {}{}", size, if write { "store" } else { "load" })
+            }
+            TrapKind::Ubsan(kind) => match ubsan_check(kind) {
+                Some(check) => format!("ubsan: {}", check),
+                None => format!("ubsan: check {:#x}", kind),
+            },
+            TrapKind::Kcfi { target, typ } => format!("kcfi: bad call through x{} (type in w{})", target, typ),
+            TrapKind::PtrAuth { key } => {
+                format!("pointer authentication failure ({})", ["ia", "ib", "da", "db"][key as usize & 0b11])
+            }
+            TrapKind::HotPatch => "hot patch window".to_string(),
+        }
+    }
+}
+
+/// What the BRK (or HLT) traps for, by the conventions above.
+pub fn trap_kind(inst: &Inst) -> Option<TrapKind> {
+    let imm = inst.exception_imm()?;
+    if inst.op == Op::A64_HLT {
+        return (imm == SEMIHOSTING_HLT).then_some(TrapKind::Semihosting);
+    }
+    if inst.op != Op::A64_BRK {
+        return None;
+    }
+    Some(match imm {
+        0 => TrapKind::Breakpoint,
+        1 | 0x3E8 => TrapKind::Trap,
+        0xF000 => TrapKind::DebugTrap,
+        0xF003 => TrapKind::FastFail,
+        BUG_BRK_IMM => TrapKind::Bug,
+        PATCH_BRK_IMM => TrapKind::HotPatch,
+        _ if imm & 0xFF00 == ASAN_BRK_IMM => {
+            let size_log2 = imm & 0xF;
+            TrapKind::Asan { write: imm & 0x10 != 0, recover: imm & 0x20 != 0, size: (size_log2 <= 4).then(|| 1 << size_log2) }
+        }
+        _ if imm & 0xFF00 == UBSAN_BRK_IMM => TrapKind::Ubsan(imm as u8),
+        _ if imm & 0xFC00 == KCFI_BRK_IMM => TrapKind::Kcfi { target: (imm & 0x1F) as u8, typ: ((imm >> 5) & 0x1F) as u8 },
+        _ if imm & 0xFFFC == PTRAUTH_BRK_IMM => TrapKind::PtrAuth { key: (imm & 0b11) as u8 },
+        _ => return None,
+    })
+}
+
+/// Names the traps in listings.
+pub struct TrapComments;
+
+impl Annotate for TrapComments {
+    fn comment(&self, _addr: u64, inst: &Inst) -> Option<String> {
+        trap_kind(inst).map(TrapKind::name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_reader::decode;
+
+    #[test]
+    fn brk_conventions() {
+        let brk = |imm: u32| decode(0xD4200000 | imm << 5);
+        assert_eq!(trap_kind(&brk(1)), Some(TrapKind::Trap));
+        assert_eq!(trap_kind(&brk(0x5500)).map(TrapKind::name), Some("ubsan: add overflow".to_string()));
+        assert_eq!(trap_kind(&brk(0x8230)), Some(TrapKind::Kcfi { target: 16, typ: 17 }));
+        assert_eq!(trap_kind(&brk(0x912)).map(TrapKind::name), Some("asan: bad 4-byte store".to_string()));
+        assert_eq!(trap_kind(&brk(0xC472)), Some(TrapKind::PtrAuth { key: 2 }));
+        assert_eq!(trap_kind(&brk(0x1234)), None);
+        assert_eq!(trap_kind(&decode(0xD45E0000)), Some(TrapKind::Semihosting)); // hlt #0xf000
+    }
+}
@@ -0,0 +1,167 @@
+//! Explanations of crashes: given the faulting pc and the registers of a
+//! minidump or core dump, the instruction that faulted, the address it
+//! accessed or jumped to, the register that held the bad value and the
+//! instructions that computed it, for crash bucketing tools to key on.
+//!
+//! ```text
+//! 0x1000: ldr  x1, [x0, #8]    // slice
+//! 0x1004: add  x1, x1, #16     // slice
+//! 0x1008: ldr  x5, [x1]        // fault: x1 = 0x10, address 0x10
+//! ```
+//!
+//! The address is what the instruction computed from the registers; the
+//! fault address the kernel reports (si_addr) may differ in the bits a
+//! tagged-address ABI ignores. Accesses whose address depends on the vector
+//! length (SVE by `mul vl`) or on vector elements (gathers) have none.
+
+use std::collections::BTreeSet;
+
+use crate::aarch64_cfg::Cfg;
+use crate::aarch64_defuse::{gpr, is_memory, is_sve_memory, Loc};
+use crate::aarch64_listing::inst_text;
+use crate::aarch64_program::Program;
+use crate::aarch64_reader::{fad_get_addrmode, AddrMode, Inst, Op};
+use crate::aarch64_relocate::target_address;
+use crate::aarch64_slice::backward_slice;
+use crate::aarch64_trap::{trap_kind, TrapKind};
+
+/// The general purpose registers of a thread context.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Registers {
+    pub x: [u64; 31],
+    pub sp: u64,
+}
+
+impl Registers {
+    pub fn get(&self, loc: Loc) -> Option<u64> {
+        match loc {
+            Loc::X(r) => self.x.get(r as usize).copied(),
+            Loc::SP => Some(self.sp),
+            _ => None,
+        }
+    }
+}
+
+/// The operand of the faulting instruction that held the bad value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Culprit {
+    /// The base register of a load or store (a Z register for gathers and
+    /// scatters by vector plus immediate).
+    Base(Loc),
+    /// The register an indirect branch or return jumped through.
+    Target(Loc),
+    /// The instruction itself: UDF, BRK, an unallocated encoding, or an
+    /// instruction that faults for other reasons (e.g. a trapped system
+    /// register access).
+    Instruction,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation {
+    pub pc: u64,
+    pub op: Op,
+    /// The instruction in assembly syntax.
+    pub text: String,
+    pub culprit: Culprit,
+    /// The value of the culprit register.
+    pub value: Option<u64>,
+    /// The address accessed or jumped to.
+    pub address: Option<u64>,
+    /// The instructions the culprit register was computed from; empty for
+    /// Culprit::Instruction.
+    pub slice: BTreeSet<u64>,
+    /// The check a BRK failed, by the immediate.
+    pub trap: Option<TrapKind>,
+}
+
+impl Explanation {
+    /// One line, e.g. "ldr x5, [x1]: x1 = 0x10, address 0x10".
+    pub fn summary(&self) -> String {
+        let reg = match self.culprit {
+            Culprit::Base(loc) | Culprit::Target(loc) => loc,
+            Culprit::Instruction => {
+                let what = self.trap.map_or("faulting instruction".to_string(), TrapKind::name);
+                return format!("{}: {}", self.text, what);
+            }
+        };
+        let value = self.value.map_or("?".to_string(), |v| format!("{:#x}", v));
+        let address = self.address.map_or("unknown".to_string(), |a| format!("{:#x}", a));
+        format!("{}: {} = {}, address {}", self.text, loc_text(reg), value, address)
+    }
+}
+
+fn loc_text(loc: Loc) -> String {
+    match loc {
+        Loc::X(r) => format!("x{}", r),
+        Loc::SP => "sp".to_string(),
+        Loc::Z(r) => format!("z{}", r),
+        other => format!("{:?}", other),
+    }
+}
+
+/// The culprit of the fault at pc, and the address it implies.
+fn culprit(inst: &Inst, pc: u64, regs: &Registers) -> (Culprit, Option<u64>) {
+    let base = gpr(inst.rn).unwrap_or(Loc::SP);
+    let value = regs.get(base);
+    match inst.op {
+        Op::A64_BR | Op::A64_BLR | Op::A64_BRA | Op::A64_BLRA | Op::A64_RET => (Culprit::Target(base), value),
+        Op::A64_RETA => (Culprit::Target(Loc::X(30)), regs.get(Loc::X(30))),
+        op if is_memory(&op) => {
+            let address = match fad_get_addrmode(inst.flags) {
+                AddrMode::AM_LITERAL => return (Culprit::Instruction, target_address(inst, pc)),
+                AddrMode::AM_SIMPLE | AddrMode::AM_POST => value,
+                AddrMode::AM_OFF_IMM | AddrMode::AM_PRE => value.map(|v| v.wrapping_add(inst.offset as u64)),
+                _ => None,
+            };
+            (Culprit::Base(base), address)
+        }
+        op if op >= Op::A64_LD1_MULT && op <= Op::A64_LD4R => (Culprit::Base(base), value),
+        op if is_sve_memory(op) => {
+            let ldst = &inst.sve_ldst;
+            if ldst.vector {
+                (Culprit::Base(Loc::Z(inst.rn)), None)
+            } else {
+                let plain = ldst.mode == AddrMode::AM_OFF_IMM && inst.offset == 0;
+                (Culprit::Base(base), value.filter(|_| plain))
+            }
+        }
+        _ => (Culprit::Instruction, None),
+    }
+}
+
+/// Explains the fault at pc with the registers at the time.
+pub fn explain(program: &Program, cfg: &Cfg, pc: u64, regs: &Registers) -> Result<Explanation, String> {
+    let inst = program.get(pc).ok_or(format!("triage: no instruction at {:#x}", pc))?;
+    let (culprit, address) = culprit(inst, pc, regs);
+    let (value, slice) = match culprit {
+        Culprit::Base(loc) | Culprit::Target(loc) => (regs.get(loc), backward_slice(program, cfg, pc, loc)),
+        Culprit::Instruction => (None, BTreeSet::new()),
+    };
+    Ok(Explanation { pc, op: inst.op, text: inst_text(inst, pc), culprit, value, address, slice, trap: trap_kind(inst) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn null_member_access() {
+        let program = Program::from_words(&[
+            0xF9400401, // 0x1000: ldr x1, [x0, #8]
+            0x91004021, //         add x1, x1, #16
+            0xF9400025, //         ldr x5, [x1]
+            0xD65F03C0, //         ret
+        ], 0x1000);
+        let cfg = Cfg::build(&program);
+        let mut regs = Registers::default();
+        regs.x[1] = 0x10;
+        let why = explain(&program, &cfg, 0x1008, &regs).unwrap();
+        assert_eq!((why.culprit, why.address), (Culprit::Base(Loc::X(1)), Some(0x10)));
+        assert_eq!(why.slice, BTreeSet::from([0x1000, 0x1004]));
+        assert_eq!(why.summary(), "ldr x5, [x1]: x1 = 0x10, address 0x10");
+
+        let at_first = explain(&program, &cfg, 0x1000, &regs).unwrap();
+        assert_eq!((at_first.address, at_first.slice.len()), (Some(8), 0));
+        assert!(explain(&program, &cfg, 0x2000, &regs).is_err());
+    }
+}
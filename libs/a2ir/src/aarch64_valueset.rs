@@ -0,0 +1,227 @@
+//! Bounded value sets of the X registers over the blocks of each function,
+//! resolving the targets of BR and BLR that constant propagation alone
+//! misses: pointers loaded from known addresses (vtable slots, function
+//! pointer tables in read-only data) and targets that differ by path.
+//!
+//! ```text
+//!       cbz  x0, 1f
+//!       adrp x8, f               // x8 = { f }
+//!       b    2f
+//! 1:    adrp x8, table
+//!       ldr  x8, [x8, #8]        // x8 = { table[1] }
+//! 2:    blr  x8                  // { f, table[1] }
+//! ```
+//!
+//! A register holds a set of at most `max_values` values, or is unknown.
+//! The same opcodes as in `aarch64_constprop` are evaluated, plus 32- and
+//! 64-bit loads, through a reader of the image's memory the caller
+//! supplies, from known addresses. Calls make the caller-saved registers
+//! unknown. The results are the edges `Cfg::indirect_targets` holds, and
+//! the callees of `CallGraph::resolve_indirect`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::aarch64_abi::{with_role, Role};
+use crate::aarch64_branch::BranchKind;
+use crate::aarch64_cfg::Cfg;
+use crate::aarch64_defuse::{def_use, Loc};
+use crate::aarch64_functions::Function;
+use crate::aarch64_pac::PacConfig;
+use crate::aarch64_program::Program;
+use crate::aarch64_reader::ExtendType::{UXTW, UXTX};
+use crate::aarch64_reader::FlagMasks::W32;
+use crate::aarch64_reader::{fad_get_addrmode, fad_get_mem_extend, AddrMode, Inst, Op, Registries};
+use crate::aarch64_relocate::target_address;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValueSetOptions {
+    /// Values a register may hold before it counts as unknown.
+    pub max_values: usize,
+    /// Strip PACs from loaded and branched-to pointers.
+    pub pac: Option<PacConfig>,
+}
+
+impl Default for ValueSetOptions {
+    fn default() -> ValueSetOptions {
+        ValueSetOptions { max_values: 16, pac: None }
+    }
+}
+
+/// The value sets of the known registers; missing ones are unknown.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct State {
+    regs: BTreeMap<u8, BTreeSet<u64>>,
+}
+
+impl State {
+    fn get(&self, r: u8) -> Option<BTreeSet<u64>> {
+        match r {
+            Registries::ZERO_REG => Some(BTreeSet::from([0])),
+            _ => self.regs.get(&r).cloned(),
+        }
+    }
+
+    fn join(&mut self, other: &State, max: usize) {
+        self.regs.retain(|r, values| match other.regs.get(r) {
+            Some(more) => {
+                values.extend(more);
+                values.len() <= max
+            }
+            None => false,
+        });
+    }
+}
+
+/// The values inst writes to Rd, if it is one of the evaluated opcodes
+/// and its inputs are known.
+fn evaluate(state: &State, inst: &Inst, pc: u64, read: &dyn Fn(u64) -> Option<u64>, options: &ValueSetOptions) -> Option<BTreeSet<u64>> {
+    let each = |f: &dyn Fn(u64) -> Option<u64>| -> Option<BTreeSet<u64>> { state.get(inst.rn)?.into_iter().map(f).collect() };
+    let values = match inst.op {
+        Op::A64_ADR | Op::A64_ADRP => BTreeSet::from([target_address(inst, pc)?]),
+        Op::A64_MOV_IMM => BTreeSet::from([inst.imm]),
+        Op::A64_MOVK => {
+            let lsl = inst.movk.lsl;
+            state.get(inst.rd)?.into_iter().map(|v| (v & !(0xFFFF << lsl)) | ((inst.movk.imm16 as u64) << lsl)).collect()
+        }
+        Op::A64_ADD_IMM => each(&|v| Some(v.wrapping_add(inst.imm)))?,
+        Op::A64_SUB_IMM => each(&|v| Some(v.wrapping_sub(inst.imm)))?,
+        Op::A64_ORR_IMM => each(&|v| Some(v | inst.imm))?,
+        Op::A64_AND_IMM => each(&|v| Some(v & inst.imm))?,
+        Op::A64_MOV_SP => state.get(inst.rn)?,
+        Op::A64_LDR => {
+            let memext = fad_get_mem_extend(inst.flags);
+            let offset = match fad_get_addrmode(inst.flags) {
+                AddrMode::AM_OFF_IMM | AddrMode::AM_PRE => inst.offset as u64,
+                AddrMode::AM_SIMPLE | AddrMode::AM_POST => 0,
+                AddrMode::AM_LITERAL => return load(target_address(inst, pc)?, memext, read, options).map(|v| BTreeSet::from([v])),
+                _ => return None,
+            };
+            each(&|base| load(strip(base, false, options).wrapping_add(offset), memext, read, options))?
+        }
+        _ => return None,
+    };
+    (values.len() <= options.max_values).then_some(values)
+}
+
+fn strip(ptr: u64, code: bool, options: &ValueSetOptions) -> u64 {
+    options.pac.map_or(ptr, |pac| pac.strip(ptr, code))
+}
+
+fn load(addr: u64, memext: u8, read: &dyn Fn(u64) -> Option<u64>, options: &ValueSetOptions) -> Option<u64> {
+    match memext {
+        UXTX => Some(strip(read(addr)?, true, options)),
+        UXTW => Some(read(addr)? & 0xFFFF_FFFF),
+        _ => None,
+    }
+}
+
+fn transfer(state: &mut State, inst: &Inst, pc: u64, read: &dyn Fn(u64) -> Option<u64>, options: &ValueSetOptions) {
+    let values = evaluate(state, inst, pc, read, options);
+    for def in def_use(inst).defs {
+        if let Loc::X(r) = def {
+            state.regs.remove(&r);
+        }
+    }
+    if let Some(mut values) = values {
+        if inst.flags & W32 != 0 && inst.op != Op::A64_LDR {
+            values = values.into_iter().map(|v| v & 0xFFFF_FFFF).collect();
+        }
+        state.regs.insert(inst.rd, values);
+    }
+    if inst.branch_kind().is_some_and(BranchKind::is_call) {
+        for loc in with_role(Role::CallerSaved) {
+            if let Loc::X(r) = loc {
+                state.regs.remove(&r);
+            }
+        }
+    }
+}
+
+/// The targets of the BR, BLR, BRA and BLRA of the functions that resolve
+/// to at most max_values addresses, by pc. read gives the 8 bytes at an
+/// address of the image, little-endian, if they are known and constant.
+pub fn resolve_indirect(program: &Program, cfg: &Cfg, functions: &[Function], read: &dyn Fn(u64) -> Option<u64>,
+                        options: &ValueSetOptions) -> BTreeMap<u64, Vec<u64>> {
+    let mut out = BTreeMap::new();
+    for function in functions {
+        let mut preds: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+        for &start in &function.blocks {
+            for next in cfg.successors(start).into_iter().filter(|n| function.blocks.contains(n)) {
+                preds.entry(next).or_default().push(start);
+            }
+        }
+
+        let mut outs: BTreeMap<u64, State> = BTreeMap::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &start in &function.blocks {
+                let Some(mut state) = entry_state(function, start, &preds, &outs, options) else { continue };
+                for (pc, inst) in program.range(start..cfg.blocks[&start].end) {
+                    transfer(&mut state, inst, pc, read, options);
+                }
+                if outs.get(&start) != Some(&state) {
+                    outs.insert(start, state);
+                    changed = true;
+                }
+            }
+        }
+
+        for &start in &function.blocks {
+            let Some(mut state) = entry_state(function, start, &preds, &outs, options) else { continue };
+            for (pc, inst) in program.range(start..cfg.blocks[&start].end) {
+                if matches!(inst.op, Op::A64_BR | Op::A64_BLR | Op::A64_BRA | Op::A64_BLRA) {
+                    if let Some(targets) = state.get(inst.rn) {
+                        out.insert(pc, targets.into_iter().map(|t| strip(t, true, options)).collect());
+                    }
+                }
+                transfer(&mut state, inst, pc, read, options);
+            }
+        }
+    }
+    out
+}
+
+/// The state at the start of the block, None if no predecessor has been
+/// reached yet.
+fn entry_state(function: &Function, start: u64, preds: &BTreeMap<u64, Vec<u64>>, outs: &BTreeMap<u64, State>,
+               options: &ValueSetOptions) -> Option<State> {
+    if start == function.entry {
+        return Some(State::default());
+    }
+    let mut incoming = preds.get(&start).into_iter().flatten().filter_map(|p| outs.get(p));
+    let mut state = incoming.next()?.clone();
+    incoming.for_each(|s| state.join(s, options.max_values));
+    Some(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_callgraph::{CallGraph, Callee};
+    use crate::aarch64_functions::functions;
+
+    #[test]
+    fn targets_by_path_and_table() {
+        let program = Program::from_words(&[
+            0xB4000080, // 0x1000: cbz x0, 0x1010
+            0x10000188, //         adr x8, 0x1034
+            0x14000004, //         b 0x1018
+            0xD503201F, //         nop
+            0x10000308, // 0x1010: adr x8, 0x1070
+            0xF9400508, //         ldr x8, [x8, #8]
+            0xD63F0100, // 0x1018: blr x8
+            0xD65F03C0, //         ret
+        ], 0x1000);
+        let read = |addr: u64| (addr == 0x1078).then_some(0x5000);
+        let mut cfg = Cfg::build(&program);
+        let functions = functions(&mut cfg, &BTreeSet::from([0x1000]));
+        let targets = resolve_indirect(&program, &cfg, &functions, &read, &ValueSetOptions::default());
+        assert_eq!(targets, BTreeMap::from([(0x1018, vec![0x1034, 0x5000])]));
+
+        let mut graph = CallGraph::build(&program, &[(0x1000, 0x1020)], &[], &[]);
+        graph.resolve_indirect(&targets);
+        assert_eq!(graph.sites[0].callee, Callee::Candidates(vec![0x1034, 0x5000]));
+        assert_eq!(graph.calls_to(0x5000).len(), 1);
+    }
+}
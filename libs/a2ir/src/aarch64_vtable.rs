@@ -0,0 +1,269 @@
+//! C++ classes of Itanium ABI binaries: vtables and the RTTI they point to,
+//! the functions that install them (constructors and destructors store the
+//! address point into the object), and the virtual calls through them.
+//!
+//! ```text
+//! vtable:  offset_to_top          // 0, or -offset of a secondary base
+//!          typeinfo               // 0 with -fno-rtti
+//!    vptr: &A::f, &A::g, ...      // the address point; objects hold vptr
+//!
+//! ldr  x8, [x0]                   // the vptr of the object in x0
+//! ldr  x8, [x8, #8]               // slot 1
+//! blr  x8                         // a virtual call: slot 1 of any vtable
+//! ```
+//!
+//! The crate reads neither ELF nor symbols, so vtables are found by
+//! scanning data (with its relocations applied) the caller supplies for
+//! the layout above: a small multiple-of-8 offset to top, no or a
+//! plausible typeinfo, then function entries. A table of function pointers
+//! behind two zero words looks the same. Type info names its class with a
+//! mangled name; a single-inheritance base is the type info after the name,
+//! and virtual or multiple inheritance isn't decoded.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::aarch64_cfg::Cfg;
+use crate::aarch64_constprop::{propagate, ConstOptions};
+use crate::aarch64_defuse::{def_use, Loc};
+use crate::aarch64_functions::Function;
+use crate::aarch64_listing::Annotate;
+use crate::aarch64_program::Program;
+use crate::aarch64_reader::{fad_get_addrmode, AddrMode, Inst, Op};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Vtable {
+    /// Address of the offset to top.
+    pub addr: u64,
+    pub offset_to_top: i64,
+    pub typeinfo: Option<u64>,
+    /// The virtual functions by slot.
+    pub functions: Vec<u64>,
+}
+
+impl Vtable {
+    /// The address point, which objects hold.
+    pub fn vptr(&self) -> u64 {
+        self.addr + 16
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeInfo {
+    pub addr: u64,
+    /// The mangled name, e.g. "3Foo" or "N2ns3BarE".
+    pub name: String,
+    /// The type info of the base class, with single inheritance.
+    pub base: Option<u64>,
+}
+
+struct Data<'a> {
+    bytes: &'a [u8],
+    base: u64,
+}
+
+impl Data<'_> {
+    fn contains(&self, addr: u64) -> bool {
+        addr >= self.base && addr - self.base < self.bytes.len() as u64
+    }
+
+    fn word(&self, addr: u64) -> Option<u64> {
+        let offset = addr.checked_sub(self.base)? as usize;
+        let bytes = self.bytes.get(offset..offset.checked_add(8)?)?;
+        Some(u64::from_le_bytes(bytes.try_into().expect("8 bytes")))
+    }
+
+    fn name(&self, addr: u64) -> Option<String> {
+        let offset = addr.checked_sub(self.base)? as usize;
+        let bytes = self.bytes.get(offset..)?;
+        let len = bytes.iter().position(|&b| b == 0)?;
+        let name = &bytes[..len];
+        let plausible = (1..=256).contains(&len) && name.iter().all(|b| b.is_ascii_alphanumeric() || b"_$.".contains(b));
+        plausible.then(|| String::from_utf8_lossy(name).into_owned())
+    }
+
+    fn type_info(&self, addr: u64, depth: usize) -> Option<TypeInfo> {
+        if depth == 0 || !addr.is_multiple_of(8) {
+            return None;
+        }
+        let name = self.name(self.word(addr + 8)?)?;
+        let base = self.word(addr + 16).filter(|&b| b != addr && self.type_info(b, depth - 1).is_some());
+        Some(TypeInfo { addr, name, base })
+    }
+}
+
+/// The type info at addr of the data at data_base, if it looks like one.
+pub fn type_info(data: &[u8], data_base: u64, addr: u64) -> Option<TypeInfo> {
+    Data { bytes: data, base: data_base }.type_info(addr, 8)
+}
+
+/// The vtables in the data at data_base, whose slots point to entries.
+pub fn find_vtables(data: &[u8], data_base: u64, entries: &BTreeSet<u64>) -> Vec<Vtable> {
+    let data = Data { bytes: data, base: data_base };
+    let mut out = Vec::new();
+    let mut addr = data_base.next_multiple_of(8);
+    while let Some(word) = data.word(addr) {
+        let offset_to_top = word as i64;
+        let typeinfo = data.word(addr + 8).unwrap_or(1);
+        let plausible = (-0x10000..=0).contains(&offset_to_top) && offset_to_top % 8 == 0
+            && (typeinfo == 0 || (data.contains(typeinfo) && data.type_info(typeinfo, 8).is_some()));
+        let functions: Vec<u64> = if plausible {
+            (0..).map_while(|i| data.word(addr + 16 + 8 * i).filter(|f| entries.contains(f))).collect()
+        } else {
+            Vec::new()
+        };
+        if functions.is_empty() {
+            addr += 8;
+            continue;
+        }
+        let next = addr + 16 + 8 * functions.len() as u64;
+        out.push(Vtable { addr, offset_to_top, typeinfo: (typeinfo != 0).then_some(typeinfo), functions });
+        addr = next;
+    }
+    out
+}
+
+/// The functions that store the address point of each vtable (by vtable
+/// address), in entry order: its constructors and destructors.
+pub fn vtable_writers(program: &Program, cfg: &Cfg, functions: &[Function], vtables: &[Vtable]) -> BTreeMap<u64, Vec<u64>> {
+    let vptrs: BTreeMap<u64, u64> = vtables.iter().map(|v| (v.vptr(), v.addr)).collect();
+    let mut out: BTreeMap<u64, Vec<u64>> = BTreeMap::new();
+    for function in functions {
+        let mut stored = BTreeSet::new();
+        for (start, end) in function.ranges(cfg) {
+            let block: Vec<_> = program.range(start..end).map(|(a, i)| (a, i.clone())).collect();
+            let resolved = propagate(&block, &ConstOptions::default());
+            let mut regs: BTreeMap<u8, u64> = BTreeMap::new();
+            for (pc, inst) in &block {
+                if inst.op == Op::A64_STR {
+                    if let Some(vtable) = regs.get(&inst.rd).and_then(|v| vptrs.get(v)) {
+                        stored.insert(*vtable);
+                    }
+                }
+                for def in def_use(inst).defs {
+                    if let Loc::X(r) = def {
+                        regs.remove(&r);
+                    }
+                }
+                if let Some(&value) = resolved.values.get(pc) {
+                    regs.insert(inst.rd, value);
+                }
+            }
+        }
+        for vtable in stored {
+            out.entry(vtable).or_default().push(function.entry);
+        }
+    }
+    out
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VirtualCall {
+    pub pc: u64,
+    pub slot: usize,
+    /// The functions in that slot of the vtables long enough to have it.
+    pub candidates: Vec<u64>,
+}
+
+/// A register loaded from memory, in the pattern of a virtual call.
+#[derive(Clone, Copy)]
+enum Loaded {
+    Vptr,
+    Slot(usize),
+}
+
+fn load_offset(inst: &Inst) -> Option<u64> {
+    match (inst.op, fad_get_addrmode(inst.flags)) {
+        (Op::A64_LDR, AddrMode::AM_SIMPLE) => Some(0),
+        (Op::A64_LDR, AddrMode::AM_OFF_IMM) => Some(inst.offset as u64),
+        _ => None,
+    }
+}
+
+/// The virtual calls of the functions: BLR through a slot of a vptr loaded
+/// from an object, in the same block.
+pub fn virtual_calls(program: &Program, cfg: &Cfg, functions: &[Function], vtables: &[Vtable]) -> Vec<VirtualCall> {
+    let mut out = Vec::new();
+    let blocks: BTreeSet<u64> = functions.iter().flat_map(|f| f.blocks.iter().copied()).collect();
+    for start in blocks {
+        let mut regs: BTreeMap<u8, Loaded> = BTreeMap::new();
+        for (pc, inst) in program.range(start..cfg.blocks[&start].end) {
+            let loaded = match (load_offset(inst), regs.get(&inst.rn)) {
+                (Some(offset), Some(Loaded::Vptr)) if offset % 8 == 0 => Some(Loaded::Slot(offset as usize / 8)),
+                (Some(0), _) => Some(Loaded::Vptr),
+                _ => None,
+            };
+            if matches!(inst.op, Op::A64_BLR | Op::A64_BLRA) {
+                if let Some(&Loaded::Slot(slot)) = regs.get(&inst.rn) {
+                    let mut candidates: Vec<u64> = vtables.iter().filter_map(|v| v.functions.get(slot).copied()).collect();
+                    candidates.sort();
+                    candidates.dedup();
+                    out.push(VirtualCall { pc, slot, candidates });
+                }
+            }
+            for def in def_use(inst).defs {
+                if let Loc::X(r) = def {
+                    regs.remove(&r);
+                }
+            }
+            if let Some(loaded) = loaded {
+                regs.insert(inst.rd, loaded);
+            }
+        }
+    }
+    out
+}
+
+/// Comments naming the slot and candidates of virtual calls.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VirtualCalls {
+    pub calls: BTreeMap<u64, VirtualCall>,
+}
+
+impl VirtualCalls {
+    pub fn new(calls: Vec<VirtualCall>) -> VirtualCalls {
+        VirtualCalls { calls: calls.into_iter().map(|c| (c.pc, c)).collect() }
+    }
+}
+
+impl Annotate for VirtualCalls {
+    fn comment(&self, addr: u64, _inst: &Inst) -> Option<String> {
+        let call = self.calls.get(&addr)?;
+        let candidates: Vec<String> = call.candidates.iter().map(|c| format!("{:#x}", c)).collect();
+        Some(format!("virtual slot {}: {}", call.slot, candidates.join(", ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_functions::functions;
+
+    #[test]
+    fn vtable_constructor_and_call() {
+        let program = Program::from_words(&[
+            0x10080088, // 0x1000: adr x8, 0x11010      (A's vptr)
+            0xF9000008, //         str x8, [x0]
+            0xD65F03C0, //         ret
+            0xF9400008, // 0x100c: ldr x8, [x0]
+            0xF9400508, //         ldr x8, [x8, #8]
+            0xD63F0100, //         blr x8
+            0xD65F03C0, //         ret
+            0xD65F03C0, // 0x101c: A::f
+            0xD65F03C0, // 0x1020: A::g
+        ], 0x1000);
+        // 0x10ff0: the type info of A, then its vtable and name.
+        let mut data: Vec<u8> = [0u64, 0x11028, 0, 0x10FF0, 0x101C, 0x1020, 0].iter().flat_map(|w| w.to_le_bytes()).collect();
+        data.extend(b"1A\0");
+        let entries = BTreeSet::from([0x1000, 0x100C, 0x101C, 0x1020]);
+        let vtables = find_vtables(&data, 0x10FF0, &entries);
+        assert_eq!(vtables, vec![Vtable { addr: 0x11000, offset_to_top: 0, typeinfo: Some(0x10FF0), functions: vec![0x101C, 0x1020] }]);
+        assert_eq!(type_info(&data, 0x10FF0, 0x10FF0), Some(TypeInfo { addr: 0x10FF0, name: "1A".to_string(), base: None }));
+
+        let mut cfg = Cfg::build(&program);
+        let functions = functions(&mut cfg, &entries);
+        assert_eq!(vtable_writers(&program, &cfg, &functions, &vtables), BTreeMap::from([(0x11000, vec![0x1000])]));
+        let calls = virtual_calls(&program, &cfg, &functions, &vtables);
+        assert_eq!(calls, vec![VirtualCall { pc: 0x1014, slot: 1, candidates: vec![0x1020] }]);
+        assert_eq!(VirtualCalls::new(calls).comment(0x1014, program.get(0x1014).unwrap()), Some("virtual slot 1: 0x1020".to_string()));
+    }
+}
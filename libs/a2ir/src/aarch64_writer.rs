@@ -0,0 +1,398 @@
+//! Encoder for the subset of A64 instructions that the transformation passes
+//! (relocation, trampolines, patching) need to emit. Every function returns the
+//! 32-bit instruction word or an error string if an operand is not encodable.
+
+use crate::aarch64_reader::{fad_get_addrmode, fad_get_cond, fad_get_mem_extend, fad_get_prec, AddrMode, ExtendType, FPSize, Inst, MemOrdering, Op,
+    Registries};
+use crate::aarch64_reader::FlagMasks::W32;
+
+pub const NOP: u32 = 0xD503201F;
+
+/// Re-maps our split register numbering (see Registries) back to the 5-bit field.
+fn reg(r: u8) -> u32 {
+    if r == Registries::STACK_POINTER { 31 } else { (r & 0b11111) as u32 }
+}
+
+/// Checks that offset is a multiple of 4 and fits into a signed bits-wide field
+/// after scaling, returning the truncated field value.
+fn branch_imm(offset: i64, bits: u32) -> Result<u32, String> {
+    if offset % 4 != 0 {
+        return Err(format!("branch offset {} is not a multiple of 4", offset));
+    }
+    let imm = offset / 4;
+    let limit = 1i64 << (bits - 1);
+    if imm < -limit || imm >= limit {
+        return Err(format!("branch offset {} out of range for a {}-bit immediate", offset, bits));
+    }
+    Ok((imm as u32) & ((1u32 << bits) - 1))
+}
+
+pub fn b(offset: i64) -> Result<u32, String> {
+    Ok(0x14000000 | branch_imm(offset, 26)?)
+}
+
+pub fn bl(offset: i64) -> Result<u32, String> {
+    Ok(0x94000000 | branch_imm(offset, 26)?)
+}
+
+pub fn bcond(cond: u8, offset: i64) -> Result<u32, String> {
+    Ok(0x54000000 | (branch_imm(offset, 19)? << 5) | (cond & 0b1111) as u32)
+}
+
+pub fn cbz(nonzero: bool, w32: bool, rt: u8, offset: i64) -> Result<u32, String> {
+    let sf = if w32 { 0 } else { 1 << 31 };
+    let op = if nonzero { 0x35000000 } else { 0x34000000 };
+    Ok(sf | op | (branch_imm(offset, 19)? << 5) | reg(rt))
+}
+
+pub fn tbz(nonzero: bool, rt: u8, bit: u32, offset: i64) -> Result<u32, String> {
+    if bit > 63 {
+        return Err(format!("test bit {} out of range", bit));
+    }
+    let op = if nonzero { 0x37000000 } else { 0x36000000 };
+    Ok(((bit >> 5) << 31) | op | ((bit & 0b11111) << 19) | (branch_imm(offset, 14)? << 5) | reg(rt))
+}
+
+pub fn brk(imm16: u16) -> u32 {
+    0xD4200000 | ((imm16 as u32) << 5)
+}
+
+pub fn br(rn: u8) -> u32 {
+    0xD61F0000 | (reg(rn) << 5)
+}
+
+pub fn blr(rn: u8) -> u32 {
+    0xD63F0000 | (reg(rn) << 5)
+}
+
+pub fn ret(rn: u8) -> u32 {
+    0xD65F0000 | (reg(rn) << 5)
+}
+
+/// ADR Xd, PC+offset (±1 MiB)
+pub fn adr(rd: u8, offset: i64) -> Result<u32, String> {
+    if !(-(1 << 20)..(1 << 20)).contains(&offset) {
+        return Err(format!("ADR offset {} out of range", offset));
+    }
+    Ok(pcrel(0x10000000, rd, offset as u32))
+}
+
+/// ADRP Xd, PC+offset. The offset is in bytes and must be page-aligned (±4 GiB).
+pub fn adrp(rd: u8, offset: i64) -> Result<u32, String> {
+    if offset & 0xFFF != 0 {
+        return Err(format!("ADRP offset {:#x} is not page-aligned", offset));
+    }
+    let pages = offset >> 12;
+    if !(-(1 << 20)..(1 << 20)).contains(&pages) {
+        return Err(format!("ADRP offset {:#x} out of range", offset));
+    }
+    Ok(pcrel(0x90000000, rd, pages as u32))
+}
+
+fn pcrel(base: u32, rd: u8, imm21: u32) -> u32 {
+    let immlo = imm21 & 0b11;
+    let immhi = (imm21 >> 2) & 0x7FFFF;
+    base | (immlo << 29) | (immhi << 5) | reg(rd)
+}
+
+/// ADD Xd|SP, Xn|SP, #imm12
+pub fn add_imm(rd: u8, rn: u8, imm12: u32) -> Result<u32, String> {
+    if imm12 > 0xFFF {
+        return Err(format!("ADD immediate {:#x} out of range", imm12));
+    }
+    Ok(0x91000000 | (imm12 << 10) | (reg(rn) << 5) | reg(rd))
+}
+
+/// MOVZ Xd, #imm16, LSL #(16 * hw)
+pub fn movz(rd: u8, imm16: u16, hw: u8) -> u32 {
+    0xD2800000 | (((hw & 0b11) as u32) << 21) | ((imm16 as u32) << 5) | reg(rd)
+}
+
+/// MOVK Xd, #imm16, LSL #(16 * hw)
+pub fn movk(rd: u8, imm16: u16, hw: u8) -> u32 {
+    0xF2800000 | (((hw & 0b11) as u32) << 21) | ((imm16 as u32) << 5) | reg(rd)
+}
+
+/// Materializes a 64-bit constant into Xd with a MOVZ followed by MOVKs for
+/// every non-zero halfword (1 to 4 instructions).
+pub fn mov_imm64(rd: u8, value: u64) -> Vec<u32> {
+    let mut out = vec![movz(rd, value as u16, 0)];
+    for hw in 1..4u8 {
+        let half = (value >> (16 * hw as u64)) as u16;
+        if half != 0 {
+            out.push(movk(rd, half, hw));
+        }
+    }
+    out
+}
+
+/// LDR (literal) of a general purpose register; memext selects LDR Wt
+/// (UXTW), LDR Xt (UXTX) or LDRSW Xt (SXTW).
+pub fn ldr_literal(memext: u8, rt: u8, offset: i64) -> Result<u32, String> {
+    let base = match memext {
+        ExtendType::UXTW => 0x18000000,
+        ExtendType::UXTX => 0x58000000,
+        ExtendType::SXTW => 0x98000000,
+        _ => return Err(format!("no literal load for memory extension {}", memext)),
+    };
+    Ok(base | (branch_imm(offset, 19)? << 5) | reg(rt))
+}
+
+/// LDR (literal) of a SIMD&FP register of precision prec (FSZ_S, FSZ_D or FSZ_Q).
+pub fn ldr_fp_literal(prec: u8, rt: u8, offset: i64) -> Result<u32, String> {
+    let base = match prec {
+        FPSize::FSZ_S => 0x1C000000,
+        FPSize::FSZ_D => 0x5C000000,
+        FPSize::FSZ_Q => 0x9C000000,
+        _ => return Err(format!("no literal load for FP precision {}", prec)),
+    };
+    Ok(base | (branch_imm(offset, 19)? << 5) | reg(rt))
+}
+
+/// PRFM <prfop>, PC+offset
+pub fn prfm_literal(prfop: u8, offset: i64) -> Result<u32, String> {
+    Ok(0xD8000000 | (branch_imm(offset, 19)? << 5) | (prfop & 0b11111) as u32)
+}
+
+/// LDR Wt|Xt or LDRSW Xt, [Xn] (unsigned offset form, offset #0)
+pub fn ldr_base(memext: u8, rt: u8, rn: u8) -> Result<u32, String> {
+    let base = match memext {
+        ExtendType::UXTW => 0xB9400000,
+        ExtendType::UXTX => 0xF9400000,
+        ExtendType::SXTW => 0xB9800000,
+        _ => return Err(format!("no register load for memory extension {}", memext)),
+    };
+    Ok(base | (reg(rn) << 5) | reg(rt))
+}
+
+/// LDR St|Dt|Qt, [Xn] (unsigned offset form, offset #0)
+pub fn ldr_fp_base(prec: u8, rt: u8, rn: u8) -> Result<u32, String> {
+    let base = match prec {
+        FPSize::FSZ_S => 0xBD400000,
+        FPSize::FSZ_D => 0xFD400000,
+        FPSize::FSZ_Q => 0x3DC00000,
+        _ => return Err(format!("no register load for FP precision {}", prec)),
+    };
+    Ok(base | (reg(rn) << 5) | reg(rt))
+}
+
+/// STP Xt, Xt2, [Xn|SP, #offset]! (64-bit, pre-indexed)
+pub fn stp_pre(rt: u8, rt2: u8, rn: u8, offset: i64) -> Result<u32, String> {
+    Ok(0xA9800000 | pair_imm(offset)? | (reg(rt2) << 10) | (reg(rn) << 5) | reg(rt))
+}
+
+/// LDP Xt, Xt2, [Xn|SP], #offset (64-bit, post-indexed)
+pub fn ldp_post(rt: u8, rt2: u8, rn: u8, offset: i64) -> Result<u32, String> {
+    Ok(0xA8C00000 | pair_imm(offset)? | (reg(rt2) << 10) | (reg(rn) << 5) | reg(rt))
+}
+
+fn pair_imm(offset: i64) -> Result<u32, String> {
+    if offset % 8 != 0 || !(-512..512).contains(&offset) {
+        return Err(format!("pair offset {} not encodable", offset));
+    }
+    Ok((((offset / 8) as u32) & 0x7F) << 15)
+}
+
+/// STADD Xs, [Xn|SP] (LDADD with the result discarded to XZR)
+pub fn stadd(rs: u8, rn: u8) -> u32 {
+    0xF8200000 | (reg(rs) << 16) | (reg(rn) << 5) | 31
+}
+
+/// LDXR Xt, [Xn|SP], or LDAXR with acquire
+pub fn ldxr(acquire: bool, rt: u8, rn: u8) -> u32 {
+    0xC85F7C00 | ((acquire as u32) << 15) | (reg(rn) << 5) | reg(rt)
+}
+
+/// STXR Ws, Xt, [Xn|SP], or STLXR with release
+pub fn stxr(release: bool, rs: u8, rt: u8, rn: u8) -> u32 {
+    0xC8007C00 | (reg(rs) << 16) | ((release as u32) << 15) | (reg(rn) << 5) | reg(rt)
+}
+
+/// CAS{A}{L} Xs, Xt, [Xn|SP]
+pub fn cas(acquire: bool, release: bool, rs: u8, rt: u8, rn: u8) -> u32 {
+    0xC8A07C00 | ((acquire as u32) << 22) | (reg(rs) << 16) | ((release as u32) << 15) | (reg(rn) << 5) | reg(rt)
+}
+
+/// CASP{A}{L} Xs, Xs+1, Xt, Xt+1, [Xn|SP]
+pub fn casp(acquire: bool, release: bool, rs: u8, rt: u8, rn: u8) -> Result<u32, String> {
+    if !rs.is_multiple_of(2) || !rt.is_multiple_of(2) {
+        return Err(format!("CASP needs even registers (Xs = {}, Xt = {})", rs, rt));
+    }
+    Ok(0x48207C00 | ((acquire as u32) << 22) | (reg(rs) << 16) | ((release as u32) << 15) | (reg(rn) << 5) | reg(rt))
+}
+
+/// LDADD{A}{L}{B,H} Ws, Wt, [Xn|SP] (Xs and Xt for SZ_X) and the other
+/// atomic memory operations of the Size size (op is one of
+/// A64_LDADD...A64_SWP).
+pub fn atomic(op: Op, size: u8, acquire: bool, release: bool, rs: u8, rt: u8, rn: u8) -> Result<u32, String> {
+    let (o3, opc) = match op {
+        Op::A64_LDADD => (0, 0b000),
+        Op::A64_LDCLR => (0, 0b001),
+        Op::A64_LDEOR => (0, 0b010),
+        Op::A64_LDSET => (0, 0b011),
+        Op::A64_LDSMAX => (0, 0b100),
+        Op::A64_LDSMIN => (0, 0b101),
+        Op::A64_LDUMAX => (0, 0b110),
+        Op::A64_LDUMIN => (0, 0b111),
+        Op::A64_SWP => (1, 0b000),
+        _ => return Err(format!("{:?} is not an atomic memory operation", op)),
+    };
+    Ok(0x38200000 | ((size as u32 & 0b11) << 30) | ((acquire as u32) << 23) | ((release as u32) << 22) | (reg(rs) << 16)
+        | (o3 << 15) | (opc << 12) | (reg(rn) << 5) | reg(rt))
+}
+
+/// LDAPR{B,H} Wt, [Xn|SP] (Xt for SZ_X)
+pub fn ldapr(size: u8, rt: u8, rn: u8) -> u32 {
+    0x38BFC000 | ((size as u32 & 0b11) << 30) | (reg(rn) << 5) | reg(rt)
+}
+
+/// Encodes a decoded instruction. Only the opcodes needed by the transformation
+/// passes are supported; everything else is an error.
+pub fn encode(inst: &Inst) -> Result<u32, String> {
+    let w32 = inst.flags & W32 != 0;
+    match inst.op {
+        Op::A64_B => b(inst.offset),
+        Op::A64_BL => bl(inst.offset),
+        Op::A64_BCOND => bcond(fad_get_cond(inst.flags), inst.offset),
+        Op::A64_CBZ => cbz(false, w32, inst.rd, inst.offset),
+        Op::A64_CBNZ => cbz(true, w32, inst.rd, inst.offset),
+        Op::A64_TBZ => tbz(false, inst.rd, inst.tbz.bit, inst.tbz.offset as i64),
+        Op::A64_TBNZ => tbz(true, inst.rd, inst.tbz.bit, inst.tbz.offset as i64),
+        Op::A64_BR => Ok(br(inst.rn)),
+        Op::A64_BLR => Ok(blr(inst.rn)),
+        Op::A64_RET => Ok(ret(inst.rn)),
+        Op::A64_BRK => Ok(brk(inst.imm as u16)),
+        Op::A64_ADR => adr(inst.rd, inst.offset),
+        Op::A64_ADRP => adrp(inst.rd, inst.offset),
+        Op::A64_MOVK if !w32 => Ok(movk(inst.rd, inst.movk.imm16 as u16, (inst.movk.lsl / 16) as u8)),
+        Op::A64_LDR if fad_get_addrmode(inst.flags) == AddrMode::AM_LITERAL => {
+            ldr_literal(fad_get_mem_extend(inst.flags), inst.rd, inst.offset)
+        }
+        Op::A64_LDR_FP if fad_get_addrmode(inst.flags) == AddrMode::AM_LITERAL => {
+            ldr_fp_literal(fad_get_prec(inst.flags), inst.rd, inst.offset)
+        }
+        Op::A64_PRFM if fad_get_addrmode(inst.flags) == AddrMode::AM_LITERAL => {
+            prfm_literal(inst.rd, inst.offset)
+        }
+        Op::A64_LDADD | Op::A64_LDCLR | Op::A64_LDEOR | Op::A64_LDSET | Op::A64_LDSMAX | Op::A64_LDSMIN
+        | Op::A64_LDUMAX | Op::A64_LDUMIN | Op::A64_SWP => {
+            let acquire = inst.ldst_order.load != MemOrdering::MO_NONE as u16;
+            let release = inst.ldst_order.store != MemOrdering::MO_NONE as u16;
+            atomic(inst.op, fad_get_mem_extend(inst.flags) & 0b11, acquire, release, inst.rs, inst.rd, inst.rn)
+        }
+        Op::A64_LDAPR => Ok(ldapr(fad_get_mem_extend(inst.flags) & 0b11, inst.rd, inst.rn)),
+        _ => Err("encode: opcode not supported by the writer".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_reader::{decode, Size};
+
+    #[test]
+    fn atomics_round_trip() {
+        for word in [0xB8200041, 0xB8BF0041, 0xF8E08041, 0x78608041, 0x78FF7041, 0x387F3041, 0xB860005F, 0x38BFC020,
+            0x78BFC3E0, 0xF8BFC020] {
+            assert_eq!(encode(&decode(word)), Ok(word), "{:#010x}", word);
+        }
+        assert_eq!(atomic(Op::A64_LDADD, Size::SZ_B, true, true, 0, 1, 2), Ok(0x38E00041));
+        assert_eq!(ldapr(Size::SZ_H, 0, 1), 0x78BFC020);
+    }
+
+    #[test]
+    fn encoders() {
+        use crate::aarch64_reader::Registries::STACK_POINTER as SP;
+        // llvm-mc -triple=aarch64 -mattr=+lse,+rcpc -show-encoding
+        let cases = [
+            (b(-4), 0x17FFFFFF), // b #-4
+            (bl(0x7FFFFFC), 0x95FFFFFF), // bl #0x7fffffc
+            (bcond(1, -0x100000), 0x54800001), // b.ne #-0x100000
+            (cbz(true, true, 3, 8), 0x35000043), // cbnz w3, #8
+            (cbz(false, false, 4, -8), 0xB4FFFFC4), // cbz x4, #-8
+            (tbz(true, 5, 63, 0x1000), 0xB7F88005), // tbnz x5, #63, #0x1000
+            (tbz(false, 6, 3, -0x8000), 0x361C0006), // tbz w6, #3, #-0x8000
+            (Ok(brk(1)), 0xD4200020), // brk #0x1
+            (Ok(br(16)), 0xD61F0200), // br x16
+            (Ok(blr(17)), 0xD63F0220), // blr x17
+            (Ok(ret(30)), 0xD65F03C0), // ret
+            (adr(0, -0x100000), 0x10800000), // adr x0, #-0x100000
+            (adrp(1, 0x7FFFF000), 0xF03FFFE1), // adrp x1, #0x7ffff000
+            (add_imm(2, SP, 0xFFF), 0x913FFFE2), // add x2, sp, #0xfff
+            (Ok(movz(3, 0xBEEF, 3)), 0xD2F7DDE3), // movz x3, #0xbeef, lsl #48
+            (Ok(movk(4, 0x1234, 1)), 0xF2A24684), // movk x4, #0x1234, lsl #16
+            (ldr_literal(ExtendType::UXTW, 5, 0xFFFFC), 0x187FFFE5), // ldr w5, #0xffffc
+            (ldr_literal(ExtendType::UXTX, 6, -0x100000), 0x58800006), // ldr x6, #-0x100000
+            (ldr_literal(ExtendType::SXTW, 7, 4), 0x98000027), // ldrsw x7, #4
+            (ldr_fp_literal(FPSize::FSZ_S, 8, 8), 0x1C000048), // ldr s8, #8
+            (ldr_fp_literal(FPSize::FSZ_D, 9, -4), 0x5CFFFFE9), // ldr d9, #-4
+            (ldr_fp_literal(FPSize::FSZ_Q, 10, 16), 0x9C00008A), // ldr q10, #16
+            (prfm_literal(0b10011, 0x20), 0xD8000113), // prfm pstl2strm, #0x20
+            (ldr_base(ExtendType::UXTW, 11, 12), 0xB940018B), // ldr w11, [x12]
+            (ldr_base(ExtendType::UXTX, 13, SP), 0xF94003ED), // ldr x13, [sp]
+            (ldr_base(ExtendType::SXTW, 14, 15), 0xB98001EE), // ldrsw x14, [x15]
+            (ldr_fp_base(FPSize::FSZ_S, 16, 0), 0xBD400010), // ldr s16, [x0]
+            (ldr_fp_base(FPSize::FSZ_D, 17, SP), 0xFD4003F1), // ldr d17, [sp]
+            (ldr_fp_base(FPSize::FSZ_Q, 18, 1), 0x3DC00032), // ldr q18, [x1]
+            (stp_pre(29, 30, SP, -512), 0xA9A07BFD), // stp x29, x30, [sp, #-512]!
+            (ldp_post(29, 30, SP, 504), 0xA8DFFBFD), // ldp x29, x30, [sp], #504
+            (Ok(stadd(0, SP)), 0xF82003FF), // stadd x0, [sp]
+            (Ok(ldxr(true, 1, 2)), 0xC85FFC41), // ldaxr x1, [x2]
+            (Ok(stxr(true, 3, 4, SP)), 0xC803FFE4), // stlxr w3, x4, [sp]
+            (Ok(cas(true, true, 5, 6, 7)), 0xC8E5FCE6), // casal x5, x6, [x7]
+            (casp(true, false, 8, 10, 12), 0x48687D8A), // caspa x8, x9, x10, x11, [x12]
+            (atomic(Op::A64_LDSMAX, Size::SZ_H, false, true, 0, 1, 2), 0x78604041), // ldsmaxlh w0, w1, [x2]
+            (atomic(Op::A64_SWP, Size::SZ_B, true, false, 3, 4, SP), 0x38A383E4), // swpab w3, w4, [sp]
+            (Ok(ldapr(Size::SZ_W, 5, 6)), 0xB8BFC0C5), // ldapr w5, [x6]
+        ];
+        for (i, (got, word)) in cases.into_iter().enumerate() {
+            assert_eq!(got, Ok(word), "case {}", i);
+        }
+        // mov x0, #0x5678; movk x0, #0x1234, lsl #48
+        assert_eq!(mov_imm64(0, 0x1234_0000_0000_5678), vec![0xD28ACF00, 0xF2E24680]);
+        assert_eq!(mov_imm64(9, 0xFFFF_FFFF_FFFF_0000), vec![0xD2800009, 0xF2BFFFE9, 0xF2DFFFE9, 0xF2FFFFE9]);
+        assert_eq!(mov_imm64(9, 0), vec![0xD2800009]);
+    }
+
+    #[test]
+    fn unencodable_operands() {
+        assert!(b(2).is_err()); // not a multiple of 4
+        assert!(b(1 << 27).is_err() && b(-(1 << 27)).is_ok());
+        assert!(bcond(0, 1 << 20).is_err() && bcond(0, -(1 << 20)).is_ok());
+        assert!(cbz(false, false, 0, 1 << 20).is_err());
+        assert!(tbz(false, 0, 64, 0).is_err());
+        assert!(tbz(false, 0, 0, 1 << 15).is_err() && tbz(false, 0, 0, -(1 << 15)).is_ok());
+        assert!(adr(0, 1 << 20).is_err() && adr(0, (1 << 20) - 1).is_ok());
+        assert!(adrp(0, 0x800).is_err()); // not page-aligned
+        assert!(adrp(0, 1 << 32).is_err() && adrp(0, -(1 << 32)).is_ok());
+        assert!(add_imm(0, 1, 0x1000).is_err());
+        assert!(ldr_literal(ExtendType::SXTB, 0, 0).is_err());
+        assert!(ldr_literal(ExtendType::UXTX, 0, 1 << 20).is_err());
+        assert!(ldr_fp_literal(FPSize::FSZ_H, 0, 0).is_err());
+        assert!(ldr_base(ExtendType::UXTH, 0, 1).is_err());
+        assert!(ldr_fp_base(FPSize::FSZ_B, 0, 1).is_err());
+        assert!(stp_pre(29, 30, 31, 12).is_err() && stp_pre(29, 30, 31, 512).is_err());
+        assert!(ldp_post(29, 30, 31, -520).is_err());
+        assert!(casp(false, false, 1, 2, 0).is_err() && casp(false, false, 2, 3, 0).is_err());
+        assert!(atomic(Op::A64_CAS, Size::SZ_X, false, false, 0, 1, 2).is_err());
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        let mut words = vec![
+            0x17FFFFFF, 0x95FFFFFF, 0x54800001, 0x35000043, 0xB4FFFFC4, 0xB7F88005, 0x361C0006, 0xD4200020,
+            0xD61F0200, 0xD63F0220, 0xD65F03C0, 0x10800000, 0xF03FFFE1, 0xF2A24684, 0x187FFFE5, 0x58800006,
+            0x98000027, 0xD8000113,
+        ];
+        if cfg!(feature = "simd") {
+            words.extend([0x1C000048, 0x5CFFFFE9, 0x9C00008A]);
+        }
+        for word in words {
+            assert_eq!(encode(&decode(word)), Ok(word), "{:#010x}", word);
+        }
+        // add x2, sp, #0xfff; ldr x13, [sp]; movk w4, #0x1234, lsl #16
+        for word in [0x913FFFE2, 0xF94003ED, 0x72A24684] {
+            assert!(encode(&decode(word)).is_err(), "{:#010x}", word);
+        }
+    }
+}
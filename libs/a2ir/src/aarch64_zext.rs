@@ -0,0 +1,73 @@
+//! The implicit zero-extension of W register writes.
+//!
+//! Writing a W register sets bits 63:32 of the X register to zero, which is
+//! easy to forget when lifting: a 32-bit ADD is a 64-bit register write. The
+//! explicit zero-extensions compilers still emit (UBFX Xd, Xn, #0, #32, the
+//! would-be UXTW) are redundant when Xn was last written as a W register.
+
+use crate::aarch64_defuse::{def_use, Loc};
+use crate::aarch64_reader::FlagMasks::W32;
+use crate::aarch64_reader::{Inst, Op};
+
+/// The X registers whose upper halves the instruction zeroes.
+pub fn zero_extended_defs(inst: &Inst) -> Vec<u8> {
+    let defs = def_use(inst).defs;
+    let mut out = Vec::new();
+    // The status result of store exclusives is always a W register.
+    if matches!(inst.op, Op::A64_STXR | Op::A64_STXP) {
+        if let Some(&Loc::X(r)) = defs.iter().find(|&&l| l == Loc::X(inst.rs)) {
+            out.push(r);
+        }
+        return out;
+    }
+    if inst.flags & W32 == 0 {
+        return out;
+    }
+    for def in defs {
+        match def {
+            Loc::X(r) if r == inst.rd || r == inst.rt2 => out.push(r),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// UBFX Xd, Xn, #0, #32: zero-extends Wn into Xd.
+pub fn is_zero_extension(inst: &Inst) -> bool {
+    inst.op == Op::A64_UBFX && inst.flags & W32 == 0 && inst.bfm.lsb == 0 && inst.bfm.width == 32
+}
+
+/// Zero-extensions in a block whose source is already zero-extended by its
+/// last write in the block. Those with Xd = Xn can be removed, the others
+/// turned into moves.
+pub fn redundant_zero_extensions(block: &[(u64, Inst)]) -> Vec<u64> {
+    // extended[r]: bits 63:32 of Xr are known to be zero
+    let mut extended = [false; 31];
+    let mut out = Vec::new();
+    for (pc, inst) in block {
+        if is_zero_extension(inst) && (inst.rn as usize) < extended.len() && extended[inst.rn as usize] {
+            out.push(*pc);
+        }
+        let zeroed = zero_extended_defs(inst);
+        for def in def_use(inst).defs {
+            if let Loc::X(r) = def {
+                extended[r as usize] = zeroed.contains(&r) || is_zero_extension(inst);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aarch64_buffer::decode_words;
+
+    #[test]
+    fn redundant_after_w_write() {
+        // add w0, w1, #1; ubfx x0, x0, #0, #32; ldr x1, [x2]; ubfx x1, x1, #0, #32
+        let block = decode_words(&[0x11000420, 0xD3407C00, 0xF9400041, 0xD3407C21], 0);
+        assert!(is_zero_extension(&block[1].1));
+        assert_eq!(redundant_zero_extensions(&block), vec![4]);
+    }
+}
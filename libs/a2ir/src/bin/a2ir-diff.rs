@@ -0,0 +1,212 @@
+//! Instruction-level comparison of two versions of a raw A64 image.
+//!
+//! ```text
+//! a2ir-diff [--base ADDR] [--old-base ADDR] [--new-base ADDR] [--threshold T]
+//!           [--old-syms FILE --new-syms FILE] [--json] OLD NEW
+//! ```
+//!
+//! Without symbols the functions are the start of the image and the targets
+//! of BL. Symbol files have one `<hex address> <name>` per line; with both,
+//! only the functions named in both versions are compared.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::process::exit;
+
+use a2ir::aarch64_bindiff::{bindiff, call_entries, function_ranges, BinDiff};
+use a2ir::aarch64_buffer::{decode_buffer, DecodeOptions};
+use a2ir::aarch64_program::Program;
+
+const USAGE: &str = "usage: a2ir-diff [--base ADDR] [--old-base ADDR] [--new-base ADDR] [--threshold T] \
+[--old-syms FILE --new-syms FILE] [--json] OLD NEW";
+
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    old_base: u64,
+    new_base: u64,
+    threshold: f32,
+    old_syms: Option<String>,
+    new_syms: Option<String>,
+    json: bool,
+    files: Vec<String>,
+}
+
+fn parse_addr(s: &str) -> Result<u64, String> {
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    u64::from_str_radix(digits, 16).map_err(|e| format!("bad address {}: {}", s, e))
+}
+
+fn parse_args(argv: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut args = Args { threshold: 0.5, ..Args::default() };
+    let mut it = argv.into_iter();
+    while let Some(arg) = it.next() {
+        let mut value = || it.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--base" => {
+                args.old_base = parse_addr(&value()?)?;
+                args.new_base = args.old_base;
+            }
+            "--old-base" => args.old_base = parse_addr(&value()?)?,
+            "--new-base" => args.new_base = parse_addr(&value()?)?,
+            "--threshold" => args.threshold = value()?.parse().map_err(|e| format!("bad threshold: {}", e))?,
+            "--old-syms" => args.old_syms = Some(value()?),
+            "--new-syms" => args.new_syms = Some(value()?),
+            "--json" => args.json = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => args.files.push(arg),
+        }
+    }
+    if args.files.len() != 2 || args.old_syms.is_some() != args.new_syms.is_some() {
+        return Err(USAGE.to_string());
+    }
+    Ok(args)
+}
+
+fn parse_symbols(text: &str) -> Result<BTreeMap<u64, String>, String> {
+    let mut out = BTreeMap::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        let (addr, name) = line.split_once(char::is_whitespace).ok_or(format!("bad line {:?}", line))?;
+        out.insert(parse_addr(addr)?, name.trim().to_string());
+    }
+    Ok(out)
+}
+
+fn read_symbols(path: &str) -> Result<BTreeMap<u64, String>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    parse_symbols(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn load(path: &str, base: u64) -> Result<Program, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    Ok(Program::from_buffer(decode_buffer(&bytes, &DecodeOptions { base, ..DecodeOptions::default() })))
+}
+
+/// [entry, end) of each function
+type Ranges = Vec<(u64, u64)>;
+
+/// The ranges of the functions named in both versions; they end at the
+/// next symbol, named in both versions or not.
+fn common_ranges(
+    old: &Program,
+    old_syms: &BTreeMap<u64, String>,
+    new: &Program,
+    new_syms: &BTreeMap<u64, String>,
+) -> (Ranges, Ranges) {
+    let common: BTreeSet<&String> = old_syms.values().filter(|n| new_syms.values().any(|m| m == *n)).collect();
+    let ranges = |program: &Program, syms: &BTreeMap<u64, String>| -> Ranges {
+        let entries: BTreeSet<u64> = syms.keys().copied().collect();
+        let kept = function_ranges(program, &entries).into_iter();
+        kept.filter(|(entry, _)| common.contains(&syms[entry])).collect()
+    };
+    (ranges(old, old_syms), ranges(new, new_syms))
+}
+
+fn text_report(diff: &BinDiff, old_names: &BTreeMap<u64, String>) -> String {
+    let mut out = String::new();
+    for m in diff.changed() {
+        let name = old_names.get(&m.old).map_or("", String::as_str);
+        out.push_str(&format!(
+            "changed {:#x} -> {:#x}  {} insts  similarity {:.3}  {:?}  {}\n",
+            m.old, m.new, m.changed, m.similarity, m.method, name
+        ));
+    }
+    out.push_str(&format!("unchanged: {}\n", diff.matches.len() - diff.changed().len()));
+    let list = |v: &[u64]| v.iter().map(|a| format!("{:#x}", a)).collect::<Vec<_>>().join(" ");
+    out.push_str(&format!("unmatched old: {}\n", list(&diff.unmatched_old)));
+    out.push_str(&format!("unmatched new: {}\n", list(&diff.unmatched_new)));
+    out
+}
+
+fn run() -> Result<(), String> {
+    let args = parse_args(std::env::args().skip(1))?;
+    let old = load(&args.files[0], args.old_base)?;
+    let new = load(&args.files[1], args.new_base)?;
+
+    let (old_names, old_ranges, new_ranges) = match (&args.old_syms, &args.new_syms) {
+        (Some(old_syms), Some(new_syms)) => {
+            let (old_syms, new_syms) = (read_symbols(old_syms)?, read_symbols(new_syms)?);
+            let (old_ranges, new_ranges) = common_ranges(&old, &old_syms, &new, &new_syms);
+            (old_syms, old_ranges, new_ranges)
+        }
+        _ => (BTreeMap::new(), function_ranges(&old, &call_entries(&old)), function_ranges(&new, &call_entries(&new))),
+    };
+
+    let diff = bindiff(&old, &old_ranges, &new, &new_ranges, args.threshold);
+    if args.json {
+        println!("{}", diff.to_json());
+    } else {
+        print!("{}", text_report(&diff, &old_names));
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("a2ir-diff: {}", e);
+        exit(2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use a2ir::aarch64_bindiff::MatchMethod;
+
+    fn argv(args: &str) -> Vec<String> {
+        args.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn arguments() {
+        let args = parse_args(argv("--base 1000 --new-base 0x2000 --json old.bin new.bin")).unwrap();
+        assert_eq!(args, Args {
+            old_base: 0x1000,
+            new_base: 0x2000,
+            threshold: 0.5,
+            json: true,
+            files: vec!["old.bin".to_string(), "new.bin".to_string()],
+            ..Args::default()
+        });
+        assert_eq!(parse_args(argv("--threshold 0.8 a b")).unwrap().threshold, 0.8);
+        assert!(parse_args(argv("--threshold high a b")).is_err());
+        assert!(parse_args(argv("--old-syms a.syms a b")).is_err()); // without --new-syms
+        assert!(parse_args(argv("a")).is_err());
+        assert!(parse_args(argv("--patch a b")).is_err());
+    }
+
+    #[test]
+    fn symbols() {
+        let syms = parse_symbols("# nm -n\n0 f\n\n0x10  main loop\n").unwrap();
+        assert_eq!(syms, BTreeMap::from([(0, "f".to_string()), (0x10, "main loop".to_string())]));
+        assert!(parse_symbols("0x10\n").is_err());
+        assert!(parse_symbols("main 0x10\n").is_err());
+    }
+
+    #[test]
+    fn functions_named_in_both_versions() {
+        let old = Program::from_words(&[
+            0x91000400, 0xD65F03C0, // f: add x0, x0, #1; ret
+            0xD2800000, 0xD65F03C0, // g: mov x0, #0; ret
+            0xD503201F, 0xD65F03C0, // h: nop; ret
+        ], 0);
+        let new = Program::from_words(&[
+            0x91000800, 0xD65F03C0, // f: add x0, x0, #2; ret
+            0xD503201F, 0xD503201F, 0xD65F03C0, // k: nop; nop; ret
+            0xD2800000, 0xD65F03C0, // g: mov x0, #0; ret
+        ], 0x100);
+        let old_syms = parse_symbols("0 f\n8 g\n10 h\n").unwrap();
+        let new_syms = parse_symbols("100 f\n108 k\n114 g\n").unwrap();
+        let (old_ranges, new_ranges) = common_ranges(&old, &old_syms, &new, &new_syms);
+        assert_eq!(old_ranges, vec![(0x0, 0x8), (0x8, 0x10)]);
+        assert_eq!(new_ranges, vec![(0x100, 0x108), (0x114, 0x11C)]);
+
+        let diff = bindiff(&old, &old_ranges, &new, &new_ranges, 0.5);
+        // h and k are left out, not unmatched.
+        assert!(diff.unmatched_old.is_empty() && diff.unmatched_new.is_empty());
+        let f = &diff.matches[0];
+        assert_eq!((f.old, f.new, f.method, f.changed), (0x0, 0x100, MatchMethod::Fingerprint, 2));
+        assert_eq!(text_report(&diff, &old_syms), "changed 0x0 -> 0x100  2 insts  similarity 1.000  Fingerprint  f\n\
+            unchanged: 1\nunmatched old: \nunmatched new: \n");
+        assert_eq!(diff.to_json(), "{\"changed\":[{\"old\":0,\"new\":256,\"method\":\"Fingerprint\",\"similarity\":1.000,\
+            \"changed\":2}],\"unchanged\":1,\"unmatched_old\":[],\"unmatched_new\":[]}");
+    }
+}
@@ -0,0 +1,245 @@
+//! Static dry run of a raw image or an AArch64 ELF file: the code reachable
+//! from the entry point, and the semihosting calls, system calls (SVC) and
+//! exception returns on the way.
+//!
+//! ```text
+//! a2ir-run [--base ADDR] [--entry ADDR] [--linux] [--dtb ADDR] [--trace]
+//!          [--coverage FILE] IMAGE
+//! ```
+//!
+//! Nothing is executed. The reachable code is that of the CFG from the
+//! entry, with the targets of BL and of the BR and BLR whose registers
+//! constant propagation recovers within their block; the arguments of the
+//! calls are recovered the same way. ELF files are loaded at their link
+//! addresses, other images at --base, as an arm64 Linux Image with --linux;
+//! --dtb passes the DTB address in X0 to the entry block, as boot loaders
+//! do. --trace lists the reached instructions and --coverage writes their
+//! addresses, one per line.
+
+use std::collections::BTreeSet;
+use std::process::exit;
+
+use a2ir::aarch64_boot::BootImage;
+use a2ir::aarch64_branch::BranchKind;
+use a2ir::aarch64_cfg::Cfg;
+use a2ir::aarch64_constprop::{propagate, ConstOptions};
+use a2ir::aarch64_el1::{exception_returns, ExceptionReturn};
+use a2ir::aarch64_listing::inst_text;
+use a2ir::aarch64_mnemonic::inst_mnemonic;
+use a2ir::aarch64_program::Program;
+use a2ir::aarch64_reader::Inst;
+use a2ir::aarch64_relocate::target_address;
+use a2ir::aarch64_semihost::{semihosting_calls, SemihostCall};
+use a2ir::aarch64_snapshot::Snapshot;
+
+const USAGE: &str = "usage: a2ir-run [--base ADDR] [--entry ADDR] [--linux] [--dtb ADDR] [--trace] \
+[--coverage FILE] IMAGE";
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Args {
+    base: u64,
+    entry: Option<u64>,
+    linux: bool,
+    dtb: Option<u64>,
+    trace: bool,
+    coverage: Option<String>,
+    file: String,
+}
+
+fn parse_addr(s: &str) -> Result<u64, String> {
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    u64::from_str_radix(digits, 16).map_err(|e| format!("bad address {}: {}", s, e))
+}
+
+fn parse_args(argv: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut files = Vec::new();
+    let mut it = argv.into_iter();
+    while let Some(arg) = it.next() {
+        let mut value = || it.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--base" => args.base = parse_addr(&value()?)?,
+            "--entry" => args.entry = Some(parse_addr(&value()?)?),
+            "--linux" => args.linux = true,
+            "--dtb" => args.dtb = Some(parse_addr(&value()?)?),
+            "--trace" => args.trace = true,
+            "--coverage" => args.coverage = Some(value()?),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 1 {
+        return Err(USAGE.to_string());
+    }
+    args.file = files.remove(0);
+    Ok(args)
+}
+
+/// The program, its entry and the options for the entry block.
+fn load(bytes: &[u8], args: &Args) -> Result<(Program, u64, ConstOptions), String> {
+    let (program, entry, options) = if bytes.starts_with(b"\x7fELF") {
+        if args.linux || args.dtb.is_some() {
+            return Err("--linux and --dtb are for raw images".to_string());
+        }
+        let (snapshot, entry) = Snapshot::from_elf(bytes)?;
+        (snapshot.program(|_| true), entry, ConstOptions::default())
+    } else {
+        let image = if args.linux { BootImage::linux(bytes, args.base)? } else { BootImage::raw(bytes, args.base) };
+        let image = match args.dtb {
+            Some(dtb) => image.with_dtb(dtb),
+            None => image,
+        };
+        (image.program(), image.entry, image.entry_options())
+    };
+    Ok((program, args.entry.unwrap_or(entry), options))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Event {
+    Semihosting(SemihostCall),
+    /// The SVC, with X8 (the Linux system call number), X0 and X1.
+    Syscall { pc: u64, number: Option<u64>, args: [Option<u64>; 2] },
+    Eret(ExceptionReturn),
+}
+
+impl Event {
+    fn pc(&self) -> u64 {
+        match self {
+            Event::Semihosting(call) => call.pc,
+            Event::Syscall { pc, .. } => *pc,
+            Event::Eret(eret) => eret.pc,
+        }
+    }
+}
+
+/// The addresses of the instructions reachable from entry, and the events
+/// they contain by pc. options hold for the entry block only.
+fn reach(program: &Program, entry: u64, options: &ConstOptions) -> (BTreeSet<u64>, Vec<Event>) {
+    let mut cfg = Cfg::build(program);
+    let mut covered = BTreeSet::new();
+    let mut seen = BTreeSet::new();
+    let mut events = Vec::new();
+    let mut work = vec![entry];
+    while let Some(start) = work.pop() {
+        if program.get(start).is_none() || !seen.insert(start) {
+            continue;
+        }
+        // An indirect branch or a call into the middle of a block.
+        cfg.split_at(start);
+        let end = cfg.blocks[&start].end;
+        let block: Vec<(u64, Inst)> = program.range(start..end).map(|(a, inst)| (a, inst.clone())).collect();
+        let options = if start == entry { *options } else { ConstOptions::default() };
+        let resolved = propagate(&block, &options);
+
+        covered.extend(block.iter().map(|(a, _)| *a));
+        events.extend(semihosting_calls(&block).into_iter().map(Event::Semihosting));
+        for (pc, _) in block.iter().filter(|(_, inst)| inst_mnemonic(inst) == "svc") {
+            let args = resolved.trap_args.get(pc).copied().unwrap_or([None, None]);
+            events.push(Event::Syscall { pc: *pc, number: resolved.syscall_numbers.get(pc).copied(), args });
+        }
+        events.extend(exception_returns(&block).into_iter().map(Event::Eret));
+
+        work.extend(cfg.successors(start));
+        let calls = block.iter().filter(|(_, inst)| inst.branch_kind() == Some(BranchKind::Call));
+        work.extend(calls.filter_map(|(pc, inst)| target_address(inst, *pc)));
+        work.extend(resolved.branch_targets.values());
+    }
+    events.sort_by_key(Event::pc);
+    (covered, events)
+}
+
+fn value_text(value: Option<u64>) -> String {
+    value.map_or("?".to_string(), |v| format!("{:#x}", v))
+}
+
+fn event_text(event: &Event) -> String {
+    match event {
+        Event::Semihosting(call) => {
+            let op = call.op.map_or("?".to_string(), |op| format!("{:?}", op));
+            format!("{:#x} semihosting {} x1={}", call.pc, op, value_text(call.param))
+        }
+        Event::Syscall { pc, number, args: [x0, x1] } => {
+            format!("{:#x} svc x8={} x0={} x1={}", pc, value_text(*number), value_text(*x0), value_text(*x1))
+        }
+        Event::Eret(eret) => {
+            let mode = eret.mode.map_or("?".to_string(), |(el, spx)| format!("EL{}{}", el, if spx { 'h' } else { 't' }));
+            format!("{:#x} eret to {} at {}", eret.pc, value_text(eret.target), mode)
+        }
+    }
+}
+
+fn run() -> Result<(), String> {
+    let args = parse_args(std::env::args().skip(1))?;
+    let bytes = std::fs::read(&args.file).map_err(|e| format!("{}: {}", args.file, e))?;
+    let (program, entry, options) = load(&bytes, &args)?;
+    if program.get(entry).is_none() {
+        return Err(format!("no code at the entry {:#x}", entry));
+    }
+    let (covered, events) = reach(&program, entry, &options);
+
+    if args.trace {
+        for &pc in &covered {
+            println!("{:#x}: {}", pc, inst_text(program.get(pc).expect("covered"), pc));
+        }
+    }
+    if let Some(path) = &args.coverage {
+        let text: String = covered.iter().map(|pc| format!("{:#x}\n", pc)).collect();
+        std::fs::write(path, text).map_err(|e| format!("{}: {}", path, e))?;
+    }
+    println!("entry {:#x}: {} of {} instructions reached", entry, covered.len(), program.len());
+    for event in &events {
+        println!("{}", event_text(event));
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("a2ir-run: {}", e);
+        exit(2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(args: &str) -> Vec<String> {
+        args.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn arguments() {
+        let args = parse_args(argv("--base 0x40000000 --dtb 48000000 --trace boot.bin")).unwrap();
+        assert_eq!(args, Args { base: 0x4000_0000, dtb: Some(0x4800_0000), trace: true, file: "boot.bin".to_string(), ..Args::default() });
+        assert!(parse_args(argv("--base")).is_err());
+        assert!(parse_args(argv("--run boot.bin")).is_err());
+        assert!(parse_args(argv("a.bin b.bin")).is_err());
+    }
+
+    #[test]
+    fn reachable_code_and_calls() {
+        let program = Program::from_words(&[
+            0xD2800808, // mov x8, #0x40
+            0xD4000001, // svc #0
+            0x94000004, // bl .+0x10
+            0x10000081, // adr x1, .+0x10
+            0xD61F0020, // br x1
+            0xD503201F, // nop: not reached
+            0xD65F03C0, // ret
+            0x52800080, // mov w0, #4
+            0xD45E0000, // hlt #0xf000
+            0xD69F03E0, // eret
+        ], 0x1000);
+        let options = ConstOptions { regs: BootImage::raw(&[], 0).with_dtb(0x4800_0000).regs, ..Default::default() };
+        let (covered, events) = reach(&program, 0x1000, &options);
+        assert_eq!(covered.len(), 9);
+        assert!(!covered.contains(&0x1014));
+        let text: Vec<String> = events.iter().map(event_text).collect();
+        assert_eq!(text, vec![
+            "0x1004 svc x8=0x40 x0=0x48000000 x1=0x0",
+            "0x1020 semihosting Write0 x1=?",
+            "0x1024 eret to ? at ?",
+        ]);
+    }
+}
@@ -1,5 +1,77 @@
-mod aarch64_reader;
+pub mod aarch64_reader;
+pub mod aarch64_buffer;
+pub mod aarch64_writer;
+pub mod aarch64_relocate;
+pub mod aarch64_hook;
+pub mod aarch64_rewriter;
+pub mod aarch64_instrument;
+pub mod aarch64_hotpatch;
+pub mod aarch64_timing;
+pub mod aarch64_defuse;
+pub mod aarch64_cost;
+pub mod aarch64_stack;
+pub mod aarch64_stats;
+pub mod aarch64_program;
+pub mod aarch64_mnemonic;
+pub mod aarch64_group;
+pub mod aarch64_effects;
+pub mod aarch64_branch;
+pub mod aarch64_fpexc;
+pub mod aarch64_operand;
+pub mod aarch64_pac;
+pub mod aarch64_constprop;
+pub mod aarch64_scs;
+pub mod aarch64_zext;
+pub mod aarch64_flags;
+pub mod aarch64_features;
+pub mod aarch64_similar;
+pub mod aarch64_bindiff;
+pub mod aarch64_gen;
+pub mod aarch64_mutate;
+pub mod aarch64_stress;
+pub mod aarch64_litmus;
+pub mod aarch64_sysreg;
+pub mod aarch64_semihost;
+pub mod aarch64_smccc;
+pub mod aarch64_smp;
+pub mod aarch64_el1;
+pub mod aarch64_boot;
+pub mod aarch64_listing;
+pub mod aarch64_source;
+pub mod aarch64_profile;
+pub mod aarch64_heat;
+pub mod aarch64_html;
+pub mod aarch64_dynlink;
+pub mod aarch64_callgraph;
+pub mod aarch64_tls;
+pub mod aarch64_canary;
+pub mod aarch64_cfg;
+pub mod aarch64_ehframe;
+pub mod aarch64_noreturn;
+pub mod aarch64_functions;
+pub mod aarch64_autocomment;
+pub mod aarch64_decoder;
+pub mod aarch64_shared;
+pub mod aarch64_lazy;
+pub mod aarch64_sweep;
+pub mod aarch64_normalize;
+pub mod aarch64_liveness;
+pub mod aarch64_abi;
+pub mod aarch64_prototype;
+pub mod aarch64_clobber;
+pub mod aarch64_valueset;
+pub mod aarch64_vtable;
+pub mod aarch64_objc;
+pub mod aarch64_golang;
+pub mod aarch64_rust;
+pub mod aarch64_kernel;
+pub mod aarch64_rebase;
+pub mod aarch64_snapshot;
+pub mod aarch64_jit;
+pub mod aarch64_slice;
+pub mod aarch64_triage;
+pub mod aarch64_trap;
 
 pub fn convertProgram() {
     // TODO: give it some abstracted form of an executable
-}
\ No newline at end of file
+}